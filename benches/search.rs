@@ -0,0 +1,161 @@
+//! 搜索流程基准测试
+//!
+//! 在生成的数据库（默认 100 万行，可用 `NETDISK_BENCH_ROWS` 修改）上比较 LIKE 搜索和
+//! FTS5 全文索引，并测量结果转换为界面模型的耗时。生成的数据库缓存在 target 目录中，
+//! 行数不变时重复运行直接使用。
+//!
+//! 搜索目前把整个输入作为一个 LIKE 子串匹配，还没有多关键词解析，加入解析后再补充对应的基准
+//!
+//! ```bash
+//! cargo bench --bench search
+//! NETDISK_BENCH_ROWS=100000 cargo bench --bench search
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use netdisk_db::models::database::{Database, FileRecord, SearchOptions};
+use netdisk_db::services::database::sqlite::SqliteDatabase;
+use rusqlite::Connection;
+use std::path::PathBuf;
+
+/// 默认生成的记录数
+const DEFAULT_ROWS: usize = 1_000_000;
+
+/// 每批插入的记录数
+const INSERT_BATCH: usize = 10_000;
+
+/// 搜索结果每页的记录数，与界面的分页大小相当
+const PAGE_SIZE: usize = 200;
+
+const FOLDERS: [&str; 8] = ["movies", "shows", "music", "photos", "backup", "anime", "docs", "courses"];
+const WORDS: [&str; 12] = [
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet", "kilo", "lima",
+];
+const EXTENSIONS: [&str; 6] = ["mkv", "mp4", "flac", "jpg", "zip", "pdf"];
+
+fn row_count() -> usize {
+    std::env::var("NETDISK_BENCH_ROWS")
+        .ok()
+        .and_then(|rows| rows.parse().ok())
+        .unwrap_or(DEFAULT_ROWS)
+}
+
+/// 第 `i` 条生成的记录，名称由两个单词和序号组成
+fn generated_record(i: usize) -> FileRecord {
+    let folder = FOLDERS[i % FOLDERS.len()];
+    let first = WORDS[i % WORDS.len()];
+    let second = WORDS[(i / WORDS.len()) % WORDS.len()];
+    let extension = EXTENSIONS[i % EXTENSIONS.len()];
+    let name = format!("{}-{}-{:07}.{}", first, second, i, extension);
+    FileRecord {
+        id: 0,
+        path: format!("/{}/{}/{}", folder, i % 1000, name),
+        size: (i as u64 * 7919) % (8 << 30),
+        etag: format!("{:032x}", i),
+        modified_time: 1_600_000_000 + i as i64,
+        file_type: extension.to_string(),
+        name,
+    }
+}
+
+/// 生成或复用基准数据库，同时建立 FTS5 索引表 `video_fts`
+fn prepare_database(rows: usize) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join(format!("bench-search-{}.db", rows));
+    if path.exists() {
+        let cached = Connection::open(&path)
+            .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM video_fts", [], |row| row.get::<_, i64>(0)))
+            .unwrap_or(0);
+        if cached as usize >= rows {
+            return path;
+        }
+        std::fs::remove_file(&path).expect("Failed to remove stale benchmark database");
+    }
+
+    eprintln!("Generating {} rows into {}", rows, path.display());
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let database = SqliteDatabase::new(path.to_str().unwrap()).expect("Failed to create database");
+    database.init_database().expect("Failed to initialize database");
+    let mut start = 0;
+    while start < rows {
+        let end = (start + INSERT_BATCH).min(rows);
+        let batch: Vec<FileRecord> = (start..end).map(generated_record).collect();
+        database.insert_files(&batch).expect("Failed to insert records");
+        start = end;
+    }
+
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE video_fts USING fts5(name, path, content='video', content_rowid='id');
+         INSERT INTO video_fts(video_fts) VALUES('rebuild');",
+    )
+    .expect("Failed to build FTS index");
+    path
+}
+
+fn bench_search(c: &mut Criterion) {
+    let rows = row_count();
+    let path = prepare_database(rows);
+    let database = SqliteDatabase::new(path.to_str().unwrap()).expect("Failed to open database");
+    let fts = Connection::open(&path).unwrap();
+    let options = SearchOptions::default();
+
+    let mut group = c.benchmark_group(format!("search/{}", rows));
+    group.sample_size(20);
+    // 常见词、少见的序号和不存在的词分别对应大量、少量和没有结果
+    for query in ["echo", "0424242", "zulu"] {
+        group.bench_with_input(BenchmarkId::new("like_paged", query), query, |b, query| {
+            b.iter(|| database.search_files_paged(black_box(query), &options, 0, PAGE_SIZE).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("like_count", query), query, |b, query| {
+            b.iter(|| database.count_files(black_box(query), &options).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("fts_paged", query), query, |b, query| {
+            let mut stmt = fts
+                .prepare(
+                    "SELECT v.id, v.path FROM video_fts f JOIN video v ON v.id = f.rowid
+                     WHERE video_fts MATCH ?1 LIMIT ?2",
+                )
+                .unwrap();
+            // FTS5 按词匹配，序号是名称中的一个词，用前缀查询对应 LIKE 的子串匹配
+            let pattern = format!("\"{}\"*", query);
+            b.iter(|| {
+                stmt.query_map(rusqlite::params![black_box(&pattern), PAGE_SIZE as i64], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .unwrap()
+                .count()
+            })
+        });
+    }
+
+    let scoped = SearchOptions {
+        scope: Some("/movies".to_string()),
+        ..SearchOptions::default()
+    };
+    group.bench_function("like_paged_scoped", |b| {
+        b.iter(|| database.search_files_paged(black_box("echo"), &scoped, 0, PAGE_SIZE).unwrap())
+    });
+    group.finish();
+}
+
+#[cfg(feature = "gui")]
+fn bench_model_conversion(c: &mut Criterion) {
+    use netdisk_db::views::ui::file_records_to_model;
+
+    let mut group = c.benchmark_group("file_records_to_model");
+    for count in [PAGE_SIZE, 10_000] {
+        let records: Vec<FileRecord> = (0..count).map(generated_record).collect();
+        // 转换会取得记录的所有权，复制记录放在计时之外
+        group.bench_with_input(BenchmarkId::from_parameter(count), &records, |b, records| {
+            b.iter_batched(|| records.clone(), file_records_to_model, BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "gui"))]
+fn bench_model_conversion(_c: &mut Criterion) {}
+
+criterion_group!(benches, bench_search, bench_model_conversion);
+criterion_main!(benches);
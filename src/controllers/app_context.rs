@@ -0,0 +1,64 @@
+//! 应用上下文 - 界面事件处理共用的子系统句柄
+//!
+//! 配置、数据库管理线程、下载、剪切板等由 `AppContext` 统一持有，
+//! 事件处理闭包克隆一份上下文即可使用其中任意句柄。新增的子系统应加在这里，
+//! 而不是作为单独的参数传给 `setup_event_handlers`
+
+use crate::models::config::AppConfig;
+use crate::services::activity_log::SharedActivityLog;
+use crate::services::aria2::SharedAria2Service;
+use crate::services::clipboard::ClipboardHandle;
+use crate::services::collections::SharedCollections;
+use crate::services::database_manager::DatabaseHandle;
+use crate::services::download_history::SharedDownloadHistory;
+use crate::services::download_manager::SharedDownloadManager;
+use crate::services::errors;
+use crate::services::http_downloader::HttpDownloader;
+use crate::services::jobs::SharedJobQueue;
+use crate::utils::path_mapping;
+use crate::utils::paths;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// 界面线程中使用的应用上下文，克隆只复制句柄
+///
+/// 包含界面定时器，只能在界面线程中使用；需要在其他线程中使用的句柄应单独克隆后传入
+#[derive(Clone)]
+pub struct AppContext {
+    pub config: Arc<Mutex<AppConfig>>,
+    pub database: DatabaseHandle,
+    pub aria2: SharedAria2Service,
+    pub downloads: SharedDownloadManager,
+    pub downloader: Arc<HttpDownloader>, // Aria2 不可用时使用的内置下载器
+    pub clipboard: ClipboardHandle,
+    pub activity_log: SharedActivityLog,
+    pub collections: SharedCollections,
+    pub download_history: SharedDownloadHistory,
+    pub job_queue: SharedJobQueue,
+    pub search_debounce: Rc<slint::Timer>, // 搜索输入的防抖定时器
+}
+
+impl AppContext {
+    /// 当前数据库的名称，用于记录最近活动
+    pub fn current_database_name(&self) -> String {
+        self.database.current_config().name
+    }
+
+    /// 按当前数据库的路径映射将数据库中的路径转换为本机路径
+    pub fn local_path(&self, path: &str) -> String {
+        path_mapping::map_path(path, &self.database.current_config().path_mappings)
+    }
+
+    /// 修改配置并保存到配置文件，保存失败时上报错误
+    ///
+    /// # Arguments
+    /// * `context` - 保存失败时显示的操作名称
+    /// * `update` - 修改配置的函数
+    pub fn update_config(&self, context: &str, update: impl FnOnce(&mut AppConfig)) {
+        let mut config = self.config.lock().unwrap();
+        update(&mut config);
+        if let Err(e) = config.save_to_file(&paths::config_file().to_string_lossy()) {
+            errors::report(&format!("保存{}失败", context), &e);
+        }
+    }
+}
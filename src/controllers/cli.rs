@@ -0,0 +1,637 @@
+//! 命令行模式 - 不启动界面，在终端中搜索、统计、导出和下载
+//!
+//! 使用 `--no-gui <子命令>` 启动，复用 `DatabaseManager` 和下载流程，
+//! 结果以表格或 JSON（`--json`）输出，便于在 SSH 和脚本中使用
+
+use crate::services::export::export_file_records;
+use crate::services::link_resolver::get_file_url;
+use crate::utils::common::format_upload_filename;
+use crate::models::config::AppConfig;
+use crate::models::database::{CompareKey, Database, DatabaseDiff, FileRecord, MaintenanceOperation, SearchOptions};
+use crate::services::api_client::shared_api_client;
+#[cfg(feature = "aria2")]
+use crate::services::aria2::{create_shared_aria2_service, Aria2Client};
+use crate::services::database::sqlite::{validate_backup, SqliteDatabase};
+use crate::services::database_manager::DatabaseManager;
+#[cfg(feature = "aria2")]
+use crate::services::download_manager::{DownloadManager, DownloadSource};
+use crate::services::http_downloader::HttpDownloader;
+use crate::services::sidecar::{self, DownloadSidecar};
+use crate::services::link_resolver::shared_link_resolver;
+use crate::services::notifications::{NotificationKind, Notifier};
+use crate::services::remote_sync::{RemoteSync, SyncSummary};
+use crate::services::single_instance::{self, InstanceRequest};
+use crate::utils::locale::{format_datetime, format_size};
+use crate::utils::paths;
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// 启用命令行模式的参数
+pub const NO_GUI_ARG: &str = "--no-gui";
+
+/// 网盘同步写入的数据库文件，位于数据目录中，启动时会被自动发现
+const REMOTE_DATABASE_FILE: &str = "netdisk.db";
+
+/// 搜索默认返回的结果数
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// 等待本地 Aria2 下载时查询进度的间隔
+#[cfg(feature = "aria2")]
+const ARIA2_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 命令行子命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// 搜索文件
+    Search { query: String, limit: usize },
+    /// 各数据库的文件数量
+    Stats,
+    /// 将搜索结果导出为 CSV
+    Export { query: String, output: Option<PathBuf> },
+    /// 按记录 ID 发送到下载
+    Send { id: i64 },
+    /// 从网盘同步文件列表到本地数据库
+    Sync { root_id: i64 },
+    /// 维护当前数据库
+    Maintain { operations: Vec<MaintenanceOperation> },
+    /// 备份当前数据库，未指定文件时写入备份目录
+    Backup { dest: Option<PathBuf> },
+    /// 从备份恢复当前数据库，未指定文件时使用最近的备份
+    Restore { source: Option<PathBuf> },
+    /// 按名称比较两个数据库
+    Compare { left: String, right: String, key: CompareKey },
+    /// 向运行中的界面实例发送请求
+    Remote { request: InstanceRequest },
+}
+
+/// 解析后的命令行参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    pub json: bool, // 以 JSON 格式输出
+}
+
+impl CliCommand {
+    /// 是否需要后端服务（获取下载链接）
+    pub fn needs_backend(&self) -> bool {
+        matches!(self, Self::Send { .. } | Self::Sync { .. })
+    }
+}
+
+/// 命令行用法说明
+pub fn usage() -> &'static str {
+    "用法: netdisk_db --no-gui [--json] [--verbose] <命令>\n\
+     \n\
+     命令:\n\
+     \x20 search <关键词> [--limit N]   搜索文件\n\
+     \x20 stats                        显示各数据库的文件数量\n\
+     \x20 export <关键词> [-o 文件]     将搜索结果导出为 CSV\n\
+     \x20 send <ID>                    将文件发送到下载\n\
+     \x20 sync [文件夹ID]               从网盘同步文件列表（默认根目录）\n\
+     \x20 maintain [vacuum|analyze|reindex]\n\
+     \x20                              维护当前数据库（默认全部执行）\n\
+     \x20 maintain cleanup <目录>...    删除已移除目录下的记录\n\
+     \x20 backup [文件]                 备份当前数据库\n\
+     \x20 restore [文件]                从备份恢复当前数据库（默认最近的备份）\n\
+     \x20 compare <数据库> <数据库> [etag]\n\
+     \x20                              比较两个数据库（默认按路径匹配）\n\
+     \x20 remote search <关键词>         在运行中的实例中搜索\n\
+     \x20 remote add-download <链接> [文件名]\n\
+     \x20                              由运行中的实例下载链接\n\
+     \x20 remote open <ID>              由运行中的实例打开文件\n\
+     \x20 remote activate               显示运行中实例的窗口"
+}
+
+/// 解析命令行参数
+///
+/// 没有 `--no-gui` 时返回 `Ok(None)`，以界面模式启动
+///
+/// # Arguments
+/// * `args` - 不含程序名的命令行参数
+pub fn parse_args<I>(args: I) -> Result<Option<CliArgs>>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut no_gui = false;
+    let mut json = false;
+    let mut limit = DEFAULT_SEARCH_LIMIT;
+    let mut output = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            NO_GUI_ARG => no_gui = true,
+            "--json" => json = true,
+            "--limit" => {
+                let value = args.next().context("--limit 需要一个数字")?;
+                limit = value.parse().with_context(|| format!("无效的数量: {}", value))?;
+            }
+            "-o" | "--output" => {
+                output = Some(PathBuf::from(args.next().context("--output 需要一个文件路径")?));
+            }
+            // 全局参数由 main 处理
+            "--data-dir" => {
+                args.next();
+            }
+            _ if arg.starts_with("--data-dir=") => {}
+            "-v" | "--verbose" | "--portable" => {}
+            _ => positional.push(arg),
+        }
+    }
+
+    if !no_gui {
+        return Ok(None);
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        Some("search") => CliCommand::Search {
+            query: positional.collect::<Vec<_>>().join(" "),
+            limit,
+        },
+        Some("stats") => CliCommand::Stats,
+        Some("export") => CliCommand::Export {
+            query: positional.collect::<Vec<_>>().join(" "),
+            output,
+        },
+        Some("send") => {
+            let id = positional.next().context("send 需要文件记录 ID")?;
+            CliCommand::Send {
+                id: id.parse().with_context(|| format!("无效的记录 ID: {}", id))?,
+            }
+        }
+        Some("sync") => {
+            let root_id = match positional.next() {
+                Some(id) => id.parse().with_context(|| format!("无效的文件夹 ID: {}", id))?,
+                None => 0,
+            };
+            CliCommand::Sync { root_id }
+        }
+        Some("maintain") => {
+            let operations = match positional.next().as_deref() {
+                None => MaintenanceOperation::ROUTINE.to_vec(),
+                Some("vacuum") => vec![MaintenanceOperation::Vacuum],
+                Some("analyze") => vec![MaintenanceOperation::Analyze],
+                Some("reindex") => vec![MaintenanceOperation::Reindex],
+                Some("cleanup") => {
+                    let roots: Vec<String> = positional.collect();
+                    if roots.is_empty() {
+                        anyhow::bail!("cleanup 需要至少一个目录");
+                    }
+                    vec![MaintenanceOperation::CleanupOrphans { roots }]
+                }
+                Some(other) => anyhow::bail!("未知的维护操作: {}\n\n{}", other, usage()),
+            };
+            CliCommand::Maintain { operations }
+        }
+        Some("backup") => CliCommand::Backup {
+            dest: positional.next().map(PathBuf::from),
+        },
+        Some("restore") => CliCommand::Restore {
+            source: positional.next().map(PathBuf::from),
+        },
+        Some("compare") => {
+            let left = positional.next().context("compare 需要两个数据库名称")?;
+            let right = positional.next().context("compare 需要两个数据库名称")?;
+            let key = match positional.next().as_deref() {
+                None | Some("path") => CompareKey::Path,
+                Some("etag") => CompareKey::Etag,
+                Some(other) => anyhow::bail!("未知的比较方式: {}（可选 path 或 etag）", other),
+            };
+            CliCommand::Compare { left, right, key }
+        }
+        Some("remote") => {
+            let line = positional.collect::<Vec<_>>().join(" ");
+            if line.is_empty() {
+                anyhow::bail!("remote 需要一个命令\n\n{}", usage());
+            }
+            CliCommand::Remote {
+                request: InstanceRequest::parse(&line)?,
+            }
+        }
+        Some(other) => anyhow::bail!("未知命令: {}\n\n{}", other, usage()),
+        None => anyhow::bail!("{}", usage()),
+    };
+
+    Ok(Some(CliArgs { command, json }))
+}
+
+/// 执行命令行子命令
+///
+/// # Arguments
+/// * `args` - 解析后的命令行参数
+/// * `config` - 应用配置
+pub async fn run(args: CliArgs, config: AppConfig) -> Result<()> {
+    // 请求由运行中的实例处理，不打开本地数据库
+    if let CliCommand::Remote { request } = &args.command {
+        return remote(request, args.json).await;
+    }
+
+    let config = Arc::new(Mutex::new(config));
+    let mut database_manager = DatabaseManager::new(config.clone())?;
+
+    match args.command {
+        CliCommand::Search { query, limit } => {
+            let records = search(&database_manager, &query, limit)?;
+            print_records(&records, args.json);
+        }
+        CliCommand::Stats => print_stats(&mut database_manager, args.json)?,
+        CliCommand::Export { query, output } => {
+            // SQLite 中负数 LIMIT 表示不限制数量
+            let records = search(&database_manager, &query, usize::MAX)?;
+            let target = output.unwrap_or_else(|| {
+                paths::export_dir().join(format!(
+                    "search-{}.csv",
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                ))
+            });
+            export_file_records(&records, &target).context("Failed to export records")?;
+            if args.json {
+                println!("{}", json!({ "exported": records.len(), "path": target }));
+            } else {
+                println!("已导出 {} 条记录到 {}", records.len(), target.display());
+            }
+        }
+        CliCommand::Send { id } => {
+            let record = find_record(&database_manager, id)?;
+            let config = config.lock().unwrap().clone();
+            let download_dir = config.database.download_dir.clone();
+            shared_link_resolver().set_parent_folder(config.database.remote_folder_id);
+            let result = send(&record, &config, download_dir).await?;
+            if args.json {
+                println!("{}", json!({ "id": id, "result": result }));
+            } else {
+                println!("{}", result);
+            }
+        }
+        CliCommand::Sync { root_id } => {
+            let notifier = Notifier::new(config.lock().unwrap().notifications.clone());
+            let summary = sync(root_id).await.inspect_err(|e| {
+                notifier.notify(NotificationKind::SyncError, "网盘同步失败", &format!("{:#}", e));
+            })?;
+            if args.json {
+                println!(
+                    "{}",
+                    json!({
+                        "added": summary.added,
+                        "updated": summary.updated,
+                        "unchanged": summary.unchanged,
+                        "removed": summary.removed,
+                    })
+                );
+            } else {
+                println!(
+                    "同步完成: 新增 {}，更新 {}，未变化 {}，删除 {}",
+                    summary.added, summary.updated, summary.unchanged, summary.removed
+                );
+            }
+        }
+        CliCommand::Maintain { operations } => {
+            let report = database_manager.run_maintenance(&operations, |index, total, operation| {
+                eprintln!("[{}/{}] {}...", index, total, operation.label());
+            })?;
+            let size = |size: Option<u64>| size.map(format_size).unwrap_or_else(|| "-".to_string());
+            if args.json {
+                println!(
+                    "{}",
+                    json!({
+                        "orphans_removed": report.orphans_removed,
+                        "size_before": report.size_before,
+                        "size_after": report.size_after,
+                    })
+                );
+            } else {
+                println!(
+                    "维护完成: 清理孤立记录 {} 条，数据库大小 {} -> {}",
+                    report.orphans_removed,
+                    size(report.size_before),
+                    size(report.size_after)
+                );
+            }
+        }
+        CliCommand::Backup { dest } => {
+            let index = database_manager.get_current_database_index();
+            let dest = dest.unwrap_or_else(|| {
+                DatabaseManager::default_backup_path(&database_manager.get_current_database_config())
+            });
+            database_manager.backup_database(index, &dest)?;
+            if args.json {
+                println!("{}", json!({ "backup": dest }));
+            } else {
+                println!("已备份到 {}", dest.display());
+            }
+        }
+        CliCommand::Restore { source } => {
+            let index = database_manager.get_current_database_index();
+            let source = match source {
+                Some(source) => source,
+                None => DatabaseManager::latest_backup(&database_manager.get_current_database_config())
+                    .context("备份目录中没有当前数据库的备份")?,
+            };
+            let records = validate_backup(&source)?;
+            let safety_copy = database_manager.restore_database(index, &source)?;
+            if args.json {
+                println!(
+                    "{}",
+                    json!({ "restored": source, "files": records, "previous": safety_copy })
+                );
+            } else {
+                println!(
+                    "已从 {} 恢复 {} 条记录，原数据已备份到 {}",
+                    source.display(),
+                    records,
+                    safety_copy.display()
+                );
+            }
+        }
+        CliCommand::Compare { left, right, key } => {
+            let index = |name: &str| {
+                config
+                    .lock()
+                    .unwrap()
+                    .database_index_by_name(name)
+                    .with_context(|| format!("未找到数据库: {}", name))
+            };
+            let diff = database_manager.compare_databases(index(&left)?, index(&right)?, key)?;
+            print_diff(&diff, &left, &right, args.json);
+        }
+    }
+
+    Ok(())
+}
+
+/// 输出两个数据库的比较结果
+fn print_diff(diff: &DatabaseDiff, left: &str, right: &str, as_json: bool) {
+    let newer = match diff.newer() {
+        Some(std::cmp::Ordering::Greater) => Some(left),
+        Some(_) => Some(right),
+        None => None,
+    };
+    if as_json {
+        let changed: Vec<_> = diff
+            .changed
+            .iter()
+            .map(|(a, b)| json!({ "left": a, "right": b }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "only_left": diff.only_left,
+                "only_right": diff.only_right,
+                "changed": changed,
+                "newer": newer,
+            }))
+            .unwrap_or_default()
+        );
+        return;
+    }
+
+    for record in &diff.only_left {
+        println!("- {}", record.path);
+    }
+    for record in &diff.only_right {
+        println!("+ {}", record.path);
+    }
+    for (a, b) in &diff.changed {
+        if a.path == b.path {
+            println!("~ {} ({} -> {})", a.path, a.etag, b.etag);
+        } else {
+            println!("~ {} -> {}", a.path, b.path);
+        }
+    }
+    println!(
+        "仅在 {} 中: {}，仅在 {} 中: {}，不同: {}",
+        left,
+        diff.only_left.len(),
+        right,
+        diff.only_right.len(),
+        diff.changed.len()
+    );
+    match newer {
+        _ if diff.is_identical() => println!("两个数据库的记录一致"),
+        Some(name) => println!("{} 包含更新的文件", name),
+        None => {}
+    }
+}
+
+/// 在当前数据库中搜索
+fn search(database_manager: &DatabaseManager, query: &str, limit: usize) -> Result<Vec<FileRecord>> {
+    let database = database_manager.get_current_database();
+    let database = database.lock().unwrap();
+    database.search_files_paged(query, &SearchOptions::default(), 0, limit)
+}
+
+/// 按 ID 查找文件记录
+fn find_record(database_manager: &DatabaseManager, id: i64) -> Result<FileRecord> {
+    let database = database_manager.get_current_database();
+    let record = database.lock().unwrap().find_file(id)?;
+    record.with_context(|| format!("未找到记录 ID {}", id))
+}
+
+/// 向运行中的实例发送请求并输出结果
+async fn remote(request: &InstanceRequest, as_json: bool) -> Result<()> {
+    let payload = single_instance::send(request)
+        .await
+        .context("无法连接运行中的实例，请先启动界面")?;
+    match request {
+        InstanceRequest::Search { .. } if !as_json => {
+            let records: Vec<FileRecord> =
+                serde_json::from_str(&payload).context("Invalid search response from the running instance")?;
+            print_records(&records, false);
+        }
+        InstanceRequest::Search { .. } => println!("{}", payload),
+        _ if as_json => println!("{}", json!({ "request": request.to_line(), "result": payload })),
+        _ if !payload.is_empty() => println!("{}", payload),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 输出文件记录
+fn print_records(records: &[FileRecord], as_json: bool) {
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(records).unwrap_or_default());
+        return;
+    }
+
+    println!("{:>8}  {:>10}  {:<19}  {}", "ID", "大小", "修改时间", "路径");
+    for record in records {
+        println!(
+            "{:>8}  {:>10}  {:<19}  {}",
+            record.id,
+            format_size(record.size),
+            format_datetime(record.modified_time),
+            record.path
+        );
+    }
+}
+
+/// 输出各数据库的文件数量
+fn print_stats(database_manager: &mut DatabaseManager, as_json: bool) -> Result<()> {
+    let databases = database_manager.get_database_list();
+    let mut stats = Vec::new();
+
+    for (name, db_type, index) in databases {
+        let count = database_manager.switch_database(index).and_then(|_| {
+            let database = database_manager.get_current_database();
+            let count = database.lock().unwrap().count_files("", &SearchOptions::default());
+            count
+        });
+        match count {
+            Ok(count) => stats.push((name, db_type, count)),
+            Err(e) => warn!("Failed to count files in {}: {}", name, e),
+        }
+    }
+
+    if as_json {
+        let stats: Vec<_> = stats
+            .iter()
+            .map(|(name, db_type, count)| json!({ "name": name, "type": db_type, "files": count }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap_or_default());
+    } else {
+        println!("{:<24}  {:<8}  {:>10}", "数据库", "类型", "文件数");
+        for (name, db_type, count) in &stats {
+            println!("{:<24}  {:<8}  {:>10}", name, db_type, count);
+        }
+    }
+
+    Ok(())
+}
+
+/// 从网盘同步文件列表到数据目录中的网盘数据库
+async fn sync(root_id: i64) -> Result<SyncSummary> {
+    let db_path = paths::data_dir().join(REMOTE_DATABASE_FILE);
+    let database = SqliteDatabase::new(&db_path.to_string_lossy())?;
+    database.init_database()?;
+
+    let sync = RemoteSync::new(shared_api_client(), Arc::new(Mutex::new(database)));
+    sync.sync(root_id, "/", |progress| {
+        eprint!(
+            "\r已同步 {} 个文件夹，{} 个文件: {}",
+            progress.folders, progress.scanned, progress.current_dir
+        );
+    })
+    .await
+    .inspect(|_| eprintln!())
+}
+
+/// 将文件发送到下载：优先使用 Aria2，不可用时使用内置下载器
+async fn send(record: &FileRecord, config: &AppConfig, target_dir: Option<String>) -> Result<String> {
+    #[cfg(feature = "aria2")]
+    {
+        if let Some(result) = send_to_aria2(record, config, &target_dir).await? {
+            return Ok(result);
+        }
+    }
+
+    warn!("Aria2 not available, falling back to built-in downloader");
+    let url = get_file_url(&record.path, &record.etag, record.size)
+        .await
+        .map_err(|e| anyhow::anyhow!("获取下载链接失败: {}", e))?;
+    let file_name = format_upload_filename(&record.path).unwrap_or_else(|| record.etag.clone());
+    let downloader = HttpDownloader::new(target_dir.unwrap_or_else(|| config.aria2.download_dir.clone()));
+    let target = downloader
+        .download(&url, &file_name, Some(record.size), |progress| {
+            if let Some(percent) = progress.percent() {
+                eprint!("\r正在下载 {}: {}%", file_name, percent);
+            }
+        })
+        .await?;
+    eprintln!();
+    sidecar::write_logged(&target, &DownloadSidecar::for_record(record, &config.database.name));
+    Ok(format!("下载完成: {}", target.display()))
+}
+
+/// 将文件交给 Aria2 下载
+///
+/// 界面实例在运行时通过本地套接字交给它的 Aria2，立即返回；否则启动本地 aria2c 并等待下载结束，
+/// 返回时 aria2c 随 `Aria2Service` 一起退出
+///
+/// # Returns
+/// * `Result<Option<String>>` - 结果说明，Aria2 不可用时为 None
+#[cfg(feature = "aria2")]
+async fn send_to_aria2(record: &FileRecord, config: &AppConfig, target_dir: &Option<String>) -> Result<Option<String>> {
+    if single_instance::is_running().unwrap_or(false) {
+        let url = get_file_url(&record.path, &record.etag, record.size)
+            .await
+            .map_err(|e| anyhow::anyhow!("获取下载链接失败: {}", e))?;
+        // 协议一行一个请求，文件名中不能有换行
+        let name = record.name.replace(['\r', '\n'], " ");
+        let request = InstanceRequest::AddDownload {
+            url,
+            name: (!name.trim().is_empty()).then_some(name),
+        };
+        let gid = single_instance::send(&request)
+            .await
+            .context("Failed to add download to the running instance")?;
+        return Ok(Some(format!("已添加到运行中实例的 Aria2: {}", gid)));
+    }
+
+    let aria2_service = create_shared_aria2_service(config.aria2.clone());
+    let started = aria2_service.lock().unwrap().start().is_ok();
+    let ready = if started {
+        let service = aria2_service.lock().unwrap();
+        service.wait_until_ready(10).await
+    } else {
+        false
+    };
+
+    let download_manager = DownloadManager::new(aria2_service.clone());
+    let client = aria2_service.lock().unwrap().get_client().cloned();
+    let Some(client) = client.filter(|_| ready) else {
+        return Ok(None);
+    };
+    let gid = download_manager
+        .enqueue(DownloadSource::from_record(record, target_dir.clone()).with_database(&config.database.name))
+        .await
+        .context("Failed to add download to Aria2")?;
+    let save_path = download_manager
+        .tracked_tasks()
+        .into_iter()
+        .find(|task| task.gid == gid)
+        .map(|task| task.save_path)
+        .unwrap_or_else(|| record.name.clone());
+
+    wait_for_aria2(&client, &gid, &record.name).await?;
+    Ok(Some(format!("下载完成: {}", save_path)))
+}
+
+/// 等待本地 Aria2 中的任务结束并显示进度
+///
+/// # Arguments
+/// * `client` - Aria2客户端
+/// * `gid` - 任务GID
+/// * `name` - 显示的文件名
+#[cfg(feature = "aria2")]
+async fn wait_for_aria2(client: &Aria2Client, gid: &str, name: &str) -> Result<()> {
+    loop {
+        let status = client.get_status(gid).await.context("Failed to query Aria2 download")?;
+        match status["status"].as_str().unwrap_or("") {
+            "complete" => {
+                eprintln!();
+                return Ok(());
+            }
+            "error" => {
+                eprintln!();
+                let message = status["errorMessage"].as_str().unwrap_or("unknown error");
+                anyhow::bail!("下载失败: {}", message);
+            }
+            "removed" => {
+                eprintln!();
+                anyhow::bail!("下载任务已被移除");
+            }
+            _ => {}
+        }
+        let length = |key: &str| status[key].as_str().and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+        let total = length("totalLength");
+        if total > 0 {
+            eprint!("\r正在下载 {}: {}%", name, length("completedLength") * 100 / total);
+        }
+        tokio::time::sleep(ARIA2_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,116 @@
+//! 收藏集接口 - 管理收藏集及其中的文件
+//!
+//! - `GET /api/collections` 列出收藏集
+//! - `POST /api/collections` 创建收藏集，请求体 `{"name": "..."}`
+//! - `PUT /api/collections/{id}` 重命名收藏集，请求体 `{"name": "..."}`
+//! - `DELETE /api/collections/{id}` 删除收藏集
+//! - `GET /api/collections/{id}/items` 列出收藏集中的文件
+//! - `POST /api/collections/{id}/items` 加入文件，请求体 `{"database": "...", "records": [...]}`
+//! - `DELETE /api/collections/{id}/items` 移除文件，请求体 `{"database": "...", "path": "..."}`
+
+use crate::models::database::FileRecord;
+use crate::services::collections::{shared_collections, SharedCollections};
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use serde_json::json;
+
+/// 注册收藏集路由
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/collections", web::get().to(list_collections))
+        .route("/api/collections", web::post().to(create_collection))
+        .route("/api/collections/{id}", web::put().to(rename_collection))
+        .route("/api/collections/{id}", web::delete().to(delete_collection))
+        .route("/api/collections/{id}/items", web::get().to(list_items))
+        .route("/api/collections/{id}/items", web::post().to(add_items))
+        .route("/api/collections/{id}/items", web::delete().to(remove_item));
+}
+
+#[derive(Deserialize)]
+struct NameRequest {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct AddItemsRequest {
+    database: String,
+    records: Vec<FileRecord>,
+}
+
+#[derive(Deserialize)]
+struct RemoveItemRequest {
+    database: String,
+    path: String,
+}
+
+/// 执行收藏集操作，未启用收藏集时返回 404，操作失败时返回 400 和错误信息
+fn with_collections(action: impl FnOnce(&SharedCollections) -> anyhow::Result<HttpResponse>) -> HttpResponse {
+    match shared_collections() {
+        Some(collections) => action(&collections).unwrap_or_else(|e| {
+            HttpResponse::BadRequest().json(json!({ "error": format!("{:#}", e) }))
+        }),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn list_collections() -> HttpResponse {
+    with_collections(|collections| Ok(HttpResponse::Ok().json(collections.list()?)))
+}
+
+async fn create_collection(body: web::Json<NameRequest>) -> HttpResponse {
+    with_collections(|collections| {
+        let id = collections.create(&body.name)?;
+        Ok(HttpResponse::Created().json(collections.get(id)?))
+    })
+}
+
+async fn rename_collection(id: web::Path<i64>, body: web::Json<NameRequest>) -> HttpResponse {
+    with_collections(|collections| {
+        let id = id.into_inner();
+        if collections.get(id)?.is_none() {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        collections.rename(id, &body.name)?;
+        Ok(HttpResponse::Ok().json(collections.get(id)?))
+    })
+}
+
+async fn delete_collection(id: web::Path<i64>) -> HttpResponse {
+    with_collections(|collections| {
+        Ok(if collections.delete(id.into_inner())? {
+            HttpResponse::NoContent().finish()
+        } else {
+            HttpResponse::NotFound().finish()
+        })
+    })
+}
+
+async fn list_items(id: web::Path<i64>) -> HttpResponse {
+    with_collections(|collections| {
+        let id = id.into_inner();
+        if collections.get(id)?.is_none() {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        Ok(HttpResponse::Ok().json(collections.items(id)?))
+    })
+}
+
+async fn add_items(id: web::Path<i64>, body: web::Json<AddItemsRequest>) -> HttpResponse {
+    with_collections(|collections| {
+        let id = id.into_inner();
+        if collections.get(id)?.is_none() {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        let added = collections.add_records(id, &body.records, &body.database)?;
+        Ok(HttpResponse::Ok().json(json!({ "added": added })))
+    })
+}
+
+async fn remove_item(id: web::Path<i64>, body: web::Json<RemoveItemRequest>) -> HttpResponse {
+    with_collections(|collections| {
+        Ok(if collections.remove_record(id.into_inner(), &body.database, &body.path)? {
+            HttpResponse::NoContent().finish()
+        } else {
+            HttpResponse::NotFound().finish()
+        })
+    })
+}
@@ -0,0 +1,166 @@
+//! 右键菜单扩展 - 下游程序注册自定义的右键菜单项
+//!
+//! 使用 `ContextMenuManager::builder()` 添加菜单项，并在启动界面前调用
+//! `set_shared_context_menu` 注册。菜单项显示在右键菜单末尾，
+//! 点击后处理函数收到当前选中的文件记录，返回的文本显示在状态栏
+//!
+//! ```no_run
+//! use netdisk_db::controllers::context_menu::{set_shared_context_menu, ContextMenuManager};
+//!
+//! let menu = ContextMenuManager::builder()
+//!     .action("复制 ETag", |records| async move {
+//!         let etags: Vec<String> = records.into_iter().map(|r| r.etag).collect();
+//!         anyhow::Ok(format!("ETag: {}", etags.join(", ")))
+//!     })
+//!     .build();
+//! set_shared_context_menu(menu);
+//! ```
+
+use crate::models::database::FileRecord;
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, info};
+
+/// 菜单项处理函数返回的任务
+pub type MenuActionFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+
+/// 菜单项处理函数，参数为选中的文件记录
+type MenuActionHandler = Arc<dyn Fn(Vec<FileRecord>) -> MenuActionFuture + Send + Sync>;
+
+/// 选中项是否显示该菜单项的判断函数
+type MenuActionFilter = Arc<dyn Fn(&[FileRecord]) -> bool + Send + Sync>;
+
+/// 一个自定义菜单项
+#[derive(Clone)]
+pub struct MenuAction {
+    label: String,
+    filter: Option<MenuActionFilter>,
+    handler: MenuActionHandler,
+}
+
+impl MenuAction {
+    /// 菜单中显示的名称
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// 选中这些文件时是否显示
+    pub fn is_available(&self, records: &[FileRecord]) -> bool {
+        !records.is_empty() && self.filter.as_ref().map_or(true, |filter| filter(records))
+    }
+}
+
+/// 自定义右键菜单项的集合
+#[derive(Clone, Default)]
+pub struct ContextMenuManager {
+    actions: Vec<MenuAction>,
+}
+
+/// `ContextMenuManager` 构建器
+#[derive(Default)]
+pub struct ContextMenuBuilder {
+    actions: Vec<MenuAction>,
+}
+
+impl ContextMenuBuilder {
+    /// 添加菜单项
+    ///
+    /// # Arguments
+    /// * `label` - 菜单中显示的名称
+    /// * `handler` - 处理函数，返回的文本显示在状态栏
+    pub fn action<F, Fut>(self, label: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Vec<FileRecord>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.push(label.into(), None, handler)
+    }
+
+    /// 添加只在满足条件时显示的菜单项
+    ///
+    /// # Arguments
+    /// * `label` - 菜单中显示的名称
+    /// * `filter` - 根据选中的文件判断是否显示
+    /// * `handler` - 处理函数，返回的文本显示在状态栏
+    pub fn action_when<P, F, Fut>(self, label: impl Into<String>, filter: P, handler: F) -> Self
+    where
+        P: Fn(&[FileRecord]) -> bool + Send + Sync + 'static,
+        F: Fn(Vec<FileRecord>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.push(label.into(), Some(Arc::new(filter)), handler)
+    }
+
+    fn push<F, Fut>(mut self, label: String, filter: Option<MenuActionFilter>, handler: F) -> Self
+    where
+        F: Fn(Vec<FileRecord>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        debug!("Registering context menu action: {}", label);
+        self.actions.push(MenuAction {
+            label,
+            filter,
+            handler: Arc::new(move |records| Box::pin(handler(records))),
+        });
+        self
+    }
+
+    /// 完成构建
+    pub fn build(self) -> ContextMenuManager {
+        ContextMenuManager { actions: self.actions }
+    }
+}
+
+impl ContextMenuManager {
+    /// 创建构建器
+    pub fn builder() -> ContextMenuBuilder {
+        ContextMenuBuilder::default()
+    }
+
+    /// 所有菜单项，按注册顺序
+    pub fn actions(&self) -> &[MenuAction] {
+        &self.actions
+    }
+
+    /// 选中这些文件时显示的菜单项及其索引
+    pub fn available_actions(&self, records: &[FileRecord]) -> Vec<(usize, &MenuAction)> {
+        self.actions
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| action.is_available(records))
+            .collect()
+    }
+
+    /// 执行菜单项
+    ///
+    /// # Arguments
+    /// * `index` - 菜单项在 `actions()` 中的索引
+    /// * `records` - 选中的文件记录
+    ///
+    /// # Returns
+    /// * `Option<MenuActionFuture>` - 索引无效时为 None
+    pub fn run(&self, index: usize, records: Vec<FileRecord>) -> Option<MenuActionFuture> {
+        let action = self.actions.get(index)?;
+        info!("Running context menu action '{}' on {} files", action.label, records.len());
+        Some((action.handler)(records))
+    }
+}
+
+/// 界面使用的自定义菜单项，启动前设置
+static SHARED_CONTEXT_MENU: OnceLock<Arc<ContextMenuManager>> = OnceLock::new();
+
+/// 设置自定义菜单项，只能设置一次
+pub fn set_shared_context_menu(manager: ContextMenuManager) {
+    if SHARED_CONTEXT_MENU.set(Arc::new(manager)).is_err() {
+        debug!("Context menu actions already set");
+    }
+}
+
+/// 获取自定义菜单项，未设置时为空
+pub fn shared_context_menu() -> Arc<ContextMenuManager> {
+    SHARED_CONTEXT_MENU
+        .get_or_init(|| Arc::new(ContextMenuManager::default()))
+        .clone()
+}
@@ -2,19 +2,81 @@
 //!
 //! 包含所有用户交互和业务流程的处理函数
 
-use crate::models::database::Database;
-use crate::services::database_manager::DatabaseManager;
-use crate::views::ui::{database_list_to_string_model, file_records_to_model, AppWindow, FileItem};
-use actix_web::Result;
-use arboard::Clipboard;
-use netdisk_core::responses::prelude::{DownloadUrlResponse, FileQuery, UploadFileResponse};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use slint::{ModelRc, VecModel};
-use std::path::Path;
+use crate::controllers::context_menu::shared_context_menu;
+use crate::models::config::{AppConfig, DatabaseConfig};
+use crate::models::database::{
+    parent_folder, CompareKey, Database, FileRecord, GroupBy, MaintenanceOperation, RenameEntry, SortColumn,
+};
+use crate::services::activity_log::{ActivityKind, ActivityLog};
+pub use crate::services::api_client::{LinkError, UploadFileItemPayload};
+use crate::services::api_client::{shared_api_client, ShareRequest};
+use crate::services::clipboard::ClipboardHandle;
+use crate::services::collections::Collections;
+use crate::services::download_history::DownloadHistory;
+use crate::services::link_format::{LinkEntry, LinkFormat};
+use crate::services::link_resolver::shared_link_resolver;
+// 不依赖界面的函数已移到服务和工具模块，这里保留原来的导出路径
+pub use crate::services::export::export_file_records;
+pub use crate::services::link_resolver::{get_file_url, get_file_urls, get_file_urls_with_progress};
+pub use crate::utils::common::format_upload_filename;
+use crate::services::database::sqlite::SqliteDatabase;
+use crate::services::errors::{self, ErrorCategory};
+use crate::services::events::{self, AppEvent};
+use crate::services::media_info::{shared_media_info, MediaInfoService};
+use crate::services::metrics;
+use crate::services::pending_ops::{shared_pending_operations, PendingKind};
+use crate::services::notifications::{self, NotificationKind};
+use crate::services::remote_sync::list_folders;
+use crate::services::rename::{self, RenamePattern};
+use crate::services::sidecar;
+use crate::services::sql_console;
+use crate::services::subtitles;
+use crate::services::database_manager::{DatabaseHandle, DatabaseManager, DatabaseSnapshot};
+use crate::services::diagnostics::shared_query_diagnostics;
+use crate::services::indexer::{IndexProgress, IndexSummary, Indexer};
+use crate::services::journal::IndexOperation;
+use crate::views::ui::{
+    active_categories, activity_entries_to_model, clamp_menu_position, collection_items_to_model, collections_to_model,
+    custom_menu_actions_to_model, download_entries_to_model, download_history_to_model,
+    database_list_to_string_model, file_item_size, file_item_to_record, file_record_to_item, file_records_to_model,
+    file_items_to_records, format_result_summary, qr_code_image, remote_folders_to_model, scope_crumbs_to_model,
+    search_options_from_ui,
+    select_row, selected_file_items, toggle_group, type_filter_chips, AppWindow, FileItem, GroupedFileModel,
+    LazyFileModel, RemoteFolderBrowser, GROUPED_RESULT_LIMIT,
+};
+use crate::utils::common::file_exists;
+use crate::utils::path_mapping::map_path;
+use crate::utils::filetype::FileCategory;
+use crate::utils::locale::{format_datetime, format_size};
+use crate::utils::paths;
+use anyhow::Context;
+use slint::{ComponentHandle, Model, ModelRc, VecModel};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// 分享有效期选项（天），与界面中的选项顺序一致，0 表示永久
+pub const SHARE_EXPIRE_DAYS: [u32; 4] = [1, 7, 30, 0];
+
+/// 在界面事件循环中执行异步任务，事件循环已停止等无法启动的情况上报错误
+///
+/// # Arguments
+/// * `context` - 任务名称，用于错误信息
+/// * `future` - 异步任务
+pub fn spawn_ui_task(context: &str, future: impl std::future::Future<Output = ()> + 'static) {
+    if let Err(e) = slint::spawn_local(future) {
+        errors::report_as(ErrorCategory::Other, &format!("无法启动{}", context), e);
+    }
+}
+
+/// "最近"视图显示的活动记录数
+const RECENT_ACTIVITY_LIMIT: usize = 200;
+
+/// 下载历史视图显示的记录数
+const DOWNLOAD_HISTORY_LIMIT: usize = 500;
+
 /// 文件下载处理函数（模拟实现）
 ///
 /// # Arguments
@@ -28,124 +90,11 @@ where
     debug!("Download proc finished");
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(rename_all = "camelCase")] // 关键！将 Rust 的 snake_case 映射到 JSON 的 camelCase
-pub struct UploadFileItemPayload {
-    #[serde(alias = "parentFileID")]
-    pub parent_file_id: i64,
-    pub filename: String, // 字段类型必须确定，不能是泛型 T
-    pub etag: String,
-    pub size: u64,
-}
-
-/// 发送文件上传请求到服务器
-///
-/// # Arguments
-/// * `data` - 文件上传数据
-/// # Returns
-/// * `Result<()>` - 成功返回 Ok，失败返回错误
-pub async fn send_file_upload_request(
-    client: &Client,
-    data: UploadFileItemPayload,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let url = "http://127.0.0.1:8080/file/upload";
-
-    info!("正在发送文件上传 POST 请求到: {}", url);
-    debug!("请求数据: {:?}", data);
-
-    // 发送 POST 请求
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .json(&data)
-        .send()
-        .await?;
-
-    let status = response.status();
-    info!("响应状态: {}", status);
-
-    // 检查 HTTP 状态码是否为成功状态
-    if !status.is_success() {
-        let error_text = response.text().await?;
-        return Err(format!(
-            "HTTP 请求失败，状态码: {}，错误信息: {}",
-            status, error_text
-        )
-        .into());
-    } else {
-        // info!("文件上传请求成功，状态码: {:?}", &response.text().await?);
-        let resp: UploadFileResponse = serde_json::from_str(&response.text().await?)?;
-        let url = resp
-            .data
-            .ok_or("响应数据确实，也许数据已经上传过了....")?
-            .file_id
-            .ok_or("服务器列表为空")?
-            .to_string();
-        Ok(url)
-    }
-}
-
-/// TODO 获取函数的url这个函数有问题
-pub async fn get_download_url(
-    client: &Client,
-    query: &FileQuery,
-) -> Result<DownloadUrlResponse, Box<dyn std::error::Error>> {
-    // 基础请求 URL
-    let base_url = "http://127.0.0.1:8080/file/download";
-
-    // 发送 GET 请求，携带查询参数
-    let response = client
-        .get(base_url)
-        .query(query) // 自动将 FileQuery 转为 URL 查询参数（如 ?fileId=19349166）
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| Box::new(e))?;
-
-    // 检查 HTTP 状态码
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_body = response.text().await.unwrap_or_default();
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("请求失败，状态码: {}, 错误信息: {}", status, error_body),
-        )));
-    }
-
-    // 将响应体反序列化为 DownloadUrlResponse
-    let download_response: DownloadUrlResponse = response.json().await.map_err(|e| Box::new(e))?;
-
-    // 检查业务状态码（如果接口用 code 字段表示业务成功）
-    if download_response.code != 0 {
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!(
-                "业务处理失败: code={}, message={}",
-                download_response.code, download_response.message
-            ),
-        )));
-    }
-
-    Ok(download_response)
-}
-
-/// 规范化文件名称，这里需要重构
-pub fn format_upload_filename<T>(filename: T) -> Option<String>
-where
-    T: AsRef<Path>,
-{
-    let path = filename.as_ref();
-
-    path.file_name()
-        // 3. 将 &OsStr 转换为 String (或 &str)
-        .and_then(|os_str| os_str.to_str())
-        .map(|s| s.to_string())
-}
 /// 发送到 Aria2 处理函数（模拟实现）
 ///
 /// # Arguments
 /// * `_url` - 文件URL或路径
-pub async fn send_to_aria2<T>(path: T, etag: T, size: u64) -> Result<(), Box<dyn std::error::Error>>
+pub async fn send_to_aria2<T>(path: T, etag: T, size: u64) -> Result<(), LinkError>
 where
     T: AsRef<str> + std::fmt::Debug,
 {
@@ -154,114 +103,51 @@ where
         &path, &etag, size
     );
 
-    // 构造请求体数据
-    let name = format_upload_filename(path.as_ref()).unwrap();
-    let payload = UploadFileItemPayload {
-        parent_file_id: 0,
-        filename: name,
-        etag: etag.as_ref().to_string(),
-        size: size,
-    };
-
-    // 创建 HTTP 客户端
-    let client = Client::new();
-    // 发送文件上传请求
-    let mut file_id: String = String::new();
-    match send_file_upload_request(&client, payload).await {
-        Ok(mesg) => {
-            file_id = mesg.clone();
-            info!("后台服务请求成功完成。{:?}", &mesg);
-        }
-        Err(e) => {
-            error!("请求失败，错误信息: {}", e);
-            return Err(e);
-        }
-    }
-    let query = FileQuery {
-        file_id: file_id.parse::<i64>().unwrap_or(0),
-    };
-    debug!("准备获取下载链接，查询参数: {:?}", &query);
-
-    let mut link = String::new();
-    match get_download_url(&client, &query).await {
-        Ok(download_response) => {
-            if let Some(data) = download_response.data {
-                info!("响应数据: {:?}", &data.download_url);
-                link = data.download_url;
-                // if let Some(urls) = data.download_url {
-                //     if let Some(first_url) = urls.first() {
-                //         info!("获取到下载链接: {}", first_url);
-                //         // 这里可以调用实际的 Aria2 接口来添加下载任务
-                //         // 例如：aria2.addUri([first_url], options);
-                //     } else {
-                //         error!("下载链接列表为空");
-                //     }
-                // } else {
-                //     error!("响应数据中没有下载链接");
-                // }
-            } else {
-                error!("响应数据为空");
-            }
-        }
-        Err(_e) => {
-            error!("获取下载链接失败，错误信息: {}", _e);
-            return Err(_e);
-        }
-    }
+    // 获取下载链接（同一文件短时间内使用缓存的链接）
+    let link = shared_link_resolver()
+        .resolve(path.as_ref(), etag.as_ref(), size)
+        .await?;
+    debug!("获取到下载链接: {}", link);
 
     debug!("Send to Aria2 proc finished");
     Ok(())
 }
-pub async fn get_file_url<T>(
-    path: T,
-    etag: T,
-    size: u64,
-) -> Result<String, Box<dyn std::error::Error>>
-where
-    T: AsRef<str> + std::fmt::Debug,
-{
-    let name = format_upload_filename(path.as_ref()).unwrap();
-    let payload = UploadFileItemPayload {
-        parent_file_id: 0,
-        filename: name,
-        etag: etag.as_ref().to_string(),
-        size: size,
-    };
-
-    let client = Client::new();
-    // 发送文件上传请求
-    let mut file_id: String = String::new();
-    match send_file_upload_request(&client, payload).await {
-        Ok(mesg) => {
-            file_id = mesg.clone();
-            info!("后台服务请求成功完成。{:?}", &mesg);
-        }
-        Err(e) => {
-            error!("请求失败，错误信息: {}", e);
-            return Err(e);
-        }
+/// 开启了网盘存在性检查时，过滤掉网盘中已不存在的文件
+///
+/// 不存在的文件会被标记为"已失效"；检查出错（如网络错误）的文件保留，由后续流程报告错误
+///
+/// # Arguments
+/// * `items` - 选中的文件项
+///
+/// # Returns
+/// * `(Vec<FileItem>, usize)` - 仍然存在的文件和已失效的文件数
+pub async fn filter_remote_existing(items: Vec<FileItem>) -> (Vec<FileItem>, usize) {
+    let resolver = shared_link_resolver();
+    if !resolver.verifies_remote() {
+        return (items, 0);
     }
-    let query = FileQuery {
-        file_id: file_id.parse::<i64>().unwrap_or(0),
-    };
-    debug!("准备获取下载链接，查询参数: {:?}", &query);
 
-    let mut link = String::new();
-    match get_download_url(&client, &query).await {
-        Ok(download_response) => {
-            if let Some(data) = download_response.data {
-                info!("响应数据: {:?}", &data.download_url);
-                link = data.download_url;
-            } else {
-                error!("响应数据为空");
+    let mut existing = Vec::with_capacity(items.len());
+    let mut missing = 0;
+    for item in items {
+        let Ok(size) = file_item_size(&item) else {
+            // 大小无效的文件保留，由后续流程报告错误
+            existing.push(item);
+            continue;
+        };
+        match resolver.check_remote(&item.path, &item.etag, size).await {
+            Ok(false) => {
+                info!("Remote file missing: {}", item.path);
+                missing += 1;
+            }
+            Ok(true) => existing.push(item),
+            Err(e) => {
+                debug!("Failed to check remote file {}: {}", item.path, e);
+                existing.push(item);
             }
-        }
-        Err(_e) => {
-            error!("获取下载链接失败，错误信息: {}", _e);
-            return Err(_e);
         }
     }
-    Some(link).ok_or("无法获取下载链接".into())
+    (existing, missing)
 }
 
 /// 发送到 url 到系统剪切板
@@ -270,283 +156,2010 @@ where
 /// * `path` - 文件路径
 /// * `etag` - 文件ETag
 /// * `size` - 文件大小
-/// * `clipboard` - 持久化的剪切板实例引用
+/// * `clipboard` - 剪切板线程句柄
 pub async fn copy_to_clipboard<T>(
     path: T,
     etag: T,
     size: u64,
-    clipboard: &mut Clipboard,
-) -> Result<String, Box<dyn std::error::Error>>
+    clipboard: &ClipboardHandle,
+) -> Result<String, LinkError>
 where
     T: AsRef<str> + std::fmt::Debug,
 {
     let link = get_file_url(path, etag, size).await?;
 
     debug!("==>Copying link to clipboard: {}", &link);
+    clipboard.set_text(link.as_str()).await?;
 
-    // 尝试复制到剪切板，最多重试3次
-    let mut attempts = 0;
-    let max_attempts = 3;
-
-    while attempts < max_attempts {
-        match clipboard.set_text(&link) {
-            Ok(_) => {
-                info!("成功复制链接到剪切板: {}", &link);
+    Ok(link)
+}
 
-                // 保持剪切板实例存活，避免过早丢弃
-                // 短暂延迟确保剪切板管理器有足够时间读取内容
-                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+/// 获取选中文件的下载链接并显示为二维码
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+pub fn handle_show_qr_code(ui: &slint::Weak<AppWindow>) {
+    let Some(window) = ui.upgrade() else {
+        return;
+    };
+    let item = window.get_selected_file_item();
+    window.set_qr_url("".into());
+    window.set_qr_status("正在获取下载链接...".into());
+    window.set_qr_dialog_visible(true);
 
-                return Ok(link);
+    let ui = ui.clone();
+    spawn_ui_task("生成二维码", async move {
+        let result = match file_item_size(&item) {
+            Ok(size) => get_file_url(item.path.as_str(), item.etag.as_str(), size).await,
+            Err(e) => Err(e),
+        };
+        let Some(window) = ui.upgrade() else {
+            return;
+        };
+        let url = match result {
+            Ok(url) => url,
+            Err(e) => {
+                if e.is_offline() {
+                    queue_pending(PendingKind::ResolveLink, &item.path, &item.etag, file_item_size(&item).unwrap_or(0), None);
+                }
+                window.set_qr_status(format!("获取下载链接失败: {}", e).into());
+                return;
+            }
+        };
+        match qr_code_image(&url) {
+            Ok(image) => {
+                window.set_qr_code(image);
+                window.set_qr_url(url.into());
+                window.set_qr_status("".into());
             }
+            Err(e) => window.set_qr_status(format!("{:#}", e).into()),
+        }
+    });
+}
+
+/// 网盘暂时无法访问时，将操作加入离线队列，网络恢复后自动重试
+///
+/// # Arguments
+/// * `kind` - 操作类型
+/// * `path` - 文件路径
+/// * `etag` - 文件ETag
+/// * `size` - 文件大小
+/// * `target_dir` - 下载目录，仅用于下载
+pub fn queue_pending(kind: PendingKind, path: &str, etag: &str, size: u64, target_dir: Option<&str>) {
+    let Some(queue) = shared_pending_operations() else {
+        return;
+    };
+    if let Err(e) = queue.enqueue(kind, path, etag, size, target_dir) {
+        warn!("Failed to queue {:?} for {}: {}", kind, path, e);
+    }
+}
+
+/// 获取多个文件的下载链接，按指定格式复制到剪切板
+///
+/// 部分文件获取失败时仍复制成功获取的链接，因网络无法访问失败的文件加入离线队列
+///
+/// # Arguments
+/// * `items` - 选中的文件项
+/// * `format` - 输出格式
+/// * `clipboard` - 剪切板线程句柄
+/// * `on_progress` - 每获取一个链接调用一次，参数为已完成数和总数
+///
+/// # Returns
+/// * `Result<(usize, usize)>` - 成功复制的链接数量和失败的数量，全部失败时返回第一个错误
+pub async fn copy_links_to_clipboard(
+    items: Vec<FileItem>,
+    format: LinkFormat,
+    clipboard: &ClipboardHandle,
+    on_progress: impl FnMut(usize, usize),
+) -> Result<(usize, usize), LinkError> {
+    // 大小无效的文件计为失败，不影响其他文件
+    let total = items.len();
+    let (records, invalid) = file_items_to_records(&items);
+    let mut first_error = invalid.into_iter().next();
+    let requests = records
+        .into_iter()
+        .map(|record| (record.path, record.etag, record.size))
+        .collect();
+
+    let results = get_file_urls_with_progress(requests, on_progress).await;
+    let mut entries = Vec::with_capacity(total);
+    for result in results {
+        match result.link {
+            Ok(url) => entries.push(LinkEntry {
+                path: result.path,
+                etag: result.etag,
+                size: result.size,
+                url,
+            }),
             Err(e) => {
-                attempts += 1;
-                if attempts >= max_attempts {
-                    error!("复制到剪切板失败，已重试{}次: {}", attempts, e);
-                    return Err(Box::new(e));
+                error!("获取 {} 的下载链接失败: {}", result.path, e);
+                if e.is_offline() {
+                    queue_pending(PendingKind::ResolveLink, &result.path, &result.etag, result.size, None);
                 }
-                debug!("复制到剪切板失败，第{}次重试: {}", attempts, e);
-                // 等待一段时间后重试
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                first_error.get_or_insert(e);
             }
         }
     }
 
-    Err("无法复制到剪切板".into())
+    if entries.is_empty() {
+        return Err(first_error.unwrap_or(LinkError::MissingData("可复制的链接")));
+    }
+
+    debug!("==>Copying {} links to clipboard as {:?}", entries.len(), format);
+    clipboard.set_text(format.format(&entries)).await?;
+
+    Ok((entries.len(), total - entries.len()))
 }
 
-/// 处理搜索请求
+/// 为选中的文件创建分享链接，并复制到剪切板
 ///
 /// # Arguments
-/// * `query` - 搜索关键词
-/// * `ui` - UI 弱引用
-/// * `database` - 数据库实例
-/// * `last_search_time` - 上次搜索时间（用于防抖）
-/// * `search_delay` - 搜索延迟时间
-pub fn handle_search_request(
-    query: &str,
-    ui: &slint::Weak<AppWindow>,
-    database: Arc<Mutex<dyn Database>>,
-    last_search_time: Arc<Mutex<Instant>>,
-    search_delay: Duration,
-) {
-    let ui = match ui.upgrade() {
-        Some(u) => u,
-        None => return,
+/// * `items` - 选中的文件项
+/// * `password` - 提取码，为空时不设置
+/// * `expire_days` - 有效天数，0 表示永久
+/// * `clipboard` - 剪切板线程句柄
+///
+/// # Returns
+/// * `Result<String>` - 复制到剪切板的分享链接（包含提取码）
+pub async fn create_share_link(
+    items: Vec<FileItem>,
+    password: &str,
+    expire_days: u32,
+    clipboard: &ClipboardHandle,
+) -> Result<String, LinkError> {
+    let first = items.first().ok_or(LinkError::MissingData("选中的文件"))?;
+    let share_name = if items.len() > 1 {
+        format!("{} 等 {} 个文件", first.name, items.len())
+    } else {
+        first.name.to_string()
     };
 
-    // 防抖检查
-    let now = Instant::now();
-    let mut last_time = last_search_time.lock().unwrap();
-
-    if now.duration_since(*last_time) < search_delay {
-        return;
+    // 分享需要网盘中的文件 ID，先秒传获取
+    let resolver = shared_link_resolver();
+    let mut file_ids = Vec::with_capacity(items.len());
+    for item in &items {
+        let size = file_item_size(item)?;
+        file_ids.push(resolver.file_id(&item.path, &item.etag, size).await?.to_string());
     }
 
-    *last_time = now;
-    drop(last_time);
+    let password = password.trim();
+    let request = ShareRequest {
+        share_name,
+        share_expire: expire_days,
+        file_id_list: file_ids.join(","),
+        share_pwd: (!password.is_empty()).then(|| password.to_string()),
+    };
+    let link = shared_api_client().create_share(&request).await?;
+    let text = match &request.share_pwd {
+        Some(password) => format!("{} 提取码: {}", link, password),
+        None => link,
+    };
 
-    // 空查询处理
-    if query.trim().is_empty() {
-        let file_items = ModelRc::new(VecModel::default());
-        ui.set_file_items(file_items);
-        return;
-    }
+    clipboard.set_text(text.as_str()).await?;
+    Ok(text)
+}
 
-    // 执行搜索
-    debug!("尝试执行搜索任务");
-    let results = database.lock().unwrap().search_files(query);
-    match results {
-        Ok(results) => {
-            debug!("Search returned {} results", results.len());
-            let file_items = file_records_to_model(results);
-            ui.set_file_items(file_items);
-        }
-        Err(e) => {
-            error!("Search failed: {}", e);
-            ui.set_file_items(ModelRc::new(VecModel::default()));
+/// 将文件项导出为 CSV 文件
+///
+/// # Arguments
+/// * `items` - 要导出的文件项
+/// * `target` - 导出文件路径
+pub fn export_file_items(items: &[FileItem], target: &Path) -> std::io::Result<()> {
+    let records: Vec<FileRecord> = items.iter().map(file_item_to_record).collect();
+    export_file_records(&records, target)
+}
+
+/// 将文件操作记录到最近活动，失败时只记录日志
+///
+/// # Arguments
+/// * `activity_log` - 活动记录
+/// * `kind` - 活动类型
+/// * `items` - 相关的文件项
+/// * `database` - 文件所在的数据库名称
+pub fn record_activity(
+    activity_log: &ActivityLog,
+    kind: ActivityKind,
+    items: &[FileItem],
+    database: &str,
+) {
+    for item in items {
+        if let Err(e) = activity_log.record(kind, &file_item_to_record(item), database) {
+            error!("Failed to record activity: {}", e);
         }
     }
 }
 
-/// 处理数据库切换请求
+/// 刷新"最近"视图
 ///
 /// # Arguments
-/// * `database_index` - 数据库索引，-1 表示刷新列表
 /// * `ui` - UI 弱引用
-/// * `database_manager` - 数据库管理器
-pub fn handle_database_changed(
-    database_index: i32,
-    ui: &slint::Weak<AppWindow>,
-    database_manager: Arc<Mutex<DatabaseManager>>,
-) {
+/// * `activity_log` - 活动记录
+pub fn handle_recent_view_requested(ui: &slint::Weak<AppWindow>, activity_log: &ActivityLog) {
     let ui = match ui.upgrade() {
         Some(u) => u,
         None => return,
     };
 
-    // 处理刷新列表请求
-    if database_index == -1 {
-        info!("Refreshing database list...");
-        let mut manager = database_manager.lock().unwrap();
-        match manager.refresh_database_list() {
-            Ok(_) => {
-                info!("Database list refreshed successfully");
-                // 更新UI中的数据库列表
-                let database_list = manager.get_database_list();
-                let database_model = database_list_to_string_model(database_list);
-                ui.set_available_databases(database_model);
-
-                // 重置当前选择为第一个数据库
-                if manager.get_database_list().len() > 0 {
-                    ui.set_current_database_index(0);
-                }
-            }
-            Err(e) => {
-                error!("Failed to refresh database list: {}", e);
-            }
+    match activity_log.recent(RECENT_ACTIVITY_LIMIT) {
+        Ok(entries) => ui.set_recent_items(activity_entries_to_model(entries)),
+        Err(e) => {
+            errors::report("无法读取最近活动", &e);
         }
-        return;
     }
+}
 
-    let index = database_index as usize;
-
-    // 切换数据库
-    let mut manager = database_manager.lock().unwrap();
-    match manager.switch_database(index) {
-        Ok(_) => {
-            info!("Successfully switched to database index: {}", index);
-
-            // 清空搜索结果
-            ui.set_file_items(ModelRc::new(VecModel::default()));
-            ui.set_search_text("".into());
-        }
+/// 刷新下载历史视图
+pub fn handle_download_history_refresh(ui: &slint::Weak<AppWindow>, history: &DownloadHistory) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    match history.recent(DOWNLOAD_HISTORY_LIMIT) {
+        Ok(entries) => ui.set_download_history(download_history_to_model(entries)),
         Err(e) => {
-            error!("Failed to switch database: {}", e);
+            errors::report("无法读取下载历史", &e);
         }
     }
 }
 
-/// 处理文件右键菜单请求
+/// 清空下载历史
+pub fn handle_download_history_clear(ui: &slint::Weak<AppWindow>, history: &DownloadHistory) {
+    if let Err(e) = history.clear() {
+        errors::report("清空下载历史失败", &e);
+    }
+    handle_download_history_refresh(ui, history);
+}
+
+/// 扫描下载目录中的下载元数据，刷新下载面板
 ///
 /// # Arguments
-/// * `file_item` - 文件项
-/// * `x` - 鼠标X坐标
-/// * `y` - 鼠标Y坐标
 /// * `ui` - UI 弱引用
-pub fn handle_file_context_menu(file_item: FileItem, x: f32, y: f32, ui: &slint::Weak<AppWindow>) {
-    info!("=== RIGHT CLICK DETECTED ===");
-    info!("File: {}, Position: ({}, {})", file_item.name, x, y);
+/// * `dirs` - 下载目录
+pub fn handle_downloads_refresh(ui: &slint::Weak<AppWindow>, dirs: &[PathBuf]) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    let entries = sidecar::scan(dirs);
+    debug!("Found {} downloads with sidecars", entries.len());
+    ui.set_download_entries(download_entries_to_model(entries));
+}
 
-    let ui = match ui.upgrade() {
-        Some(u) => u,
-        None => {
-            error!("Failed to upgrade UI handle");
-            return;
+/// 在索引中定位下载文件的来源记录：切换到来源数据库，在文件所在的文件夹中搜索文件名
+///
+/// # Arguments
+/// * `database_name` - 来源数据库名称
+/// * `path` - 文件在索引中的原始路径
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_download_locate(database_name: &str, path: &str, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let locate = {
+        let ui = ui.clone();
+        let database = database.clone();
+        let path = path.to_string();
+        move || {
+            if let Some(window) = ui.upgrade() {
+                let name = path.rsplit(['/', '\\']).next().unwrap_or(&path);
+                window.set_search_text(name.into());
+                window.set_active_view(0);
+            }
+            handle_search_in_folder_of(&path, &ui, &database);
         }
     };
 
-    info!(
-        "Context menu requested for file: {} at position ({}, {})",
-        file_item.name, x, y
-    );
-
-    // 设置选中的文件项
-    ui.set_selected_file_item(file_item);
-    ui.set_context_menu_visible(true);
-    ui.set_context_menu_x(x as f32);
-    ui.set_context_menu_y(y as f32);
+    if database_name.is_empty() {
+        locate();
+        return;
+    }
 
-    info!("=== CONTEXT MENU SHOULD BE VISIBLE ===");
+    // 数据库列表和切换都在数据库管理线程中执行，之后回到事件循环中搜索
+    let ui = ui.clone();
+    let database_name = database_name.to_string();
+    let handle = database.clone();
+    database.snapshot_then(move |snapshot| {
+        if snapshot.current.name == database_name {
+            let _ = slint::invoke_from_event_loop(locate);
+            return;
+        }
+        let Some(&(_, _, index)) = snapshot.databases.iter().find(|(name, _, _)| *name == database_name) else {
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(window) = ui.upgrade() {
+                    window.set_status_message(format!("找不到来源数据库: {}", database_name).into());
+                }
+            });
+            return;
+        };
+        handle.switch_database_then(index, move |result| match result {
+            // 切换数据库后界面会被清空，在其后执行搜索
+            Ok(_) => {
+                let _ = slint::invoke_from_event_loop(locate);
+            }
+            Err(e) => {
+                errors::report_as(ErrorCategory::Database, "切换数据库失败", e);
+            }
+        });
+    });
 }
 
-/// 处理打开文件请求
+/// 刷新收藏集列表；当前打开的收藏集已被删除时回到搜索结果
 ///
 /// # Arguments
-/// * `file_path` - 文件路径
-pub fn handle_open_file(file_path: &str) {
-    info!("Opening file: {}", file_path);
+/// * `ui` - UI 弱引用
+/// * `collections` - 收藏集
+pub fn handle_collections_refresh(ui: &slint::Weak<AppWindow>, collections: &Collections) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    match collections.list() {
+        Ok(list) => {
+            let current = i64::from(ui.get_current_collection());
+            if ui.get_active_view() == 3 && !list.iter().any(|collection| collection.id == current) {
+                ui.set_current_collection(-1);
+                ui.set_active_view(0);
+            }
+            ui.set_collections(collections_to_model(list));
+        }
+        Err(e) => {
+            errors::report("无法读取收藏集", &e);
+        }
+    }
+}
 
-    // 使用系统默认程序打开文件
-    #[cfg(target_os = "windows")]
-    {
-        let _ = std::process::Command::new("cmd")
-            .args(&["/C", "start", "", file_path])
-            .spawn();
+/// 创建收藏集
+pub fn handle_collection_create(name: &str, ui: &slint::Weak<AppWindow>, collections: &Collections) {
+    match collections.create(name) {
+        Ok(id) => {
+            handle_collections_refresh(ui, collections);
+            handle_collection_opened(id as i32, ui, collections);
+        }
+        Err(e) => {
+            errors::report("创建收藏集失败", &e);
+        }
     }
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        let _ = std::process::Command::new("open").arg(file_path).spawn();
+/// 在收藏集视图中显示收藏集中的文件
+pub fn handle_collection_opened(id: i32, ui: &slint::Weak<AppWindow>, collections: &Collections) {
+    let Some(window) = ui.upgrade() else {
+        return;
+    };
+    let collection = match collections.get(i64::from(id)) {
+        Ok(Some(collection)) => collection,
+        Ok(None) => {
+            handle_collections_refresh(ui, collections);
+            return;
+        }
+        Err(e) => {
+            errors::report("无法读取收藏集", &e);
+            return;
+        }
+    };
+    match collections.items(collection.id) {
+        Ok(items) => {
+            window.set_current_collection(id);
+            window.set_current_collection_name(collection.name.into());
+            window.set_collection_items(collection_items_to_model(items));
+            window.set_active_view(3);
+        }
+        Err(e) => {
+            errors::report("无法读取收藏集", &e);
+        }
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        let _ = std::process::Command::new("xdg-open")
-            .arg(file_path)
-            .spawn();
+/// 删除收藏集
+pub fn handle_collection_delete(id: i32, ui: &slint::Weak<AppWindow>, collections: &Collections) {
+    if let Err(e) = collections.delete(i64::from(id)) {
+        errors::report("删除收藏集失败", &e);
     }
+    handle_collections_refresh(ui, collections);
 }
 
-/// 处理打开文件位置请求
+/// 将选中的文件加入收藏集
 ///
 /// # Arguments
-/// * `file_path` - 文件路径
-pub fn handle_open_file_location(file_path: &str) {
-    info!("Opening file location for: {}", file_path);
-
-    if let Some(parent_path) = std::path::Path::new(file_path).parent() {
-        let parent_string = parent_path.to_string_lossy().to_string();
-
-        // 使用系统文件管理器打开文件夹
-        #[cfg(target_os = "windows")]
-        {
-            let _ = std::process::Command::new("explorer")
-                .arg(&parent_string)
-                .spawn();
+/// * `id` - 收藏集 ID
+/// * `ui` - UI 弱引用
+/// * `collections` - 收藏集
+/// * `database` - 数据库管理线程句柄，记录文件所在的数据库名称
+pub fn handle_collection_add_selected(
+    id: i32,
+    ui: &slint::Weak<AppWindow>,
+    collections: &Collections,
+    database: &DatabaseHandle,
+) {
+    let Some(window) = ui.upgrade() else {
+        return;
+    };
+    let records: Vec<FileRecord> = selected_file_items(&window).iter().map(file_item_to_record).collect();
+    let database_name = database.current_config().name;
+    match collections.add_records(i64::from(id), &records, &database_name) {
+        Ok(added) => {
+            window.set_status_message(format!("已加入 {} 个文件到收藏集", added).into());
+            handle_collections_refresh(ui, collections);
         }
-
-        #[cfg(target_os = "macos")]
-        {
-            let _ = std::process::Command::new("open")
-                .arg(&parent_string)
-                .spawn();
+        Err(e) => {
+            errors::report("加入收藏集失败", &e);
         }
+    }
+}
 
-        #[cfg(target_os = "linux")]
-        {
-            let _ = std::process::Command::new("xdg-open")
-                .arg(&parent_string)
-                .spawn();
-        }
+/// 从收藏集中移除文件并刷新收藏集视图
+pub fn handle_collection_remove_item(
+    id: i32,
+    database: &str,
+    path: &str,
+    ui: &slint::Weak<AppWindow>,
+    collections: &Collections,
+) {
+    if let Err(e) = collections.remove_record(i64::from(id), database, path) {
+        errors::report("移除失败", &e);
     }
+    handle_collections_refresh(ui, collections);
+    handle_collection_opened(id, ui, collections);
 }
 
-/// 初始化数据库选择器
+/// 处理结果行点击，更新多选状态
 ///
 /// # Arguments
+/// * `index` - 点击的行
+/// * `toggle` - 是否按住 Ctrl
+/// * `extend` - 是否按住 Shift
 /// * `ui` - UI 弱引用
-/// * `database_manager` - 数据库管理器
-pub fn initialize_database_selector(
+pub fn handle_row_clicked(index: i32, toggle: bool, extend: bool, ui: &slint::Weak<AppWindow>) {
+    let ui = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    if index < 0 {
+        return;
+    }
+
+    select_row(&ui, index as usize, toggle, extend);
+}
+
+/// 处理搜索请求
+///
+/// 使用尾沿防抖：每次输入都会重新开始计时，停止输入 `search_delay` 后执行最后一次查询
+///
+/// # Arguments
+/// * `query` - 搜索关键词
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄，查询执行时使用当前数据库
+/// * `debounce_timer` - 防抖定时器，新的输入会取消尚未执行的查询
+/// * `search_delay` - 搜索延迟时间
+/// * `min_query_len` - 最短查询长度（字符数），更短的查询只清空结果
+pub fn handle_search_request(
+    query: &str,
     ui: &slint::Weak<AppWindow>,
-    database_manager: Arc<Mutex<DatabaseManager>>,
+    database: DatabaseHandle,
+    debounce_timer: &slint::Timer,
+    search_delay: Duration,
+    min_query_len: usize,
 ) {
-    let ui = match ui.upgrade() {
+    let ui_handle = match ui.upgrade() {
         Some(u) => u,
         None => return,
     };
 
-    let manager = database_manager.lock().unwrap();
-    let database_list = manager.get_database_list();
-    let current_index = manager.get_current_database_index();
+    // 空查询和过短的查询不执行，避免 LIKE '%a%' 扫描全表；限定了搜索范围时列出范围内的全部文件
+    let scoped = !ui_handle.get_search_scope().is_empty();
+    let too_short = query.trim().chars().count() < min_query_len.max(1);
+    if too_short && !(scoped && query.trim().is_empty()) {
+        debounce_timer.stop();
+        let file_items = ModelRc::new(VecModel::default());
+        ui_handle.set_file_items(file_items);
+        let hint = if query.trim().is_empty() {
+            String::new()
+        } else {
+            format!("请至少输入 {} 个字符", min_query_len)
+        };
+        ui_handle.set_result_summary(hint.into());
+        return;
+    }
 
-    // 设置数据库列表 - 使用字符串模型供ComboBox使用
-    let database_model = database_list_to_string_model(database_list);
-    ui.set_available_databases(database_model);
-    ui.set_current_database_index(current_index as i32);
+    // 重新开始计时，之前尚未执行的查询被取消
+    let query = query.to_string();
+    let ui = ui.clone();
+    debounce_timer.start(slint::TimerMode::SingleShot, search_delay, move || {
+        if let Some(ui) = ui.upgrade() {
+            run_search(&query, &ui, &database);
+        }
+    });
+}
 
-    debug!(
-        "Initialized database selector with {} databases",
-        manager.get_database_list().len()
+/// 执行搜索并以懒加载模型更新结果列表
+///
+/// # Arguments
+/// * `query` - 搜索关键词
+/// * `ui` - UI 实例
+/// * `database` - 数据库管理线程句柄
+fn run_search(query: &str, ui: &AppWindow, database: &DatabaseHandle) {
+    // 执行搜索，结果按页懒加载
+    debug!("尝试执行搜索任务");
+    let options = search_options_from_ui(ui);
+    let group_by = GroupBy::from_index(ui.get_group_mode());
+    ui.set_selected_index(-1);
+    ui.set_selection_count(0);
+    let started = Instant::now();
+
+    // 查询在数据库管理线程中执行，作用于命令执行时的当前数据库；不等待结果，结果回到事件循环中显示
+    let ui = ui.as_weak();
+    let search_query = query.to_string();
+
+    // 分组视图：一次加载结果并按文件夹或类型归类
+    if let Some(group_by) = group_by {
+        database.search_then(query, options, GROUPED_RESULT_LIMIT, move |result| {
+            let _ = slint::invoke_from_event_loop(move || {
+                let Some(ui) = ui.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(records) => {
+                        let model = GroupedFileModel::new(records, group_by);
+                        debug!("Search matched {} results in groups", model.file_count());
+                        report_search(&search_query, model.file_count(), started);
+                        ui.set_result_summary(
+                            format_result_summary(model.file_count(), started.elapsed()).into(),
+                        );
+                        ui.set_file_items(ModelRc::new(model));
+                    }
+                    Err(e) => show_search_error(&ui, e),
+                }
+            });
+        });
+        return;
+    }
+
+    // 懒加载视图：只查询匹配数，各页在显示时读取
+    let handle = database.clone();
+    database.count_files_then(query, options.clone(), move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui_handle) = ui.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(total) => {
+                    let model = LazyFileModel::new(handle, ui, &search_query, options, total);
+                    debug!("Search matched {} results", total);
+                    report_search(&search_query, total, started);
+                    ui_handle.set_result_summary(format_result_summary(total, started.elapsed()).into());
+                    ui_handle.set_file_items(ModelRc::new(model));
+                }
+                Err(e) => show_search_error(&ui_handle, e),
+            }
+        });
+    });
+}
+
+/// 搜索失败时上报错误并清空结果列表
+fn show_search_error(ui: &AppWindow, error: anyhow::Error) {
+    errors::report_as(ErrorCategory::Database, "搜索失败", error);
+    ui.set_file_items(ModelRc::new(VecModel::default()));
+    ui.set_result_summary("".into());
+}
+
+/// 使用当前的搜索框内容、搜索范围和类型筛选重新搜索，都为空时不搜索
+fn rerun_search(ui: &AppWindow, database: &DatabaseHandle) {
+    let query = ui.get_search_text().to_string();
+    if !query.trim().is_empty() || !ui.get_search_scope().is_empty() || !active_categories(ui).is_empty() {
+        run_search(&query, ui, database);
+    }
+}
+
+/// 切换类型筛选并重新搜索
+///
+/// # Arguments
+/// * `index` - 分类在 `FileCategory::ALL` 中的索引，-1 清除所有筛选
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_type_filter_toggled(index: i32, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    let mut active = active_categories(&ui);
+    match usize::try_from(index).ok().and_then(|index| FileCategory::ALL.get(index)) {
+        Some(category) if active.contains(category) => active.retain(|active| active != category),
+        Some(category) => active.push(*category),
+        None => active.clear(),
+    }
+    info!("Type filter: {:?}", active);
+    ui.set_type_filter_chips(type_filter_chips(&active));
+    if active.is_empty() && ui.get_search_text().trim().is_empty() && ui.get_search_scope().is_empty() {
+        // 清除筛选后没有搜索内容，清空只属于该筛选的结果
+        ui.set_file_items(ModelRc::new(VecModel::default()));
+        ui.set_result_summary("".into());
+        return;
+    }
+    rerun_search(&ui, database);
+}
+
+/// 设置搜索范围并重新搜索
+///
+/// # Arguments
+/// * `scope` - 文件夹路径，空字符串表示搜索全部
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_search_scope_changed(scope: &str, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    info!("Search scope: {:?}", scope);
+    ui.set_search_scope(scope.into());
+    ui.set_search_scope_crumbs(scope_crumbs_to_model(scope));
+    if scope.is_empty() && ui.get_search_text().trim().is_empty() {
+        // 清除范围后没有搜索内容，清空只属于该范围的结果
+        ui.set_file_items(ModelRc::new(VecModel::default()));
+        ui.set_result_summary("".into());
+        return;
+    }
+    rerun_search(&ui, database);
+}
+
+/// 在文件所在的文件夹中搜索
+///
+/// # Arguments
+/// * `path` - 文件路径
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_search_in_folder_of(path: &str, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    handle_search_scope_changed(&parent_folder(path), ui, database);
+}
+
+/// 在当前的搜索框内容、搜索范围和类型筛选下随机选取一个文件，作为唯一的结果显示并打开详情面板
+///
+/// 条件都为空时在整个数据库中选取，按 Enter 重新搜索即可回到原来的结果
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_random_file(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+    let query = ui_handle.get_search_text().trim().to_string();
+    let ui = ui.clone();
+    database.random_file_then(&query, search_options_from_ui(&ui_handle), move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui.upgrade() {
+                show_random_file(&ui, result);
+            }
+        });
+    });
+}
+
+/// 将随机选取的文件作为唯一的结果显示
+fn show_random_file(ui: &AppWindow, result: anyhow::Result<Option<FileRecord>>) {
+    match result {
+        Ok(Some(record)) => {
+            info!("Random file: {}", record.path);
+            let item = file_record_to_item(record);
+            ui.set_file_items(ModelRc::new(VecModel::from(vec![item.clone()])));
+            ui.set_selection_count(0);
+            ui.set_selected_index(0);
+            ui.set_selected_file_item(item.clone());
+            ui.set_result_summary("随机选取的文件".into());
+            ui.set_status_message(format!("随机文件: {}，按 Ctrl+R 换一个", item.name).into());
+            ui.set_details_visible(true);
+            ui.invoke_media_info_requested();
+        }
+        Ok(None) => ui.set_status_message("没有符合当前条件的文件".into()),
+        Err(e) => {
+            errors::report_as(ErrorCategory::Database, "无法随机选取文件", e);
+        }
+    }
+}
+
+/// 推送搜索完成事件并记录搜索指标
+fn report_search(query: &str, results: usize, started: Instant) {
+    metrics::observe_search(started.elapsed(), results);
+    events::publish(AppEvent::SearchProgress {
+        query: query.to_string(),
+        results,
+        elapsed_ms: started.elapsed().as_millis(),
+    });
+}
+
+/// 处理结果列排序请求
+///
+/// # Arguments
+/// * `column` - 排序列名（name/size/modified_time 等）
+/// * `ascending` - 是否升序
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_sort_requested(
+    column: &str,
+    ascending: bool,
+    ui: &slint::Weak<AppWindow>,
+    database: &DatabaseHandle,
+) {
+    let ui = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    if SortColumn::from_name(column).is_none() {
+        error!("Unknown sort column: {}", column);
+        return;
+    }
+
+    info!("Sorting results by {} ({})", column, if ascending { "asc" } else { "desc" });
+    ui.set_sort_column(column.into());
+    ui.set_sort_ascending(ascending);
+
+    // 立即使用新的排序重新搜索，不经过防抖
+    rerun_search(&ui, database);
+}
+
+/// 处理分组方式切换，立即按新的分组方式重新搜索
+///
+/// # Arguments
+/// * `mode` - 分组下拉框索引（0 不分组，1 文件夹，2 文件类型）
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_group_mode_changed(
+    mode: i32,
+    ui: &slint::Weak<AppWindow>,
+    database: &DatabaseHandle,
+) {
+    let ui = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    info!("Grouping results by {:?}", GroupBy::from_index(mode));
+    ui.set_group_mode(mode);
+
+    rerun_search(&ui, database);
+}
+
+/// 处理分组标题点击，展开或折叠分组
+///
+/// # Arguments
+/// * `key` - 分组名
+/// * `ui` - UI 弱引用
+pub fn handle_group_toggled(key: &str, ui: &slint::Weak<AppWindow>) {
+    if let Some(ui) = ui.upgrade() {
+        toggle_group(&ui, key);
+    }
+}
+
+/// 处理从索引删除文件的请求
+///
+/// # Arguments
+/// * `file_item` - 要删除的文件项
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_delete_file(
+    file_item: FileItem,
+    ui: &slint::Weak<AppWindow>,
+    database: &DatabaseHandle,
+) {
+    if file_item.path.is_empty() {
+        return;
+    }
+
+    info!("Removing from index: {} (id: {})", file_item.path, file_item.id);
+    let operation = IndexOperation::delete(vec![file_item_to_record(&file_item)]);
+    let ui = ui.clone();
+    let handle = database.clone();
+    database.apply_then(operation, move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(_) => {
+                    ui.set_selected_index(-1);
+                    refresh_after_write(&ui, &handle);
+                    ui.set_status_message("已从索引删除，按 Ctrl+Z 撤销".into());
+                }
+                Err(e) => {
+                    errors::report_as(ErrorCategory::Database, "从索引中删除失败", e);
+                }
+            }
+        });
+    });
+}
+
+/// 根据选中项和输入的规则生成重命名预览
+fn rename_preview_for_selection(
+    ui: &AppWindow,
+    mode: i32,
+    first: &str,
+    second: &str,
+) -> anyhow::Result<Vec<RenameEntry>> {
+    let (records, _) = file_items_to_records(&selected_file_items(ui));
+    rename::preview(&records, &RenamePattern::from_ui(mode, first, second))
+}
+
+/// 处理重命名预览请求，在对话框中列出新旧名称
+///
+/// # Arguments
+/// * `mode` - 0 查找替换，1 编号
+/// * `first` - 查找内容或编号模板
+/// * `second` - 替换内容或起始编号
+/// * `ui` - UI 弱引用
+pub fn handle_rename_preview(mode: i32, first: &str, second: &str, ui: &slint::Weak<AppWindow>) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+
+    match rename_preview_for_selection(&ui, mode, first, second) {
+        Ok(entries) => {
+            let lines: Vec<slint::SharedString> = entries
+                .iter()
+                .map(|entry| format!("{} → {}", entry.old_name, entry.new_name).into())
+                .collect();
+            ui.set_rename_error(if lines.is_empty() { "没有需要重命名的文件".into() } else { "".into() });
+            ui.set_rename_preview(ModelRc::new(VecModel::from(lines)));
+        }
+        Err(e) => {
+            ui.set_rename_error(e.to_string().into());
+            ui.set_rename_preview(ModelRc::default());
+        }
+    }
+}
+
+/// 修改索引后刷新当前结果和撤销提示
+fn refresh_after_write(ui: &AppWindow, database: &DatabaseHandle) {
+    rerun_search(ui, database);
+    let ui = ui.as_weak();
+    database.snapshot_then(move |snapshot| {
+        let next_undo = snapshot.next_undo.unwrap_or_default();
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui.upgrade() {
+                ui.set_undo_label(next_undo.into());
+            }
+        });
+    });
+}
+
+/// 处理批量重命名，只修改索引中的名称和路径
+///
+/// # Arguments
+/// * `mode` - 0 查找替换，1 编号
+/// * `first` - 查找内容或编号模板
+/// * `second` - 替换内容或起始编号
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄，重命名记录在操作日志中，可以撤销
+pub fn handle_rename_apply(
+    mode: i32,
+    first: &str,
+    second: &str,
+    ui: &slint::Weak<AppWindow>,
+    database: &DatabaseHandle,
+) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+
+    let entries = match rename_preview_for_selection(&ui, mode, first, second) {
+        Ok(entries) if !entries.is_empty() => entries,
+        Ok(_) => return,
+        Err(e) => {
+            ui.set_rename_error(e.to_string().into());
+            return;
+        }
+    };
+
+    info!("Renaming {} records in index", entries.len());
+    let ui = ui.as_weak();
+    let handle = database.clone();
+    database.apply_then(IndexOperation::Rename(entries), move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(renamed) => {
+                    ui.set_rename_dialog_visible(false);
+                    refresh_after_write(&ui, &handle);
+                    ui.set_status_message(format!("已重命名 {} 个文件", renamed).into());
+                }
+                Err(e) => {
+                    errors::report_as(ErrorCategory::Database, "重命名失败", e);
+                }
+            }
+        });
+    });
+}
+
+/// 撤销上一次索引修改（Ctrl+Z）
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_undo(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let ui = ui.clone();
+    let handle = database.clone();
+    database.undo_then(move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(Some(description)) => {
+                    refresh_after_write(&ui, &handle);
+                    ui.set_status_message(format!("已撤销{}", description).into());
+                }
+                Ok(None) => ui.set_status_message("没有可撤销的操作".into()),
+                Err(e) => {
+                    errors::report_as(ErrorCategory::Database, "撤销失败", e);
+                }
+            }
+        });
+    });
+}
+
+/// 重做上一次撤销的索引修改（Ctrl+Y / Ctrl+Shift+Z）
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_redo(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let ui = ui.clone();
+    let handle = database.clone();
+    database.redo_then(move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(Some(description)) => {
+                    refresh_after_write(&ui, &handle);
+                    ui.set_status_message(format!("已重做{}", description).into());
+                }
+                Ok(None) => ui.set_status_message("没有可重做的操作".into()),
+                Err(e) => {
+                    errors::report_as(ErrorCategory::Database, "重做失败", e);
+                }
+            }
+        });
+    });
+}
+
+/// 处理数据库维护请求，在后台线程中执行并在对话框中显示进度
+///
+/// # Arguments
+/// * `kind` - 0 全部常规维护，1 压缩，2 更新统计信息，3 重建索引，4 清理孤立记录
+/// * `roots` - 清理孤立记录时已移除的目录，每行一个
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_maintenance(kind: i32, roots: &str, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+
+    let operations = match kind {
+        1 => vec![MaintenanceOperation::Vacuum],
+        2 => vec![MaintenanceOperation::Analyze],
+        3 => vec![MaintenanceOperation::Reindex],
+        4 => {
+            let roots: Vec<String> = roots
+                .lines()
+                .map(str::trim)
+                .filter(|root| !root.is_empty())
+                .map(str::to_string)
+                .collect();
+            if roots.is_empty() {
+                return;
+            }
+            vec![MaintenanceOperation::CleanupOrphans { roots }]
+        }
+        _ => MaintenanceOperation::ROUTINE.to_vec(),
+    };
+    ui_handle.set_maintenance_running(true);
+
+    let ui = ui.clone();
+    let database = database.clone();
+    std::thread::spawn(move || {
+        let progress_ui = ui.clone();
+        let result = database.run_maintenance(operations, move |index, total, operation| {
+            let message = format!("[{}/{}] {}...", index, total, operation.label());
+            let ui = progress_ui.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_maintenance_status(message.into());
+                }
+            });
+        });
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            ui.set_maintenance_running(false);
+            match result {
+                Ok(report) => {
+                    let size = |size: Option<u64>| size.map(format_size).unwrap_or_else(|| "-".to_string());
+                    let message = format!(
+                        "维护完成：清理孤立记录 {} 条，数据库大小 {} → {}",
+                        report.orphans_removed,
+                        size(report.size_before),
+                        size(report.size_after)
+                    );
+                    ui.set_maintenance_status(message.clone().into());
+                    ui.set_status_message(message.into());
+                    if report.orphans_removed > 0 {
+                        refresh_after_write(&ui, &database);
+                    }
+                }
+                Err(e) => {
+                    let report = errors::report("数据库维护失败", &e);
+                    ui.set_maintenance_status(report.to_string().into());
+                }
+            }
+        });
+    });
+}
+
+/// 在 SQL 控制台中执行只读查询
+///
+/// 结果包含文件记录的全部字段时显示在结果列表中，否则以表格文本显示在控制台中
+///
+/// # Arguments
+/// * `sql` - 查询语句
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_sql_query(sql: &str, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+    ui_handle.set_sql_console_running(true);
+    ui_handle.set_sql_console_status("".into());
+
+    // 查询在数据库管理线程启动的工作线程中执行
+    let ui = ui.clone();
+    let started = Instant::now();
+    database.query_read_only_then(sql, sql_console::CONSOLE_ROW_LIMIT, move |result| {
+        let elapsed = started.elapsed();
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            ui.set_sql_console_running(false);
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    ui.set_sql_console_status(format!("查询失败: {:#}", e).into());
+                    return;
+                }
+            };
+
+            let truncated = if result.truncated {
+                format!("（只显示前 {} 行）", sql_console::CONSOLE_ROW_LIMIT)
+            } else {
+                String::new()
+            };
+            let status = format!("{} 行，耗时 {} ms{}", result.rows.len(), elapsed.as_millis(), truncated);
+            match result.records {
+                Some(records) => {
+                    ui.set_selected_index(-1);
+                    ui.set_selection_count(0);
+                    ui.set_file_items(file_records_to_model(records));
+                    ui.set_result_summary(format!("SQL 查询: {}", status).into());
+                    ui.set_sql_console_columns("".into());
+                    ui.set_sql_console_rows(ModelRc::new(VecModel::default()));
+                    ui.set_sql_console_status(status.into());
+                    ui.set_active_view(0);
+                }
+                None => {
+                    let rows: Vec<slint::SharedString> =
+                        result.rows.iter().map(|row| row.join(" | ").into()).collect();
+                    ui.set_sql_console_columns(result.columns.join(" | ").into());
+                    ui.set_sql_console_rows(ModelRc::new(VecModel::from(rows)));
+                    ui.set_sql_console_status(status.into());
+                }
+            }
+        });
+    });
+}
+
+/// 在诊断面板中显示最近的慢查询
+pub fn handle_diagnostics_refresh(ui: &slint::Weak<AppWindow>) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    let entries: Vec<slint::SharedString> = shared_query_diagnostics()
+        .recent()
+        .iter()
+        .map(|query| {
+            let marker = if query.has_full_scan() { "⚠ " } else { "" };
+            format!("{}{}  {}", marker, format_datetime(query.recorded_at), query.summary()).into()
+        })
+        .collect();
+    ui.set_diagnostics_entries(ModelRc::new(VecModel::from(entries)));
+}
+
+/// 清空慢查询记录
+pub fn handle_diagnostics_clear(ui: &slint::Weak<AppWindow>) {
+    shared_query_diagnostics().clear();
+    handle_diagnostics_refresh(ui);
+}
+
+/// 刷新剪切板历史列表，每项为"复制时间  内容摘要"
+pub fn handle_clipboard_history_refresh(ui: &slint::Weak<AppWindow>, clipboard: &ClipboardHandle) {
+    let Some(ui) = ui.upgrade() else {
+        return;
+    };
+    let entries: Vec<slint::SharedString> = clipboard
+        .history()
+        .recent()
+        .iter()
+        .map(|entry| format!("{}  {}", format_datetime(entry.copied_at), entry.summary()).into())
+        .collect();
+    ui.set_clipboard_history(ModelRc::new(VecModel::from(entries)));
+}
+
+/// 清空剪切板历史
+pub fn handle_clipboard_history_clear(ui: &slint::Weak<AppWindow>, clipboard: &ClipboardHandle) {
+    clipboard.history().clear();
+    handle_clipboard_history_refresh(ui, clipboard);
+}
+
+/// 比较对话框中最多显示的差异条数
+const COMPARE_RESULT_LIMIT: usize = 1000;
+
+/// 比较两个数据库并在对话框中显示差异
+///
+/// # Arguments
+/// * `left` - 左侧数据库索引
+/// * `right` - 右侧数据库索引
+/// * `key` - 0 按路径，1 按 etag
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_compare(left: i32, right: i32, key: i32, ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+    if left < 0 || right < 0 {
+        return;
+    }
+    ui_handle.set_compare_running(true);
+    ui_handle.set_compare_summary("".into());
+
+    let ui = ui.clone();
+    let database = database.clone();
+    std::thread::spawn(move || {
+        let result = database.compare_databases(left as usize, right as usize, CompareKey::from_index(key));
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            ui.set_compare_running(false);
+            let diff = match result {
+                Ok(diff) => diff,
+                Err(e) => {
+                    let report = errors::report("比较数据库失败", &e);
+                    ui.set_compare_summary(report.to_string().into());
+                    return;
+                }
+            };
+
+            let lines: Vec<slint::SharedString> = diff
+                .only_left
+                .iter()
+                .map(|record| format!("- {}", record.path))
+                .chain(diff.only_right.iter().map(|record| format!("+ {}", record.path)))
+                .chain(diff.changed.iter().map(|(a, b)| {
+                    if a.path == b.path {
+                        format!("~ {}  {} → {}", a.path, a.etag, b.etag)
+                    } else {
+                        format!("~ {} → {}", a.path, b.path)
+                    }
+                }))
+                .take(COMPARE_RESULT_LIMIT)
+                .map(slint::SharedString::from)
+                .collect();
+
+            let names = ui.get_available_databases();
+            let name = |index: i32| names.row_data(index as usize).unwrap_or_default();
+            let mut summary = if diff.is_identical() {
+                "两个数据库的记录一致".to_string()
+            } else {
+                format!(
+                    "仅在左侧 {} 条（-），仅在右侧 {} 条（+），不同 {} 条（~）",
+                    diff.only_left.len(),
+                    diff.only_right.len(),
+                    diff.changed.len()
+                )
+            };
+            match diff.newer() {
+                Some(std::cmp::Ordering::Greater) => summary += &format!("；{} 更新", name(left)),
+                Some(_) => summary += &format!("；{} 更新", name(right)),
+                None => {}
+            }
+            ui.set_compare_summary(summary.into());
+            ui.set_compare_results(ModelRc::new(VecModel::from(lines)));
+        });
+    });
+}
+
+/// 将当前数据库备份到备份目录
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_backup(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+    ui_handle.set_maintenance_running(true);
+    ui_handle.set_maintenance_status("正在备份...".into());
+
+    let ui = ui.clone();
+    let database = database.clone();
+    std::thread::spawn(move || {
+        let result = database.snapshot().and_then(|snapshot| {
+            let dest = DatabaseManager::default_backup_path(&snapshot.current);
+            database
+                .backup_database(snapshot.current_index, dest.clone())
+                .map(|_| dest)
+        });
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            ui.set_maintenance_running(false);
+            match result {
+                Ok(dest) => {
+                    let message = format!("已备份到 {}", dest.display());
+                    ui.set_maintenance_status(message.clone().into());
+                    ui.set_status_message(message.into());
+                }
+                Err(e) => {
+                    let report = errors::report("备份数据库失败", &e);
+                    ui.set_maintenance_status(report.to_string().into());
+                }
+            }
+        });
+    });
+}
+
+/// 从备份目录中最近的备份恢复当前数据库，恢复前的数据会另外备份
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+pub fn handle_restore(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(ui_handle) = ui.upgrade() else {
+        return;
+    };
+    ui_handle.set_maintenance_running(true);
+    ui_handle.set_maintenance_status("正在恢复...".into());
+
+    let ui = ui.clone();
+    let database = database.clone();
+    std::thread::spawn(move || {
+        let result = database.snapshot().and_then(|snapshot| {
+            let source = DatabaseManager::latest_backup(&snapshot.current)
+                .ok_or_else(|| anyhow::anyhow!("没有找到 {} 的备份", snapshot.current.name))?;
+            let safety_copy = database.restore_database(snapshot.current_index, source.clone())?;
+            Ok((source, safety_copy))
+        });
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui.upgrade() else {
+                return;
+            };
+            ui.set_maintenance_running(false);
+            match result {
+                Ok((source, safety_copy)) => {
+                    let message = format!(
+                        "已从 {} 恢复，恢复前的数据备份在 {}",
+                        source.display(),
+                        safety_copy.display()
+                    );
+                    ui.set_maintenance_status(message.clone().into());
+                    ui.set_status_message(message.into());
+                    refresh_after_write(&ui, &database);
+                }
+                Err(e) => {
+                    let report = errors::report("恢复数据库失败", &e);
+                    ui.set_maintenance_status(report.to_string().into());
+                }
+            }
+        });
+    });
+}
+
+/// 处理数据库切换请求
+///
+/// # Arguments
+/// * `database_index` - 数据库索引，-1 表示刷新列表
+/// * `ui` - UI 弱引用
+/// * `database_manager` - 数据库管理线程句柄
+pub fn handle_database_changed(
+    database_index: i32,
+    ui: &slint::Weak<AppWindow>,
+    database_manager: &DatabaseHandle,
+) {
+    let ui = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    // 处理刷新列表请求，扫描目录期间不阻塞界面
+    if database_index == -1 {
+        info!("Refreshing database list...");
+        let ui = ui.as_weak();
+        database_manager.refresh_database_list_then(move |result| {
+            let _ = slint::invoke_from_event_loop(move || {
+                let Some(ui) = ui.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(snapshot) => {
+                        info!("Database list refreshed successfully");
+                        // 更新UI中的数据库列表
+                        let database_model = database_list_to_string_model(snapshot.databases);
+                        ui.set_available_databases(database_model);
+
+                        // 列表顺序可能变化，按刷新后当前数据库的索引选中
+                        ui.set_current_database_index(snapshot.current_index as i32);
+                    }
+                    Err(e) => {
+                        errors::report("刷新数据库列表失败", &e);
+                    }
+                }
+            });
+        });
+        return;
+    }
+
+    let index = database_index as usize;
+
+    // 切换数据库，不等待结果
+    // 界面通过 `handle_database_switched` 更新，这里只处理切换失败
+    database_manager.switch_database_then(index, move |result| match result {
+        Ok(_) => {
+            info!("Successfully switched to database index: {}", index);
+        }
+        Err(e) => {
+            errors::report_as(ErrorCategory::Database, "切换数据库失败", e);
+        }
+    });
+}
+
+/// 当前数据库变化后更新界面，由数据库管理器的 `on_database_changed` 回调触发
+///
+/// 旧数据库的搜索结果被清空，之后的搜索使用新的数据库
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `index` - 新数据库的索引
+/// * `config` - 新数据库的配置
+pub fn handle_database_switched(ui: &AppWindow, index: usize, config: &DatabaseConfig) {
+    debug!("Search pipeline switched to database: {}", config.name);
+    ui.set_file_items(ModelRc::new(VecModel::default()));
+    ui.set_search_text("".into());
+    ui.set_result_summary("".into());
+    ui.set_selected_index(-1);
+    ui.set_selection_count(0);
+    ui.set_undo_label("".into());
+    ui.set_current_database_index(index as i32);
+    ui.set_database_name(config.name.clone().into());
+    apply_remote_folder(ui, config);
+}
+
+/// 使用数据库配置中的网盘文件夹作为秒传的目标文件夹
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `config` - 当前数据库配置
+pub fn apply_remote_folder(ui: &AppWindow, config: &DatabaseConfig) {
+    shared_link_resolver().set_parent_folder(config.remote_folder_id);
+    ui.set_remote_folder_name(config.remote_folder_path.as_deref().unwrap_or("/").into());
+}
+
+/// 加载网盘文件夹选择对话框中当前位置的子文件夹
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `browser` - 对话框的浏览位置
+pub fn handle_remote_folder_load(ui: &slint::Weak<AppWindow>, browser: &RemoteFolderBrowser) {
+    let ui_handle = ui.clone();
+    let (folder_id, path) = (browser.current_id(), browser.path());
+    if let Some(ui) = ui_handle.upgrade() {
+        ui.set_remote_folder_path(path.clone().into());
+        ui.set_remote_folders(ModelRc::new(VecModel::default()));
+        ui.set_remote_folder_status("正在加载...".into());
+    }
+
+    spawn_ui_task("加载网盘文件夹", async move {
+        let result = list_folders(&shared_api_client(), folder_id).await;
+        let ui = match ui_handle.upgrade() {
+            Some(ui) => ui,
+            None => return,
+        };
+        // 加载期间已切换到其他文件夹时丢弃结果
+        if ui.get_remote_folder_path() != path.as_str() {
+            return;
+        }
+
+        match result {
+            Ok(folders) => {
+                let status = if folders.is_empty() { "没有子文件夹" } else { "" };
+                ui.set_remote_folder_status(status.into());
+                ui.set_remote_folders(remote_folders_to_model(folders));
+            }
+            Err(e) => {
+                errors::report(&format!("加载网盘文件夹 {} 失败", folder_id), &e);
+                ui.set_remote_folder_status(format!("加载失败: {}", e).into());
+            }
+        }
+    });
+}
+
+/// 将对话框当前位置设为当前数据库的网盘文件夹，并保存配置
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `browser` - 对话框的浏览位置
+/// * `config` - 共享的应用配置
+pub fn handle_remote_folder_selected(
+    ui: &AppWindow,
+    browser: &RemoteFolderBrowser,
+    config: &Mutex<AppConfig>,
+) {
+    let (folder_id, path) = (browser.current_id(), browser.path());
+    let mut config = config.lock().unwrap();
+    config.set_remote_folder(folder_id, Some(path.clone()));
+    apply_remote_folder(ui, &config.database);
+    if let Err(e) = config.save_to_file(&paths::config_file().to_string_lossy()) {
+        warn!("Failed to save remote folder: {}", e);
+    }
+
+    info!("Remote folder for {} set to {} ({})", config.database.name, path, folder_id);
+    ui.set_status_message(format!("获取链接时文件将保存到网盘文件夹 {}", path).into());
+}
+
+/// 处理拖放到窗口的文件夹，在后台线程中将其索引到数据库
+///
+/// 当前数据库可写时写入当前数据库，否则在工作目录下以文件夹名新建 SQLite 数据库
+///
+/// # Arguments
+/// * `folder` - 拖放的文件夹
+/// * `ui` - UI 弱引用
+/// * `database_manager` - 数据库管理线程句柄
+/// * `active_job` - 当前索引任务的取消标志，同一时间只允许一个索引任务
+pub fn handle_folder_dropped(
+    folder: PathBuf,
+    ui: &slint::Weak<AppWindow>,
+    database_manager: DatabaseHandle,
+    active_job: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+) {
+    let ui_handle = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    if !folder.is_dir() {
+        debug!("Ignoring dropped non-directory: {:?}", folder);
+        return;
+    }
+
+    let mut job = active_job.lock().unwrap();
+    if job.is_some() {
+        ui_handle.set_status_message("已有索引任务正在运行".into());
+        return;
+    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    *job = Some(cancel.clone());
+    drop(job);
+
+    ui_handle.set_indexing_status(format!("正在扫描 {}", folder.display()).into());
+    ui_handle.set_indexing_visible(true);
+
+    let ui_weak = ui.clone();
+    std::thread::spawn(move || {
+        let progress_ui = ui_weak.clone();
+        let folder_text = folder.to_string_lossy().to_string();
+        let on_progress = {
+            let folder_text = folder_text.clone();
+            move |progress: &IndexProgress| {
+                events::publish(AppEvent::IndexingProgress {
+                    folder: folder_text.clone(),
+                    scanned: progress.scanned,
+                    indexed: progress.indexed,
+                    current_dir: progress.current_dir.clone(),
+                    finished: false,
+                });
+                let text = format!(
+                    "已索引 {} 个文件 - {}",
+                    progress.indexed, progress.current_dir
+                );
+                let ui = progress_ui.clone();
+                let _ = slint::invoke_from_event_loop(move || {
+                    if let Some(ui) = ui.upgrade() {
+                        ui.set_indexing_status(text.into());
+                    }
+                });
+            }
+        };
+
+        // 在数据库管理线程启动的工作线程中写入当前数据库，当前数据库只读时新建数据库
+        let result = match database_manager.index_folder(folder.clone(), cancel.clone(), on_progress.clone()) {
+            Ok(Some(summary)) => Ok((summary, false)),
+            Ok(None) => index_into_new_database(&folder, cancel, on_progress).map(|summary| (summary, true)),
+            Err(e) => Err(e),
+        };
+
+        active_job.lock().unwrap().take();
+        if let Ok((summary, _)) = &result {
+            if !summary.cancelled {
+                notifications::notify(
+                    NotificationKind::IndexingComplete,
+                    "索引完成",
+                    &format!("{}: 已索引 {} 个文件", folder_text, summary.indexed),
+                );
+            }
+            events::publish(AppEvent::IndexingProgress {
+                folder: folder_text,
+                scanned: summary.indexed,
+                indexed: summary.indexed,
+                current_dir: String::new(),
+                finished: true,
+            });
+        }
+        let _ = slint::invoke_from_event_loop(move || {
+            let ui = match ui_weak.upgrade() {
+                Some(u) => u,
+                None => return,
+            };
+            ui.set_indexing_visible(false);
+
+            match result {
+                Ok((summary, new_database)) => {
+                    let message = if summary.cancelled {
+                        format!("索引已取消，已写入 {} 个文件", summary.indexed)
+                    } else {
+                        format!("索引完成，共 {} 个文件", summary.indexed)
+                    };
+                    ui.set_status_message(message.into());
+
+                    if new_database {
+                        // 新建的数据库文件会在刷新列表时被发现
+                        let ui_weak = ui.as_weak();
+                        let handle = database_manager.clone();
+                        database_manager.refresh_database_list_then(move |result| {
+                            if let Err(e) = result {
+                                errors::report("刷新数据库列表失败", &e);
+                            }
+                            initialize_database_selector(&ui_weak, &handle);
+                        });
+                    } else {
+                        rerun_search(&ui, &database_manager);
+                    }
+                }
+                Err(e) => {
+                    errors::report("索引失败", &e);
+                }
+            }
+        });
+    });
+}
+
+/// 当前数据库只读时，在数据目录下以文件夹名新建 SQLite 数据库并写入
+///
+/// # Arguments
+/// * `folder` - 要索引的文件夹
+/// * `cancel` - 取消标志
+/// * `on_progress` - 进度回调
+fn index_into_new_database(
+    folder: &Path,
+    cancel: Arc<AtomicBool>,
+    on_progress: impl FnMut(&IndexProgress),
+) -> anyhow::Result<IndexSummary> {
+    let name = folder
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "index".to_string());
+    let db_path = paths::data_dir()
+        .join(format!("{}.db", name))
+        .to_string_lossy()
+        .to_string();
+    let database = SqliteDatabase::new(&db_path)
+        .and_then(|db| {
+            db.init_database()?;
+            Ok(db)
+        })
+        .with_context(|| format!("创建数据库 {} 失败", db_path))?;
+
+    info!("Indexing into new database: {}", db_path);
+    Indexer::new(Arc::new(Mutex::new(database)))
+        .with_cancel_flag(cancel)
+        .index_folder(folder, on_progress)
+}
+
+/// 取消正在运行的索引任务
+///
+/// # Arguments
+/// * `active_job` - 当前索引任务的取消标志
+pub fn handle_cancel_indexing(active_job: &Arc<Mutex<Option<Arc<AtomicBool>>>>) {
+    if let Some(flag) = active_job.lock().unwrap().as_ref() {
+        info!("Cancelling indexing job");
+        flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 处理文件右键菜单请求
+///
+/// # Arguments
+/// * `file_item` - 文件项
+/// * `x` - 鼠标X坐标
+/// * `y` - 鼠标Y坐标
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄，用于判断当前数据库是否显示写操作
+pub fn handle_file_context_menu(
+    file_item: FileItem,
+    x: f32,
+    y: f32,
+    ui: &slint::Weak<AppWindow>,
+    database: &DatabaseHandle,
+) {
+    info!("=== RIGHT CLICK DETECTED ===");
+    info!("File: {}, Position: ({}, {})", file_item.name, x, y);
+
+    let ui = match ui.upgrade() {
+        Some(u) => u,
+        None => {
+            error!("Failed to upgrade UI handle");
+            return;
+        }
+    };
+
+    info!(
+        "Context menu requested for file: {} at position ({}, {})",
+        file_item.name, x, y
     );
+
+    // 根据文件状态决定显示哪些菜单项，本地文件是否存在在后台检查
+    let items = selected_file_items(&ui);
+    check_local_exists(&ui.as_weak(), file_item.path.to_string());
+    ui.set_menu_can_link(!items.is_empty() && items.iter().all(|item| !item.etag.is_empty()));
+    // 写操作先隐藏，数据库管理线程返回当前数据库可写后再显示
+    ui.set_menu_can_write(false);
+    let ui_weak = ui.as_weak();
+    database.snapshot_then(move |snapshot| {
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_menu_can_write(snapshot.writable);
+            }
+        });
+    });
+    let (records, _) = file_items_to_records(&items);
+    ui.set_menu_has_video(records.iter().any(subtitles::is_video));
+    let custom_menu = shared_context_menu();
+    ui.set_custom_menu_actions(custom_menu_actions_to_model(custom_menu.available_actions(&records)));
+
+    // 设置选中的文件项
+    ui.set_selected_file_item(file_item);
+    ui.set_copy_as_expanded(false);
+    ui.set_add_to_collection_expanded(false);
+
+    // 先按估计的大小定位，菜单显示后根据实际大小重新定位
+    ui.set_context_menu_anchor_x(x);
+    ui.set_context_menu_anchor_y(y);
+    position_context_menu(&ui, CONTEXT_MENU_ESTIMATED_SIZE);
+    ui.set_context_menu_visible(true);
+
+    info!("=== CONTEXT MENU SHOULD BE VISIBLE ===");
+}
+
+/// 处理自定义菜单项点击，对选中的文件执行注册的处理函数
+///
+/// # Arguments
+/// * `index` - 菜单项在注册顺序中的索引
+/// * `ui` - UI 弱引用
+pub fn handle_custom_menu_action(index: i32, ui: &slint::Weak<AppWindow>) {
+    let ui_handle = match ui.upgrade() {
+        Some(u) => u,
+        None => return,
+    };
+
+    let menu = shared_context_menu();
+    let Some(action) = usize::try_from(index).ok().and_then(|i| menu.actions().get(i)) else {
+        warn!("Unknown context menu action: {}", index);
+        return;
+    };
+    let label = action.label().to_string();
+    let (records, _) = file_items_to_records(&selected_file_items(&ui_handle));
+    let Some(task) = menu.run(index as usize, records) else {
+        return;
+    };
+    ui_handle.set_status_message(format!("正在执行 {}...", label).into());
+
+    let ui = ui.clone();
+    let context = label.clone();
+    spawn_ui_task(&context, async move {
+        match task.await {
+            Ok(message) => {
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_status_message(message.into());
+                }
+            }
+            Err(e) => {
+                errors::report(&format!("{} 失败", label), &e);
+            }
+        }
+    });
+}
+
+/// 菜单显示前使用的估计大小（宽、高），与界面中菜单的宽度一致
+const CONTEXT_MENU_ESTIMATED_SIZE: (f32, f32) = (150.0, 320.0);
+
+/// 按右键点击位置和菜单大小设置菜单位置，保证菜单完整显示在窗口内
+fn position_context_menu(ui: &AppWindow, menu: (f32, f32)) {
+    let window = ui.window().size().to_logical(ui.window().scale_factor());
+    let anchor = (ui.get_context_menu_anchor_x(), ui.get_context_menu_anchor_y());
+    let (x, y) = clamp_menu_position(anchor, menu, (window.width, window.height));
+    ui.set_context_menu_x(x);
+    ui.set_context_menu_y(y);
+}
+
+/// 处理右键菜单大小变化（显示、展开"复制为…"等），重新定位菜单
+///
+/// # Arguments
+/// * `width` - 菜单宽度
+/// * `height` - 菜单高度
+/// * `ui` - UI 弱引用
+pub fn handle_context_menu_resized(width: f32, height: f32, ui: &slint::Weak<AppWindow>) {
+    if let Some(ui) = ui.upgrade() {
+        if ui.get_context_menu_visible() {
+            position_context_menu(&ui, (width, height));
+        }
+    }
+}
+
+/// 关闭右键菜单，点击菜单外部或按 Esc 时调用
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+pub fn handle_context_menu_dismissed(ui: &slint::Weak<AppWindow>) {
+    if let Some(ui) = ui.upgrade() {
+        debug!("Context menu dismissed");
+        ui.set_context_menu_visible(false);
+        ui.set_copy_as_expanded(false);
+        ui.set_add_to_collection_expanded(false);
+    }
+}
+
+/// 在后台检查本地文件是否存在，并更新右键菜单中"打开文件"和"打开文件位置"的状态
+///
+/// 路径可能位于网络驱动器或已断开的移动硬盘上，检查可能很慢，因此不在界面线程中执行。
+/// 检查期间这两项不可用；结果返回时如果菜单已切换到其他文件则忽略
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `path` - 本地文件路径
+pub fn check_local_exists(ui: &slint::Weak<AppWindow>, path: String) {
+    if let Some(ui) = ui.upgrade() {
+        ui.set_menu_can_open(false);
+        ui.set_menu_open_hint("正在检查本地文件…".into());
+    }
+
+    let ui = ui.clone();
+    std::thread::spawn(move || {
+        let exists = !path.is_empty() && file_exists(&path);
+        let _ = slint::invoke_from_event_loop(move || {
+            let ui = match ui.upgrade() {
+                Some(ui) => ui,
+                None => return,
+            };
+            if ui.get_selected_file_item().path != path.as_str() {
+                return;
+            }
+            ui.set_menu_can_open(exists);
+            let hint = if exists { "" } else { "本地文件不存在，请先下载" };
+            ui.set_menu_open_hint(hint.into());
+        });
+    });
+}
+
+/// 在后台读取选中文件的媒体信息，显示在详情面板中
+///
+/// 只处理本地存在的视频和音频文件，路径按当前数据库的路径映射转换；
+/// 结果返回时如果已选中其他文件则忽略
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database` - 数据库管理线程句柄，用于读取路径映射
+pub fn handle_media_info_requested(ui: &slint::Weak<AppWindow>, database: &DatabaseHandle) {
+    let Some(window) = ui.upgrade() else {
+        return;
+    };
+    window.set_media_info("".into());
+    let item = window.get_selected_file_item();
+    if !window.get_details_visible() || !MediaInfoService::is_supported(&item.file_type, &item.name) {
+        return;
+    }
+    let Some(service) = shared_media_info() else {
+        return;
+    };
+    let path = map_path(&item.path, &database.current_config().path_mappings);
+
+    let ui = ui.clone();
+    spawn_ui_task("读取媒体信息", async move {
+        let result = service.media_info(&path).await;
+        let Some(window) = ui.upgrade() else {
+            return;
+        };
+        if window.get_selected_file_item().path != item.path {
+            return;
+        }
+        match result {
+            Ok(Some(info)) => window.set_media_info(info.summary().into()),
+            Ok(None) => {}
+            Err(e) => debug!("Failed to read media info for {}: {:#}", path, e),
+        }
+    });
+}
+
+/// 处理打开文件请求
+///
+/// 路径作为独立参数交给系统打开方式（Windows 下为 ShellExecute），
+/// 不经过 `cmd /C` 解析，空格和 `&`、`^` 等特殊字符不会被拆分或转义
+///
+/// # Arguments
+/// * `file_path` - 文件路径
+///
+/// # Returns
+/// * `std::io::Result<()>` - 文件不存在或无法启动默认程序时返回错误
+pub fn handle_open_file(file_path: &str) -> std::io::Result<()> {
+    info!("Opening file: {}", file_path);
+
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+
+    // 使用系统默认程序打开文件，不等待程序退出
+    open::that_detached(path).map_err(|e| {
+        error!("Failed to open {}: {}", file_path, e);
+        e
+    })
+}
+
+/// 处理打开文件位置请求，在文件管理器中选中该文件
+///
+/// Windows 使用 `explorer /select,`，macOS 使用 `open -R`，
+/// Linux 通过 D-Bus 调用 `org.freedesktop.FileManager1.ShowItems`，
+/// 文件管理器不支持时退回到打开所在文件夹
+///
+/// # Arguments
+/// * `file_path` - 文件路径
+pub fn handle_open_file_location(file_path: &str) -> std::io::Result<()> {
+    info!("Opening file location for: {}", file_path);
+
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+
+    let result = reveal_in_file_manager(path);
+    if let Err(e) = &result {
+        error!("Failed to reveal {}: {}", file_path, e);
+    }
+    result
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_in_file_manager(path: &Path) -> std::io::Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    // explorer 自行解析参数，路径需要用引号包裹；其退出码不可靠，不检查
+    std::process::Command::new("explorer")
+        .raw_arg(format!("/select,\"{}\"", path.display()))
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal_in_file_manager(path: &Path) -> std::io::Result<()> {
+    std::process::Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn reveal_in_file_manager(path: &Path) -> std::io::Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let status = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", file_uri(&path)))
+        .arg("string:")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        other => {
+            debug!("FileManager1 unavailable ({:?}), opening parent folder", other);
+            match path.parent() {
+                Some(parent) => open::that_detached(parent),
+                None => open::that_detached(&path),
+            }
+        }
+    }
+}
+
+/// 将本地路径转换为 `file://` URI，保留路径分隔符，其余保留字符按 UTF-8 百分号编码
+///
+/// # Arguments
+/// * `path` - 绝对路径
+pub fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let path = if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    };
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            // Windows 盘符中的冒号
+            b':' => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// 初始化数据库选择器
+///
+/// # Arguments
+/// * `ui` - UI 弱引用
+/// * `database_manager` - 数据库管理线程句柄
+pub fn initialize_database_selector(
+    ui: &slint::Weak<AppWindow>,
+    database_manager: &DatabaseHandle,
+) {
+    let ui = ui.clone();
+    database_manager.snapshot_then(move |snapshot| {
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui.upgrade() {
+                show_database_list(&ui, snapshot);
+            }
+        });
+    });
+}
+
+/// 在数据库选择器中显示数据库列表并选中当前数据库
+fn show_database_list(ui: &AppWindow, snapshot: DatabaseSnapshot) {
+    let database_count = snapshot.databases.len();
+
+    // 设置数据库列表 - 使用字符串模型供ComboBox使用
+    let database_model = database_list_to_string_model(snapshot.databases);
+    ui.set_available_databases(database_model);
+    ui.set_current_database_index(snapshot.current_index as i32);
+    ui.set_database_name(snapshot.current.name.clone().into());
+    apply_remote_folder(ui, &snapshot.current);
+
+    debug!("Initialized database selector with {} databases", database_count);
 }
@@ -0,0 +1,45 @@
+//! 后台任务接口 - 查询和取消任务
+//!
+//! - `GET /api/jobs` 列出任务
+//! - `GET /api/jobs/{id}` 查询任务
+//! - `POST /api/jobs/{id}/cancel` 请求取消任务
+
+use crate::services::jobs::{shared_job_queue, JobId};
+use actix_web::{web, HttpResponse};
+
+/// 注册任务路由
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/jobs", web::get().to(list_jobs))
+        .route("/api/jobs/{id}", web::get().to(get_job))
+        .route("/api/jobs/{id}/cancel", web::post().to(cancel_job));
+}
+
+async fn list_jobs() -> HttpResponse {
+    let jobs = shared_job_queue()
+        .map(|queue| queue.list())
+        .unwrap_or_default();
+    HttpResponse::Ok().json(jobs)
+}
+
+async fn get_job(id: web::Path<JobId>) -> HttpResponse {
+    match shared_job_queue().and_then(|queue| queue.get(id.into_inner())) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn cancel_job(id: web::Path<JobId>) -> HttpResponse {
+    let id = id.into_inner();
+    let queue = match shared_job_queue() {
+        Some(queue) => queue,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    if queue.cancel(id) {
+        HttpResponse::Accepted().finish()
+    } else if queue.get(id).is_some() {
+        // 任务已经结束
+        HttpResponse::Conflict().finish()
+    } else {
+        HttpResponse::NotFound().finish()
+    }
+}
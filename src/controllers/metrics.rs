@@ -0,0 +1,16 @@
+//! Prometheus 指标接口 - `/metrics`
+
+use crate::services::metrics;
+use actix_web::{web, HttpResponse};
+
+/// 注册指标路由
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(metrics_handler));
+}
+
+/// 导出 Prometheus 文本格式的指标
+async fn metrics_handler() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::gather())
+}
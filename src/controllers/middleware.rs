@@ -0,0 +1,127 @@
+//! 后端服务中间件 - 按 IP 限流、请求日志和访问令牌注入
+//!
+//! 限流使用固定时间窗口计数，超过限制返回 429；
+//! 请求日志以结构化字段记录方法、路径、状态码和耗时；
+//! 每个请求开始时从共享令牌中取出最新的访问令牌交给接口
+
+use crate::services::token_manager::SharedAccessToken;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Extensions, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 清理过期计数的阈值（记录的 IP 数）
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// 按 IP 的固定窗口限流器
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// 创建限流器
+    ///
+    /// # Arguments
+    /// * `limit` - 每个窗口内允许的请求数
+    /// * `window` - 窗口长度
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 记录一次请求，返回是否允许
+    pub fn check(&self, ip: IpAddr) -> bool {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// 在指定时间记录一次请求，返回是否允许
+    pub fn check_at(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut hits = self.hits.lock().unwrap();
+
+        if hits.len() > PRUNE_THRESHOLD {
+            let window = self.window;
+            hits.retain(|_, (start, _)| now.duration_since(*start) < window);
+        }
+
+        let entry = hits.entry(ip).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+        entry.1 += 1;
+        entry.1 <= self.limit
+    }
+}
+
+/// 限流中间件，限流器通过 `app_data` 注入
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let allowed = match (req.app_data::<web::Data<RateLimiter>>(), req.peer_addr()) {
+        (Some(limiter), Some(addr)) => limiter.check(addr.ip()),
+        _ => true,
+    };
+
+    if !allowed {
+        warn!(
+            peer = ?req.peer_addr(),
+            path = req.path(),
+            "Rate limit exceeded"
+        );
+        let response = HttpResponse::TooManyRequests().finish();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    next.call(req).await.map(|res| res.map_into_left_body())
+}
+
+/// 请求日志中间件
+pub async fn log_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let started = Instant::now();
+
+    let res = next.call(req).await?;
+    info!(
+        method = %method,
+        path = %path,
+        status = res.status().as_u16(),
+        latency_ms = started.elapsed().as_millis() as u64,
+        "request"
+    );
+    Ok(res)
+}
+
+/// 访问令牌中间件，共享令牌通过 `app_data` 注入
+///
+/// 把请求开始时的最新令牌作为 `web::Data<AccessToken>` 放入请求，
+/// 请求级数据优先于应用级数据，接口因此不会拿到启动时的旧令牌
+pub async fn fresh_access_token(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let token = match req.app_data::<web::Data<SharedAccessToken>>() {
+        Some(shared) => Some(shared.read().await.clone()),
+        None => None,
+    };
+    if let Some(token) = token {
+        let mut data = Extensions::new();
+        data.insert(web::Data::new(token));
+        req.add_data_container(Rc::new(data));
+    }
+    next.call(req).await
+}
@@ -0,0 +1,219 @@
+//! 快速搜索 - 全局快捷键唤出的置顶搜索窗口
+//!
+//! 类似 Spotlight：输入关键词后显示当前数据库中的前 10 条结果，方向键选择，Enter 复制下载链接后隐藏窗口，
+//! Esc 或窗口失去焦点时隐藏。搜索和获取链接通过 `SearchEngine` 完成，与主窗口共用数据库管理线程
+
+use crate::controllers::handlers::spawn_ui_task;
+use crate::models::config::{QuickSearchConfig, SearchConfig};
+use crate::models::database::SearchOptions;
+use crate::services::clipboard::ClipboardHandle;
+use crate::services::errors::{self, ErrorCategory};
+use crate::services::hotkey::{self, GlobalHotkey};
+use crate::services::search_engine::SearchEngine;
+use crate::views::ui::{file_records_to_model, try_file_item_to_record, AppTheme, FileItem, QuickSearchWindow};
+use anyhow::Result;
+use slint::winit_030::winit::event::WindowEvent;
+use slint::winit_030::{EventResult, WinitWindowAccessor};
+use slint::{ComponentHandle, ModelRc, VecModel};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// 快速搜索显示的结果数
+pub const QUICK_SEARCH_LIMIT: usize = 10;
+
+/// 快速搜索窗口和注册的全局快捷键，释放后快捷键失效
+pub struct QuickSearch {
+    _window: QuickSearchWindow,
+    _hotkey: GlobalHotkey,
+}
+
+impl QuickSearch {
+    /// 创建快速搜索窗口并注册全局快捷键，需要在界面线程中、事件循环启动前调用
+    ///
+    /// # Arguments
+    /// * `engine` - 与主窗口共用数据库的搜索引擎
+    /// * `clipboard` - 剪切板线程句柄
+    /// * `config` - 快捷键配置
+    /// * `search` - 搜索防抖间隔和最短查询长度
+    pub fn setup(
+        engine: Arc<SearchEngine>,
+        clipboard: ClipboardHandle,
+        config: &QuickSearchConfig,
+        search: &SearchConfig,
+    ) -> Result<Self> {
+        let window = QuickSearchWindow::new()?;
+        let debounce = Rc::new(slint::Timer::default());
+        let delay = Duration::from_millis(search.debounce_ms);
+        let min_query_len = search.min_query_len;
+
+        let window_handle = window.as_weak();
+        let search_engine = engine.clone();
+        window.on_query_changed(move |query| {
+            let window_handle = window_handle.clone();
+            let engine = search_engine.clone();
+            debounce.start(slint::TimerMode::SingleShot, delay, move || {
+                if let Some(window) = window_handle.upgrade() {
+                    run_search(&window, &engine, &query, min_query_len);
+                }
+            });
+        });
+
+        let window_handle = window.as_weak();
+        let link_engine = engine.clone();
+        window.on_copy_link_requested(move |item| {
+            copy_link(window_handle.clone(), link_engine.clone(), clipboard.clone(), item);
+        });
+
+        let window_handle = window.as_weak();
+        window.on_dismissed(move || {
+            if let Some(window) = window_handle.upgrade() {
+                hide(&window);
+            }
+        });
+
+        // 切换到其他窗口时隐藏，和系统的快速启动窗口一致
+        let window_handle = window.as_weak();
+        window.window().on_winit_window_event(move |_window, event| {
+            if let WindowEvent::Focused(false) = event {
+                if let Some(window) = window_handle.upgrade() {
+                    hide(&window);
+                }
+            }
+            EventResult::Propagate
+        });
+
+        let window_handle = window.as_weak();
+        let hotkey = hotkey::register(&config.hotkey, move || {
+            let window_handle = window_handle.clone();
+            let engine = engine.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(window) = window_handle.upgrade() {
+                    toggle(&window, &engine);
+                }
+            });
+        })?;
+        info!("Quick search available with {}", config.hotkey);
+
+        Ok(Self { _window: window, _hotkey: hotkey })
+    }
+}
+
+/// 窗口已显示时隐藏，否则清空上次的查询后显示
+fn toggle(window: &QuickSearchWindow, engine: &SearchEngine) {
+    let visible = window
+        .window()
+        .with_winit_window(|winit_window| winit_window.is_visible())
+        .flatten()
+        .unwrap_or(false);
+    if visible {
+        hide(window);
+    } else {
+        show(window, engine);
+    }
+}
+
+/// 按主窗口的主题显示，每个窗口有各自的 AppTheme
+fn show(window: &QuickSearchWindow, engine: &SearchEngine) {
+    window.global::<AppTheme>().set_mode(engine.config().theme.as_index());
+    window.set_query("".into());
+    window.set_results(ModelRc::new(VecModel::<FileItem>::default()));
+    window.set_selected_index(0);
+    window.set_status_text("".into());
+    if let Err(e) = window.show() {
+        errors::report_as(ErrorCategory::Other, "无法显示快速搜索窗口", e);
+        return;
+    }
+    window.window().with_winit_window(|winit_window| {
+        winit_window.focus_window();
+    });
+    window.invoke_focus_input();
+}
+
+fn hide(window: &QuickSearchWindow) {
+    if let Err(e) = window.hide() {
+        debug!("Failed to hide quick search window: {}", e);
+    }
+}
+
+/// 搜索并显示前 `QUICK_SEARCH_LIMIT` 条结果
+///
+/// 查询在数据库管理线程中执行，结果回到事件循环中显示；期间输入已变化时丢弃结果
+fn run_search(window: &QuickSearchWindow, engine: &SearchEngine, query: &str, min_query_len: usize) {
+    let query = query.trim();
+    window.set_selected_index(0);
+    if query.chars().count() < min_query_len {
+        window.set_results(ModelRc::new(VecModel::<FileItem>::default()));
+        window.set_status_text("".into());
+        return;
+    }
+
+    let window_handle = window.as_weak();
+    let database_name = engine.current_database().name;
+    let searched = query.to_string();
+    engine.search_then(query, SearchOptions::default(), QUICK_SEARCH_LIMIT, move |result| {
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(window) = window_handle.upgrade() else {
+                return;
+            };
+            if window.get_query().trim() != searched {
+                return;
+            }
+            match result {
+                Ok(records) => {
+                    let count = records.len();
+                    window.set_results(file_records_to_model(records));
+                    window.set_status_text(if count == 0 {
+                        "没有匹配的文件".into()
+                    } else {
+                        format!("{} · ↑↓ 选择，Enter 复制下载链接", database_name).into()
+                    });
+                }
+                Err(e) => {
+                    window.set_results(ModelRc::new(VecModel::<FileItem>::default()));
+                    window.set_status_text(format!("搜索失败: {:#}", e).into());
+                }
+            }
+        });
+    });
+}
+
+/// 获取选中文件的下载链接并复制到剪切板，成功后隐藏窗口
+fn copy_link(
+    window_handle: slint::Weak<QuickSearchWindow>,
+    engine: Arc<SearchEngine>,
+    clipboard: ClipboardHandle,
+    item: FileItem,
+) {
+    let record = match try_file_item_to_record(&item) {
+        Ok(record) => record,
+        Err(e) => {
+            if let Some(window) = window_handle.upgrade() {
+                window.set_status_text(format!("无法获取链接: {}", e).into());
+            }
+            return;
+        }
+    };
+    if let Some(window) = window_handle.upgrade() {
+        window.set_busy(true);
+        window.set_status_text(format!("正在获取 {} 的下载链接…", record.name).into());
+    }
+    spawn_ui_task("快速搜索复制链接", async move {
+        let result = match engine.resolve_link(&record).await {
+            Ok(url) => clipboard.set_text(url).await,
+            Err(e) => Err(e),
+        };
+        let Some(window) = window_handle.upgrade() else {
+            return;
+        };
+        window.set_busy(false);
+        match result {
+            Ok(()) => {
+                debug!("Quick search copied link of {}", record.path);
+                hide(&window);
+            }
+            Err(e) => window.set_status_text(format!("无法获取链接: {}", e).into()),
+        }
+    });
+}
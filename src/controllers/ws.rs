@@ -0,0 +1,61 @@
+//! WebSocket 推送接口 - `/api/ws`
+//!
+//! 连接后持续推送事件总线中的搜索、索引和下载状态事件（JSON 文本消息）
+
+use crate::services::events::{self, AppEvent};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// 注册 WebSocket 路由
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/ws", web::get().to(ws_handler));
+}
+
+/// 建立 WebSocket 连接并转发事件
+async fn ws_handler(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, Error> {
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    let mut receiver = events::subscribe();
+    debug!("WebSocket client connected: {:?}", req.peer_addr());
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                event = receiver.recv() => {
+                    let event: AppEvent = match event {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("WebSocket client lagged, skipped {} events", skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    };
+                    if session.text(text).await.is_err() {
+                        break;
+                    }
+                }
+                message = messages.recv() => {
+                    match message {
+                        Some(Ok(Message::Ping(bytes))) => {
+                            if session.pong(&bytes).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                        Some(Ok(_)) => {}
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+        debug!("WebSocket client disconnected");
+    });
+
+    Ok(response)
+}
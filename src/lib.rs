@@ -11,26 +11,101 @@ pub mod models {
     pub mod database;
 }
 
+// 图形界面和界面事件处理，需要 `gui` feature
+#[cfg(feature = "gui")]
 pub mod views {
     pub mod ui;
 }
 
 pub mod controllers {
+    #[cfg(all(feature = "gui", feature = "aria2"))]
+    pub mod app_context;
+    pub mod cli;
+    #[cfg(feature = "server")]
+    pub mod collections;
+    pub mod context_menu;
+    #[cfg(feature = "gui")]
     pub mod handlers;
-    // pub mod search_handler; // 暂时未使用的高级搜索功能
+    #[cfg(feature = "server")]
+    pub mod jobs;
+    #[cfg(feature = "server")]
+    pub mod metrics;
+    #[cfg(feature = "server")]
+    pub mod middleware;
+    #[cfg(feature = "hotkey")]
+    pub mod quick_search;
+    #[cfg(feature = "server")]
+    pub mod ws;
 }
 
 pub mod services {
+    pub mod activity_log;
+    pub mod api_client;
+    #[cfg(feature = "aria2")]
     pub mod aria2;
+    pub mod checksum;
+    pub mod collections;
+    #[cfg(feature = "clipboard")]
+    pub mod clipboard;
+    pub mod crash_report;
+    pub mod credentials;
     pub mod database_manager;
+    pub mod diagnostics;
+    pub mod disk_space;
+    pub mod download_history;
+    #[cfg(feature = "aria2")]
+    pub mod download_manager;
+    pub mod errors;
+    pub mod events;
+    pub mod export;
+    pub mod hotkey;
+    pub mod http_downloader;
+    pub mod indexer;
+    pub mod jobs;
+    pub mod journal;
+    pub mod link_format;
+    pub mod link_resolver;
+    pub mod logging;
+    pub mod media_info;
+    pub mod metrics;
+    pub mod notifications;
+    pub mod pending_ops;
+    pub mod player;
+    pub mod qr_code;
+    pub mod remote_sync;
+    pub mod rename;
+    pub mod search_engine;
+    pub mod self_check;
+    pub mod shutdown;
+    pub mod single_instance;
+    pub mod sidecar;
+    pub mod sql_console;
+    pub mod subtitles;
+    pub mod thumbnail;
+    pub mod token_manager;
+    pub mod update_check;
+    #[cfg(feature = "aria2")]
+    pub mod watch_folder;
     pub mod database {
         pub mod connector;
         pub mod sqlite;
     }
 }
 
+// 测试工具：内存数据库和模拟的 Aria2 服务
+pub mod testing {
+    #[cfg(feature = "aria2")]
+    pub mod mock_aria2;
+    pub mod mock_database;
+}
+
 pub mod utils {
     pub mod common;
+    pub mod filetype;
+    pub mod locale;
+    pub mod path_mapping;
+    pub mod paths;
+    pub mod time;
 }
 
 // 重新导出主要类型以提供简洁的API
@@ -38,14 +113,18 @@ pub use models::config::{AppConfig, DatabaseConfig};
 pub use models::database::{Database, FileRecord};
 
 // 重新导出控制器函数
+#[cfg(feature = "gui")]
 pub use controllers::handlers::{
     handle_search_request,
     handle_database_changed,
     handle_file_context_menu,
     handle_open_file,
     handle_open_file_location,
+    handle_sort_requested,
+    handle_delete_file,
     initialize_database_selector,
 };
 
 // 重新导出服务类型
-pub use services::database_manager::DatabaseManager;
+pub use services::database_manager::{DatabaseHandle, DatabaseManager};
+pub use services::search_engine::{SearchEngine, SearchEngineBuilder};
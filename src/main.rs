@@ -2,45 +2,191 @@
 //!
 //! 使用现代MVC架构组织的文件搜索应用程序
 
+use actix_web::middleware::{from_fn, Condition};
 use actix_web::{web, HttpServer};
 use anyhow::Context;
-use arboard::Clipboard;
 use netdisk_core::create_app;
-use netdisk_core::netdisk_api::prelude::get_access_token_from_cache;
 use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
-use netdisk_core::responses::prelude::AccessToken;
-use netdisk_db::controllers::handlers::copy_to_clipboard;
+use netdisk_db::controllers::middleware::{self, RateLimiter};
+#[cfg(feature = "hotkey")]
+use netdisk_db::controllers::quick_search::QuickSearch;
+use netdisk_db::controllers::app_context::AppContext;
+use netdisk_db::controllers::context_menu::{set_shared_context_menu, ContextMenuManager};
+use netdisk_db::controllers::{cli, collections, jobs, metrics, ws};
 use netdisk_db::controllers::handlers::{
-    get_file_url, handle_file_context_menu, handle_open_file, handle_open_file_location, send_to_aria2,
+    copy_links_to_clipboard, create_share_link, export_file_items, filter_remote_existing,
+    format_upload_filename, get_file_url, handle_cancel_indexing, handle_context_menu_dismissed,
+    handle_context_menu_resized, handle_custom_menu_action, handle_delete_file, handle_file_context_menu, handle_folder_dropped,
+    handle_database_switched, handle_group_mode_changed, handle_group_toggled, handle_media_info_requested, handle_open_file, handle_open_file_location, handle_random_file, handle_recent_view_requested,
+    handle_remote_folder_load, handle_remote_folder_selected, handle_rename_apply, handle_rename_preview,
+    handle_search_in_folder_of, handle_search_scope_changed, handle_show_qr_code, handle_type_filter_toggled,
+    handle_backup, handle_clipboard_history_clear, handle_collection_add_selected, handle_collection_create,
+    handle_collection_delete, handle_collection_opened, handle_download_history_clear, handle_download_history_refresh,
+    handle_download_locate, handle_downloads_refresh, handle_collection_remove_item, handle_collections_refresh, handle_clipboard_history_refresh, handle_compare, handle_diagnostics_clear, handle_diagnostics_refresh, handle_maintenance, handle_sql_query, handle_redo, handle_restore, handle_row_clicked, handle_sort_requested, handle_undo, queue_pending,
+    record_activity, spawn_ui_task, SHARE_EXPIRE_DAYS,
 };
 use netdisk_db::prelude::*; // 使用库的prelude简化导入
+use netdisk_db::services::activity_log::{ActivityKind, ActivityLog};
+use netdisk_db::services::api_client::{set_shared_api_client, shared_api_client, ApiClient};
+use netdisk_db::services::clipboard::ClipboardHandle;
+use netdisk_db::services::collections::{set_shared_collections, Collections};
+use netdisk_db::services::crash_report;
+use netdisk_db::services::credentials;
 use netdisk_db::services::aria2::{create_shared_aria2_service, SharedAria2Service};
+use netdisk_db::services::diagnostics::shared_query_diagnostics;
+use netdisk_db::services::disk_space;
+use netdisk_db::services::download_history::{
+    record_download, set_shared_download_history, DownloadHistory, DownloadHistoryEntry, DownloadOutcome,
+};
+use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, SharedDownloadManager, Submitted};
+use netdisk_db::services::errors::{self, set_error_sink, ErrorCategory};
+use netdisk_db::services::http_downloader::HttpDownloader;
+use netdisk_db::services::jobs::{set_shared_job_queue, JobQueue, SharedJobQueue};
+use netdisk_db::services::link_format::LinkFormat;
+use netdisk_db::services::link_resolver::{set_shared_link_resolver, LinkResolver};
+use netdisk_db::services::logging;
+use netdisk_db::services::media_info::{set_shared_media_info, MediaInfoCache, MediaInfoService};
+use netdisk_db::services::notifications::{self, set_shared_notifier, NotificationKind, Notifier};
+use netdisk_db::services::player;
+use netdisk_db::services::pending_ops::{
+    is_offline_error, set_shared_pending_operations, shared_pending_operations, PendingKind,
+    PendingOperation, PendingOperations, SharedPendingOperations,
+};
+use netdisk_db::services::subtitles::find_subtitles;
+use netdisk_db::services::self_check;
+use netdisk_db::services::shutdown::{SharedShutdown, ShutdownCoordinator};
+use netdisk_db::services::single_instance::{self, InstanceRequest};
+use netdisk_db::services::sidecar;
+use netdisk_db::services::thumbnail::ThumbnailService;
+use netdisk_db::services::token_manager::{CachedTokenSource, TokenManager};
+use netdisk_db::services::update_check;
+use netdisk_db::services::watch_folder::WatchFolder;
+use netdisk_db::models::config::{BackendConfig, LoggingConfig, PlayerConfig, Theme};
+use netdisk_db::models::database::SearchOptions;
+use netdisk_db::views::ui::{
+    apply_theme, column_widths_from_ui, file_items_to_records, file_record_to_item, format_quota, refresh_remote_state, result_file_items,
+    refresh_thumbnail, refresh_verification, restore_ui_state, save_ui_state, selected_file_items, self_check_to_model,
+    set_thumbnail_service,
+    FileItem, QueuedDownloadItem, RemoteFolderBrowser,
+};
+use slint::winit_030::winit::event::WindowEvent;
+use slint::winit_030::{EventResult, WinitWindowAccessor};
 use slint::ComponentHandle;
+use netdisk_db::utils::filetype::{category_of, FileCategory};
+use netdisk_db::utils::locale::{self, Locale};
+use netdisk_db::utils::path_mapping;
+use netdisk_db::utils::paths;
 use std::io;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
 use tokio::task;
 use tracing::{debug, error, info, span, warn, Level};
-use tracing_subscriber;
+
+/// 退出时等待后台任务结束的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 指定数据根目录的命令行参数
+const DATA_DIR_ARG: &str = "--data-dir";
+
+/// 启用便携模式的命令行参数
+const PORTABLE_ARG: &str = "--portable";
+
+/// 将本程序的日志级别提高到 debug 的命令行参数
+const VERBOSE_ARGS: [&str; 2] = ["--verbose", "-v"];
+
+/// 网盘空间用量的刷新间隔
+const QUOTA_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// 同时运行的后台任务数
+const JOB_CONCURRENCY: usize = 2;
+
+/// 本地套接字搜索请求返回的最大结果数
+const IPC_SEARCH_LIMIT: usize = 50;
+
+/// 网盘无法访问、操作已加入离线队列时的提示
+const OFFLINE_QUEUED_MESSAGE: &str = "网盘暂时无法访问，已加入待处理队列，恢复后自动重试";
+
+/// 获取配置文件路径：存在 config.toml 时使用 TOML，否则使用 config.json
+fn config_path() -> String {
+    paths::config_file().to_string_lossy().into_owned()
+}
+
+/// 从命令行参数中解析数据根目录（`--data-dir <DIR>` 或 `--data-dir=<DIR>`）
+fn data_dir_from_args() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_ARG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    None
+}
+
+/// 命令行参数中是否有 `--verbose`（或 `-v`）
+fn verbose_from_args() -> bool {
+    std::env::args().skip(1).any(|arg| VERBOSE_ARGS.contains(&arg.as_str()))
+}
+
+/// 读取配置文件中的日志配置，配置文件不存在或无法解析时使用默认值
+///
+/// 日志需要在加载配置之前初始化，这里只读取日志部分，完整的加载和迁移由 `initialize_config` 完成
+fn logging_config() -> LoggingConfig {
+    AppConfig::load_from_file(&config_path())
+        .map(|config| config.logging)
+        .unwrap_or_default()
+}
+
 /// 初始化应用程序配置
 ///
-/// 如果配置文件不存在则创建默认配置
-/// 并扫描当前目录下的数据库文件
+/// 如果配置文件不存在则创建默认配置（当前目录下有旧版配置时迁移过来）
+/// 并扫描配置的目录下的数据库文件
 fn initialize_config() -> Result<AppConfig> {
-    let config_path = "config.json";
+    let config_path = config_path();
 
-    let mut config = if std::path::Path::new(config_path).exists() {
-        AppConfig::load_from_file(config_path).context("Failed to load config file")?
+    let mut config = if std::path::Path::new(&config_path).exists() {
+        let (config, needs_save) =
+            AppConfig::load_with_fallback(&config_path).context("Failed to load config file")?;
+        if needs_save {
+            config
+                .save_to_file(&config_path)
+                .context("Failed to save migrated or restored config file")?;
+        }
+        config
+    } else if let Some(legacy_path) = paths::legacy_config_file() {
+        info!("Migrating config file {:?} to {:?}", legacy_path, paths::config_dir());
+        let legacy_config = AppConfig::load_from_file(&legacy_path.to_string_lossy())
+            .context("Failed to load legacy config file")?;
+        let target = paths::config_dir().join(legacy_path.file_name().unwrap_or_default());
+        legacy_config
+            .save_to_file(&target.to_string_lossy())
+            .context("Failed to migrate config file")?;
+        legacy_config
     } else {
         info!("Config file not found, creating default config");
         let default_config = AppConfig::default();
         default_config
-            .save_to_file(config_path)
+            .save_to_file(&config_path)
             .context("Failed to create default config file")?;
         default_config
     };
 
-    // 扫描当前目录下的数据库文件
+    // 明文保存的数据库密码移入系统密钥环
+    match credentials::secure_config(&mut config) {
+        Ok(true) => config
+            .save_to_file(&config_path)
+            .context("Failed to save config after securing credentials")?,
+        Ok(false) => {}
+        Err(e) => warn!("Failed to move database passwords into keyring: {}", e),
+    }
+
+    // 扫描配置的目录下的数据库文件
     scan_for_database_files(&mut config)?;
 
     // 记录配置信息
@@ -56,43 +202,16 @@ fn initialize_config() -> Result<AppConfig> {
     Ok(config)
 }
 
-/// 扫描当前目录下的数据库文件
+/// 扫描配置的目录下的数据库文件
 fn scan_for_database_files(config: &mut AppConfig) -> Result<()> {
-    let current_dir = std::env::current_dir().context("Failed to get current directory")?;
-    info!("Scanning for database files in: {:?}", current_dir);
-
-    let mut found_databases = Vec::new();
-
-    // 读取当前目录下的所有文件
-    if let Ok(entries) = std::fs::read_dir(&current_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-
-                // 检查是否为.db文件
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("db") {
-                    if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
-                        // 跳过临时文件和系统文件
-                        if !file_name.starts_with('.') && !file_name.starts_with('~') {
-                            let db_name = file_name.trim_end_matches(".db").to_string();
-                            let db_path = path.to_string_lossy().to_string();
-
-                            info!("Found database file: {} at path: {}", db_name, db_path);
-
-                            found_databases.push(DatabaseConfig {
-                                db_type: "sqlite".to_string(),
-                                connection_string: db_path,
-                                name: db_name,
-                                description: Some(format!(
-                                    "Auto-discovered database: {}",
-                                    file_name
-                                )),
-                            });
-                        }
-                    }
-                }
-            }
-        }
+    info!("Scanning for database files in: {:?}", config.scan.scan_directories());
+
+    let found_databases = DatabaseManager::discover_sqlite_databases(&config.scan);
+    for db_config in &found_databases {
+        info!(
+            "Found database file: {} at path: {}",
+            db_config.name, db_config.connection_string
+        );
     }
 
     // 如果找到了数据库文件，更新配置
@@ -102,21 +221,10 @@ fn scan_for_database_files(config: &mut AppConfig) -> Result<()> {
             found_databases.len()
         );
 
-        // 清空现有的多数据库配置
-        config.multi_database.databases.clear();
-
-        // 添加发现的数据库
-        for db_config in found_databases {
-            config.add_database(db_config);
-        }
-
-        // 设置第一个发现的数据库为默认
-        if !config.multi_database.databases.is_empty() {
-            config.multi_database.default_database = 0;
-            config.database = config.multi_database.databases[0].clone();
-        }
+        // 只替换自动发现的数据库，手动添加的数据库保留
+        config.replace_discovered_databases(found_databases);
     } else {
-        info!("No database files found in current directory, using existing configuration");
+        info!("No database files found in scan directories, using existing configuration");
     }
 
     Ok(())
@@ -129,7 +237,10 @@ fn scan_for_database_files(config: &mut AppConfig) -> Result<()> {
 fn create_ui(config: &AppConfig) -> Result<AppWindow> {
     let ui = AppWindow::new().context("Failed to create UI window")?;
 
-    // 可以在这里根据配置设置UI属性
+    // 恢复保存的窗口状态和主题
+    restore_ui_state(&ui, config);
+    apply_theme(&ui, config.theme);
+
     debug!(
         "UI window created with size: {}x{}",
         config.window_width, config.window_height
@@ -142,235 +253,1697 @@ fn create_ui(config: &AppConfig) -> Result<AppWindow> {
 ///
 /// # Arguments
 /// * `ui` - UI 实例
-/// * `database_manager` - 数据库管理器
-/// * `aria2_service` - Aria2服务实例
-fn setup_event_handlers(
-    ui: &AppWindow,
-    database_manager: Arc<Mutex<DatabaseManager>>,
-    aria2_service: SharedAria2Service,
-) -> Result<()> {
+/// * `context` - 应用上下文，事件处理闭包各自克隆一份
+fn setup_event_handlers(ui: &AppWindow, context: &AppContext) -> Result<()> {
     let ui_handle = ui.as_weak();
-    let database_handle = database_manager.lock().unwrap().get_current_database();
-    let last_search_time = Arc::new(Mutex::new(Instant::now()));
-    let search_delay = Duration::from_millis(300); // 300ms 防抖延迟
+    let (search_delay, min_query_len) = {
+        let config = context.config.lock().unwrap();
+        (
+            Duration::from_millis(config.search.debounce_ms),
+            config.search.min_query_len,
+        )
+    };
+
+    // 切换数据库后清空旧数据库的结果，搜索总是使用当前数据库
+    context.database.on_database_changed({
+        let ui_weak = ui.as_weak();
+        move |index, config| {
+            let ui_weak = ui_weak.clone();
+            let config = config.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    handle_database_switched(&ui, index, &config);
+                }
+            });
+        }
+    });
 
     // 搜索请求处理
-    ui.on_search_requested(move |query| {
-        handle_search_request(
-            &query,
-            &ui_handle.clone(),
-            database_handle.clone(),
-            last_search_time.clone(),
-            search_delay,
-        );
+    ui.on_search_requested({
+        let ctx = context.clone();
+        move |query| {
+            handle_search_request(
+                &query,
+                &ui_handle.clone(),
+                ctx.database.clone(),
+                &ctx.search_debounce,
+                search_delay,
+                min_query_len,
+            );
+        }
+    });
+
+    // 搜索范围：面包屑、分组标题和右键菜单中的"在此文件夹中搜索"
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_search_scope_changed(move |scope| {
+        handle_search_scope_changed(&scope, &ui_handle, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_type_filter_toggled(move |index| {
+        handle_type_filter_toggled(index, &ui_handle, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_search_in_folder_of(move |path| {
+        handle_search_in_folder_of(&path, &ui_handle, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_random_file_requested(move || {
+        handle_random_file(&ui_handle, &ctx.database);
+    });
+
+    // 结果列排序处理
+    let ui_handle = ui.as_weak();
+    ui.on_sort_requested({
+        let ctx = context.clone();
+        move |column, ascending| {
+            handle_sort_requested(&column, ascending, &ui_handle, &ctx.database);
+        }
+    });
+
+    // 结果分组方式切换和分组折叠
+    let ui_handle = ui.as_weak();
+    ui.on_group_mode_changed({
+        let ctx = context.clone();
+        move |mode| {
+            handle_group_mode_changed(mode, &ui_handle, &ctx.database);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.on_group_toggled(move |key| {
+        handle_group_toggled(&key, &ui_handle);
+    });
+
+    // 从索引中删除文件（Delete 键）
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_delete_file_requested(move |file_item| {
+        handle_delete_file(file_item, &ui_handle, &ctx.database);
+    });
+
+    // 批量重命名：输入变化时预览
+    let ui_handle = ui.as_weak();
+    ui.on_rename_preview_requested(move |mode, first, second| {
+        handle_rename_preview(mode, &first, &second, &ui_handle);
+    });
+
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_rename_apply(move |mode, first, second| {
+        handle_rename_apply(mode, &first, &second, &ui_handle, &ctx.database);
+    });
+
+    // 数据库维护
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_maintenance_requested(move |kind, roots| {
+        handle_maintenance(kind, &roots, &ui_handle, &ctx.database);
+    });
+
+    // 比较两个数据库
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_compare_requested(move |left, right, key| {
+        handle_compare(left, right, key, &ui_handle, &ctx.database);
+    });
+
+    // 备份和恢复当前数据库
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_backup_requested(move || {
+        handle_backup(&ui_handle, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_restore_requested(move || {
+        handle_restore(&ui_handle, &ctx.database);
+    });
+
+    // 撤销和重做删除、重命名等索引修改
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_undo_requested(move || {
+        handle_undo(&ui_handle, &ctx.database);
     });
 
-    // ui.on_search_requested(move |query| {
-    //     // 1. 克隆所有需要在异步任务中使用的句柄和值
-    //     let ui_handle = ui_handle.clone();
-    //     let database_handle = database_handle.clone();
-    //     let last_search_time = last_search_time.clone();
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_redo_requested(move || {
+        handle_redo(&ui_handle, &ctx.database);
+    });
 
-    //     // search_delay 是基本类型 (如 u64)，可以直接复制
-    //     // query 是 String，已被 move 进回调闭包，现在 move 进 task
+    // 列宽调整后保存到配置
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_column_resized(move |_column, _width| {
+        if let Some(ui) = ui_handle.upgrade() {
+            ctx.update_config("列宽", |config| config.ui.column_widths = column_widths_from_ui(&ui));
+        }
+    });
 
-    //     // 2. 使用 tokio::task::spawn_blocking 将耗时的同步操作移到阻塞线程池
-    //     task::spawn_blocking(move || {
-    //         // 这个闭包在专用的阻塞线程上运行，不会阻塞 UI 线程
+    // 查询诊断面板
+    {
+        let config = context.config.lock().unwrap();
+        ui.set_diagnostics_enabled(config.search.diagnostics);
+        ui.set_diagnostics_threshold(config.search.slow_query_ms.min(i32::MAX as u64) as i32);
+    }
+    let ctx = context.clone();
+    ui.on_diagnostics_changed(move |enabled, threshold| {
+        let threshold = threshold.max(0) as u64;
+        shared_query_diagnostics().configure(enabled, threshold);
+        ctx.update_config("诊断设置", |config| {
+            config.search.diagnostics = enabled;
+            config.search.slow_query_ms = threshold;
+        });
+    });
+    let ui_handle = ui.as_weak();
+    ui.on_diagnostics_refresh(move || handle_diagnostics_refresh(&ui_handle));
+    let ui_handle = ui.as_weak();
+    ui.on_diagnostics_clear(move || handle_diagnostics_clear(&ui_handle));
 
-    //         // 3. 在新线程中执行同步搜索逻辑
-    //         handle_search_request(
-    //             &query, // 现在 query 是该线程拥有的 String
-    //             &ui_handle,
-    //             database_handle,
-    //             last_search_time,
-    //             search_delay,
-    //         );
+    // 只读 SQL 控制台
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_sql_query_requested(move |sql| handle_sql_query(&sql, &ui_handle, &ctx.database));
 
-    //         // 注意：如果 handle_search_request 返回结果，您可能需要将结果返回
-    //         // 并在主异步线程中使用 .await 接收它（如果需要）
-    //     });
+    // 主题切换后保存到配置
+    let ctx = context.clone();
+    ui.on_theme_changed(move |mode| {
+        let theme = Theme::from_index(mode);
+        info!("Theme changed to {:?}", theme);
+        ctx.update_config("主题", |config| config.theme = theme);
+    });
 
-    //     // UI 回调立即返回，保持 UI 响应性
-    // });
     // 数据库切换处理
     let ui_handle = ui.as_weak();
-    let manager_handle = database_manager.clone();
+    let ctx = context.clone();
     ui.on_database_changed(move |index| {
-        handle_database_changed(index, &ui_handle, manager_handle.clone());
+        handle_database_changed(index, &ui_handle, &ctx.database);
     });
 
     // 文件右键菜单处理
     let ui_handle = ui.as_weak();
+    let ctx = context.clone();
     ui.on_file_context_menu_requested(move |file_item, x, y| {
-        handle_file_context_menu(file_item, x, y, &ui_handle);
+        handle_file_context_menu(file_item, x, y, &ui_handle, &ctx.database);
+    });
+
+    // 下游程序注册的自定义菜单项
+    let ui_handle = ui.as_weak();
+    ui.on_custom_menu_action(move |index| {
+        handle_custom_menu_action(index, &ui_handle);
+    });
+
+    // 右键菜单大小变化时重新定位，点击外部或按 Esc 关闭
+    let ui_handle = ui.as_weak();
+    ui.on_context_menu_resized(move |width, height| {
+        handle_context_menu_resized(width, height, &ui_handle);
+    });
+    let ui_handle = ui.as_weak();
+    ui.on_context_menu_dismissed(move || {
+        handle_context_menu_dismissed(&ui_handle);
+    });
+
+    // 打开文件处理，记录到最近活动
+    ui.on_open_url(|url| {
+        if let Err(e) = open::that_detached(url.as_str()) {
+            errors::report_as(ErrorCategory::Other, "打开链接失败", e);
+        }
+    });
+
+    ui.on_open_crash_report(|path| {
+        if let Err(e) = handle_open_file(&path) {
+            errors::report_as(ErrorCategory::FileSystem, "打开崩溃报告失败", e);
+        }
+    });
+
+    ui.on_open_file({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move |file_path| {
+            let result = handle_open_file(&ctx.local_path(&file_path));
+            if let Some(ui) = ui_weak.upgrade() {
+                if let Err(e) = result {
+                    errors::report_as(ErrorCategory::FileSystem, "打开文件失败", e);
+                    return;
+                }
+                let item = ui.get_selected_file_item();
+                if item.path == file_path {
+                    let database = ctx.current_database_name();
+                    record_activity(&ctx.activity_log, ActivityKind::Opened, &[item], &database);
+                }
+            }
+        }
+    });
+
+    // 在文件管理器中显示并选中文件
+    ui.on_open_file_location({
+        let ctx = context.clone();
+        move |file_path| {
+            if let Err(e) = handle_open_file_location(&ctx.local_path(&file_path)) {
+                errors::report_as(ErrorCategory::FileSystem, "打开文件位置失败", e);
+            }
+        }
     });
 
-    // 打开文件处理
-    ui.on_open_file(move |file_path| {
-        handle_open_file(&file_path);
+    // 下载面板：扫描下载目录中的下载元数据
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_downloads_requested(move || {
+        handle_downloads_refresh(&ui_handle, &download_dirs(&ctx));
+        if let Some(ui) = ui_handle.upgrade() {
+            refresh_download_queue(&ui, &ctx.downloads);
+        }
+    });
+    // 下载队列：同时下载数和等待中的任务顺序
+    ui.set_max_active_downloads(context.downloads.max_active().min(i32::MAX as usize) as i32);
+    let ctx = context.clone();
+    ui.on_max_active_downloads_edited(move |value| {
+        let max_active = value.max(0) as usize;
+        ctx.downloads.set_max_active(max_active);
+        ctx.update_config("同时下载数", |config| config.aria2.max_active_downloads = max_active);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_download_queue_move(move |id, index| {
+        ctx.downloads.move_queued(id.max(0) as u64, index.max(0) as usize);
+        if let Some(ui) = ui_handle.upgrade() {
+            refresh_download_queue(&ui, &ctx.downloads);
+        }
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_download_queue_remove(move |id| {
+        ctx.downloads.remove_queued(id.max(0) as u64);
+        if let Some(ui) = ui_handle.upgrade() {
+            refresh_download_queue(&ui, &ctx.downloads);
+        }
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_download_locate(move |database, path| {
+        handle_download_locate(&database, &path, &ui_handle, &ctx.database);
+    });
+    ui.on_download_reveal(|file_path| {
+        if let Err(e) = handle_open_file_location(&file_path) {
+            errors::report_as(ErrorCategory::FileSystem, "打开文件位置失败", e);
+        }
     });
 
-    // 打开文件位置处理
-    ui.on_open_file_location(move |file_path| {
-        handle_open_file_location(&file_path);
+    // 下载历史
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_download_history_requested(move || handle_download_history_refresh(&ui_handle, &ctx.download_history));
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_download_history_clear(move || handle_download_history_clear(&ui_handle, &ctx.download_history));
+    ui.on_download_history_redownload({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move |id| match ctx.download_history.get(i64::from(id)) {
+            Ok(Some(entry)) => spawn_downloads(vec![file_record_to_item(entry.record)], ui_weak.clone(), &ctx),
+            Ok(None) => {}
+            Err(e) => {
+                errors::report("无法读取下载历史", &e);
+            }
+        }
     });
 
+    // 下载选中的文件（支持多选）
     ui.on_send_to_aria2({
         let ui_weak = ui.as_weak();
-        let aria2_service_clone = aria2_service.clone();
-        move |file_path, etag, size_kb| {
-            let ui_handle = ui_weak.clone();
-            let aria2_service_inner = aria2_service_clone.clone();
-            let path = file_path.to_string();
-            let tag = etag.to_string();
-            let size_bytes = size_kb.to_string().trim().parse::<u64>().unwrap();
-            debug!(
-                "Sending to Aria2: path={}, etag={}, size_bytes={}",
-                path, tag, size_bytes
-            );
+        let ctx = context.clone();
+        move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                spawn_downloads(selected_file_items(&ui), ui_weak.clone(), &ctx);
+            }
+        }
+    });
 
-            let _ = slint::spawn_local(async move {
-                // 首先尝试使用本地Aria2服务
-                if let Some(aria2_client) = aria2_service_inner.lock().unwrap().get_client() {
-                    match get_file_url(&path, &tag, size_bytes).await {
-                        Ok(download_url) => {
-                            match aria2_client.add_download(&download_url, None).await {
-                                Ok(gid) => {
-                                    info!("Download task added to Aria2 with GID: {}", gid);
-                                    if let Some(ui) = ui_handle.upgrade() {
-                                        ui.set_search_text("下载任务已添加到Aria2".into());
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Failed to add download to Aria2: {}", e);
-                                    if let Some(ui) = ui_handle.upgrade() {
-                                        ui.set_search_text(format!("Aria2添加失败: {}", e).into());
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to get download URL: {}", e);
-                            if let Some(ui) = ui_handle.upgrade() {
-                                ui.set_search_text(format!("获取下载链接失败: {}", e).into());
-                            }
-                        }
+    // 下载选中的视频，以及索引中与视频同名的字幕
+    ui.on_send_with_subtitles({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move || {
+            let Some(ui) = ui_weak.upgrade() else {
+                return;
+            };
+            let mut items = selected_file_items(&ui);
+            let (records, _) = file_items_to_records(&items);
+
+            // 每个视频都要搜索一次，在工作线程中查找，结果回到事件循环后开始下载
+            let (reply_tx, reply_rx) = oneshot::channel();
+            let database = ctx.database.clone();
+            std::thread::spawn(move || {
+                let _ = reply_tx.send(find_subtitles(&database, &records));
+            });
+            let ui_weak = ui_weak.clone();
+            let ctx = ctx.clone();
+            spawn_ui_task("查找字幕", async move {
+                let Ok(result) = reply_rx.await else {
+                    return;
+                };
+                let Some(ui) = ui_weak.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(subtitles) => {
+                        ui.set_status_message(format!("找到 {} 个字幕文件", subtitles.len()).into());
+                        items.extend(subtitles.into_iter().map(file_record_to_item));
+                        spawn_downloads(items, ui_weak.clone(), &ctx);
                     }
-                } else {
-                    // 回退到原来的HTTP方式
-                    warn!("Aria2 client not available, falling back to HTTP method");
-                    match send_to_aria2(path, tag, size_bytes).await {
-                        Ok(_) => {
-                            if let Some(ui) = ui_handle.upgrade() {
-                                ui.set_search_text("上传成功".into());
-                            }
-                        }
-                        Err(e) => {
-                            if let Some(ui) = ui_handle.upgrade() {
-                                ui.set_search_text(format!("请求失败: {}", e).into());
-                            }
-                        }
+                    Err(e) => {
+                        errors::report_as(ErrorCategory::Database, "查找字幕失败", e);
                     }
                 }
             });
         }
     });
-    let clipboard = Arc::new(Mutex::new(Clipboard::new()?));
+
+    // 复制选中文件的下载链接（支持多选，每行一个链接）
+    // 剪切板在独立线程中访问，避免阻塞界面
     ui.on_copy_to_clipboard({
         let ui_weak = ui.as_weak();
-        let clipboard_ref = Arc::clone(&clipboard);
-        move |file_path, etag, size_kb| {
-            let ui_handle = ui_weak.clone();
-            let clipboard_inner = Arc::clone(&clipboard_ref);
-            let path = file_path.to_string();
-            let tag = etag.to_string();
-            let size_bytes = size_kb.to_string().trim().parse::<u64>().unwrap();
-            let _ = slint::spawn_local(async move {
-                let mut clipboard = clipboard_inner.lock().unwrap();
-                match copy_to_clipboard(path, tag, size_bytes, &mut *clipboard).await {
-                    Ok(_) => {
-                        if let Some(ui) = ui_handle.upgrade() {
-                            ui.set_search_text("成功获取链接".into());
+        let ctx = context.clone();
+        move |format_index| {
+            if let Some(ui) = ui_weak.upgrade() {
+                spawn_copy_links(
+                    selected_file_items(&ui),
+                    LinkFormat::from_index(format_index),
+                    ui_weak.clone(),
+                    &ctx,
+                );
+            }
+        }
+    });
+
+    // 复制当前结果集中全部文件的下载链接
+    ui.on_copy_all_links({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move || {
+            let Some(ui) = ui_weak.upgrade() else {
+                return;
+            };
+            // 懒加载的结果通过数据库管理线程读取，在事件循环中等待
+            let pending = result_file_items(&ui);
+            let ui_weak = ui_weak.clone();
+            let ctx = ctx.clone();
+            spawn_ui_task("复制全部链接", async move {
+                let result = pending.await;
+                let Some(ui) = ui_weak.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(items) if items.is_empty() => ui.set_status_message("没有可复制的结果".into()),
+                    Ok(items) => spawn_copy_links(items, LinkFormat::Url, ui_weak.clone(), &ctx),
+                    Err(e) => {
+                        errors::report("无法读取搜索结果", &e);
+                    }
+                }
+            });
+        }
+    });
+
+    // 创建分享链接并复制到剪切板
+    ui.on_create_share_link({
+        let ui_weak = ui.as_weak();
+        let clipboard = context.clipboard.clone();
+        move |password, expire_index| {
+            let ui = match ui_weak.upgrade() {
+                Some(ui) => ui,
+                None => return,
+            };
+            let items = selected_file_items(&ui);
+            let expire_days = SHARE_EXPIRE_DAYS
+                .get(expire_index.max(0) as usize)
+                .copied()
+                .unwrap_or(0);
+            ui.set_status_message("正在创建分享链接...".into());
+
+            let ui_weak = ui_weak.clone();
+            let clipboard = clipboard.clone();
+            spawn_ui_task("创建分享链接", async move {
+                match create_share_link(items, &password, expire_days, &clipboard).await {
+                    Ok(link) => {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.set_status_message(format!("已复制分享链接: {}", link).into());
                         }
                     }
                     Err(e) => {
-                        if let Some(ui) = ui_handle.upgrade() {
-                            ui.set_search_text(format!("无法获取链接: {}", e).into());
-                        }
+                        errors::report("创建分享失败", &anyhow::Error::new(e));
                     }
                 }
             });
-
         }
     });
 
-    Ok(())
-}
+    // 最近活动视图
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_recent_view_requested(move || {
+        handle_recent_view_requested(&ui_handle, &ctx.activity_log);
+    });
 
-pub async fn start_backend_service(port: u16) -> io::Result<()> {
-    // 1. 初始化配置和环境
-    let env = match NetDiskEnv::new() {
-        Ok(env) => env,
-        Err(e) => {
-            error!("❌ 致命错误：无法初始化 NetDiskEnv：{}", e);
-            // 无法初始化环境，直接返回 IO 错误
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to initialize NetDiskEnv: {}", e),
-            ));
+    ui.on_recent_copy_link({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move |item| {
+            spawn_copy_links(vec![item], LinkFormat::Url, ui_weak.clone(), &ctx);
         }
-    };
+    });
 
-    // 2. 尝试加载 AccessToken
-    let file_path = env.config_dir.join("config.toml");
-    let mut access_token: AccessToken = AccessToken::default();
-    match get_access_token_from_cache(&file_path).await {
-        Ok(token) => {
-            access_token = token;
-        }
-        Err(_) => {
-            error!("Error to message: Failed to load access token from cache.");
+    ui.on_recent_send_to_aria2({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move |item| {
+            spawn_downloads(vec![item], ui_weak.clone(), &ctx);
         }
-    }
-
-    // 3. 注入全局数据 (web::Data 是 Arc 的封装，用于线程间共享)
-    // 推荐在外部先创建 Arc，再创建 web::Data
-    let config_path_data = web::Data::new(env);
-    let access_token_data = web::Data::new(access_token);
+    });
 
-    let addr = format!("127.0.0.1:{}", port);
-    info!("Web 后端服务正在绑定到：{}", addr);
+    // 收藏集
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collections_refresh(move || handle_collections_refresh(&ui_handle, &ctx.collections));
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_create(move |name| handle_collection_create(&name, &ui_handle, &ctx.collections));
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_opened(move |id| handle_collection_opened(id, &ui_handle, &ctx.collections));
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_delete(move |id| handle_collection_delete(id, &ui_handle, &ctx.collections));
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_add_selected(move |id| {
+        handle_collection_add_selected(id, &ui_handle, &ctx.collections, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_remove_item(move |id, database, path| {
+        handle_collection_remove_item(id, &database, &path, &ui_handle, &ctx.collections);
+    });
 
-    // 4. 启动 Actix Web 服务器
-    // 注意：HttpServer::new 接收一个 move 闭包
-    let server = HttpServer::new(move || {
-        // 在每次新 worker 线程创建时，克隆 web::Data
-        create_app(config_path_data.clone(), access_token_data.clone())
-    })
-    .bind(addr)?; // 绑定端口，如果失败会返回 io::Error
+    ui.on_collection_download({
+        let ui_weak = ui.as_weak();
+        let ctx = context.clone();
+        move |id| match collection_file_items(&ctx, id) {
+            Ok(items) => spawn_downloads(items, ui_weak.clone(), &ctx),
+            Err(e) => {
+                errors::report("无法读取收藏集", &e);
+            }
+        }
+    });
 
-    // 运行服务器并等待
-    server.run().await
-}
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_collection_export(move |id| match collection_file_items(&ctx, id) {
+        Ok(items) => spawn_export(items, "collection", ui_handle.clone(), &ctx.job_queue),
+        Err(e) => {
+            errors::report("无法读取收藏集", &e);
+        }
+    });
+    handle_collections_refresh(&ui.as_weak(), &context.collections);
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // 初始化日志系统
-    tracing_subscriber::fmt::init();
+    // 校验选中文件的本地副本（后台任务）
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_verify_checksums(move || {
+        let ui = match ui_handle.upgrade() {
+            Some(ui) => ui,
+            None => return,
+        };
+        let (records, _) = file_items_to_records(&selected_file_items(&ui));
+        let database = ctx.database.clone();
+        ui.set_status_message(format!("正在校验 {} 个文件...", records.len()).into());
 
-    // 创建应用范围跟踪
-    let span = span!(Level::INFO, "netdisk_db", foo = 42, bar = "hello");
-    let _enter = span.enter();
+        let ui_weak = ui_handle.clone();
+        ctx.job_queue.submit(format!("校验 {} 个文件", records.len()), move |job| async move {
+            let cancel = job.cancel_flag();
+            // 校验在数据库管理线程启动的工作线程中执行，这里等待结果
+            let result = task::spawn_blocking(move || {
+                database.verify_checksums(records, cancel, move |done, total| {
+                    job.set_progress(format!("{}/{}", done, total));
+                })
+            })
+            .await?;
+            let summary = match result {
+                Ok(summary) => summary,
+                Err(e) => {
+                    errors::report("校验本地文件失败", &e);
+                    return Err(e);
+                }
+            };
+            let message = if summary.mismatched > 0 {
+                format!("校验完成：{} 个文件与 etag 不一致", summary.mismatched)
+            } else {
+                format!("校验完成：{} 个文件一致，{} 个跳过", summary.matched, summary.skipped)
+            };
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    refresh_verification(&ui);
+                    ui.set_status_message(message.into());
+                }
+            });
+            Ok(())
+        });
+    });
 
-    info!("Starting File Search Application");
-    let port = 8080;
+    // 导出选中文件为 CSV（后台任务）
+    let ui_handle = ui.as_weak();
+    let job_queue = context.job_queue.clone();
+    ui.on_export_selection(move || {
+        if let Some(ui) = ui_handle.upgrade() {
+            spawn_export(selected_file_items(&ui), "selection", ui_handle.clone(), &job_queue);
+        }
+    });
 
-    // 初始化配置
-    let config = initialize_config()?;
-    debug!("Configuration loaded successfully");
+    // 结果行点击（Ctrl/Shift 多选）
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_row_clicked(move |index, toggle, extend| {
+        handle_row_clicked(index, toggle, extend, &ui_handle);
+        handle_media_info_requested(&ui_handle, &ctx.database);
+    });
+    let ui_handle = ui.as_weak();
+    let ctx = context.clone();
+    ui.on_media_info_requested(move || handle_media_info_requested(&ui_handle, &ctx.database));
 
-    // 启动Aria2服务
+    // 详情面板：直接复制字段文本
+    ui.on_copy_text({
+        let ui_weak = ui.as_weak();
+        let clipboard = context.clipboard.clone();
+        move |text| {
+            let ui_weak = ui_weak.clone();
+            let clipboard = clipboard.clone();
+            spawn_ui_task("复制文本", async move {
+                let result = clipboard.set_text(text.as_str()).await;
+                match result {
+                    Ok(_) => {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.set_status_message("已复制到剪切板".into());
+                        }
+                    }
+                    Err(e) => {
+                        errors::report_as(ErrorCategory::Clipboard, "复制失败", e);
+                    }
+                }
+            });
+        }
+    });
+
+    // 下载链接二维码
+    let ui_handle = ui.as_weak();
+    ui.on_show_qr_code(move || handle_show_qr_code(&ui_handle));
+
+    // 剪切板历史：重新复制之前复制过的链接
+    let ui_handle = ui.as_weak();
+    let clipboard = context.clipboard.clone();
+    ui.on_clipboard_history_requested(move || handle_clipboard_history_refresh(&ui_handle, &clipboard));
+    let ui_handle = ui.as_weak();
+    let clipboard = context.clipboard.clone();
+    ui.on_clipboard_history_clear(move || handle_clipboard_history_clear(&ui_handle, &clipboard));
+    ui.on_clipboard_history_copy({
+        let ui_weak = ui.as_weak();
+        let clipboard = context.clipboard.clone();
+        move |index| {
+            let Some(entry) = clipboard.history().get(index.max(0) as usize) else {
+                return;
+            };
+            let ui_weak = ui_weak.clone();
+            let clipboard = clipboard.clone();
+            spawn_ui_task("重新复制", async move {
+                match clipboard.set_text(entry.text).await {
+                    Ok(_) => {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.set_status_message("已重新复制到剪切板".into());
+                        }
+                    }
+                    Err(e) => {
+                        errors::report_as(ErrorCategory::Clipboard, "复制失败", e);
+                    }
+                }
+            });
+        }
+    });
+
+    // 拖放文件夹到窗口时建立索引
+    let active_index_job = Arc::new(Mutex::new(None));
+    ui.window().on_winit_window_event({
+        let ui_handle = ui.as_weak();
+        let ctx = context.clone();
+        let active_job = active_index_job.clone();
+        move |_window, event| {
+            if let WindowEvent::DroppedFile(path) = event {
+                handle_folder_dropped(
+                    path.clone(),
+                    &ui_handle,
+                    ctx.database.clone(),
+                    active_job.clone(),
+                );
+            }
+            EventResult::Propagate
+        }
+    });
+    ui.on_cancel_indexing(move || {
+        handle_cancel_indexing(&active_index_job);
+    });
+
+    // 网盘文件夹选择对话框
+    let remote_browser = Rc::new(RefCell::new(RemoteFolderBrowser::default()));
+    ui.on_remote_folder_requested({
+        let ui_weak = ui.as_weak();
+        let browser = remote_browser.clone();
+        move || {
+            browser.borrow_mut().reset();
+            handle_remote_folder_load(&ui_weak, &browser.borrow());
+        }
+    });
+    ui.on_remote_folder_open({
+        let ui_weak = ui.as_weak();
+        let browser = remote_browser.clone();
+        move |id, name| {
+            let Ok(id) = id.parse::<i64>() else {
+                warn!("Invalid remote folder id: {}", id);
+                return;
+            };
+            browser.borrow_mut().enter(id, &name);
+            handle_remote_folder_load(&ui_weak, &browser.borrow());
+        }
+    });
+    ui.on_remote_folder_up({
+        let ui_weak = ui.as_weak();
+        let browser = remote_browser.clone();
+        move || {
+            if browser.borrow_mut().up() {
+                handle_remote_folder_load(&ui_weak, &browser.borrow());
+            }
+        }
+    });
+    ui.on_remote_folder_confirm({
+        let ui_weak = ui.as_weak();
+        let config = context.config.clone();
+        move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                handle_remote_folder_selected(&ui, &remote_browser.borrow(), &config);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// 在UI事件循环中依次下载文件，并记录到最近活动
+///
+/// # Arguments
+/// * `items` - 要下载的文件项
+/// * `ui_handle` - UI 弱引用
+/// * `context` - 应用上下文，使用其中的下载管理器、内置下载器和活动记录
+fn spawn_downloads(items: Vec<FileItem>, ui_handle: slint::Weak<AppWindow>, context: &AppContext) {
+    // 按当前数据库的配置决定下载目录
+    let database_config = context.database.current_config();
+    let download_manager = context.downloads.clone();
+    let downloader = context.downloader.clone();
+    record_activity(
+        &context.activity_log,
+        ActivityKind::SentToAria2,
+        &items,
+        &database_config.name,
+    );
+
+    spawn_ui_task("下载", async move {
+        let (items, missing) = filter_remote_existing(items).await;
+        if missing > 0 {
+            if let Some(ui) = ui_handle.upgrade() {
+                refresh_remote_state(&ui);
+                ui.set_status_message(format!("{} 个文件在网盘中已失效，已跳过", missing).into());
+            }
+        }
+
+        // 大小无效的文件跳过并提示，不影响其他文件
+        let (records, invalid) = file_items_to_records(&items);
+        if let (Some(ui), Some(e)) = (ui_handle.upgrade(), invalid.first()) {
+            ui.set_status_message(format!("{} 个文件无法下载: {}", invalid.len(), e).into());
+        }
+
+        for record in records {
+            download_file_item(
+                record,
+                &ui_handle,
+                &download_manager,
+                &downloader,
+                database_config.download_dir.clone(),
+                &database_config.name,
+            )
+            .await;
+        }
+    });
+}
+
+/// 还原最小化的主窗口并显示到最前面
+fn activate_window(ui: &AppWindow) {
+    let window = ui.window();
+    window.set_minimized(false);
+    window.with_winit_window(|winit_window| {
+        winit_window.set_visible(true);
+        winit_window.focus_window();
+    });
+}
+
+/// 处理其他实例和脚本通过本地套接字发送的请求
+///
+/// 请求在监听任务中处理，只能使用可以跨线程的句柄；访问界面需要通过事件循环
+///
+/// # Arguments
+/// * `ui_handle` - UI 弱引用
+/// * `database` - 数据库管理线程句柄
+/// * `downloads` - 下载管理器
+fn instance_request_handler(
+    ui_handle: slint::Weak<AppWindow>,
+    database: DatabaseHandle,
+    downloads: SharedDownloadManager,
+) -> single_instance::RequestHandler {
+    Arc::new(move |request| {
+        let ui_handle = ui_handle.clone();
+        let database = database.clone();
+        let downloads = downloads.clone();
+        Box::pin(async move {
+            let result = match request {
+                InstanceRequest::Activate => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui_handle.upgrade() {
+                            activate_window(&ui);
+                        }
+                    });
+                    Ok(String::new())
+                }
+                // 数据库请求通过通道等待管理线程，放到阻塞线程池中执行
+                InstanceRequest::Search { query } => task::spawn_blocking(move || -> anyhow::Result<String> {
+                    let records = database.search(&query, SearchOptions::default(), IPC_SEARCH_LIMIT)?;
+                    Ok(serde_json::to_string(&records)?)
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.into())),
+                InstanceRequest::AddDownload { url, name } => downloads.submit_uri(&url, None, name.as_deref()).await,
+                InstanceRequest::Open { id } => task::spawn_blocking(move || -> anyhow::Result<String> {
+                    let record = database
+                        .find_file(id)?
+                        .with_context(|| format!("Record {} not found", id))?;
+                    let path = path_mapping::map_path(&record.path, &database.current_config().path_mappings);
+                    handle_open_file(&path)?;
+                    Ok(path)
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.into())),
+            };
+            match result {
+                Ok(payload) => single_instance::ok_response(&payload),
+                Err(e) => {
+                    warn!("Instance request failed: {:#}", e);
+                    single_instance::error_response(&e)
+                }
+            }
+        })
+    })
+}
+
+/// 在后台运行自检并更新自检对话框
+///
+/// # Arguments
+/// * `ui_handle` - UI 弱引用
+/// * `config` - 当前配置
+/// * `config_check` - 启动时的配置文件检查结果
+/// * `port_check` - 启动时的后端端口检查结果
+/// * `requested` - 是否由用户打开，否则只在有问题时显示对话框
+fn spawn_self_check(
+    ui_handle: slint::Weak<AppWindow>,
+    config: AppConfig,
+    config_check: self_check::CheckItem,
+    port_check: self_check::CheckItem,
+    requested: bool,
+) {
+    if let Some(ui) = ui_handle.upgrade() {
+        ui.set_self_check_running(true);
+    }
+    spawn_ui_task("启动自检", async move {
+        let report = self_check::run(&config, config_check, port_check).await;
+        report.log();
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        ui.set_self_check_running(false);
+        ui.set_self_check_items(self_check_to_model(&report));
+        if report.is_healthy() {
+            if !requested {
+                ui.set_status_message("启动自检通过".into());
+            }
+        } else {
+            ui.set_self_check_visible(true);
+        }
+    });
+}
+
+/// 刷新下载面板中等待提交到Aria2的任务
+fn refresh_download_queue(ui: &AppWindow, download_manager: &DownloadManager) {
+    let items: Vec<QueuedDownloadItem> = download_manager
+        .queued_downloads()
+        .into_iter()
+        .map(|queued| QueuedDownloadItem {
+            id: queued.id.min(i32::MAX as u64) as i32,
+            name: queued.source.name.into(),
+            size_text: locale::format_size(queued.source.size).into(),
+            database: queued.source.database.into(),
+        })
+        .collect();
+    ui.set_download_queue(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// 下载面板扫描的目录：Aria2默认目录、内置下载器目录和各数据库设置的下载目录
+fn download_dirs(context: &AppContext) -> Vec<PathBuf> {
+    let config = context.config.lock().unwrap();
+    let mut dirs = vec![
+        PathBuf::from(&config.aria2.download_dir),
+        context.downloader.download_dir().to_path_buf(),
+    ];
+    dirs.extend(
+        std::iter::once(&config.database)
+            .chain(&config.multi_database.databases)
+            .filter_map(|database| database.download_dir.as_ref())
+            .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// 收藏集中的全部文件项
+fn collection_file_items(context: &AppContext, id: i32) -> anyhow::Result<Vec<FileItem>> {
+    Ok(context
+        .collections
+        .items(i64::from(id))?
+        .into_iter()
+        .map(|item| file_record_to_item(item.record))
+        .collect())
+}
+
+/// 在后台任务中将文件项导出为导出目录下的 CSV 文件
+///
+/// # Arguments
+/// * `items` - 要导出的文件项
+/// * `prefix` - 文件名前缀，后接导出时间
+/// * `ui_handle` - UI 弱引用
+/// * `job_queue` - 后台任务队列
+fn spawn_export(items: Vec<FileItem>, prefix: &str, ui_handle: slint::Weak<AppWindow>, job_queue: &SharedJobQueue) {
+    let target = paths::export_dir().join(format!(
+        "{}-{}.csv",
+        prefix,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    if let Some(ui) = ui_handle.upgrade() {
+        ui.set_status_message(format!("正在导出 {} 个文件...", items.len()).into());
+    }
+
+    job_queue.submit(format!("导出 {} 个文件", items.len()), move |_ctx| async move {
+        let result = task::spawn_blocking({
+            let target = target.clone();
+            move || export_file_items(&items, &target)
+        })
+        .await?;
+        match &result {
+            Ok(_) => {
+                let message = format!("已导出到 {}", target.display());
+                let _ = slint::invoke_from_event_loop(move || {
+                    if let Some(ui) = ui_handle.upgrade() {
+                        ui.set_status_message(message.into());
+                    }
+                });
+            }
+            Err(e) => {
+                errors::report_as(ErrorCategory::FileSystem, "导出失败", format!("{:#}", e));
+            }
+        }
+        result?;
+        Ok(())
+    });
+}
+
+/// 在UI事件循环中获取下载链接并复制到剪切板，成功后记录到最近活动
+///
+/// # Arguments
+/// * `items` - 文件项
+/// * `format` - 复制的链接格式
+/// * `ui_handle` - UI 弱引用
+/// * `context` - 应用上下文，使用其中的剪切板和活动记录
+fn spawn_copy_links(
+    items: Vec<FileItem>,
+    format: LinkFormat,
+    ui_handle: slint::Weak<AppWindow>,
+    context: &AppContext,
+) {
+    let database = context.current_database_name();
+    let clipboard = context.clipboard.clone();
+    let activity_log = context.activity_log.clone();
+    spawn_ui_task("复制链接", async move {
+        let (items, missing) = filter_remote_existing(items).await;
+        if items.is_empty() {
+            if let Some(ui) = ui_handle.upgrade() {
+                refresh_remote_state(&ui);
+                ui.set_status_message("所选文件在网盘中已失效".into());
+            }
+            return;
+        }
+
+        // 多个文件时在状态栏显示获取链接的进度
+        let show_progress = items.len() > 1;
+        let set_progress = |done: usize, total: usize| {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_link_progress_done(done as i32);
+                ui.set_link_progress_total(total as i32);
+            }
+        };
+        if show_progress {
+            set_progress(0, items.len());
+        }
+        let result = copy_links_to_clipboard(items.clone(), format, &clipboard, |done, total| {
+            if show_progress {
+                set_progress(done, total);
+            }
+        })
+        .await;
+        set_progress(0, 0);
+        let ui = match ui_handle.upgrade() {
+            Some(ui) => ui,
+            None => return,
+        };
+        refresh_remote_state(&ui);
+        match result {
+            Ok((count, failed)) => {
+                record_activity(&activity_log, ActivityKind::CopiedLink, &items, &database);
+                let failed = failed + missing;
+                let message = if failed > 0 {
+                    format!("已复制 {} 个链接（{}），{} 个失败", count, format.label(), failed)
+                } else {
+                    format!("已复制 {} 个链接（{}）", count, format.label())
+                };
+                ui.set_status_message(message.into());
+            }
+            Err(e) if e.is_offline() => {
+                refresh_pending_status(&ui);
+                ui.set_status_message(OFFLINE_QUEUED_MESSAGE.into());
+            }
+            Err(e) => {
+                errors::report("无法获取链接", &anyhow::Error::new(e));
+            }
+        }
+    });
+}
+
+/// 下载单个文件：优先提交到Aria2，不可用时回退到内置HTTP下载器
+///
+/// # Arguments
+/// * `record` - 要下载的文件
+/// * `ui_handle` - UI 弱引用，用于显示进度
+/// * `download_manager` - 下载管理器
+/// * `downloader` - 内置下载器
+/// * `target_dir` - 下载目录，None 表示使用Aria2默认目录
+/// * `database` - 来源数据库名称，写入下载元数据
+async fn download_file_item(
+    record: FileRecord,
+    ui_handle: &slint::Weak<AppWindow>,
+    download_manager: &SharedDownloadManager,
+    downloader: &HttpDownloader,
+    target_dir: Option<String>,
+    database: &str,
+) {
+    let source = DownloadSource::from_record(&record, target_dir.clone()).with_database(database);
+    let FileRecord {
+        path,
+        etag: tag,
+        size: size_bytes,
+        ..
+    } = record;
+    debug!(
+        "Sending to Aria2: path={}, etag={}, size_bytes={}",
+        path, tag, size_bytes
+    );
+
+    // 首先尝试使用本地Aria2服务
+    if download_manager.is_available() {
+        // 空间不足且设置了拒绝时由 enqueue 返回错误，这里只准备提示
+        let space_warning = download_manager.preflight(&source).await.and_then(|check| check.message());
+        match download_manager.submit(source).await {
+            Ok(submitted) => {
+                let status = match submitted {
+                    Submitted::Started(gid) => {
+                        info!("Download task added to Aria2 with GID: {}", gid);
+                        "下载任务已添加到Aria2".to_string()
+                    }
+                    Submitted::Queued(id) => {
+                        info!("Download {} queued as #{}", path, id);
+                        format!("同时下载数已满，已加入下载队列（{} 个等待中）", download_manager.queued_downloads().len())
+                    }
+                };
+                if let Some(ui) = ui_handle.upgrade() {
+                    let message = match space_warning {
+                        Some(warning) => format!("{}，{}", status, warning),
+                        None => status,
+                    };
+                    ui.set_status_message(message.into());
+                }
+            }
+            Err(e) if is_offline_error(&e) => {
+                warn!("Netdisk unreachable, queueing download: {:#}", e);
+                queue_pending(PendingKind::Download, &path, &tag, size_bytes, target_dir.as_deref());
+                if let Some(ui) = ui_handle.upgrade() {
+                    refresh_pending_status(&ui);
+                    ui.set_status_message(OFFLINE_QUEUED_MESSAGE.into());
+                }
+            }
+            Err(e) => {
+                // 获取链接阶段的错误按网络错误上报，其余归为 Aria2
+                let category = match ErrorCategory::classify(&e) {
+                    ErrorCategory::Other => ErrorCategory::Aria2,
+                    category => category,
+                };
+                errors::report_as(category, "Aria2添加失败", format!("{:#}", e));
+            }
+        }
+        return;
+    }
+
+    // 回退到内置HTTP下载器
+    warn!("Aria2 client not available, falling back to built-in downloader");
+    let space_check = disk_space::preflight(downloader.download_dir(), size_bytes, 0)
+        .inspect_err(|e| debug!("Skipping disk space check: {:#}", e))
+        .ok();
+    if let Some(check) = space_check.filter(|check| !check.is_sufficient()) {
+        let message = check.message().unwrap_or_default();
+        if check.blocks(download_manager.block_insufficient_space()) {
+            errors::report_as(ErrorCategory::FileSystem, "下载已取消", message);
+            return;
+        }
+        if let Some(ui) = ui_handle.upgrade() {
+            ui.set_status_message(message.into());
+        }
+    }
+    let download_url = match get_file_url(&path, &tag, size_bytes).await {
+        Ok(url) => url,
+        Err(e) if e.is_offline() => {
+            warn!("Netdisk unreachable, queueing download: {}", e);
+            queue_pending(PendingKind::Download, &path, &tag, size_bytes, target_dir.as_deref());
+            if let Some(ui) = ui_handle.upgrade() {
+                refresh_pending_status(&ui);
+                ui.set_status_message(OFFLINE_QUEUED_MESSAGE.into());
+            }
+            return;
+        }
+        Err(e) => {
+            errors::report("获取下载链接失败", &anyhow::Error::new(e));
+            return;
+        }
+    };
+    let file_name = format_upload_filename(&path).unwrap_or_else(|| tag.clone());
+    let started_at = chrono::Local::now().timestamp();
+    let history_entry = |save_path: &std::path::Path, outcome, error| DownloadHistoryEntry {
+        id: 0,
+        gid: String::new(),
+        record: source.record(),
+        database: source.database.clone(),
+        save_path: save_path.to_string_lossy().to_string(),
+        started_at,
+        finished_at: chrono::Local::now().timestamp(),
+        outcome,
+        error,
+    };
+
+    let mut last_percent = None;
+    let result = downloader
+        .download(&download_url, &file_name, Some(size_bytes), |progress| {
+            let percent = progress.percent();
+            if percent != last_percent {
+                last_percent = percent;
+                if let (Some(ui), Some(p)) = (ui_handle.upgrade(), percent) {
+                    ui.set_status_message(format!("正在下载 {}: {}%", file_name, p).into());
+                }
+            }
+        })
+        .await;
+
+    match result {
+        Ok(target) => {
+            info!("Built-in download finished: {:?}", target);
+            sidecar::write_logged(&target, &source.sidecar());
+            record_download(&history_entry(&target, DownloadOutcome::Complete, None));
+            notifications::notify(NotificationKind::DownloadComplete, "下载完成", &target.to_string_lossy());
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_status_message(format!("下载完成: {}", target.display()).into());
+            }
+        }
+        Err(e) => {
+            let target = downloader.download_dir().join(&file_name);
+            record_download(&history_entry(&target, DownloadOutcome::Failed, Some(format!("{:#}", e))));
+            errors::report("下载失败", &e);
+        }
+    }
+}
+
+/// 定期轮询Aria2全局统计并更新状态栏
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `aria2_service` - Aria2服务实例
+///
+/// # Returns
+/// * `slint::Timer` - 轮询定时器，需要在UI运行期间保持存活
+fn start_aria2_stat_polling(ui: &AppWindow, aria2_service: SharedAria2Service) -> slint::Timer {
+    let timer = slint::Timer::default();
+    let ui_weak = ui.as_weak();
+
+    timer.start(
+        slint::TimerMode::Repeated,
+        Duration::from_secs(2),
+        move || {
+            let ui_handle = ui_weak.clone();
+            let client = aria2_service.lock().unwrap().get_client().cloned();
+            spawn_ui_task("查询Aria2状态", async move {
+                let status = match client {
+                    Some(client) => match client.get_global_stat().await {
+                        Ok(stat) => format!(
+                            "Aria2: ↓ {}/s ↑ {}/s | 活动 {} 等待 {} 已停止 {}",
+                            locale::format_size(stat.download_speed),
+                            locale::format_size(stat.upload_speed),
+                            stat.num_active,
+                            stat.num_waiting,
+                            stat.num_stopped
+                        ),
+                        Err(e) => {
+                            debug!("Failed to get Aria2 global stat: {}", e);
+                            "Aria2: 连接失败".to_string()
+                        }
+                    },
+                    None => "Aria2: 未连接".to_string(),
+                };
+                if let Some(ui) = ui_handle.upgrade() {
+                    ui.set_aria2_status(status.into());
+                }
+            });
+        },
+    );
+
+    timer
+}
+
+/// 启动时检查新版本，有新版本时在状态栏显示提示
+///
+/// # Arguments
+/// * `ui` - UI实例
+/// * `releases_url` - GitHub 最新发布接口
+fn check_for_update(ui: &AppWindow, releases_url: String) {
+    let ui_weak = ui.as_weak();
+    spawn_ui_task("检查新版本", async move {
+        match update_check::check_for_update(&releases_url).await {
+            Ok(Some(release)) => {
+                info!("New version available: {} ({})", release.version, release.url);
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_update_version(release.version.into());
+                    ui.set_update_url(release.url.into());
+                }
+            }
+            Ok(None) => debug!("Already running the latest version"),
+            Err(e) => debug!("Update check failed: {:#}", e),
+        }
+    });
+}
+
+/// 定期获取网盘空间用量并显示在状态栏
+///
+/// # Arguments
+/// * `ui` - UI实例
+fn start_quota_polling(ui: &AppWindow) -> slint::Timer {
+    fn refresh(ui_weak: slint::Weak<AppWindow>) {
+        spawn_ui_task("查询网盘容量", async move {
+            match shared_api_client().account_info().await {
+                Ok(info) => {
+                    if let Some(ui) = ui_weak.upgrade() {
+                        ui.set_quota_status(format_quota(&info).into());
+                    }
+                }
+                Err(e) => debug!("Failed to get account info: {}", e),
+            }
+        });
+    }
+
+    refresh(ui.as_weak());
+    let timer = slint::Timer::default();
+    let ui_weak = ui.as_weak();
+    timer.start(slint::TimerMode::Repeated, QUOTA_REFRESH_INTERVAL, move || {
+        refresh(ui_weak.clone())
+    });
+
+    timer
+}
+
+/// 定期重试离线队列中的操作，并在状态栏显示队列中的操作数
+///
+/// 点击状态栏中的队列提示时立即重试
+///
+/// # Arguments
+/// * `ui` - UI实例
+/// * `pending` - 离线操作队列
+/// * `download_manager` - 下载管理器
+/// * `downloader` - Aria2不可用时使用的内置下载器
+/// * `interval` - 重试间隔
+///
+/// # Returns
+/// * `slint::Timer` - 重试定时器，需要在UI运行期间保持存活
+fn start_pending_retry(
+    ui: &AppWindow,
+    pending: SharedPendingOperations,
+    download_manager: SharedDownloadManager,
+    downloader: Arc<HttpDownloader>,
+    interval: Duration,
+) -> slint::Timer {
+    let initial = pending.count().unwrap_or(0);
+    ui.set_pending_status(format_pending_status(initial).into());
+
+    // 上一轮重试未结束时跳过
+    let running = Rc::new(Cell::new(false));
+    let retry = move |ui_weak: slint::Weak<AppWindow>| {
+        if running.replace(true) {
+            return;
+        }
+        let running = running.clone();
+        let pending = pending.clone();
+        let download_manager = download_manager.clone();
+        let downloader = downloader.clone();
+        spawn_ui_task("重试离线操作", async move {
+            let result = pending
+                .retry_all(|operation| execute_pending(operation, &download_manager, &downloader))
+                .await;
+            running.set(false);
+
+            let Some(ui) = ui_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(summary) => {
+                    ui.set_pending_status(format_pending_status(summary.remaining).into());
+                    if summary.completed > 0 {
+                        ui.set_status_message(
+                            format!("网络已恢复，完成 {} 个待处理操作", summary.completed).into(),
+                        );
+                    }
+                }
+                Err(e) => warn!("Failed to retry pending operations: {}", e),
+            }
+        });
+    };
+
+    let retry = Rc::new(retry);
+    ui.on_retry_pending({
+        let ui_weak = ui.as_weak();
+        let retry = retry.clone();
+        move || retry(ui_weak.clone())
+    });
+
+    let timer = slint::Timer::default();
+    let ui_weak = ui.as_weak();
+    timer.start(slint::TimerMode::Repeated, interval, move || retry(ui_weak.clone()));
+
+    timer
+}
+
+/// 执行离线队列中的一个操作
+///
+/// # Arguments
+/// * `operation` - 暂存的操作
+/// * `download_manager` - 下载管理器
+/// * `downloader` - Aria2不可用时使用的内置下载器
+async fn execute_pending(
+    operation: PendingOperation,
+    download_manager: &DownloadManager,
+    downloader: &HttpDownloader,
+) -> Result<()> {
+    match operation.kind {
+        // 链接进入缓存，之后复制时直接使用
+        PendingKind::ResolveLink => {
+            get_file_url(&operation.path, &operation.etag, operation.size).await?;
+        }
+        PendingKind::Download if download_manager.is_available() => {
+            let submitted = download_manager
+                .submit(DownloadSource::from_path(
+                    &operation.path,
+                    &operation.etag,
+                    operation.size,
+                    operation.target_dir,
+                ))
+                .await?;
+            info!("Queued download submitted: {:?}", submitted);
+        }
+        PendingKind::Download => {
+            let url = get_file_url(&operation.path, &operation.etag, operation.size).await?;
+            let file_name =
+                format_upload_filename(&operation.path).unwrap_or_else(|| operation.etag.clone());
+            let target = downloader
+                .download(&url, &file_name, Some(operation.size), |_| {})
+                .await?;
+            info!("Queued download finished: {:?}", target);
+        }
+    }
+    Ok(())
+}
+
+/// 更新状态栏中离线队列的操作数
+fn refresh_pending_status(ui: &AppWindow) {
+    if let Some(pending) = shared_pending_operations() {
+        ui.set_pending_status(format_pending_status(pending.count().unwrap_or(0)).into());
+    }
+}
+
+/// 状态栏中离线队列的提示，队列为空时为空字符串
+fn format_pending_status(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        count => format!("待处理: {}（点击重试）", count),
+    }
+}
+
+/// 定期在状态栏显示后台任务数
+///
+/// # Arguments
+/// * `ui` - UI实例
+/// * `job_queue` - 后台任务队列
+fn start_job_status_polling(ui: &AppWindow, job_queue: SharedJobQueue) -> slint::Timer {
+    let timer = slint::Timer::default();
+    let ui_weak = ui.as_weak();
+
+    timer.start(
+        slint::TimerMode::Repeated,
+        Duration::from_secs(1),
+        move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                let status = match job_queue.active_count() {
+                    0 => String::new(),
+                    count => format!("后台任务: {}", count),
+                };
+                ui.set_jobs_status(status.into());
+            }
+        },
+    );
+
+    timer
+}
+
+/// 定期检查下载任务状态，自动重启链接过期的任务，提交下载队列中等待的任务，并应用限速规划
+///
+/// # Arguments
+/// * `ui` - UI 实例，下载面板打开时刷新下载队列
+/// * `download_manager` - 下载管理器
+///
+/// # Returns
+/// * `slint::Timer` - 检查定时器，需要在UI运行期间保持存活
+fn start_download_monitor(ui: &AppWindow, download_manager: SharedDownloadManager) -> slint::Timer {
+    let timer = slint::Timer::default();
+    let ui_weak = ui.as_weak();
+
+    timer.start(
+        slint::TimerMode::Repeated,
+        Duration::from_secs(5),
+        move || {
+            let manager = download_manager.clone();
+            let ui_handle = ui_weak.clone();
+            spawn_ui_task("检查下载任务", async move {
+                if let Err(e) = manager.check_tasks().await {
+                    warn!("Failed to check download tasks: {}", e);
+                }
+                if let Some(ui) = ui_handle.upgrade().filter(|ui| ui.get_downloads_visible()) {
+                    refresh_download_queue(&ui, &manager);
+                }
+                if let Err(e) = manager.apply_speed_schedule().await {
+                    warn!("Failed to apply speed schedule: {}", e);
+                }
+            });
+        },
+    );
+
+    timer
+}
+
+pub async fn start_backend_service(
+    backend: BackendConfig,
+    bound_port: std::sync::mpsc::Sender<u16>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> io::Result<()> {
+    // 1. 初始化配置和环境
+    let env = match NetDiskEnv::new() {
+        Ok(env) => env,
+        Err(e) => {
+            error!("❌ 致命错误：无法初始化 NetDiskEnv：{}", e);
+            // 无法初始化环境，直接返回 IO 错误
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Failed to initialize NetDiskEnv: {}", e),
+            ));
+        }
+    };
+
+    // 2. 加载 AccessToken，并在后台定期刷新
+    let file_path = env.config_dir.join("config.toml");
+    let mut token_source = CachedTokenSource::new(file_path);
+    if let Some((client_id, client_secret)) = backend.client_credentials() {
+        token_source = token_source.with_credentials(backend.token_url.clone(), client_id, client_secret);
+    }
+    let token_manager = Arc::new(TokenManager::new(
+        token_source,
+        Duration::from_secs(backend.token_refresh_secs.max(60)),
+    ));
+    if let Err(e) = token_manager.refresh().await {
+        error!("Error to message: {}", e);
+    }
+    tokio::spawn(token_manager.clone().run(shutdown.resubscribe()));
+
+    // 3. 注入全局数据 (web::Data 是 Arc 的封装，用于线程间共享)
+    // 推荐在外部先创建 Arc，再创建 web::Data
+    // create_app 注册的是启动时的令牌，fresh_access_token 中间件在每个请求中换成最新令牌
+    let config_path_data = web::Data::new(env);
+    let shared_token = token_manager.token();
+    let access_token_data = web::Data::new(shared_token.read().await.clone());
+    let shared_token_data = web::Data::new(shared_token);
+
+    // 按 IP 限流，所有 worker 共用同一个限流器
+    let rate_limiter = web::Data::new(RateLimiter::new(
+        backend.rate_limit_per_minute,
+        Duration::from_secs(60),
+    ));
+
+    // 4. 启动 Actix Web 服务器，端口被占用时依次尝试后续端口
+    // 注意：HttpServer::new 接收一个 move 闭包
+    let attempts = backend.port_attempts.max(1);
+    let mut last_error = None;
+    let mut bound = None;
+    for offset in 0..attempts {
+        let port = match backend.port.checked_add(offset) {
+            Some(port) => port,
+            None => break,
+        };
+        let addr = format!("{}:{}", backend.host, port);
+        info!("Web 后端服务正在绑定到：{}", addr);
+
+        let config_path_data = config_path_data.clone();
+        let access_token_data = access_token_data.clone();
+        let shared_token_data = shared_token_data.clone();
+        let rate_limiter = rate_limiter.clone();
+        let rate_limited = backend.rate_limit_per_minute > 0;
+        let request_logging = backend.request_logging;
+        let server = HttpServer::new(move || {
+            // 在每次新 worker 线程创建时，克隆 web::Data
+            create_app(config_path_data.clone(), access_token_data.clone())
+                .configure(ws::configure)
+                .configure(jobs::configure)
+                .configure(collections::configure)
+                .configure(metrics::configure)
+                .app_data(shared_token_data.clone())
+                .app_data(rate_limiter.clone())
+                .wrap(from_fn(middleware::fresh_access_token))
+                .wrap(Condition::new(rate_limited, from_fn(middleware::rate_limit)))
+                .wrap(Condition::new(request_logging, from_fn(middleware::log_requests)))
+        });
+        match server.bind(&addr) {
+            Ok(server) => {
+                bound = Some((server, port));
+                break;
+            }
+            Err(e) => {
+                warn!("Failed to bind backend service to {}: {}", addr, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let (server, port) = match bound {
+        Some(bound) => bound,
+        None => {
+            return Err(last_error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::AddrInUse, "No backend port available")
+            }))
+        }
+    };
+    let _ = bound_port.send(port);
+
+    // 运行服务器，收到退出通知后停止接受新连接并等待处理中的请求完成
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        let _ = shutdown.recv().await;
+        info!("Stopping backend service");
+        handle.stop(true).await;
+    });
+    server.await
+}
+
+/// 在独立线程中启动后端服务，并让共享 API 客户端使用其实际端口
+///
+/// 使用 spawn_blocking 因为 HttpServer 不是 Send
+fn spawn_backend_service(config: &AppConfig, shutdown: &SharedShutdown) {
+    let backend = config.backend.clone();
+    let (port_tx, port_rx) = std::sync::mpsc::channel();
+    let server_handle = task::spawn_blocking({
+        let backend = backend.clone();
+        let shutdown = shutdown.subscribe();
+        move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                if let Err(e) = start_backend_service(backend, port_tx, shutdown).await {
+                    error!("Backend service stopped: {}", e);
+                }
+            })
+        }
+    });
+    shutdown.register("backend", server_handle);
+
+    // 等待后端绑定端口，未配置外部后端时 API 客户端使用内置后端的实际端口
+    let port = match port_rx.recv_timeout(Duration::from_secs(5)) {
+        Ok(port) => port,
+        Err(_) => {
+            warn!("Backend service did not report a port, using configured port");
+            backend.port
+        }
+    };
+    let api_client = Arc::new(ApiClient::new(config.api.clone(), &backend.base_url(port)));
+    set_shared_api_client(api_client.clone());
+    set_shared_link_resolver(Arc::new(
+        LinkResolver::new(api_client, Duration::from_secs(config.api.link_cache_ttl_secs))
+            .with_remote_check(config.api.verify_remote_exists),
+    ));
+}
+
+/// 为每个外部播放器生成"用 <name> 播放"菜单项，只对单个视频或音频文件显示
+fn player_menu(players: &[PlayerConfig]) -> ContextMenuManager {
+    players
+        .iter()
+        .filter(|player| !player.command.trim().is_empty())
+        .fold(ContextMenuManager::builder(), |menu, player| {
+            let player = player.clone();
+            menu.action_when(
+                format!("用 {} 播放", player.name),
+                |records| match records {
+                    [record] => {
+                        !record.etag.is_empty()
+                            && matches!(
+                                category_of(&record.file_type, &record.name),
+                                Some(FileCategory::Video | FileCategory::Audio)
+                            )
+                    }
+                    _ => false,
+                },
+                move |records| {
+                    let player = player.clone();
+                    async move {
+                        let record = records.into_iter().next().context("没有选中文件")?;
+                        let url = get_file_url(record.path.as_str(), record.etag.as_str(), record.size).await?;
+                        player::launch(&player, &url, &record.name)?;
+                        anyhow::Ok(format!("已用 {} 播放 {}", player.name, record.name))
+                    }
+                },
+            )
+        })
+        .build()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli_args = cli::parse_args(std::env::args().skip(1))?;
+
+    // 配置、数据库、缓存和日志放在平台标准目录，可通过 --data-dir 指定；
+    // 便携模式（--portable 或可执行文件旁有 portable 文件）下都放在可执行文件所在目录
+    if let Some(dir) = data_dir_from_args() {
+        paths::set_override_dir(dir);
+    } else if std::env::args().skip(1).any(|arg| arg == PORTABLE_ARG) || paths::portable_marker_exists() {
+        paths::enable_portable()?;
+    }
+    paths::ensure_dirs()?;
+
+    // 初始化日志系统，命令行模式下日志写到 stderr，避免混入输出
+    let _log_guard = logging::init(&logging_config(), verbose_from_args(), cli_args.is_some())?;
+
+    // 创建应用范围跟踪
+    let span = span!(Level::INFO, "netdisk_db", foo = 42, bar = "hello");
+    let _enter = span.enter();
+
+    info!("Starting File Search Application");
+    if paths::is_portable() {
+        info!("Running in portable mode, config directory: {:?}", paths::config_dir());
+    }
+
+    // 配置文件在加载前检查，加载时可能从备份恢复
+    let config_check = self_check::check_config(&config_path());
+
+    // 初始化配置
+    let config = initialize_config()?;
+    debug!("Configuration loaded successfully");
+
+    // 文件大小和日期按配置或系统语言区域格式化
+    locale::set_locale(Locale::from_config(&config.locale));
+
+    // 程序崩溃时在数据目录写入崩溃报告，下次启动时提示查看
+    crash_report::install_panic_hook(&config);
+
+    // 查询诊断默认关闭，可在配置或诊断面板中开启
+    shared_query_diagnostics().configure(config.search.diagnostics, config.search.slow_query_ms);
+
+    // 退出时通知后端服务等后台任务停止
+    let shutdown: SharedShutdown = Arc::new(ShutdownCoordinator::new());
+
+    // 索引、导出、批量下载等耗时操作在后台任务队列中执行
+    let job_queue: SharedJobQueue = Arc::new(JobQueue::new(
+        tokio::runtime::Handle::current(),
+        JOB_CONCURRENCY,
+    ));
+    set_shared_job_queue(job_queue.clone());
+
+    // 命令行模式：执行子命令后退出，不启动界面
+    if let Some(cli_args) = cli_args {
+        if cli_args.command.needs_backend() {
+            spawn_backend_service(&config, &shutdown);
+        }
+        let result = cli::run(cli_args, config).await;
+        shutdown.shutdown(SHUTDOWN_TIMEOUT).await;
+        return result;
+    }
+
+    // 已有实例在运行时激活它的窗口后退出，避免重复启动 aria2c 和后端服务
+    let Some(_instance_lock) = single_instance::acquire().context("Failed to check for a running instance")? else {
+        single_instance::send(&InstanceRequest::Activate)
+            .await
+            .context("Another instance is running but did not respond")?;
+        info!("Another instance is running, activated its window");
+        return Ok(());
+    };
+
+    // 启动Aria2服务
     let aria2_service = create_shared_aria2_service(config.aria2.clone());
     {
         let mut aria2_service_lock = aria2_service.lock().unwrap();
@@ -387,32 +1960,211 @@ async fn main() -> Result<()> {
         }
     }
 
-    // 启动后端服务 - 使用 spawn_blocking 因为 HttpServer 不是 Send
-    let _server_handle = task::spawn_blocking(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async { start_backend_service(port).await })
-    });
+    // 启动后端服务，端口在后端占用之前检查
+    let port_check = self_check::check_backend_port(&config.backend);
+    spawn_backend_service(&config, &shutdown);
 
     // 初始化数据库管理器
     let config_arc = Arc::new(Mutex::new(config.clone()));
-    let database_manager = Arc::new(Mutex::new(DatabaseManager::new(config_arc.clone())?));
+    let database_manager = DatabaseHandle::spawn(DatabaseManager::new(config_arc.clone())?);
     debug!("Database manager initialized successfully");
 
+    // 恢复上次使用的数据库
+    let last_database = config
+        .ui
+        .selected_database
+        .as_deref()
+        .and_then(|name| config_arc.lock().unwrap().database_index_by_name(name));
+    if let Some(index) = last_database {
+        if let Err(e) = database_manager.switch_database(index) {
+            warn!("Failed to restore last database: {}", e);
+        }
+    }
+
+    // 配置的外部播放器显示在右键菜单末尾
+    set_shared_context_menu(player_menu(&config.players));
+
     // 创建UI
     let ui = create_ui(&config)?;
     debug!("UI created successfully");
 
-    // 设置事件处理器（传递aria2服务）
-    setup_event_handlers(&ui, database_manager.clone(), aria2_service.clone())?;
+    if let Some(report) = crash_report::take_pending_report() {
+        warn!("Previous run crashed, report: {:?}", report);
+        ui.set_crash_report_path(report.to_string_lossy().to_string().into());
+        ui.set_crash_dialog_visible(true);
+    }
+
+    // 上报的错误显示在状态栏，可能来自任意线程
+    set_error_sink({
+        let ui_weak = ui.as_weak();
+        move |report| {
+            let ui_weak = ui_weak.clone();
+            let message = report.to_string();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_status_message(message.into());
+                }
+            });
+        }
+    });
+
+    // 下载管理器和内置下载器（Aria2不可用时的兜底方案）
+    let download_manager = Arc::new(DownloadManager::new(aria2_service.clone()));
+    let downloader = Arc::new(HttpDownloader::new(&config.aria2.download_dir));
+
+    // 监视文件夹：放入的种子和链接文件自动提交到Aria2
+    if let Some(watch_folder) = WatchFolder::from_config(&config.watch_folder, download_manager.clone()) {
+        let ui_weak = ui.as_weak();
+        let handle = tokio::spawn(Arc::new(watch_folder).run(shutdown.subscribe(), move |summary| {
+            let message = format!("监视文件夹：已提交 {} 个下载任务", summary.submitted);
+            let ui_weak = ui_weak.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_status_message(message.into());
+                }
+            });
+        }));
+        shutdown.register("watch-folder", handle);
+    }
+
+    // 最近活动记录
+    let activity_log = Arc::new(
+        ActivityLog::open(&paths::activity_log_file().to_string_lossy()).context("Failed to open activity log")?,
+    );
+
+    // 收藏集，后端接口和界面共用
+    let collections = Arc::new(
+        Collections::open(&paths::collections_file().to_string_lossy()).context("Failed to open collections")?,
+    );
+    set_shared_collections(collections.clone());
+
+    // 桌面通知：下载完成、索引完成
+    set_shared_notifier(Arc::new(Notifier::new(config.notifications.clone())));
+
+    // 下载历史，下载管理器在任务结束时写入
+    let download_history = Arc::new(
+        DownloadHistory::open(&paths::download_history_file().to_string_lossy())
+            .context("Failed to open download history")?,
+    );
+    set_shared_download_history(download_history.clone());
+
+    // 网盘无法访问时暂存的操作
+    let pending_operations = Arc::new(
+        PendingOperations::open(&paths::pending_ops_file().to_string_lossy())
+            .context("Failed to open pending operations")?,
+    );
+    set_shared_pending_operations(pending_operations.clone());
+
+    // 本地视频和音频的媒体信息，缓存在缓存目录中
+    match MediaInfoCache::open(&paths::media_info_file().to_string_lossy()) {
+        Ok(cache) => set_shared_media_info(Arc::new(MediaInfoService::new(cache))),
+        Err(e) => warn!("Failed to open media info cache: {}", e),
+    }
+
+    // 事件处理共用的子系统句柄
+    let context = AppContext {
+        config: config_arc.clone(),
+        database: database_manager.clone(),
+        aria2: aria2_service.clone(),
+        downloads: download_manager.clone(),
+        downloader: downloader.clone(),
+        // 剪切板在独立线程中访问，避免阻塞界面
+        clipboard: ClipboardHandle::spawn(),
+        activity_log,
+        collections,
+        download_history,
+        job_queue: job_queue.clone(),
+        search_debounce: Rc::new(slint::Timer::default()),
+    };
+    setup_event_handlers(&ui, &context)?;
+
+    // 之后启动的实例请求激活窗口，脚本通过同一套接字搜索、下载和打开文件
+    let instance_handler = instance_request_handler(ui.as_weak(), context.database.clone(), context.downloads.clone());
+    let handle = tokio::spawn(single_instance::serve(instance_handler, shutdown.subscribe()));
+    shutdown.register("single-instance", handle);
+
+    // 启动自检：有问题时显示自检对话框，配置文件和端口沿用启动时的检查结果
+    spawn_self_check(ui.as_weak(), config.clone(), config_check.clone(), port_check.clone(), false);
+    ui.on_self_check_requested({
+        let ui_weak = ui.as_weak();
+        let config = config_arc.clone();
+        move || {
+            let config = config.lock().unwrap().clone();
+            spawn_self_check(ui_weak.clone(), config, config_check.clone(), port_check.clone(), true);
+        }
+    });
+
+    // 全局快捷键唤出的快速搜索窗口，快捷键被占用或系统不支持时只记录日志
+    #[cfg(feature = "hotkey")]
+    let _quick_search = if config.quick_search.enabled {
+        let engine = Arc::new(SearchEngine::with_database(config_arc.clone(), context.database.clone()));
+        QuickSearch::setup(engine, context.clipboard.clone(), &config.quick_search, &config.search)
+            .inspect_err(|e| warn!("Quick search disabled: {:#}", e))
+            .ok()
+    } else {
+        None
+    };
 
     // 初始化数据库选择器
-    initialize_database_selector(&ui.as_weak(), database_manager.clone());
+    initialize_database_selector(&ui.as_weak(), &database_manager);
+
+    // 缩略图服务，生成完成后刷新结果列表中的对应行
+    let thumbnail_service = Arc::new(ThumbnailService::new(paths::thumbnail_cache_dir()));
+    thumbnail_service.set_on_ready({
+        let ui_weak = ui.as_weak();
+        move |path| {
+            let ui_weak = ui_weak.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    refresh_thumbnail(&ui, &path);
+                }
+            });
+        }
+    });
+    set_thumbnail_service(thumbnail_service);
+
+    // 状态栏中的Aria2传输统计
+    let _aria2_stat_timer = start_aria2_stat_polling(&ui, aria2_service.clone());
+    let _download_monitor_timer = start_download_monitor(&ui, download_manager.clone());
+    let _job_status_timer = start_job_status_polling(&ui, job_queue.clone());
+    let _quota_timer = start_quota_polling(&ui);
+    if config.update.enabled {
+        check_for_update(&ui, config.update.releases_url.clone());
+    }
+    let _pending_retry_timer = start_pending_retry(
+        &ui,
+        pending_operations,
+        download_manager.clone(),
+        downloader,
+        Duration::from_secs(config.api.offline_retry_secs.max(5)),
+    );
 
     info!("Application initialized, starting main loop");
 
     // 运行应用
     ui.run().context("Failed to run UI application")?;
 
+    // 保存窗口和界面状态
+    {
+        let mut config = config_arc.lock().unwrap();
+        save_ui_state(&ui, &mut config);
+        if let Err(e) = config.save_to_file(&config_path()) {
+            warn!("Failed to save UI state: {}", e);
+        }
+    }
+
+    // 保存Aria2会话，停止后端服务和Aria2进程
+    let aria2_client = aria2_service.lock().unwrap().get_client().cloned();
+    if let Some(client) = aria2_client {
+        if let Err(e) = client.save_session().await {
+            warn!("Failed to save Aria2 session: {}", e);
+        }
+    }
+    shutdown.shutdown(SHUTDOWN_TIMEOUT).await;
+    if let Err(e) = aria2_service.lock().unwrap().stop() {
+        warn!("Failed to stop Aria2 service: {}", e);
+    }
+
     info!("Application shutdown");
     Ok(())
 }
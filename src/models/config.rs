@@ -2,21 +2,46 @@
 //! 
 //! 提供应用程序配置的序列化和反序列化功能
 
+use crate::utils::paths;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::PathBuf;
 use anyhow::{Result, Context};
+use tracing::{info, warn};
 
 /// 数据库配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DatabaseConfig {
     pub db_type: String, // "sqlite", "mysql", etc.
     pub connection_string: String,
     pub name: String, // 数据库显示名称
     pub description: Option<String>, // 数据库描述
+    #[serde(default)]
+    pub download_dir: Option<String>, // 该数据库文件的下载目录，未设置时使用Aria2默认目录
+    #[serde(default)]
+    pub auto_discovered: bool, // 是否由目录扫描发现，重新扫描时只替换这类数据库
+    #[serde(default)]
+    pub credential_id: Option<String>, // 密钥环中保存密码的凭据 ID，连接字符串中不含密码
+    #[serde(default)]
+    pub remote_folder_id: i64, // 获取链接时文件秒传到的网盘文件夹 ID，0 为根目录
+    #[serde(default)]
+    pub remote_folder_path: Option<String>, // 网盘文件夹路径，仅用于显示
+    #[serde(default)]
+    pub path_mappings: Vec<PathMapping>, // 打开文件和显示位置前的路径前缀替换，用于在其他系统上生成的数据库
+}
+
+/// 路径前缀映射，例如 `D:\media` -> `/mnt/media`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathMapping {
+    pub from: String, // 数据库中的路径前缀
+    pub to: String, // 本机上对应的路径
 }
 
 /// 多数据库配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MultiDatabaseConfig {
     pub databases: Vec<DatabaseConfig>,
     pub default_database: usize, // 默认数据库索引
@@ -24,22 +49,468 @@ pub struct MultiDatabaseConfig {
 
 /// Aria2配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Aria2Config {
     pub enabled: bool,
     pub rpc_host: String,
     pub rpc_port: u16,
     pub rpc_secret: Option<String>,
     pub download_dir: String,
+    #[serde(default)]
+    pub speed_schedule: Vec<SpeedLimitRule>, // 按时间段的限速规划
+    #[serde(default)]
+    pub block_insufficient_space: bool, // 下载目录剩余空间不足以保存文件时拒绝提交，默认只提示
+    #[serde(default)]
+    pub max_active_downloads: usize, // 同时提交到Aria2的任务数，其余在应用中排队，0 表示不限制
+}
+
+/// 限速时间段规则
+///
+/// 在 `[start_hour, end_hour)` 时间段内应用 `max_download_limit`，
+/// `start_hour > end_hour` 表示跨越午夜（如 22 点到次日 7 点），`start_hour == end_hour` 表示全天
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedLimitRule {
+    pub start_hour: u8,
+    pub end_hour: u8,
+    pub max_download_limit: String, // Aria2格式，如 "2M"，"0" 表示不限速
+}
+
+impl SpeedLimitRule {
+    /// 判断给定小时是否落在该规则的时间段内
+    pub fn matches(&self, hour: u8) -> bool {
+        if self.start_hour == self.end_hour {
+            true
+        } else if self.start_hour < self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+impl Aria2Config {
+    /// 获取指定小时应用的下载限速，没有匹配规则时返回 None
+    pub fn speed_limit_for_hour(&self, hour: u8) -> Option<&str> {
+        self.speed_schedule
+            .iter()
+            .find(|rule| rule.matches(hour))
+            .map(|rule| rule.max_download_limit.as_str())
+    }
+}
+
+/// 界面主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System, // 跟随操作系统设置
+}
+
+impl Theme {
+    /// 对应 UI 中 AppTheme.mode 的取值
+    pub fn as_index(&self) -> i32 {
+        match self {
+            Self::Light => 0,
+            Self::Dark => 1,
+            Self::System => 2,
+        }
+    }
+
+    /// 从 UI 中 AppTheme.mode 的取值解析，未知值视为跟随系统
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            0 => Self::Light,
+            1 => Self::Dark,
+            _ => Self::System,
+        }
+    }
+}
+
+/// 界面状态配置结构，退出时保存，启动时恢复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub column_widths: Vec<f32>, // 结果列表列宽：名称、大小、修改时间
+    #[serde(default)]
+    pub window_x: Option<i32>, // 窗口位置（逻辑像素），未保存时由系统决定
+    #[serde(default)]
+    pub window_y: Option<i32>,
+    #[serde(default)]
+    pub selected_database: Option<String>, // 上次使用的数据库名称
+    #[serde(default)]
+    pub sort_column: Option<String>, // 上次的排序列
+    #[serde(default = "default_sort_ascending")]
+    pub sort_ascending: bool,
+}
+
+fn default_sort_ascending() -> bool {
+    true
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            column_widths: Vec::new(),
+            window_x: None,
+            window_y: None,
+            selected_database: None,
+            sort_column: None,
+            sort_ascending: true,
+        }
+    }
+}
+
+/// 搜索行为配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64, // 输入防抖间隔（毫秒）
+    #[serde(default = "default_min_query_len")]
+    pub min_query_len: usize, // 最短查询长度（字符数），更短的查询不会执行
+    #[serde(default)]
+    pub diagnostics: bool, // 记录慢查询的执行计划
+    #[serde(default = "default_slow_query_ms")]
+    pub slow_query_ms: u64, // 慢查询阈值（毫秒）
+}
+
+fn default_debounce_ms() -> u64 {
+    300
+}
+
+fn default_min_query_len() -> usize {
+    2
+}
+
+fn default_slow_query_ms() -> u64 {
+    crate::services::diagnostics::DEFAULT_SLOW_QUERY_MS
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: default_debounce_ms(),
+            min_query_len: default_min_query_len(),
+            diagnostics: false,
+            slow_query_ms: default_slow_query_ms(),
+        }
+    }
+}
+
+/// 内置后端服务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    pub host: String, // 绑定地址
+    pub port: u16, // 首选端口
+    pub port_attempts: u16, // 端口被占用时依次尝试的端口数（含首选端口）
+    pub rate_limit_per_minute: u32, // 每个 IP 每分钟允许的请求数，0 表示不限流
+    pub request_logging: bool, // 是否记录每个请求的日志
+    pub token_refresh_secs: u64, // 令牌中没有有效期时的刷新间隔（秒）
+    pub token_url: String, // 开放平台获取访问令牌的接口
+    pub client_id: Option<String>, // 开放平台 client_id，未设置时只从缓存文件读取令牌
+    pub client_secret: Option<String>, // 开放平台 client_secret
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            port_attempts: 10,
+            rate_limit_per_minute: 0,
+            request_logging: true,
+            token_refresh_secs: 1800,
+            token_url: "https://open-api.123pan.com/api/v1/access_token".to_string(),
+            client_id: None,
+            client_secret: None,
+        }
+    }
+}
+
+impl BackendConfig {
+    /// 客户端访问后端服务使用的基础 URL，通配地址改为本机回环地址
+    pub fn base_url(&self, port: u16) -> String {
+        let host = match self.host.as_str() {
+            "0.0.0.0" | "::" | "[::]" => "127.0.0.1",
+            host => host,
+        };
+        format!("http://{}:{}", host, port)
+    }
+
+    /// 开放平台凭据，client_id 和 client_secret 都设置时才返回
+    pub fn client_credentials(&self) -> Option<(String, String)> {
+        match (&self.client_id, &self.client_secret) {
+            (Some(id), Some(secret)) if !id.is_empty() && !secret.is_empty() => {
+                Some((id.clone(), secret.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 后端 API 接口配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiEndpoints {
+    pub base_url: Option<String>, // 后端地址，未设置时使用内置后端服务
+    pub upload_path: String, // 文件上传（秒传）接口路径
+    pub download_path: String, // 下载链接接口路径
+    pub list_path: String, // 文件列表接口路径
+    pub user_info_path: String, // 账户信息（空间用量）接口路径
+    pub share_path: String, // 创建分享接口路径
+    pub share_url_prefix: String, // 分享链接前缀，后接分享 key
+    pub timeout_secs: u64, // 请求超时（秒）
+    pub link_cache_ttl_secs: u64, // 下载链接缓存有效期（秒），0 表示不缓存
+    pub verify_remote_exists: bool, // 复制链接和发送下载前检查文件在网盘中是否存在
+    pub offline_retry_secs: u64, // 网络不可用时暂存的操作的重试间隔（秒）
+}
+
+impl Default for ApiEndpoints {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            upload_path: "/file/upload".to_string(),
+            download_path: "/file/download".to_string(),
+            list_path: "/file/list".to_string(),
+            user_info_path: "/user/info".to_string(),
+            share_path: "/share/create".to_string(),
+            share_url_prefix: "https://www.123pan.com/s/".to_string(),
+            timeout_secs: 30,
+            link_cache_ttl_secs: 600,
+            verify_remote_exists: false,
+            offline_retry_secs: 30,
+        }
+    }
+}
+
+/// 数据库文件扫描配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig {
+    pub directories: Vec<String>, // 扫描 .db 文件的目录，为空时扫描数据目录和当前目录
+    pub max_depth: usize, // 递归深度，0 表示只扫描目录本身
+}
+
+impl ScanConfig {
+    /// 获取实际扫描的目录列表
+    pub fn scan_directories(&self) -> Vec<PathBuf> {
+        if self.directories.is_empty() {
+            vec![paths::data_dir(), PathBuf::from(paths::resolve_path("."))]
+        } else {
+            self.directories.iter().map(PathBuf::from).collect()
+        }
+    }
+}
+
+/// 监视文件夹配置
+///
+/// 放入目录中的 .torrent、.magnet 和 .txt 链接文件会自动提交到Aria2，
+/// 处理后移动到 `submitted` 或 `failed` 子目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchFolderConfig {
+    pub enabled: bool,
+    pub directory: Option<String>, // 监视的目录
+    pub poll_secs: u64, // 扫描间隔（秒）
+}
+
+impl Default for WatchFolderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            poll_secs: 10,
+        }
+    }
+}
+
+/// 外部播放器，在右键菜单中显示为"用 <name> 播放"
+///
+/// 命令模板按空白拆分为程序和参数（可以用引号包含空格），不经过 shell；
+/// `{url}` 替换为下载链接，`{name}` 替换为文件名，模板中没有 `{url}` 时链接追加在最后
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    pub name: String, // 菜单中显示的名称，例如 "mpv"
+    pub command: String, // 命令模板，例如 "mpv --force-media-title={name} {url}"
 }
 
+/// 启动时检查新版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    pub enabled: bool, // 默认关闭，不会在未经同意时访问网络
+    pub releases_url: String, // GitHub 最新发布接口
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            releases_url: "https://api.github.com/repos/bleedingfight/netdisk-db/releases/latest".to_string(),
+        }
+    }
+}
+
+/// 文件大小的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    #[default]
+    Binary, // 1 KB = 1024 B
+    Iec, // 1 KiB = 1024 B
+    Decimal, // 1 kB = 1000 B（SI）
+}
+
+/// 文件大小和日期的区域格式
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocaleConfig {
+    pub language: Option<String>, // 语言区域，例如 "de_DE"，未设置时使用系统语言
+    pub size_units: SizeUnits,
+    pub date_format: Option<String>, // 日期格式（chrono 格式字符串），未设置时按语言区域
+}
+
+/// 桌面通知，可以按事件类型单独关闭
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool, // 总开关
+    pub download_complete: bool, // 下载完成
+    pub indexing_complete: bool, // 文件夹索引完成
+    pub sync_errors: bool, // 网盘同步失败
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            download_complete: true,
+            indexing_complete: true,
+            sync_errors: true,
+        }
+    }
+}
+
+/// 快速搜索窗口
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickSearchConfig {
+    pub enabled: bool, // 是否注册全局快捷键
+    pub hotkey: String, // 唤出快速搜索窗口的全局快捷键，例如 "CmdOrCtrl+Shift+Space"
+}
+
+impl Default for QuickSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hotkey: "CmdOrCtrl+Shift+Space".to_string(),
+        }
+    }
+}
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty, // 便于阅读的多字段文本
+    Compact, // 单行文本
+    Json, // 每行一个 JSON 对象，便于日志系统采集
+}
+
+/// 日志配置
+///
+/// 设置了环境变量 `RUST_LOG` 时以环境变量为准，`--verbose` 将本程序的日志级别提高到 debug
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub level: String, // 默认日志级别
+    pub modules: BTreeMap<String, String>, // 模块 -> 日志级别，例如 "actix_web" -> "warn"
+    pub format: LogFormat, // 控制台输出格式
+    pub file: bool, // 是否同时写入日志目录下按天滚动的日志文件
+    pub max_files: usize, // 最多保留的日志文件数
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            format: LogFormat::default(),
+            file: true,
+            max_files: 7,
+        }
+    }
+}
+
+/// 配置文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// 根据文件扩展名判断格式，未知扩展名按 JSON 处理
+    pub fn from_path(path: &str) -> Self {
+        match std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref()
+        {
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// 当前配置文件版本
+///
+/// 1: 早期版本，只有单个 `database`（或顶层 `database_path`），没有版本号
+/// 2: 增加 `multi_database` 和 `aria2`，开始记录 `config_version`
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
 /// 应用程序主配置结构
+///
+/// 缺失的字段使用默认值，旧版本的配置文件在新增字段后仍可加载
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    pub config_version: u32, // 配置文件版本，加载时按版本逐级升级
     pub database: DatabaseConfig, // 当前使用的数据库配置
     pub multi_database: MultiDatabaseConfig, // 多数据库配置
     pub aria2: Aria2Config, // Aria2下载配置
     pub window_width: u32,
     pub window_height: u32,
+    #[serde(default)]
+    pub ui: UiConfig, // 界面状态
+    #[serde(default)]
+    pub theme: Theme, // 界面主题
+    #[serde(default)]
+    pub search: SearchConfig, // 搜索行为
+    #[serde(default)]
+    pub scan: ScanConfig, // 数据库文件扫描
+    #[serde(default)]
+    pub backend: BackendConfig, // 内置后端服务
+    #[serde(default)]
+    pub api: ApiEndpoints, // 后端 API 接口
+    #[serde(default)]
+    pub watch_folder: WatchFolderConfig, // 自动提交下载的监视文件夹
+    #[serde(default)]
+    pub logging: LoggingConfig, // 日志
+    #[serde(default)]
+    pub update: UpdateConfig, // 新版本检查
+    #[serde(default)]
+    pub notifications: NotificationConfig, // 桌面通知
+    #[serde(default)]
+    pub quick_search: QuickSearchConfig, // 全局快捷键唤出的快速搜索窗口
+    #[serde(default)]
+    pub locale: LocaleConfig, // 文件大小和日期格式
+    #[serde(default)]
+    pub players: Vec<PlayerConfig>, // 播放视频和音频的外部播放器
 }
 
 impl Default for DatabaseConfig {
@@ -49,6 +520,12 @@ impl Default for DatabaseConfig {
             connection_string: "file_search.db".to_string(),
             name: "Default Database".to_string(),
             description: Some("Default file search database".to_string()),
+            download_dir: None,
+            auto_discovered: false,
+            credential_id: None,
+            remote_folder_id: 0,
+            remote_folder_path: None,
+            path_mappings: Vec::new(),
         }
     }
 }
@@ -60,7 +537,10 @@ impl Default for Aria2Config {
             rpc_host: "127.0.0.1".to_string(),
             rpc_port: 6800,
             rpc_secret: None,
-            download_dir: "./downloads".to_string(),
+            download_dir: paths::downloads_dir().to_string_lossy().to_string(),
+            speed_schedule: Vec::new(),
+            block_insufficient_space: false,
+            max_active_downloads: 0,
         }
     }
 }
@@ -68,7 +548,11 @@ impl Default for Aria2Config {
 impl Default for MultiDatabaseConfig {
     fn default() -> Self {
         Self {
-            databases: vec![DatabaseConfig::default()],
+            // 内置的默认数据库在扫描到数据库文件后被替换
+            databases: vec![DatabaseConfig {
+                auto_discovered: true,
+                ..DatabaseConfig::default()
+            }],
             default_database: 0,
         }
     }
@@ -81,38 +565,189 @@ impl Default for AppConfig {
         let aria2_config = Aria2Config::default();
         
         Self {
+            config_version: CURRENT_CONFIG_VERSION,
             database: default_db,
             multi_database: multi_db,
             aria2: aria2_config,
             window_width: 800,
             window_height: 600,
+            ui: UiConfig::default(),
+            theme: Theme::default(),
+            search: SearchConfig::default(),
+            scan: ScanConfig::default(),
+            backend: BackendConfig::default(),
+            api: ApiEndpoints::default(),
+            watch_folder: WatchFolderConfig::default(),
+            logging: LoggingConfig::default(),
+            update: UpdateConfig::default(),
+            notifications: NotificationConfig::default(),
+            quick_search: QuickSearchConfig::default(),
+            locale: LocaleConfig::default(),
+            players: Vec::new(),
         }
     }
 }
 
 impl AppConfig {
-    /// 从文件加载配置
+    /// 从文件加载配置，按扩展名识别格式（.toml 为 TOML，其余为 JSON）
+    ///
+    /// 旧版本的配置会在内存中升级到当前版本
     pub fn load_from_file(path: &str) -> Result<Self> {
+        Self::load_and_migrate(path).map(|(config, _)| config)
+    }
+
+    /// 从文件加载配置并升级到当前版本
+    ///
+    /// # Returns
+    /// * `Result<(AppConfig, bool)>` - 配置以及是否进行了升级（需要重新保存）
+    pub fn load_and_migrate(path: &str) -> Result<(Self, bool)> {
         let content = fs::read_to_string(path)
             .context("Failed to read config file")?;
         
-        let config: AppConfig = serde_json::from_str(&content)
+        let mut value: serde_json::Value = match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => {
+                let value: toml::Value = toml::from_str(&content)
+                    .context("Failed to parse TOML config file")?;
+                serde_json::to_value(value).context("Failed to convert TOML config")?
+            }
+            ConfigFormat::Json => serde_json::from_str(&content)
+                .context("Failed to parse config file")?,
+        };
+        
+        let migrated = Self::migrate(&mut value);
+        let mut config: AppConfig = serde_json::from_value(value)
             .context("Failed to parse config file")?;
+        config.map_paths(paths::resolve_path);
         
-        Ok(config)
+        Ok((config, migrated))
     }
 
-    /// 保存配置到文件
+    /// 加载配置，配置文件损坏时使用上次保存前的备份
+    ///
+    /// # Returns
+    /// * `Result<(AppConfig, bool)>` - 配置以及是否需要重新保存（升级或从备份恢复）
+    pub fn load_with_fallback(path: &str) -> Result<(Self, bool)> {
+        let error = match Self::load_and_migrate(path) {
+            Ok(loaded) => return Ok(loaded),
+            Err(e) => e,
+        };
+        let backup = Self::backup_path(path);
+        if !std::path::Path::new(&backup).exists() {
+            return Err(error);
+        }
+        warn!("Failed to load config file {}: {:#}, using backup {}", path, error, backup);
+        let (config, _) = Self::load_and_migrate(&backup)
+            .with_context(|| format!("Failed to load config backup {}", backup))?;
+        Ok((config, true))
+    }
+
+    /// 配置文件的备份路径，每次保存前写入
+    pub fn backup_path(path: &str) -> String {
+        format!("{}.bak", path)
+    }
+
+    /// 将旧版本的配置逐级升级到当前版本
+    ///
+    /// # Returns
+    /// * `bool` - 是否进行了升级
+    pub fn migrate(value: &mut serde_json::Value) -> bool {
+        let Some(object) = value.as_object_mut() else {
+            return false;
+        };
+        
+        let version = object
+            .get("config_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1) as u32;
+        if version >= CURRENT_CONFIG_VERSION {
+            return false;
+        }
+        
+        if version < 2 {
+            // 早期版本用顶层 database_path 指定数据库文件
+            if let Some(path) = object.remove("database_path").or_else(|| object.remove("db_path")) {
+                let mut database = serde_json::to_value(DatabaseConfig::default())
+                    .unwrap_or_default();
+                database["connection_string"] = path;
+                object.entry("database").or_insert(database);
+            }
+            
+            // 单数据库配置转为多数据库列表
+            if !object.contains_key("multi_database") {
+                if let Some(database) = object.get("database").cloned() {
+                    object.insert(
+                        "multi_database".to_string(),
+                        serde_json::json!({ "databases": [database], "default_database": 0 }),
+                    );
+                }
+            }
+        }
+        
+        object.insert(
+            "config_version".to_string(),
+            serde_json::Value::from(CURRENT_CONFIG_VERSION),
+        );
+        info!("Migrated config from version {} to {}", version, CURRENT_CONFIG_VERSION);
+        true
+    }
+
+    /// 保存配置到文件，按扩展名选择格式（.toml 为 TOML，其余为 JSON）
+    ///
+    /// 保存前将现有的有效配置复制到 `backup_path`，新内容先写入临时文件再替换，
+    /// 写入中断不会留下不完整的配置文件
     pub fn save_to_file(&self, path: &str) -> Result<()> {
-        let content = serde_json::to_string_pretty(self)
-            .context("Failed to serialize config")?;
+        let mut saved = self.clone();
+        saved.map_paths(paths::portable_path);
+        let content = match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => toml::to_string_pretty(&saved)
+                .context("Failed to serialize config as TOML")?,
+            ConfigFormat::Json => serde_json::to_string_pretty(&saved)
+                .context("Failed to serialize config")?,
+        };
+        
+        // 已损坏的配置不覆盖备份
+        if Self::load_and_migrate(path).is_ok() {
+            if let Err(e) = fs::copy(path, Self::backup_path(path)) {
+                warn!("Failed to back up config file {}: {}", path, e);
+            }
+        }
         
-        fs::write(path, content)
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, content)
             .context("Failed to write config file")?;
+        fs::rename(&temp_path, path)
+            .context("Failed to replace config file")?;
         
         Ok(())
     }
 
+    /// 转换配置中所有的文件和目录路径
+    ///
+    /// 加载时转换为实际路径，保存时转换为配置中的形式，便携模式的路径处理都经过这里
+    ///
+    /// # Arguments
+    /// * `convert` - 路径转换函数，例如 `paths::resolve_path`
+    pub fn map_paths(&mut self, convert: impl Fn(&str) -> String) {
+        let convert_database = |db: &mut DatabaseConfig| {
+            // 只有 SQLite 的连接字符串是文件路径
+            if db.db_type == "sqlite" {
+                db.connection_string = convert(&db.connection_string);
+            }
+            if let Some(dir) = db.download_dir.as_mut() {
+                *dir = convert(dir);
+            }
+        };
+        convert_database(&mut self.database);
+        self.multi_database.databases.iter_mut().for_each(convert_database);
+        self.aria2.download_dir = convert(&self.aria2.download_dir);
+        for dir in &mut self.scan.directories {
+            *dir = convert(dir);
+        }
+        if let Some(dir) = self.watch_folder.directory.as_mut() {
+            *dir = convert(dir);
+        }
+    }
+
     /// 切换到指定数据库
     pub fn switch_database(&mut self, index: usize) -> Result<()> {
         if index >= self.multi_database.databases.len() {
@@ -130,6 +765,61 @@ impl AppConfig {
         self.multi_database.default_database
     }
 
+    /// 按名称查找数据库索引
+    pub fn database_index_by_name(&self, name: &str) -> Option<usize> {
+        self.multi_database
+            .databases
+            .iter()
+            .position(|db| db.name == name)
+    }
+
+    /// 用新扫描到的数据库替换自动发现的数据库，保留手动添加的数据库
+    ///
+    /// 当前数据库仍在列表中时保持选中，否则选中第一个数据库
+    pub fn replace_discovered_databases(&mut self, discovered: Vec<DatabaseConfig>) {
+        let current_name = self.database.name.clone();
+        let databases = &mut self.multi_database.databases;
+        let previous: Vec<DatabaseConfig> =
+            databases.iter().filter(|db| db.auto_discovered).cloned().collect();
+        databases.retain(|db| !db.auto_discovered);
+        databases.extend(discovered.into_iter().map(|db| {
+            // 重新发现的数据库保留之前设置的网盘文件夹
+            let (remote_folder_id, remote_folder_path) = previous
+                .iter()
+                .find(|old| old.connection_string == db.connection_string)
+                .map(|old| (old.remote_folder_id, old.remote_folder_path.clone()))
+                .unwrap_or((db.remote_folder_id, db.remote_folder_path.clone()));
+            DatabaseConfig {
+                auto_discovered: true,
+                remote_folder_id,
+                remote_folder_path,
+                ..db
+            }
+        }));
+
+        if databases.is_empty() {
+            return;
+        }
+        let index = self.database_index_by_name(&current_name).unwrap_or(0);
+        self.multi_database.default_database = index;
+        self.database = self.multi_database.databases[index].clone();
+    }
+
+    /// 设置当前数据库获取链接时使用的网盘文件夹
+    ///
+    /// # Arguments
+    /// * `folder_id` - 网盘文件夹 ID，0 为根目录
+    /// * `folder_path` - 网盘文件夹路径，仅用于显示
+    pub fn set_remote_folder(&mut self, folder_id: i64, folder_path: Option<String>) {
+        self.database.remote_folder_id = folder_id;
+        self.database.remote_folder_path = folder_path;
+        let index = self.current_database_index();
+        if let Some(db) = self.multi_database.databases.get_mut(index) {
+            db.remote_folder_id = self.database.remote_folder_id;
+            db.remote_folder_path = self.database.remote_folder_path.clone();
+        }
+    }
+
     /// 获取数据库列表
     pub fn database_list(&self) -> &Vec<DatabaseConfig> {
         &self.multi_database.databases
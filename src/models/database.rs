@@ -2,8 +2,10 @@
 //!
 //! 定义数据库操作的通用接口和文件记录数据结构
 
+use crate::utils::filetype::FileCategory;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// 文件记录数据结构
 // #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +46,302 @@ pub struct FileRecord {
 //     }
 // }
 
+/// 搜索结果排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortColumn {
+    Name,
+    Path,
+    Size,
+    ModifiedTime,
+    FileType,
+}
+
+impl SortColumn {
+    /// 从列名解析排序字段（与 UI 中的列标识一致）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Self::Name),
+            "path" => Some(Self::Path),
+            "size" => Some(Self::Size),
+            "modified_time" => Some(Self::ModifiedTime),
+            "file_type" => Some(Self::FileType),
+            _ => None,
+        }
+    }
+
+    /// 对应的数据库列名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Path => "path",
+            Self::Size => "size",
+            Self::ModifiedTime => "modified_time",
+            Self::FileType => "file_type",
+        }
+    }
+}
+
+/// 搜索结果排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOrder {
+    pub column: SortColumn,
+    pub ascending: bool,
+}
+
+impl SortOrder {
+    /// 按排序方式对内存中的记录排序
+    pub fn sort_records(&self, records: &mut [FileRecord]) {
+        records.sort_by(|a, b| {
+            let ordering = match self.column {
+                SortColumn::Name => a.name.cmp(&b.name),
+                SortColumn::Path => a.path.cmp(&b.path),
+                SortColumn::Size => a.size.cmp(&b.size),
+                SortColumn::ModifiedTime => a.modified_time.cmp(&b.modified_time),
+                SortColumn::FileType => a.file_type.cmp(&b.file_type),
+            };
+            if self.ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+}
+
+/// 搜索结果分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Folder,   // 按父目录
+    FileType, // 按文件类型
+}
+
+impl GroupBy {
+    /// 从 UI 中分组下拉框的索引解析，0 表示不分组
+    pub fn from_index(index: i32) -> Option<Self> {
+        match index {
+            1 => Some(Self::Folder),
+            2 => Some(Self::FileType),
+            _ => None,
+        }
+    }
+
+    /// 计算记录所属的分组名
+    pub fn key(&self, record: &FileRecord) -> String {
+        match self {
+            Self::Folder => parent_folder(&record.path),
+            Self::FileType if record.file_type.is_empty() => "(未知类型)".to_string(),
+            Self::FileType => record.file_type.clone(),
+        }
+    }
+}
+
+/// 文件所在的文件夹，没有父目录时为 `/`
+pub fn parent_folder(path: &str) -> String {
+    std::path::Path::new(path)
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .filter(|parent| !parent.is_empty())
+        .unwrap_or_else(|| "/".to_string())
+}
+
+/// 一组搜索结果
+#[derive(Debug, Clone)]
+pub struct RecordGroup {
+    pub key: String,
+    pub records: Vec<FileRecord>,
+}
+
+/// 将记录按分组名归类，分组按名称排序，组内保持原有顺序
+///
+/// # Arguments
+/// * `records` - 搜索结果
+/// * `group_by` - 分组方式
+pub fn group_records(records: Vec<FileRecord>, group_by: GroupBy) -> Vec<RecordGroup> {
+    let mut groups: std::collections::BTreeMap<String, Vec<FileRecord>> =
+        std::collections::BTreeMap::new();
+    for record in records {
+        groups.entry(group_by.key(&record)).or_default().push(record);
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, records)| RecordGroup { key, records })
+        .collect()
+}
+
+/// 一条重命名，记录新旧名称和路径，交换新旧值即可撤销
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameEntry {
+    pub id: i64,
+    pub old_name: String,
+    pub old_path: String,
+    pub new_name: String,
+    pub new_path: String,
+}
+
+impl RenameEntry {
+    /// 撤销该重命名的反向操作
+    pub fn reversed(&self) -> Self {
+        Self {
+            id: self.id,
+            old_name: self.new_name.clone(),
+            old_path: self.new_path.clone(),
+            new_name: self.old_name.clone(),
+            new_path: self.old_path.clone(),
+        }
+    }
+}
+
+/// 数据库维护操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaintenanceOperation {
+    /// 压缩数据库文件（VACUUM）
+    Vacuum,
+    /// 更新查询优化器统计信息（ANALYZE）
+    Analyze,
+    /// 重建索引（REINDEX）
+    Reindex,
+    /// 删除路径位于已移除目录下的记录
+    CleanupOrphans { roots: Vec<String> },
+}
+
+impl MaintenanceOperation {
+    /// 不需要参数的常规维护，依次执行
+    pub const ROUTINE: [MaintenanceOperation; 3] = [Self::Analyze, Self::Reindex, Self::Vacuum];
+
+    /// 界面和命令行显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Vacuum => "压缩数据库",
+            Self::Analyze => "更新统计信息",
+            Self::Reindex => "重建索引",
+            Self::CleanupOrphans { .. } => "清理孤立记录",
+        }
+    }
+}
+
+/// 比较两个数据库时匹配记录的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareKey {
+    /// 按路径匹配，路径相同但 etag 或大小不同的记录视为已修改
+    #[default]
+    Path,
+    /// 按 etag 匹配，etag 相同但路径不同的记录视为已移动
+    Etag,
+}
+
+impl CompareKey {
+    /// 根据界面选择创建：0 按路径，1 按 etag
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => Self::Etag,
+            _ => Self::Path,
+        }
+    }
+}
+
+/// 两个数据库的比较结果
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseDiff {
+    pub only_left: Vec<FileRecord>,               // 只在左侧数据库中的记录
+    pub only_right: Vec<FileRecord>,              // 只在右侧数据库中的记录
+    pub changed: Vec<(FileRecord, FileRecord)>,   // 两侧都有但内容（按路径）或路径（按 etag）不同
+    pub left_latest: i64,                         // 左侧最新的修改时间
+    pub right_latest: i64,
+}
+
+impl DatabaseDiff {
+    /// 两个数据库的记录是否一致
+    pub fn is_identical(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty() && self.changed.is_empty()
+    }
+
+    /// 按最新修改时间判断哪个数据库更新
+    ///
+    /// # Returns
+    /// * `Option<Ordering>` - `Greater` 表示左侧更新，两侧相同或没有记录时为 None
+    pub fn newer(&self) -> Option<std::cmp::Ordering> {
+        match self.left_latest.cmp(&self.right_latest) {
+            std::cmp::Ordering::Equal => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// 只读查询的结果
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,           // 每个值格式化为文本，NULL 为空字符串
+    pub records: Option<Vec<FileRecord>>, // 结果列包含文件记录的全部字段时转换后的记录
+    pub truncated: bool,                  // 超过行数限制，只返回了前面的行
+}
+
+/// 搜索选项
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchOptions {
+    /// 排序方式，None 表示按数据库默认顺序
+    pub sort: Option<SortOrder>,
+    /// 搜索范围，只返回该文件夹下的文件，None 表示搜索全部
+    pub scope: Option<String>,
+    /// 文件类型筛选，只返回属于其中任一分类的文件，为空表示不筛选
+    pub categories: Vec<FileCategory>,
+}
+
+impl SearchOptions {
+    /// 文件是否满足类型筛选
+    pub fn matches_categories(&self, record: &FileRecord) -> bool {
+        self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|category| category.matches(&record.file_type, &record.name))
+    }
+
+    /// 文件是否在搜索范围内
+    pub fn matches_scope(&self, record: &FileRecord) -> bool {
+        self.scope_prefix().map_or(true, |scope| record.path.starts_with(&scope))
+    }
+
+    /// 文件是否同时满足搜索范围和类型筛选，供没有在查询中筛选的数据库实现使用
+    pub fn matches(&self, record: &FileRecord) -> bool {
+        self.matches_scope(record) && self.matches_categories(record)
+    }
+
+    /// 搜索范围的路径前缀，以分隔符结尾，避免 `/movies` 匹配到 `/movies2`
+    pub fn scope_prefix(&self) -> Option<String> {
+        let scope = self.scope.as_deref().map(str::trim).filter(|scope| !scope.is_empty())?;
+        if scope.ends_with(['/', '\\']) {
+            Some(scope.to_string())
+        } else {
+            let separator = if scope.contains('\\') && !scope.contains('/') { '\\' } else { '/' };
+            Some(format!("{}{}", scope, separator))
+        }
+    }
+}
+
+/// 统计匹配数后按随机偏移读取一条记录，`Database::random_file` 的默认实现
+///
+/// 按 id 顺序取第 N 条，需要跳过前面的记录，大数据库上不加条件时应使用索引更友好的实现
+pub fn random_file_by_offset<D: Database + ?Sized>(
+    database: &D,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Option<FileRecord>> {
+    let count = database.count_files(query, options)?;
+    if count == 0 {
+        return Ok(None);
+    }
+    let options = SearchOptions {
+        sort: None,
+        ..options.clone()
+    };
+    let offset = fastrand::usize(..count);
+    Ok(database.search_files_paged(query, &options, offset, 1)?.into_iter().next())
+}
+
 /// 数据库操作通用接口
 ///
 /// 实现此接口可以为不同的数据库提供支持
@@ -70,6 +368,70 @@ pub trait Database: Send + Sync {
         self.search_files(query)
     }
 
+    /// 按 ID 精确查找文件记录
+    ///
+    /// # Arguments
+    /// * `id` - 记录ID
+    ///
+    /// # Returns
+    /// * `Result<Option<FileRecord>>` - 记录不存在时返回 None，不支持时默认返回错误
+    fn find_file(&self, id: i64) -> Result<Option<FileRecord>> {
+        anyhow::bail!("Database does not support looking up records by id ({})", id)
+    }
+
+    /// 统计匹配的文件数量
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 匹配的记录总数
+    fn count_files(&self, query: &str, options: &SearchOptions) -> Result<usize> {
+        // 默认实现：基于普通搜索结果计数
+        let records = self.search_files(query)?;
+        Ok(records.iter().filter(|record| options.matches(record)).count())
+    }
+
+    /// 分页搜索文件
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项（排序等）
+    /// * `offset` - 起始记录偏移
+    /// * `limit` - 本页最多返回的记录数
+    ///
+    /// # Returns
+    /// * `Result<Vec<FileRecord>>` - 当前页的搜索结果
+    fn search_files_paged(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FileRecord>> {
+        // 默认实现：在普通搜索结果上筛选、排序并切片
+        let mut records = self.search_files(query)?;
+        records.retain(|record| options.matches(record));
+        if let Some(sort) = options.sort {
+            sort.sort_records(&mut records);
+        }
+        Ok(records.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// 随机选取一条匹配的记录，用于重新发现很久没看过的文件
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词，为空时不限制
+    /// * `options` - 搜索范围和类型筛选，排序被忽略
+    ///
+    /// # Returns
+    /// * `Result<Option<FileRecord>>` - 没有匹配的记录时返回 None
+    fn random_file(&self, query: &str, options: &SearchOptions) -> Result<Option<FileRecord>> {
+        // 默认实现：统计匹配数后按随机偏移读取一条，每条记录被选中的概率相同
+        random_file_by_offset(self, query, options)
+    }
+
     /// 获取支持的搜索字段
     ///
     /// # Returns
@@ -78,6 +440,144 @@ pub trait Database: Send + Sync {
         vec!["name".to_string(), "path".to_string()]
     }
 
+    /// 数据库是否可写，只读数据库不提供删除等写操作
+    fn is_writable(&self) -> bool {
+        false
+    }
+
+    /// 批量插入文件记录
+    ///
+    /// # Arguments
+    /// * `records` - 文件记录，`id` 字段会被忽略
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 插入的记录数，只读数据库默认返回错误
+    fn insert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        anyhow::bail!("Database does not support inserting records ({} records)", records.len())
+    }
+
+    /// 从索引中删除文件记录
+    ///
+    /// # Arguments
+    /// * `id` - 记录ID
+    ///
+    /// # Returns
+    /// * `Result<()>` - 只读数据库默认返回错误
+    fn delete_file(&self, id: i64) -> Result<()> {
+        anyhow::bail!("Database does not support deleting records (id: {})", id)
+    }
+
+    /// 按 ID 批量删除文件记录
+    ///
+    /// # Arguments
+    /// * `ids` - 记录ID
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 删除的记录数，只读数据库默认返回错误
+    fn delete_files(&self, ids: &[i64]) -> Result<usize> {
+        anyhow::bail!("Database does not support deleting records ({} records)", ids.len())
+    }
+
+    /// 插入记录并保留原 ID，用于撤销删除后恢复原样
+    ///
+    /// # Arguments
+    /// * `records` - 文件记录，`id` 为 0 时分配新的 ID
+    /// * `verified` - 按路径恢复的本地文件校验结果
+    ///
+    /// # Returns
+    /// * `Result<Vec<i64>>` - 与 `records` 顺序一致的记录 ID，只读数据库默认返回错误
+    fn restore_files(&self, records: &[FileRecord], _verified: &HashMap<String, bool>) -> Result<Vec<i64>> {
+        anyhow::bail!("Database does not support restoring records ({} records)", records.len())
+    }
+
+    /// 获取路径前缀下所有记录的 ETag，用于增量同步
+    ///
+    /// # Arguments
+    /// * `prefix` - 路径前缀
+    ///
+    /// # Returns
+    /// * `Result<HashMap<String, String>>` - 路径到 ETag 的映射，不支持时默认返回错误
+    fn etags_under(&self, prefix: &str) -> Result<HashMap<String, String>> {
+        anyhow::bail!("Database does not support listing etags ({})", prefix)
+    }
+
+    /// 按路径插入或更新文件记录
+    ///
+    /// # Arguments
+    /// * `records` - 文件记录，路径已存在时更新该记录，`id` 字段会被忽略
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 写入的记录数，只读数据库默认返回错误
+    fn upsert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        anyhow::bail!("Database does not support updating records ({} records)", records.len())
+    }
+
+    /// 按路径删除文件记录
+    ///
+    /// # Arguments
+    /// * `paths` - 要删除的文件路径
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 删除的记录数，只读数据库默认返回错误
+    fn delete_paths(&self, paths: &[String]) -> Result<usize> {
+        anyhow::bail!("Database does not support deleting records ({} paths)", paths.len())
+    }
+
+    /// 重命名文件记录，只修改索引中的名称和路径
+    ///
+    /// # Arguments
+    /// * `renames` - 重命名列表，按 `id` 匹配记录并更新为新的名称和路径，不检查原路径
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 更新的记录数，只读数据库默认返回错误
+    fn rename_files(&self, renames: &[RenameEntry]) -> Result<usize> {
+        anyhow::bail!("Database does not support renaming records ({} records)", renames.len())
+    }
+
+    /// 保存本地文件校验结果
+    ///
+    /// # Arguments
+    /// * `results` - 文件路径以及本地文件哈希是否与 etag 一致
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 更新的记录数，只读数据库默认返回错误
+    fn set_verified(&self, results: &[(String, bool)]) -> Result<usize> {
+        anyhow::bail!("Database does not support saving verification results ({} records)", results.len())
+    }
+
+    /// 查询本地文件校验结果
+    ///
+    /// # Arguments
+    /// * `paths` - 文件路径
+    ///
+    /// # Returns
+    /// * `Result<HashMap<String, bool>>` - 已校验文件的结果，未校验的文件不在其中
+    fn verification_status(&self, _paths: &[String]) -> Result<HashMap<String, bool>> {
+        Ok(HashMap::new())
+    }
+
+    /// 执行维护操作
+    ///
+    /// # Arguments
+    /// * `operation` - 维护操作
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 清理孤立记录时为删除的记录数，其他操作为 0
+    fn run_maintenance(&self, operation: &MaintenanceOperation) -> Result<usize> {
+        anyhow::bail!("Database does not support maintenance: {}", operation.label())
+    }
+
+    /// 执行只读查询，用于高级用户的 SQL 控制台
+    ///
+    /// 实现必须拒绝会修改数据库的语句
+    ///
+    /// # Arguments
+    /// * `sql` - 单条查询语句
+    /// * `limit` - 最多返回的行数
+    fn query_read_only(&self, sql: &str, _limit: usize) -> Result<QueryResult> {
+        anyhow::bail!("Database does not support custom queries: {}", sql)
+    }
+
     /// 初始化数据库
     ///
     /// 创建必要的表结构和索引
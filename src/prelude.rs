@@ -8,14 +8,20 @@ pub use crate::{
         config::{AppConfig, DatabaseConfig},
         database::{Database, FileRecord},
     },
-    services::database_manager::DatabaseManager,
+    services::database_manager::{DatabaseHandle, DatabaseManager},
+    services::search_engine::SearchEngine,
+    utils::common::{get_timestamp, format_file_size},
+};
+
+// 界面相关的类型和函数
+#[cfg(feature = "gui")]
+pub use crate::{
     views::ui::{file_records_to_model, database_list_to_string_model, AppWindow},
     controllers::handlers::{
         handle_search_request,
         handle_database_changed,
         initialize_database_selector,
     },
-    utils::common::{get_timestamp, format_file_size},
 };
 
 // 重新导出错误处理类型
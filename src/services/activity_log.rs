@@ -0,0 +1,193 @@
+//! 活动记录服务 - 记录打开、复制链接和发送下载的文件
+//!
+//! 记录保存在独立的 SQLite 数据库中，用于"最近"视图快速找回文件
+
+use crate::models::database::FileRecord;
+use anyhow::{Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use std::sync::Arc;
+use tracing::debug;
+
+/// 活动类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Opened,
+    CopiedLink,
+    SentToAria2,
+}
+
+impl ActivityKind {
+    /// 数据库中保存的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Opened => "opened",
+            Self::CopiedLink => "copied",
+            Self::SentToAria2 => "aria2",
+        }
+    }
+
+    /// 从数据库中保存的名称解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "opened" => Some(Self::Opened),
+            "copied" => Some(Self::CopiedLink),
+            "aria2" => Some(Self::SentToAria2),
+            _ => None,
+        }
+    }
+
+    /// 界面显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Opened => "打开",
+            Self::CopiedLink => "复制链接",
+            Self::SentToAria2 => "下载",
+        }
+    }
+}
+
+/// 一条活动记录
+#[derive(Debug, Clone)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub record: FileRecord,
+    pub database: String, // 文件所在的数据库名称
+    pub timestamp: i64,
+}
+
+/// 活动记录
+pub struct ActivityLog {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+/// 共享的活动记录实例
+pub type SharedActivityLog = Arc<ActivityLog>;
+
+impl ActivityLog {
+    /// 打开活动记录数据库，不存在时创建
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    pub fn open(path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path);
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .context("Failed to create activity log connection pool")?;
+
+        pool.get()
+            .context("Failed to get connection from pool")?
+            .execute(
+                "CREATE TABLE IF NOT EXISTS activity (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    kind TEXT NOT NULL,
+                    record_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    etag TEXT NOT NULL,
+                    modified_time INTEGER NOT NULL,
+                    file_type TEXT NOT NULL,
+                    database TEXT NOT NULL,
+                    timestamp INTEGER NOT NULL
+                )",
+                [],
+            )
+            .context("Failed to create activity table")?;
+
+        Ok(Self { pool })
+    }
+
+    /// 记录一次活动
+    ///
+    /// # Arguments
+    /// * `kind` - 活动类型
+    /// * `record` - 相关的文件记录
+    /// * `database` - 文件所在的数据库名称
+    pub fn record(&self, kind: ActivityKind, record: &FileRecord, database: &str) -> Result<()> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        conn.execute(
+            "INSERT INTO activity
+                (kind, record_id, name, path, size, etag, modified_time, file_type, database, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                kind.as_str(),
+                record.id,
+                record.name,
+                record.path,
+                record.size as i64,
+                record.etag,
+                record.modified_time,
+                record.file_type,
+                database,
+                chrono::Local::now().timestamp()
+            ],
+        )
+        .context("Failed to record activity")?;
+
+        debug!("Recorded activity {:?} for {}", kind, record.path);
+        Ok(())
+    }
+
+    /// 获取最近的活动记录，按时间倒序
+    ///
+    /// # Arguments
+    /// * `limit` - 最多返回的记录数
+    pub fn recent(&self, limit: usize) -> Result<Vec<ActivityEntry>> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT kind, record_id, name, path, size, etag, modified_time, file_type, database, timestamp
+                 FROM activity
+                 ORDER BY timestamp DESC, id DESC
+                 LIMIT ?1",
+            )
+            .context("Failed to prepare activity query")?;
+
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                let kind: String = row.get(0)?;
+                let size: i64 = row.get(4)?;
+                Ok((
+                    kind,
+                    FileRecord {
+                        id: row.get(1)?,
+                        name: row.get(2)?,
+                        path: row.get(3)?,
+                        size: size.max(0) as u64,
+                        etag: row.get(5)?,
+                        modified_time: row.get(6)?,
+                        file_type: row.get(7)?,
+                    },
+                    row.get::<_, String>(8)?,
+                    row.get::<_, i64>(9)?,
+                ))
+            })
+            .context("Failed to query activity")?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (kind, record, database, timestamp) = row.context("Failed to map activity")?;
+            if let Some(kind) = ActivityKind::from_name(&kind) {
+                entries.push(ActivityEntry {
+                    kind,
+                    record,
+                    database,
+                    timestamp,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+}
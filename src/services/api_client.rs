@@ -0,0 +1,430 @@
+//! 后端 API 客户端 - 调用网盘后端的上传和下载接口
+//!
+//! 接口地址、路径和超时来自配置中的 `api` 部分，
+//! 未配置地址时使用内置后端服务实际绑定的地址
+
+use crate::models::config::ApiEndpoints;
+use crate::services::metrics;
+use netdisk_core::responses::prelude::{DownloadUrlResponse, FileQuery, UploadFileResponse};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// 获取下载链接过程中的错误
+#[derive(Debug, Error)]
+pub enum LinkError {
+    #[error("无效的文件路径: {0}")]
+    InvalidPath(String),
+    #[error("无效的文件大小: {0}")]
+    InvalidSize(String),
+    #[error("HTTP 请求失败: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("HTTP 请求失败，状态码: {status}，错误信息: {body}")]
+    Status { status: u16, body: String },
+    #[error("响应解析失败: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("业务处理失败: code={code}, message={message}")]
+    Business { code: i64, message: String },
+    #[error("响应数据缺失: {0}")]
+    MissingData(&'static str),
+    #[error("网盘中不存在该文件: {0}")]
+    RemoteMissing(String),
+    #[error("复制到剪切板失败: {0}")]
+    Clipboard(String),
+}
+
+impl LinkError {
+    /// 是否为后端或网盘暂时无法访问导致的错误（连接失败、超时或网关错误），
+    /// 这类请求可以在网络恢复后重试
+    pub fn is_offline(&self) -> bool {
+        match self {
+            Self::Http(e) => e.is_connect() || e.is_timeout(),
+            Self::Status { status, .. } => matches!(status, 502..=504),
+            _ => false,
+        }
+    }
+}
+
+/// 未设置共享客户端时使用的后端地址
+const DEFAULT_BASE_URL: &str = "http://127.0.0.1:8080";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")] // 关键！将 Rust 的 snake_case 映射到 JSON 的 camelCase
+pub struct UploadFileItemPayload {
+    #[serde(alias = "parentFileID")]
+    pub parent_file_id: i64,
+    pub filename: String, // 字段类型必须确定，不能是泛型 T
+    pub etag: String,
+    pub size: u64,
+}
+
+/// 文件列表中的文件或文件夹
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteFile {
+    #[serde(alias = "fileID")]
+    pub file_id: i64,
+    pub filename: String,
+    #[serde(rename = "type")]
+    pub kind: i32, // 0 文件，1 文件夹
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub etag: String,
+    #[serde(default)]
+    pub trashed: i32, // 非 0 表示在回收站中
+    #[serde(default)]
+    pub update_at: String, // 如 "2024-01-15 10:30:00"
+}
+
+impl RemoteFile {
+    /// 是否为文件夹
+    pub fn is_folder(&self) -> bool {
+        self.kind == 1
+    }
+}
+
+/// 文件列表的一页
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FileListPage {
+    #[serde(alias = "lastFileID")]
+    pub last_file_id: i64, // 下一页的起点，-1 表示最后一页
+    #[serde(default)]
+    pub file_list: Vec<RemoteFile>,
+}
+
+/// 创建分享链接的请求
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareRequest {
+    pub share_name: String,
+    pub share_expire: u32, // 有效天数，0 表示永久
+    #[serde(rename = "fileIDList")]
+    pub file_id_list: String, // 逗号分隔的文件 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_pwd: Option<String>, // 提取码
+}
+
+/// 创建的分享
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    #[serde(alias = "shareID")]
+    pub share_id: i64,
+    pub share_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShareResponse {
+    code: i32,
+    #[serde(default)]
+    message: String,
+    data: Option<ShareInfo>,
+}
+
+/// 网盘账户信息
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    #[serde(default)]
+    pub uid: i64,
+    #[serde(default)]
+    pub nickname: String,
+    #[serde(default)]
+    pub space_used: u64, // 已用空间（字节）
+    #[serde(default)]
+    pub space_permanent: u64, // 永久空间（字节）
+    #[serde(default)]
+    pub space_temp: u64, // 临时扩容空间（字节）
+}
+
+impl AccountInfo {
+    /// 总空间（永久空间加临时空间）
+    pub fn space_total(&self) -> u64 {
+        self.space_permanent + self.space_temp
+    }
+
+    /// 已用空间比例（0-100），总空间未知时返回 None
+    pub fn used_percent(&self) -> Option<u8> {
+        let total = self.space_total();
+        if total == 0 {
+            return None;
+        }
+        Some((self.space_used.min(total) as f64 / total as f64 * 100.0).round() as u8)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountInfoResponse {
+    code: i32,
+    #[serde(default)]
+    message: String,
+    data: Option<AccountInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileListResponse {
+    code: i32,
+    #[serde(default)]
+    message: String,
+    data: Option<FileListPage>,
+}
+
+/// 后端 API 客户端
+pub struct ApiClient {
+    client: Client,
+    base_url: String,
+    endpoints: ApiEndpoints,
+}
+
+/// 共享的 API 客户端实例
+pub type SharedApiClient = Arc<ApiClient>;
+
+/// 处理函数使用的 API 客户端，启动后端服务后设置
+static SHARED_API_CLIENT: OnceLock<SharedApiClient> = OnceLock::new();
+
+/// 设置处理函数使用的 API 客户端，只能设置一次
+pub fn set_shared_api_client(client: SharedApiClient) {
+    info!("API client using {}", client.base_url());
+    if SHARED_API_CLIENT.set(client).is_err() {
+        debug!("API client already set");
+    }
+}
+
+/// 获取处理函数使用的 API 客户端，未设置时使用默认配置
+pub fn shared_api_client() -> SharedApiClient {
+    SHARED_API_CLIENT
+        .get_or_init(|| Arc::new(ApiClient::new(ApiEndpoints::default(), DEFAULT_BASE_URL)))
+        .clone()
+}
+
+impl ApiClient {
+    /// 创建 API 客户端
+    ///
+    /// # Arguments
+    /// * `endpoints` - 接口配置
+    /// * `fallback_base_url` - 配置中没有设置地址时使用的地址（内置后端服务）
+    pub fn new(endpoints: ApiEndpoints, fallback_base_url: &str) -> Self {
+        let base_url = endpoints
+            .base_url
+            .clone()
+            .unwrap_or_else(|| fallback_base_url.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let client = Client::builder()
+            .timeout(Duration::from_secs(endpoints.timeout_secs))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            base_url,
+            endpoints,
+        }
+    }
+
+    /// 后端服务地址
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 拼接接口的完整 URL
+    pub fn endpoint_url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    /// 发送文件上传请求到服务器，失败时记录链接获取失败指标
+    ///
+    /// # Arguments
+    /// * `data` - 文件上传数据
+    /// # Returns
+    /// * `Result<String>` - 上传后的文件 ID
+    pub async fn upload_file(&self, data: &UploadFileItemPayload) -> Result<String, LinkError> {
+        let result = self.try_upload_file(data).await;
+        if result.is_err() {
+            metrics::LINK_RESOLUTION_FAILURES.with_label_values(&["upload"]).inc();
+        }
+        result
+    }
+
+    async fn try_upload_file(&self, data: &UploadFileItemPayload) -> Result<String, LinkError> {
+        let url = self.endpoint_url(&self.endpoints.upload_path);
+
+        info!("正在发送文件上传 POST 请求到: {}", url);
+        debug!("请求数据: {:?}", data);
+
+        // 发送 POST 请求
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(data)
+            .send()
+            .await?;
+
+        let status = response.status();
+        info!("响应状态: {}", status);
+
+        // 检查 HTTP 状态码是否为成功状态
+        if !status.is_success() {
+            return Err(LinkError::Status {
+                status: status.as_u16(),
+                body: response.text().await?,
+            });
+        }
+
+        let resp: UploadFileResponse = serde_json::from_str(&response.text().await?)?;
+        // 秒传没有返回文件 ID 说明网盘中没有相同 ETag 的文件
+        let file_id = resp
+            .data
+            .and_then(|data| data.file_id)
+            .ok_or_else(|| LinkError::RemoteMissing(data.filename.clone()))?
+            .to_string();
+        Ok(file_id)
+    }
+
+    /// 获取文件的下载链接，失败时记录链接获取失败指标
+    ///
+    /// # Arguments
+    /// * `query` - 文件 ID 查询参数
+    pub async fn download_url(&self, query: &FileQuery) -> Result<DownloadUrlResponse, LinkError> {
+        let result = self.try_download_url(query).await;
+        if result.is_err() {
+            metrics::LINK_RESOLUTION_FAILURES.with_label_values(&["download"]).inc();
+        }
+        result
+    }
+
+    /// 获取文件夹中的一页文件列表
+    ///
+    /// # Arguments
+    /// * `parent_id` - 文件夹 ID，根目录为 0
+    /// * `last_file_id` - 上一页返回的 `last_file_id`，第一页为 0
+    /// * `limit` - 每页数量
+    pub async fn list_files(
+        &self,
+        parent_id: i64,
+        last_file_id: i64,
+        limit: u32,
+    ) -> Result<FileListPage, LinkError> {
+        let url = self.endpoint_url(&self.endpoints.list_path);
+        debug!("Listing remote folder {} from {}", parent_id, last_file_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .query(&[
+                ("parentFileId", parent_id.to_string()),
+                ("lastFileId", last_file_id.to_string()),
+                ("limit", limit.to_string()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(LinkError::Status {
+                status: response.status().as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        let list: FileListResponse = serde_json::from_str(&response.text().await?)?;
+        if list.code != 0 {
+            return Err(LinkError::Business {
+                code: list.code as i64,
+                message: list.message,
+            });
+        }
+        Ok(list.data.unwrap_or_default())
+    }
+
+    /// 创建分享链接
+    ///
+    /// # Arguments
+    /// * `request` - 分享名称、有效期、文件 ID 和提取码
+    ///
+    /// # Returns
+    /// * `Result<String>` - 分享链接
+    pub async fn create_share(&self, request: &ShareRequest) -> Result<String, LinkError> {
+        let url = self.endpoint_url(&self.endpoints.share_path);
+        info!("正在创建分享: {}", request.share_name);
+
+        let response = self.client.post(&url).json(request).send().await?;
+        if !response.status().is_success() {
+            return Err(LinkError::Status {
+                status: response.status().as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        let share: ShareResponse = serde_json::from_str(&response.text().await?)?;
+        if share.code != 0 {
+            return Err(LinkError::Business {
+                code: share.code as i64,
+                message: share.message,
+            });
+        }
+        let share = share.data.ok_or(LinkError::MissingData("分享信息"))?;
+        Ok(format!("{}{}", self.endpoints.share_url_prefix, share.share_key))
+    }
+
+    /// 获取网盘账户信息（昵称和空间用量）
+    pub async fn account_info(&self) -> Result<AccountInfo, LinkError> {
+        let url = self.endpoint_url(&self.endpoints.user_info_path);
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(LinkError::Status {
+                status: response.status().as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        let info: AccountInfoResponse = serde_json::from_str(&response.text().await?)?;
+        if info.code != 0 {
+            return Err(LinkError::Business {
+                code: info.code as i64,
+                message: info.message,
+            });
+        }
+        info.data.ok_or(LinkError::MissingData("账户信息"))
+    }
+
+    async fn try_download_url(&self, query: &FileQuery) -> Result<DownloadUrlResponse, LinkError> {
+        let url = self.endpoint_url(&self.endpoints.download_path);
+
+        // 发送 GET 请求，携带查询参数
+        let response = self
+            .client
+            .get(&url)
+            .query(query) // 自动将 FileQuery 转为 URL 查询参数（如 ?fileId=19349166）
+            .header("Content-Type", "application/json")
+            .send()
+            .await?;
+
+        // 检查 HTTP 状态码
+        if !response.status().is_success() {
+            return Err(LinkError::Status {
+                status: response.status().as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        // 将响应体反序列化为 DownloadUrlResponse
+        let download_response: DownloadUrlResponse = serde_json::from_str(&response.text().await?)?;
+
+        // 检查业务状态码（如果接口用 code 字段表示业务成功）
+        if download_response.code != 0 {
+            return Err(LinkError::Business {
+                code: download_response.code as i64,
+                message: download_response.message.to_string(),
+            });
+        }
+
+        Ok(download_response)
+    }
+}
@@ -3,7 +3,10 @@
 //! 提供Aria2 RPC客户端功能，用于管理下载任务
 
 use crate::models::config::Aria2Config;
+use crate::services::metrics;
+use crate::utils::paths;
 use anyhow::{Result, Context};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::process::{Child, Command, Stdio};
@@ -12,6 +15,7 @@ use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info, warn};
 
 /// Aria2 RPC 客户端
+#[derive(Clone)]
 pub struct Aria2Client {
     config: Aria2Config,
     client: reqwest::Client,
@@ -50,6 +54,108 @@ pub struct DownloadOptions {
     pub out: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<Vec<String>>,
+    #[serde(rename = "select-file", skip_serializing_if = "Option::is_none")]
+    pub select_file: Option<String>, // 种子中要下载的文件序号，如 "1,3-5"
+    #[serde(rename = "seed-time", skip_serializing_if = "Option::is_none")]
+    pub seed_time: Option<String>, // 下载完成后做种的分钟数，"0" 表示不做种
+}
+
+/// 种子和磁力链接的下载选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TorrentOptions {
+    pub dir: Option<String>, // 下载目录，None 表示使用配置中的默认目录
+    pub select_files: Vec<usize>, // 只下载这些文件（从 1 开始的序号），为空时下载全部
+    pub seed_time_minutes: Option<u64>, // 做种时间，None 表示使用Aria2的设置
+}
+
+impl TorrentOptions {
+    /// 转换为Aria2选项
+    ///
+    /// # Arguments
+    /// * `default_dir` - 未指定下载目录时使用的目录
+    pub fn to_download_options(&self, default_dir: &str) -> DownloadOptions {
+        let select_file = (!self.select_files.is_empty()).then(|| {
+            self.select_files
+                .iter()
+                .map(|index| index.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        });
+        DownloadOptions {
+            dir: Some(self.dir.as_deref().unwrap_or(default_dir).to_string()),
+            out: None,
+            header: None,
+            select_file,
+            seed_time: self.seed_time_minutes.map(|minutes| minutes.to_string()),
+        }
+    }
+}
+
+/// 种子任务中的一个文件（aria2.getFiles）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TorrentFile {
+    pub index: usize, // 从 1 开始，用于 `select_files`
+    pub path: String,
+    pub length: u64,
+    pub selected: bool,
+}
+
+impl TorrentFile {
+    /// 解析 aria2.getFiles 返回的文件列表
+    pub fn list_from_value(value: &Value) -> Vec<Self> {
+        let field = |file: &Value, key: &str| file.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        value
+            .as_array()
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|file| Self {
+                        index: field(file, "index").parse().unwrap_or(0),
+                        path: field(file, "path"),
+                        length: field(file, "length").parse().unwrap_or(0),
+                        selected: field(file, "selected") == "true",
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 是否为磁力链接（包含 BitTorrent info hash）
+pub fn is_magnet_uri(uri: &str) -> bool {
+    uri.trim_start()
+        .strip_prefix("magnet:?")
+        .map_or(false, |query| query.split('&').any(|param| param.starts_with("xt=urn:btih:")))
+}
+
+/// Aria2 全局传输统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GlobalStat {
+    pub download_speed: u64,
+    pub upload_speed: u64,
+    pub num_active: u64,
+    pub num_waiting: u64,
+    pub num_stopped: u64,
+}
+
+impl GlobalStat {
+    /// 从 aria2.getGlobalStat 的结果解析（Aria2 以字符串形式返回数字）
+    pub fn from_value(value: &Value) -> Self {
+        let field = |name: &str| {
+            value[name]
+                .as_str()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+
+        Self {
+            download_speed: field("downloadSpeed"),
+            upload_speed: field("uploadSpeed"),
+            num_active: field("numActive"),
+            num_waiting: field("numWaiting"),
+            num_stopped: field("numStopped"),
+        }
+    }
 }
 
 impl Aria2Client {
@@ -63,8 +169,17 @@ impl Aria2Client {
         }
     }
 
-    /// 发送RPC请求到Aria2
+    /// 发送RPC请求到Aria2，失败时记录错误指标
     async fn send_rpc_request(&self, method: &str, params: Vec<Value>) -> Result<Aria2Response> {
+        let result = self.call_rpc(method, params).await;
+        if result.is_err() {
+            metrics::ARIA2_RPC_ERRORS.with_label_values(&[method]).inc();
+        }
+        result
+    }
+
+    /// 发送RPC请求到Aria2
+    async fn call_rpc(&self, method: &str, params: Vec<Value>) -> Result<Aria2Response> {
         let mut request_body = json!({
             "jsonrpc": "2.0",
             "id": "netdisk_db",
@@ -128,12 +243,34 @@ impl Aria2Client {
         }
     }
 
+    /// 配置中的默认下载目录
+    pub fn download_dir(&self) -> &str {
+        &self.config.download_dir
+    }
+
     /// 添加下载任务
     pub async fn add_download(&self, url: &str, filename: Option<&str>) -> Result<String> {
-        let mut options = DownloadOptions {
-            dir: Some(self.config.download_dir.clone()),
+        self.add_download_to(url, None, filename).await
+    }
+
+    /// 添加下载任务到指定目录
+    ///
+    /// # Arguments
+    /// * `url` - 下载链接
+    /// * `dir` - 下载目录，None 表示使用配置中的默认目录
+    /// * `filename` - 保存的文件名，None 表示由Aria2根据URL决定
+    pub async fn add_download_to(
+        &self,
+        url: &str,
+        dir: Option<&str>,
+        filename: Option<&str>,
+    ) -> Result<String> {
+        let options = DownloadOptions {
+            dir: Some(dir.unwrap_or(&self.config.download_dir).to_string()),
             out: filename.map(|f| f.to_string()),
             header: None,
+            select_file: None,
+            seed_time: None,
         };
 
         let task = DownloadTask {
@@ -160,6 +297,127 @@ impl Aria2Client {
         }
     }
 
+    /// 添加种子下载任务（aria2.addTorrent）
+    ///
+    /// # Arguments
+    /// * `torrent` - .torrent 文件内容
+    /// * `options` - 下载目录、要下载的文件和做种时间
+    pub async fn add_torrent(&self, torrent: &[u8], options: &TorrentOptions) -> Result<String> {
+        let params = vec![
+            json!(BASE64_STANDARD.encode(torrent)),
+            json!([]),
+            json!(options.to_download_options(&self.config.download_dir)),
+        ];
+
+        let response = self.send_rpc_request("aria2.addTorrent", params).await?;
+        Self::gid_from_response(response.result, "Torrent")
+    }
+
+    /// 添加磁力链接下载任务
+    ///
+    /// Aria2先下载种子元数据，再按 `options` 下载内容
+    ///
+    /// # Arguments
+    /// * `magnet` - 磁力链接
+    /// * `options` - 下载目录、要下载的文件和做种时间
+    pub async fn add_magnet(&self, magnet: &str, options: &TorrentOptions) -> Result<String> {
+        if !is_magnet_uri(magnet) {
+            anyhow::bail!("Invalid magnet link: {}", magnet);
+        }
+        let params = vec![
+            json!([magnet.trim()]),
+            json!(options.to_download_options(&self.config.download_dir)),
+        ];
+
+        let response = self.send_rpc_request("aria2.addUri", params).await?;
+        Self::gid_from_response(response.result, "Magnet")
+    }
+
+    /// 获取任务中的文件列表（aria2.getFiles），用于选择种子中要下载的文件
+    pub async fn get_files(&self, gid: &str) -> Result<Vec<TorrentFile>> {
+        let response = self.send_rpc_request("aria2.getFiles", vec![json!(gid)]).await?;
+        response
+            .result
+            .map(|result| TorrentFile::list_from_value(&result))
+            .ok_or_else(|| anyhow::anyhow!("No result in response"))
+    }
+
+    /// 修改任务要下载的文件（aria2.changeOption 的 select-file）
+    ///
+    /// # Arguments
+    /// * `gid` - 任务GID
+    /// * `indexes` - 从 1 开始的文件序号
+    pub async fn select_files(&self, gid: &str, indexes: &[usize]) -> Result<()> {
+        let options = TorrentOptions {
+            select_files: indexes.to_vec(),
+            ..TorrentOptions::default()
+        };
+        let select_file = options
+            .to_download_options(&self.config.download_dir)
+            .select_file
+            .ok_or_else(|| anyhow::anyhow!("No files selected"))?;
+        self.send_rpc_request("aria2.changeOption", vec![json!(gid), json!({ "select-file": select_file })])
+            .await?;
+        Ok(())
+    }
+
+    /// 从响应中取出任务GID
+    fn gid_from_response(result: Option<Value>, kind: &str) -> Result<String> {
+        result
+            .as_ref()
+            .and_then(|result| result.as_str())
+            .map(|gid| {
+                info!("{} task added successfully with GID: {}", kind, gid);
+                gid.to_string()
+            })
+            .ok_or_else(|| anyhow::anyhow!("GID not found in response"))
+    }
+
+    /// 保存会话（aria2.saveSession），未完成的任务在下次启动时恢复
+    pub async fn save_session(&self) -> Result<()> {
+        self.send_rpc_request("aria2.saveSession", vec![]).await?;
+        Ok(())
+    }
+
+    /// 当前 aria2c 进程的会话 ID（aria2.getSessionInfo），进程重启后改变
+    pub async fn get_session_id(&self) -> Result<String> {
+        let response = self.send_rpc_request("aria2.getSessionInfo", vec![]).await?;
+        response
+            .result
+            .as_ref()
+            .and_then(|result| result["sessionId"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Session ID not found in response"))
+    }
+
+    /// 获取全局传输统计（aria2.getGlobalStat）
+    pub async fn get_global_stat(&self) -> Result<GlobalStat> {
+        let response = self.send_rpc_request("aria2.getGlobalStat", vec![]).await?;
+
+        if let Some(result) = response.result {
+            Ok(GlobalStat::from_value(&result))
+        } else {
+            Err(anyhow::anyhow!("No result in response"))
+        }
+    }
+
+    /// 修改全局选项（aria2.changeGlobalOption）
+    ///
+    /// # Arguments
+    /// * `options` - 选项键值对，如 `{"max-overall-download-limit": "2M"}`
+    pub async fn change_global_option(&self, options: Value) -> Result<()> {
+        self.send_rpc_request("aria2.changeGlobalOption", vec![options])
+            .await?;
+        Ok(())
+    }
+
+    /// 移除已完成/失败任务的结果（aria2.removeDownloadResult）
+    pub async fn remove_download_result(&self, gid: &str) -> Result<()> {
+        self.send_rpc_request("aria2.removeDownloadResult", vec![json!(gid)])
+            .await?;
+        Ok(())
+    }
+
     /// 获取下载状态
     pub async fn get_status(&self, gid: &str) -> Result<Value> {
         let response = self.send_rpc_request("aria2.tellStatus", vec![json!(gid)]).await?;
@@ -189,6 +447,15 @@ impl Aria2Service {
         }
     }
 
+    /// 连接已在运行的Aria2（例如由用户自己启动的 aria2c），不启动进程
+    pub fn connect(config: Aria2Config) -> Self {
+        Self {
+            client: Some(Aria2Client::new(config.clone())),
+            process: None,
+            config,
+        }
+    }
+
     /// 启动Aria2服务
     pub fn start(&mut self) -> Result<()> {
         if !self.config.enabled {
@@ -229,6 +496,13 @@ impl Aria2Service {
             .stdout(Stdio::null())
             .stderr(Stdio::null());
 
+        // 会话文件保存未完成的任务，下次启动时恢复
+        let session_file = paths::aria2_session_file();
+        if session_file.exists() {
+            command.arg("--input-file").arg(&session_file);
+        }
+        command.arg("--save-session").arg(&session_file);
+
         // 如果有RPC密钥，添加认证
         if let Some(ref secret) = self.config.rpc_secret {
             command.arg("--rpc-secret").arg(secret);
@@ -269,6 +543,11 @@ impl Aria2Service {
             .unwrap_or(false)
     }
 
+    /// 获取Aria2配置
+    pub fn config(&self) -> &Aria2Config {
+        &self.config
+    }
+
     /// 获取Aria2客户端
     pub fn get_client(&self) -> Option<&Aria2Client> {
         self.client.as_ref()
@@ -0,0 +1,131 @@
+//! 本地文件校验 - 重新计算本地文件的 MD5 并与记录中的 etag 比较
+//!
+//! 网盘的 etag 即文件内容的 MD5。校验结果保存在数据库的 `verified` 列中，
+//! 不一致的文件在结果列表中标出，用于发现损坏的本地副本
+
+use crate::models::config::PathMapping;
+use crate::models::database::FileRecord;
+use crate::services::database_manager::SharedDatabase;
+use crate::utils::path_mapping::map_path;
+use anyhow::{Context, Result};
+use md5::{Digest, Md5};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info, warn};
+
+/// 读取文件时的缓冲区大小
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 计算文件的 MD5
+///
+/// # Arguments
+/// * `path` - 文件路径
+/// * `cancel` - 取消标志，设置后尽快返回错误
+///
+/// # Returns
+/// * `Result<String>` - 小写十六进制的 MD5
+pub fn file_md5(path: &Path, cancel: &AtomicBool) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Checksum cancelled");
+        }
+        let read = file.read(&mut buffer).with_context(|| format!("Failed to read {:?}", path))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 校验结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifySummary {
+    pub matched: usize,
+    pub mismatched: usize,
+    pub skipped: usize, // 本地文件不存在或没有 etag
+    pub cancelled: bool,
+}
+
+/// 本地文件校验器
+pub struct ChecksumVerifier {
+    database: SharedDatabase,
+    path_mappings: Vec<PathMapping>,
+}
+
+impl ChecksumVerifier {
+    /// 创建校验器，结果写入 `database`
+    pub fn new(database: SharedDatabase) -> Self {
+        Self {
+            database,
+            path_mappings: Vec::new(),
+        }
+    }
+
+    /// 读取文件前按数据库的路径映射转换为本机路径，用于在其他系统上生成的数据库
+    pub fn with_path_mappings(mut self, path_mappings: Vec<PathMapping>) -> Self {
+        self.path_mappings = path_mappings;
+        self
+    }
+
+    /// 校验本地存在的文件，逐个写入结果
+    ///
+    /// # Arguments
+    /// * `records` - 要校验的文件记录
+    /// * `cancel` - 取消标志
+    /// * `progress` - 每个文件处理后调用，参数为已处理数和总数
+    pub fn verify(
+        &self,
+        records: &[FileRecord],
+        cancel: &AtomicBool,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<VerifySummary> {
+        let mut summary = VerifySummary::default();
+        for (index, record) in records.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                summary.cancelled = true;
+                break;
+            }
+            let local_path = map_path(&record.path, &self.path_mappings);
+            let path = Path::new(&local_path);
+            if record.etag.is_empty() || !path.is_file() {
+                summary.skipped += 1;
+                progress(index + 1, records.len());
+                continue;
+            }
+
+            let hash = match file_md5(path, cancel) {
+                Ok(hash) => hash,
+                Err(_) if cancel.load(Ordering::Relaxed) => {
+                    summary.cancelled = true;
+                    break;
+                }
+                Err(e) => {
+                    warn!("Failed to hash {}: {:#}", record.path, e);
+                    summary.skipped += 1;
+                    progress(index + 1, records.len());
+                    continue;
+                }
+            };
+
+            let matched = hash.eq_ignore_ascii_case(record.etag.trim());
+            if matched {
+                summary.matched += 1;
+            } else {
+                summary.mismatched += 1;
+                info!("Checksum mismatch: {} (etag {}, local {})", record.path, record.etag, hash);
+            }
+            self.database
+                .lock()
+                .unwrap()
+                .set_verified(&[(record.path.clone(), matched)])?;
+            progress(index + 1, records.len());
+        }
+        debug!("Checksum verification finished: {:?}", summary);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,243 @@
+//! 剪切板服务 - 在独立线程中访问系统剪切板
+//!
+//! 部分 Linux 环境（X11/Wayland）下创建或写入剪切板会阻塞，
+//! 所有剪切板操作通过通道交给专用线程执行，界面线程和异步任务不会被卡住。
+//! 剪切板实例由该线程一直持有，X11 下复制的内容在程序运行期间保持可用。
+//! 成功写入的内容记录在剪切板历史中，可以在界面中重新复制之前的链接
+
+use crate::services::api_client::LinkError;
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, error, info};
+
+/// 写入失败时的最大尝试次数
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 重试前的等待时间
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// 剪切板历史默认保留的条数
+pub const DEFAULT_HISTORY_CAPACITY: usize = 20;
+
+/// 剪切板后端，默认使用系统剪切板
+pub trait ClipboardBackend {
+    /// 设置剪切板文本
+    fn set_text(&mut self, text: &str) -> Result<(), String>;
+}
+
+impl ClipboardBackend for arboard::Clipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        arboard::Clipboard::set_text(self, text).map_err(|e| e.to_string())
+    }
+}
+
+/// 发送给剪切板线程的命令
+pub enum ClipboardCommand {
+    /// 设置剪切板文本，不等待结果
+    SetText(String),
+    /// 设置剪切板文本，并通过通道返回结果
+    SetTextWithReply(String, oneshot::Sender<Result<(), String>>),
+}
+
+/// 剪切板历史中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardEntry {
+    pub text: String,
+    pub copied_at: i64, // 复制时间（Unix 时间戳）
+}
+
+impl ClipboardEntry {
+    /// 列表中显示的摘要：第一行，多行内容附加总行数
+    pub fn summary(&self) -> String {
+        let mut lines = self.text.lines().filter(|line| !line.trim().is_empty());
+        let first = lines.next().unwrap_or_default().trim().to_string();
+        let rest = lines.count();
+        if rest > 0 {
+            format!("{}（共 {} 行）", first, rest + 1)
+        } else {
+            first
+        }
+    }
+}
+
+/// 最近复制到剪切板的内容，剪切板线程写入，界面线程读取
+pub struct ClipboardHistory {
+    capacity: usize,
+    entries: Mutex<VecDeque<ClipboardEntry>>,
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl ClipboardHistory {
+    /// 创建剪切板历史
+    ///
+    /// # Arguments
+    /// * `capacity` - 最多保留的条数
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 记录一次复制，已有相同内容时移到最前，超过容量时丢弃最早的记录
+    pub fn record(&self, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.text != text);
+        entries.push_back(ClipboardEntry {
+            text: text.to_string(),
+            copied_at: chrono::Utc::now().timestamp(),
+        });
+        while entries.len() > self.capacity {
+            entries.pop_front();
+        }
+    }
+
+    /// 最近复制的内容，最新的在前
+    pub fn recent(&self) -> Vec<ClipboardEntry> {
+        self.entries.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// `recent()` 中第 `index` 条记录
+    pub fn get(&self, index: usize) -> Option<ClipboardEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.len().checked_sub(index + 1).and_then(|i| entries.get(i).cloned())
+    }
+
+    /// 清空记录
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// 剪切板线程的句柄，可以克隆后在多处使用
+#[derive(Clone)]
+pub struct ClipboardHandle {
+    tx: mpsc::Sender<ClipboardCommand>,
+    history: Arc<ClipboardHistory>,
+}
+
+impl ClipboardHandle {
+    /// 启动使用系统剪切板的剪切板线程
+    pub fn spawn() -> Self {
+        Self::spawn_with(|| arboard::Clipboard::new().map_err(|e| e.to_string()))
+    }
+
+    /// 启动剪切板线程
+    ///
+    /// # Arguments
+    /// * `create` - 在剪切板线程中创建后端，失败时下次写入前重新创建
+    pub fn spawn_with<B, F>(create: F) -> Self
+    where
+        B: ClipboardBackend + 'static,
+        F: FnMut() -> Result<B, String> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let history = Arc::new(ClipboardHistory::default());
+        let spawned = thread::Builder::new()
+            .name("clipboard".to_string())
+            .spawn({
+                let history = history.clone();
+                move || run_worker(rx, create, &history)
+            });
+        if let Err(e) = spawned {
+            error!("Failed to start clipboard thread: {}", e);
+        }
+        Self { tx, history }
+    }
+
+    /// 剪切板历史，只记录成功写入的内容
+    pub fn history(&self) -> &ClipboardHistory {
+        &self.history
+    }
+
+    /// 发送命令，不等待执行结果
+    pub fn send(&self, command: ClipboardCommand) -> Result<(), LinkError> {
+        self.tx
+            .send(command)
+            .map_err(|_| LinkError::Clipboard("剪切板线程已退出".to_string()))
+    }
+
+    /// 设置剪切板文本并等待结果
+    pub async fn set_text(&self, text: impl Into<String>) -> Result<(), LinkError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ClipboardCommand::SetTextWithReply(text.into(), reply_tx))?;
+        reply_rx
+            .await
+            .map_err(|_| LinkError::Clipboard("剪切板线程已退出".to_string()))?
+            .map_err(LinkError::Clipboard)
+    }
+}
+
+/// 剪切板线程：依次执行命令，直到所有句柄被丢弃
+fn run_worker<B, F>(rx: mpsc::Receiver<ClipboardCommand>, mut create: F, history: &ClipboardHistory)
+where
+    B: ClipboardBackend,
+    F: FnMut() -> Result<B, String>,
+{
+    let mut backend: Option<B> = None;
+    while let Ok(command) = rx.recv() {
+        let (text, reply) = match command {
+            ClipboardCommand::SetText(text) => (text, None),
+            ClipboardCommand::SetTextWithReply(text, reply) => (text, Some(reply)),
+        };
+        let result = write_text(&mut backend, &mut create, &text);
+        if result.is_ok() {
+            history.record(&text);
+        }
+        if let Some(reply) = reply {
+            let _ = reply.send(result);
+        }
+    }
+    debug!("Clipboard thread stopped");
+}
+
+/// 写入剪切板，失败时丢弃后端并重新创建，最多尝试 `MAX_ATTEMPTS` 次
+fn write_text<B, F>(backend: &mut Option<B>, create: &mut F, text: &str) -> Result<(), String>
+where
+    B: ClipboardBackend,
+    F: FnMut() -> Result<B, String>,
+{
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        if backend.is_none() {
+            match create() {
+                Ok(created) => *backend = Some(created),
+                Err(e) => {
+                    debug!("Failed to open clipboard (attempt {}): {}", attempt, e);
+                    last_error = e;
+                    thread::sleep(RETRY_DELAY);
+                    continue;
+                }
+            }
+        }
+
+        if let Some(clipboard) = backend.as_mut() {
+            match clipboard.set_text(text) {
+                Ok(()) => {
+                    info!("成功复制到剪切板: {}", text);
+                    return Ok(());
+                }
+                Err(e) => {
+                    debug!("复制到剪切板失败，第{}次尝试: {}", attempt, e);
+                    *backend = None;
+                    last_error = e;
+                    thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    error!("复制到剪切板失败，已尝试{}次: {}", MAX_ATTEMPTS, last_error);
+    Err(last_error)
+}
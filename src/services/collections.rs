@@ -0,0 +1,292 @@
+//! 收藏集 - 将任意数据库中的文件记录归入命名的集合
+//!
+//! 收藏集保存在独立的 SQLite 数据库中，记录文件的全部字段和所在的数据库名称，
+//! 切换或删除数据库后仍可查看，之后可以批量下载或导出
+
+use crate::models::database::FileRecord;
+use anyhow::{bail, Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use tracing::debug;
+
+/// 一个收藏集
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub created_at: i64,
+    pub item_count: usize,
+}
+
+/// 收藏集中的一个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionItem {
+    pub record: FileRecord,
+    pub database: String, // 文件所在的数据库名称
+    #[serde(default)]
+    pub added_at: i64,
+}
+
+/// 收藏集存储
+pub struct Collections {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+/// 共享的收藏集实例
+pub type SharedCollections = Arc<Collections>;
+
+static SHARED_COLLECTIONS: OnceLock<SharedCollections> = OnceLock::new();
+
+/// 设置共享的收藏集，供后端接口使用，只能设置一次
+pub fn set_shared_collections(collections: SharedCollections) {
+    if SHARED_COLLECTIONS.set(collections).is_err() {
+        debug!("Collections already set");
+    }
+}
+
+/// 获取共享的收藏集，未设置时为 None
+pub fn shared_collections() -> Option<SharedCollections> {
+    SHARED_COLLECTIONS.get().cloned()
+}
+
+impl Collections {
+    /// 打开收藏集数据库，不存在时创建
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    pub fn open(path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path);
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .context("Failed to create collections connection pool")?;
+
+        pool.get()
+            .context("Failed to get connection from pool")?
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS collections (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL UNIQUE,
+                    created_at INTEGER NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS collection_items (
+                    collection_id INTEGER NOT NULL,
+                    database TEXT NOT NULL,
+                    record_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    etag TEXT NOT NULL,
+                    modified_time INTEGER NOT NULL,
+                    file_type TEXT NOT NULL,
+                    added_at INTEGER NOT NULL,
+                    PRIMARY KEY (collection_id, database, path)
+                );",
+            )
+            .context("Failed to create collections tables")?;
+
+        Ok(Self { pool })
+    }
+
+    fn connection(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+        self.pool.get().context("Failed to get connection from pool")
+    }
+
+    /// 所有收藏集，按名称排序
+    pub fn list(&self) -> Result<Vec<Collection>> {
+        let conn = self.connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.name, c.created_at, COUNT(i.path)
+                 FROM collections c LEFT JOIN collection_items i ON i.collection_id = c.id
+                 GROUP BY c.id
+                 ORDER BY c.name COLLATE NOCASE",
+            )
+            .context("Failed to prepare collections query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Collection {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    created_at: row.get(2)?,
+                    item_count: row.get::<_, i64>(3)?.max(0) as usize,
+                })
+            })
+            .context("Failed to query collections")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to map collection")
+    }
+
+    /// 按 ID 查找收藏集
+    pub fn get(&self, id: i64) -> Result<Option<Collection>> {
+        Ok(self.list()?.into_iter().find(|collection| collection.id == id))
+    }
+
+    /// 创建收藏集
+    ///
+    /// # Returns
+    /// * `Result<i64>` - 新收藏集的 ID，名称为空或已存在时返回错误
+    pub fn create(&self, name: &str) -> Result<i64> {
+        let name = valid_name(name)?;
+        let conn = self.connection()?;
+        if Self::find_by_name(&conn, name)?.is_some() {
+            bail!("收藏集 \"{}\" 已存在", name);
+        }
+        conn.execute(
+            "INSERT INTO collections (name, created_at) VALUES (?1, ?2)",
+            params![name, chrono::Local::now().timestamp()],
+        )
+        .context("Failed to create collection")?;
+        debug!("Created collection {}", name);
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 重命名收藏集
+    pub fn rename(&self, id: i64, name: &str) -> Result<()> {
+        let name = valid_name(name)?;
+        let conn = self.connection()?;
+        if Self::find_by_name(&conn, name)?.is_some_and(|existing| existing != id) {
+            bail!("收藏集 \"{}\" 已存在", name);
+        }
+        let changed = conn
+            .execute("UPDATE collections SET name = ?1 WHERE id = ?2", params![name, id])
+            .context("Failed to rename collection")?;
+        if changed == 0 {
+            bail!("收藏集不存在: {}", id);
+        }
+        Ok(())
+    }
+
+    /// 删除收藏集及其中的全部文件
+    ///
+    /// # Returns
+    /// * `Result<bool>` - 收藏集不存在时返回 false
+    pub fn delete(&self, id: i64) -> Result<bool> {
+        let mut conn = self.connection()?;
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        tx.execute("DELETE FROM collection_items WHERE collection_id = ?1", params![id])
+            .context("Failed to delete collection items")?;
+        let deleted = tx
+            .execute("DELETE FROM collections WHERE id = ?1", params![id])
+            .context("Failed to delete collection")?;
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(deleted > 0)
+    }
+
+    /// 将文件加入收藏集，已在其中的文件忽略
+    ///
+    /// # Arguments
+    /// * `id` - 收藏集 ID
+    /// * `records` - 文件记录
+    /// * `database` - 文件所在的数据库名称
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 新加入的文件数
+    pub fn add_records(&self, id: i64, records: &[FileRecord], database: &str) -> Result<usize> {
+        let mut conn = self.connection()?;
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let exists: Option<i64> = tx
+            .query_row("SELECT id FROM collections WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()
+            .context("Failed to query collection")?;
+        if exists.is_none() {
+            bail!("收藏集不存在: {}", id);
+        }
+
+        let now = chrono::Local::now().timestamp();
+        let mut added = 0;
+        for record in records {
+            added += tx
+                .execute(
+                    "INSERT OR IGNORE INTO collection_items
+                        (collection_id, database, record_id, name, path, size, etag, modified_time, file_type, added_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        id,
+                        database,
+                        record.id,
+                        record.name,
+                        record.path,
+                        record.size as i64,
+                        record.etag,
+                        record.modified_time,
+                        record.file_type,
+                        now
+                    ],
+                )
+                .context("Failed to add collection item")?;
+        }
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(added)
+    }
+
+    /// 从收藏集中移除文件
+    ///
+    /// # Returns
+    /// * `Result<bool>` - 文件不在收藏集中时返回 false
+    pub fn remove_record(&self, id: i64, database: &str, path: &str) -> Result<bool> {
+        let removed = self
+            .connection()?
+            .execute(
+                "DELETE FROM collection_items WHERE collection_id = ?1 AND database = ?2 AND path = ?3",
+                params![id, database, path],
+            )
+            .context("Failed to remove collection item")?;
+        Ok(removed > 0)
+    }
+
+    /// 收藏集中的文件，按加入顺序
+    pub fn items(&self, id: i64) -> Result<Vec<CollectionItem>> {
+        let conn = self.connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT record_id, name, path, size, etag, modified_time, file_type, database, added_at
+                 FROM collection_items
+                 WHERE collection_id = ?1
+                 ORDER BY added_at, rowid",
+            )
+            .context("Failed to prepare collection items query")?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                let size: i64 = row.get(3)?;
+                Ok(CollectionItem {
+                    record: FileRecord {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        path: row.get(2)?,
+                        size: size.max(0) as u64,
+                        etag: row.get(4)?,
+                        modified_time: row.get(5)?,
+                        file_type: row.get(6)?,
+                    },
+                    database: row.get(7)?,
+                    added_at: row.get(8)?,
+                })
+            })
+            .context("Failed to query collection items")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to map collection item")
+    }
+
+    fn find_by_name(conn: &rusqlite::Connection, name: &str) -> Result<Option<i64>> {
+        conn.query_row(
+            "SELECT id FROM collections WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to query collection")
+    }
+}
+
+/// 去掉首尾空白后的名称，为空时返回错误
+fn valid_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("收藏集名称不能为空");
+    }
+    Ok(name)
+}
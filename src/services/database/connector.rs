@@ -64,6 +64,41 @@ impl SqliteConnector {
     pub fn new() -> Self {
         Self
     }
+    
+    /// 收集目录下的 .db 文件，`depth` 为剩余的递归层数
+    fn collect_database_files(dir: &std::path::Path, depth: usize, databases: &mut Vec<DatabaseConnectionInfo>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_name = match path.file_name().and_then(|s| s.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            
+            // 跳过隐藏文件、临时文件和系统文件
+            if file_name.starts_with('.') || file_name.starts_with('~') {
+                continue;
+            }
+            
+            if path.is_dir() {
+                if depth > 0 {
+                    Self::collect_database_files(&path, depth - 1, databases);
+                }
+            } else if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("db") {
+                let db_name = file_name.trim_end_matches(".db").to_string();
+                databases.push(DatabaseConnectionInfo {
+                    name: db_name,
+                    db_type: "sqlite".to_string(),
+                    connection_string: path.to_string_lossy().to_string(),
+                    description: Some(format!("SQLite database file: {}", file_name)),
+                });
+            }
+        }
+    }
 }
 
 impl DatabaseConnector for SqliteConnector {
@@ -80,40 +115,18 @@ impl DatabaseConnector for SqliteConnector {
     fn get_database_list(&self, connection_info: &HashMap<String, String>) -> Result<Vec<DatabaseConnectionInfo>> {
         let mut databases = Vec::new();
         
-        // 获取搜索路径，默认为当前目录
+        // 获取搜索路径，默认为当前目录；max_depth 为递归深度，默认不递归
         let search_path = connection_info.get("path")
             .map(|s| s.as_str())
             .unwrap_or(".");
+        let max_depth = connection_info.get("max_depth")
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(0);
         
         let base_path = std::path::Path::new(search_path);
         
         if base_path.exists() && base_path.is_dir() {
-            // 读取目录下的所有.db文件
-            if let Ok(entries) = std::fs::read_dir(base_path) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        
-                        // 检查是否为.db文件
-                        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("db") {
-                            if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
-                                // 跳过临时文件和系统文件
-                                if !file_name.starts_with('.') && !file_name.starts_with('~') {
-                                    let db_name = file_name.trim_end_matches(".db").to_string();
-                                    let db_path = path.to_string_lossy().to_string();
-                                    
-                                    databases.push(DatabaseConnectionInfo {
-                                        name: db_name,
-                                        db_type: "sqlite".to_string(),
-                                        connection_string: db_path,
-                                        description: Some(format!("SQLite database file: {}", file_name)),
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            Self::collect_database_files(base_path, max_depth, &mut databases);
         }
         
         Ok(databases)
@@ -125,6 +138,12 @@ impl DatabaseConnector for SqliteConnector {
             connection_string: connection_string.to_string(),
             name: name.to_string(),
             description,
+            download_dir: None,
+            auto_discovered: false,
+            credential_id: None,
+            remote_folder_id: 0,
+            remote_folder_path: None,
+            path_mappings: Vec::new(),
         }
     }
 }
@@ -201,6 +220,12 @@ impl DatabaseConnector for MySqlConnector {
             connection_string: connection_string.to_string(),
             name: name.to_string(),
             description,
+            download_dir: None,
+            auto_discovered: false,
+            credential_id: None,
+            remote_folder_id: 0,
+            remote_folder_path: None,
+            path_mappings: Vec::new(),
         }
     }
 }
\ No newline at end of file
@@ -2,12 +2,134 @@
 //!
 //! 提供 SQLite 数据库的具体实现
 
-use crate::models::database::{Database, FileRecord};
+use crate::models::database::{
+    CompareKey, Database, DatabaseDiff, FileRecord, MaintenanceOperation, QueryResult, RenameEntry,
+    SearchOptions, random_file_by_offset,
+};
 use anyhow::{Context, Result};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
-use tracing::debug;
+use rusqlite::{params, OptionalExtension};
+use crate::services::diagnostics::{shared_query_diagnostics, SlowQuery};
+use crate::services::sql_console::{self, FILE_RECORD_COLUMNS};
+use crate::utils::time::{normalize_timestamp, parse_timestamp};
+use rusqlite::backup::Backup;
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+use rusqlite::{Connection, OpenFlags, ToSql};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// 在线备份每步复制的页数，步与步之间让出锁，不阻塞其他连接
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 256;
+/// 在线备份每步之间的等待时间
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(10);
+
+/// video 表是否有指定列，旧数据库和导入的数据库可能缺少后来添加的列
+fn has_column(conn: &Connection, column: &str) -> Result<bool> {
+    conn.prepare("SELECT 1 FROM pragma_table_info('video') WHERE name = ?1")
+        .and_then(|mut stmt| stmt.exists(params![column]))
+        .context("Failed to inspect video table")
+}
+
+/// 转义 LIKE 模式中的通配符，配合 `ESCAPE '\'` 使用
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 搜索范围对应的 LIKE 模式，没有范围时匹配全部
+fn scope_pattern(options: &SearchOptions) -> String {
+    options
+        .scope_prefix()
+        .map(|prefix| format!("{}%", escape_like(&prefix)))
+        .unwrap_or_else(|| "%".to_string())
+}
+
+/// 类型筛选对应的 WHERE 条件（以 ` AND` 开头），没有筛选时为空
+///
+/// 与 `FileCategory::matches` 一致：`file_type` 是扩展名或 MIME 类型，或者文件名有该分类的扩展名。
+/// 扩展名和 MIME 前缀都是内置的常量，可以直接拼接到 SQL 中
+fn category_condition(options: &SearchOptions) -> String {
+    if options.categories.is_empty() {
+        return String::new();
+    }
+    let mut extensions = Vec::new();
+    let mut conditions = Vec::new();
+    for category in &options.categories {
+        extensions.extend(category.extensions().iter().map(|ext| format!("'{}'", ext)));
+        conditions.extend(
+            category
+                .mime_prefixes()
+                .iter()
+                .map(|prefix| format!("lower(file_type) LIKE '{}%'", prefix)),
+        );
+        conditions.extend(
+            category
+                .extensions()
+                .iter()
+                .map(|ext| format!("lower(name) LIKE '%.{}'", ext)),
+        );
+    }
+    conditions.insert(0, format!("lower(file_type) IN ({})", extensions.join(", ")));
+    format!(" AND ({})", conditions.join(" OR "))
+}
+
+/// SQL 控制台中显示的值
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).to_string(),
+        ValueRef::Blob(blob) => format!("<{} 字节>", blob.len()),
+    }
+}
+
+/// 读取修改时间列，秒、毫秒级时间戳和日期时间字符串统一转换为秒，无法识别时为 0
+fn read_timestamp(row: &rusqlite::Row, column: usize) -> rusqlite::Result<i64> {
+    Ok(match row.get_ref(column)? {
+        ValueRef::Integer(timestamp) => normalize_timestamp(timestamp),
+        ValueRef::Real(timestamp) => normalize_timestamp(timestamp as i64),
+        ValueRef::Text(text) => parse_timestamp(&String::from_utf8_lossy(text)).unwrap_or_else(|| {
+            debug!("Unrecognized modified_time: {:?}", String::from_utf8_lossy(text));
+            0
+        }),
+        ValueRef::Null | ValueRef::Blob(_) => 0,
+    })
+}
+
+/// 按列位置读取文件记录，`columns` 的顺序与 `FILE_RECORD_COLUMNS` 相同
+fn read_record_at(row: &rusqlite::Row, columns: &[usize]) -> rusqlite::Result<FileRecord> {
+    Ok(FileRecord {
+        id: row.get(columns[0])?,
+        path: row.get(columns[1])?,
+        size: row.get::<_, i64>(columns[2])?.max(0) as u64,
+        etag: row.get(columns[3])?,
+        modified_time: read_timestamp(row, columns[4])?,
+        file_type: row.get(columns[5])?,
+        name: row.get(columns[6])?,
+    })
+}
+
+/// 诊断记录中显示的查询参数
+fn describe_param(param: &dyn ToSql) -> String {
+    let value = match param.to_sql() {
+        Ok(ToSqlOutput::Borrowed(value)) => Value::from(value),
+        Ok(ToSqlOutput::Owned(value)) => value,
+        _ => return "?".to_string(),
+    };
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(text) => format!("'{}'", text),
+        Value::Blob(blob) => format!("<{} 字节>", blob.len()),
+    }
+}
 
 /// SQLite 数据库连接池包装器
 pub struct SqliteDatabase {
@@ -27,6 +149,158 @@ impl SqliteDatabase {
 
         Ok(Self { pool })
     }
+
+    /// 使用 SQLite 在线备份将数据库复制到 `dest`，备份期间仍可读写
+    ///
+    /// # Arguments
+    /// * `dest` - 备份文件路径，已存在时被覆盖
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+        let mut target = Connection::open(dest)
+            .with_context(|| format!("Failed to open backup file {:?}", dest))?;
+        Backup::new(&conn, &mut target)
+            .context("Failed to start backup")?
+            .run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+            .with_context(|| format!("Failed to back up database to {:?}", dest))?;
+        info!("Backed up database to {:?}", dest);
+        Ok(())
+    }
+
+    /// 用备份文件的内容替换当前数据库
+    ///
+    /// 备份文件先经过 `validate_backup` 检查，损坏的备份不会覆盖现有数据
+    ///
+    /// # Arguments
+    /// * `source` - 备份文件路径
+    pub fn restore_from(&self, source: &Path) -> Result<()> {
+        validate_backup(source)?;
+        let backup = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open backup file {:?}", source))?;
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+        Backup::new(&backup, &mut conn)
+            .context("Failed to start restore")?
+            .run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+            .with_context(|| format!("Failed to restore database from {:?}", source))?;
+        info!("Restored database from {:?}", source);
+        Ok(())
+    }
+}
+
+/// 比较两个 SQLite 数据库文件中的记录
+///
+/// 以只读方式打开 `left`，通过 ATTACH 附加 `right`，差异由 SQLite 在一次连接中计算，
+/// 不需要将两个数据库全部读入内存
+///
+/// # Arguments
+/// * `left` - 左侧数据库文件
+/// * `right` - 右侧数据库文件
+/// * `key` - 按路径或 etag 匹配记录
+pub fn compare_databases(left: &Path, right: &Path, key: CompareKey) -> Result<DatabaseDiff> {
+    let conn = Connection::open_with_flags(left, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open {:?}", left))?;
+    conn.execute("ATTACH DATABASE ?1 AS other", params![right.to_string_lossy()])
+        .with_context(|| format!("Failed to attach {:?}", right))?;
+
+    const COLUMNS: &str = "id, path, size, etag, modified_time, file_type, name";
+    let query = |sql: &str| -> Result<Vec<FileRecord>> {
+        let mut stmt = conn.prepare(sql).context("Failed to prepare compare query")?;
+        let records = stmt
+            .query_map([], SqliteDatabase::read_file_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to compare databases")?;
+        Ok(records)
+    };
+    let prefixed = |alias: &str| {
+        COLUMNS
+            .split(", ")
+            .map(|column| format!("{}.{}", alias, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    // 匹配条件、参与比较的记录（按 etag 比较时忽略没有 etag 的记录）和两侧都存在但不同的条件
+    let (matches, filter, differs) = match key {
+        CompareKey::Path => ("b.path = a.path", "1", "a.etag <> b.etag OR a.size <> b.size"),
+        CompareKey::Etag => (
+            "b.etag = a.etag",
+            "a.etag <> ''",
+            "a.path <> b.path \
+             AND NOT EXISTS (SELECT 1 FROM other.video c WHERE c.etag = a.etag AND c.path = a.path)",
+        ),
+    };
+    let only = |from: &str, to: &str| {
+        format!(
+            "SELECT {} FROM {}.video a WHERE {} AND NOT EXISTS (SELECT 1 FROM {}.video b WHERE {}) ORDER BY a.path",
+            COLUMNS, from, filter, to, matches
+        )
+    };
+    // 两侧的记录分两次按相同顺序查询，再逐条配对
+    let changed = |alias: &str| {
+        format!(
+            "SELECT {} FROM main.video a JOIN other.video b ON {} WHERE {} AND ({}) ORDER BY a.path, a.id, b.id",
+            prefixed(alias),
+            matches,
+            filter,
+            differs
+        )
+    };
+    let latest = |schema: &str| -> Result<i64> {
+        conn.query_row(
+            &format!("SELECT COALESCE(MAX(modified_time), 0) FROM {}.video", schema),
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to read latest modified time")
+    };
+
+    let diff = DatabaseDiff {
+        only_left: query(&only("main", "other"))?,
+        only_right: query(&only("other", "main"))?,
+        changed: query(&changed("a"))?.into_iter().zip(query(&changed("b"))?).collect(),
+        left_latest: latest("main")?,
+        right_latest: latest("other")?,
+    };
+    debug!(
+        "Compared {:?} with {:?}: {} only left, {} only right, {} changed",
+        left,
+        right,
+        diff.only_left.len(),
+        diff.only_right.len(),
+        diff.changed.len()
+    );
+    Ok(diff)
+}
+
+/// 检查备份文件是否完整并包含文件表
+///
+/// # Returns
+/// * `Result<usize>` - 备份中的文件记录数
+pub fn validate_backup(path: &Path) -> Result<usize> {
+    if !path.is_file() {
+        anyhow::bail!("备份文件不存在: {}", path.display());
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open backup file {:?}", path))?;
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .with_context(|| format!("{} 不是有效的 SQLite 数据库", path.display()))?;
+    if integrity != "ok" {
+        anyhow::bail!("备份文件已损坏: {}", integrity);
+    }
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM video", [], |row| row.get(0))
+        .with_context(|| format!("{} 中没有文件表", path.display()))?;
+    Ok(count as usize)
 }
 
 impl Database for SqliteDatabase {
@@ -72,6 +346,16 @@ impl Database for SqliteDatabase {
         )
         .context("Failed to create index on video.path")?;
 
+        // 旧数据库没有校验结果列：NULL 未校验，1 与 etag 一致，0 不一致
+        let has_verified = has_column(&conn, "verified")?;
+        // 只读数据库无法添加，校验结果不可用但不影响搜索
+        if !has_verified {
+            debug!("添加 verified 列...");
+            if let Err(e) = conn.execute("ALTER TABLE video ADD COLUMN verified INTEGER", []) {
+                debug!("无法添加 verified 列: {}", e);
+            }
+        }
+
         // 如果表为空，添加示例数据
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM video", [], |row| row.get(0))
@@ -101,81 +385,152 @@ impl Database for SqliteDatabase {
             .context("Failed to prepare search statement")?;
 
         debug!("执行命令:{}", &command);
+        let started = Instant::now();
+        let query_params: [&dyn ToSql; 1] = [&search_pattern];
         let file_iter = stmt
-            .query_map(params![search_pattern], |row| {
-                // 获取所有字段的原始值用于调试
-                let id: i64 = row.get(0)?;
-                let path: String = row.get(1)?;
-                
-                // 安全地获取 size 字段，作为 u64 获取
-                let size_value: Result<i64, _> = row.get(2);
-                let size = match size_value {
-                    Ok(s) => {
-                        debug!("Got size as i64: {} for file: {}", s, path);
-                        if s < 0 {
-                            debug!("Negative size detected: {}, converting to positive", s);
-                            s as u64
-                        } else {
-                            s as u64
-                        }
-                    }
-                    Err(e) => {
-                        debug!("Failed to get size for file {}: {}, using 0", path, e);
-                        0u64
-                    }
-                };
-                
-                let etag: String = row.get(3)?;
-                
-                // 安全地获取 modified_time 字段
-                let modified_time_value: Result<i64, _> = row.get(4);
-                let modified_time = match modified_time_value {
-                    Ok(t) => {
-                        debug!("Got modified_time as i64: {} for file: {}", t, path);
-                        t
-                    }
-                    Err(_) => {
-                        // 如果无法作为 i64 获取，尝试作为字符串然后解析
-                        let time_str: Result<String, _> = row.get(4);
-                        match time_str {
-                            Ok(s) => {
-                                debug!("Got modified_time as string: '{}' for file: {}", s, path);
-                                s.parse::<i64>().unwrap_or(0)
-                            }
-                            Err(e) => {
-                                debug!("Failed to get modified_time for file {}: {}, using 0", path, e);
-                                0
-                            }
-                        }
-                    }
-                };
-                
-                let file_type: String = row.get(5)?;
-                let name: String = row.get(6)?;
-                
-                debug!("Creating FileRecord: id={}, name={}, path={}, size='{}', etag={}, modified_time={}, file_type={}",
-                       id, name, path, size, etag, modified_time, file_type);
-                
-                Ok(FileRecord {
-                    id,
-                    path,
-                    size,
-                    etag,
-                    modified_time,
-                    file_type,
-                    name,
-                })
-            })
+            .query_map(&query_params[..], Self::read_file_record)
             .context("Failed to execute search query")?;
 
         let mut results = Vec::new();
         for file in file_iter {
             results.push(file.context("Failed to map file record")?);
         }
+        Self::observe_query(&conn, command, &query_params, started);
 
         Ok(results)
     }
 
+    fn count_files(&self, query: &str, options: &SearchOptions) -> Result<usize> {
+        let search_pattern = format!("%{}%", query);
+
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let sql = format!(
+            "SELECT COUNT(*) FROM video WHERE path LIKE ?1 AND path LIKE ?2 ESCAPE '\\'{}",
+            category_condition(options)
+        );
+        let scope = scope_pattern(options);
+        let query_params: [&dyn ToSql; 2] = [&search_pattern, &scope];
+        let started = Instant::now();
+        let count: i64 = conn
+            .query_row(&sql, &query_params[..], |row| row.get(0))
+            .context("Failed to count matching files")?;
+        Self::observe_query(&conn, &sql, &query_params, started);
+
+        Ok(count.max(0) as usize)
+    }
+
+    fn search_files_paged(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FileRecord>> {
+        let search_pattern = format!("%{}%", query);
+
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 排序列来自枚举白名单，最后按 id 排序保证分页结果稳定
+        let order_by = match options.sort {
+            Some(sort) => format!(
+                "{} {}, id",
+                sort.column.as_str(),
+                if sort.ascending { "ASC" } else { "DESC" }
+            ),
+            None => "id".to_string(),
+        };
+        let sql = format!(
+            "SELECT id, path, size, etag, modified_time, file_type, name
+             FROM video
+             WHERE path LIKE ?1 AND path LIKE ?4 ESCAPE '\\'{}
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            category_condition(options),
+            order_by
+        );
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .context("Failed to prepare paged search statement")?;
+
+        let (limit, offset, scope) = (limit as i64, offset as i64, scope_pattern(options));
+        let query_params: [&dyn ToSql; 4] = [&search_pattern, &limit, &offset, &scope];
+        let started = Instant::now();
+        let file_iter = stmt
+            .query_map(&query_params[..], Self::read_file_record)
+            .context("Failed to execute paged search query")?;
+
+        let mut results = Vec::new();
+        for file in file_iter {
+            results.push(file.context("Failed to map file record")?);
+        }
+        Self::observe_query(&conn, &sql, &query_params, started);
+
+        Ok(results)
+    }
+
+    fn random_file(&self, query: &str, options: &SearchOptions) -> Result<Option<FileRecord>> {
+        // 有筛选条件时需要扫描匹配的记录，使用统计后按偏移读取的默认实现
+        if !query.trim().is_empty() || options.scope_prefix().is_some() || !options.categories.is_empty() {
+            return random_file_by_offset(self, query, options);
+        }
+
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 不加条件时在 id 范围内取随机值，读取不小于它的第一条记录，只需两次主键查找。
+        // 删除记录留下的空洞之后的记录被选中的概率稍高，对随机浏览没有影响
+        let (min_id, max_id): (Option<i64>, Option<i64>) = conn
+            .query_row("SELECT MIN(id), MAX(id) FROM video", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to read id range")?;
+        let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+            return Ok(None);
+        };
+        let pivot = fastrand::i64(min_id..=max_id);
+
+        let sql = "SELECT id, path, size, etag, modified_time, file_type, name
+             FROM video
+             WHERE id >= ?1
+             ORDER BY id
+             LIMIT 1";
+        let query_params: [&dyn ToSql; 1] = [&pivot];
+        let started = Instant::now();
+        let record = conn
+            .query_row(sql, &query_params[..], Self::read_file_record)
+            .optional()
+            .context("Failed to read random file")?;
+        Self::observe_query(&conn, sql, &query_params, started);
+
+        Ok(record)
+    }
+
+    fn find_file(&self, id: i64) -> Result<Option<FileRecord>> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let sql = "SELECT id, path, size, etag, modified_time, file_type, name FROM video WHERE id = ?1";
+        let query_params: [&dyn ToSql; 1] = [&id];
+        let started = Instant::now();
+        let record = conn
+            .query_row(sql, &query_params[..], Self::read_file_record)
+            .optional()
+            .context("Failed to find file record")?;
+        Self::observe_query(&conn, sql, &query_params, started);
+
+        Ok(record)
+    }
+
     fn search_field(&self, field: &str, query: &str) -> Result<Vec<FileRecord>> {
         let search_pattern = format!("%{}%", query);
 
@@ -212,70 +567,7 @@ impl Database for SqliteDatabase {
             .context("Failed to prepare search statement")?;
 
         let file_iter = stmt
-            .query_map(params![search_pattern], |row| {
-                // 获取所有字段的原始值用于调试
-                let id: i64 = row.get(0)?;
-                let path: String = row.get(1)?;
-                
-                // 安全地获取 size 字段，作为 u64 获取
-                let size_value: Result<i64, _> = row.get(2);
-                let size = match size_value {
-                    Ok(s) => {
-                        debug!("Got size as i64: {} for file: {}", s, path);
-                        if s < 0 {
-                            debug!("Negative size detected: {}, converting to positive", s);
-                            s as u64
-                        } else {
-                            s as u64
-                        }
-                    }
-                    Err(e) => {
-                        debug!("Failed to get size for file {}: {}, using 0", path, e);
-                        0u64
-                    }
-                };
-                
-                let etag: String = row.get(3)?;
-                
-                // 安全地获取 modified_time 字段
-                let modified_time_value: Result<i64, _> = row.get(4);
-                let modified_time = match modified_time_value {
-                    Ok(t) => {
-                        debug!("Got modified_time as i64: {} for file: {}", t, path);
-                        t
-                    }
-                    Err(_) => {
-                        // 如果无法作为 i64 获取，尝试作为字符串然后解析
-                        let time_str: Result<String, _> = row.get(4);
-                        match time_str {
-                            Ok(s) => {
-                                debug!("Got modified_time as string: '{}' for file: {}", s, path);
-                                s.parse::<i64>().unwrap_or(0)
-                            }
-                            Err(e) => {
-                                debug!("Failed to get modified_time for file {}: {}, using 0", path, e);
-                                0
-                            }
-                        }
-                    }
-                };
-                
-                let file_type: String = row.get(5)?;
-                let name: String = row.get(6)?;
-                
-                debug!("Creating FileRecord: id={}, name={}, path={}, size='{}', etag={}, modified_time={}, file_type={}",
-                       id, name, path, size, etag, modified_time, file_type);
-                
-                Ok(FileRecord {
-                    id,
-                    path,
-                    size,
-                    etag,
-                    modified_time,
-                    file_type,
-                    name,
-                })
-            })
+            .query_map(params![search_pattern], Self::read_file_record)
             .context("Failed to execute search query")?;
 
         let mut results = Vec::new();
@@ -286,6 +578,402 @@ impl Database for SqliteDatabase {
         Ok(results)
     }
 
+    fn is_writable(&self) -> bool {
+        self.pool
+            .get()
+            .ok()
+            .and_then(|conn| conn.is_readonly(rusqlite::DatabaseName::Main).ok())
+            .map(|readonly| !readonly)
+            .unwrap_or(false)
+    }
+
+    fn insert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 整批在一个事务中写入
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO video (name, path, size, etag, modified_time, file_type)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )
+                .context("Failed to prepare insert statement")?;
+            for record in records {
+                stmt.execute(params![
+                    record.name,
+                    record.path,
+                    record.size as i64,
+                    record.etag,
+                    normalize_timestamp(record.modified_time),
+                    record.file_type
+                ])
+                .context("Failed to insert file record")?;
+            }
+        }
+        tx.commit().context("Failed to commit inserted records")?;
+
+        debug!("插入记录: {} 条", records.len());
+        Ok(records.len())
+    }
+
+    fn delete_file(&self, id: i64) -> Result<()> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let affected = conn
+            .execute("DELETE FROM video WHERE id = ?1", params![id])
+            .context("Failed to delete file record")?;
+        if affected == 0 {
+            anyhow::bail!("File record {} not found", id);
+        }
+
+        debug!("删除记录: id={}", id);
+        Ok(())
+    }
+
+    fn delete_files(&self, ids: &[i64]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let mut deleted = 0;
+        {
+            let mut stmt = tx
+                .prepare("DELETE FROM video WHERE id = ?1")
+                .context("Failed to prepare delete statement")?;
+            for id in ids {
+                deleted += stmt
+                    .execute(params![id])
+                    .context("Failed to delete file record")?;
+            }
+        }
+        tx.commit().context("Failed to commit deleted records")?;
+
+        debug!("删除记录: {} 条", deleted);
+        Ok(deleted)
+    }
+
+    fn restore_files(&self, records: &[FileRecord], verified: &HashMap<String, bool>) -> Result<Vec<i64>> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 尽量恢复原来的 id；导入或旧的数据库不一定使用 AUTOINCREMENT，id 可能已分配给新记录，
+        // 此时由数据库分配新的 id，撤销仍然生效
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let has_verified = has_column(&tx, "verified")?;
+        let mut ids = Vec::with_capacity(records.len());
+        {
+            let sql = if has_verified {
+                "INSERT INTO video (id, name, path, size, etag, modified_time, file_type, verified)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+            } else {
+                "INSERT INTO video (id, name, path, size, etag, modified_time, file_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+            };
+            let mut stmt = tx.prepare(sql).context("Failed to prepare restore statement")?;
+            let mut id_taken = tx
+                .prepare("SELECT 1 FROM video WHERE id = ?1")
+                .context("Failed to prepare id lookup")?;
+            for record in records {
+                let id = match record.id {
+                    0 => None,
+                    id if id_taken.exists(params![id]).context("Failed to look up record id")? => {
+                        debug!("id {} 已被占用，恢复 {} 时分配新 id", id, record.path);
+                        None
+                    }
+                    id => Some(id),
+                };
+                let size = record.size as i64;
+                let modified_time = normalize_timestamp(record.modified_time);
+                let verified = verified.get(&record.path).map(|matched| *matched as i64);
+                let mut values: Vec<&dyn ToSql> = vec![
+                    &id,
+                    &record.name,
+                    &record.path,
+                    &size,
+                    &record.etag,
+                    &modified_time,
+                    &record.file_type,
+                ];
+                if has_verified {
+                    values.push(&verified);
+                }
+                stmt.execute(&values[..]).context("Failed to restore file record")?;
+                ids.push(tx.last_insert_rowid());
+            }
+        }
+        tx.commit().context("Failed to commit restored records")?;
+
+        debug!("恢复记录: {} 条", ids.len());
+        Ok(ids)
+    }
+
+    fn etags_under(&self, prefix: &str) -> Result<HashMap<String, String>> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 前缀中的 LIKE 通配符需要转义
+        let escaped = escape_like(prefix);
+        let mut stmt = conn
+            .prepare("SELECT path, etag FROM video WHERE path LIKE ?1 ESCAPE '\\'")
+            .context("Failed to prepare etag query")?;
+        let rows = stmt
+            .query_map(params![format!("{}%", escaped)], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("Failed to query etags")?;
+
+        let mut etags = HashMap::new();
+        for row in rows {
+            let (path, etag) = row.context("Failed to read etag row")?;
+            etags.insert(path, etag);
+        }
+        Ok(etags)
+    }
+
+    fn upsert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        // 路径已存在时更新，否则插入，整批在一个事务中写入
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        {
+            let mut update = tx
+                .prepare(
+                    "UPDATE video SET name = ?1, size = ?3, etag = ?4, modified_time = ?5, file_type = ?6
+                     WHERE path = ?2",
+                )
+                .context("Failed to prepare update statement")?;
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO video (name, path, size, etag, modified_time, file_type)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )
+                .context("Failed to prepare insert statement")?;
+            for record in records {
+                let values = params![
+                    record.name,
+                    record.path,
+                    record.size as i64,
+                    record.etag,
+                    normalize_timestamp(record.modified_time),
+                    record.file_type
+                ];
+                let updated = update
+                    .execute(values)
+                    .context("Failed to update file record")?;
+                if updated == 0 {
+                    insert.execute(values).context("Failed to insert file record")?;
+                }
+            }
+        }
+        tx.commit().context("Failed to commit upserted records")?;
+
+        debug!("写入记录: {} 条", records.len());
+        Ok(records.len())
+    }
+
+    fn delete_paths(&self, paths: &[String]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let mut deleted = 0;
+        {
+            let mut stmt = tx
+                .prepare("DELETE FROM video WHERE path = ?1")
+                .context("Failed to prepare delete statement")?;
+            for path in paths {
+                deleted += stmt
+                    .execute(params![path])
+                    .context("Failed to delete file record")?;
+            }
+        }
+        tx.commit().context("Failed to commit deleted records")?;
+
+        debug!("删除记录: {} 条", deleted);
+        Ok(deleted)
+    }
+
+    fn rename_files(&self, renames: &[RenameEntry]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let mut renamed = 0;
+        {
+            let mut stmt = tx
+                .prepare("UPDATE video SET name = ?1, path = ?2 WHERE id = ?3")
+                .context("Failed to prepare rename statement")?;
+            // 按 id 匹配，链式重命名（1→2、2→3）和同路径的其他记录互不影响
+            for entry in renames {
+                renamed += stmt
+                    .execute(params![entry.new_name, entry.new_path, entry.id])
+                    .context("Failed to rename file record")?;
+            }
+        }
+        tx.commit().context("Failed to commit renamed records")?;
+
+        debug!("重命名记录: {} 条", renamed);
+        Ok(renamed)
+    }
+
+    fn set_verified(&self, results: &[(String, bool)]) -> Result<usize> {
+        let mut conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx
+                .prepare("UPDATE video SET verified = ?1 WHERE path = ?2")
+                .context("Failed to prepare verified statement")?;
+            for (path, matched) in results {
+                updated += stmt
+                    .execute(params![*matched as i64, path])
+                    .context("Failed to update verified column")?;
+            }
+        }
+        tx.commit().context("Failed to commit verification results")?;
+
+        debug!("更新校验结果: {} 条", updated);
+        Ok(updated)
+    }
+
+    fn verification_status(&self, paths: &[String]) -> Result<HashMap<String, bool>> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let mut stmt = conn
+            .prepare("SELECT verified FROM video WHERE path = ?1 AND verified IS NOT NULL")
+            .context("Failed to prepare verification query")?;
+        let mut status = HashMap::new();
+        for path in paths {
+            let verified: Option<i64> = stmt
+                .query_row(params![path], |row| row.get(0))
+                .optional()
+                .context("Failed to query verification status")?;
+            if let Some(verified) = verified {
+                status.insert(path.clone(), verified != 0);
+            }
+        }
+        Ok(status)
+    }
+
+    fn run_maintenance(&self, operation: &MaintenanceOperation) -> Result<usize> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        debug!("执行维护: {}", operation.label());
+        match operation {
+            MaintenanceOperation::Vacuum => {
+                conn.execute_batch("VACUUM").context("Failed to vacuum database")?;
+                Ok(0)
+            }
+            MaintenanceOperation::Analyze => {
+                conn.execute_batch("ANALYZE").context("Failed to analyze database")?;
+                Ok(0)
+            }
+            MaintenanceOperation::Reindex => {
+                conn.execute_batch("REINDEX").context("Failed to reindex database")?;
+                Ok(0)
+            }
+            MaintenanceOperation::CleanupOrphans { roots } => {
+                let mut removed = 0;
+                for root in roots {
+                    let root = root.trim_end_matches(['/', '\\']);
+                    if root.is_empty() {
+                        continue;
+                    }
+                    // 目录本身和其下的所有文件，LIKE 通配符需要转义
+                    let escaped = escape_like(root);
+                    removed += conn
+                        .execute(
+                            "DELETE FROM video WHERE path = ?1 OR path LIKE ?2 ESCAPE '\\' OR path LIKE ?3 ESCAPE '\\'",
+                            params![root, format!("{}/%", escaped), format!("{}\\\\%", escaped)],
+                        )
+                        .context("Failed to delete orphaned records")?;
+                }
+                debug!("清理孤立记录: {} 条", removed);
+                Ok(removed)
+            }
+        }
+    }
+
+    fn query_read_only(&self, sql: &str, limit: usize) -> Result<QueryResult> {
+        let sql = sql_console::validate(sql)?;
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let mut stmt = conn.prepare(sql).context("查询语句无效")?;
+        // 白名单之外再由 SQLite 确认语句不会写入，例如 WITH ... DELETE
+        if !stmt.readonly() {
+            anyhow::bail!("只允许执行只读查询");
+        }
+
+        let columns: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+        // 结果列包含文件记录的全部字段时，按列名找到每个字段的位置
+        let record_columns: Option<Vec<usize>> = FILE_RECORD_COLUMNS
+            .iter()
+            .map(|name| columns.iter().position(|column| column.eq_ignore_ascii_case(name)))
+            .collect();
+        let mut mappable = record_columns.is_some();
+        let mut records = Vec::new();
+        let mut result = QueryResult {
+            columns,
+            ..QueryResult::default()
+        };
+
+        let mut rows = stmt.query([]).context("Failed to execute query")?;
+        while let Some(row) = rows.next().context("Failed to read query result")? {
+            if result.rows.len() >= limit {
+                result.truncated = true;
+                break;
+            }
+            let values = (0..result.columns.len())
+                .map(|index| row.get_ref(index).map(value_text))
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .context("Failed to read query result")?;
+            if let (true, Some(indices)) = (mappable, record_columns.as_deref()) {
+                // 类型不符时（例如 size 为文本）只显示为表格
+                match read_record_at(row, indices) {
+                    Ok(record) => records.push(record),
+                    Err(_) => mappable = false,
+                }
+            }
+            result.rows.push(values);
+        }
+        result.records = mappable.then_some(records);
+        debug!("Custom query returned {} rows", result.rows.len());
+        Ok(result)
+    }
+
     fn get_search_fields(&self) -> Vec<String> {
         vec![
             "id",
@@ -303,6 +991,89 @@ impl Database for SqliteDatabase {
 }
 
 impl SqliteDatabase {
+    /// 诊断模式下，查询耗时超过阈值时记录执行计划
+    ///
+    /// # Arguments
+    /// * `conn` - 执行查询的连接，用于获取执行计划
+    /// * `sql` - 执行的 SQL
+    /// * `query_params` - 查询参数
+    /// * `started` - 查询开始时间
+    fn observe_query(conn: &Connection, sql: &str, query_params: &[&dyn ToSql], started: Instant) {
+        let elapsed = started.elapsed();
+        let diagnostics = shared_query_diagnostics();
+        if !diagnostics.should_record(elapsed) {
+            return;
+        }
+
+        // 执行计划每行的第 4 列为步骤说明，例如 "SCAN video" 或 "SEARCH video USING INDEX ..."
+        let plan = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .and_then(|mut stmt| {
+                stmt.query_map(query_params, |row| row.get::<_, String>(3))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .unwrap_or_else(|e| {
+                debug!("Failed to explain query: {}", e);
+                Vec::new()
+            });
+        let query = SlowQuery {
+            sql: sql.to_string(),
+            params: query_params.iter().map(|param| describe_param(*param)).collect(),
+            elapsed,
+            plan,
+            recorded_at: chrono::Utc::now().timestamp(),
+        };
+        warn!("Slow query: {}", query.summary());
+        diagnostics.record(query);
+    }
+
+    /// 将查询结果行转换为文件记录
+    ///
+    /// 查询列顺序必须为: id, path, size, etag, modified_time, file_type, name
+    fn read_file_record(row: &rusqlite::Row) -> rusqlite::Result<FileRecord> {
+        // 获取所有字段的原始值用于调试
+        let id: i64 = row.get(0)?;
+        let path: String = row.get(1)?;
+        
+        // 安全地获取 size 字段，作为 u64 获取
+        let size_value: Result<i64, _> = row.get(2);
+        let size = match size_value {
+            Ok(s) => {
+                debug!("Got size as i64: {} for file: {}", s, path);
+                if s < 0 {
+                    debug!("Negative size detected: {}, converting to positive", s);
+                    s as u64
+                } else {
+                    s as u64
+                }
+            }
+            Err(e) => {
+                debug!("Failed to get size for file {}: {}, using 0", path, e);
+                0u64
+            }
+        };
+        
+        let etag: String = row.get(3)?;
+        
+        let modified_time = read_timestamp(row, 4)?;
+        
+        let file_type: String = row.get(5)?;
+        let name: String = row.get(6)?;
+        
+        debug!("Creating FileRecord: id={}, name={}, path={}, size='{}', etag={}, modified_time={}, file_type={}",
+               id, name, path, size, etag, modified_time, file_type);
+        
+        Ok(FileRecord {
+            id,
+            path,
+            size,
+            etag,
+            modified_time,
+            file_type,
+            name,
+        })
+    }
+
     /// 添加示例数据到数据库（使用提供的连接）
     fn add_sample_data_with_conn(conn: &rusqlite::Connection) -> Result<()> {
         debug!("开始添加示例数据...");
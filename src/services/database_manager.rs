@@ -3,17 +3,58 @@
 //! 提供数据库实例的动态创建和管理功能
 
 use anyhow::{Result, Context};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc, Mutex};
 use std::collections::HashMap;
-use crate::models::config::{AppConfig, DatabaseConfig};
-use crate::models::database::Database;
-use crate::services::database::{sqlite::SqliteDatabase, connector::DatabaseConnectorFactory};
-use tracing::{debug, info};
+use std::path::{Path, PathBuf};
+use std::thread;
+use crate::models::config::{AppConfig, DatabaseConfig, ScanConfig};
+use crate::models::database::{
+    CompareKey, Database, DatabaseDiff, FileRecord, MaintenanceOperation, QueryResult, SearchOptions,
+};
+use crate::services::checksum::{ChecksumVerifier, VerifySummary};
+use crate::services::credentials;
+use crate::services::indexer::{IndexProgress, IndexSummary, Indexer};
+use crate::services::journal::{IndexOperation, OperationJournal};
+use crate::utils::paths;
+use crate::services::database::{sqlite::{self, SqliteDatabase}, connector::{DatabaseConnector, DatabaseConnectorFactory, SqliteConnector}};
+use tracing::{debug, error, info, warn};
+
+/// 共享的数据库实例
+pub type SharedDatabase = Arc<Mutex<dyn Database>>;
+
+/// 当前数据库变化时的回调，参数为新数据库的索引和配置
+pub type DatabaseListener = Box<dyn Fn(usize, &DatabaseConfig) + Send>;
+
+/// 维护进度回调，参数为正在执行的操作序号（从 1 开始）、总数和操作
+pub type MaintenanceProgress = Box<dyn FnMut(usize, usize, &MaintenanceOperation) + Send>;
+
+/// 索引进度回调，每写入一批调用一次
+pub type IndexProgressCallback = Box<dyn FnMut(&IndexProgress) + Send>;
+
+/// 校验进度回调，参数为已处理数和总数
+pub type VerifyProgress = Box<dyn FnMut(usize, usize) + Send>;
+
+/// 命令结果的回调，在数据库管理线程或执行耗时命令的工作线程中调用
+pub type Reply<T> = Box<dyn FnOnce(T) + Send>;
+
+/// 维护结果
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceReport {
+    pub orphans_removed: usize,
+    pub size_before: Option<u64>, // 数据库文件大小（字节），非文件数据库为 None
+    pub size_after: Option<u64>,
+}
+
+/// 备份文件的扩展名，不会被数据库扫描当作 .db 文件发现
+pub const BACKUP_EXTENSION: &str = "bak";
 
 /// 数据库管理器
 pub struct DatabaseManager {
-    current_database: Arc<Mutex<dyn Database>>,
+    current_database: SharedDatabase,
     config: Arc<Mutex<AppConfig>>,
+    listeners: Vec<DatabaseListener>,
+    journal: OperationJournal, // 当前数据库的写入记录，切换数据库后清空
 }
 
 impl DatabaseManager {
@@ -33,33 +74,327 @@ impl DatabaseManager {
         Ok(Self {
             current_database: current_db,
             config,
+            listeners: Vec::new(),
+            journal: OperationJournal::default(),
         })
     }
     
+    /// 使用已创建的数据库实例作为当前数据库，不扫描数据库目录
+    ///
+    /// 用于测试（例如 `MockDatabase`）和嵌入到其他程序中；切换数据库时仍按配置创建实例
+    pub fn with_database(config: Arc<Mutex<AppConfig>>, database: SharedDatabase) -> Self {
+        Self {
+            current_database: database,
+            config,
+            listeners: Vec::new(),
+            journal: OperationJournal::default(),
+        }
+    }
+
     /// 获取当前数据库实例
-    pub fn get_current_database(&self) -> Arc<Mutex<dyn Database>> {
+    pub fn get_current_database(&self) -> SharedDatabase {
         self.current_database.clone()
     }
     
+    /// 注册当前数据库变化的回调
+    ///
+    /// 切换数据库或移除当前数据库后调用，回调在调用切换的线程中执行
+    pub fn on_database_changed(&mut self, listener: impl Fn(usize, &DatabaseConfig) + Send + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+    
+    /// 通知所有回调当前数据库已变化
+    fn notify_database_changed(&self) {
+        let (index, config) = {
+            let config = self.config.lock().unwrap();
+            (config.current_database_index(), config.database.clone())
+        };
+        for listener in &self.listeners {
+            listener(index, &config);
+        }
+    }
+    
     /// 切换到指定数据库
     pub fn switch_database(&mut self, index: usize) -> Result<()> {
-        let mut config = self.config.lock().unwrap();
-        
-        // 切换到新的数据库配置
-        config.switch_database(index)?;
-        
-        // 创建新的数据库实例
-        let new_db = Self::create_database(&config.database)?;
-        
-        // 更新当前数据库
-        self.current_database = new_db;
-        
-        info!("Switched to database: {} (index: {})", 
-              config.database.name, index);
+        {
+            let mut config = self.config.lock().unwrap();
+            
+            // 切换到新的数据库配置
+            config.switch_database(index)?;
+            
+            // 创建新的数据库实例
+            let new_db = Self::create_database(&config.database)?;
+            
+            // 更新当前数据库，之前的写入记录不再适用
+            self.current_database = new_db;
+            self.journal.clear();
+            
+            info!("Switched to database: {} (index: {})", 
+                  config.database.name, index);
+        } // 释放锁，回调中可能再次读取配置
         
+        self.notify_database_changed();
         Ok(())
     }
     
+    /// 在当前数据库中执行写入并记录到操作日志
+    ///
+    /// # Arguments
+    /// * `operation` - 插入、删除或重命名
+    ///
+    /// # Returns
+    /// * `Result<usize>` - 影响的记录数，失败时不记录
+    pub fn apply(&mut self, operation: IndexOperation) -> Result<usize> {
+        let (affected, applied) = operation.execute(&*self.current_database.lock().unwrap())?;
+        info!("Applied index operation: {}", applied.describe());
+        self.journal.record(applied);
+        Ok(affected)
+    }
+    
+    /// 撤销最近一次写入
+    ///
+    /// # Returns
+    /// * `Result<Option<String>>` - 撤销的操作描述，没有可撤销的操作时为 None
+    pub fn undo(&mut self) -> Result<Option<String>> {
+        let Some(operation) = self.journal.take_undo() else {
+            return Ok(None);
+        };
+        let result = operation.inverse().execute(&*self.current_database.lock().unwrap());
+        match result {
+            Ok((_, undone)) => {
+                let description = operation.describe();
+                info!("Undid index operation: {}", description);
+                self.journal.push_redo(undone.inverse());
+                Ok(Some(description))
+            }
+            Err(e) => {
+                self.journal.push_undo(operation);
+                Err(e)
+            }
+        }
+    }
+    
+    /// 重做最近一次撤销的写入
+    ///
+    /// # Returns
+    /// * `Result<Option<String>>` - 重做的操作描述，没有可重做的操作时为 None
+    pub fn redo(&mut self) -> Result<Option<String>> {
+        let Some(operation) = self.journal.take_redo() else {
+            return Ok(None);
+        };
+        let result = operation.execute(&*self.current_database.lock().unwrap());
+        match result {
+            Ok((_, redone)) => {
+                let description = operation.describe();
+                info!("Redid index operation: {}", description);
+                self.journal.push_undo(redone);
+                Ok(Some(description))
+            }
+            Err(e) => {
+                self.journal.push_redo(operation);
+                Err(e)
+            }
+        }
+    }
+    
+    /// 对当前数据库依次执行维护操作
+    ///
+    /// 清理孤立记录后操作日志中的记录可能已不存在，日志会被清空
+    ///
+    /// # Arguments
+    /// * `operations` - 维护操作
+    /// * `progress` - 每个操作开始前调用
+    pub fn run_maintenance(
+        &mut self,
+        operations: &[MaintenanceOperation],
+        progress: impl FnMut(usize, usize, &MaintenanceOperation),
+    ) -> Result<MaintenanceReport> {
+        if Self::removes_records(operations) {
+            self.journal.clear();
+        }
+        Self::maintain(
+            &self.current_database,
+            &self.get_current_database_config(),
+            operations,
+            progress,
+        )
+    }
+
+    /// 维护操作中是否有清理孤立记录，会删除操作日志引用的记录
+    fn removes_records(operations: &[MaintenanceOperation]) -> bool {
+        operations
+            .iter()
+            .any(|operation| matches!(operation, MaintenanceOperation::CleanupOrphans { .. }))
+    }
+
+    /// 依次执行维护操作，每个操作只在执行期间锁定数据库
+    fn maintain(
+        database: &SharedDatabase,
+        db_config: &DatabaseConfig,
+        operations: &[MaintenanceOperation],
+        mut progress: impl FnMut(usize, usize, &MaintenanceOperation),
+    ) -> Result<MaintenanceReport> {
+        let file_size = || {
+            (db_config.db_type == "sqlite")
+                .then(|| std::fs::metadata(&db_config.connection_string).ok().map(|m| m.len()))
+                .flatten()
+        };
+
+        let mut report = MaintenanceReport {
+            size_before: file_size(),
+            ..MaintenanceReport::default()
+        };
+        for (index, operation) in operations.iter().enumerate() {
+            progress(index + 1, operations.len(), operation);
+            info!("Running maintenance on {}: {}", db_config.name, operation.label());
+            let affected = database.lock().unwrap().run_maintenance(operation)?;
+            if let MaintenanceOperation::CleanupOrphans { .. } = operation {
+                report.orphans_removed += affected;
+            }
+        }
+        report.size_after = file_size();
+        Ok(report)
+    }
+    
+    /// 操作日志
+    pub fn journal(&self) -> &OperationJournal {
+        &self.journal
+    }
+    
+    /// 获取指定数据库的配置，只有 SQLite 数据库支持备份、恢复和比较
+    fn sqlite_config(&self, index: usize) -> Result<DatabaseConfig> {
+        let config = self.config.lock().unwrap();
+        let db_config = config
+            .multi_database
+            .databases
+            .get(index)
+            .with_context(|| format!("Database index {} out of range", index))?;
+        if db_config.db_type != "sqlite" {
+            anyhow::bail!("{} 数据库不支持此操作: {}", db_config.db_type, db_config.name);
+        }
+        Ok(db_config.clone())
+    }
+    
+    /// 使用 SQLite 在线备份复制指定数据库，备份期间数据库仍可使用
+    ///
+    /// # Arguments
+    /// * `index` - 数据库索引
+    /// * `dest` - 备份文件路径
+    pub fn backup_database(&self, index: usize, dest: &Path) -> Result<()> {
+        Self::backup_file(&self.sqlite_config(index)?, dest)
+    }
+
+    fn backup_file(db_config: &DatabaseConfig, dest: &Path) -> Result<()> {
+        SqliteDatabase::new(&db_config.connection_string)?.backup_to(dest)
+    }
+    
+    /// 用备份文件恢复指定数据库
+    ///
+    /// 恢复前先将现有数据备份到备份目录，恢复当前数据库后清空操作日志并通知回调
+    ///
+    /// # Arguments
+    /// * `index` - 数据库索引
+    /// * `source` - 备份文件路径
+    ///
+    /// # Returns
+    /// * `Result<PathBuf>` - 恢复前自动创建的备份
+    pub fn restore_database(&mut self, index: usize, source: &Path) -> Result<PathBuf> {
+        let safety_copy = Self::restore_file(&self.sqlite_config(index)?, source)?;
+        self.reload_database(index)?;
+        Ok(safety_copy)
+    }
+
+    /// 先备份现有数据再用备份文件覆盖，不修改当前数据库实例
+    fn restore_file(db_config: &DatabaseConfig, source: &Path) -> Result<PathBuf> {
+        let database = SqliteDatabase::new(&db_config.connection_string)?;
+        let safety_copy = Self::default_backup_path(db_config);
+        database
+            .backup_to(&safety_copy)
+            .context("Failed to back up database before restore")?;
+        database.restore_from(source)?;
+        Ok(safety_copy)
+    }
+
+    /// 指定数据库是当前数据库时重新加载，清空操作日志并通知回调
+    ///
+    /// # Arguments
+    /// * `index` - 数据库索引，不是当前数据库时不做任何操作
+    pub fn reload_database(&mut self, index: usize) -> Result<()> {
+        if index != self.get_current_database_index() {
+            return Ok(());
+        }
+        self.current_database = Self::create_database(&self.get_current_database_config())?;
+        self.journal.clear();
+        self.notify_database_changed();
+        Ok(())
+    }
+    
+    /// 比较两个 SQLite 数据库中的记录，用于核对镜像或判断哪个导出更新
+    ///
+    /// # Arguments
+    /// * `left` - 左侧数据库索引
+    /// * `right` - 右侧数据库索引
+    /// * `key` - 按路径或 etag 匹配记录
+    pub fn compare_databases(&self, left: usize, right: usize, key: CompareKey) -> Result<DatabaseDiff> {
+        Self::compare_files(&self.sqlite_config(left)?, &self.sqlite_config(right)?, key)
+    }
+
+    fn compare_files(left: &DatabaseConfig, right: &DatabaseConfig, key: CompareKey) -> Result<DatabaseDiff> {
+        sqlite::compare_databases(
+            Path::new(&left.connection_string),
+            Path::new(&right.connection_string),
+            key,
+        )
+    }
+
+    /// 当前数据库和数据库列表的快照
+    pub fn snapshot(&self) -> DatabaseSnapshot {
+        DatabaseSnapshot {
+            current: self.get_current_database_config(),
+            current_index: self.get_current_database_index(),
+            databases: self.get_database_list(),
+            next_undo: self.journal.next_undo(),
+            next_redo: self.journal.next_redo(),
+            writable: self.current_database.lock().unwrap().is_writable(),
+        }
+    }
+    
+    /// 数据库的默认备份路径：备份目录下的 `<数据库文件名>-<时间>.bak`
+    pub fn default_backup_path(db_config: &DatabaseConfig) -> PathBuf {
+        paths::backup_dir().join(format!(
+            "{}-{}.{}",
+            Self::backup_prefix(db_config),
+            chrono::Local::now().format("%Y%m%d-%H%M%S%3f"),
+            BACKUP_EXTENSION
+        ))
+    }
+    
+    /// 备份目录中该数据库最近的备份
+    pub fn latest_backup(db_config: &DatabaseConfig) -> Option<PathBuf> {
+        let prefix = format!("{}-", Self::backup_prefix(db_config));
+        std::fs::read_dir(paths::backup_dir())
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().and_then(|ext| ext.to_str()) == Some(BACKUP_EXTENSION)
+                    && path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(&prefix))
+            })
+            // 文件名中的时间可以按字符串排序
+            .max()
+    }
+    
+    /// 备份文件名前缀，使用数据库文件名，不同目录的同名数据库共用前缀
+    fn backup_prefix(db_config: &DatabaseConfig) -> String {
+        Path::new(&db_config.connection_string)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| db_config.name.clone())
+    }
+    
     /// 获取当前数据库信息
     pub fn get_current_database_info(&self) -> (String, String) {
         let config = self.config.lock().unwrap();
@@ -67,6 +402,11 @@ impl DatabaseManager {
         (db_config.name.clone(), db_config.db_type.clone())
     }
     
+    /// 获取当前数据库配置
+    pub fn get_current_database_config(&self) -> DatabaseConfig {
+        self.config.lock().unwrap().database.clone()
+    }
+    
     /// 获取数据库列表
     pub fn get_database_list(&self) -> Vec<(String, String, usize)> {
         let config = self.config.lock().unwrap();
@@ -84,7 +424,7 @@ impl DatabaseManager {
     }
     
     /// 根据配置创建数据库实例
-    fn create_database(db_config: &DatabaseConfig) -> Result<Arc<Mutex<dyn Database>>> {
+    fn create_database(db_config: &DatabaseConfig) -> Result<SharedDatabase> {
         debug!("Creating database instance: {} ({})", db_config.name, db_config.db_type);
         
         match db_config.db_type.as_str() {
@@ -111,12 +451,29 @@ impl DatabaseManager {
     /// 移除数据库配置
     pub fn remove_database(&mut self, index: usize) -> Result<()> {
         let mut app_config = self.config.lock().unwrap();
+        let credential_id = app_config
+            .multi_database
+            .databases
+            .get(index)
+            .and_then(|db| db.credential_id.clone());
+        // 移除后当前索引会前移，需要在移除前判断是否移除了当前数据库
+        let removed_current = index == app_config.current_database_index();
         app_config.remove_database(index)?;
         
+        // 同时删除密钥环中保存的密码
+        if let Some(credential_id) = credential_id {
+            if let Err(e) = credentials::delete_password(&credential_id) {
+                warn!("Failed to delete credential {}: {}", credential_id, e);
+            }
+        }
+        
         // 如果移除了当前使用的数据库，需要重新加载当前数据库
-        if index == self.get_current_database_index() {
+        if removed_current {
             let current_db = Self::create_database(&app_config.database)?;
             self.current_database = current_db;
+            self.journal.clear();
+            drop(app_config);
+            self.notify_database_changed();
         }
         
         Ok(())
@@ -129,12 +486,69 @@ impl DatabaseManager {
             .context("Failed to save configuration")
     }
     
+    /// 扫描配置中的目录，发现 SQLite 数据库文件
+    ///
+    /// # Arguments
+    /// * `scan` - 扫描目录和递归深度
+    pub fn discover_sqlite_databases(scan: &ScanConfig) -> Vec<DatabaseConfig> {
+        let connector = SqliteConnector::new();
+        let mut found: Vec<DatabaseConfig> = Vec::new();
+        
+        for path in scan.scan_directories() {
+            let mut connection_info = HashMap::new();
+            connection_info.insert("path".to_string(), path.to_string_lossy().to_string());
+            connection_info.insert("max_depth".to_string(), scan.max_depth.to_string());
+            
+            let databases = match connector.get_database_list(&connection_info) {
+                Ok(databases) => databases,
+                Err(e) => {
+                    info!("Failed to scan {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            for db_info in databases {
+                let config = connector.create_database_config(
+                    &db_info.name,
+                    &db_info.connection_string,
+                    db_info.description
+                );
+                if !found.iter().any(|existing| Self::same_location(existing, &config)) {
+                    found.push(config);
+                }
+            }
+        }
+        
+        found
+    }
+    
+    /// 判断手动添加的数据库中是否已有指向同一位置的数据库
+    fn contains_manual_database(app_config: &AppConfig, config: &DatabaseConfig) -> bool {
+        app_config
+            .multi_database
+            .databases
+            .iter()
+            .filter(|existing| !existing.auto_discovered)
+            .any(|existing| Self::same_location(existing, config))
+    }
+    
+    /// 判断两个数据库配置是否指向同一位置（文件路径按规范化后比较）
+    fn same_location(a: &DatabaseConfig, b: &DatabaseConfig) -> bool {
+        if a.db_type != b.db_type {
+            return false;
+        }
+        let canonical = |s: &str| {
+            std::fs::canonicalize(s).unwrap_or_else(|_| std::path::PathBuf::from(s))
+        };
+        a.connection_string == b.connection_string
+            || canonical(&a.connection_string) == canonical(&b.connection_string)
+    }
+    
     /// 扫描数据库目录，自动发现数据库
     fn scan_and_add_databases(app_config: &mut AppConfig) -> Result<()> {
         info!("Scanning for available databases...");
         
-        // 清空现有的多数据库配置（保留默认的）
-        app_config.multi_database.databases.clear();
+        // 手动添加的数据库保留，自动发现的数据库整体替换
+        let mut discovered: Vec<DatabaseConfig> = Vec::new();
         
         // 使用连接器工厂获取所有支持的连接器
         let connectors = DatabaseConnectorFactory::get_all_connectors();
@@ -143,45 +557,58 @@ impl DatabaseManager {
             let db_type = connector.get_db_type();
             info!("Scanning for {} databases...", db_type);
             
-            // 根据数据库类型设置不同的连接信息
-            let mut connection_info = HashMap::new();
+            // 根据数据库类型设置不同的连接信息，SQLite 扫描配置中的每个目录
+            let mut connection_infos = Vec::new();
             match db_type {
                 "sqlite" => {
-                    connection_info.insert("path".to_string(), ".".to_string());
+                    for path in app_config.scan.scan_directories() {
+                        let mut connection_info = HashMap::new();
+                        connection_info.insert("path".to_string(), path.to_string_lossy().to_string());
+                        connection_info.insert("max_depth".to_string(), app_config.scan.max_depth.to_string());
+                        connection_infos.push(connection_info);
+                    }
                 }
                 "mysql" => {
+                    let mut connection_info = HashMap::new();
                     connection_info.insert("host".to_string(), "localhost".to_string());
                     connection_info.insert("port".to_string(), "3306".to_string());
                     connection_info.insert("username".to_string(), "root".to_string());
                     connection_info.insert("password".to_string(), "".to_string());
+                    connection_infos.push(connection_info);
                 }
-                _ => {}
+                _ => connection_infos.push(HashMap::new()),
             }
             
             // 获取数据库列表
-            match connector.get_database_list(&connection_info) {
-                Ok(databases) => {
-                    info!("Found {} {} databases", databases.len(), db_type);
-                    for db_info in databases {
-                        let config = connector.create_database_config(
-                            &db_info.name,
-                            &db_info.connection_string,
-                            db_info.description
-                        );
-                        app_config.add_database(config);
+            for connection_info in &connection_infos {
+                match connector.get_database_list(connection_info) {
+                    Ok(databases) => {
+                        info!("Found {} {} databases", databases.len(), db_type);
+                        for db_info in databases {
+                            let config = connector.create_database_config(
+                                &db_info.name,
+                                &db_info.connection_string,
+                                db_info.description
+                            );
+                            // 同一文件可能在多个扫描目录中被发现，也可能已被手动添加
+                            let known = discovered.iter().any(|db| Self::same_location(db, &config))
+                                || Self::contains_manual_database(app_config, &config);
+                            if !known {
+                                discovered.push(config);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        info!("Failed to scan {} databases: {}", db_type, e);
                     }
-                }
-                Err(e) => {
-                    info!("Failed to scan {} databases: {}", db_type, e);
                 }
             }
         }
         
-        // 设置第一个发现的数据库为默认
+        app_config.replace_discovered_databases(discovered);
+        
         if !app_config.multi_database.databases.is_empty() {
-            app_config.multi_database.default_database = 0;
-            app_config.database = app_config.multi_database.databases[0].clone();
-            info!("Set default database to: {}", app_config.database.name);
+            info!("Current database: {}", app_config.database.name);
         } else {
             info!("No databases found, using existing configuration");
         }
@@ -190,9 +617,710 @@ impl DatabaseManager {
     }
     
     /// 刷新数据库列表
+    ///
+    /// 当前数据库不再存在时配置会回退到第一个数据库，此时重新加载当前数据库并通知回调
     pub fn refresh_database_list(&mut self) -> Result<()> {
         let mut config = self.config.lock().unwrap();
+        let previous = config.database.clone();
         Self::scan_and_add_databases(&mut config)?;
+
+        if previous.name != config.database.name || !Self::same_location(&previous, &config.database) {
+            info!("Current database {} is gone, switching to {}", previous.name, config.database.name);
+            self.current_database = Self::create_database(&config.database)?;
+            self.journal.clear();
+            drop(config);
+            self.notify_database_changed();
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// 发送给数据库管理线程的命令
+pub enum DatabaseCommand {
+    /// 在当前数据库中搜索
+    Search {
+        query: String,
+        options: SearchOptions,
+        limit: usize,
+        reply: Reply<Result<Vec<FileRecord>>>,
+    },
+    /// 在当前数据库中读取一页结果以及这些记录的校验状态
+    Page {
+        query: String,
+        options: SearchOptions,
+        offset: usize,
+        limit: usize,
+        reply: Reply<Result<ResultPage>>,
+    },
+    /// 统计当前数据库中匹配的记录数
+    Count {
+        query: String,
+        options: SearchOptions,
+        reply: Reply<Result<usize>>,
+    },
+    /// 读取当前数据库中记录的校验状态
+    Verification {
+        paths: Vec<String>,
+        reply: Reply<Result<HashMap<String, bool>>>,
+    },
+    /// 在当前数据库中执行只读查询，在工作线程中执行
+    Query {
+        sql: String,
+        limit: usize,
+        reply: Reply<Result<QueryResult>>,
+    },
+    /// 在当前数据库中随机选取一条匹配的记录
+    Random {
+        query: String,
+        options: SearchOptions,
+        reply: Reply<Result<Option<FileRecord>>>,
+    },
+    /// 在当前数据库中按 ID 查找记录
+    Find {
+        id: i64,
+        reply: Reply<Result<Option<FileRecord>>>,
+    },
+    /// 切换到指定数据库，返回新数据库的配置
+    Switch {
+        index: usize,
+        reply: Reply<Result<DatabaseConfig>>,
+    },
+    /// 重新扫描数据库目录，返回刷新后的快照
+    Refresh { reply: Reply<Result<DatabaseSnapshot>> },
+    /// 获取当前数据库和数据库列表
+    Snapshot { reply: Reply<DatabaseSnapshot> },
+    /// 注册当前数据库变化的回调
+    Subscribe { listener: DatabaseListener },
+    /// 执行写入并记录到操作日志
+    Apply {
+        operation: IndexOperation,
+        reply: Reply<Result<usize>>,
+    },
+    /// 撤销最近一次写入
+    Undo { reply: Reply<Result<Option<String>>> },
+    /// 重做最近一次撤销的写入
+    Redo { reply: Reply<Result<Option<String>>> },
+    /// 对当前数据库执行维护操作，在工作线程中执行
+    Maintain {
+        operations: Vec<MaintenanceOperation>,
+        progress: MaintenanceProgress,
+        reply: Reply<Result<MaintenanceReport>>,
+    },
+    /// 将文件夹索引到当前数据库，在工作线程中执行；当前数据库只读时返回 `None`
+    Index {
+        root: PathBuf,
+        cancel: Arc<AtomicBool>,
+        progress: IndexProgressCallback,
+        reply: Reply<Result<Option<IndexSummary>>>,
+    },
+    /// 校验本地文件并将结果写入当前数据库，在工作线程中执行
+    Verify {
+        records: Vec<FileRecord>,
+        cancel: Arc<AtomicBool>,
+        progress: VerifyProgress,
+        reply: Reply<Result<VerifySummary>>,
+    },
+    /// 备份指定数据库，在工作线程中执行
+    Backup {
+        index: usize,
+        dest: PathBuf,
+        reply: Reply<Result<()>>,
+    },
+    /// 比较两个数据库中的记录，在工作线程中执行
+    Compare {
+        left: usize,
+        right: usize,
+        key: CompareKey,
+        reply: Reply<Result<DatabaseDiff>>,
+    },
+    /// 用备份文件覆盖指定数据库，在工作线程中执行，完成后需要发送 `Reload`
+    Restore {
+        index: usize,
+        source: PathBuf,
+        reply: Reply<Result<PathBuf>>,
+    },
+    /// 指定数据库是当前数据库时重新加载
+    Reload {
+        index: usize,
+        reply: Reply<Result<()>>,
+    },
+}
+
+/// 数据库管理器当前状态的快照
+#[derive(Debug, Clone)]
+pub struct DatabaseSnapshot {
+    pub current: DatabaseConfig,
+    pub current_index: usize,
+    pub databases: Vec<(String, String, usize)>, // 名称、类型、索引
+    pub next_undo: Option<String>,               // 下一次撤销的操作描述
+    pub next_redo: Option<String>,
+    pub writable: bool, // 当前数据库是否支持写入
+}
+
+/// 结果列表中的一页记录
+#[derive(Debug, Clone, Default)]
+pub struct ResultPage {
+    pub records: Vec<FileRecord>,
+    pub verified: HashMap<String, bool>, // 已校验记录的路径及是否与 etag 一致
+}
+
+/// 数据库管理线程的句柄，可以克隆后在多处使用
+///
+/// `DatabaseManager` 由独立线程持有，调用方通过命令访问，不会长时间持有锁；
+/// 每次请求都作用于命令执行时的当前数据库，切换数据库后不会使用旧的实例。
+/// 维护、备份、恢复和比较在工作线程中执行，不会阻塞其他请求。
+/// 界面线程使用 `*_then` 方法，结果通过回调返回，不等待数据库管理线程
+#[derive(Clone)]
+pub struct DatabaseHandle {
+    tx: mpsc::Sender<DatabaseCommand>,
+    config: Arc<Mutex<AppConfig>>,
+}
+
+impl DatabaseHandle {
+    /// 启动数据库管理线程
+    ///
+    /// # Arguments
+    /// * `manager` - 由线程持有的数据库管理器
+    pub fn spawn(manager: DatabaseManager) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let config = manager.config.clone();
+        let spawned = thread::Builder::new()
+            .name("database-manager".to_string())
+            .spawn(move || run_manager(manager, rx));
+        if let Err(e) = spawned {
+            error!("Failed to start database manager thread: {}", e);
+        }
+        Self { tx, config }
+    }
+
+    /// 发送命令并等待结果
+    fn request<T: Send + 'static>(&self, command: impl FnOnce(Reply<T>) -> DatabaseCommand) -> Result<T> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.tx
+            .send(command(Box::new(move |value| {
+                let _ = reply_tx.send(value);
+            })))
+            .map_err(|_| anyhow::anyhow!("数据库管理线程已退出"))?;
+        reply_rx
+            .recv()
+            .map_err(|_| anyhow::anyhow!("数据库管理线程已退出"))
+    }
+
+    /// 发送命令，不等待结果
+    ///
+    /// `on_reply` 在数据库管理线程中调用，其中不能再同步调用 `DatabaseHandle` 的方法，
+    /// 访问界面需要通过 `slint::invoke_from_event_loop`
+    fn request_then<T: Send + 'static>(
+        &self,
+        command: impl FnOnce(Reply<T>) -> DatabaseCommand,
+        on_reply: impl FnOnce(T) + Send + 'static,
+    ) {
+        if self.tx.send(command(Box::new(on_reply))).is_err() {
+            warn!("Database manager thread stopped, request dropped");
+        }
+    }
+
+    /// 在当前数据库中搜索
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项
+    /// * `limit` - 最多返回的记录数
+    pub fn search(&self, query: &str, options: SearchOptions, limit: usize) -> Result<Vec<FileRecord>> {
+        self.request(|reply| DatabaseCommand::Search {
+            query: query.to_string(),
+            options,
+            limit,
+            reply,
+        })?
+    }
+
+    /// 与 `search` 相同，不等待结果
+    pub fn search_then(
+        &self,
+        query: &str,
+        options: SearchOptions,
+        limit: usize,
+        on_reply: impl FnOnce(Result<Vec<FileRecord>>) + Send + 'static,
+    ) {
+        self.request_then(
+            |reply| DatabaseCommand::Search {
+                query: query.to_string(),
+                options,
+                limit,
+                reply,
+            },
+            on_reply,
+        );
+    }
+
+    /// 在当前数据库中读取一页结果以及这些记录的校验状态，不等待结果
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项
+    /// * `offset` - 跳过的记录数
+    /// * `limit` - 本页最多返回的记录数
+    pub fn search_page_then(
+        &self,
+        query: &str,
+        options: SearchOptions,
+        offset: usize,
+        limit: usize,
+        on_reply: impl FnOnce(Result<ResultPage>) + Send + 'static,
+    ) {
+        self.request_then(
+            |reply| DatabaseCommand::Page {
+                query: query.to_string(),
+                options,
+                offset,
+                limit,
+                reply,
+            },
+            on_reply,
+        );
+    }
+
+    /// 统计当前数据库中匹配的记录数
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项
+    pub fn count_files(&self, query: &str, options: SearchOptions) -> Result<usize> {
+        self.request(|reply| DatabaseCommand::Count {
+            query: query.to_string(),
+            options,
+            reply,
+        })?
+    }
+
+    /// 与 `count_files` 相同，不等待结果
+    pub fn count_files_then(
+        &self,
+        query: &str,
+        options: SearchOptions,
+        on_reply: impl FnOnce(Result<usize>) + Send + 'static,
+    ) {
+        self.request_then(
+            |reply| DatabaseCommand::Count {
+                query: query.to_string(),
+                options,
+                reply,
+            },
+            on_reply,
+        );
+    }
+
+    /// 读取当前数据库中记录的校验状态，不等待结果
+    ///
+    /// # Arguments
+    /// * `paths` - 记录路径
+    pub fn verification_status_then(
+        &self,
+        paths: Vec<String>,
+        on_reply: impl FnOnce(Result<HashMap<String, bool>>) + Send + 'static,
+    ) {
+        self.request_then(|reply| DatabaseCommand::Verification { paths, reply }, on_reply);
+    }
+
+    /// 在当前数据库中执行只读查询，不等待结果
+    ///
+    /// # Arguments
+    /// * `sql` - 查询语句
+    /// * `limit` - 最多返回的行数
+    pub fn query_read_only_then(
+        &self,
+        sql: &str,
+        limit: usize,
+        on_reply: impl FnOnce(Result<QueryResult>) + Send + 'static,
+    ) {
+        self.request_then(
+            |reply| DatabaseCommand::Query {
+                sql: sql.to_string(),
+                limit,
+                reply,
+            },
+            on_reply,
+        );
+    }
+
+    /// 在当前数据库中随机选取一条匹配的记录
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词，为空时不限制
+    /// * `options` - 搜索范围和类型筛选
+    pub fn random_file(&self, query: &str, options: SearchOptions) -> Result<Option<FileRecord>> {
+        self.request(|reply| DatabaseCommand::Random {
+            query: query.to_string(),
+            options,
+            reply,
+        })?
+    }
+
+    /// 与 `random_file` 相同，不等待结果
+    pub fn random_file_then(
+        &self,
+        query: &str,
+        options: SearchOptions,
+        on_reply: impl FnOnce(Result<Option<FileRecord>>) + Send + 'static,
+    ) {
+        self.request_then(
+            |reply| DatabaseCommand::Random {
+                query: query.to_string(),
+                options,
+                reply,
+            },
+            on_reply,
+        );
+    }
+
+    /// 在当前数据库中按 ID 查找记录
+    ///
+    /// # Arguments
+    /// * `id` - 记录ID
+    pub fn find_file(&self, id: i64) -> Result<Option<FileRecord>> {
+        self.request(|reply| DatabaseCommand::Find { id, reply })?
+    }
+
+    /// 切换到指定数据库
+    ///
+    /// # Returns
+    /// * `Result<DatabaseConfig>` - 新数据库的配置
+    pub fn switch_database(&self, index: usize) -> Result<DatabaseConfig> {
+        self.request(|reply| DatabaseCommand::Switch { index, reply })?
+    }
+
+    /// 与 `switch_database` 相同，不等待结果
+    pub fn switch_database_then(
+        &self,
+        index: usize,
+        on_reply: impl FnOnce(Result<DatabaseConfig>) + Send + 'static,
+    ) {
+        self.request_then(|reply| DatabaseCommand::Switch { index, reply }, on_reply);
+    }
+
+    /// 重新扫描数据库目录，刷新数据库列表
+    pub fn refresh_database_list(&self) -> Result<()> {
+        self.request(|reply| DatabaseCommand::Refresh { reply })?.map(|_| ())
+    }
+
+    /// 与 `refresh_database_list` 相同，不等待结果，完成后返回刷新后的快照
+    pub fn refresh_database_list_then(&self, on_reply: impl FnOnce(Result<DatabaseSnapshot>) + Send + 'static) {
+        self.request_then(|reply| DatabaseCommand::Refresh { reply }, on_reply);
+    }
+
+    /// 获取当前数据库和数据库列表
+    pub fn snapshot(&self) -> Result<DatabaseSnapshot> {
+        self.request(|reply| DatabaseCommand::Snapshot { reply })
+    }
+
+    /// 与 `snapshot` 相同，不等待结果
+    pub fn snapshot_then(&self, on_reply: impl FnOnce(DatabaseSnapshot) + Send + 'static) {
+        self.request_then(|reply| DatabaseCommand::Snapshot { reply }, on_reply);
+    }
+
+    /// 注册当前数据库变化的回调，回调在数据库管理线程中执行
+    ///
+    /// # Arguments
+    /// * `listener` - 参数为新数据库的索引和配置
+    pub fn on_database_changed(&self, listener: impl Fn(usize, &DatabaseConfig) + Send + 'static) {
+        let command = DatabaseCommand::Subscribe {
+            listener: Box::new(listener),
+        };
+        if self.tx.send(command).is_err() {
+            warn!("Database manager thread stopped, listener not registered");
+        }
+    }
+
+    /// 在当前数据库中执行写入，可通过 `undo` 撤销
+    ///
+    /// # Arguments
+    /// * `operation` - 插入、删除或重命名
+    pub fn apply(&self, operation: IndexOperation) -> Result<usize> {
+        self.request(|reply| DatabaseCommand::Apply { operation, reply })?
+    }
+
+    /// 与 `apply` 相同，不等待结果
+    pub fn apply_then(&self, operation: IndexOperation, on_reply: impl FnOnce(Result<usize>) + Send + 'static) {
+        self.request_then(|reply| DatabaseCommand::Apply { operation, reply }, on_reply);
+    }
+
+    /// 撤销最近一次写入，返回撤销的操作描述
+    pub fn undo(&self) -> Result<Option<String>> {
+        self.request(|reply| DatabaseCommand::Undo { reply })?
+    }
+
+    /// 与 `undo` 相同，不等待结果
+    pub fn undo_then(&self, on_reply: impl FnOnce(Result<Option<String>>) + Send + 'static) {
+        self.request_then(|reply| DatabaseCommand::Undo { reply }, on_reply);
+    }
+
+    /// 重做最近一次撤销的写入，返回重做的操作描述
+    pub fn redo(&self) -> Result<Option<String>> {
+        self.request(|reply| DatabaseCommand::Redo { reply })?
+    }
+
+    /// 与 `redo` 相同，不等待结果
+    pub fn redo_then(&self, on_reply: impl FnOnce(Result<Option<String>>) + Send + 'static) {
+        self.request_then(|reply| DatabaseCommand::Redo { reply }, on_reply);
+    }
+
+    /// 对当前数据库执行维护操作，在工作线程中执行，每个操作只在执行期间锁定数据库
+    ///
+    /// # Arguments
+    /// * `operations` - 维护操作
+    /// * `progress` - 每个操作开始前在工作线程中调用
+    pub fn run_maintenance(
+        &self,
+        operations: Vec<MaintenanceOperation>,
+        progress: impl FnMut(usize, usize, &MaintenanceOperation) + Send + 'static,
+    ) -> Result<MaintenanceReport> {
+        self.request(|reply| DatabaseCommand::Maintain {
+            operations,
+            progress: Box::new(progress),
+            reply,
+        })?
+    }
+
+    /// 将文件夹索引到当前数据库，在工作线程中执行，每批记录只在写入期间锁定数据库
+    ///
+    /// # Arguments
+    /// * `root` - 要索引的文件夹
+    /// * `cancel` - 取消标志，设置后在当前批次结束时停止
+    /// * `progress` - 每写入一批在工作线程中调用
+    ///
+    /// # Returns
+    /// * `Result<Option<IndexSummary>>` - 当前数据库只读时返回 `None`
+    pub fn index_folder(
+        &self,
+        root: PathBuf,
+        cancel: Arc<AtomicBool>,
+        progress: impl FnMut(&IndexProgress) + Send + 'static,
+    ) -> Result<Option<IndexSummary>> {
+        self.request(|reply| DatabaseCommand::Index {
+            root,
+            cancel,
+            progress: Box::new(progress),
+            reply,
+        })?
+    }
+
+    /// 校验本地文件并将结果写入当前数据库，按当前数据库的路径映射读取文件
+    ///
+    /// # Arguments
+    /// * `records` - 要校验的文件记录
+    /// * `cancel` - 取消标志
+    /// * `progress` - 每个文件处理后在工作线程中调用，参数为已处理数和总数
+    pub fn verify_checksums(
+        &self,
+        records: Vec<FileRecord>,
+        cancel: Arc<AtomicBool>,
+        progress: impl FnMut(usize, usize) + Send + 'static,
+    ) -> Result<VerifySummary> {
+        self.request(|reply| DatabaseCommand::Verify {
+            records,
+            cancel,
+            progress: Box::new(progress),
+            reply,
+        })?
+    }
+
+    /// 备份指定数据库
+    ///
+    /// # Arguments
+    /// * `index` - 数据库索引
+    /// * `dest` - 备份文件路径
+    pub fn backup_database(&self, index: usize, dest: PathBuf) -> Result<()> {
+        self.request(|reply| DatabaseCommand::Backup { index, dest, reply })?
+    }
+
+    /// 用备份文件恢复指定数据库，返回恢复前自动创建的备份
+    pub fn restore_database(&self, index: usize, source: PathBuf) -> Result<PathBuf> {
+        let safety_copy = self.request(|reply| DatabaseCommand::Restore { index, source, reply })??;
+        self.request(|reply| DatabaseCommand::Reload { index, reply })??;
+        Ok(safety_copy)
+    }
+
+    /// 比较两个数据库中的记录
+    pub fn compare_databases(&self, left: usize, right: usize, key: CompareKey) -> Result<DatabaseDiff> {
+        self.request(|reply| DatabaseCommand::Compare { left, right, key, reply })?
+    }
+
+    /// 获取当前数据库配置，直接读取共享的配置，不经过数据库管理线程
+    pub fn current_config(&self) -> DatabaseConfig {
+        self.config.lock().unwrap().database.clone()
+    }
+}
+
+/// 在工作线程中执行耗时的命令，数据库管理线程继续处理其他请求
+fn run_in_background<T: Send + 'static>(name: &str, reply: Reply<T>, job: impl FnOnce() -> T + Send + 'static) {
+    let spawned = thread::Builder::new()
+        .name(format!("database-{}", name))
+        .spawn(move || reply(job()));
+    // 启动失败时丢弃回调，等待结果的调用方会收到线程已退出的错误
+    if let Err(e) = spawned {
+        error!("Failed to start database {} thread: {}", name, e);
+    }
+}
+
+/// 数据库管理线程：依次执行命令，直到所有句柄被丢弃
+fn run_manager(mut manager: DatabaseManager, rx: mpsc::Receiver<DatabaseCommand>) {
+    while let Ok(command) = rx.recv() {
+        match command {
+            DatabaseCommand::Search {
+                query,
+                options,
+                limit,
+                reply,
+            } => {
+                let database = manager.get_current_database();
+                let result = database
+                    .lock()
+                    .unwrap()
+                    .search_files_paged(&query, &options, 0, limit);
+                reply(result);
+            }
+            DatabaseCommand::Page {
+                query,
+                options,
+                offset,
+                limit,
+                reply,
+            } => {
+                let database = manager.get_current_database();
+                let database = database.lock().unwrap();
+                let result = database
+                    .search_files_paged(&query, &options, offset, limit)
+                    .map(|records| {
+                        let paths: Vec<String> = records.iter().map(|record| record.path.clone()).collect();
+                        let verified = database.verification_status(&paths).unwrap_or_default();
+                        ResultPage { records, verified }
+                    });
+                reply(result);
+            }
+            DatabaseCommand::Count { query, options, reply } => {
+                let database = manager.get_current_database();
+                let result = database.lock().unwrap().count_files(&query, &options);
+                reply(result);
+            }
+            DatabaseCommand::Verification { paths, reply } => {
+                let database = manager.get_current_database();
+                let result = database.lock().unwrap().verification_status(&paths);
+                reply(result);
+            }
+            DatabaseCommand::Query { sql, limit, reply } => {
+                let database = manager.get_current_database();
+                run_in_background("query", reply, move || {
+                    let database = database.lock().unwrap();
+                    database.query_read_only(&sql, limit)
+                });
+            }
+            DatabaseCommand::Random { query, options, reply } => {
+                let database = manager.get_current_database();
+                let result = database.lock().unwrap().random_file(&query, &options);
+                reply(result);
+            }
+            DatabaseCommand::Find { id, reply } => {
+                let database = manager.get_current_database();
+                let result = database.lock().unwrap().find_file(id);
+                reply(result);
+            }
+            DatabaseCommand::Switch { index, reply } => {
+                let result = manager
+                    .switch_database(index)
+                    .map(|_| manager.get_current_database_config());
+                reply(result);
+            }
+            DatabaseCommand::Refresh { reply } => {
+                reply(manager.refresh_database_list().map(|_| manager.snapshot()));
+            }
+            DatabaseCommand::Snapshot { reply } => {
+                reply(manager.snapshot());
+            }
+            DatabaseCommand::Subscribe { listener } => {
+                manager.listeners.push(listener);
+            }
+            DatabaseCommand::Apply { operation, reply } => {
+                reply(manager.apply(operation));
+            }
+            DatabaseCommand::Undo { reply } => {
+                reply(manager.undo());
+            }
+            DatabaseCommand::Redo { reply } => {
+                reply(manager.redo());
+            }
+            DatabaseCommand::Maintain {
+                operations,
+                progress,
+                reply,
+            } => {
+                // 清理孤立记录会删除操作日志引用的记录，开始前清空日志
+                if DatabaseManager::removes_records(&operations) {
+                    manager.journal.clear();
+                }
+                let database = manager.get_current_database();
+                let db_config = manager.get_current_database_config();
+                run_in_background("maintenance", reply, move || {
+                    DatabaseManager::maintain(&database, &db_config, &operations, progress)
+                });
+            }
+            DatabaseCommand::Index {
+                root,
+                cancel,
+                progress,
+                reply,
+            } => {
+                let database = manager.get_current_database();
+                let writable = database.lock().unwrap().is_writable();
+                if writable {
+                    run_in_background("index", reply, move || {
+                        Indexer::new(database)
+                            .with_cancel_flag(cancel)
+                            .index_folder(&root, progress)
+                            .map(Some)
+                    });
+                } else {
+                    reply(Ok(None));
+                }
+            }
+            DatabaseCommand::Verify {
+                records,
+                cancel,
+                progress,
+                reply,
+            } => {
+                let database = manager.get_current_database();
+                let path_mappings = manager.get_current_database_config().path_mappings;
+                run_in_background("verify", reply, move || {
+                    ChecksumVerifier::new(database)
+                        .with_path_mappings(path_mappings)
+                        .verify(&records, &cancel, progress)
+                });
+            }
+            DatabaseCommand::Backup { index, dest, reply } => match manager.sqlite_config(index) {
+                Ok(db_config) => run_in_background("backup", reply, move || {
+                    DatabaseManager::backup_file(&db_config, &dest)
+                }),
+                Err(e) => reply(Err(e)),
+            },
+            DatabaseCommand::Compare { left, right, key, reply } => {
+                match manager.sqlite_config(left).and_then(|left| Ok((left, manager.sqlite_config(right)?))) {
+                    Ok((left, right)) => run_in_background("compare", reply, move || {
+                        DatabaseManager::compare_files(&left, &right, key)
+                    }),
+                    Err(e) => reply(Err(e)),
+                }
+            }
+            DatabaseCommand::Restore { index, source, reply } => match manager.sqlite_config(index) {
+                Ok(db_config) => run_in_background("restore", reply, move || {
+                    DatabaseManager::restore_file(&db_config, &source)
+                }),
+                Err(e) => reply(Err(e)),
+            },
+            DatabaseCommand::Reload { index, reply } => {
+                reply(manager.reload_database(index));
+            }
+        }
+    }
+    debug!("Database manager thread stopped");
+}
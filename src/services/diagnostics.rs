@@ -0,0 +1,117 @@
+//! 查询诊断 - 记录慢查询的耗时和执行计划
+//!
+//! 默认关闭。启用后 `SqliteDatabase` 的搜索耗时超过阈值时，通过 EXPLAIN QUERY PLAN
+//! 获取执行计划并写入日志，最近的记录在诊断面板中查看，用于判断导入的数据库是否需要添加索引
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// 最多保留的慢查询记录数
+pub const SLOW_QUERY_HISTORY: usize = 50;
+
+/// 默认的慢查询阈值（毫秒）
+pub const DEFAULT_SLOW_QUERY_MS: u64 = 200;
+
+/// 一次慢查询
+#[derive(Debug, Clone)]
+pub struct SlowQuery {
+    pub sql: String,
+    pub params: Vec<String>,
+    pub elapsed: Duration,
+    pub plan: Vec<String>,  // EXPLAIN QUERY PLAN 的每一步，获取失败时为空
+    pub recorded_at: i64,   // Unix 时间戳（秒）
+}
+
+impl SlowQuery {
+    /// 是否包含全表扫描（计划中出现 `SCAN` 且没有使用索引）
+    pub fn has_full_scan(&self) -> bool {
+        self.plan
+            .iter()
+            .any(|step| step.starts_with("SCAN") && !step.contains("INDEX"))
+    }
+
+    /// 面板和日志中显示的一行摘要
+    pub fn summary(&self) -> String {
+        // SQL 中的换行和缩进合并为单个空格
+        let sql = self.sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        format!(
+            "{} ms  {}  [{}]  计划: {}",
+            self.elapsed.as_millis(),
+            sql,
+            self.params.join(", "),
+            if self.plan.is_empty() { "-".to_string() } else { self.plan.join(" | ") }
+        )
+    }
+}
+
+/// 查询诊断设置和最近的慢查询
+pub struct QueryDiagnostics {
+    enabled: AtomicBool,
+    threshold_ms: AtomicU64,
+    history: Mutex<VecDeque<SlowQuery>>,
+}
+
+static QUERY_DIAGNOSTICS: OnceLock<QueryDiagnostics> = OnceLock::new();
+
+/// 全局的查询诊断，所有 `SqliteDatabase` 实例共用
+pub fn shared_query_diagnostics() -> &'static QueryDiagnostics {
+    QUERY_DIAGNOSTICS.get_or_init(QueryDiagnostics::default)
+}
+
+impl Default for QueryDiagnostics {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            threshold_ms: AtomicU64::new(DEFAULT_SLOW_QUERY_MS),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl QueryDiagnostics {
+    /// 更新设置
+    ///
+    /// # Arguments
+    /// * `enabled` - 是否记录慢查询
+    /// * `threshold_ms` - 耗时达到该值（毫秒）的查询视为慢查询，0 表示记录所有查询
+    pub fn configure(&self, enabled: bool, threshold_ms: u64) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        self.threshold_ms.store(threshold_ms, Ordering::Relaxed);
+    }
+
+    /// 是否已启用
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 慢查询阈值（毫秒）
+    pub fn threshold_ms(&self) -> u64 {
+        self.threshold_ms.load(Ordering::Relaxed)
+    }
+
+    /// 耗时 `elapsed` 的查询是否需要记录
+    pub fn should_record(&self, elapsed: Duration) -> bool {
+        self.is_enabled() && elapsed.as_millis() >= u128::from(self.threshold_ms())
+    }
+
+    /// 记录一次慢查询，超过 `SLOW_QUERY_HISTORY` 时丢弃最早的记录
+    pub fn record(&self, query: SlowQuery) {
+        let mut history = self.history.lock().unwrap();
+        history.push_back(query);
+        while history.len() > SLOW_QUERY_HISTORY {
+            history.pop_front();
+        }
+    }
+
+    /// 最近的慢查询，最新的在前
+    pub fn recent(&self) -> Vec<SlowQuery> {
+        self.history.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// 清空记录
+    pub fn clear(&self) {
+        self.history.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,82 @@
+//! 磁盘空间检查 - 提交下载前比较目标目录的剩余空间
+//!
+//! 剩余空间不足以保存文件时提示或拒绝下载；正在下载的任务加上新文件超过剩余空间时只提示
+
+use crate::utils::locale::format_size;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// 提交下载前的空间检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceCheck {
+    /// 空间足够
+    Sufficient,
+    /// 文件本身可以保存，但和正在下载的任务合计超过剩余空间
+    QueueExceeds { required: u64, queued: u64, available: u64 },
+    /// 剩余空间不足以保存文件
+    Insufficient { required: u64, available: u64 },
+}
+
+impl SpaceCheck {
+    /// 按文件大小、正在下载的剩余字节数和剩余空间判断
+    ///
+    /// # Arguments
+    /// * `required` - 要下载的文件大小
+    /// * `queued` - 正在下载的任务还需要的字节数
+    /// * `available` - 目标目录所在磁盘的剩余空间
+    pub fn evaluate(required: u64, queued: u64, available: u64) -> Self {
+        if required > available {
+            Self::Insufficient { required, available }
+        } else if required.saturating_add(queued) > available {
+            Self::QueueExceeds { required, queued, available }
+        } else {
+            Self::Sufficient
+        }
+    }
+
+    /// 是否空间足够
+    pub fn is_sufficient(&self) -> bool {
+        matches!(self, Self::Sufficient)
+    }
+
+    /// 是否应拒绝下载，只有文件本身无法保存且设置了拒绝时才拒绝
+    pub fn blocks(&self, block_insufficient: bool) -> bool {
+        block_insufficient && matches!(self, Self::Insufficient { .. })
+    }
+
+    /// 界面显示的提示，空间足够时返回 None
+    pub fn message(&self) -> Option<String> {
+        match *self {
+            Self::Sufficient => None,
+            Self::QueueExceeds { required, queued, available } => Some(format!(
+                "下载队列共需 {}，超过剩余空间 {}",
+                format_size(required.saturating_add(queued)),
+                format_size(available)
+            )),
+            Self::Insufficient { required, available } => Some(format!(
+                "磁盘空间不足: 需要 {}，剩余 {}",
+                format_size(required),
+                format_size(available)
+            )),
+        }
+    }
+}
+
+/// 目录所在磁盘的剩余空间，目录尚未创建时检查最近的已存在的上级目录，相对路径没有时检查当前目录
+pub fn available_space(dir: &Path) -> Result<u64> {
+    let existing = dir
+        .ancestors()
+        .find(|path| !path.as_os_str().is_empty() && path.exists())
+        .unwrap_or(Path::new("."));
+    fs2::available_space(existing).with_context(|| format!("Failed to query free space of {}", existing.display()))
+}
+
+/// 检查目标目录能否保存下载的文件
+///
+/// # Arguments
+/// * `dir` - 下载目录
+/// * `required` - 要下载的文件大小
+/// * `queued` - 正在下载的任务还需要的字节数
+pub fn preflight(dir: &Path, required: u64, queued: u64) -> Result<SpaceCheck> {
+    Ok(SpaceCheck::evaluate(required, queued, available_space(dir)?))
+}
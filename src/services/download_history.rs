@@ -0,0 +1,230 @@
+//! 下载历史 - 记录已完成和失败的下载
+//!
+//! 历史保存在独立的 SQLite 数据库中，重启后仍可查看，可以重新下载或打开所在文件夹
+
+use crate::models::database::FileRecord;
+use anyhow::{Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, warn};
+
+/// 下载的最终状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    Complete,
+    Failed,
+}
+
+impl DownloadOutcome {
+    /// 数据库中保存的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Complete => "complete",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// 从数据库中保存的名称解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "complete" => Some(Self::Complete),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// 界面显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Complete => "已完成",
+            Self::Failed => "失败",
+        }
+    }
+}
+
+/// 一条下载历史
+#[derive(Debug, Clone)]
+pub struct DownloadHistoryEntry {
+    pub id: i64,
+    pub gid: String, // Aria2任务GID，内置下载器下载时为空
+    pub record: FileRecord,
+    pub database: String,  // 文件所在的数据库名称
+    pub save_path: String, // 下载文件的本机路径
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub outcome: DownloadOutcome,
+    pub error: Option<String>,
+}
+
+impl DownloadHistoryEntry {
+    /// 下载用时（秒）
+    pub fn duration_secs(&self) -> i64 {
+        (self.finished_at - self.started_at).max(0)
+    }
+}
+
+/// 下载历史
+pub struct DownloadHistory {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+/// 共享的下载历史实例
+pub type SharedDownloadHistory = Arc<DownloadHistory>;
+
+static SHARED_DOWNLOAD_HISTORY: OnceLock<SharedDownloadHistory> = OnceLock::new();
+
+/// 设置共享的下载历史，下载结束时写入，只能设置一次
+pub fn set_shared_download_history(history: SharedDownloadHistory) {
+    if SHARED_DOWNLOAD_HISTORY.set(history).is_err() {
+        debug!("Download history already set");
+    }
+}
+
+/// 获取共享的下载历史，未设置时不记录
+pub fn shared_download_history() -> Option<SharedDownloadHistory> {
+    SHARED_DOWNLOAD_HISTORY.get().cloned()
+}
+
+/// 写入共享的下载历史，未设置时忽略，失败时只记录日志
+pub fn record_download(entry: &DownloadHistoryEntry) {
+    if let Some(history) = shared_download_history() {
+        if let Err(e) = history.record(entry) {
+            warn!("Failed to record download history for {}: {:#}", entry.record.path, e);
+        }
+    }
+}
+
+impl DownloadHistory {
+    /// 打开下载历史数据库，不存在时创建
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    pub fn open(path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path);
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .context("Failed to create download history connection pool")?;
+
+        pool.get()
+            .context("Failed to get connection from pool")?
+            .execute(
+                "CREATE TABLE IF NOT EXISTS download_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    gid TEXT NOT NULL,
+                    record_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    etag TEXT NOT NULL,
+                    file_type TEXT NOT NULL,
+                    database TEXT NOT NULL,
+                    save_path TEXT NOT NULL,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    status TEXT NOT NULL,
+                    error TEXT
+                )",
+                [],
+            )
+            .context("Failed to create download_history table")?;
+
+        Ok(Self { pool })
+    }
+
+    /// 记录一次下载
+    ///
+    /// # Returns
+    /// * `Result<i64>` - 新记录的 ID
+    pub fn record(&self, entry: &DownloadHistoryEntry) -> Result<i64> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        conn.execute(
+            "INSERT INTO download_history
+                (gid, record_id, name, path, size, etag, file_type, database, save_path, started_at, finished_at, status, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                entry.gid,
+                entry.record.id,
+                entry.record.name,
+                entry.record.path,
+                entry.record.size as i64,
+                entry.record.etag,
+                entry.record.file_type,
+                entry.database,
+                entry.save_path,
+                entry.started_at,
+                entry.finished_at,
+                entry.outcome.as_str(),
+                entry.error
+            ],
+        )
+        .context("Failed to record download history")?;
+        debug!("Recorded {} download of {}", entry.outcome.as_str(), entry.record.path);
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 最近的下载历史，最新的在前
+    pub fn recent(&self, limit: usize) -> Result<Vec<DownloadHistoryEntry>> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        let mut stmt = conn
+            .prepare(&format!("{} ORDER BY finished_at DESC, id DESC LIMIT ?1", SELECT_COLUMNS))
+            .context("Failed to prepare download history query")?;
+        let rows = stmt
+            .query_map(params![limit as i64], map_row)
+            .context("Failed to query download history")?;
+
+        // 无法识别状态的记录跳过
+        Ok(rows.filter_map(|row| row.ok().flatten()).collect())
+    }
+
+    /// 按 ID 查找下载历史
+    pub fn get(&self, id: i64) -> Result<Option<DownloadHistoryEntry>> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        let entry = conn
+            .query_row(&format!("{} WHERE id = ?1", SELECT_COLUMNS), params![id], map_row)
+            .optional()
+            .context("Failed to query download history")?;
+        Ok(entry.flatten())
+    }
+
+    /// 清空下载历史
+    pub fn clear(&self) -> Result<()> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        conn.execute("DELETE FROM download_history", [])
+            .context("Failed to clear download history")?;
+        Ok(())
+    }
+}
+
+const SELECT_COLUMNS: &str = "SELECT id, gid, record_id, name, path, size, etag, file_type, database, save_path,
+        started_at, finished_at, status, error
+     FROM download_history";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<Option<DownloadHistoryEntry>> {
+    let status: String = row.get(12)?;
+    let Some(outcome) = DownloadOutcome::from_name(&status) else {
+        return Ok(None);
+    };
+    let size: i64 = row.get(5)?;
+    Ok(Some(DownloadHistoryEntry {
+        id: row.get(0)?,
+        gid: row.get(1)?,
+        record: FileRecord {
+            id: row.get(2)?,
+            name: row.get(3)?,
+            path: row.get(4)?,
+            size: size.max(0) as u64,
+            etag: row.get(6)?,
+            modified_time: 0,
+            file_type: row.get(7)?,
+        },
+        database: row.get(8)?,
+        save_path: row.get(9)?,
+        started_at: row.get(10)?,
+        finished_at: row.get(11)?,
+        outcome,
+        error: row.get(13)?,
+    }))
+}
@@ -0,0 +1,727 @@
+//! 下载管理器 - 跟踪Aria2下载任务
+//!
+//! 负责解析下载链接、提交任务到Aria2，并在网盘链接过期时自动重新获取链接重启任务
+
+use crate::services::link_resolver::get_file_url;
+use crate::services::aria2::{is_magnet_uri, Aria2Client, SharedAria2Service, TorrentFile, TorrentOptions};
+use crate::services::disk_space::{self, SpaceCheck};
+use crate::services::events::{self, AppEvent};
+use crate::services::link_resolver::shared_link_resolver;
+use crate::services::metrics;
+use crate::services::notifications::{self, NotificationKind};
+use crate::services::pending_ops::is_offline_error;
+use crate::services::sidecar::{self, DownloadSidecar};
+use crate::services::download_history::{self, DownloadHistoryEntry, DownloadOutcome};
+use crate::models::database::FileRecord;
+use crate::utils::filetype::category_of;
+use anyhow::{Context, Result};
+use chrono::Timelike;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, info, warn};
+
+/// 链接过期后最多重新获取的次数
+const MAX_URL_REFRESHES: u32 = 3;
+
+/// 下载任务的来源文件信息，用于重新获取下载链接
+#[derive(Debug, Clone)]
+pub struct DownloadSource {
+    pub path: String,
+    pub name: String,      // 保存的文件名，替代下载链接中的CDN文件名
+    pub file_type: String, // 决定按类型存放的子目录
+    pub etag: String,
+    pub size: u64,
+    pub target_dir: Option<String>, // 下载目录，None 表示使用Aria2默认目录
+    pub record_id: i64,   // 索引中的记录 ID，写入下载元数据
+    pub database: String, // 来源数据库名称，写入下载元数据
+}
+
+impl DownloadSource {
+    /// 由文件记录创建
+    ///
+    /// # Arguments
+    /// * `record` - 文件记录
+    /// * `target_dir` - 下载目录，None 表示使用Aria2默认目录
+    pub fn from_record(record: &FileRecord, target_dir: Option<String>) -> Self {
+        Self {
+            path: record.path.clone(),
+            name: record.name.clone(),
+            file_type: record.file_type.clone(),
+            etag: record.etag.clone(),
+            size: record.size,
+            target_dir,
+            record_id: record.id,
+            database: String::new(),
+        }
+    }
+
+    /// 设置来源数据库名称
+    pub fn with_database(mut self, database: &str) -> Self {
+        self.database = database.to_string();
+        self
+    }
+
+    /// 只有路径时创建（如离线队列中的任务），文件名取路径的最后一段，类型由扩展名判断
+    pub fn from_path(path: &str, etag: &str, size: u64, target_dir: Option<String>) -> Self {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        Self {
+            path: path.to_string(),
+            name: name.to_string(),
+            file_type: String::new(),
+            etag: etag.to_string(),
+            size,
+            target_dir,
+            record_id: 0,
+            database: String::new(),
+        }
+    }
+
+    /// Aria2中的保存目录：下载目录下按文件类型的子目录，无法识别类型时直接使用下载目录
+    ///
+    /// # Arguments
+    /// * `default_dir` - 未指定 `target_dir` 时使用的Aria2默认下载目录
+    pub fn save_dir(&self, default_dir: &str) -> String {
+        let base = self.target_dir.as_deref().unwrap_or(default_dir);
+        match category_of(&self.file_type, &self.name) {
+            Some(category) => Path::new(base).join(category.dir_name()).to_string_lossy().to_string(),
+            None => base.to_string(),
+        }
+    }
+
+    /// 来源文件的记录，修改时间未知
+    pub fn record(&self) -> FileRecord {
+        FileRecord {
+            id: self.record_id,
+            name: self.name.clone(),
+            path: self.path.clone(),
+            size: self.size,
+            etag: self.etag.clone(),
+            modified_time: 0,
+            file_type: self.file_type.clone(),
+        }
+    }
+
+    /// 写入下载文件旁的来源信息，时间为当前时间
+    pub fn sidecar(&self) -> DownloadSidecar {
+        DownloadSidecar {
+            database: self.database.clone(),
+            record_id: self.record_id,
+            etag: self.etag.clone(),
+            path: self.path.clone(),
+            size: self.size,
+            created_at: chrono::Local::now().timestamp(),
+        }
+    }
+
+    /// Aria2中保存的文件名
+    ///
+    /// 只取索引中文件名的最后一段，避免 `..` 或绝对路径写到下载目录之外；
+    /// 取不到文件名时使用 etag，两者都为空时由Aria2根据链接决定
+    pub fn save_name(&self) -> Option<&str> {
+        let name = self.name.rsplit(['/', '\\']).next().unwrap_or_default();
+        Path::new(name)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .or(Some(self.etag.as_str()))
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// 被跟踪的下载任务
+#[derive(Debug, Clone)]
+pub struct TrackedDownload {
+    pub gid: String,
+    pub source: DownloadSource,
+    pub url_refreshes: u32,
+    pub save_path: String, // 下载文件的本机路径
+    pub started_at: i64,   // 首次提交的时间，重新获取链接后不变
+}
+
+impl TrackedDownload {
+    /// 下载结束时写入下载历史的记录，结束时间为当前时间
+    pub fn history_entry(&self, outcome: DownloadOutcome, error: Option<String>) -> DownloadHistoryEntry {
+        DownloadHistoryEntry {
+            id: 0,
+            gid: self.gid.clone(),
+            record: self.source.record(),
+            database: self.source.database.clone(),
+            save_path: self.save_path.clone(),
+            started_at: self.started_at,
+            finished_at: chrono::Local::now().timestamp(),
+            outcome,
+            error,
+        }
+    }
+}
+
+/// 在应用中排队、尚未提交到Aria2的下载
+#[derive(Debug, Clone)]
+pub struct QueuedDownload {
+    pub id: u64,
+    pub source: DownloadSource,
+    pub queued_at: i64,
+}
+
+/// 提交下载的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submitted {
+    /// 已提交到Aria2，附带任务GID
+    Started(String),
+    /// 同时下载数已满，在应用队列中等待，附带队列 ID
+    Queued(u64),
+}
+
+/// 下载管理器
+pub struct DownloadManager {
+    aria2_service: SharedAria2Service,
+    tasks: Mutex<HashMap<String, TrackedDownload>>,
+    queue: Mutex<Vec<QueuedDownload>>,
+    next_queue_id: AtomicU64,
+    max_active: AtomicUsize, // 同时提交到Aria2的任务数，0 表示不限制
+    starting: AtomicUsize,   // 已占用空位、正在提交到Aria2的任务数
+    applied_speed_limit: Mutex<Option<(String, String)>>, // 已应用限速的Aria2会话 ID 和限速值
+    checking: AtomicBool, // 是否有 check_tasks 正在执行
+}
+
+/// 共享的下载管理器实例
+pub type SharedDownloadManager = Arc<DownloadManager>;
+
+impl DownloadManager {
+    /// 创建新的下载管理器
+    pub fn new(aria2_service: SharedAria2Service) -> Self {
+        let max_active = aria2_service.lock().unwrap().config().max_active_downloads;
+        Self {
+            aria2_service,
+            tasks: Mutex::new(HashMap::new()),
+            queue: Mutex::new(Vec::new()),
+            next_queue_id: AtomicU64::new(1),
+            max_active: AtomicUsize::new(max_active),
+            starting: AtomicUsize::new(0),
+            applied_speed_limit: Mutex::new(None),
+            checking: AtomicBool::new(false),
+        }
+    }
+
+    /// 获取Aria2客户端副本，避免跨 await 持有锁
+    fn client(&self) -> Option<Aria2Client> {
+        self.aria2_service.lock().unwrap().get_client().cloned()
+    }
+
+    /// Aria2是否可用
+    pub fn is_available(&self) -> bool {
+        self.client().is_some()
+    }
+
+    /// 获取当前跟踪的任务列表
+    pub fn tracked_tasks(&self) -> Vec<TrackedDownload> {
+        self.tasks.lock().unwrap().values().cloned().collect()
+    }
+
+    /// 解析下载链接并提交到Aria2
+    ///
+    /// # Arguments
+    /// * `source` - 来源文件信息
+    ///
+    /// # Returns
+    /// * `Result<String>` - Aria2任务GID
+    pub async fn enqueue(&self, source: DownloadSource) -> Result<String> {
+        let client = self
+            .client()
+            .ok_or_else(|| anyhow::anyhow!("Aria2 client not available"))?;
+
+        if let Some(check) = self.preflight_with(&client, &source).await {
+            if check.blocks(self.block_insufficient_space()) {
+                anyhow::bail!(check.message().unwrap_or_default());
+            }
+            if let Some(message) = check.message() {
+                warn!("Enqueueing {} anyway: {}", source.path, message);
+            }
+        }
+
+        let url = Self::resolve_url(&source).await?;
+        let dir = source.save_dir(client.download_dir());
+        let gid = client
+            .add_download_to(&url, Some(&dir), source.save_name())
+            .await?;
+        let save_path = Path::new(&dir).join(source.save_name().unwrap_or_default());
+        if source.save_name().is_some() {
+            sidecar::write_logged(&save_path, &source.sidecar());
+        }
+
+        Self::publish_status(&gid, &source, "active");
+        self.tasks.lock().unwrap().insert(
+            gid.clone(),
+            TrackedDownload {
+                gid: gid.clone(),
+                source,
+                url_refreshes: 0,
+                save_path: save_path.to_string_lossy().to_string(),
+                started_at: chrono::Local::now().timestamp(),
+            },
+        );
+        self.update_active_gauge();
+
+        Ok(gid)
+    }
+
+    /// 提交下载，同时下载数已满或队列中还有等待的下载时放入应用队列，按队列顺序提交
+    ///
+    /// 与Aria2自身的 max-concurrent-downloads 无关，队列中的任务不会出现在Aria2中，重启后不保留
+    ///
+    /// # Arguments
+    /// * `source` - 来源文件信息
+    pub async fn submit(&self, source: DownloadSource) -> Result<Submitted> {
+        if self.queue.lock().unwrap().is_empty() {
+            if let Some(_slot) = self.reserve_slot() {
+                return self.enqueue(source).await.map(Submitted::Started);
+            }
+        }
+        let id = self.next_queue_id.fetch_add(1, Ordering::Relaxed);
+        debug!("Download slots full, queueing {} as #{}", source.path, id);
+        Self::publish_status(&format!("queue-{}", id), &source, "queued");
+        self.queue.lock().unwrap().push(QueuedDownload {
+            id,
+            source,
+            queued_at: chrono::Local::now().timestamp(),
+        });
+        // 有空位时也先交给队列，排在前面的下载先拿到空位
+        self.start_queued().await;
+        Ok(Submitted::Queued(id))
+    }
+
+    /// 同时提交到Aria2的任务数，0 表示不限制
+    pub fn max_active(&self) -> usize {
+        self.max_active.load(Ordering::Relaxed)
+    }
+
+    /// 设置同时提交到Aria2的任务数，调大后在下次检查任务时提交队列中的任务
+    pub fn set_max_active(&self, max_active: usize) {
+        self.max_active.store(max_active, Ordering::Relaxed);
+    }
+
+    /// 占用一个同时下载的空位，没有空位时返回 None
+    ///
+    /// 检查和占用在同一把锁内完成，提交过程中等待网络时其他调用不会再拿到这个空位；
+    /// 返回值丢弃时释放，此时提交成功的任务已经计入 `tasks`
+    fn reserve_slot(&self) -> Option<SlotReservation<'_>> {
+        let tasks = self.tasks.lock().unwrap();
+        let max_active = self.max_active();
+        if max_active != 0 && tasks.len() + self.starting.load(Ordering::Acquire) >= max_active {
+            return None;
+        }
+        self.starting.fetch_add(1, Ordering::AcqRel);
+        Some(SlotReservation(&self.starting))
+    }
+
+    /// 应用队列中等待的下载，按提交顺序
+    pub fn queued_downloads(&self) -> Vec<QueuedDownload> {
+        self.queue.lock().unwrap().clone()
+    }
+
+    /// 将队列中的下载移动到指定位置，超出范围时移到末尾
+    ///
+    /// # Returns
+    /// * `bool` - 队列中是否有该下载
+    pub fn move_queued(&self, id: u64, index: usize) -> bool {
+        let mut queue = self.queue.lock().unwrap();
+        let Some(from) = queue.iter().position(|queued| queued.id == id) else {
+            return false;
+        };
+        let queued = queue.remove(from);
+        let index = index.min(queue.len());
+        queue.insert(index, queued);
+        true
+    }
+
+    /// 从队列中移除下载
+    pub fn remove_queued(&self, id: u64) -> Option<QueuedDownload> {
+        let mut queue = self.queue.lock().unwrap();
+        let index = queue.iter().position(|queued| queued.id == id)?;
+        Some(queue.remove(index))
+    }
+
+    /// 按队列顺序提交等待的下载，直到同时下载数已满
+    ///
+    /// 网络不可用时下载留在队首，下次检查时重试；其他错误记入下载历史并跳过
+    ///
+    /// # Returns
+    /// * `usize` - 提交的下载数
+    pub async fn start_queued(&self) -> usize {
+        let mut started = 0;
+        while let Some(_slot) = self.reserve_slot() {
+            let next = {
+                let mut queue = self.queue.lock().unwrap();
+                if queue.is_empty() { None } else { Some(queue.remove(0)) }
+            };
+            let Some(queued) = next else {
+                break;
+            };
+            match self.enqueue(queued.source.clone()).await {
+                Ok(gid) => {
+                    info!("Started queued download #{} as {}", queued.id, gid);
+                    started += 1;
+                }
+                Err(e) if is_offline_error(&e) => {
+                    debug!("Netdisk unreachable, keeping queued download #{}: {:#}", queued.id, e);
+                    self.queue.lock().unwrap().insert(0, queued);
+                    break;
+                }
+                Err(e) => {
+                    warn!("Failed to start queued download {}: {:#}", queued.source.path, e);
+                    Self::publish_status(&format!("queue-{}", queued.id), &queued.source, "error");
+                    let now = chrono::Local::now().timestamp();
+                    download_history::record_download(&DownloadHistoryEntry {
+                        id: 0,
+                        gid: String::new(),
+                        record: queued.source.record(),
+                        database: queued.source.database.clone(),
+                        save_path: String::new(),
+                        started_at: now,
+                        finished_at: now,
+                        outcome: DownloadOutcome::Failed,
+                        error: Some(format!("{:#}", e)),
+                    });
+                }
+            }
+        }
+        started
+    }
+
+    /// 提交下载前检查保存目录的剩余空间，Aria2不可用或无法查询空间时返回 None
+    ///
+    /// # Arguments
+    /// * `source` - 来源文件信息
+    pub async fn preflight(&self, source: &DownloadSource) -> Option<SpaceCheck> {
+        let client = self.client()?;
+        self.preflight_with(&client, source).await
+    }
+
+    async fn preflight_with(&self, client: &Aria2Client, source: &DownloadSource) -> Option<SpaceCheck> {
+        let dir = source.save_dir(client.download_dir());
+        let queued = self.queued_bytes(client).await;
+        match disk_space::preflight(Path::new(&dir), source.size, queued) {
+            Ok(check) => Some(check),
+            Err(e) => {
+                debug!("Skipping disk space check for {}: {:#}", dir, e);
+                None
+            }
+        }
+    }
+
+    /// 空间不足时是否拒绝提交，内置下载器也按此设置
+    pub fn block_insufficient_space(&self) -> bool {
+        self.aria2_service.lock().unwrap().config().block_insufficient_space
+    }
+
+    /// 正在跟踪的任务还需要下载的字节数加上应用队列中等待的下载大小，无法查询进度的任务按文件大小计算
+    async fn queued_bytes(&self, client: &Aria2Client) -> u64 {
+        let mut total = self
+            .queued_downloads()
+            .iter()
+            .fold(0u64, |total, queued| total.saturating_add(queued.source.size));
+        for task in self.tracked_tasks() {
+            let completed = match client.get_status(&task.gid).await {
+                Ok(status) => status["completedLength"]
+                    .as_str()
+                    .and_then(|length| length.parse::<u64>().ok())
+                    .unwrap_or(0),
+                Err(_) => 0,
+            };
+            total = total.saturating_add(task.source.size.saturating_sub(completed));
+        }
+        total
+    }
+
+    /// 提交任意链接（http、ftp、magnet 等）到Aria2，不跟踪链接过期
+    ///
+    /// # Arguments
+    /// * `uri` - 下载链接
+    /// * `target_dir` - 下载目录，None 表示使用Aria2默认目录
+    /// * `name` - 保存的文件名，None 表示由链接决定，磁力链接忽略
+    pub async fn submit_uri(&self, uri: &str, target_dir: Option<&str>, name: Option<&str>) -> Result<String> {
+        let client = self
+            .client()
+            .ok_or_else(|| anyhow::anyhow!("Aria2 client not available"))?;
+        let gid = if is_magnet_uri(uri) {
+            let options = TorrentOptions {
+                dir: target_dir.map(str::to_string),
+                ..TorrentOptions::default()
+            };
+            client.add_magnet(uri, &options).await?
+        } else {
+            client.add_download_to(uri, target_dir, name).await?
+        };
+        events::publish(AppEvent::DownloadStatus {
+            gid: gid.clone(),
+            path: uri.to_string(),
+            status: "active".to_string(),
+        });
+        Ok(gid)
+    }
+
+    /// 提交种子文件到Aria2
+    ///
+    /// # Arguments
+    /// * `name` - 种子文件名，用于事件中显示
+    /// * `torrent` - .torrent 文件内容
+    /// * `options` - 下载目录、要下载的文件和做种时间
+    pub async fn submit_torrent(&self, name: &str, torrent: &[u8], options: &TorrentOptions) -> Result<String> {
+        let client = self
+            .client()
+            .ok_or_else(|| anyhow::anyhow!("Aria2 client not available"))?;
+        let gid = client.add_torrent(torrent, options).await?;
+        events::publish(AppEvent::DownloadStatus {
+            gid: gid.clone(),
+            path: name.to_string(),
+            status: "active".to_string(),
+        });
+        Ok(gid)
+    }
+
+    /// 种子或磁力任务中的文件列表
+    pub async fn torrent_files(&self, gid: &str) -> Result<Vec<TorrentFile>> {
+        let client = self
+            .client()
+            .ok_or_else(|| anyhow::anyhow!("Aria2 client not available"))?;
+        client.get_files(gid).await
+    }
+
+    /// 只下载种子任务中的指定文件
+    ///
+    /// # Arguments
+    /// * `gid` - 任务GID
+    /// * `indexes` - 从 1 开始的文件序号，见 `torrent_files`
+    pub async fn select_torrent_files(&self, gid: &str, indexes: &[usize]) -> Result<()> {
+        let client = self
+            .client()
+            .ok_or_else(|| anyhow::anyhow!("Aria2 client not available"))?;
+        client.select_files(gid, indexes).await
+    }
+
+    /// 检查所有被跟踪任务的状态
+    ///
+    /// 已完成或被移除的任务停止跟踪；因链接过期失败的任务会重新获取链接并重启；
+    /// 有空位时提交应用队列中等待的下载
+    ///
+    /// 上一次检查还在等待网络请求时直接返回，避免两次检查重启同一个过期任务
+    pub async fn check_tasks(&self) -> Result<()> {
+        if self.checking.swap(true, Ordering::AcqRel) {
+            debug!("Previous download check still running, skipping");
+            return Ok(());
+        }
+        let _guard = CheckGuard(&self.checking);
+        self.check_tracked_tasks().await
+    }
+
+    async fn check_tracked_tasks(&self) -> Result<()> {
+        let client = match self.client() {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+
+        for task in self.tracked_tasks() {
+            let status = match client.get_status(&task.gid).await {
+                Ok(status) => status,
+                Err(e) => {
+                    debug!("Failed to query task {}: {}, no longer tracking", task.gid, e);
+                    self.tasks.lock().unwrap().remove(&task.gid);
+                    continue;
+                }
+            };
+
+            let state = status["status"].as_str().unwrap_or("");
+            match state {
+                "complete" => {
+                    // 只有移除了任务的一方写入历史和发送通知，同一个任务不会处理两次
+                    if self.tasks.lock().unwrap().remove(&task.gid).is_none() {
+                        continue;
+                    }
+                    Self::publish_status(&task.gid, &task.source, state);
+                    download_history::record_download(&task.history_entry(DownloadOutcome::Complete, None));
+                    notifications::notify(NotificationKind::DownloadComplete, "下载完成", &task.save_path);
+                }
+                "removed" => {
+                    self.tasks.lock().unwrap().remove(&task.gid);
+                    Self::publish_status(&task.gid, &task.source, state);
+                }
+                "error" => {
+                    if self.tasks.lock().unwrap().remove(&task.gid).is_none() {
+                        continue;
+                    }
+                    Self::publish_status(&task.gid, &task.source, state);
+                    let message = status["errorMessage"].as_str().unwrap_or("unknown error").to_string();
+                    if Self::is_expired_url_status(&status) {
+                        let entry = task.history_entry(DownloadOutcome::Failed, Some(message));
+                        if let Err(e) = self.restart_with_fresh_url(&client, task).await {
+                            error!("Failed to restart expired download: {}", e);
+                            download_history::record_download(&entry);
+                        }
+                    } else {
+                        warn!("Download {} failed: {}", task.gid, message);
+                        download_history::record_download(&task.history_entry(DownloadOutcome::Failed, Some(message)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.update_active_gauge();
+        self.start_queued().await;
+        Ok(())
+    }
+
+    /// 更新正在跟踪的下载任务数指标
+    fn update_active_gauge(&self) {
+        let count = self.tasks.lock().unwrap().len();
+        metrics::ACTIVE_DOWNLOADS.set(count as i64);
+    }
+
+    /// 向 WebSocket 客户端推送任务状态
+    fn publish_status(gid: &str, source: &DownloadSource, status: &str) {
+        events::publish(AppEvent::DownloadStatus {
+            gid: gid.to_string(),
+            path: source.path.clone(),
+            status: status.to_string(),
+        });
+    }
+
+    /// 按当前本地时间应用限速规划
+    pub async fn apply_speed_schedule(&self) -> Result<()> {
+        let hour = chrono::Local::now().hour() as u8;
+        self.apply_speed_schedule_at(hour).await
+    }
+
+    /// 按指定小时应用限速规划，仅在限速值变化或Aria2重新启动后才修改全局选项
+    ///
+    /// 新启动的 aria2c 没有限速，按会话 ID 判断连接的是否还是应用过限速的进程
+    ///
+    /// # Arguments
+    /// * `hour` - 当前小时（0-23）
+    pub async fn apply_speed_schedule_at(&self, hour: u8) -> Result<()> {
+        let (client, limit) = {
+            let service = self.aria2_service.lock().unwrap();
+            let limit = match service.config().speed_limit_for_hour(hour) {
+                Some(limit) => limit.to_string(),
+                None if service.config().speed_schedule.is_empty() => return Ok(()),
+                // 有规划但当前时段没有匹配规则时恢复不限速
+                None => "0".to_string(),
+            };
+            match service.get_client() {
+                Some(client) => (client.clone(), limit),
+                None => return Ok(()),
+            }
+        };
+
+        let session = match client.get_session_id().await {
+            Ok(session) => session,
+            // Aria2暂时无法连接，重新连接后再应用
+            Err(e) => {
+                debug!("Skipping speed schedule, Aria2 unreachable: {:#}", e);
+                *self.applied_speed_limit.lock().unwrap() = None;
+                return Ok(());
+            }
+        };
+        let applied = (session, limit);
+        if self.applied_speed_limit.lock().unwrap().as_ref() == Some(&applied) {
+            return Ok(());
+        }
+
+        client
+            .change_global_option(json!({ "max-overall-download-limit": applied.1 }))
+            .await?;
+        info!("Applied Aria2 download speed limit: {} (hour {})", applied.1, hour);
+        *self.applied_speed_limit.lock().unwrap() = Some(applied);
+
+        Ok(())
+    }
+
+    /// 重新获取下载链接并重启任务
+    async fn restart_with_fresh_url(&self, client: &Aria2Client, task: TrackedDownload) -> Result<()> {
+        if task.url_refreshes >= MAX_URL_REFRESHES {
+            anyhow::bail!(
+                "Download URL for {} expired {} times, giving up",
+                task.source.path,
+                task.url_refreshes
+            );
+        }
+
+        info!("Download URL expired for {}, fetching a fresh link", task.source.path);
+        shared_link_resolver().invalidate(&task.source.etag, task.source.size);
+        let url = Self::resolve_url(&task.source).await?;
+
+        // 清除失败的任务结果，避免在Aria2中残留
+        if let Err(e) = client.remove_download_result(&task.gid).await {
+            debug!("Failed to remove result of {}: {}", task.gid, e);
+        }
+
+        let dir = task.source.save_dir(client.download_dir());
+        let gid = client
+            .add_download_to(&url, Some(&dir), task.source.save_name())
+            .await?;
+        info!("Restarted download {} as {}", task.gid, gid);
+        Self::publish_status(&gid, &task.source, "active");
+
+        self.tasks.lock().unwrap().insert(
+            gid.clone(),
+            TrackedDownload {
+                gid,
+                source: task.source,
+                url_refreshes: task.url_refreshes + 1,
+                save_path: task.save_path,
+                started_at: task.started_at,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// 通过后端服务获取下载链接
+    async fn resolve_url(source: &DownloadSource) -> Result<String> {
+        get_file_url(source.path.as_str(), source.etag.as_str(), source.size)
+            .await
+            .context("Failed to get download URL")
+    }
+
+    /// 判断Aria2任务状态是否为下载链接过期导致的失败
+    pub fn is_expired_url_status(status: &Value) -> bool {
+        let code = status["errorCode"].as_str().unwrap_or("");
+        let message = status["errorMessage"].as_str().unwrap_or("");
+        is_expired_url_error(code, message)
+    }
+}
+
+/// 占用的同时下载空位，丢弃时释放
+struct SlotReservation<'a>(&'a AtomicUsize);
+
+impl Drop for SlotReservation<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// 检查结束（包括被取消）时清除正在检查的标记
+struct CheckGuard<'a>(&'a AtomicBool);
+
+impl Drop for CheckGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// 判断Aria2错误是否表示下载链接已过期
+///
+/// 网盘链接过期时服务器返回 403，Aria2 将其报告为错误码 22（非预期的HTTP响应）或 24（认证失败）
+///
+/// # Arguments
+/// * `error_code` - Aria2 errorCode
+/// * `error_message` - Aria2 errorMessage
+pub fn is_expired_url_error(error_code: &str, error_message: &str) -> bool {
+    let message = error_message.to_lowercase();
+    let mentions_forbidden =
+        message.contains("403") || message.contains("forbidden") || message.contains("expired");
+
+    error_code == "24" || mentions_forbidden
+}
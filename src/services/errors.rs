@@ -0,0 +1,130 @@
+//! 错误上报 - 对错误分类，统一写入日志并推送给界面和 WebSocket 客户端
+//!
+//! 处理函数遇到需要让用户知道的错误时调用 `report` 或 `report_as`，
+//! 不再各自调用 `error!()` 和设置状态栏。界面在启动时通过 `set_error_sink` 注册显示方式
+
+use crate::services::api_client::LinkError;
+use crate::services::events::{self, AppEvent};
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+use tracing::{debug, error};
+
+/// 错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    Database,
+    Network,
+    Aria2,
+    Clipboard,
+    FileSystem,
+    Other,
+}
+
+impl ErrorCategory {
+    /// 界面显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Database => "数据库",
+            Self::Network => "网络",
+            Self::Aria2 => "Aria2",
+            Self::Clipboard => "剪切板",
+            Self::FileSystem => "文件",
+            Self::Other => "错误",
+        }
+    }
+
+    /// 根据错误链中的错误类型分类
+    pub fn classify(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<LinkError>() {
+                return match e {
+                    LinkError::Clipboard(_) => Self::Clipboard,
+                    _ => Self::Network,
+                };
+            }
+            if cause.is::<rusqlite::Error>() || cause.is::<r2d2::Error>() {
+                return Self::Database;
+            }
+            if cause.is::<reqwest::Error>() {
+                return Self::Network;
+            }
+            if cause.is::<std::io::Error>() {
+                return Self::FileSystem;
+            }
+            if cause.to_string().starts_with("Aria2") {
+                return Self::Aria2;
+            }
+        }
+        Self::Other
+    }
+}
+
+/// 一条上报的错误
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub category: ErrorCategory,
+    pub context: String, // 出错的操作，例如"搜索失败"
+    pub message: String,
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.category.label(), self.context, self.message)
+    }
+}
+
+/// 界面显示错误的方式，可能在任意线程中调用
+type ErrorSink = Box<dyn Fn(&ErrorReport) + Send + Sync>;
+
+static ERROR_SINK: OnceLock<ErrorSink> = OnceLock::new();
+
+/// 设置界面显示错误的方式，只能设置一次
+pub fn set_error_sink(sink: impl Fn(&ErrorReport) + Send + Sync + 'static) {
+    if ERROR_SINK.set(Box::new(sink)).is_err() {
+        debug!("Error sink already set");
+    }
+}
+
+/// 上报错误，自动分类
+///
+/// # Arguments
+/// * `context` - 出错的操作
+/// * `error` - 错误
+pub fn report(context: &str, error: &anyhow::Error) -> ErrorReport {
+    let category = ErrorCategory::classify(error);
+    dispatch(ErrorReport {
+        category,
+        context: context.to_string(),
+        message: format!("{:#}", error),
+    })
+}
+
+/// 上报已知分类的错误
+///
+/// # Arguments
+/// * `category` - 错误分类
+/// * `context` - 出错的操作
+/// * `error` - 错误
+pub fn report_as(category: ErrorCategory, context: &str, error: impl fmt::Display) -> ErrorReport {
+    dispatch(ErrorReport {
+        category,
+        context: context.to_string(),
+        message: error.to_string(),
+    })
+}
+
+/// 写入日志并发送给界面和事件总线
+fn dispatch(report: ErrorReport) -> ErrorReport {
+    error!(category = ?report.category, "{}: {}", report.context, report.message);
+    events::publish(AppEvent::Error {
+        category: report.category,
+        context: report.context.clone(),
+        message: report.message.clone(),
+    });
+    if let Some(sink) = ERROR_SINK.get() {
+        sink(&report);
+    }
+    report
+}
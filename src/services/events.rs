@@ -0,0 +1,67 @@
+//! 事件总线 - 向 WebSocket 客户端推送搜索、索引、下载、后台任务状态和错误
+//!
+//! 各模块发布事件，没有订阅者时事件直接丢弃
+
+use crate::services::errors::ErrorCategory;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// 每个订阅者最多缓存的事件数，落后太多的订阅者会丢失旧事件
+const EVENT_CAPACITY: usize = 256;
+
+/// 推送给客户端的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AppEvent {
+    /// 搜索完成
+    SearchProgress {
+        query: String,
+        results: usize,
+        elapsed_ms: u128,
+    },
+    /// 文件夹索引进度
+    IndexingProgress {
+        folder: String,
+        scanned: usize,
+        indexed: usize,
+        current_dir: String,
+        finished: bool,
+    },
+    /// 下载任务状态变化
+    DownloadStatus {
+        gid: String,
+        path: String,
+        status: String,
+    },
+    /// 后台任务状态变化
+    JobStatus {
+        id: u64,
+        name: String,
+        status: String,
+    },
+    /// 需要告知用户的错误
+    Error {
+        category: ErrorCategory,
+        context: String,
+        message: String,
+    },
+}
+
+/// 全局事件总线
+static EVENT_BUS: OnceLock<broadcast::Sender<AppEvent>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<AppEvent> {
+    EVENT_BUS.get_or_init(|| broadcast::channel(EVENT_CAPACITY).0)
+}
+
+/// 发布事件
+pub fn publish(event: AppEvent) {
+    // 没有订阅者时 send 返回错误，忽略即可
+    let _ = sender().send(event);
+}
+
+/// 订阅事件
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    sender().subscribe()
+}
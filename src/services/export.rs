@@ -0,0 +1,46 @@
+//! 导出 - 将文件记录写入 CSV 文件
+
+use crate::models::database::FileRecord;
+use crate::utils::locale::format_datetime;
+use std::path::Path;
+use tracing::info;
+
+/// 将文件记录导出为 CSV 文件
+///
+/// # Arguments
+/// * `records` - 要导出的文件记录
+/// * `target` - 导出文件路径
+pub fn export_file_records(records: &[FileRecord], target: &Path) -> std::io::Result<()> {
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut content = String::from("name,path,size,etag,modified_time,file_type\n");
+    for record in records {
+        let size = record.size.to_string();
+        let modified = format_datetime(record.modified_time);
+        let fields = [
+            record.name.as_str(),
+            record.path.as_str(),
+            size.as_str(),
+            record.etag.as_str(),
+            modified.as_str(),
+            record.file_type.as_str(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+        content.push_str(&line.join(","));
+        content.push('\n');
+    }
+
+    std::fs::write(target, content)?;
+    info!("Exported {} items to {:?}", records.len(), target);
+    Ok(())
+}
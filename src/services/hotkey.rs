@@ -0,0 +1,80 @@
+//! 全局快捷键 - 主窗口不在前台时也能唤出快速搜索窗口
+//!
+//! 快捷键写作 `修饰键+按键`，例如 `CmdOrCtrl+Shift+Space`、`Alt+F1`，修饰键可选 Shift、Ctrl、Alt、
+//! Super 和 CmdOrCtrl（macOS 上为 Cmd，其他系统为 Ctrl），不区分大小写。
+//! Linux 上依赖 X11，纯 Wayland 会话中无法注册。没有 `hotkey` feature 时注册总是失败
+
+use anyhow::Result;
+#[cfg(feature = "hotkey")]
+use anyhow::Context;
+#[cfg(feature = "hotkey")]
+use global_hotkey::hotkey::HotKey;
+#[cfg(feature = "hotkey")]
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+#[cfg(feature = "hotkey")]
+use tracing::{debug, info, warn};
+
+/// 已注册的全局快捷键，释放时取消注册
+pub struct GlobalHotkey {
+    #[cfg(feature = "hotkey")]
+    manager: GlobalHotKeyManager,
+    #[cfg(feature = "hotkey")]
+    hotkey: HotKey,
+}
+
+/// 检查快捷键的写法
+pub fn validate(accelerator: &str) -> Result<()> {
+    parse(accelerator).map(|_| ())
+}
+
+#[cfg(feature = "hotkey")]
+fn parse(accelerator: &str) -> Result<HotKey> {
+    accelerator
+        .trim()
+        .parse::<HotKey>()
+        .with_context(|| format!("Invalid hotkey: {}", accelerator))
+}
+
+#[cfg(not(feature = "hotkey"))]
+fn parse(accelerator: &str) -> Result<()> {
+    anyhow::bail!("Built without global hotkey support, cannot use {}", accelerator)
+}
+
+/// 注册全局快捷键，需要在界面线程中、事件循环启动前调用
+///
+/// # Arguments
+/// * `accelerator` - 快捷键
+/// * `on_pressed` - 按下快捷键时调用，在快捷键监听线程中执行，访问界面需要通过事件循环
+#[cfg(feature = "hotkey")]
+pub fn register(accelerator: &str, on_pressed: impl Fn() + Send + Sync + 'static) -> Result<GlobalHotkey> {
+    let hotkey = parse(accelerator)?;
+    let manager = GlobalHotKeyManager::new().context("Failed to create global hotkey manager")?;
+    manager
+        .register(hotkey)
+        .with_context(|| format!("Failed to register hotkey {}, it may be used by another program", accelerator))?;
+    let id = hotkey.id();
+    GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+        if event.id() == id && event.state() == HotKeyState::Pressed {
+            debug!("Global hotkey pressed");
+            on_pressed();
+        }
+    }));
+    info!("Registered global hotkey {}", accelerator);
+    Ok(GlobalHotkey { manager, hotkey })
+}
+
+#[cfg(not(feature = "hotkey"))]
+pub fn register(accelerator: &str, _on_pressed: impl Fn() + Send + Sync + 'static) -> Result<GlobalHotkey> {
+    parse(accelerator)?;
+    Ok(GlobalHotkey {})
+}
+
+#[cfg(feature = "hotkey")]
+impl Drop for GlobalHotkey {
+    fn drop(&mut self) {
+        GlobalHotKeyEvent::set_event_handler(None::<fn(GlobalHotKeyEvent)>);
+        if let Err(e) = self.manager.unregister(self.hotkey) {
+            warn!("Failed to unregister global hotkey: {}", e);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! 内置 HTTP 下载服务 - 最后兜底的下载后端
+//!
+//! 当 Aria2 不可用时，直接使用 reqwest 流式写入磁盘，支持 Range 断点续传和进度回调。
+//! 下载中的数据写入 `<文件名>.part`，完成后才重命名为目标文件；`<文件名>.part.json` 记录服务器返回的
+//! ETag 和文件大小，只有与之前的下载一致时才续传
+
+use anyhow::{Context, Result};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// 下载进度信息
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress {
+    /// 已下载字节数（包含续传前已存在的部分）
+    pub downloaded: u64,
+    /// 文件总大小，服务器未返回时为 None
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// 获取下载百分比（0-100）
+    pub fn percent(&self) -> Option<u8> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| ((self.downloaded.min(total) * 100) / total) as u8)
+    }
+}
+
+/// 未完成下载的续传信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PartialDownload {
+    etag: Option<String>, // 服务器返回的 ETag，续传时作为 If-Range
+    total: Option<u64>,   // 服务器返回的文件总大小
+}
+
+impl PartialDownload {
+    /// 读取续传信息，不存在或无法解析时为 None
+    async fn load(path: &Path) -> Option<Self> {
+        let content = tokio::fs::read_to_string(path).await.ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string(self)?;
+        tokio::fs::write(path, content)
+            .await
+            .context("Failed to save download state")
+    }
+
+    /// 是否可以续传：至少有一个校验依据，并且记录的大小与期望的大小一致
+    fn matches(&self, expected_size: Option<u64>) -> bool {
+        let size_matches = match (self.total, expected_size) {
+            (Some(total), Some(expected)) => total == expected,
+            _ => true,
+        };
+        let has_validator = self.etag.is_some() || (self.total.is_some() && expected_size.is_some());
+        size_matches && has_validator
+    }
+}
+
+/// 内置 HTTP 下载器
+pub struct HttpDownloader {
+    client: reqwest::Client,
+    download_dir: PathBuf,
+}
+
+impl HttpDownloader {
+    /// 创建新的下载器
+    ///
+    /// # Arguments
+    /// * `download_dir` - 下载文件保存目录
+    pub fn new<P: AsRef<Path>>(download_dir: P) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            download_dir: download_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// 获取下载目录
+    pub fn download_dir(&self) -> &Path {
+        &self.download_dir
+    }
+
+    /// 下载文件到下载目录，之前中断的下载会通过 Range 请求续传
+    ///
+    /// # Arguments
+    /// * `url` - 下载链接
+    /// * `file_name` - 保存的文件名
+    /// * `expected_size` - 文件大小，已知时用于判断未完成的下载能否续传
+    /// * `on_progress` - 进度回调，每写入一个数据块调用一次
+    ///
+    /// # Returns
+    /// * `Result<PathBuf>` - 下载完成的文件路径
+    pub async fn download<F>(
+        &self,
+        url: &str,
+        file_name: &str,
+        expected_size: Option<u64>,
+        mut on_progress: F,
+    ) -> Result<PathBuf>
+    where
+        F: FnMut(DownloadProgress),
+    {
+        tokio::fs::create_dir_all(&self.download_dir)
+            .await
+            .context("Failed to create download directory")?;
+
+        let target = self.download_dir.join(file_name);
+        let part = Self::part_path(&target);
+        let state_path = Self::state_path(&target);
+
+        // 只有记录的下载信息与本次下载一致时才续传，否则从头下载
+        let state = PartialDownload::load(&state_path).await;
+        let part_len = tokio::fs::metadata(&part).await.map(|meta| meta.len()).unwrap_or(0);
+        let mut existing = match &state {
+            Some(state) if part_len > 0 && state.matches(expected_size) => part_len,
+            _ => 0,
+        };
+        let if_range = state.as_ref().and_then(|state| state.etag.clone());
+
+        let mut response = self.send(url, existing, if_range.as_deref()).await?;
+
+        // 请求的起点不小于文件大小：记录的大小和服务器返回的大小都与已下载的部分一致时已经完整，
+        // 否则丢弃后重新下载
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            let total = state.as_ref().and_then(|state| state.total);
+            if existing > 0 && total == Some(existing) && Self::total_size(&response, 0) == total {
+                info!("File already fully downloaded: {:?}", target);
+                on_progress(DownloadProgress {
+                    downloaded: existing,
+                    total,
+                });
+                return Self::finish(&part, &state_path, &target).await;
+            }
+            warn!("Partial download of {:?} does not match the server, restarting", target);
+            existing = 0;
+            response = self.send(url, 0, None).await?;
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Download request failed with status: {}", status);
+        }
+
+        // 仅当服务器从请求的位置返回 206 时才续传，否则从头开始写
+        let resumed = existing > 0
+            && status == StatusCode::PARTIAL_CONTENT
+            && Self::range_start(&response) == Some(existing);
+        if existing > 0 && !resumed {
+            warn!("Server did not resume {:?}, restarting download", target);
+        }
+        if resumed {
+            debug!("Resuming download of {:?} from byte {}", target, existing);
+        }
+
+        let offset = if resumed { existing } else { 0 };
+        let total = Self::total_size(&response, offset);
+        if let (Some(total), Some(expected)) = (total, expected_size) {
+            if total != expected {
+                warn!("Server reports {} bytes for {:?}, expected {}", total, target, expected);
+            }
+        }
+        if !resumed {
+            let state = PartialDownload {
+                etag: response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                total,
+            };
+            state.save(&state_path).await?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&part)
+            .await
+            .context("Failed to open partial download file")?;
+
+        let mut downloaded = offset;
+        on_progress(DownloadProgress { downloaded, total });
+
+        while let Some(chunk) = response.chunk().await.context("Failed to read response chunk")? {
+            file.write_all(&chunk)
+                .await
+                .context("Failed to write downloaded data")?;
+            downloaded += chunk.len() as u64;
+            on_progress(DownloadProgress { downloaded, total });
+        }
+
+        file.flush().await.context("Failed to flush downloaded file")?;
+        drop(file);
+
+        // 连接提前断开时保留未完成的部分，下次续传
+        if let Some(total) = total {
+            if downloaded != total {
+                anyhow::bail!("Download interrupted at {} of {} bytes, it will resume next time", downloaded, total);
+            }
+        }
+
+        let target = Self::finish(&part, &state_path, &target).await?;
+        info!("Download finished: {:?} ({} bytes)", target, downloaded);
+        Ok(target)
+    }
+
+    /// 发送下载请求，`from` 大于 0 时只请求剩余部分
+    ///
+    /// 有 ETag 时附带 `If-Range`，服务器上的文件已变化时会返回完整内容而不是 206
+    async fn send(&self, url: &str, from: u64, etag: Option<&str>) -> Result<reqwest::Response> {
+        let mut request = self.client.get(url);
+        if from > 0 {
+            request = request.header(RANGE, format!("bytes={}-", from));
+            if let Some(etag) = etag {
+                request = request.header(IF_RANGE, etag);
+            }
+        }
+        request.send().await.context("Failed to send download request")
+    }
+
+    /// 下载完成后把 `.part` 文件重命名为目标文件，并删除续传信息
+    async fn finish(part: &Path, state_path: &Path, target: &Path) -> Result<PathBuf> {
+        tokio::fs::rename(part, target)
+            .await
+            .with_context(|| format!("Failed to move {:?} to {:?}", part, target))?;
+        if let Err(e) = tokio::fs::remove_file(state_path).await {
+            debug!("Failed to remove download state {:?}: {}", state_path, e);
+        }
+        Ok(target.to_path_buf())
+    }
+
+    /// 下载中的数据文件：`<文件名>.part`
+    pub fn part_path(target: &Path) -> PathBuf {
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(".part");
+        target.with_file_name(name)
+    }
+
+    /// 续传信息文件：`<文件名>.part.json`
+    fn state_path(target: &Path) -> PathBuf {
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(".part.json");
+        target.with_file_name(name)
+    }
+
+    /// 206 响应中 Content-Range 的起始位置
+    fn range_start(response: &reqwest::Response) -> Option<u64> {
+        // Content-Range: bytes 100-199/200
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|range| range.strip_prefix("bytes "))
+            .and_then(|range| range.split('-').next())
+            .and_then(|start| start.trim().parse::<u64>().ok())
+    }
+
+    /// 从响应头推断文件总大小
+    fn total_size(response: &reqwest::Response, offset: u64) -> Option<u64> {
+        // Content-Range: bytes 100-199/200
+        if let Some(range) = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+        {
+            if let Some(total) = range.rsplit('/').next().and_then(|s| s.parse::<u64>().ok()) {
+                return Some(total);
+            }
+        }
+
+        response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(|len| len + offset)
+    }
+}
@@ -0,0 +1,187 @@
+//! 本地文件夹索引服务 - 扫描文件夹并写入数据库
+//!
+//! 递归遍历文件夹，按批次写入当前数据库，支持进度回调和取消
+
+use crate::models::database::{Database, FileRecord};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info, warn};
+
+/// 每批写入数据库的记录数
+const BATCH_SIZE: usize = 500;
+
+/// 索引进度信息
+#[derive(Debug, Clone, Default)]
+pub struct IndexProgress {
+    /// 已扫描的文件数
+    pub scanned: usize,
+    /// 已写入数据库的文件数
+    pub indexed: usize,
+    /// 当前正在扫描的目录
+    pub current_dir: String,
+}
+
+/// 索引结果
+#[derive(Debug, Clone, Copy)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub cancelled: bool,
+}
+
+/// 文件夹索引器
+pub struct Indexer {
+    database: Arc<Mutex<dyn Database>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Indexer {
+    /// 创建索引器
+    ///
+    /// # Arguments
+    /// * `database` - 写入的目标数据库
+    pub fn new(database: Arc<Mutex<dyn Database>>) -> Self {
+        Self {
+            database,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 使用外部的取消标志，用于在索引开始前就需要能够取消的场景
+    pub fn with_cancel_flag(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    /// 获取取消标志，设置为 true 后索引会在当前批次结束时停止
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// 递归索引文件夹
+    ///
+    /// # Arguments
+    /// * `root` - 要索引的文件夹
+    /// * `on_progress` - 进度回调，每写入一批调用一次
+    ///
+    /// # Returns
+    /// * `Result<IndexSummary>` - 写入的记录数以及是否被取消
+    pub fn index_folder<F>(&self, root: &Path, mut on_progress: F) -> Result<IndexSummary>
+    where
+        F: FnMut(&IndexProgress),
+    {
+        if !root.is_dir() {
+            anyhow::bail!("Not a directory: {}", root.display());
+        }
+
+        info!("Indexing folder: {:?}", root);
+        let mut progress = IndexProgress::default();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut pending_dirs: Vec<PathBuf> = vec![root.to_path_buf()];
+
+        while let Some(dir) = pending_dirs.pop() {
+            if self.cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+
+            progress.current_dir = dir.to_string_lossy().to_string();
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Failed to read directory {:?}: {}", dir, e);
+                    continue;
+                }
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let file_type = match entry.file_type() {
+                    Ok(file_type) => file_type,
+                    Err(_) => continue,
+                };
+
+                if file_type.is_dir() {
+                    pending_dirs.push(path);
+                } else if file_type.is_file() {
+                    if let Some(record) = file_record_from_path(&path) {
+                        progress.scanned += 1;
+                        batch.push(record);
+                    }
+                }
+
+                if batch.len() >= BATCH_SIZE {
+                    progress.indexed += self.flush(&mut batch)?;
+                    on_progress(&progress);
+                    if self.cancelled.load(Ordering::Relaxed) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let cancelled = self.cancelled.load(Ordering::Relaxed);
+        if !cancelled {
+            progress.indexed += self.flush(&mut batch)?;
+            on_progress(&progress);
+        }
+
+        info!(
+            "Indexing {:?} finished: {} files indexed{}",
+            root,
+            progress.indexed,
+            if cancelled { " (cancelled)" } else { "" }
+        );
+
+        Ok(IndexSummary {
+            indexed: progress.indexed,
+            cancelled,
+        })
+    }
+
+    /// 将一批记录写入数据库
+    fn flush(&self, batch: &mut Vec<FileRecord>) -> Result<usize> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let count = self
+            .database
+            .lock()
+            .unwrap()
+            .insert_files(batch)
+            .context("Failed to write indexed files")?;
+        debug!("Indexed batch of {} files", count);
+        batch.clear();
+        Ok(count)
+    }
+}
+
+/// 根据本地文件生成文件记录
+///
+/// 本地文件没有网盘 ETag，`etag` 留空，`file_type` 使用小写扩展名
+pub fn file_record_from_path(path: &Path) -> Option<FileRecord> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let modified_time = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let file_type = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    Some(FileRecord {
+        id: 0,
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        etag: String::new(),
+        modified_time,
+        file_type,
+        name,
+    })
+}
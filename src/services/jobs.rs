@@ -0,0 +1,284 @@
+//! 后台任务队列 - 在 tokio 运行时中执行耗时操作
+//!
+//! 索引、批量获取链接、导出、批量下载等操作提交为任务，
+//! 可以查询任务状态和进度，并通过取消标志请求任务停止
+
+use crate::services::events::{self, AppEvent};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info};
+
+/// 保留的已结束任务数，超过后删除最早结束的任务
+const FINISHED_JOB_HISTORY: usize = 100;
+
+/// 任务 ID
+pub type JobId = u64;
+
+/// 任务状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", content = "message", rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    /// 任务是否已经结束
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed(_) | Self::Cancelled)
+    }
+
+    /// 状态名称，用于事件推送
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed(_) => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// 任务信息
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub name: String,
+    pub status: JobStatus,
+    pub progress: String, // 最近一次进度描述
+    pub created_at: i64,
+}
+
+/// 任务执行时的上下文，用于报告进度和检查取消
+#[derive(Clone)]
+pub struct JobContext {
+    id: JobId,
+    cancelled: Arc<AtomicBool>,
+    jobs: Arc<Mutex<BTreeMap<JobId, JobEntry>>>,
+}
+
+impl JobContext {
+    /// 任务 ID
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// 是否已请求取消，任务应在合适的位置检查并尽快返回
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// 取消标志，可交给支持取消的服务（如 `Indexer`）
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// 报告进度
+    pub fn set_progress(&self, progress: impl Into<String>) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(&self.id) {
+            entry.info.progress = progress.into();
+        }
+    }
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// 后台任务队列
+pub struct JobQueue {
+    runtime: Handle,
+    slots: Arc<Semaphore>,
+    next_id: AtomicU64,
+    jobs: Arc<Mutex<BTreeMap<JobId, JobEntry>>>,
+}
+
+/// 共享的任务队列实例
+pub type SharedJobQueue = Arc<JobQueue>;
+
+impl JobQueue {
+    /// 创建任务队列
+    ///
+    /// # Arguments
+    /// * `runtime` - 执行任务的 tokio 运行时
+    /// * `concurrency` - 同时运行的最大任务数
+    pub fn new(runtime: Handle, concurrency: usize) -> Self {
+        Self {
+            runtime,
+            slots: Arc::new(Semaphore::new(concurrency.max(1))),
+            next_id: AtomicU64::new(1),
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// 提交任务，可以在任意线程（包括界面线程）调用
+    ///
+    /// # Arguments
+    /// * `name` - 任务名称
+    /// * `job` - 接收上下文并返回任务 Future 的闭包
+    ///
+    /// # Returns
+    /// * `JobId` - 任务 ID
+    pub fn submit<F, Fut>(&self, name: impl Into<String>, job: F) -> JobId
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let name = name.into();
+        info!("Job {} queued: {}", id, name);
+        publish_status(id, &name, &JobStatus::Queued);
+
+        self.jobs.lock().unwrap().insert(
+            id,
+            JobEntry {
+                info: JobInfo {
+                    id,
+                    name,
+                    status: JobStatus::Queued,
+                    progress: String::new(),
+                    created_at: chrono::Local::now().timestamp(),
+                },
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        let context = JobContext {
+            id,
+            cancelled,
+            jobs: self.jobs.clone(),
+        };
+        let slots = self.slots.clone();
+        let jobs = self.jobs.clone();
+        self.runtime.spawn(async move {
+            let _permit = match slots.acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+
+            // 排队期间被取消的任务不再执行
+            if context.is_cancelled() {
+                Self::finish(&jobs, id, JobStatus::Cancelled);
+                return;
+            }
+            Self::set_status(&jobs, id, JobStatus::Running);
+
+            let cancel_flag = context.cancel_flag();
+            let status = match job(context).await {
+                Ok(()) if cancel_flag.load(Ordering::Relaxed) => JobStatus::Cancelled,
+                Ok(()) => JobStatus::Completed,
+                Err(e) => {
+                    error!("Job {} failed: {}", id, e);
+                    JobStatus::Failed(e.to_string())
+                }
+            };
+            Self::finish(&jobs, id, status);
+        });
+
+        id
+    }
+
+    /// 请求取消任务
+    ///
+    /// # Returns
+    /// * `bool` - 任务存在且尚未结束时返回 true
+    pub fn cancel(&self, id: JobId) -> bool {
+        match self.jobs.lock().unwrap().get(&id) {
+            Some(entry) if !entry.info.status.is_finished() => {
+                info!("Cancelling job {}", id);
+                entry.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 查询任务
+    pub fn get(&self, id: JobId) -> Option<JobInfo> {
+        self.jobs.lock().unwrap().get(&id).map(|entry| entry.info.clone())
+    }
+
+    /// 所有任务，按提交顺序
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    /// 排队或运行中的任务数
+    pub fn active_count(&self) -> usize {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|entry| !entry.info.status.is_finished())
+            .count()
+    }
+
+    fn set_status(jobs: &Mutex<BTreeMap<JobId, JobEntry>>, id: JobId, status: JobStatus) {
+        if let Some(entry) = jobs.lock().unwrap().get_mut(&id) {
+            publish_status(id, &entry.info.name, &status);
+            entry.info.status = status;
+        }
+    }
+
+    /// 标记任务结束，并清理过多的历史任务
+    fn finish(jobs: &Mutex<BTreeMap<JobId, JobEntry>>, id: JobId, status: JobStatus) {
+        debug!("Job {} finished: {:?}", id, status);
+        let mut jobs = jobs.lock().unwrap();
+        if let Some(entry) = jobs.get_mut(&id) {
+            publish_status(id, &entry.info.name, &status);
+            entry.info.status = status;
+        }
+
+        let finished: Vec<JobId> = jobs
+            .iter()
+            .filter(|(_, entry)| entry.info.status.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+        if finished.len() > FINISHED_JOB_HISTORY {
+            for id in &finished[..finished.len() - FINISHED_JOB_HISTORY] {
+                jobs.remove(id);
+            }
+        }
+    }
+}
+
+/// 发布任务状态事件
+fn publish_status(id: JobId, name: &str, status: &JobStatus) {
+    events::publish(AppEvent::JobStatus {
+        id,
+        name: name.to_string(),
+        status: status.label().to_string(),
+    });
+}
+
+/// 后端接口使用的任务队列，启动时设置
+static SHARED_JOB_QUEUE: OnceLock<SharedJobQueue> = OnceLock::new();
+
+/// 设置后端接口使用的任务队列，只能设置一次
+pub fn set_shared_job_queue(queue: SharedJobQueue) {
+    if SHARED_JOB_QUEUE.set(queue).is_err() {
+        debug!("Job queue already set");
+    }
+}
+
+/// 获取后端接口使用的任务队列
+pub fn shared_job_queue() -> Option<SharedJobQueue> {
+    SHARED_JOB_QUEUE.get().cloned()
+}
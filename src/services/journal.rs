@@ -0,0 +1,174 @@
+//! 操作日志 - 记录对索引的写入，支持撤销和重做
+//!
+//! 通过 `DatabaseManager::apply` 执行的插入、删除和重命名会记录在当前数据库的日志中，
+//! 切换数据库后清空。日志只保留最近 `JOURNAL_LIMIT` 次操作
+
+use crate::models::database::{Database, FileRecord, RenameEntry};
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+
+/// 最多保留的操作数
+pub const JOURNAL_LIMIT: usize = 100;
+
+/// 一次对索引的写入
+///
+/// 插入和删除都按 `id` 作用于具体的记录，执行后记录分配的 `id` 和删除前的校验结果，
+/// 撤销删除时按原 `id` 恢复，之后的重命名仍能匹配到同一条记录
+#[derive(Debug, Clone)]
+pub enum IndexOperation {
+    /// 插入记录，`id` 为 0 的记录执行时分配新的 ID
+    Insert {
+        records: Vec<FileRecord>,
+        verified: HashMap<String, bool>, // 路径 -> 本地文件校验结果
+    },
+    /// 按 `id` 删除记录
+    Delete {
+        records: Vec<FileRecord>,
+        verified: HashMap<String, bool>,
+    },
+    /// 按 `id` 修改名称和路径
+    Rename(Vec<RenameEntry>),
+}
+
+impl IndexOperation {
+    /// 插入新记录
+    pub fn insert(records: Vec<FileRecord>) -> Self {
+        Self::Insert {
+            records,
+            verified: HashMap::new(),
+        }
+    }
+
+    /// 删除已有记录，校验结果在执行时保存
+    pub fn delete(records: Vec<FileRecord>) -> Self {
+        Self::Delete {
+            records,
+            verified: HashMap::new(),
+        }
+    }
+
+    /// 在数据库中执行
+    ///
+    /// # Returns
+    /// * `Result<(usize, IndexOperation)>` - 影响的记录数，以及补全了 `id` 和校验结果、可以记录到日志中的操作
+    pub fn execute(&self, database: &dyn Database) -> Result<(usize, Self)> {
+        match self {
+            Self::Insert { records, verified } => {
+                let ids = database.restore_files(records, verified)?;
+                let records = records
+                    .iter()
+                    .zip(ids)
+                    .map(|(record, id)| FileRecord { id, ..record.clone() })
+                    .collect::<Vec<_>>();
+                Ok((
+                    records.len(),
+                    Self::Insert {
+                        records,
+                        verified: verified.clone(),
+                    },
+                ))
+            }
+            Self::Delete { records, .. } => {
+                let paths: Vec<String> = records.iter().map(|r| r.path.clone()).collect();
+                let verified = database.verification_status(&paths)?;
+                let ids: Vec<i64> = records.iter().map(|r| r.id).collect();
+                let deleted = database.delete_files(&ids)?;
+                Ok((
+                    deleted,
+                    Self::Delete {
+                        records: records.clone(),
+                        verified,
+                    },
+                ))
+            }
+            Self::Rename(renames) => Ok((database.rename_files(renames)?, self.clone())),
+        }
+    }
+
+    /// 撤销该操作的反向操作
+    pub fn inverse(&self) -> Self {
+        match self {
+            Self::Insert { records, verified } => Self::Delete {
+                records: records.clone(),
+                verified: verified.clone(),
+            },
+            Self::Delete { records, verified } => Self::Insert {
+                records: records.clone(),
+                verified: verified.clone(),
+            },
+            Self::Rename(renames) => Self::Rename(renames.iter().map(RenameEntry::reversed).collect()),
+        }
+    }
+
+    /// 界面显示的描述，例如"删除 3 个文件"
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Insert { records, .. } => format!("添加 {} 个文件", records.len()),
+            Self::Delete { records, .. } => format!("删除 {} 个文件", records.len()),
+            Self::Rename(renames) => format!("重命名 {} 个文件", renames.len()),
+        }
+    }
+}
+
+/// 撤销和重做栈
+#[derive(Debug, Default)]
+pub struct OperationJournal {
+    undo: VecDeque<IndexOperation>,
+    redo: Vec<IndexOperation>,
+}
+
+impl OperationJournal {
+    /// 记录新执行的操作，清空重做栈
+    pub fn record(&mut self, operation: IndexOperation) {
+        self.redo.clear();
+        self.push_undo(operation);
+    }
+
+    /// 放回撤销栈，不影响重做栈
+    pub fn push_undo(&mut self, operation: IndexOperation) {
+        self.undo.push_back(operation);
+        while self.undo.len() > JOURNAL_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// 放回重做栈
+    pub fn push_redo(&mut self, operation: IndexOperation) {
+        self.redo.push(operation);
+    }
+
+    /// 取出最近一次可撤销的操作
+    pub fn take_undo(&mut self) -> Option<IndexOperation> {
+        self.undo.pop_back()
+    }
+
+    /// 取出最近一次可重做的操作
+    pub fn take_redo(&mut self) -> Option<IndexOperation> {
+        self.redo.pop()
+    }
+
+    /// 下一次撤销的描述
+    pub fn next_undo(&self) -> Option<String> {
+        self.undo.back().map(IndexOperation::describe)
+    }
+
+    /// 下一次重做的描述
+    pub fn next_redo(&self) -> Option<String> {
+        self.redo.last().map(IndexOperation::describe)
+    }
+
+    /// 可撤销的操作数
+    pub fn len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty()
+    }
+
+    /// 清空日志
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,118 @@
+//! 链接格式 - 复制下载链接时可选的输出格式
+//!
+//! 支持纯链接、aria2c 命令、curl 命令、Markdown 链接和包含 ETag/大小的 JSON
+
+use crate::utils::common::format_upload_filename;
+use serde_json::json;
+
+/// 复制链接的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkFormat {
+    #[default]
+    Url,
+    Aria2c,
+    Curl,
+    Markdown,
+    Json,
+}
+
+/// 一个待格式化的下载链接
+#[derive(Debug, Clone)]
+pub struct LinkEntry {
+    pub path: String,
+    pub etag: String,
+    pub size: u64,
+    pub url: String,
+}
+
+impl LinkEntry {
+    /// 文件名，用作下载时的保存名称
+    pub fn file_name(&self) -> String {
+        format_upload_filename(&self.path).unwrap_or_else(|| self.etag.clone())
+    }
+}
+
+impl LinkFormat {
+    /// 所有格式，顺序与界面中"复制为…"菜单一致
+    pub const ALL: [LinkFormat; 5] = [
+        Self::Url,
+        Self::Aria2c,
+        Self::Curl,
+        Self::Markdown,
+        Self::Json,
+    ];
+
+    /// 根据界面菜单中的索引获取格式，超出范围时为纯链接
+    pub fn from_index(index: i32) -> Self {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| Self::ALL.get(index).copied())
+            .unwrap_or_default()
+    }
+
+    /// 界面显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Url => "链接",
+            Self::Aria2c => "aria2c 命令",
+            Self::Curl => "curl 命令",
+            Self::Markdown => "Markdown",
+            Self::Json => "JSON",
+        }
+    }
+
+    /// 格式化多个链接，除 JSON 外每行一个
+    ///
+    /// # Arguments
+    /// * `entries` - 下载链接
+    pub fn format(&self, entries: &[LinkEntry]) -> String {
+        if *self == Self::Json {
+            let items: Vec<_> = entries
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "name": entry.file_name(),
+                        "path": entry.path,
+                        "etag": entry.etag,
+                        "size": entry.size,
+                        "url": entry.url,
+                    })
+                })
+                .collect();
+            return serde_json::to_string_pretty(&items).unwrap_or_default();
+        }
+
+        entries
+            .iter()
+            .map(|entry| self.format_one(entry))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 格式化单个链接
+    fn format_one(&self, entry: &LinkEntry) -> String {
+        match self {
+            Self::Url | Self::Json => entry.url.clone(),
+            Self::Aria2c => format!(
+                "aria2c -o {} {}",
+                shell_quote(&entry.file_name()),
+                shell_quote(&entry.url)
+            ),
+            Self::Curl => format!(
+                "curl -L -o {} {}",
+                shell_quote(&entry.file_name()),
+                shell_quote(&entry.url)
+            ),
+            Self::Markdown => format!(
+                "[{}]({})",
+                entry.file_name().replace('[', "\\[").replace(']', "\\]"),
+                entry.url.replace('(', "%28").replace(')', "%29")
+            ),
+        }
+    }
+}
+
+/// 用单引号包裹参数，供 shell 命令使用
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -0,0 +1,344 @@
+//! 下载链接解析 - 通过后端获取文件下载链接，并按 ETag 缓存
+//!
+//! 获取链接需要先上传（秒传）再请求下载地址，同一个文件短时间内重复复制或发送时
+//! 直接使用缓存的链接。链接过期导致下载失败时应调用 `invalidate` 后重新获取。
+//! 网盘中已不存在的文件会被记录下来，界面据此显示"已失效"状态。
+//! 秒传的目标文件夹由当前数据库的 `remote_folder_id` 决定，切换数据库时更新
+
+use crate::utils::common::format_upload_filename;
+use crate::services::api_client::{
+    shared_api_client, LinkError, SharedApiClient, UploadFileItemPayload,
+};
+use netdisk_core::responses::prelude::FileQuery;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
+
+/// 批量获取下载链接时同时进行的请求数
+const BATCH_LINK_CONCURRENCY: usize = 4;
+
+/// 未设置共享解析器时的缓存有效期
+const DEFAULT_TTL: Duration = Duration::from_secs(600);
+
+/// 缓存键：ETag 和文件大小
+type LinkKey = (String, u64);
+
+/// 批量获取链接时单个文件的结果
+#[derive(Debug)]
+pub struct LinkResult {
+    pub path: String,
+    pub etag: String,
+    pub size: u64,
+    pub link: Result<String, LinkError>,
+}
+
+/// 下载链接解析器
+pub struct LinkResolver {
+    api: SharedApiClient,
+    ttl: Duration,
+    verify_remote: bool,
+    parent_folder: AtomicI64,
+    cache: Mutex<HashMap<LinkKey, (String, Instant)>>,
+    missing: Mutex<HashSet<LinkKey>>,
+}
+
+/// 共享的链接解析器实例
+pub type SharedLinkResolver = Arc<LinkResolver>;
+
+/// 复制链接和发送下载使用的解析器，启动后端服务后设置
+static SHARED_LINK_RESOLVER: OnceLock<SharedLinkResolver> = OnceLock::new();
+
+/// 设置共享的链接解析器，只能设置一次
+pub fn set_shared_link_resolver(resolver: SharedLinkResolver) {
+    if SHARED_LINK_RESOLVER.set(resolver).is_err() {
+        debug!("Link resolver already set");
+    }
+}
+
+/// 获取共享的链接解析器，未设置时使用共享 API 客户端和默认有效期
+pub fn shared_link_resolver() -> SharedLinkResolver {
+    SHARED_LINK_RESOLVER
+        .get_or_init(|| Arc::new(LinkResolver::new(shared_api_client(), DEFAULT_TTL)))
+        .clone()
+}
+
+/// 获取文件的下载链接，同一文件短时间内使用缓存的链接
+///
+/// # Arguments
+/// * `path` - 文件路径
+/// * `etag` - 文件ETag
+/// * `size` - 文件大小
+pub async fn get_file_url<T>(
+    path: T,
+    etag: T,
+    size: u64,
+) -> Result<String, LinkError>
+where
+    T: AsRef<str> + std::fmt::Debug,
+{
+    shared_link_resolver()
+        .resolve(path.as_ref(), etag.as_ref(), size)
+        .await
+}
+
+/// 并发获取多个文件的下载链接，单个文件失败不影响其他文件
+///
+/// # Arguments
+/// * `items` - 文件路径、ETag 和大小
+///
+/// # Returns
+/// * `Vec<LinkResult>` - 与输入顺序一致的结果，失败的文件带有错误信息
+pub async fn get_file_urls(items: Vec<(String, String, u64)>) -> Vec<LinkResult> {
+    shared_link_resolver()
+        .resolve_many(items, BATCH_LINK_CONCURRENCY)
+        .await
+}
+
+/// 批量获取下载链接，每完成一个文件调用一次 `on_progress(已完成数, 总数)`
+pub async fn get_file_urls_with_progress(
+    items: Vec<(String, String, u64)>,
+    on_progress: impl FnMut(usize, usize),
+) -> Vec<LinkResult> {
+    shared_link_resolver()
+        .resolve_many_with_progress(items, BATCH_LINK_CONCURRENCY, on_progress)
+        .await
+}
+
+impl LinkResolver {
+    /// 创建链接解析器
+    ///
+    /// # Arguments
+    /// * `api` - 后端 API 客户端
+    /// * `ttl` - 链接缓存有效期，为 0 时不缓存
+    pub fn new(api: SharedApiClient, ttl: Duration) -> Self {
+        Self {
+            api,
+            ttl,
+            verify_remote: false,
+            parent_folder: AtomicI64::new(0),
+            cache: Mutex::new(HashMap::new()),
+            missing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 设置是否在复制链接和发送下载前检查文件在网盘中是否存在
+    pub fn with_remote_check(mut self, enabled: bool) -> Self {
+        self.verify_remote = enabled;
+        self
+    }
+
+    /// 是否在复制链接和发送下载前检查文件在网盘中是否存在
+    pub fn verifies_remote(&self) -> bool {
+        self.verify_remote
+    }
+
+    /// 设置秒传的目标网盘文件夹
+    ///
+    /// # Arguments
+    /// * `folder_id` - 网盘文件夹 ID，0 为根目录
+    pub fn set_parent_folder(&self, folder_id: i64) {
+        debug!("Using remote parent folder {}", folder_id);
+        self.parent_folder.store(folder_id, Ordering::Relaxed);
+    }
+
+    /// 秒传的目标网盘文件夹 ID
+    pub fn parent_folder(&self) -> i64 {
+        self.parent_folder.load(Ordering::Relaxed)
+    }
+
+    /// 检查文件在网盘中是否存在，结果会更新"已失效"标记
+    ///
+    /// # Returns
+    /// * `Result<bool>` - 文件是否存在，网络等其他错误返回 Err
+    pub async fn check_remote(&self, path: &str, etag: &str, size: u64) -> Result<bool, LinkError> {
+        if self.cached(etag, size).is_some() {
+            return Ok(true);
+        }
+        match self.file_id(path, etag, size).await {
+            Ok(_) => Ok(true),
+            Err(LinkError::RemoteMissing(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 文件是否已知在网盘中不存在
+    pub fn is_missing(&self, etag: &str, size: u64) -> bool {
+        self.missing
+            .lock()
+            .unwrap()
+            .contains(&(etag.to_string(), size))
+    }
+
+    /// 获取文件的下载链接，缓存未过期时直接返回
+    ///
+    /// # Arguments
+    /// * `path` - 文件路径，用于上传时的文件名
+    /// * `etag` - 文件ETag
+    /// * `size` - 文件大小
+    pub async fn resolve(
+        &self,
+        path: &str,
+        etag: &str,
+        size: u64,
+    ) -> Result<String, LinkError> {
+        if let Some(link) = self.cached(etag, size) {
+            debug!("Using cached download link for {}", etag);
+            return Ok(link);
+        }
+
+        let link = self.fetch(path, etag, size).await?;
+        self.insert(etag, size, link.clone());
+        Ok(link)
+    }
+
+    /// 并发获取多个文件的下载链接，单个文件失败不影响其他文件
+    ///
+    /// # Arguments
+    /// * `items` - 文件路径、ETag 和大小
+    /// * `concurrency` - 同时进行的请求数
+    ///
+    /// # Returns
+    /// * `Vec<LinkResult>` - 与输入顺序一致的结果
+    pub async fn resolve_many(
+        self: &Arc<Self>,
+        items: Vec<(String, String, u64)>,
+        concurrency: usize,
+    ) -> Vec<LinkResult> {
+        self.resolve_many_with_progress(items, concurrency, |_, _| {}).await
+    }
+
+    /// 并发获取多个文件的下载链接，每完成一个文件调用一次 `on_progress(已完成数, 总数)`
+    pub async fn resolve_many_with_progress(
+        self: &Arc<Self>,
+        items: Vec<(String, String, u64)>,
+        concurrency: usize,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Vec<LinkResult> {
+        let total = items.len();
+        let mut done = 0;
+        let slots = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        let mut results: Vec<Option<LinkResult>> = (0..items.len()).map(|_| None).collect();
+
+        for (index, (path, etag, size)) in items.into_iter().enumerate() {
+            let resolver = self.clone();
+            let slots = slots.clone();
+            tasks.spawn(async move {
+                // 信号量不会被关闭，获取失败时直接请求
+                let _permit = slots.acquire_owned().await.ok();
+                let link = resolver.resolve(&path, &etag, size).await;
+                (index, LinkResult { path, etag, size, link })
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => error!("Link resolution task failed: {}", e),
+            }
+            done += 1;
+            on_progress(done, total);
+        }
+
+        let failed = results
+            .iter()
+            .filter(|result| !matches!(result, Some(LinkResult { link: Ok(_), .. })))
+            .count();
+        if failed > 0 {
+            warn!("{} of {} links could not be resolved", failed, results.len());
+        }
+        results.into_iter().flatten().collect()
+    }
+
+    /// 获取未过期的缓存链接
+    pub fn cached(&self, etag: &str, size: u64) -> Option<String> {
+        let mut cache = self.cache.lock().unwrap();
+        let key = (etag.to_string(), size);
+        match cache.get(&key) {
+            Some((link, expires)) if *expires > Instant::now() => Some(link.clone()),
+            Some(_) => {
+                cache.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 缓存链接
+    pub fn insert(&self, etag: &str, size: u64, link: String) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut cache = self.cache.lock().unwrap();
+        let now = Instant::now();
+        cache.retain(|_, (_, expires)| *expires > now);
+        cache.insert((etag.to_string(), size), (link, now + self.ttl));
+    }
+
+    /// 移除缓存的链接，链接失效时调用
+    pub fn invalidate(&self, etag: &str, size: u64) {
+        self.cache.lock().unwrap().remove(&(etag.to_string(), size));
+    }
+
+    /// 通过后端上传（秒传）文件信息，获取网盘中的文件 ID
+    ///
+    /// # Arguments
+    /// * `path` - 文件路径，用于上传时的文件名
+    /// * `etag` - 文件ETag
+    /// * `size` - 文件大小
+    pub async fn file_id(&self, path: &str, etag: &str, size: u64) -> Result<i64, LinkError> {
+        let payload = UploadFileItemPayload {
+            parent_file_id: self.parent_folder(),
+            filename: format_upload_filename(path)
+                .ok_or_else(|| LinkError::InvalidPath(path.to_string()))?,
+            etag: etag.to_string(),
+            size,
+        };
+
+        // 发送文件上传请求
+        let key = (etag.to_string(), size);
+        match self.api.upload_file(&payload).await {
+            Ok(file_id) => {
+                info!("后台服务请求成功完成。{:?}", &file_id);
+                self.missing.lock().unwrap().remove(&key);
+                Ok(file_id.parse::<i64>().unwrap_or(0))
+            }
+            Err(e) => {
+                if matches!(e, LinkError::RemoteMissing(_)) {
+                    self.missing.lock().unwrap().insert(key);
+                }
+                error!("请求失败，错误信息: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// 通过后端上传文件信息并获取下载链接
+    async fn fetch(&self, path: &str, etag: &str, size: u64) -> Result<String, LinkError> {
+        let query = FileQuery {
+            file_id: self.file_id(path, etag, size).await?,
+        };
+        debug!("准备获取下载链接，查询参数: {:?}", &query);
+
+        match self.api.download_url(&query).await {
+            Ok(download_response) => match download_response.data {
+                Some(data) => {
+                    info!("响应数据: {:?}", &data.download_url);
+                    Ok(data.download_url)
+                }
+                None => {
+                    error!("响应数据为空");
+                    Err(LinkError::MissingData("下载链接"))
+                }
+            },
+            Err(e) => {
+                error!("获取下载链接失败，错误信息: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
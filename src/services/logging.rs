@@ -0,0 +1,99 @@
+//! 日志 - 按配置初始化 tracing 日志
+//!
+//! 日志级别按模块在配置中设置，环境变量 `RUST_LOG` 优先；控制台输出可选文本或 JSON，
+//! 同时写入日志目录下按天滚动的日志文件，超过保留数量的旧文件自动删除。
+//! 最近的日志另外保存在内存中，写入崩溃报告
+
+use crate::models::config::{LogFormat, LoggingConfig};
+use crate::services::crash_report::RecentLogsLayer;
+use crate::utils::paths;
+use anyhow::{Context, Result};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+/// 日志文件名前缀，文件名形如 `netdisk_db.2024-01-01.log`
+pub const LOG_FILE_PREFIX: &str = "netdisk_db";
+
+/// 本程序的模块名，`--verbose` 时提高到 debug
+const CRATE_TARGET: &str = "netdisk_db";
+
+/// 由配置生成日志过滤规则，例如 `info,actix_web=warn`
+///
+/// # Arguments
+/// * `config` - 日志配置
+/// * `verbose` - 是否将本程序的日志级别提高到 debug
+pub fn filter_directives(config: &LoggingConfig, verbose: bool) -> String {
+    let mut directives = vec![config.level.trim().to_string()];
+    directives.extend(
+        config
+            .modules
+            .iter()
+            .filter(|(module, _)| !(verbose && module.as_str() == CRATE_TARGET))
+            .map(|(module, level)| format!("{}={}", module.trim(), level.trim())),
+    );
+    if verbose {
+        directives.push(format!("{}=debug", CRATE_TARGET));
+    }
+    directives.retain(|directive| !directive.is_empty());
+    directives.join(",")
+}
+
+/// 初始化全局日志，只能调用一次
+///
+/// # Arguments
+/// * `config` - 日志配置
+/// * `verbose` - 是否将本程序的日志级别提高到 debug
+/// * `stderr` - 控制台日志写到 stderr（命令行模式，避免混入输出）
+///
+/// # Returns
+/// * `Result<Option<WorkerGuard>>` - 写入日志文件时返回的后台写入句柄，需要保留到程序退出
+pub fn init(config: &LoggingConfig, verbose: bool, stderr: bool) -> Result<Option<WorkerGuard>> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::new(directives),
+        _ => EnvFilter::try_new(filter_directives(config, verbose))
+            .context("Invalid logging level in config")?,
+    };
+
+    let writer = if stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let console = match config.format {
+        LogFormat::Pretty => fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Compact => fmt::layer().compact().with_writer(writer).boxed(),
+        LogFormat::Json => fmt::layer().json().with_writer(writer).boxed(),
+    };
+
+    let (file, guard) = if config.file {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(config.max_files.max(1))
+            .build(paths::log_dir())
+            .context("Failed to create log file")?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        // 日志文件不使用终端颜色，JSON 格式时文件也写 JSON
+        let layer = match config.format {
+            LogFormat::Json => fmt::layer().json().with_writer(writer).boxed(),
+            _ => fmt::layer().with_ansi(false).with_writer(writer).boxed(),
+        };
+        (Some(layer), Some(guard))
+    } else {
+        (None, None)
+    };
+
+    Registry::default()
+        .with(filter)
+        .with(console)
+        .with(file)
+        .with(RecentLogsLayer)
+        .try_init()
+        .context("Failed to initialize logging")?;
+    Ok(guard)
+}
@@ -0,0 +1,284 @@
+//! 媒体信息 - 用 ffprobe 读取本地视频和音频文件的时长、分辨率和编码
+//!
+//! 结果按 路径+大小+修改时间 缓存在缓存目录下的独立 SQLite 数据库中，
+//! 文件变化后重新读取。未安装 ffprobe 时返回错误，详情面板中不显示媒体信息
+
+use crate::utils::filetype::{category_of, FileCategory};
+use anyhow::{bail, Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+use serde::Deserialize;
+use std::sync::{Arc, OnceLock};
+use std::time::UNIX_EPOCH;
+use tracing::debug;
+
+/// 媒体信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaInfo {
+    pub duration_secs: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub bit_rate: Option<u64>, // 总码率（bit/s）
+}
+
+impl MediaInfo {
+    /// 详情面板中显示的摘要，例如 "1:30:05 · 1920×1080 · h264 / aac · 5.0 Mbps"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(duration) = self.duration_secs {
+            parts.push(format_duration(duration));
+        }
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            parts.push(format!("{}×{}", width, height));
+        }
+        let codecs: Vec<&str> = [&self.video_codec, &self.audio_codec]
+            .into_iter()
+            .filter_map(|codec| codec.as_deref())
+            .collect();
+        if !codecs.is_empty() {
+            parts.push(codecs.join(" / "));
+        }
+        if let Some(bit_rate) = self.bit_rate {
+            parts.push(format!("{:.1} Mbps", bit_rate as f64 / 1_000_000.0));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// 将秒数格式化为 "H:MM:SS"，不足一小时时为 "M:SS"
+pub fn format_duration(secs: f64) -> String {
+    let total = secs.max(0.0).round() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// ffprobe `-print_format json` 输出中用到的字段
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// 解析 ffprobe 的 JSON 输出，使用第一个视频流和第一个音频流
+pub fn parse_ffprobe_output(json: &str) -> Result<MediaInfo> {
+    let output: ProbeOutput = serde_json::from_str(json).context("Invalid ffprobe output")?;
+    let stream = |kind: &str| {
+        output
+            .streams
+            .iter()
+            .find(|stream| stream.codec_type.as_deref() == Some(kind))
+    };
+    let video = stream("video");
+    let audio = stream("audio");
+    let format = output.format.as_ref();
+
+    // 部分容器只在流中记录时长
+    let duration = format
+        .and_then(|format| format.duration.as_deref())
+        .or_else(|| video.or(audio).and_then(|stream| stream.duration.as_deref()))
+        .and_then(|duration| duration.parse::<f64>().ok());
+
+    Ok(MediaInfo {
+        duration_secs: duration,
+        width: video.and_then(|stream| stream.width),
+        height: video.and_then(|stream| stream.height),
+        video_codec: video.and_then(|stream| stream.codec_name.clone()),
+        audio_codec: audio.and_then(|stream| stream.codec_name.clone()),
+        bit_rate: format
+            .and_then(|format| format.bit_rate.as_deref())
+            .and_then(|bit_rate| bit_rate.parse().ok()),
+    })
+}
+
+/// 运行 ffprobe 读取媒体信息
+///
+/// # Arguments
+/// * `path` - 本地文件路径
+pub async fn probe(path: &str) -> Result<MediaInfo> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("无法运行 ffprobe，请确认已安装 FFmpeg")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_ffprobe_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 媒体信息缓存
+pub struct MediaInfoCache {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl MediaInfoCache {
+    /// 打开缓存数据库，不存在时创建
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    pub fn open(path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path);
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .context("Failed to create media info connection pool")?;
+
+        pool.get()
+            .context("Failed to get connection from pool")?
+            .execute(
+                "CREATE TABLE IF NOT EXISTS media_info (
+                    path TEXT PRIMARY KEY,
+                    size INTEGER NOT NULL,
+                    modified INTEGER NOT NULL,
+                    duration REAL,
+                    width INTEGER,
+                    height INTEGER,
+                    video_codec TEXT,
+                    audio_codec TEXT,
+                    bit_rate INTEGER
+                )",
+                [],
+            )
+            .context("Failed to create media_info table")?;
+
+        Ok(Self { pool })
+    }
+
+    /// 读取缓存，文件大小或修改时间变化后视为未缓存
+    pub fn get(&self, path: &str, size: u64, modified: i64) -> Result<Option<MediaInfo>> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        conn.query_row(
+            "SELECT duration, width, height, video_codec, audio_codec, bit_rate
+             FROM media_info WHERE path = ?1 AND size = ?2 AND modified = ?3",
+            params![path, size as i64, modified],
+            |row| {
+                Ok(MediaInfo {
+                    duration_secs: row.get(0)?,
+                    width: row.get(1)?,
+                    height: row.get(2)?,
+                    video_codec: row.get(3)?,
+                    audio_codec: row.get(4)?,
+                    bit_rate: row.get::<_, Option<i64>>(5)?.map(|bit_rate| bit_rate as u64),
+                })
+            },
+        )
+        .optional()
+        .context("Failed to read media info")
+    }
+
+    /// 写入缓存，替换该路径的旧记录
+    pub fn put(&self, path: &str, size: u64, modified: i64, info: &MediaInfo) -> Result<()> {
+        let conn = self.pool.get().context("Failed to get connection from pool")?;
+        conn.execute(
+            "INSERT OR REPLACE INTO media_info
+                (path, size, modified, duration, width, height, video_codec, audio_codec, bit_rate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                path,
+                size as i64,
+                modified,
+                info.duration_secs,
+                info.width,
+                info.height,
+                info.video_codec,
+                info.audio_codec,
+                info.bit_rate.map(|bit_rate| bit_rate as i64)
+            ],
+        )
+        .context("Failed to save media info")?;
+        Ok(())
+    }
+}
+
+/// 媒体信息服务：优先读取缓存，未缓存时运行 ffprobe
+pub struct MediaInfoService {
+    cache: MediaInfoCache,
+}
+
+/// 共享的媒体信息服务实例
+pub type SharedMediaInfoService = Arc<MediaInfoService>;
+
+static SHARED_MEDIA_INFO: OnceLock<SharedMediaInfoService> = OnceLock::new();
+
+/// 设置共享的媒体信息服务，只能设置一次
+pub fn set_shared_media_info(service: SharedMediaInfoService) {
+    if SHARED_MEDIA_INFO.set(service).is_err() {
+        debug!("Media info service already set");
+    }
+}
+
+/// 获取共享的媒体信息服务，未设置时不显示媒体信息
+pub fn shared_media_info() -> Option<SharedMediaInfoService> {
+    SHARED_MEDIA_INFO.get().cloned()
+}
+
+impl MediaInfoService {
+    /// 创建媒体信息服务
+    pub fn new(cache: MediaInfoCache) -> Self {
+        Self { cache }
+    }
+
+    /// 是否读取该文件的媒体信息：只处理视频和音频
+    pub fn is_supported(file_type: &str, name: &str) -> bool {
+        matches!(
+            category_of(file_type, name),
+            Some(FileCategory::Video | FileCategory::Audio)
+        )
+    }
+
+    /// 读取本地文件的媒体信息
+    ///
+    /// # Returns
+    /// * `Result<Option<MediaInfo>>` - 本地文件不存在时返回 None
+    pub async fn media_info(&self, path: &str) -> Result<Option<MediaInfo>> {
+        let Ok(metadata) = tokio::fs::metadata(path).await else {
+            return Ok(None);
+        };
+        if !metadata.is_file() {
+            return Ok(None);
+        }
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs() as i64);
+
+        if let Some(info) = self.cache.get(path, size, modified)? {
+            return Ok(Some(info));
+        }
+        let info = probe(path).await?;
+        debug!("Probed media info for {}: {:?}", path, info);
+        self.cache.put(path, size, modified, &info)?;
+        Ok(Some(info))
+    }
+}
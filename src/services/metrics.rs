@@ -0,0 +1,89 @@
+//! 运行指标 - Prometheus 格式的计数器和直方图
+//!
+//! 指标注册在独立的 Registry 中，由后端服务的 `/metrics` 接口导出
+
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+
+lazy_static! {
+    /// 应用指标注册表
+    pub static ref REGISTRY: Registry = Registry::new();
+
+    /// 搜索耗时（秒）
+    pub static ref SEARCH_DURATION: Histogram = register(Histogram::with_opts(
+        HistogramOpts::new("netdisk_search_duration_seconds", "Search latency in seconds")
+            .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]),
+    ));
+
+    /// 每次搜索的结果数
+    pub static ref SEARCH_RESULTS: Histogram = register(Histogram::with_opts(
+        HistogramOpts::new("netdisk_search_results", "Number of results per search")
+            .buckets(vec![0.0, 1.0, 10.0, 100.0, 1_000.0, 10_000.0, 100_000.0]),
+    ));
+
+    /// Aria2 RPC 错误数，按 RPC 方法区分
+    pub static ref ARIA2_RPC_ERRORS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("netdisk_aria2_rpc_errors_total", "Failed Aria2 RPC calls"),
+        &["method"],
+    ));
+
+    /// 下载链接获取失败数，按阶段区分（upload / download）
+    pub static ref LINK_RESOLUTION_FAILURES: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("netdisk_link_resolution_failures_total", "Failed download link resolutions"),
+        &["stage"],
+    ));
+
+    /// 正在跟踪的下载任务数
+    pub static ref ACTIVE_DOWNLOADS: IntGauge = register(IntGauge::new(
+        "netdisk_active_downloads",
+        "Downloads currently tracked in Aria2",
+    ));
+}
+
+/// 注册指标，指标定义固定，注册失败属于编程错误
+fn register<T, E>(metric: Result<T, E>) -> T
+where
+    T: prometheus::core::Collector + Clone + 'static,
+    E: std::fmt::Debug,
+{
+    let metric = metric.expect("invalid metric definition");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+/// 记录一次搜索
+///
+/// # Arguments
+/// * `elapsed` - 搜索耗时
+/// * `results` - 结果数
+pub fn observe_search(elapsed: Duration, results: usize) {
+    SEARCH_DURATION.observe(elapsed.as_secs_f64());
+    SEARCH_RESULTS.observe(results as f64);
+}
+
+/// 确保所有指标已注册（lazy_static 在首次访问时才注册）
+fn ensure_registered() {
+    lazy_static::initialize(&SEARCH_DURATION);
+    lazy_static::initialize(&SEARCH_RESULTS);
+    lazy_static::initialize(&ARIA2_RPC_ERRORS);
+    lazy_static::initialize(&LINK_RESOLUTION_FAILURES);
+    lazy_static::initialize(&ACTIVE_DOWNLOADS);
+}
+
+/// 以 Prometheus 文本格式导出所有指标
+pub fn gather() -> String {
+    ensure_registered();
+    let mut buffer = Vec::new();
+    if TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .is_err()
+    {
+        return String::new();
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -0,0 +1,107 @@
+//! 桌面通知 - 下载完成、索引完成和同步失败等耗时操作结束时提示用户
+//!
+//! 按配置中的总开关和事件类型决定是否显示；通知在独立线程中发送，部分 Linux 桌面上
+//! 发送通知会等待 D-Bus 响应，不能阻塞界面线程。没有 `notifications` feature 时只记录日志
+
+use crate::models::config::NotificationConfig;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::debug;
+
+/// 通知的应用名称
+#[cfg(feature = "notifications")]
+const APP_NAME: &str = "netdisk_db";
+
+/// 通知的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    DownloadComplete,
+    IndexingComplete,
+    SyncError,
+}
+
+/// 桌面通知
+pub struct Notifier {
+    config: Mutex<NotificationConfig>,
+}
+
+/// 共享的通知实例
+pub type SharedNotifier = Arc<Notifier>;
+
+static SHARED_NOTIFIER: OnceLock<SharedNotifier> = OnceLock::new();
+
+/// 设置共享的通知实例，只能设置一次
+pub fn set_shared_notifier(notifier: SharedNotifier) {
+    if SHARED_NOTIFIER.set(notifier).is_err() {
+        debug!("Notifier already set");
+    }
+}
+
+/// 获取共享的通知实例，未设置时不显示通知
+pub fn shared_notifier() -> Option<SharedNotifier> {
+    SHARED_NOTIFIER.get().cloned()
+}
+
+/// 通过共享的通知实例显示通知，未设置或该类型已关闭时忽略
+///
+/// # Arguments
+/// * `kind` - 事件类型
+/// * `summary` - 标题
+/// * `body` - 正文
+pub fn notify(kind: NotificationKind, summary: &str, body: &str) {
+    if let Some(notifier) = shared_notifier() {
+        notifier.notify(kind, summary, body);
+    }
+}
+
+impl Notifier {
+    /// 按配置创建
+    pub fn new(config: NotificationConfig) -> Self {
+        Self {
+            config: Mutex::new(config),
+        }
+    }
+
+    /// 更新配置，之后的通知按新配置决定是否显示
+    pub fn configure(&self, config: NotificationConfig) {
+        *self.config.lock().unwrap() = config;
+    }
+
+    /// 该类型的通知是否显示
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        let config = self.config.lock().unwrap();
+        config.enabled
+            && match kind {
+                NotificationKind::DownloadComplete => config.download_complete,
+                NotificationKind::IndexingComplete => config.indexing_complete,
+                NotificationKind::SyncError => config.sync_errors,
+            }
+    }
+
+    /// 显示通知，该类型已关闭时忽略
+    pub fn notify(&self, kind: NotificationKind, summary: &str, body: &str) {
+        if !self.is_enabled(kind) {
+            return;
+        }
+        debug!("Notification {:?}: {} - {}", kind, summary, body);
+        show(summary.to_string(), body.to_string());
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn show(summary: String, body: String) {
+    std::thread::spawn(move || {
+        let result = notify_rust::Notification::new()
+            .appname(APP_NAME)
+            .summary(&summary)
+            .body(&body)
+            .show();
+        if let Err(e) = result {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+#[cfg(not(feature = "notifications"))]
+fn show(summary: String, _body: String) {
+    debug!("Built without notifications, not showing: {}", summary);
+}
@@ -0,0 +1,338 @@
+//! 离线操作队列 - 后端或网盘无法访问时暂存获取链接和下载请求
+//!
+//! 请求保存在独立的 SQLite 数据库中，程序重启后仍然保留。
+//! 定时重试队列中的操作，网络恢复后自动完成
+
+use crate::services::api_client::LinkError;
+use anyhow::{Context, Result};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, info, warn};
+
+/// 非网络原因失败的操作最多重试的次数，超过后从队列中移除
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// 暂存的操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingKind {
+    /// 获取下载链接（包括秒传上传），成功后链接进入缓存
+    ResolveLink,
+    /// 发送下载
+    Download,
+}
+
+impl PendingKind {
+    /// 数据库中保存的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ResolveLink => "link",
+            Self::Download => "download",
+        }
+    }
+
+    /// 从数据库中保存的名称解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "link" => Some(Self::ResolveLink),
+            "download" => Some(Self::Download),
+            _ => None,
+        }
+    }
+
+    /// 界面显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ResolveLink => "获取链接",
+            Self::Download => "下载",
+        }
+    }
+}
+
+/// 一条暂存的操作
+#[derive(Debug, Clone)]
+pub struct PendingOperation {
+    pub id: i64,
+    pub kind: PendingKind,
+    pub path: String,
+    pub etag: String,
+    pub size: u64,
+    pub target_dir: Option<String>, // 下载目录，仅用于下载
+    pub attempts: u32,
+    pub last_error: String,
+    pub created_at: i64,
+}
+
+/// 一轮重试的结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetrySummary {
+    pub completed: usize,
+    pub dropped: usize,   // 超过重试次数被移除的操作数
+    pub remaining: usize, // 仍在队列中的操作数
+    pub offline: bool,    // 重试时仍然无法访问网络
+}
+
+/// 判断错误是否为网络无法访问导致，可以稍后重试
+pub fn is_offline_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<LinkError>())
+        .any(LinkError::is_offline)
+}
+
+/// 离线操作队列
+pub struct PendingOperations {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+/// 共享的离线操作队列实例
+pub type SharedPendingOperations = Arc<PendingOperations>;
+
+impl PendingOperations {
+    /// 打开离线操作数据库，不存在时创建
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    pub fn open(path: &str) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path);
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .context("Failed to create pending operations connection pool")?;
+
+        pool.get()
+            .context("Failed to get connection from pool")?
+            .execute(
+                "CREATE TABLE IF NOT EXISTS pending_ops (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    kind TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    etag TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    target_dir TEXT,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT NOT NULL DEFAULT '',
+                    created_at INTEGER NOT NULL,
+                    UNIQUE(kind, etag, size)
+                )",
+                [],
+            )
+            .context("Failed to create pending_ops table")?;
+
+        Ok(Self { pool })
+    }
+
+    /// 加入队列，同一文件的同类操作只保留一条
+    ///
+    /// # Arguments
+    /// * `kind` - 操作类型
+    /// * `path` - 文件路径
+    /// * `etag` - 文件ETag
+    /// * `size` - 文件大小
+    /// * `target_dir` - 下载目录
+    ///
+    /// # Returns
+    /// * `Result<bool>` - 是否新加入了队列
+    pub fn enqueue(
+        &self,
+        kind: PendingKind,
+        path: &str,
+        etag: &str,
+        size: u64,
+        target_dir: Option<&str>,
+    ) -> Result<bool> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO pending_ops (kind, path, etag, size, target_dir, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    kind.as_str(),
+                    path,
+                    etag,
+                    size as i64,
+                    target_dir,
+                    chrono::Local::now().timestamp()
+                ],
+            )
+            .context("Failed to queue pending operation")?;
+
+        if inserted > 0 {
+            info!("Queued {:?} for {} until the netdisk is reachable", kind, path);
+        }
+        Ok(inserted > 0)
+    }
+
+    /// 队列中的所有操作，按加入顺序
+    pub fn list(&self) -> Result<Vec<PendingOperation>> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, path, etag, size, target_dir, attempts, last_error, created_at
+                 FROM pending_ops
+                 ORDER BY id",
+            )
+            .context("Failed to prepare pending operations query")?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                let size: i64 = row.get(4)?;
+                let attempts: i64 = row.get(6)?;
+                Ok((
+                    row.get::<_, String>(1)?,
+                    PendingOperation {
+                        id: row.get(0)?,
+                        kind: PendingKind::ResolveLink,
+                        path: row.get(2)?,
+                        etag: row.get(3)?,
+                        size: size.max(0) as u64,
+                        target_dir: row.get(5)?,
+                        attempts: attempts.max(0) as u32,
+                        last_error: row.get(7)?,
+                        created_at: row.get(8)?,
+                    },
+                ))
+            })
+            .context("Failed to query pending operations")?;
+
+        let mut operations = Vec::new();
+        for row in rows {
+            let (kind, mut operation) = row.context("Failed to map pending operation")?;
+            if let Some(kind) = PendingKind::from_name(&kind) {
+                operation.kind = kind;
+                operations.push(operation);
+            }
+        }
+        Ok(operations)
+    }
+
+    /// 队列中的操作数
+    pub fn count(&self) -> Result<usize> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pending_ops", [], |row| row.get(0))
+            .context("Failed to count pending operations")?;
+        Ok(count as usize)
+    }
+
+    /// 从队列中移除操作
+    pub fn remove(&self, id: i64) -> Result<()> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        conn.execute("DELETE FROM pending_ops WHERE id = ?1", params![id])
+            .context("Failed to remove pending operation")?;
+        Ok(())
+    }
+
+    /// 记录一次失败的重试
+    ///
+    /// # Returns
+    /// * `Result<u32>` - 累计的重试次数
+    pub fn record_failure(&self, id: i64, error: &str) -> Result<u32> {
+        let conn = self
+            .pool
+            .get()
+            .context("Failed to get connection from pool")?;
+
+        conn.execute(
+            "UPDATE pending_ops SET attempts = attempts + 1, last_error = ?2 WHERE id = ?1",
+            params![id, error],
+        )
+        .context("Failed to update pending operation")?;
+
+        let attempts: i64 = conn
+            .query_row(
+                "SELECT attempts FROM pending_ops WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+        Ok(attempts.max(0) as u32)
+    }
+
+    /// 按加入顺序重试队列中的操作
+    ///
+    /// 成功的操作从队列中移除；遇到网络错误时停止本轮重试，等待下一轮；
+    /// 其他错误累计重试次数，超过 `MAX_ATTEMPTS` 后移除
+    ///
+    /// # Arguments
+    /// * `execute` - 执行单个操作
+    pub async fn retry_all<F, Fut>(&self, mut execute: F) -> Result<RetrySummary>
+    where
+        F: FnMut(PendingOperation) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let operations = self.list()?;
+        let mut summary = RetrySummary {
+            remaining: operations.len(),
+            ..Default::default()
+        };
+
+        for operation in operations {
+            let (id, path) = (operation.id, operation.path.clone());
+            match execute(operation).await {
+                Ok(()) => {
+                    debug!("Pending operation {} for {} completed", id, path);
+                    self.remove(id)?;
+                    summary.completed += 1;
+                    summary.remaining -= 1;
+                }
+                Err(e) if is_offline_error(&e) => {
+                    debug!("Netdisk still unreachable: {:#}", e);
+                    self.record_failure(id, &format!("{:#}", e))?;
+                    summary.offline = true;
+                    break;
+                }
+                Err(e) => {
+                    let attempts = self.record_failure(id, &format!("{:#}", e))?;
+                    if attempts >= MAX_ATTEMPTS {
+                        warn!("Dropping pending operation for {} after {} attempts: {:#}", path, attempts, e);
+                        self.remove(id)?;
+                        summary.dropped += 1;
+                        summary.remaining -= 1;
+                    }
+                }
+            }
+        }
+
+        if summary.completed > 0 || summary.dropped > 0 {
+            info!(
+                "Pending operations: {} completed, {} dropped, {} remaining",
+                summary.completed, summary.dropped, summary.remaining
+            );
+        }
+        Ok(summary)
+    }
+}
+
+/// 复制链接和下载使用的离线操作队列，启动时设置
+static SHARED_PENDING_OPERATIONS: OnceLock<SharedPendingOperations> = OnceLock::new();
+
+/// 设置共享的离线操作队列，只能设置一次
+pub fn set_shared_pending_operations(queue: SharedPendingOperations) {
+    if SHARED_PENDING_OPERATIONS.set(queue).is_err() {
+        debug!("Pending operations queue already set");
+    }
+}
+
+/// 获取共享的离线操作队列，未设置时不暂存操作
+pub fn shared_pending_operations() -> Option<SharedPendingOperations> {
+    SHARED_PENDING_OPERATIONS.get().cloned()
+}
@@ -0,0 +1,98 @@
+//! 外部播放器 - 用 mpv、VLC 等播放器直接播放下载链接
+//!
+//! 播放器命令在配置的 `players` 中设置，启动时注册为右键菜单项。
+//! 命令模板拆分为程序和参数后直接启动，不经过 shell，链接和文件名中的特殊字符不会被解析
+
+use crate::models::config::PlayerConfig;
+use anyhow::{bail, Context, Result};
+use std::process::{Command, Stdio};
+use tracing::{debug, info};
+
+/// 命令模板中的下载链接占位符
+pub const URL_PLACEHOLDER: &str = "{url}";
+
+/// 命令模板中的文件名占位符
+pub const NAME_PLACEHOLDER: &str = "{name}";
+
+/// 按空白拆分命令模板，单引号和双引号中的空白不拆分，引号本身去掉
+pub fn split_command(template: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_part = false;
+
+    for ch in template.chars() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                in_part = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_part {
+                    parts.push(std::mem::take(&mut current));
+                    in_part = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_part = true;
+            }
+        }
+    }
+    if in_part {
+        parts.push(current);
+    }
+    parts
+}
+
+/// 由命令模板生成程序和参数
+///
+/// # Arguments
+/// * `template` - 命令模板
+/// * `url` - 下载链接
+/// * `name` - 文件名
+///
+/// # Returns
+/// * `Result<(String, Vec<String>)>` - 程序和参数，模板为空时返回错误
+pub fn build_command(template: &str, url: &str, name: &str) -> Result<(String, Vec<String>)> {
+    let mut parts = split_command(template).into_iter();
+    let Some(program) = parts.next() else {
+        bail!("播放器命令为空");
+    };
+    let mut args: Vec<String> = parts.collect();
+    let has_url = args.iter().any(|arg| arg.contains(URL_PLACEHOLDER));
+    for arg in &mut args {
+        *arg = arg.replace(URL_PLACEHOLDER, url).replace(NAME_PLACEHOLDER, name);
+    }
+    if !has_url {
+        args.push(url.to_string());
+    }
+    Ok((program, args))
+}
+
+/// 启动外部播放器，不等待播放器退出
+///
+/// # Arguments
+/// * `player` - 播放器配置
+/// * `url` - 下载链接
+/// * `name` - 文件名
+pub fn launch(player: &PlayerConfig, url: &str, name: &str) -> Result<()> {
+    let (program, args) = build_command(&player.command, url, name)?;
+    info!("Launching {} for {}", player.name, name);
+    debug!("Player command: {} {:?}", program, args);
+
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("无法启动 {}（{}）", player.name, program))?;
+    // 在后台回收子进程，避免播放器退出后留下僵尸进程
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! 二维码 - 将下载链接编码为二维码图像
+//!
+//! 只生成 RGBA 像素数据，由界面转换为图像显示，方便用手机扫码获取链接
+
+use anyhow::{Context, Result};
+use qrcode::{Color, QrCode};
+
+/// 二维码四周的空白宽度（模块数），扫码要求至少 4 个模块
+pub const QUIET_ZONE: usize = 4;
+
+/// 二维码图像的 RGBA 像素数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrImage {
+    pub size: u32, // 图像宽高（像素），二维码总是正方形
+    pub pixels: Vec<u8>, // 逐行的 RGBA 数据
+}
+
+/// 将文本编码为二维码的模块矩阵，`true` 表示深色模块
+///
+/// # Returns
+/// * `Result<(usize, Vec<bool>)>` - 每行模块数和逐行的模块，文本过长时返回错误
+pub fn encode(text: &str) -> Result<(usize, Vec<bool>)> {
+    let code = QrCode::new(text.as_bytes()).context("链接过长，无法生成二维码")?;
+    let modules = code.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+    Ok((code.width(), modules))
+}
+
+/// 生成二维码图像，深色模块为黑色，其余为白色
+///
+/// # Arguments
+/// * `text` - 编码的文本，一般是下载链接
+/// * `scale` - 每个模块的像素数
+pub fn render(text: &str, scale: u32) -> Result<QrImage> {
+    let (width, modules) = encode(text)?;
+    let scale = scale.max(1) as usize;
+    let size = (width + QUIET_ZONE * 2) * scale;
+
+    let mut pixels = vec![0xff; size * size * 4];
+    for (index, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        let (row, column) = (index / width + QUIET_ZONE, index % width + QUIET_ZONE);
+        for y in row * scale..(row + 1) * scale {
+            let start = (y * size + column * scale) * 4;
+            for pixel in pixels[start..start + scale * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[0, 0, 0, 0xff]);
+            }
+        }
+    }
+    Ok(QrImage {
+        size: size as u32,
+        pixels,
+    })
+}
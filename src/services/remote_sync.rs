@@ -0,0 +1,271 @@
+//! 网盘同步服务 - 从网盘文件列表建立本地索引
+//!
+//! 逐个文件夹分页拉取网盘文件列表，写入本地 SQLite 数据库。
+//! 按路径比较 ETag 做增量同步：只写入新增和变化的文件，删除网盘上已不存在的记录
+
+use crate::models::database::{Database, FileRecord};
+use crate::services::api_client::{ApiClient, FileListPage, RemoteFile};
+use crate::utils::time::parse_timestamp;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+/// 每页拉取的文件数
+const PAGE_SIZE: u32 = 100;
+
+/// 每批写入数据库的记录数
+const BATCH_SIZE: usize = 500;
+
+/// 文件列表中表示最后一页的 `last_file_id`
+const LAST_PAGE: i64 = -1;
+
+/// 网盘文件列表来源
+pub trait RemoteLister: Send + Sync {
+    /// 获取文件夹中的一页文件
+    ///
+    /// # Arguments
+    /// * `parent_id` - 文件夹 ID
+    /// * `last_file_id` - 上一页返回的 `last_file_id`，第一页为 0
+    fn list_page(
+        &self,
+        parent_id: i64,
+        last_file_id: i64,
+    ) -> impl Future<Output = Result<FileListPage>> + Send;
+}
+
+impl RemoteLister for ApiClient {
+    async fn list_page(&self, parent_id: i64, last_file_id: i64) -> Result<FileListPage> {
+        self.list_files(parent_id, last_file_id, PAGE_SIZE)
+            .await
+            .map_err(|e| anyhow!("Failed to list remote folder {}: {}", parent_id, e))
+    }
+}
+
+impl<L: RemoteLister> RemoteLister for Arc<L> {
+    fn list_page(
+        &self,
+        parent_id: i64,
+        last_file_id: i64,
+    ) -> impl Future<Output = Result<FileListPage>> + Send {
+        self.as_ref().list_page(parent_id, last_file_id)
+    }
+}
+
+/// 同步进度
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgress {
+    /// 已遍历的文件夹数
+    pub folders: usize,
+    /// 已遍历的文件数
+    pub scanned: usize,
+    /// 当前正在拉取的文件夹
+    pub current_dir: String,
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub cancelled: bool,
+}
+
+/// 网盘同步器
+pub struct RemoteSync<L: RemoteLister> {
+    lister: L,
+    database: Arc<Mutex<dyn Database>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<L: RemoteLister> RemoteSync<L> {
+    /// 创建同步器
+    ///
+    /// # Arguments
+    /// * `lister` - 网盘文件列表来源
+    /// * `database` - 写入的目标数据库
+    pub fn new(lister: L, database: Arc<Mutex<dyn Database>>) -> Self {
+        Self {
+            lister,
+            database,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 获取取消标志，设置为 true 后同步会在当前页结束时停止
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// 同步网盘文件夹
+    ///
+    /// # Arguments
+    /// * `root_id` - 网盘文件夹 ID，根目录为 0
+    /// * `root_path` - 该文件夹在本地索引中的路径，如 `/`
+    /// * `on_progress` - 进度回调，每拉取一页调用一次
+    ///
+    /// # Returns
+    /// * `Result<SyncSummary>` - 新增、更新、未变化和删除的记录数。取消时不删除记录
+    pub async fn sync<F>(&self, root_id: i64, root_path: &str, mut on_progress: F) -> Result<SyncSummary>
+    where
+        F: FnMut(&SyncProgress),
+    {
+        let root_path = normalize_root(root_path);
+        info!("Syncing remote folder {} into {}", root_id, root_path);
+
+        let mut known = self
+            .database
+            .lock()
+            .unwrap()
+            .etags_under(&root_path)
+            .context("Failed to read existing records")?;
+        let mut summary = SyncSummary::default();
+        let mut progress = SyncProgress::default();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut pending_dirs = vec![(root_id, root_path.clone())];
+
+        'folders: while let Some((folder_id, folder_path)) = pending_dirs.pop() {
+            progress.folders += 1;
+            progress.current_dir = folder_path.clone();
+            let mut last_file_id = 0;
+
+            loop {
+                if self.cancelled.load(Ordering::Relaxed) {
+                    break 'folders;
+                }
+
+                let page = self.lister.list_page(folder_id, last_file_id).await?;
+                for file in page.file_list.iter().filter(|file| file.trashed == 0) {
+                    let path = format!("{}{}", folder_path, file.filename);
+                    if file.is_folder() {
+                        pending_dirs.push((file.file_id, format!("{}/", path)));
+                        continue;
+                    }
+
+                    progress.scanned += 1;
+                    match known.remove(&path) {
+                        Some(etag) if etag == file.etag => summary.unchanged += 1,
+                        Some(_) => {
+                            summary.updated += 1;
+                            batch.push(remote_file_record(file, path));
+                        }
+                        None => {
+                            summary.added += 1;
+                            batch.push(remote_file_record(file, path));
+                        }
+                    }
+                }
+
+                if batch.len() >= BATCH_SIZE {
+                    self.flush(&mut batch)?;
+                }
+                on_progress(&progress);
+
+                if page.last_file_id == LAST_PAGE || page.file_list.is_empty() {
+                    break;
+                }
+                last_file_id = page.last_file_id;
+            }
+        }
+
+        self.flush(&mut batch)?;
+        summary.cancelled = self.cancelled.load(Ordering::Relaxed);
+
+        // 只有完整遍历后才能确定哪些文件已被删除
+        if !summary.cancelled && !known.is_empty() {
+            let removed: Vec<String> = known.into_keys().collect();
+            summary.removed = self
+                .database
+                .lock()
+                .unwrap()
+                .delete_paths(&removed)
+                .context("Failed to remove deleted files")?;
+        }
+
+        info!(
+            "Remote sync finished: {} added, {} updated, {} unchanged, {} removed{}",
+            summary.added,
+            summary.updated,
+            summary.unchanged,
+            summary.removed,
+            if summary.cancelled { " (cancelled)" } else { "" }
+        );
+        Ok(summary)
+    }
+
+    /// 将一批记录写入数据库
+    fn flush(&self, batch: &mut Vec<FileRecord>) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let count = self
+            .database
+            .lock()
+            .unwrap()
+            .upsert_files(batch)
+            .context("Failed to write synced files")?;
+        debug!("Synced batch of {} files", count);
+        batch.clear();
+        Ok(())
+    }
+}
+
+/// 列出网盘文件夹中的子文件夹（不含回收站中的），按名称排序，用于选择网盘文件夹
+///
+/// # Arguments
+/// * `lister` - 网盘文件列表来源
+/// * `parent_id` - 文件夹 ID，根目录为 0
+pub async fn list_folders<L: RemoteLister>(lister: &L, parent_id: i64) -> Result<Vec<RemoteFile>> {
+    let mut folders = Vec::new();
+    let mut last_file_id = 0;
+    loop {
+        let page = lister.list_page(parent_id, last_file_id).await?;
+        folders.extend(
+            page.file_list
+                .iter()
+                .filter(|file| file.is_folder() && file.trashed == 0)
+                .cloned(),
+        );
+        if page.last_file_id == LAST_PAGE || page.file_list.is_empty() {
+            break;
+        }
+        last_file_id = page.last_file_id;
+    }
+
+    folders.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(folders)
+}
+
+/// 本地索引中的文件夹路径统一以 `/` 开头和结尾
+fn normalize_root(root_path: &str) -> String {
+    let trimmed = root_path.trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", trimmed)
+    }
+}
+
+/// 根据网盘文件生成文件记录，`file_type` 使用小写扩展名
+fn remote_file_record(file: &RemoteFile, path: String) -> FileRecord {
+    let modified_time = parse_timestamp(&file.update_at).unwrap_or(0);
+    let file_type = std::path::Path::new(&file.filename)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    FileRecord {
+        id: 0,
+        path,
+        size: file.size,
+        etag: file.etag.clone(),
+        modified_time,
+        file_type,
+        name: file.filename.clone(),
+    }
+}
@@ -0,0 +1,129 @@
+//! 批量重命名 - 根据查找替换或编号模板生成索引中的新文件名
+//!
+//! 只修改数据库中的 `name` 和 `path`，不会重命名本地或网盘上的文件。
+//! 编号模板支持 `{n}`、`{n:3}`（补零到 3 位）、`{name}`（不含扩展名）和 `{ext}`
+
+use crate::models::database::{FileRecord, RenameEntry};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+
+/// 重命名方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenamePattern {
+    /// 将名称中的 `find` 全部替换为 `replace`
+    Replace { find: String, replace: String },
+    /// 按模板生成名称，编号从 `start` 开始
+    Numbered { template: String, start: usize },
+}
+
+impl RenamePattern {
+    /// 根据界面输入创建
+    ///
+    /// # Arguments
+    /// * `mode` - 0 查找替换，1 编号
+    /// * `first` - 查找内容或编号模板
+    /// * `second` - 替换内容或起始编号
+    pub fn from_ui(mode: i32, first: &str, second: &str) -> Self {
+        match mode {
+            1 => Self::Numbered {
+                template: first.to_string(),
+                start: second.trim().parse().unwrap_or(1),
+            },
+            _ => Self::Replace {
+                find: first.to_string(),
+                replace: second.to_string(),
+            },
+        }
+    }
+
+    /// 生成第 `index` 个文件的新名称
+    pub fn apply(&self, name: &str, index: usize) -> String {
+        match self {
+            Self::Replace { find, replace } => name.replace(find.as_str(), replace),
+            Self::Numbered { template, start } => expand_template(template, name, start + index),
+        }
+    }
+}
+
+/// 展开编号模板
+fn expand_template(template: &str, name: &str, number: usize) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(pos) if pos > 0 => (&name[..pos], &name[pos + 1..]),
+        _ => (name, ""),
+    };
+
+    let mut output = String::with_capacity(template.len() + name.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let token = &rest[open + 1..open + close];
+        match token {
+            "n" => output.push_str(&number.to_string()),
+            "name" => output.push_str(stem),
+            "ext" => output.push_str(ext),
+            _ => match token.strip_prefix("n:").and_then(|w| w.parse::<usize>().ok()) {
+                Some(width) => output.push_str(&format!("{:0width$}", number, width = width)),
+                None => output.push_str(&rest[open..=open + close]),
+            },
+        }
+        rest = &rest[open + close + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 将路径中的文件名替换为新名称，保留原有的分隔符
+fn replace_file_name(path: &str, new_name: &str) -> String {
+    match path.rfind(['/', '\\']) {
+        Some(pos) => format!("{}{}", &path[..=pos], new_name),
+        None => new_name.to_string(),
+    }
+}
+
+/// 预览重命名结果，名称不变的记录不包含在结果中
+///
+/// # Arguments
+/// * `records` - 选中的文件记录，编号按此顺序分配
+/// * `pattern` - 重命名方式
+///
+/// # Returns
+/// * `Result<Vec<RenameEntry>>` - 新名称为空、包含路径分隔符或互相重复时返回错误
+pub fn preview(records: &[FileRecord], pattern: &RenamePattern) -> Result<Vec<RenameEntry>> {
+    if let RenamePattern::Replace { find, .. } = pattern {
+        if find.is_empty() {
+            bail!("查找内容不能为空");
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut new_paths = HashSet::new();
+    for (index, record) in records.iter().enumerate() {
+        let new_name = pattern.apply(&record.name, index);
+        if new_name.trim().is_empty() {
+            bail!("{} 的新名称为空", record.name);
+        }
+        if new_name.contains(['/', '\\']) {
+            bail!("新名称不能包含路径分隔符: {}", new_name);
+        }
+
+        let new_path = replace_file_name(&record.path, &new_name);
+        if !new_paths.insert(new_path.clone()) {
+            bail!("重命名后存在重复的文件: {}", new_path);
+        }
+        if new_name == record.name {
+            continue;
+        }
+        entries.push(RenameEntry {
+            id: record.id,
+            old_name: record.name.clone(),
+            old_path: record.path.clone(),
+            new_name,
+            new_path,
+        });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,251 @@
+//! 搜索引擎 - 不依赖界面的库接口
+//!
+//! 打开配置中的数据库，提供搜索、获取下载链接和添加下载任务。
+//! 图形界面只是这一层之上的界面，作为库使用时可以关闭 `gui` feature，不需要 Slint。
+//! 添加下载任务需要 `aria2` feature
+//!
+//! ```no_run
+//! use netdisk_db::services::search_engine::SearchEngine;
+//! use netdisk_db::models::database::SearchOptions;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let engine = SearchEngine::builder().config_file("config.json").build()?;
+//! let records = engine.search("movie", SearchOptions::default(), 20)?;
+//! if let Some(record) = records.first() {
+//!     println!("{}", engine.resolve_link(record).await?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::models::config::{AppConfig, DatabaseConfig};
+use crate::models::database::{FileRecord, SearchOptions};
+use crate::services::api_client::LinkError;
+#[cfg(feature = "aria2")]
+use crate::services::aria2::create_shared_aria2_service;
+use crate::services::database_manager::{DatabaseHandle, DatabaseManager};
+#[cfg(feature = "aria2")]
+use crate::services::download_manager::{DownloadManager, DownloadSource, SharedDownloadManager};
+use crate::services::link_resolver::{get_file_url, get_file_urls, shared_link_resolver, LinkResult};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "aria2")]
+use tracing::{debug, warn};
+
+/// 等待 Aria2 就绪的最长时间（秒）
+#[cfg(feature = "aria2")]
+const ARIA2_READY_TIMEOUT_SECS: u64 = 10;
+
+/// `SearchEngine` 的构建器
+#[derive(Default)]
+pub struct SearchEngineBuilder {
+    config: Option<AppConfig>,
+    config_file: Option<PathBuf>,
+    database: Option<String>,
+}
+
+impl SearchEngineBuilder {
+    /// 使用已加载的配置
+    pub fn config(mut self, config: AppConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// 从配置文件加载配置，同时设置了 `config` 时以 `config` 为准
+    pub fn config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// 启动后切换到指定名称的数据库，默认使用配置中的当前数据库
+    pub fn database(mut self, name: impl Into<String>) -> Self {
+        self.database = Some(name.into());
+        self
+    }
+
+    /// 打开数据库，创建搜索引擎
+    pub fn build(self) -> Result<SearchEngine> {
+        let config = match (self.config, self.config_file) {
+            (Some(config), _) => config,
+            (None, Some(path)) => AppConfig::load_from_file(&path.to_string_lossy())
+                .with_context(|| format!("Failed to load config file {:?}", path))?,
+            (None, None) => AppConfig::default(),
+        };
+        let config = Arc::new(Mutex::new(config));
+        let database = DatabaseHandle::spawn(DatabaseManager::new(config.clone())?);
+
+        if let Some(name) = self.database {
+            let index = config
+                .lock()
+                .unwrap()
+                .database_index_by_name(&name)
+                .with_context(|| format!("数据库不存在: {}", name))?;
+            database.switch_database(index)?;
+        }
+
+        Ok(SearchEngine {
+            config,
+            database,
+            #[cfg(feature = "aria2")]
+            downloads: Mutex::new(None),
+        })
+    }
+}
+
+/// 不依赖界面的搜索引擎
+///
+/// 可以放在 `Arc` 中由多个线程共用，数据库访问由数据库管理线程串行执行
+pub struct SearchEngine {
+    config: Arc<Mutex<AppConfig>>,
+    database: DatabaseHandle,
+    #[cfg(feature = "aria2")]
+    downloads: Mutex<Option<SharedDownloadManager>>,
+}
+
+impl SearchEngine {
+    /// 创建构建器
+    pub fn builder() -> SearchEngineBuilder {
+        SearchEngineBuilder::default()
+    }
+
+    /// 使用已启动的数据库管理线程创建，与图形界面共用当前数据库和配置
+    ///
+    /// # Arguments
+    /// * `config` - 共享的应用配置
+    /// * `database` - 数据库管理线程的句柄
+    pub fn with_database(config: Arc<Mutex<AppConfig>>, database: DatabaseHandle) -> Self {
+        Self {
+            config,
+            database,
+            #[cfg(feature = "aria2")]
+            downloads: Mutex::new(None),
+        }
+    }
+
+    /// 数据库管理线程的句柄，用于这里没有封装的操作（维护、备份、比较等）
+    pub fn database_handle(&self) -> &DatabaseHandle {
+        &self.database
+    }
+
+    /// 当前配置的副本
+    pub fn config(&self) -> AppConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// 可用的数据库名称，顺序与 `open_database` 的索引一致
+    pub fn databases(&self) -> Result<Vec<String>> {
+        let snapshot = self.database.snapshot()?;
+        Ok(snapshot.databases.into_iter().map(|(name, _, _)| name).collect())
+    }
+
+    /// 当前数据库的配置
+    pub fn current_database(&self) -> DatabaseConfig {
+        self.database.current_config()
+    }
+
+    /// 切换到指定数据库
+    ///
+    /// # Arguments
+    /// * `index` - 数据库在 `databases` 中的索引
+    pub fn open_database(&self, index: usize) -> Result<DatabaseConfig> {
+        let config = self.database.switch_database(index)?;
+        shared_link_resolver().set_parent_folder(config.remote_folder_id);
+        Ok(config)
+    }
+
+    /// 在当前数据库中搜索
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项
+    /// * `limit` - 最多返回的记录数
+    pub fn search(&self, query: &str, options: SearchOptions, limit: usize) -> Result<Vec<FileRecord>> {
+        self.database.search(query, options, limit)
+    }
+
+    /// 与 `search` 相同，不等待结果，`on_reply` 在数据库管理线程中调用
+    pub fn search_then(
+        &self,
+        query: &str,
+        options: SearchOptions,
+        limit: usize,
+        on_reply: impl FnOnce(Result<Vec<FileRecord>>) + Send + 'static,
+    ) {
+        self.database.search_then(query, options, limit, on_reply);
+    }
+
+    /// 随机选取一条匹配的记录
+    ///
+    /// # Arguments
+    /// * `query` - 搜索关键词，为空时在全部文件中选取
+    /// * `options` - 搜索范围和类型筛选
+    pub fn random_file(&self, query: &str, options: SearchOptions) -> Result<Option<FileRecord>> {
+        self.database.random_file(query, options)
+    }
+
+    /// 获取文件的下载链接
+    ///
+    /// 需要先启动后端服务并设置共享的 API 客户端，同一文件短时间内使用缓存的链接
+    pub async fn resolve_link(&self, record: &FileRecord) -> Result<String, LinkError> {
+        get_file_url(record.path.as_str(), record.etag.as_str(), record.size).await
+    }
+
+    /// 并发获取多个文件的下载链接，结果与输入顺序一致
+    pub async fn resolve_links(&self, records: &[FileRecord]) -> Vec<LinkResult> {
+        let items = records
+            .iter()
+            .map(|record| (record.path.clone(), record.etag.clone(), record.size))
+            .collect();
+        get_file_urls(items).await
+    }
+
+    /// 启动配置中的 Aria2 服务，之后才能使用 `enqueue_download`
+    ///
+    /// # Returns
+    /// * `Result<bool>` - Aria2 是否在超时前就绪
+    #[cfg(feature = "aria2")]
+    pub async fn start_downloads(&self) -> Result<bool> {
+        let aria2_config = self.config.lock().unwrap().aria2.clone();
+        let aria2_service = create_shared_aria2_service(aria2_config);
+        aria2_service
+            .lock()
+            .unwrap()
+            .start()
+            .context("Failed to start Aria2 service")?;
+        let ready = {
+            let service = aria2_service.lock().unwrap();
+            service.wait_until_ready(ARIA2_READY_TIMEOUT_SECS).await
+        };
+        if !ready {
+            warn!("Aria2 service is not ready");
+        }
+
+        *self.downloads.lock().unwrap() = Some(Arc::new(DownloadManager::new(aria2_service)));
+        Ok(ready)
+    }
+
+    /// 将文件添加到 Aria2 下载
+    ///
+    /// # Arguments
+    /// * `record` - 要下载的文件
+    /// * `target_dir` - 下载目录，None 时使用当前数据库配置的下载目录
+    ///
+    /// # Returns
+    /// * `Result<String>` - Aria2 任务 GID
+    #[cfg(feature = "aria2")]
+    pub async fn enqueue_download(&self, record: &FileRecord, target_dir: Option<String>) -> Result<String> {
+        let downloads = self
+            .downloads
+            .lock()
+            .unwrap()
+            .clone()
+            .context("下载服务未启动，请先调用 start_downloads")?;
+        let database = self.current_database();
+        let target_dir = target_dir.or(database.download_dir);
+        debug!("Enqueue download {} to {:?}", record.path, target_dir);
+        downloads
+            .enqueue(DownloadSource::from_record(record, target_dir).with_database(&database.name))
+            .await
+    }
+}
@@ -0,0 +1,251 @@
+//! 启动自检 - 检查配置、数据库、Aria2、后端端口和访问令牌
+//!
+//! 启动时逐项检查，结果集中显示在自检对话框中，每个问题附带修复建议，
+//! 不再分散在日志的各处警告里。检查只读取状态，不修改配置和数据库
+
+use crate::models::config::{AppConfig, Aria2Config, BackendConfig, DatabaseConfig};
+#[cfg(feature = "aria2")]
+use crate::services::aria2::Aria2Service;
+use crate::services::token_manager::{CachedTokenSource, TokenSource};
+use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+use rusqlite::{Connection, OpenFlags};
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// 读取访问令牌的超时时间
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Warning, // 部分功能受影响，程序可以继续使用
+    Failed,
+}
+
+impl CheckStatus {
+    /// 对应 UI 中 SelfCheckItem.status 的取值
+    pub fn as_index(&self) -> i32 {
+        match self {
+            Self::Passed => 0,
+            Self::Warning => 1,
+            Self::Failed => 2,
+        }
+    }
+}
+
+/// 一项检查的结果
+#[derive(Debug, Clone)]
+pub struct CheckItem {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<String>, // 修复建议，检查通过时为 None
+}
+
+impl CheckItem {
+    fn passed(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Passed,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn problem(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// 自检结果
+#[derive(Debug, Clone, Default)]
+pub struct SelfCheckReport {
+    pub items: Vec<CheckItem>,
+}
+
+impl SelfCheckReport {
+    /// 未通过的检查数
+    pub fn problem_count(&self) -> usize {
+        self.items.iter().filter(|item| item.status != CheckStatus::Passed).count()
+    }
+
+    /// 是否全部通过
+    pub fn is_healthy(&self) -> bool {
+        self.problem_count() == 0
+    }
+
+    /// 写入日志，通过的检查只在 debug 级别记录
+    pub fn log(&self) {
+        for item in &self.items {
+            match item.status {
+                CheckStatus::Passed => debug!("Self-check {}: {}", item.name, item.detail),
+                _ => warn!("Self-check {}: {} ({})", item.name, item.detail, item.hint.as_deref().unwrap_or_default()),
+            }
+        }
+        info!("Self-check finished, {} of {} checks need attention", self.problem_count(), self.items.len());
+    }
+}
+
+/// 检查配置文件能否读取，需要在加载配置（可能从备份恢复）之前调用
+///
+/// # Arguments
+/// * `path` - 配置文件路径
+pub fn check_config(path: &str) -> CheckItem {
+    const NAME: &str = "配置文件";
+    if !Path::new(path).exists() {
+        return CheckItem::problem(
+            NAME,
+            CheckStatus::Warning,
+            format!("未找到 {}", path),
+            "已使用默认配置创建配置文件，请检查下载目录和数据库设置",
+        );
+    }
+    match AppConfig::load_from_file(path) {
+        Ok(_) => CheckItem::passed(NAME, path),
+        Err(e) if Path::new(&AppConfig::backup_path(path)).exists() => CheckItem::problem(
+            NAME,
+            CheckStatus::Warning,
+            format!("无法解析: {:#}", e),
+            format!("已从备份 {} 恢复，之前的修改可能丢失", AppConfig::backup_path(path)),
+        ),
+        Err(e) => CheckItem::problem(
+            NAME,
+            CheckStatus::Failed,
+            format!("无法解析: {:#}", e),
+            "修正配置文件中的语法错误，或删除后重新启动以生成默认配置",
+        ),
+    }
+}
+
+/// 检查配置中的数据库能否打开，SQLite 数据库以只读方式打开并读取表结构
+pub fn check_databases(config: &AppConfig) -> Vec<CheckItem> {
+    config.multi_database.databases.iter().map(check_database).collect()
+}
+
+fn check_database(database: &DatabaseConfig) -> CheckItem {
+    let name = format!("数据库 {}", database.name);
+    if database.db_type != "sqlite" {
+        return CheckItem::passed(name, format!("{} 数据库在使用时连接", database.db_type));
+    }
+    let path = &database.connection_string;
+    if !Path::new(path).exists() {
+        return CheckItem::problem(
+            name,
+            CheckStatus::Warning,
+            format!("{} 不存在", path),
+            "文件可能已移动或删除，在设置中重新扫描数据库目录或移除该数据库",
+        );
+    }
+    let opened = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).and_then(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+    });
+    match opened {
+        Ok(_) => CheckItem::passed(name, path.as_str()),
+        Err(e) => CheckItem::problem(
+            name,
+            CheckStatus::Failed,
+            format!("无法打开 {}: {}", path, e),
+            "文件可能不是 SQLite 数据库或已损坏，在维护对话框中从最近的备份恢复",
+        ),
+    }
+}
+
+/// 检查是否安装了 aria2c
+pub fn check_aria2(config: &Aria2Config) -> CheckItem {
+    const NAME: &str = "Aria2";
+    if !config.enabled {
+        return CheckItem::passed(NAME, "已在配置中关闭，使用内置下载器");
+    }
+    if !cfg!(feature = "aria2") {
+        return CheckItem::passed(NAME, "未启用 aria2 feature，使用内置下载器");
+    }
+    if aria2_installed() {
+        CheckItem::passed(NAME, "已找到 aria2c")
+    } else {
+        CheckItem::problem(
+            NAME,
+            CheckStatus::Warning,
+            "未找到 aria2c，下载将使用内置下载器",
+            "安装 aria2（Linux: apt install aria2，macOS: brew install aria2，Windows: 将 aria2c.exe 放入 PATH）",
+        )
+    }
+}
+
+#[cfg(feature = "aria2")]
+fn aria2_installed() -> bool {
+    Aria2Service::check_aria2_installed()
+}
+
+#[cfg(not(feature = "aria2"))]
+fn aria2_installed() -> bool {
+    false
+}
+
+/// 检查后端服务的首选端口是否空闲，需要在启动后端服务之前调用
+pub fn check_backend_port(backend: &BackendConfig) -> CheckItem {
+    const NAME: &str = "后端端口";
+    match TcpListener::bind((backend.host.as_str(), backend.port)) {
+        Ok(_) => CheckItem::passed(NAME, format!("{}:{} 可用", backend.host, backend.port)),
+        Err(e) if backend.port_attempts > 1 => CheckItem::problem(
+            NAME,
+            CheckStatus::Warning,
+            format!("{}:{} 不可用: {}", backend.host, backend.port, e),
+            format!("将依次尝试之后的 {} 个端口；如需固定端口，关闭占用该端口的程序或修改 backend.port", backend.port_attempts - 1),
+        ),
+        Err(e) => CheckItem::problem(
+            NAME,
+            CheckStatus::Failed,
+            format!("{}:{} 不可用: {}", backend.host, backend.port, e),
+            "关闭占用该端口的程序，或修改 backend.port，或将 backend.port_attempts 设为大于 1",
+        ),
+    }
+}
+
+/// 检查网盘访问令牌能否从缓存中读取
+pub async fn check_token() -> CheckItem {
+    const NAME: &str = "访问令牌";
+    const HINT: &str = "使用 netdisk 命令行重新登录授权，令牌写入缓存后重新启动";
+    let env = match NetDiskEnv::new() {
+        Ok(env) => env,
+        Err(e) => return CheckItem::problem(NAME, CheckStatus::Failed, format!("无法读取网盘环境配置: {}", e), HINT),
+    };
+    let source = CachedTokenSource::new(env.config_dir.join("config.toml"));
+    match tokio::time::timeout(TOKEN_TIMEOUT, source.fetch()).await {
+        Ok(Ok(_)) => CheckItem::passed(NAME, "已从缓存读取"),
+        Ok(Err(e)) => CheckItem::problem(NAME, CheckStatus::Failed, format!("{:#}", e), HINT),
+        Err(_) => CheckItem::problem(NAME, CheckStatus::Warning, "读取超时", "检查网络连接，令牌刷新需要访问网盘"),
+    }
+}
+
+/// 执行配置文件之外的全部检查
+///
+/// # Arguments
+/// * `config` - 已加载的配置
+/// * `config_check` - 加载配置前的配置文件检查结果
+/// * `port_check` - 启动后端服务前的端口检查结果
+pub async fn run(config: &AppConfig, config_check: CheckItem, port_check: CheckItem) -> SelfCheckReport {
+    let blocking_config = config.clone();
+    let mut items = vec![config_check];
+    // 打开数据库和启动 aria2c 会阻塞，放到阻塞线程池中执行
+    match tokio::task::spawn_blocking(move || {
+        let mut items = check_databases(&blocking_config);
+        items.push(check_aria2(&blocking_config.aria2));
+        items
+    })
+    .await
+    {
+        Ok(checked) => items.extend(checked),
+        Err(e) => warn!("Self-check task failed: {}", e),
+    }
+    items.push(port_check);
+    items.push(check_token().await);
+    SelfCheckReport { items }
+}
@@ -0,0 +1,87 @@
+//! 退出协调 - 界面关闭后通知后台任务停止并等待其结束
+//!
+//! 后台任务通过 `subscribe` 获得退出通知，
+//! 界面退出时调用 `shutdown`，在超时时间内等待已登记的任务结束
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// 退出协调器
+pub struct ShutdownCoordinator {
+    sender: broadcast::Sender<()>,
+    triggered: AtomicBool,
+    tasks: Mutex<Vec<(String, JoinHandle<()>)>>,
+}
+
+/// 共享的退出协调器实例
+pub type SharedShutdown = Arc<ShutdownCoordinator>;
+
+impl ShutdownCoordinator {
+    /// 创建退出协调器
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(1).0,
+            triggered: AtomicBool::new(false),
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 订阅退出通知
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.sender.subscribe()
+    }
+
+    /// 是否已经开始退出
+    pub fn is_shutting_down(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    /// 登记需要在退出时等待的后台任务
+    ///
+    /// # Arguments
+    /// * `name` - 任务名称，用于日志
+    /// * `handle` - 任务句柄
+    pub fn register(&self, name: &str, handle: JoinHandle<()>) {
+        self.tasks.lock().unwrap().push((name.to_string(), handle));
+    }
+
+    /// 通知所有订阅者退出，可重复调用
+    pub fn trigger(&self) {
+        if !self.triggered.swap(true, Ordering::SeqCst) {
+            info!("Shutdown requested");
+            let _ = self.sender.send(());
+        }
+    }
+
+    /// 通知退出并等待已登记的任务结束，超时的任务会被中止
+    ///
+    /// # Arguments
+    /// * `timeout` - 所有任务共用的等待时间
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.trigger();
+
+        let tasks: Vec<_> = self.tasks.lock().unwrap().drain(..).collect();
+        let deadline = tokio::time::Instant::now() + timeout;
+        for (name, handle) in tasks {
+            let abort = handle.abort_handle();
+            match tokio::time::timeout_at(deadline, handle).await {
+                Ok(_) => debug!("Background task {} finished", name),
+                Err(_) => {
+                    warn!("Background task {} did not stop in time, aborting", name);
+                    abort.abort();
+                }
+            }
+        }
+        info!("Shutdown complete");
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,135 @@
+//! 下载元数据 - 在下载文件旁写入记录来源的 JSON 文件
+//!
+//! 提交下载时在目标文件旁写入 `<文件名>.netdisk.json`，记录来源数据库、记录 ID、ETag 和
+//! 原始路径。下载面板扫描下载目录中的这些文件，可以从下载的文件定位回索引中的记录
+
+use crate::models::database::FileRecord;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// 元数据文件名后缀，附加在下载文件名之后
+pub const SIDECAR_SUFFIX: &str = ".netdisk.json";
+
+/// 扫描下载目录时的最大深度，按类型的子目录在第二层
+const SCAN_DEPTH: usize = 3;
+
+/// 下载文件的来源信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadSidecar {
+    pub database: String, // 来源数据库名称
+    pub record_id: i64,
+    pub etag: String,
+    pub path: String, // 文件在索引中的原始路径
+    pub size: u64,
+    pub created_at: i64, // 提交下载的时间
+}
+
+impl DownloadSidecar {
+    /// 由文件记录创建，时间为当前时间
+    ///
+    /// # Arguments
+    /// * `record` - 下载的文件记录
+    /// * `database` - 来源数据库名称
+    pub fn for_record(record: &FileRecord, database: &str) -> Self {
+        Self {
+            database: database.to_string(),
+            record_id: record.id,
+            etag: record.etag.clone(),
+            path: record.path.clone(),
+            size: record.size,
+            created_at: chrono::Local::now().timestamp(),
+        }
+    }
+}
+
+/// 下载文件对应的元数据文件路径
+pub fn sidecar_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(SIDECAR_SUFFIX);
+    target.with_file_name(name)
+}
+
+/// 元数据文件对应的下载文件路径，不是元数据文件时返回 None
+pub fn target_of(sidecar: &Path) -> Option<PathBuf> {
+    let name = sidecar.file_name()?.to_str()?;
+    let target = name.strip_suffix(SIDECAR_SUFFIX).filter(|target| !target.is_empty())?;
+    Some(sidecar.with_file_name(target))
+}
+
+/// 在下载文件旁写入元数据，目录不存在时创建
+///
+/// # Arguments
+/// * `target` - 下载文件路径，文件可以尚未下载完成
+/// * `sidecar` - 来源信息
+pub fn write(target: &Path, sidecar: &DownloadSidecar) -> Result<PathBuf> {
+    let path = sidecar_path(target);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(sidecar).context("Failed to serialize sidecar")?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    debug!("Wrote download sidecar {}", path.display());
+    Ok(path)
+}
+
+/// 写入元数据，失败时只记录日志，不影响下载
+pub fn write_logged(target: &Path, sidecar: &DownloadSidecar) {
+    if let Err(e) = write(target, sidecar) {
+        warn!("Failed to write download sidecar for {}: {:#}", target.display(), e);
+    }
+}
+
+/// 读取下载文件的元数据，没有元数据文件时返回 None
+pub fn read(target: &Path) -> Result<Option<DownloadSidecar>> {
+    let path = sidecar_path(target);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let sidecar = serde_json::from_str(&json).with_context(|| format!("Invalid sidecar {}", path.display()))?;
+    Ok(Some(sidecar))
+}
+
+/// 扫描下载目录中的元数据，按提交时间从新到旧排序，无法解析的文件跳过
+///
+/// # Arguments
+/// * `dirs` - 下载目录，重复的目录只扫描一次
+///
+/// # Returns
+/// * `Vec<(PathBuf, DownloadSidecar)>` - 下载文件路径和来源信息
+pub fn scan(dirs: &[PathBuf]) -> Vec<(PathBuf, DownloadSidecar)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut found = Vec::new();
+    for dir in dirs {
+        if seen.insert(dir.clone()) {
+            scan_dir(dir, SCAN_DEPTH, &mut found);
+        }
+    }
+    found.sort_by(|(_, a), (_, b)| b.created_at.cmp(&a.created_at));
+    found
+}
+
+fn scan_dir(dir: &Path, depth: usize, found: &mut Vec<(PathBuf, DownloadSidecar)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 1 {
+                scan_dir(&path, depth - 1, found);
+            }
+            continue;
+        }
+        let Some(target) = target_of(&path) else {
+            continue;
+        };
+        match read(&target) {
+            Ok(Some(sidecar)) => found.push((target, sidecar)),
+            Ok(None) => {}
+            Err(e) => debug!("Skipping sidecar {}: {:#}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! 单实例 - 保证同一数据目录只运行一个图形界面实例
+//!
+//! 第一个实例持有数据目录中的锁文件，并在本地套接字（Unix）或命名管道（Windows）上监听。
+//! 之后启动的实例无法取得锁时，向运行中的实例发送 `activate` 并退出，运行中的实例把窗口
+//! 显示到最前面。避免两个实例各自启动 aria2c 和后端服务争用同一端口。
+//!
+//! 同一接口也供脚本和其他程序控制运行中的实例（`netdisk_db --no-gui remote <命令>`）。
+//! 协议为一行一个请求、一行一个响应的文本，响应以 `ok` 或 `error` 开头，之后是结果：
+//!
+//! | 请求 | 成功时的响应 |
+//! |------|--------------|
+//! | `activate` | `ok` |
+//! | `search <关键词>` | `ok <JSON 数组>`，当前数据库中的匹配记录 |
+//! | `add-download <链接> [文件名]` | `ok <gid>`，链接交给 aria2 下载，未指定文件名时由链接决定 |
+//! | `open <ID>` | `ok <路径>`，用系统默认程序打开该记录对应的本地文件 |
+
+use crate::utils::paths;
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::File;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// 发送给运行中实例的请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceRequest {
+    /// 显示并激活主窗口
+    Activate,
+    /// 在当前数据库中搜索
+    Search { query: String },
+    /// 将链接添加到下载，`name` 为保存的文件名
+    AddDownload { url: String, name: Option<String> },
+    /// 打开记录对应的本地文件
+    Open { id: i64 },
+}
+
+impl InstanceRequest {
+    /// 解析一行请求
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        match command {
+            "activate" => Ok(Self::Activate),
+            "search" if !argument.is_empty() => Ok(Self::Search {
+                query: argument.to_string(),
+            }),
+            "add-download" if !argument.is_empty() => {
+                // 链接中不含空格，之后的部分都是文件名
+                let (url, name) = argument.split_once(' ').unwrap_or((argument, ""));
+                let name = name.trim();
+                Ok(Self::AddDownload {
+                    url: url.to_string(),
+                    name: (!name.is_empty()).then(|| name.to_string()),
+                })
+            }
+            "open" => Ok(Self::Open {
+                id: argument.parse().with_context(|| format!("Invalid record id: {:?}", argument))?,
+            }),
+            "search" | "add-download" => anyhow::bail!("{} requires an argument", command),
+            other => anyhow::bail!("Unknown command: {}", other),
+        }
+    }
+
+    /// 协议中的一行，不含换行
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Activate => "activate".to_string(),
+            Self::Search { query } => format!("search {}", query),
+            Self::AddDownload { url, name: None } => format!("add-download {}", url),
+            Self::AddDownload { url, name: Some(name) } => format!("add-download {} {}", url, name),
+            Self::Open { id } => format!("open {}", id),
+        }
+    }
+}
+
+/// 成功的响应
+///
+/// # Arguments
+/// * `payload` - 结果，不能包含换行，为空时只返回 `ok`
+pub fn ok_response(payload: &str) -> String {
+    if payload.is_empty() {
+        "ok".to_string()
+    } else {
+        format!("ok {}", payload)
+    }
+}
+
+/// 失败的响应，错误链中的换行替换为空格
+pub fn error_response(error: &anyhow::Error) -> String {
+    format!("error {}", format!("{:#}", error).replace(['\r', '\n'], " "))
+}
+
+/// 处理请求的异步任务，完成时返回响应
+pub type ResponseFuture = Pin<Box<dyn Future<Output = String> + Send>>;
+
+/// 处理请求，返回的文本作为响应发送，不含换行
+pub type RequestHandler = Arc<dyn Fn(InstanceRequest) -> ResponseFuture + Send + Sync>;
+
+/// 单实例锁，释放后其他实例可以启动
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// 尝试取得单实例锁
+///
+/// # Returns
+/// * `Result<Option<InstanceLock>>` - 已有实例在运行时返回 None
+pub fn acquire() -> Result<Option<InstanceLock>> {
+    let path = paths::instance_lock_file();
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    match file.try_lock_exclusive() {
+        Ok(()) => Ok(Some(InstanceLock { _file: file })),
+        Err(e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+    }
+}
+
+/// 是否已有实例在运行，检查后立即释放锁
+pub fn is_running() -> Result<bool> {
+    Ok(acquire()?.is_none())
+}
+
+/// 向运行中的实例发送请求
+///
+/// # Returns
+/// * `Result<String>` - 运行中实例返回的结果，不含开头的 `ok`
+pub async fn send(request: &InstanceRequest) -> Result<String> {
+    let stream = connect().await.context("Failed to connect to the running instance")?;
+    let (reader, mut writer) = tokio::io::split(stream);
+    writer.write_all(format!("{}\n", request.to_line()).as_bytes()).await?;
+    writer.flush().await?;
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+    let response = response.trim_end();
+    if let Some(message) = response.strip_prefix("error") {
+        anyhow::bail!("Running instance rejected {}: {}", request.to_line(), message.trim());
+    }
+    match response.strip_prefix("ok") {
+        Some(payload) => Ok(payload.trim_start().to_string()),
+        None => anyhow::bail!("Unexpected response from the running instance: {:?}", response),
+    }
+}
+
+/// 监听其他进程的请求，直到收到退出通知
+///
+/// # Arguments
+/// * `handler` - 处理请求
+/// * `shutdown` - 退出通知
+pub async fn serve(handler: RequestHandler, mut shutdown: broadcast::Receiver<()>) {
+    let mut listener = match Listener::bind() {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Failed to listen for other instances: {:#}", e);
+            return;
+        }
+    };
+    info!("Listening for other instances");
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(stream) => {
+                    tokio::spawn(handle_connection(stream, handler.clone()));
+                }
+                Err(e) => {
+                    warn!("Failed to accept instance connection: {}", e);
+                }
+            },
+            _ = shutdown.recv() => break,
+        }
+    }
+    listener.close();
+}
+
+/// 逐行读取请求并写回响应
+async fn handle_connection<S>(stream: S, handler: RequestHandler)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match InstanceRequest::parse(&line) {
+            Ok(request) => {
+                debug!("Instance request: {:?}", request);
+                handler(request).await
+            }
+            Err(e) => error_response(&e),
+        };
+        if writer.write_all(format!("{}\n", response).as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+struct Listener {
+    inner: tokio::net::UnixListener,
+    path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl Listener {
+    fn bind() -> Result<Self> {
+        let path = paths::instance_socket_file();
+        // 持有锁时残留的套接字文件来自异常退出的实例
+        let _ = std::fs::remove_file(&path);
+        let inner = tokio::net::UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+        Ok(Self { inner, path })
+    }
+
+    async fn accept(&mut self) -> std::io::Result<tokio::net::UnixStream> {
+        self.inner.accept().await.map(|(stream, _)| stream)
+    }
+
+    fn close(self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+async fn connect() -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(paths::instance_socket_file()).await
+}
+
+/// Windows 命名管道名称，按数据目录区分，便携模式和安装模式互不影响
+#[cfg(windows)]
+fn pipe_name() -> String {
+    use md5::{Digest, Md5};
+    let digest = Md5::digest(paths::data_dir().to_string_lossy().as_bytes());
+    let suffix: String = digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
+    format!(r"\\.\pipe\netdisk_db-{}", suffix)
+}
+
+#[cfg(windows)]
+struct Listener {
+    next: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(windows)]
+impl Listener {
+    fn bind() -> Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(pipe_name())
+            .context("Failed to create named pipe")?;
+        Ok(Self { next })
+    }
+
+    async fn accept(&mut self) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeServer> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        self.next.connect().await?;
+        // 每个连接使用一个管道实例，连接后立即创建下一个等待的实例
+        let next = ServerOptions::new().create(pipe_name())?;
+        Ok(std::mem::replace(&mut self.next, next))
+    }
+
+    fn close(self) {}
+}
+
+#[cfg(windows)]
+async fn connect() -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+    const ERROR_PIPE_BUSY: i32 = 231;
+    const BUSY_RETRIES: u32 = 20;
+    let mut attempt = 0;
+    loop {
+        match ClientOptions::new().open(pipe_name()) {
+            // 所有管道实例都在处理连接时稍后重试
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempt < BUSY_RETRIES => {
+                attempt += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! SQL 控制台 - 对当前数据库执行只读查询
+//!
+//! 只允许以白名单中的关键字开头的单条语句，数据库实现还会在执行前再次确认语句不会写入。
+//! 结果列包含文件记录的全部字段时，结果显示在普通的结果列表中，可以继续复制链接和下载
+
+use anyhow::{bail, Result};
+
+/// 允许的语句类型
+pub const ALLOWED_STATEMENTS: [&str; 4] = ["SELECT", "WITH", "EXPLAIN", "VALUES"];
+
+/// 控制台最多返回的行数
+pub const CONSOLE_ROW_LIMIT: usize = 1000;
+
+/// 转换为文件记录需要的结果列
+pub const FILE_RECORD_COLUMNS: [&str; 7] = ["id", "path", "size", "etag", "modified_time", "file_type", "name"];
+
+/// 检查语句是否在白名单中
+///
+/// # Returns
+/// * `Result<&str>` - 去掉首尾空白和末尾分号后的语句
+pub fn validate(sql: &str) -> Result<&str> {
+    let statement = sql.trim().trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+    if statement.is_empty() {
+        bail!("请输入查询语句");
+    }
+    // 字符串中的分号也会被拒绝，换来不需要解析 SQL
+    if statement.contains(';') {
+        bail!("一次只能执行一条语句");
+    }
+    let keyword: String = statement
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if !ALLOWED_STATEMENTS.contains(&keyword.as_str()) {
+        bail!("只允许执行 {} 语句", ALLOWED_STATEMENTS.join("、"));
+    }
+    Ok(statement)
+}
@@ -0,0 +1,84 @@
+//! 字幕 - 在索引中查找与视频同名的字幕文件
+//!
+//! 字幕文件名与视频相同，或在视频文件名后附加语言标记，例如 `Movie.mkv` 对应
+//! `Movie.srt`、`Movie.zh.ass`。同一文件夹中有字幕时只使用这些字幕，否则使用索引中所有同名字幕
+
+use crate::models::database::{parent_folder, FileRecord, SearchOptions};
+use crate::services::database_manager::DatabaseHandle;
+use crate::utils::common::get_file_extension;
+use crate::utils::filetype::{category_of, FileCategory};
+use anyhow::Result;
+use std::collections::HashSet;
+use tracing::debug;
+
+/// 字幕文件扩展名（小写，不含点）
+pub const SUBTITLE_EXTENSIONS: [&str; 5] = ["srt", "ass", "ssa", "vtt", "sub"];
+
+/// 每个视频在索引中查找字幕时最多读取的记录数
+const SEARCH_LIMIT: usize = 200;
+
+/// 是否是字幕文件
+pub fn is_subtitle(name: &str) -> bool {
+    get_file_extension(name).is_some_and(|ext| SUBTITLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 是否是视频文件
+pub fn is_video(record: &FileRecord) -> bool {
+    category_of(&record.file_type, &record.name) == Some(FileCategory::Video)
+}
+
+/// 去掉扩展名的文件名
+fn file_stem(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// 字幕是否属于该视频：去掉扩展名后与视频同名，或以视频名加 `.` 开头（不区分大小写）
+pub fn is_subtitle_for(video_name: &str, subtitle_name: &str) -> bool {
+    if !is_subtitle(subtitle_name) {
+        return false;
+    }
+    let video = file_stem(video_name).to_lowercase();
+    let subtitle = file_stem(subtitle_name).to_lowercase();
+    subtitle == video || subtitle.strip_prefix(&video).is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// 从候选记录中选出视频的字幕，同一文件夹中有字幕时只返回这些
+///
+/// # Arguments
+/// * `video` - 视频记录
+/// * `candidates` - 候选记录，一般是按视频名搜索的结果
+pub fn matching_subtitles(video: &FileRecord, candidates: &[FileRecord]) -> Vec<FileRecord> {
+    let matches: Vec<&FileRecord> = candidates
+        .iter()
+        .filter(|candidate| candidate.path != video.path && is_subtitle_for(&video.name, &candidate.name))
+        .collect();
+    let folder = parent_folder(&video.path);
+    let same_folder: Vec<&FileRecord> = matches
+        .iter()
+        .copied()
+        .filter(|candidate| parent_folder(&candidate.path) == folder)
+        .collect();
+    let selected = if same_folder.is_empty() { matches } else { same_folder };
+    selected.into_iter().cloned().collect()
+}
+
+/// 在当前数据库中查找选中视频的字幕，跳过非视频记录，结果按路径去重
+///
+/// # Arguments
+/// * `database` - 数据库管理线程句柄
+/// * `videos` - 选中的文件记录
+pub fn find_subtitles(database: &DatabaseHandle, videos: &[FileRecord]) -> Result<Vec<FileRecord>> {
+    let selected: HashSet<&str> = videos.iter().map(|record| record.path.as_str()).collect();
+    let mut seen = HashSet::new();
+    let mut subtitles = Vec::new();
+    for video in videos.iter().filter(|record| is_video(record)) {
+        let candidates = database.search(file_stem(&video.name), SearchOptions::default(), SEARCH_LIMIT)?;
+        for subtitle in matching_subtitles(video, &candidates) {
+            if !selected.contains(subtitle.path.as_str()) && seen.insert(subtitle.path.clone()) {
+                subtitles.push(subtitle);
+            }
+        }
+    }
+    debug!("Found {} subtitles for {} selected files", subtitles.len(), videos.len());
+    Ok(subtitles)
+}
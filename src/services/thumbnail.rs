@@ -0,0 +1,187 @@
+//! 缩略图服务 - 为本地图片和视频生成小尺寸缩略图
+//!
+//! 缩略图在后台工作线程中生成，并按 路径+修改时间 缓存到缓存目录
+
+use crate::utils::common::get_file_extension;
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, warn};
+
+/// 缩略图最大边长（像素）
+const THUMBNAIL_SIZE: u32 = 96;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "avi", "mov", "webm", "flv", "wmv"];
+
+/// 缩略图生成完成回调，参数为源文件路径
+type ReadyCallback = Arc<dyn Fn(String) + Send + Sync>;
+
+/// 缩略图服务
+pub struct ThumbnailService {
+    cache_dir: PathBuf,
+    sender: Mutex<Sender<String>>,
+    in_flight: Arc<Mutex<HashSet<String>>>,
+    on_ready: Arc<Mutex<Option<ReadyCallback>>>,
+}
+
+/// 共享的缩略图服务实例
+pub type SharedThumbnailService = Arc<ThumbnailService>;
+
+impl ThumbnailService {
+    /// 创建缩略图服务并启动后台工作线程
+    ///
+    /// # Arguments
+    /// * `cache_dir` - 缩略图缓存目录
+    pub fn new<P: AsRef<Path>>(cache_dir: P) -> Self {
+        let cache_dir = cache_dir.as_ref().to_path_buf();
+        let (sender, receiver) = channel::<String>();
+        let in_flight = Arc::new(Mutex::new(HashSet::new()));
+        let on_ready: Arc<Mutex<Option<ReadyCallback>>> = Arc::new(Mutex::new(None));
+
+        let worker_dir = cache_dir.clone();
+        let worker_in_flight = in_flight.clone();
+        let worker_on_ready = on_ready.clone();
+        std::thread::spawn(move || {
+            let ffmpeg_available = Self::check_ffmpeg_installed();
+            for path in receiver {
+                let result = Self::cache_path_in(&worker_dir, &path)
+                    .ok_or_else(|| anyhow::anyhow!("Source file not found: {}", path))
+                    .and_then(|target| Self::generate(Path::new(&path), &target, ffmpeg_available));
+
+                worker_in_flight.lock().unwrap().remove(&path);
+                match result {
+                    Ok(_) => {
+                        let callback = worker_on_ready.lock().unwrap().clone();
+                        if let Some(callback) = callback {
+                            callback(path);
+                        }
+                    }
+                    Err(e) => debug!("Failed to generate thumbnail for {}: {}", path, e),
+                }
+            }
+        });
+
+        Self {
+            cache_dir,
+            sender: Mutex::new(sender),
+            in_flight,
+            on_ready,
+        }
+    }
+
+    /// 设置缩略图生成完成后的回调（在工作线程中调用）
+    pub fn set_on_ready<F>(&self, callback: F)
+    where
+        F: Fn(String) + Send + Sync + 'static,
+    {
+        *self.on_ready.lock().unwrap() = Some(Arc::new(callback));
+    }
+
+    /// 判断文件类型是否支持生成缩略图
+    pub fn is_supported(path: &str) -> bool {
+        get_file_extension(path)
+            .map(|ext| ext.to_lowercase())
+            .map(|ext| {
+                IMAGE_EXTENSIONS.contains(&ext.as_str()) || VIDEO_EXTENSIONS.contains(&ext.as_str())
+            })
+            .unwrap_or(false)
+    }
+
+    /// 获取已缓存的缩略图，不存在时返回 None
+    pub fn cached_thumbnail(&self, path: &str) -> Option<PathBuf> {
+        Self::cache_path_in(&self.cache_dir, path).filter(|p| p.exists())
+    }
+
+    /// 请求为本地文件生成缩略图，文件不存在或已在生成中时忽略
+    pub fn request(&self, path: &str) {
+        if !Self::is_supported(path) || !Path::new(path).is_file() {
+            return;
+        }
+
+        if !self.in_flight.lock().unwrap().insert(path.to_string()) {
+            return;
+        }
+
+        if let Err(e) = self.sender.lock().unwrap().send(path.to_string()) {
+            warn!("Thumbnail worker is not running: {}", e);
+            self.in_flight.lock().unwrap().remove(path);
+        }
+    }
+
+    /// 计算缩略图缓存路径（源文件路径和修改时间的哈希），源文件不存在时返回 None
+    fn cache_path_in(cache_dir: &Path, path: &str) -> Option<PathBuf> {
+        let modified = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        modified.hash(&mut hasher);
+
+        Some(cache_dir.join(format!("{:016x}.png", hasher.finish())))
+    }
+
+    /// 生成缩略图：图片直接缩放，视频使用 ffmpeg 截取一帧
+    fn generate(source: &Path, target: &Path, ffmpeg_available: bool) -> Result<()> {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create thumbnail cache directory")?;
+        }
+
+        let ext = source
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+            image::open(source)
+                .context("Failed to decode image")?
+                .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+                .save(target)
+                .context("Failed to save thumbnail")?;
+        } else if ffmpeg_available {
+            let status = Command::new("ffmpeg")
+                .args(["-y", "-loglevel", "error", "-ss", "5", "-i"])
+                .arg(source)
+                .args(["-frames:v", "1", "-vf"])
+                .arg(format!(
+                    "scale={}:{}:force_original_aspect_ratio=decrease",
+                    THUMBNAIL_SIZE, THUMBNAIL_SIZE
+                ))
+                .arg(target)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .context("Failed to run ffmpeg")?;
+            if !status.success() || !target.exists() {
+                anyhow::bail!("ffmpeg exited with status: {}", status);
+            }
+        } else {
+            anyhow::bail!("ffmpeg is not installed, cannot generate video thumbnail");
+        }
+
+        debug!("Generated thumbnail {:?} for {:?}", target, source);
+        Ok(())
+    }
+
+    /// 检查 ffmpeg 是否可用
+    fn check_ffmpeg_installed() -> bool {
+        Command::new("ffmpeg")
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+}
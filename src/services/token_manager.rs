@@ -0,0 +1,266 @@
+//! 访问令牌管理 - 在过期前刷新网盘 API 的访问令牌
+//!
+//! 令牌保存在 `Arc<RwLock<AccessToken>>` 中，`fresh_access_token` 中间件在每个请求开始时
+//! 取出最近一次刷新的令牌交给接口。配置了开放平台凭据时每次刷新都重新授权并写回缓存文件。
+//! 令牌带有有效期时在过期前刷新，否则按固定间隔刷新；刷新失败时保留旧令牌并按较短间隔重试
+
+use crate::utils::time::{normalize_timestamp, parse_timestamp};
+use anyhow::{anyhow, bail, Context, Result};
+use netdisk_core::netdisk_api::prelude::get_access_token_from_cache;
+use netdisk_core::responses::prelude::AccessToken;
+use serde::Deserialize;
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// 刷新失败后的重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 按有效期刷新时在过期前预留的时间，不超过有效期的五分之一
+const REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// 令牌中表示过期时刻的字段，值为时间字符串或时间戳
+const EXPIRES_AT_FIELDS: [&str; 3] = ["expiredAt", "expired_at", "expires_at"];
+
+/// 令牌中表示有效秒数的字段
+const EXPIRES_IN_FIELDS: [&str; 2] = ["expires_in", "expiresIn"];
+
+/// 共享的访问令牌
+pub type SharedAccessToken = Arc<RwLock<AccessToken>>;
+
+/// 访问令牌来源
+pub trait TokenSource: Send + Sync + 'static {
+    /// 获取有效的访问令牌，令牌即将过期时应重新授权并写回缓存
+    fn fetch(&self) -> impl Future<Output = Result<AccessToken>> + Send;
+}
+
+/// 开放平台授权接口的响应
+#[derive(Deserialize)]
+struct AuthorizeResponse {
+    code: i64,
+    #[serde(default)]
+    message: String,
+    data: Option<serde_json::Value>,
+}
+
+/// netdisk_core 的令牌缓存文件
+///
+/// 设置了 client_id 和 client_secret 时向开放平台重新授权，并把新令牌写回缓存文件；
+/// 否则只重新读取缓存文件，由其他程序负责更新
+pub struct CachedTokenSource {
+    cache_file: PathBuf,
+    authorization: Option<Authorization>,
+}
+
+/// 开放平台授权参数
+struct Authorization {
+    client: reqwest::Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+impl CachedTokenSource {
+    pub fn new(cache_file: PathBuf) -> Self {
+        Self { cache_file, authorization: None }
+    }
+
+    /// 使用开放平台凭据重新授权
+    ///
+    /// # Arguments
+    /// * `token_url` - 获取访问令牌的接口
+    /// * `client_id` - 开放平台 client_id
+    /// * `client_secret` - 开放平台 client_secret
+    pub fn with_credentials(mut self, token_url: String, client_id: String, client_secret: String) -> Self {
+        self.authorization = Some(Authorization {
+            client: reqwest::Client::new(),
+            token_url,
+            client_id,
+            client_secret,
+        });
+        self
+    }
+
+    async fn load(&self) -> Result<AccessToken> {
+        get_access_token_from_cache(&self.cache_file)
+            .await
+            .map_err(|e| anyhow!("Failed to load access token from {}: {}", self.cache_file.display(), e))
+    }
+
+    async fn authorize(&self, authorization: &Authorization) -> Result<AccessToken> {
+        let response: AuthorizeResponse = authorization
+            .client
+            .post(&authorization.token_url)
+            .header("Platform", "open_platform")
+            .json(&serde_json::json!({
+                "clientID": authorization.client_id,
+                "clientSecret": authorization.client_secret,
+            }))
+            .send()
+            .await
+            .context("Failed to request access token")?
+            .error_for_status()
+            .context("Failed to request access token")?
+            .json()
+            .await
+            .context("Invalid access token response")?;
+        if response.code != 0 {
+            bail!("Access token request failed: code={}, message={}", response.code, response.message);
+        }
+        let data = response.data.context("Access token response has no data")?;
+        serde_json::from_value(data).context("Invalid access token response")
+    }
+
+    /// 把令牌写回缓存文件，保留文件中的其他配置项
+    fn store(&self, token: &AccessToken) -> Result<()> {
+        let mut cache = match std::fs::read_to_string(&self.cache_file) {
+            Ok(content) => content.parse::<toml::Table>().with_context(|| {
+                format!("Failed to parse token cache {}", self.cache_file.display())
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", self.cache_file.display())),
+        };
+        cache.extend(toml::Table::try_from(token).context("Failed to serialize access token")?);
+
+        if let Some(parent) = self.cache_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // 先写临时文件再重命名，避免中途失败留下损坏的缓存
+        let temp = self.cache_file.with_extension("toml.tmp");
+        std::fs::write(&temp, toml::to_string_pretty(&cache)?)
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, &self.cache_file)
+            .with_context(|| format!("Failed to write {}", self.cache_file.display()))?;
+        Ok(())
+    }
+}
+
+impl TokenSource for CachedTokenSource {
+    async fn fetch(&self) -> Result<AccessToken> {
+        let Some(authorization) = &self.authorization else {
+            return self.load().await;
+        };
+        let token = self.authorize(authorization).await?;
+        if let Err(e) = self.store(&token) {
+            warn!("Failed to persist access token: {:#}", e);
+        }
+        Ok(token)
+    }
+}
+
+/// 令牌的剩余有效期
+///
+/// # Arguments
+/// * `token` - 序列化后的令牌
+/// * `now` - 当前 Unix 时间戳（秒）
+///
+/// # Returns
+/// * `Option<Duration>` - 剩余有效期，已过期时为 0，令牌中没有有效期时返回 None
+pub fn token_lifetime(token: &Value, now: i64) -> Option<Duration> {
+    let number = |value: &Value| value.as_i64().or_else(|| value.as_str()?.trim().parse().ok());
+    let expires_in = EXPIRES_IN_FIELDS
+        .iter()
+        .find_map(|field| token.get(*field).and_then(number))
+        .filter(|seconds| *seconds > 0);
+    let remaining = expires_in.or_else(|| {
+        EXPIRES_AT_FIELDS.iter().find_map(|field| {
+            let expires_at = match token.get(*field)? {
+                Value::String(text) => parse_timestamp(text),
+                value => value.as_i64().map(normalize_timestamp),
+            };
+            expires_at.filter(|at| *at > 0).map(|at| at - now)
+        })
+    })?;
+    Some(Duration::from_secs(remaining.max(0) as u64))
+}
+
+/// 距下次刷新的时间
+///
+/// 知道有效期时在过期前 `REFRESH_MARGIN` 刷新，已过期时按重试间隔；不知道有效期时使用固定间隔
+///
+/// # Arguments
+/// * `lifetime` - 令牌的剩余有效期
+/// * `interval` - 固定的刷新间隔
+pub fn refresh_delay(lifetime: Option<Duration>, interval: Duration) -> Duration {
+    match lifetime {
+        None => interval,
+        Some(lifetime) if lifetime.is_zero() => RETRY_INTERVAL.min(interval),
+        Some(lifetime) => lifetime - REFRESH_MARGIN.min(lifetime / 5),
+    }
+}
+
+/// 访问令牌管理器
+pub struct TokenManager<S: TokenSource> {
+    source: S,
+    token: SharedAccessToken,
+    expires_at: Mutex<Option<Instant>>, // 当前令牌的过期时刻，未知时为 None
+    refresh_interval: Duration,
+}
+
+impl<S: TokenSource> TokenManager<S> {
+    /// 创建令牌管理器，初始令牌为空
+    ///
+    /// # Arguments
+    /// * `source` - 令牌来源
+    /// * `refresh_interval` - 令牌中没有有效期时的刷新间隔
+    pub fn new(source: S, refresh_interval: Duration) -> Self {
+        Self {
+            source,
+            token: Arc::new(RwLock::new(AccessToken::default())),
+            expires_at: Mutex::new(None),
+            refresh_interval,
+        }
+    }
+
+    /// 共享的令牌，交给后端接口使用
+    pub fn token(&self) -> SharedAccessToken {
+        self.token.clone()
+    }
+
+    /// 立即刷新令牌，失败时保留旧令牌
+    pub async fn refresh(&self) -> Result<()> {
+        let token = self.source.fetch().await?;
+        let lifetime = serde_json::to_value(&token)
+            .ok()
+            .and_then(|value| token_lifetime(&value, chrono::Utc::now().timestamp()));
+        *self.expires_at.lock().unwrap() = lifetime.and_then(|lifetime| Instant::now().checked_add(lifetime));
+        *self.token.write().await = token;
+        debug!("Access token refreshed, expires in {:?}", lifetime);
+        Ok(())
+    }
+
+    /// 按当前令牌的过期时刻计算距下次刷新的时间
+    pub fn next_refresh_delay(&self) -> Duration {
+        let lifetime = self
+            .expires_at
+            .lock()
+            .unwrap()
+            .map(|expires_at| expires_at.saturating_duration_since(Instant::now()));
+        refresh_delay(lifetime, self.refresh_interval)
+    }
+
+    /// 在令牌过期前刷新，收到退出通知后返回
+    pub async fn run(self: Arc<Self>, mut shutdown: broadcast::Receiver<()>) {
+        let mut delay = self.next_refresh_delay();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.recv() => break,
+            }
+            delay = match self.refresh().await {
+                Ok(()) => self.next_refresh_delay(),
+                Err(e) => {
+                    let retry = RETRY_INTERVAL.min(self.next_refresh_delay());
+                    warn!("{}, retrying in {:?}", e, retry);
+                    retry
+                }
+            };
+        }
+        info!("Token manager stopped");
+    }
+}
@@ -0,0 +1,125 @@
+//! 新版本检查 - 查询 GitHub 最新发布并与当前版本比较
+//!
+//! 默认关闭，在配置的 `update.enabled` 中开启。只在启动时查询一次，
+//! 有新版本时在状态栏显示提示，点击打开发布页面
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::time::Duration;
+
+/// 当前版本
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 查询超时，检查失败不影响启动
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 一次发布
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String, // 不含 `v` 前缀的版本号
+    pub url: String, // 发布页面
+}
+
+/// GitHub releases 接口返回的字段
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// 语义化版本号，只比较主、次、修订号和预发布标识
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>, // 预发布标识，例如 `beta.1`
+}
+
+impl Version {
+    /// 解析版本号，允许 `v` 前缀，缺少的次、修订号按 0 处理，忽略 `+` 之后的构建信息
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches(['v', 'V']);
+        let text = text.split('+').next().unwrap_or_default();
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (text, None),
+        };
+        let mut numbers = core.split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next().map_or(Some(0), |n| n.parse().ok())?;
+        let patch = numbers.next().map_or(Some(0), |n| n.parse().ok())?;
+        if numbers.next().is_some() {
+            return None;
+        }
+        Some(Self { major, minor, patch, pre })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            // 同一版本号时预发布版本较旧
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `latest` 是否比 `current` 新，无法解析的版本号视为不是新版本
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (Version::parse(latest), Version::parse(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// 查询最新发布，比当前版本新时返回
+///
+/// # Arguments
+/// * `releases_url` - GitHub 最新发布接口，例如 `https://api.github.com/repos/<owner>/<repo>/releases/latest`
+///
+/// # Returns
+/// * `Result<Option<Release>>` - 有新版本时返回该版本，已是最新时返回 None
+pub async fn check_for_update(releases_url: &str) -> Result<Option<Release>> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        // GitHub API 要求设置 User-Agent
+        .user_agent(concat!("netdisk_db/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to create HTTP client")?;
+    let release: GithubRelease = client
+        .get(releases_url)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .context("Failed to query latest release")?
+        .error_for_status()
+        .context("Failed to query latest release")?
+        .json()
+        .await
+        .context("Invalid release response")?;
+
+    if release.draft || release.prerelease || !is_newer(&release.tag_name, CURRENT_VERSION) {
+        return Ok(None);
+    }
+    Ok(Some(Release {
+        version: release.tag_name.trim_start_matches(['v', 'V']).to_string(),
+        url: release.html_url,
+    }))
+}
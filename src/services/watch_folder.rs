@@ -0,0 +1,278 @@
+//! 监视文件夹 - 自动将放入目录的链接文件提交到Aria2
+//!
+//! 支持 .torrent 种子文件，以及每行一个链接的 .magnet 和 .txt 文件（`#` 开头的行为注释）。
+//! 提交成功的文件移动到 `submitted` 子目录，失败的链接写入 `failed` 子目录中的同名文件，
+//! 修正后放回监视目录即可重新提交
+
+use crate::models::config::WatchFolderConfig;
+use crate::services::aria2::TorrentOptions;
+use crate::services::download_manager::SharedDownloadManager;
+use crate::services::errors::{self, ErrorCategory};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// 提交成功的文件移动到的子目录
+pub const SUBMITTED_DIR: &str = "submitted";
+/// 提交失败的链接写入的子目录
+pub const FAILED_DIR: &str = "failed";
+
+/// 文件最后修改后等待的时间，避免读取正在写入的文件
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// 监视目录中的一个链接文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkFile {
+    /// .torrent 文件内容
+    Torrent(Vec<u8>),
+    /// .magnet 或 .txt 中的链接
+    Links(Vec<String>),
+}
+
+/// 解析文本中的链接，忽略空行、注释和不像链接的行
+pub fn parse_links(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| line.starts_with("magnet:?") || line.contains("://"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 读取链接文件，不支持的扩展名返回 None
+pub fn read_link_file(path: &Path) -> Result<Option<LinkFile>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("torrent") => {
+            let bytes = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+            Ok(Some(LinkFile::Torrent(bytes)))
+        }
+        Some("magnet") | Some("txt") => {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            Ok(Some(LinkFile::Links(parse_links(&contents))))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// 一次扫描的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchScanSummary {
+    pub files: usize,     // 处理的文件数
+    pub submitted: usize, // 提交成功的任务数
+    pub failed: usize,    // 提交失败的链接或种子数
+}
+
+/// 监视文件夹
+pub struct WatchFolder {
+    directory: PathBuf,
+    download_manager: SharedDownloadManager,
+    poll_interval: Duration,
+}
+
+impl WatchFolder {
+    /// 创建监视文件夹
+    ///
+    /// # Arguments
+    /// * `directory` - 监视的目录
+    /// * `download_manager` - 提交任务使用的下载管理器
+    /// * `poll_interval` - 扫描间隔
+    pub fn new(directory: impl Into<PathBuf>, download_manager: SharedDownloadManager, poll_interval: Duration) -> Self {
+        Self {
+            directory: directory.into(),
+            download_manager,
+            poll_interval,
+        }
+    }
+
+    /// 根据配置创建，未启用或未设置目录时返回 None
+    pub fn from_config(config: &WatchFolderConfig, download_manager: SharedDownloadManager) -> Option<Self> {
+        let directory = config.directory.as_deref().filter(|_| config.enabled)?;
+        Some(Self::new(
+            directory,
+            download_manager,
+            Duration::from_secs(config.poll_secs.max(1)),
+        ))
+    }
+
+    /// 监视的目录
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// 目录中等待提交的文件，跳过子目录和刚修改过的文件
+    pub fn pending_files(&self) -> Result<Vec<PathBuf>> {
+        let now = SystemTime::now();
+        let mut files = Vec::new();
+        let entries = std::fs::read_dir(&self.directory)
+            .with_context(|| format!("Failed to read watch folder {:?}", self.directory))?;
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let settled = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .map_or(true, |age| age >= SETTLE_TIME);
+            let path = entry.path();
+            let supported = matches!(
+                path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref(),
+                Some("torrent" | "magnet" | "txt")
+            );
+            if supported && settled {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// 扫描一次目录并提交所有等待的文件
+    pub async fn scan_once(&self) -> Result<WatchScanSummary> {
+        let mut summary = WatchScanSummary::default();
+        // Aria2不可用时文件留在原处，等待下次扫描
+        if !self.download_manager.is_available() {
+            debug!("Aria2 not available, skipping watch folder scan");
+            return Ok(summary);
+        }
+        for path in self.pending_files()? {
+            let link_file = match read_link_file(&path) {
+                Ok(Some(link_file)) => link_file,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("{:#}", e);
+                    continue;
+                }
+            };
+            summary.files += 1;
+            let failed_links = self.submit(&path, link_file, &mut summary).await;
+            self.archive(&path, failed_links)?;
+        }
+        if summary.files > 0 {
+            info!(
+                "Watch folder: {} files, {} submitted, {} failed",
+                summary.files, summary.submitted, summary.failed
+            );
+        }
+        Ok(summary)
+    }
+
+    /// 提交一个文件中的任务
+    ///
+    /// # Returns
+    /// * `Option<String>` - 需要写入 `failed` 目录的内容，全部成功时为 None
+    async fn submit(&self, path: &Path, link_file: LinkFile, summary: &mut WatchScanSummary) -> Option<String> {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match link_file {
+            LinkFile::Torrent(bytes) => match self.download_manager.submit_torrent(&name, &bytes, &TorrentOptions::default()).await {
+                Ok(gid) => {
+                    debug!("Submitted torrent {} as {}", name, gid);
+                    summary.submitted += 1;
+                    None
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    errors::report_as(ErrorCategory::Aria2, &format!("提交种子 {} 失败", name), e);
+                    Some(String::new())
+                }
+            },
+            LinkFile::Links(links) => {
+                let mut failed = Vec::new();
+                for link in links {
+                    match self.download_manager.submit_uri(&link, None, None).await {
+                        Ok(gid) => {
+                            debug!("Submitted {} as {}", link, gid);
+                            summary.submitted += 1;
+                        }
+                        Err(e) => {
+                            summary.failed += 1;
+                            warn!("Failed to submit {}: {}", link, e);
+                            failed.push(link);
+                        }
+                    }
+                }
+                if failed.is_empty() {
+                    return None;
+                }
+                errors::report_as(
+                    ErrorCategory::Aria2,
+                    &format!("{} 中的链接提交失败", name),
+                    format!("{} 个链接未提交", failed.len()),
+                );
+                Some(failed.join("\n") + "\n")
+            }
+        }
+    }
+
+    /// 移动处理过的文件，失败的链接写入 `failed` 目录
+    ///
+    /// # Arguments
+    /// * `path` - 处理过的文件
+    /// * `failed` - None 表示全部成功；种子文件失败时为空字符串，整个文件移动到 `failed`
+    fn archive(&self, path: &Path, failed: Option<String>) -> Result<()> {
+        let subdir = if failed.is_some() { FAILED_DIR } else { SUBMITTED_DIR };
+        let target_dir = self.directory.join(subdir);
+        std::fs::create_dir_all(&target_dir)
+            .with_context(|| format!("Failed to create {:?}", target_dir))?;
+        let target = unique_target(&target_dir, path);
+
+        match failed {
+            Some(contents) if !contents.is_empty() => {
+                std::fs::write(&target, contents).with_context(|| format!("Failed to write {:?}", target))?;
+                std::fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+            _ => {
+                std::fs::rename(path, &target)
+                    .with_context(|| format!("Failed to move {:?} to {:?}", path, target))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 定期扫描目录，收到退出通知后返回
+    ///
+    /// # Arguments
+    /// * `shutdown` - 退出通知
+    /// * `on_scan` - 每次提交了文件后调用，可用于在界面中提示
+    pub async fn run(
+        self: Arc<Self>,
+        mut shutdown: broadcast::Receiver<()>,
+        on_scan: impl Fn(&WatchScanSummary) + Send + 'static,
+    ) {
+        info!("Watching {:?} for download links", self.directory);
+        loop {
+            match self.scan_once().await {
+                Ok(summary) if summary.files > 0 => on_scan(&summary),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to scan watch folder: {:#}", e),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.poll_interval) => {}
+                _ = shutdown.recv() => break,
+            }
+        }
+        info!("Watch folder stopped");
+    }
+}
+
+/// `dir` 中与 `path` 同名的文件已存在时，在文件名前加时间戳
+fn unique_target(dir: &Path, path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let target = dir.join(&name);
+    if !target.exists() {
+        return target;
+    }
+    dir.join(format!("{}-{}", chrono::Local::now().format("%Y%m%d%H%M%S%3f"), name))
+}
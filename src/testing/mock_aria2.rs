@@ -0,0 +1,221 @@
+//! 模拟 Aria2 - 在本地端口上响应 JSON-RPC 请求
+//!
+//! 不需要安装 aria2c，用于测试下载管理器和 Aria2 客户端。
+//! 记录收到的每个请求，可以为指定方法预设错误或返回值
+
+use crate::models::config::Aria2Config;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// 收到的 RPC 请求
+#[derive(Debug, Clone)]
+pub struct RpcCall {
+    pub method: String,
+    pub params: Vec<Value>,
+}
+
+/// 预设的响应
+enum ScriptedReply {
+    Result(Value),
+    Error { code: i64, message: String },
+}
+
+#[derive(Default)]
+struct MockState {
+    calls: Mutex<Vec<RpcCall>>,
+    replies: Mutex<HashMap<String, VecDeque<ScriptedReply>>>,
+    next_gid: AtomicU64,
+}
+
+/// 模拟的 Aria2 RPC 服务，丢弃时停止
+pub struct MockAria2Server {
+    port: u16,
+    state: Arc<MockState>,
+    task: JoinHandle<()>,
+}
+
+impl MockAria2Server {
+    /// 在随机端口上启动，需要在 tokio 运行时中调用
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Failed to bind mock Aria2 server")?;
+        let port = listener.local_addr()?.port();
+        let state = Arc::new(MockState::default());
+
+        let task = tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, &state).await {
+                            debug!("Mock Aria2 connection failed: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+
+        Ok(Self { port, state, task })
+    }
+
+    /// 监听的端口
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// 连接到该服务的 Aria2 配置
+    pub fn config(&self) -> Aria2Config {
+        Aria2Config {
+            enabled: true,
+            rpc_host: "127.0.0.1".to_string(),
+            rpc_port: self.port,
+            rpc_secret: None,
+            ..Aria2Config::default()
+        }
+    }
+
+    /// 已收到的请求，按接收顺序
+    pub fn calls(&self) -> Vec<RpcCall> {
+        self.state.calls.lock().unwrap().clone()
+    }
+
+    /// 已收到的指定方法的请求
+    pub fn calls_to(&self, method: &str) -> Vec<RpcCall> {
+        self.calls().into_iter().filter(|call| call.method == method).collect()
+    }
+
+    /// 让 `method` 的下一次调用返回指定结果，多次调用时按顺序依次返回
+    pub fn reply_next(&self, method: &str, result: Value) {
+        self.push_reply(method, ScriptedReply::Result(result));
+    }
+
+    /// 让 `method` 的下一次调用返回 RPC 错误
+    pub fn fail_next(&self, method: &str, code: i64, message: &str) {
+        self.push_reply(
+            method,
+            ScriptedReply::Error {
+                code,
+                message: message.to_string(),
+            },
+        );
+    }
+
+    fn push_reply(&self, method: &str, reply: ScriptedReply) {
+        self.state
+            .replies
+            .lock()
+            .unwrap()
+            .entry(method.to_string())
+            .or_default()
+            .push_back(reply);
+    }
+}
+
+impl Drop for MockAria2Server {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 读取一个 HTTP 请求并返回 JSON-RPC 响应，响应后关闭连接
+async fn handle_connection(mut stream: TcpStream, state: &MockState) -> Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (header_end, content_length) = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            anyhow::bail!("Connection closed before request headers");
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&buffer[..position]).to_ascii_lowercase();
+            let content_length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            break (position + 4, content_length);
+        }
+    };
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request: Value = serde_json::from_slice(&buffer[header_end..]).context("Invalid JSON-RPC request")?;
+    let body = respond(&request, state).to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// 生成 JSON-RPC 响应：优先使用预设的响应，否则按方法返回默认结果
+fn respond(request: &Value, state: &MockState) -> Value {
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = request["params"].as_array().cloned().unwrap_or_default();
+    let id = request["id"].clone();
+    state.calls.lock().unwrap().push(RpcCall {
+        method: method.clone(),
+        params: params.clone(),
+    });
+
+    let scripted = state
+        .replies
+        .lock()
+        .unwrap()
+        .get_mut(&method)
+        .and_then(VecDeque::pop_front);
+    let result = match scripted {
+        Some(ScriptedReply::Error { code, message }) => {
+            return json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } });
+        }
+        Some(ScriptedReply::Result(result)) => result,
+        None => default_result(&method, &params, state),
+    };
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// 没有预设响应时各方法的结果
+fn default_result(method: &str, params: &[Value], state: &MockState) -> Value {
+    match method {
+        "aria2.getVersion" => json!({ "version": "1.37.0-mock", "enabledFeatures": [] }),
+        "aria2.addUri" | "aria2.addTorrent" => {
+            let gid = state.next_gid.fetch_add(1, Ordering::Relaxed) + 1;
+            json!(format!("{:016x}", gid))
+        }
+        "aria2.tellStatus" => json!({
+            "gid": params.first().cloned().unwrap_or_default(),
+            "status": "active",
+            "totalLength": "0",
+            "completedLength": "0",
+            "downloadSpeed": "0",
+        }),
+        "aria2.getGlobalStat" => json!({
+            "downloadSpeed": "0",
+            "uploadSpeed": "0",
+            "numActive": "0",
+            "numWaiting": "0",
+            "numStopped": "0",
+        }),
+        "aria2.getFiles" => json!([]),
+        "aria2.getSessionInfo" => json!({ "sessionId": "mock-session" }),
+        _ => json!("OK"),
+    }
+}
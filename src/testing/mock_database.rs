@@ -0,0 +1,251 @@
+//! 内存数据库 - 用于测试的 `Database` 实现
+//!
+//! 记录保存在 `Vec` 中，按名称和路径做不区分大小写的子串匹配。
+//! 可以为指定方法预设失败，测试错误处理流程；每次调用都会记录方法名，便于断言调用顺序
+
+use crate::models::database::{Database, FileRecord, RenameEntry, SearchOptions};
+use crate::services::database_manager::SharedDatabase;
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// 内存中的数据库
+#[derive(Default)]
+pub struct MockDatabase {
+    records: Mutex<Vec<FileRecord>>,
+    failures: Mutex<HashMap<String, VecDeque<String>>>, // 方法名 -> 依次返回的错误信息
+    calls: Mutex<Vec<String>>,
+    read_only: bool,
+}
+
+impl MockDatabase {
+    /// 创建包含指定记录的数据库，`id` 为 0 的记录按顺序分配 ID
+    pub fn new(records: Vec<FileRecord>) -> Self {
+        let database = Self::default();
+        database.push_records(records);
+        database
+    }
+
+    /// 创建只读数据库，写入方法使用 `Database` 的默认实现（返回错误）
+    pub fn read_only(records: Vec<FileRecord>) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(records)
+        }
+    }
+
+    /// 包装为 `SharedDatabase`，可以交给 `DatabaseManager::with_database`
+    pub fn into_shared(self) -> SharedDatabase {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// 让 `method` 的下一次调用返回错误，多次调用时按顺序依次返回
+    ///
+    /// # Arguments
+    /// * `method` - `Database` 的方法名，例如 `search_files_paged`
+    /// * `message` - 错误信息
+    pub fn fail_next(&self, method: &str, message: &str) {
+        self.failures
+            .lock()
+            .unwrap()
+            .entry(method.to_string())
+            .or_default()
+            .push_back(message.to_string());
+    }
+
+    /// 已调用的方法名，按调用顺序
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// 当前的全部记录
+    pub fn records(&self) -> Vec<FileRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// 记录调用，有预设的失败时返回错误
+    fn enter(&self, method: &str) -> Result<()> {
+        self.calls.lock().unwrap().push(method.to_string());
+        let failure = self
+            .failures
+            .lock()
+            .unwrap()
+            .get_mut(method)
+            .and_then(VecDeque::pop_front);
+        match failure {
+            Some(message) => anyhow::bail!("{}", message),
+            None => Ok(()),
+        }
+    }
+
+    /// 添加记录并分配 ID
+    fn push_records(&self, records: Vec<FileRecord>) -> usize {
+        let mut stored = self.records.lock().unwrap();
+        let mut next_id = stored.iter().map(|record| record.id).max().unwrap_or(0) + 1;
+        let count = records.len();
+        for mut record in records {
+            if record.id == 0 {
+                record.id = next_id;
+            }
+            next_id = next_id.max(record.id + 1);
+            stored.push(record);
+        }
+        count
+    }
+
+    /// 按关键词、搜索范围和类型过滤
+    fn matching(&self, query: &str, options: &SearchOptions) -> Vec<FileRecord> {
+        let query = query.to_lowercase();
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| {
+                record.name.to_lowercase().contains(&query) || record.path.to_lowercase().contains(&query)
+            })
+            .filter(|record| options.matches(record))
+            .cloned()
+            .collect()
+    }
+
+    /// 只读数据库拒绝写入
+    fn ensure_writable(&self, method: &str) -> Result<()> {
+        if self.read_only {
+            anyhow::bail!("Mock database is read-only: {}", method);
+        }
+        Ok(())
+    }
+}
+
+impl Database for MockDatabase {
+    fn search_files(&self, query: &str) -> Result<Vec<FileRecord>> {
+        self.enter("search_files")?;
+        Ok(self.matching(query, &SearchOptions::default()))
+    }
+
+    fn find_file(&self, id: i64) -> Result<Option<FileRecord>> {
+        self.enter("find_file")?;
+        Ok(self.records.lock().unwrap().iter().find(|record| record.id == id).cloned())
+    }
+
+    fn count_files(&self, query: &str, options: &SearchOptions) -> Result<usize> {
+        self.enter("count_files")?;
+        Ok(self.matching(query, options).len())
+    }
+
+    fn search_files_paged(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FileRecord>> {
+        self.enter("search_files_paged")?;
+        let mut records = self.matching(query, options);
+        if let Some(sort) = options.sort {
+            sort.sort_records(&mut records);
+        }
+        Ok(records.into_iter().skip(offset).take(limit).collect())
+    }
+
+    fn is_writable(&self) -> bool {
+        !self.read_only
+    }
+
+    fn insert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        self.enter("insert_files")?;
+        self.ensure_writable("insert_files")?;
+        Ok(self.push_records(records.to_vec()))
+    }
+
+    fn delete_file(&self, id: i64) -> Result<()> {
+        self.enter("delete_file")?;
+        self.ensure_writable("delete_file")?;
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|record| record.id != id);
+        if records.len() == before {
+            anyhow::bail!("File not found in index (id: {})", id);
+        }
+        Ok(())
+    }
+
+    fn etags_under(&self, prefix: &str) -> Result<HashMap<String, String>> {
+        self.enter("etags_under")?;
+        Ok(self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.path.starts_with(prefix))
+            .map(|record| (record.path.clone(), record.etag.clone()))
+            .collect())
+    }
+
+    fn upsert_files(&self, records: &[FileRecord]) -> Result<usize> {
+        self.enter("upsert_files")?;
+        self.ensure_writable("upsert_files")?;
+        let mut inserted = Vec::new();
+        {
+            let mut stored = self.records.lock().unwrap();
+            for record in records {
+                match stored.iter_mut().find(|stored| stored.path == record.path) {
+                    Some(existing) => {
+                        *existing = FileRecord {
+                            id: existing.id,
+                            ..record.clone()
+                        }
+                    }
+                    None => inserted.push(FileRecord { id: 0, ..record.clone() }),
+                }
+            }
+        }
+        self.push_records(inserted);
+        Ok(records.len())
+    }
+
+    fn delete_paths(&self, paths: &[String]) -> Result<usize> {
+        self.enter("delete_paths")?;
+        self.ensure_writable("delete_paths")?;
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|record| !paths.contains(&record.path));
+        Ok(before - records.len())
+    }
+
+    fn delete_files(&self, ids: &[i64]) -> Result<usize> {
+        self.enter("delete_files")?;
+        self.ensure_writable("delete_files")?;
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|record| !ids.contains(&record.id));
+        Ok(before - records.len())
+    }
+
+    fn restore_files(&self, records: &[FileRecord], _verified: &HashMap<String, bool>) -> Result<Vec<i64>> {
+        self.enter("restore_files")?;
+        self.ensure_writable("restore_files")?;
+        let first_new = self.records.lock().unwrap().len();
+        self.push_records(records.to_vec());
+        Ok(self.records.lock().unwrap()[first_new..].iter().map(|record| record.id).collect())
+    }
+
+    fn rename_files(&self, renames: &[RenameEntry]) -> Result<usize> {
+        self.enter("rename_files")?;
+        self.ensure_writable("rename_files")?;
+        let mut records = self.records.lock().unwrap();
+        let mut renamed = 0;
+        for entry in renames {
+            if let Some(record) = records.iter_mut().find(|record| record.id == entry.id) {
+                record.name = entry.new_name.clone();
+                record.path = entry.new_path.clone();
+                renamed += 1;
+            }
+        }
+        Ok(renamed)
+    }
+
+    fn init_database(&self) -> Result<()> {
+        self.enter("init_database")
+    }
+}
@@ -2,6 +2,10 @@
 //! 
 //! 包含项目中使用的各种工具函数
 
+use crate::models::config::SizeUnits;
+use chrono::{Local, TimeZone};
+use std::fmt;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::debug;
 
@@ -22,35 +26,113 @@ pub fn get_timestamp() -> u64 {
     timestamp
 }
 
-/// 格式化文件大小为人类可读格式
-/// 
+/// 拆分为数值和单位的文件大小，界面中分开显示以便按单位对齐
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileSize {
+    pub value: f64,
+    pub unit: &'static str,
+}
+
+impl FileSize {
+    /// 数值部分，字节数显示为整数，其余保留两位小数
+    ///
+    /// # Arguments
+    /// * `decimal_separator` - 小数点，例如德语区域为 `,`
+    pub fn number(&self, decimal_separator: char) -> String {
+        if self.unit == "B" {
+            return format!("{}", self.value as u64);
+        }
+        let number = format!("{:.2}", self.value);
+        if decimal_separator == '.' {
+            number
+        } else {
+            number.replace('.', &decimal_separator.to_string())
+        }
+    }
+}
+
+impl fmt::Display for FileSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.number('.'), self.unit)
+    }
+}
+
+/// 将文件大小转换为合适的单位
+///
 /// # Arguments
 /// * `size` - 文件大小（字节）
-/// 
-/// # Returns
-/// * `String` - 格式化后的大小字符串（如 "1.50 MB"）
-pub fn format_file_size(size: i64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size = size as f64;
+/// * `units` - 单位制：二进制（KB，1024）、IEC（KiB，1024）或十进制（kB，1000）
+pub fn split_file_size(size: u64, units: SizeUnits) -> FileSize {
+    let (base, names): (f64, &[&'static str]) = match units {
+        SizeUnits::Binary => (1024.0, &["B", "KB", "MB", "GB", "TB", "PB"]),
+        SizeUnits::Iec => (1024.0, &["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        SizeUnits::Decimal => (1000.0, &["B", "kB", "MB", "GB", "TB", "PB"]),
+    };
+    let mut value = size as f64;
     let mut unit_index = 0;
 
     // 转换到合适的单位
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
+    while value >= base && unit_index < names.len() - 1 {
+        value /= base;
         unit_index += 1;
     }
 
-    // 格式化输出
-    let result = if unit_index == 0 {
-        format!("{} {}", size as u64, UNITS[unit_index])
-    } else {
-        format!("{:.2} {}", size, UNITS[unit_index])
-    };
-    
+    FileSize {
+        value,
+        unit: names[unit_index],
+    }
+}
+
+/// 格式化文件大小为人类可读格式（二进制单位）
+/// 
+/// # Arguments
+/// * `size` - 文件大小（字节）
+/// 
+/// # Returns
+/// * `String` - 格式化后的大小字符串（如 "1.50 MB"）
+pub fn format_file_size(size: u64) -> String {
+    let result = split_file_size(size, SizeUnits::Binary).to_string();
     debug!("Formatted file size: {} bytes -> {}", size, result);
     result
 }
 
+/// 格式化数量，添加千位分隔符
+/// 
+/// # Arguments
+/// * `count` - 数量
+/// 
+/// # Returns
+/// * `String` - 格式化后的数量（如 "1,234"）
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(ch);
+    }
+    result
+}
+
+/// 将 Unix 时间戳格式化为本地时区的日期时间
+/// 
+/// # Arguments
+/// * `timestamp` - Unix时间戳（秒）
+/// 
+/// # Returns
+/// * `String` - 格式化后的时间（如 "2024-01-15 10:30"），无效时间戳返回 "-"
+pub fn format_timestamp(timestamp: i64) -> String {
+    if timestamp <= 0 {
+        return "-".to_string();
+    }
+
+    match Local.timestamp_opt(timestamp, 0).single() {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M").to_string(),
+        None => "-".to_string(),
+    }
+}
+
 /// 检查文件是否存在
 /// 
 /// # Arguments
@@ -73,4 +155,16 @@ pub fn get_file_extension(filename: &str) -> Option<&str> {
     std::path::Path::new(filename)
         .extension()
         .and_then(|ext| ext.to_str())
-}
\ No newline at end of file
+}
+
+/// 从文件路径中取出文件名，用于秒传和下载时的文件名
+pub fn format_upload_filename<T>(filename: T) -> Option<String>
+where
+    T: AsRef<Path>,
+{
+    let path = filename.as_ref();
+
+    path.file_name()
+        .and_then(|os_str| os_str.to_str())
+        .map(|s| s.to_string())
+}
@@ -0,0 +1,195 @@
+//! 文件类型 - 根据扩展名和 MIME 类型判断文件分类
+//!
+//! 数据库中的 `file_type` 可能是小写扩展名（本地索引、网盘同步），也可能是 MIME 类型（旧数据库），
+//! 两种都能识别；都无法识别时再看文件名的扩展名。结果列表的图标和类型筛选使用同一套规则
+
+use crate::utils::common::get_file_extension;
+
+/// 文件分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileCategory {
+    Video,
+    Audio,
+    Image,
+    Document,
+    Archive,
+}
+
+impl FileCategory {
+    /// 所有分类，顺序与界面中的筛选按钮一致
+    pub const ALL: [FileCategory; 5] = [
+        FileCategory::Video,
+        FileCategory::Audio,
+        FileCategory::Image,
+        FileCategory::Document,
+        FileCategory::Archive,
+    ];
+
+    /// 该分类的扩展名（小写，不含点）
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Video => &["mp4", "mkv", "avi", "mov", "webm", "flv", "wmv", "ts", "m2ts", "rmvb", "m4v", "mpg", "mpeg"],
+            Self::Audio => &["mp3", "flac", "wav", "aac", "ogg", "m4a", "opus", "wma", "ape"],
+            Self::Image => &["jpg", "jpeg", "png", "gif", "webp", "bmp", "svg", "heic", "tiff"],
+            Self::Document => &[
+                "pdf", "doc", "docx", "txt", "md", "epub", "mobi", "ppt", "pptx", "xls", "xlsx", "csv", "odt", "rtf",
+            ],
+            Self::Archive => &["zip", "rar", "7z", "tar", "gz", "bz2", "xz", "zst", "iso"],
+        }
+    }
+
+    /// 该分类的 MIME 类型前缀
+    pub fn mime_prefixes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Video => &["video/"],
+            Self::Audio => &["audio/"],
+            Self::Image => &["image/"],
+            Self::Document => &[
+                "text/",
+                "application/pdf",
+                "application/msword",
+                "application/epub",
+                "application/vnd.openxmlformats",
+                "application/vnd.ms-",
+                "application/vnd.oasis.opendocument",
+            ],
+            Self::Archive => &[
+                "application/zip",
+                "application/gzip",
+                "application/x-7z",
+                "application/x-rar",
+                "application/vnd.rar",
+                "application/x-tar",
+                "application/x-bzip2",
+                "application/x-xz",
+            ],
+        }
+    }
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Video => "视频",
+            Self::Audio => "音频",
+            Self::Image => "图片",
+            Self::Document => "文档",
+            Self::Archive => "压缩包",
+        }
+    }
+
+    /// 下载时按类型存放的子目录名
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Video => "Videos",
+            Self::Audio => "Music",
+            Self::Image => "Pictures",
+            Self::Document => "Documents",
+            Self::Archive => "Archives",
+        }
+    }
+
+    /// 结果列表中的图标
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Video => "🎬",
+            Self::Audio => "🎵",
+            Self::Image => "🖼",
+            Self::Document => "📄",
+            Self::Archive => "📦",
+        }
+    }
+
+    /// 在 `ALL` 中的位置，对应界面筛选位掩码中的一位
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|category| category == self).unwrap_or_default()
+    }
+
+    /// 界面中的筛选位掩码转换为分类列表
+    pub fn from_mask(mask: i32) -> Vec<FileCategory> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|category| mask & (1 << category.index()) != 0)
+            .collect()
+    }
+
+    /// 文件是否属于该分类：`file_type` 是该分类的扩展名或 MIME 类型，或者文件名有该分类的扩展名
+    ///
+    /// 与 SQLite 中类型筛选的 WHERE 条件一致
+    ///
+    /// # Arguments
+    /// * `file_type` - 数据库中的 `file_type`，扩展名或 MIME 类型
+    /// * `name` - 文件名
+    pub fn matches(&self, file_type: &str, name: &str) -> bool {
+        let file_type = file_type.trim().to_lowercase();
+        let name_ext = get_file_extension(name).unwrap_or_default().to_lowercase();
+        self.extensions().contains(&file_type.as_str())
+            || self.mime_prefixes().iter().any(|prefix| file_type.starts_with(prefix))
+            || self.extensions().contains(&name_ext.as_str())
+    }
+}
+
+/// 判断文件分类，依次根据 `file_type` 的扩展名、MIME 类型和文件名的扩展名
+pub fn category_of(file_type: &str, name: &str) -> Option<FileCategory> {
+    let file_type = file_type.trim().to_lowercase();
+    let by_extension = |ext: &str| {
+        FileCategory::ALL
+            .iter()
+            .copied()
+            .find(|category| category.extensions().contains(&ext))
+    };
+
+    by_extension(file_type.trim_start_matches('.'))
+        .or_else(|| {
+            FileCategory::ALL.iter().copied().find(|category| {
+                category
+                    .mime_prefixes()
+                    .iter()
+                    .any(|prefix| file_type.starts_with(prefix))
+            })
+        })
+        .or_else(|| by_extension(&get_file_extension(name)?.to_lowercase()))
+}
+
+/// 根据文件名推断 MIME 类型，未知类型返回 `application/octet-stream`
+pub fn mime_type(name: &str) -> &'static str {
+    let ext = get_file_extension(name).unwrap_or_default().to_lowercase();
+    match ext.as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "mov" => "video/quicktime",
+        "flv" => "video/x-flv",
+        "wmv" => "video/x-ms-wmv",
+        "ts" | "m2ts" => "video/mp2t",
+        "mpg" | "mpeg" => "video/mpeg",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "aac" => "audio/aac",
+        "ogg" | "opus" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "epub" => "application/epub+zip",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "zip" => "application/zip",
+        "rar" => "application/vnd.rar",
+        "7z" => "application/x-7z-compressed",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
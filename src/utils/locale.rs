@@ -0,0 +1,132 @@
+//! 区域格式 - 按语言区域格式化文件大小和日期
+//!
+//! 区域设置在启动时根据配置和系统语言确定一次，界面模型转换、导出和命令行输出都通过这里格式化，
+//! 保证同一个值在各处显示一致
+
+use crate::models::config::{LocaleConfig, SizeUnits};
+use crate::utils::common::split_file_size;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, TimeZone};
+use std::sync::OnceLock;
+
+/// 区域格式设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    pub size_units: SizeUnits,
+    pub decimal_separator: char,
+    pub date_format: String, // chrono 的格式字符串
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            size_units: SizeUnits::Binary,
+            decimal_separator: '.',
+            date_format: "%Y-%m-%d %H:%M".to_string(),
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// 设置全局的区域格式，只能在启动时设置一次
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// 全局的区域格式，未设置时按系统语言确定
+pub fn locale() -> &'static Locale {
+    LOCALE.get_or_init(|| Locale::from_tag(&system_language()))
+}
+
+/// 按全局区域格式格式化文件大小
+pub fn format_size(size: u64) -> String {
+    locale().format_size(size)
+}
+
+/// 按全局区域格式拆分文件大小的数值和单位
+pub fn size_parts(size: u64) -> (String, &'static str) {
+    locale().size_parts(size)
+}
+
+/// 按全局区域格式格式化 Unix 时间戳
+pub fn format_datetime(timestamp: i64) -> String {
+    locale().format_timestamp(timestamp)
+}
+
+/// 是否是有效的 chrono 日期格式字符串
+pub fn is_valid_date_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// 系统语言，依次读取 `LC_ALL`、`LC_TIME`、`LANG`，例如 `de_DE.UTF-8`
+pub fn system_language() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+impl Locale {
+    /// 按语言标签确定格式，例如 `en_US`、`de-DE`、`zh_CN.UTF-8`，未知语言使用 ISO 日期格式
+    pub fn from_tag(tag: &str) -> Self {
+        // 去掉编码部分，统一为 `语言_地区`
+        let tag = tag.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+        let language = tag.split('_').next().unwrap_or_default().to_lowercase();
+
+        let date_format = match (language.as_str(), tag.as_str()) {
+            ("en", "en_US") => "%m/%d/%Y %H:%M",
+            ("en", "en_GB" | "en_AU" | "en_NZ" | "en_IE" | "en_IN") | ("fr" | "es" | "it" | "pt", _) => "%d/%m/%Y %H:%M",
+            ("de" | "ru" | "pl" | "cs" | "fi" | "nb" | "tr", _) => "%d.%m.%Y %H:%M",
+            ("nl", _) => "%d-%m-%Y %H:%M",
+            ("ja" | "zh" | "ko", _) => "%Y/%m/%d %H:%M",
+            _ => "%Y-%m-%d %H:%M",
+        };
+        let decimal_separator = match language.as_str() {
+            "de" | "fr" | "es" | "it" | "pt" | "ru" | "pl" | "cs" | "nl" | "fi" | "nb" | "tr" => ',',
+            _ => '.',
+        };
+
+        Self {
+            decimal_separator,
+            date_format: date_format.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// 按配置确定格式，没有设置语言时使用系统语言
+    pub fn from_config(config: &LocaleConfig) -> Self {
+        let language = config.language.clone().unwrap_or_else(system_language);
+        let mut locale = Self::from_tag(&language);
+        locale.size_units = config.size_units;
+        // 无效的格式字符串在格式化时会 panic，忽略并使用语言的默认格式
+        if let Some(format) = config.date_format.as_ref().filter(|format| is_valid_date_format(format)) {
+            locale.date_format = format.clone();
+        }
+        locale
+    }
+
+    /// 格式化文件大小，例如 "1.50 MB"，德语区域为 "1,50 MB"
+    pub fn format_size(&self, size: u64) -> String {
+        let (number, unit) = self.size_parts(size);
+        format!("{} {}", number, unit)
+    }
+
+    /// 文件大小的数值和单位，例如 ("1.50", "MB")，界面中分开显示以便对齐
+    pub fn size_parts(&self, size: u64) -> (String, &'static str) {
+        let size = split_file_size(size, self.size_units);
+        (size.number(self.decimal_separator), size.unit)
+    }
+
+    /// 将 Unix 时间戳格式化为本地时区的日期时间，无效时间戳返回 "-"
+    pub fn format_timestamp(&self, timestamp: i64) -> String {
+        if timestamp <= 0 {
+            return "-".to_string();
+        }
+        match Local.timestamp_opt(timestamp, 0).single() {
+            Some(datetime) => datetime.format(&self.date_format).to_string(),
+            None => "-".to_string(),
+        }
+    }
+}
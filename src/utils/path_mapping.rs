@@ -0,0 +1,64 @@
+//! 路径映射 - 将数据库中其他系统的路径转换为本机路径
+//!
+//! 在 Windows 上生成的数据库保存的是 `D:\media\a.mkv` 这样的路径，在 Linux 上浏览时
+//! 需要按数据库配置的前缀映射替换为 `/mnt/media/a.mkv`。两种分隔符都能识别，
+//! Windows 风格的前缀不区分大小写；替换后剩余部分的分隔符与映射目标一致
+
+use crate::models::config::PathMapping;
+
+/// 是否是 Windows 风格的路径：以盘符开头或包含反斜杠
+pub fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    has_drive || path.contains('\\')
+}
+
+/// 统一为正斜杠并去掉末尾的分隔符
+fn normalize_separators(path: &str) -> String {
+    path.trim().replace('\\', "/").trim_end_matches('/').to_string()
+}
+
+/// 映射前缀与路径匹配时，返回路径中前缀之后的部分（以 `/` 开头或为空）
+fn strip_mapped_prefix<'a>(path: &'a str, from: &str, ignore_case: bool) -> Option<&'a str> {
+    if path.len() < from.len() || !path.is_char_boundary(from.len()) {
+        return None;
+    }
+    let (head, rest) = path.split_at(from.len());
+    let matched = if ignore_case { head.eq_ignore_ascii_case(from) } else { head == from };
+    // 只在路径组件边界处匹配，`D:/media` 不匹配 `D:/media2`
+    (matched && (rest.is_empty() || rest.starts_with('/'))).then_some(rest)
+}
+
+/// 按前缀映射转换路径，多条映射匹配时使用最长的前缀，没有匹配时原样返回
+///
+/// # Arguments
+/// * `path` - 数据库中的文件路径
+/// * `mappings` - 当前数据库的路径映射
+pub fn map_path(path: &str, mappings: &[PathMapping]) -> String {
+    let normalized = normalize_separators(path);
+    let best = mappings
+        .iter()
+        .filter(|mapping| !mapping.from.trim().is_empty())
+        .filter_map(|mapping| {
+            let from = normalize_separators(&mapping.from);
+            let rest = strip_mapped_prefix(&normalized, &from, is_windows_path(&mapping.from))?;
+            Some((from.len(), mapping, rest))
+        })
+        .max_by_key(|(len, _, _)| *len);
+
+    let Some((_, mapping, rest)) = best else {
+        return path.to_string();
+    };
+
+    let to = mapping.to.trim();
+    if is_windows_path(to) {
+        format!("{}{}", to.trim_end_matches(['\\', '/']), rest.replace('/', "\\"))
+    } else {
+        let to = to.trim_end_matches('/');
+        if to.is_empty() && rest.is_empty() {
+            "/".to_string()
+        } else {
+            format!("{}{}", to, rest)
+        }
+    }
+}
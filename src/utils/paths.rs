@@ -0,0 +1,242 @@
+//! 路径工具 - 统一管理配置、数据、缓存和日志目录
+//!
+//! 默认使用平台标准目录（Linux 下遵循 XDG 规范），
+//! 设置覆盖目录后所有文件都放在该目录下。
+//! 便携模式下覆盖目录为可执行文件所在目录，配置中的路径保存为相对该目录的路径，
+//! 换一台电脑、盘符变化后仍然有效
+
+use anyhow::{Context, Result};
+use directories::{ProjectDirs, UserDirs};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, warn};
+
+/// 覆盖数据根目录的环境变量
+pub const DATA_DIR_ENV: &str = "NETDISK_DB_DATA_DIR";
+
+const APP_NAME: &str = "netdisk-db";
+const JSON_CONFIG_FILE: &str = "config.json";
+const TOML_CONFIG_FILE: &str = "config.toml";
+
+/// 可执行文件旁存在该文件时自动启用便携模式
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// 覆盖的数据根目录，只能在启动时设置一次
+static OVERRIDE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 便携模式的根目录（可执行文件所在目录）
+static PORTABLE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置数据根目录，之后所有路径都位于该目录下
+///
+/// # Arguments
+/// * `dir` - 数据根目录
+pub fn set_override_dir(dir: PathBuf) {
+    debug!("Using data directory override: {:?}", dir);
+    if OVERRIDE_DIR.set(dir).is_err() {
+        warn!("Data directory override already set");
+    }
+}
+
+/// 可执行文件所在目录
+pub fn executable_dir() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("Failed to locate executable")?;
+    exe.parent()
+        .map(Path::to_path_buf)
+        .context("Executable has no parent directory")
+}
+
+/// 可执行文件旁是否有便携模式标记文件
+pub fn portable_marker_exists() -> bool {
+    executable_dir().map_or(false, |dir| dir.join(PORTABLE_MARKER).exists())
+}
+
+/// 启用便携模式：配置、数据库、缓存、日志和下载都放在可执行文件所在目录下
+pub fn enable_portable() -> Result<()> {
+    let root = executable_dir()?;
+    set_override_dir(root.clone());
+    let _ = PORTABLE_ROOT.set(root);
+    Ok(())
+}
+
+/// 是否处于便携模式
+pub fn is_portable() -> bool {
+    PORTABLE_ROOT.get().is_some()
+}
+
+/// 将配置中的路径转换为实际路径：便携模式下相对路径相对于可执行文件所在目录
+pub fn resolve_path(path: &str) -> String {
+    match PORTABLE_ROOT.get() {
+        Some(root) => resolve_path_in(root, path),
+        None => path.to_string(),
+    }
+}
+
+/// 将实际路径转换为保存到配置中的路径：便携模式下根目录内的路径保存为相对路径
+pub fn portable_path(path: &str) -> String {
+    match PORTABLE_ROOT.get() {
+        Some(root) => relative_path_in(root, path),
+        None => path.to_string(),
+    }
+}
+
+/// 相对路径解析为 `root` 下的路径，绝对路径和空路径保持不变
+pub fn resolve_path_in(root: &Path, path: &str) -> String {
+    if path.is_empty() || Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    root.join(path).to_string_lossy().into_owned()
+}
+
+/// `root` 下的路径转换为相对路径，其他路径保持不变
+pub fn relative_path_in(root: &Path, path: &str) -> String {
+    match Path::new(path).strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// 获取覆盖的数据根目录（命令行设置优先于环境变量）
+fn override_dir() -> Option<PathBuf> {
+    OVERRIDE_DIR
+        .get()
+        .cloned()
+        .or_else(|| std::env::var_os(DATA_DIR_ENV).map(PathBuf::from))
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", APP_NAME)
+}
+
+/// 在覆盖目录或平台目录下选择路径，都不可用时退回当前目录
+fn resolve<F>(sub_dir: &str, platform_dir: F) -> PathBuf
+where
+    F: FnOnce(&ProjectDirs) -> PathBuf,
+{
+    if let Some(base) = override_dir() {
+        return base.join(sub_dir);
+    }
+
+    project_dirs()
+        .map(|dirs| platform_dir(&dirs))
+        .unwrap_or_else(|| PathBuf::from(".").join(sub_dir))
+}
+
+/// 配置目录
+pub fn config_dir() -> PathBuf {
+    resolve("config", |dirs| dirs.config_dir().to_path_buf())
+}
+
+/// 数据目录，存放数据库和活动记录
+pub fn data_dir() -> PathBuf {
+    resolve("data", |dirs| dirs.data_dir().to_path_buf())
+}
+
+/// 缓存目录
+pub fn cache_dir() -> PathBuf {
+    resolve("cache", |dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// 日志目录
+pub fn log_dir() -> PathBuf {
+    resolve("logs", |dirs| dirs.data_local_dir().join("logs"))
+}
+
+/// 默认下载目录：用户下载目录下的应用子目录
+pub fn downloads_dir() -> PathBuf {
+    if let Some(base) = override_dir() {
+        return base.join("downloads");
+    }
+
+    UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(|dir| dir.join(APP_NAME)))
+        .unwrap_or_else(|| PathBuf::from("./downloads"))
+}
+
+/// 配置文件路径：存在 config.toml 时使用 TOML，否则使用 config.json
+pub fn config_file() -> PathBuf {
+    let toml = config_dir().join(TOML_CONFIG_FILE);
+    if toml.exists() {
+        toml
+    } else {
+        config_dir().join(JSON_CONFIG_FILE)
+    }
+}
+
+/// 旧版本写在当前目录下的配置文件，用于迁移
+pub fn legacy_config_file() -> Option<PathBuf> {
+    [TOML_CONFIG_FILE, JSON_CONFIG_FILE]
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+}
+
+/// 缩略图缓存目录
+pub fn thumbnail_cache_dir() -> PathBuf {
+    cache_dir().join("thumbnails")
+}
+
+/// 媒体信息缓存数据库
+pub fn media_info_file() -> PathBuf {
+    cache_dir().join("media_info.sqlite")
+}
+
+/// 最近活动记录数据库
+pub fn activity_log_file() -> PathBuf {
+    data_dir().join("activity.sqlite")
+}
+
+/// 收藏集数据库
+pub fn collections_file() -> PathBuf {
+    data_dir().join("collections.sqlite")
+}
+
+/// 下载历史数据库
+pub fn download_history_file() -> PathBuf {
+    data_dir().join("download_history.sqlite")
+}
+
+/// 离线操作队列数据库
+pub fn pending_ops_file() -> PathBuf {
+    data_dir().join("pending_ops.sqlite")
+}
+
+/// Aria2 会话文件，保存未完成的下载任务
+pub fn aria2_session_file() -> PathBuf {
+    data_dir().join("aria2.session")
+}
+
+/// 单实例锁文件，运行中的实例一直持有
+pub fn instance_lock_file() -> PathBuf {
+    data_dir().join("instance.lock")
+}
+
+/// 运行中的实例监听的本地套接字（Unix），Windows 上使用命名管道
+pub fn instance_socket_file() -> PathBuf {
+    data_dir().join("instance.sock")
+}
+
+/// 选中项导出目录
+pub fn export_dir() -> PathBuf {
+    data_dir().join("exports")
+}
+
+/// 数据库备份目录
+pub fn backup_dir() -> PathBuf {
+    data_dir().join("backups")
+}
+
+/// 崩溃报告目录
+pub fn crash_dir() -> PathBuf {
+    data_dir().join("crashes")
+}
+
+/// 创建所有应用目录
+pub fn ensure_dirs() -> Result<()> {
+    for dir in [config_dir(), data_dir(), cache_dir(), log_dir()] {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory {:?}", dir))?;
+    }
+    Ok(())
+}
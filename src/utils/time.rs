@@ -0,0 +1,66 @@
+//! 时间工具 - 将各种形式的修改时间统一为 Unix 时间戳（秒）
+//!
+//! 外部数据库中的 modified_time 可能是秒、毫秒级时间戳，也可能是 "YYYY-MM-DD HH:MM:SS" 字符串，
+//! 导入和读取时都经过这里转换，之后只使用秒级时间戳。不带时区的日期时间按 UTC 处理，
+//! 与 SQLite 的 `datetime('now')` 一致
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// 大于该值的时间戳视为毫秒（秒级时间戳要到 5138 年才会超过）
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// 大于该值的时间戳视为微秒
+const MICROS_THRESHOLD: i64 = 100_000_000_000_000;
+
+/// 支持的日期时间字符串格式
+const DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+/// 将秒、毫秒或微秒级时间戳统一为秒，负数视为无效，返回 0
+pub fn normalize_timestamp(timestamp: i64) -> i64 {
+    if timestamp <= 0 {
+        0
+    } else if timestamp > MICROS_THRESHOLD {
+        timestamp / 1_000_000
+    } else if timestamp > MILLIS_THRESHOLD {
+        timestamp / 1_000
+    } else {
+        timestamp
+    }
+}
+
+/// 解析字符串形式的修改时间
+///
+/// 支持数字时间戳（秒、毫秒、微秒）、RFC 3339、"YYYY-MM-DD HH:MM:SS"（可带小数秒或 `T` 分隔）
+/// 以及只有日期的 "YYYY-MM-DD"
+///
+/// # Returns
+/// * `Option<i64>` - 秒级时间戳，无法识别时返回 None
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(number) = text.parse::<i64>() {
+        return Some(normalize_timestamp(number));
+    }
+    if let Ok(number) = text.parse::<f64>() {
+        return Some(normalize_timestamp(number as i64));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.timestamp());
+    }
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|datetime| datetime.and_utc().timestamp())
+}
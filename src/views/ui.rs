@@ -2,9 +2,87 @@
 //!
 //! 包含 UI 数据转换和界面相关的工具函数
 
-use crate::models::database::FileRecord;
-use slint::ModelRc;
-use tracing::debug;
+use crate::controllers::context_menu::MenuAction;
+use crate::models::config::{AppConfig, Theme};
+use crate::models::database::{
+    group_records, FileRecord, GroupBy, RecordGroup, SearchOptions, SortColumn, SortOrder,
+};
+use crate::services::activity_log::ActivityEntry;
+use crate::services::api_client::{AccountInfo, LinkError, RemoteFile};
+use crate::services::collections::{Collection, CollectionItem};
+use crate::services::database_manager::{DatabaseHandle, ResultPage};
+use crate::services::download_history::{DownloadHistoryEntry, DownloadOutcome};
+use crate::services::link_resolver::shared_link_resolver;
+use crate::services::media_info::format_duration;
+use crate::services::qr_code;
+use crate::services::self_check::SelfCheckReport;
+use crate::services::sidecar::DownloadSidecar;
+use crate::services::thumbnail::SharedThumbnailService;
+use crate::utils::common::format_count;
+use crate::utils::filetype::{category_of, FileCategory};
+use crate::utils::locale::{format_datetime, format_size, size_parts};
+use anyhow::Result;
+use slint::{ComponentHandle, LogicalPosition, LogicalSize, Model, ModelNotify, ModelRc, ModelTracker};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+/// 懒加载模型每页的记录数
+const PAGE_SIZE: usize = 200;
+/// 懒加载模型最多缓存的页数
+const MAX_CACHED_PAGES: usize = 16;
+/// 分组视图最多加载的记录数
+pub const GROUPED_RESULT_LIMIT: usize = 10_000;
+
+/// 下一个懒加载模型的编号，异步读取的结果只交给仍在显示的同一个模型
+static NEXT_LAZY_MODEL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 结果列表使用的缩略图服务（未设置时只显示文件类型图标）
+static THUMBNAIL_SERVICE: OnceLock<SharedThumbnailService> = OnceLock::new();
+
+/// 设置结果列表使用的缩略图服务，只能设置一次
+pub fn set_thumbnail_service(service: SharedThumbnailService) {
+    if THUMBNAIL_SERVICE.set(service).is_err() {
+        debug!("Thumbnail service already set");
+    }
+}
+
+/// 根据文件类型（MIME）或扩展名选择图标
+///
+/// # Arguments
+/// * `file_type` - 数据库中的文件类型，可能是 MIME 类型或扩展名
+/// * `name` - 文件名，用于在文件类型缺失时按扩展名判断
+pub fn file_icon(file_type: &str, name: &str) -> &'static str {
+    category_of(file_type, name).map_or("📁", |category| category.icon())
+}
+
+/// 加载已缓存的缩略图，未缓存时请求后台生成
+fn load_thumbnail(path: &str) -> Option<slint::Image> {
+    let service = THUMBNAIL_SERVICE.get()?;
+    match service.cached_thumbnail(path) {
+        Some(thumbnail) => slint::Image::load_from_path(&thumbnail).ok(),
+        None => {
+            service.request(path);
+            None
+        }
+    }
+}
+
+/// 二维码每个模块的像素数，界面中按像素缩放显示
+const QR_CODE_SCALE: u32 = 4;
+
+/// 将链接转换为二维码图像
+pub fn qr_code_image(url: &str) -> Result<slint::Image> {
+    let qr = qr_code::render(url, QR_CODE_SCALE)?;
+    let buffer = slint::SharedPixelBuffer::<slint::Rgba8Pixel>::clone_from_slice(&qr.pixels, qr.size, qr.size);
+    Ok(slint::Image::from_rgba8(buffer))
+}
 
 // 包含 Slint 生成的模块
 slint::include_modules!();
@@ -16,55 +94,824 @@ slint::include_modules!();
 ///
 /// # Returns
 /// * `ModelRc<FileItem>` - Slint UI 模型
-// pub fn file_records_to_model(file_records: Vec<FileRecord>) -> ModelRc<FileItem> {
-//     debug!(
-//         "Converting {} file records to UI model,FileRecord = {:?}",
-//         file_records.len(),
-//         &file_records[0]
-//     );
-
-//     let items: Vec<FileItem> = file_records
-//         .into_iter()
-//         .map(|record| FileItem {
-//             id: record.id as i32,
-//             name: record.name.into(),
-//             path: record.path.into(),
-//             size: record.size as i32,
-//             modified_time: record.modified_time.into(),
-//             file_type: record.file_type.into(),
-//         })
-//         .collect();
-
-//     ModelRc::new(slint::VecModel::from(items))
-// }
 pub fn file_records_to_model(file_records: Vec<FileRecord>) -> ModelRc<FileItem> {
     debug!("Converting {} file records to UI model", file_records.len());
 
-    let items: Vec<FileItem> = file_records
-        .into_iter()
-        .map(|record| {
-            debug!(
-                "Processing record: name=[{}], path=[{}], size=[{}], etag=[{}]",
-                record.name, record.path, record.size, record.etag
-            );
-
-            let final_size = record.size.to_string().into();
-
-            FileItem {
-                id: record.id as i32,
-                path: record.path.into(),
-                size: final_size,
-                etag: record.etag.into(),
-                modified_time: record.modified_time as i32,
-                file_type: record.file_type.into(),
-                name: record.name.into(),
+    let items: Vec<FileItem> = file_records.into_iter().map(file_record_to_item).collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将单条文件记录转换为 UI 数据项
+pub fn file_record_to_item(record: FileRecord) -> FileItem {
+    debug!(
+        "Processing record: name=[{}], path=[{}], size=[{}], etag=[{}]",
+        record.name, record.path, record.size, record.etag
+    );
+
+    // 显示用的格式化值，原始值保留在隐藏字段中供排序和下载使用
+    let display_size = format_size(record.size).into();
+    let (size_value, size_unit) = size_parts(record.size);
+    let modified_text = format_datetime(record.modified_time).into();
+    let icon = file_icon(&record.file_type, &record.name).into();
+    let thumbnail = load_thumbnail(&record.path);
+
+    let remote_missing = shared_link_resolver().is_missing(&record.etag, record.size);
+
+    FileItem {
+        icon,
+        remote_missing,
+        checksum_mismatch: false,
+        has_thumbnail: thumbnail.is_some(),
+        thumbnail: thumbnail.unwrap_or_default(),
+        id: record.id as i32,
+        path: record.path.into(),
+        size: display_size,
+        size_value: size_value.into(),
+        size_unit: size_unit.into(),
+        size_bytes: record.size.to_string().into(),
+        etag: record.etag.into(),
+        modified_time: record.modified_time as i32,
+        modified_text,
+        file_type: record.file_type.into(),
+        name: record.name.into(),
+        selected: false,
+        is_group_header: false,
+        group_expanded: false,
+        group_count: 0,
+    }
+}
+
+/// 获取 UI 数据项的文件大小（字节）
+///
+/// Slint 的 int 只有 32 位，`FileItem` 中的大小以字符串保存，统一在这里解析
+pub fn file_item_size(item: &FileItem) -> Result<u64, LinkError> {
+    let text = item.size_bytes.trim();
+    text.parse::<u64>()
+        .map_err(|_| LinkError::InvalidSize(format!("{} ({})", text, item.path)))
+}
+
+/// 将 UI 数据项转换回文件记录，大小无效时返回错误
+pub fn try_file_item_to_record(item: &FileItem) -> Result<FileRecord, LinkError> {
+    Ok(FileRecord {
+        id: item.id as i64,
+        path: item.path.to_string(),
+        size: file_item_size(item)?,
+        etag: item.etag.to_string(),
+        modified_time: item.modified_time as i64,
+        file_type: item.file_type.to_string(),
+        name: item.name.to_string(),
+    })
+}
+
+/// 将 UI 数据项转换回文件记录，大小无效时记为 0（用于导出和活动记录）
+pub fn file_item_to_record(item: &FileItem) -> FileRecord {
+    try_file_item_to_record(item).unwrap_or_else(|_| FileRecord {
+        id: item.id as i64,
+        path: item.path.to_string(),
+        size: 0,
+        etag: item.etag.to_string(),
+        modified_time: item.modified_time as i64,
+        file_type: item.file_type.to_string(),
+        name: item.name.to_string(),
+    })
+}
+
+/// 将多个 UI 数据项转换为文件记录，跳过大小无效的数据项
+///
+/// # Returns
+/// * `(Vec<FileRecord>, Vec<LinkError>)` - 有效的文件记录和无效数据项的错误
+pub fn file_items_to_records(items: &[FileItem]) -> (Vec<FileRecord>, Vec<LinkError>) {
+    let mut records = Vec::with_capacity(items.len());
+    let mut errors = Vec::new();
+    for item in items {
+        match try_file_item_to_record(item) {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                error!("Invalid file item: {}", e);
+                errors.push(e);
             }
+        }
+    }
+    (records, errors)
+}
+
+/// 将活动记录转换为"最近"视图的 UI 模型
+pub fn activity_entries_to_model(entries: Vec<ActivityEntry>) -> ModelRc<RecentItem> {
+    let items: Vec<RecentItem> = entries
+        .into_iter()
+        .map(|entry| RecentItem {
+            action: entry.kind.label().into(),
+            time_text: format_datetime(entry.timestamp).into(),
+            database: entry.database.into(),
+            item: file_record_to_item(entry.record),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将下载目录中找到的下载元数据转换为下载面板的 UI 模型
+pub fn download_entries_to_model(entries: Vec<(PathBuf, DownloadSidecar)>) -> ModelRc<DownloadEntry> {
+    let items: Vec<DownloadEntry> = entries
+        .into_iter()
+        .map(|(target, sidecar)| DownloadEntry {
+            name: target.file_name().unwrap_or_default().to_string_lossy().to_string().into(),
+            exists: target.is_file(),
+            local_path: target.to_string_lossy().to_string().into(),
+            source_path: sidecar.path.into(),
+            database: sidecar.database.into(),
+            time_text: format_datetime(sidecar.created_at).into(),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将自检结果转换为自检对话框的 UI 模型
+pub fn self_check_to_model(report: &SelfCheckReport) -> ModelRc<SelfCheckItem> {
+    let items: Vec<SelfCheckItem> = report
+        .items
+        .iter()
+        .map(|item| SelfCheckItem {
+            name: item.name.clone().into(),
+            status: item.status.as_index(),
+            detail: item.detail.clone().into(),
+            hint: item.hint.clone().unwrap_or_default().into(),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将下载历史转换为下载历史视图的 UI 模型
+pub fn download_history_to_model(entries: Vec<DownloadHistoryEntry>) -> ModelRc<DownloadHistoryItem> {
+    let items: Vec<DownloadHistoryItem> = entries
+        .into_iter()
+        .map(|entry| DownloadHistoryItem {
+            id: entry.id as i32,
+            status: entry.outcome.label().into(),
+            failed: entry.outcome == DownloadOutcome::Failed,
+            error: entry.error.clone().unwrap_or_default().into(),
+            time_text: format_datetime(entry.finished_at).into(),
+            duration_text: format_duration(entry.duration_secs() as f64).into(),
+            database: entry.database.clone().into(),
+            local_path: entry.save_path.clone().into(),
+            item: file_record_to_item(entry.record),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将收藏集列表转换为侧栏的 UI 模型
+pub fn collections_to_model(collections: Vec<Collection>) -> ModelRc<CollectionEntry> {
+    let items: Vec<CollectionEntry> = collections
+        .into_iter()
+        .map(|collection| CollectionEntry {
+            id: collection.id as i32,
+            name: collection.name.into(),
+            count: collection.item_count as i32,
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将收藏集中的文件转换为收藏集视图的 UI 模型
+pub fn collection_items_to_model(items: Vec<CollectionItem>) -> ModelRc<CollectionFile> {
+    let items: Vec<CollectionFile> = items
+        .into_iter()
+        .map(|entry| CollectionFile {
+            database: entry.database.into(),
+            item: file_record_to_item(entry.record),
         })
         .collect();
 
     ModelRc::new(slint::VecModel::from(items))
 }
 
+/// 将网盘文件夹转换为文件夹选择对话框的 UI 模型
+pub fn remote_folders_to_model(folders: Vec<RemoteFile>) -> ModelRc<RemoteFolder> {
+    let items: Vec<RemoteFolder> = folders
+        .into_iter()
+        .map(|folder| RemoteFolder {
+            id: folder.file_id.to_string().into(),
+            name: folder.filename.into(),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将对选中项可用的自定义菜单项转换为 UI 模型
+pub fn custom_menu_actions_to_model(actions: Vec<(usize, &MenuAction)>) -> ModelRc<CustomMenuAction> {
+    let items: Vec<CustomMenuAction> = actions
+        .into_iter()
+        .map(|(index, action)| CustomMenuAction {
+            index: index as i32,
+            label: action.label().into(),
+        })
+        .collect();
+
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 网盘文件夹选择对话框的浏览位置
+#[derive(Debug, Clone, Default)]
+pub struct RemoteFolderBrowser {
+    stack: Vec<(i64, String)>, // 从根目录到当前文件夹经过的文件夹 ID 和名称
+}
+
+impl RemoteFolderBrowser {
+    /// 当前文件夹 ID，根目录为 0
+    pub fn current_id(&self) -> i64 {
+        self.stack.last().map(|(id, _)| *id).unwrap_or(0)
+    }
+
+    /// 当前文件夹路径，如 `/电影/2024`
+    pub fn path(&self) -> String {
+        if self.stack.is_empty() {
+            return "/".to_string();
+        }
+        self.stack.iter().map(|(_, name)| format!("/{}", name)).collect()
+    }
+
+    /// 进入子文件夹
+    pub fn enter(&mut self, id: i64, name: &str) {
+        self.stack.push((id, name.to_string()));
+    }
+
+    /// 返回上一级文件夹，已在根目录时返回 false
+    pub fn up(&mut self) -> bool {
+        self.stack.pop().is_some()
+    }
+
+    /// 回到根目录
+    pub fn reset(&mut self) {
+        self.stack.clear();
+    }
+}
+
+/// 懒加载的搜索结果模型
+///
+/// 只在 UI 请求某一行时按页通过数据库管理线程读取，并缓存有限数量的页，
+/// 配合 ListView 的虚拟化渲染处理数万条结果；页面读取完成前对应的行显示为空白
+pub struct LazyFileModel {
+    database: DatabaseHandle,
+    ui: slint::Weak<AppWindow>,
+    id: u64,
+    query: String,
+    options: SearchOptions,
+    total: usize,
+    pages: RefCell<HashMap<usize, Vec<FileItem>>>,
+    loading: RefCell<HashSet<usize>>,
+    selection: RefCell<BTreeSet<usize>>,
+    anchor: Cell<Option<usize>>,
+    notify: ModelNotify,
+}
+
+impl LazyFileModel {
+    /// 创建懒加载模型，匹配总数由调用方通过 `DatabaseHandle::count_files_then` 查询
+    ///
+    /// # Arguments
+    /// * `database` - 数据库管理线程句柄
+    /// * `ui` - 显示该模型的窗口，页面读取完成后从窗口中找回模型
+    /// * `query` - 搜索关键词
+    /// * `options` - 搜索选项（排序等）
+    /// * `total` - 匹配的记录数
+    pub fn new(
+        database: DatabaseHandle,
+        ui: slint::Weak<AppWindow>,
+        query: &str,
+        options: SearchOptions,
+        total: usize,
+    ) -> Self {
+        debug!("Lazy model created for query '{}' with {} rows", query, total);
+
+        Self {
+            database,
+            ui,
+            id: NEXT_LAZY_MODEL_ID.fetch_add(1, Ordering::Relaxed),
+            query: query.to_string(),
+            options,
+            total,
+            pages: RefCell::new(HashMap::new()),
+            loading: RefCell::new(HashSet::new()),
+            selection: RefCell::new(BTreeSet::new()),
+            anchor: Cell::new(None),
+            notify: ModelNotify::default(),
+        }
+    }
+
+    /// 请求读取指定页，结果回到事件循环后写入缓存
+    fn load_page(&self, page: usize) {
+        if self.pages.borrow().contains_key(&page) || !self.loading.borrow_mut().insert(page) {
+            return;
+        }
+
+        let ui = self.ui.clone();
+        let id = self.id;
+        self.database.search_page_then(
+            &self.query,
+            self.options.clone(),
+            page * PAGE_SIZE,
+            PAGE_SIZE,
+            move |result| {
+                let _ = slint::invoke_from_event_loop(move || {
+                    if let Some(ui) = ui.upgrade() {
+                        with_lazy_model(&ui, id, |lazy| lazy.insert_page(page, result));
+                    }
+                });
+            },
+        );
+    }
+
+    /// 缓存读取完成的页并刷新对应的行，超出缓存上限时淘汰距离最远且没有选中行的页
+    fn insert_page(&self, page: usize, result: Result<ResultPage>) {
+        self.loading.borrow_mut().remove(&page);
+        let ResultPage { records, verified } = result.unwrap_or_else(|e| {
+            error!("Failed to load result page {}: {}", page, e);
+            ResultPage::default()
+        });
+
+        {
+            let mut pages = self.pages.borrow_mut();
+            if pages.len() >= MAX_CACHED_PAGES {
+                let selection = self.selection.borrow();
+                let farthest = pages
+                    .keys()
+                    .copied()
+                    .filter(|cached| {
+                        selection
+                            .range(cached * PAGE_SIZE..(cached + 1) * PAGE_SIZE)
+                            .next()
+                            .is_none()
+                    })
+                    .max_by_key(|cached| cached.abs_diff(page));
+                if let Some(farthest) = farthest {
+                    pages.remove(&farthest);
+                }
+            }
+            let items = records
+                .into_iter()
+                .map(|record| {
+                    let checksum_mismatch = verified.get(&record.path) == Some(&false);
+                    FileItem {
+                        checksum_mismatch,
+                        ..file_record_to_item(record)
+                    }
+                })
+                .collect();
+            pages.insert(page, items);
+        }
+
+        let start = page * PAGE_SIZE;
+        for row in start..(start + PAGE_SIZE).min(self.total) {
+            self.notify.row_changed(row);
+        }
+    }
+}
+
+/// 结果列表仍显示编号为 `id` 的懒加载模型时对其执行 `f`，否则丢弃
+fn with_lazy_model(ui: &AppWindow, id: u64, f: impl FnOnce(&LazyFileModel)) {
+    let model = ui.get_file_items();
+    if let Some(lazy) = model.as_any().downcast_ref::<LazyFileModel>().filter(|lazy| lazy.id == id) {
+        f(lazy);
+    }
+}
+
+impl LazyFileModel {
+    /// 缩略图生成完成后刷新已缓存的对应行
+    pub fn refresh_thumbnail(&self, path: &str) {
+        let mut changed_rows = Vec::new();
+        {
+            let mut pages = self.pages.borrow_mut();
+            for (page, items) in pages.iter_mut() {
+                for (i, item) in items.iter_mut().enumerate() {
+                    if item.path.as_str() == path {
+                        if let Some(thumbnail) = load_thumbnail(path) {
+                            item.thumbnail = thumbnail;
+                            item.has_thumbnail = true;
+                            changed_rows.push(page * PAGE_SIZE + i);
+                        }
+                    }
+                }
+            }
+        }
+
+        for row in changed_rows {
+            self.notify.row_changed(row);
+        }
+    }
+
+    /// 本地文件校验后重新读取已加载行的校验状态
+    pub fn refresh_verification(&self) {
+        let paths: Vec<String> = self
+            .pages
+            .borrow()
+            .values()
+            .flat_map(|items| items.iter().map(|item| item.path.to_string()))
+            .collect();
+        let ui = self.ui.clone();
+        let id = self.id;
+        self.database.verification_status_then(paths, move |result| {
+            let _ = slint::invoke_from_event_loop(move || {
+                let Some(ui) = ui.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(verified) => with_lazy_model(&ui, id, |lazy| lazy.apply_verification(&verified)),
+                    Err(e) => error!("Failed to load verification status: {}", e),
+                }
+            });
+        });
+    }
+
+    /// 更新已加载行的"校验不一致"标记
+    fn apply_verification(&self, verified: &HashMap<String, bool>) {
+        let mut changed_rows = Vec::new();
+        {
+            let mut pages = self.pages.borrow_mut();
+            for (page, items) in pages.iter_mut() {
+                for (i, item) in items.iter_mut().enumerate() {
+                    let mismatch = verified.get(item.path.as_str()) == Some(&false);
+                    if item.checksum_mismatch != mismatch {
+                        item.checksum_mismatch = mismatch;
+                        changed_rows.push(page * PAGE_SIZE + i);
+                    }
+                }
+            }
+        }
+
+        for row in changed_rows {
+            self.notify.row_changed(row);
+        }
+    }
+
+    /// 更新已加载行的"网盘中已失效"标记
+    pub fn refresh_remote_state(&self) {
+        let resolver = shared_link_resolver();
+        let mut changed_rows = Vec::new();
+        {
+            let mut pages = self.pages.borrow_mut();
+            for (page, items) in pages.iter_mut() {
+                for (i, item) in items.iter_mut().enumerate() {
+                    let size = file_item_size(item).unwrap_or(0);
+                    let missing = resolver.is_missing(&item.etag, size);
+                    if item.remote_missing != missing {
+                        item.remote_missing = missing;
+                        changed_rows.push(page * PAGE_SIZE + i);
+                    }
+                }
+            }
+        }
+
+        for row in changed_rows {
+            self.notify.row_changed(row);
+        }
+    }
+}
+
+impl LazyFileModel {
+    /// 更新多选状态
+    ///
+    /// # Arguments
+    /// * `row` - 点击的行
+    /// * `toggle` - 是否切换该行的选中状态（Ctrl+点击）
+    /// * `extend` - 是否从上次点击的行选择到该行（Shift+点击）
+    pub fn select(&self, row: usize, toggle: bool, extend: bool) {
+        if row >= self.total {
+            return;
+        }
+
+        let previous = self.selection.borrow().clone();
+        {
+            let mut selection = self.selection.borrow_mut();
+            match (extend, self.anchor.get()) {
+                (true, Some(anchor)) => {
+                    if !toggle {
+                        selection.clear();
+                    }
+                    selection.extend(anchor.min(row)..=anchor.max(row));
+                }
+                _ if toggle => {
+                    if !selection.remove(&row) {
+                        selection.insert(row);
+                    }
+                    self.anchor.set(Some(row));
+                }
+                _ => {
+                    selection.clear();
+                    selection.insert(row);
+                    self.anchor.set(Some(row));
+                }
+            }
+        }
+
+        let current = self.selection.borrow().clone();
+        for changed in previous.symmetric_difference(&current) {
+            self.notify.row_changed(*changed);
+        }
+
+        // 选中行所在的页在读取后保留在缓存中，批量操作可以使用完整的记录
+        let selected_pages: BTreeSet<usize> = current.iter().map(|row| row / PAGE_SIZE).collect();
+        for page in selected_pages {
+            self.load_page(page);
+        }
+    }
+
+    /// 已选中的行数
+    pub fn selection_count(&self) -> usize {
+        self.selection.borrow().len()
+    }
+
+    /// 按行顺序获取已选中的文件项，跳过所在页尚未读取完成的行
+    pub fn selected_items(&self) -> Vec<FileItem> {
+        let pages = self.pages.borrow();
+        self.selection
+            .borrow()
+            .iter()
+            .filter_map(|row| pages.get(&(row / PAGE_SIZE))?.get(row % PAGE_SIZE).cloned())
+            .map(|item| FileItem { selected: true, ..item })
+            .collect()
+    }
+
+    /// 结果集中的全部文件项，通过数据库管理线程查询，不经过分页缓存
+    pub fn all_items(&self) -> impl Future<Output = Result<Vec<FileItem>>> + 'static {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.database
+            .search_then(&self.query, self.options.clone(), self.total, move |result| {
+                let _ = reply_tx.send(result);
+            });
+        async move {
+            let records = reply_rx.await.map_err(|_| anyhow::anyhow!("数据库管理线程已退出"))??;
+            Ok(records.into_iter().map(file_record_to_item).collect())
+        }
+    }
+}
+
+impl Model for LazyFileModel {
+    type Data = FileItem;
+
+    fn row_count(&self) -> usize {
+        self.total
+    }
+
+    fn row_data(&self, row: usize) -> Option<Self::Data> {
+        if row >= self.total {
+            return None;
+        }
+
+        let page = row / PAGE_SIZE;
+        let selected = self.selection.borrow().contains(&row);
+        let cached = self
+            .pages
+            .borrow()
+            .get(&page)
+            .map(|items| items.get(row % PAGE_SIZE).cloned());
+        match cached {
+            Some(item) => item.map(|item| FileItem { selected, ..item }),
+            None => {
+                self.load_page(page);
+                Some(FileItem {
+                    selected,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    fn model_tracker(&self) -> &dyn ModelTracker {
+        &self.notify
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 分组视图中的一行：分组标题或组内的文件
+#[derive(Debug, Clone, Copy)]
+enum GroupedRow {
+    Header(usize),
+    File(usize, usize),
+}
+
+/// 分组显示的搜索结果模型
+///
+/// 分组标题可折叠，折叠后隐藏组内的文件行
+pub struct GroupedFileModel {
+    groups: Vec<RecordGroup>,
+    collapsed: RefCell<HashSet<String>>,
+    rows: RefCell<Vec<GroupedRow>>,
+    notify: ModelNotify,
+}
+
+impl GroupedFileModel {
+    /// 将查询结果分组，调用方最多查询 `GROUPED_RESULT_LIMIT` 条记录
+    ///
+    /// # Arguments
+    /// * `records` - 查询结果
+    /// * `group_by` - 分组方式
+    pub fn new(records: Vec<FileRecord>, group_by: GroupBy) -> Self {
+        let groups = group_records(records, group_by);
+        debug!("Grouped model created with {} groups", groups.len());
+
+        let model = Self {
+            groups,
+            collapsed: RefCell::new(HashSet::new()),
+            rows: RefCell::new(Vec::new()),
+            notify: ModelNotify::default(),
+        };
+        model.rebuild_rows();
+        model
+    }
+
+    /// 匹配的文件数（不含分组标题）
+    pub fn file_count(&self) -> usize {
+        self.groups.iter().map(|group| group.records.len()).sum()
+    }
+
+    /// 全部分组中的文件项，包括折叠的分组
+    pub fn all_items(&self) -> Vec<FileItem> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.records.iter().cloned().map(file_record_to_item))
+            .collect()
+    }
+
+    /// 展开或折叠分组
+    pub fn toggle_group(&self, key: &str) {
+        {
+            let mut collapsed = self.collapsed.borrow_mut();
+            if !collapsed.remove(key) {
+                collapsed.insert(key.to_string());
+            }
+        }
+        self.rebuild_rows();
+        self.notify.reset();
+    }
+
+    /// 根据折叠状态重新计算可见行
+    fn rebuild_rows(&self) {
+        let collapsed = self.collapsed.borrow();
+        let mut rows = self.rows.borrow_mut();
+        rows.clear();
+        for (group_index, group) in self.groups.iter().enumerate() {
+            rows.push(GroupedRow::Header(group_index));
+            if !collapsed.contains(&group.key) {
+                rows.extend((0..group.records.len()).map(|i| GroupedRow::File(group_index, i)));
+            }
+        }
+    }
+}
+
+impl Model for GroupedFileModel {
+    type Data = FileItem;
+
+    fn row_count(&self) -> usize {
+        self.rows.borrow().len()
+    }
+
+    fn row_data(&self, row: usize) -> Option<Self::Data> {
+        let grouped_row = *self.rows.borrow().get(row)?;
+        match grouped_row {
+            GroupedRow::Header(group_index) => {
+                let group = &self.groups[group_index];
+                Some(FileItem {
+                    name: group.key.as_str().into(),
+                    is_group_header: true,
+                    group_expanded: !self.collapsed.borrow().contains(&group.key),
+                    group_count: group.records.len() as i32,
+                    ..Default::default()
+                })
+            }
+            GroupedRow::File(group_index, record_index) => self.groups[group_index]
+                .records
+                .get(record_index)
+                .cloned()
+                .map(file_record_to_item),
+        }
+    }
+
+    fn model_tracker(&self) -> &dyn ModelTracker {
+        &self.notify
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 展开或折叠结果列表中的分组
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `key` - 分组名
+pub fn toggle_group(ui: &AppWindow, key: &str) {
+    let model = ui.get_file_items();
+    if let Some(grouped) = model.as_any().downcast_ref::<GroupedFileModel>() {
+        grouped.toggle_group(key);
+        ui.set_selected_index(-1);
+    }
+}
+
+/// 缩略图生成完成后刷新结果列表中的对应行
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `path` - 生成了缩略图的源文件路径
+pub fn refresh_thumbnail(ui: &AppWindow, path: &str) {
+    let model = ui.get_file_items();
+    if let Some(lazy) = model.as_any().downcast_ref::<LazyFileModel>() {
+        lazy.refresh_thumbnail(path);
+    }
+}
+
+/// 刷新结果列表中的"网盘中已失效"标记
+///
+/// # Arguments
+/// * `ui` - UI 实例
+pub fn refresh_remote_state(ui: &AppWindow) {
+    let model = ui.get_file_items();
+    if let Some(lazy) = model.as_any().downcast_ref::<LazyFileModel>() {
+        lazy.refresh_remote_state();
+    } else if let Some(grouped) = model.as_any().downcast_ref::<GroupedFileModel>() {
+        // 分组视图的行每次读取时重新生成
+        grouped.notify.reset();
+    }
+}
+
+/// 本地文件校验后刷新结果列表中的"校验不一致"标记
+///
+/// # Arguments
+/// * `ui` - UI 实例
+pub fn refresh_verification(ui: &AppWindow) {
+    let model = ui.get_file_items();
+    if let Some(lazy) = model.as_any().downcast_ref::<LazyFileModel>() {
+        lazy.refresh_verification();
+    }
+}
+
+/// 处理结果列表中的行点击，更新多选状态
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `row` - 点击的行
+/// * `toggle` - 是否按住 Ctrl
+/// * `extend` - 是否按住 Shift
+pub fn select_row(ui: &AppWindow, row: usize, toggle: bool, extend: bool) {
+    let model = ui.get_file_items();
+    if let Some(lazy) = model.as_any().downcast_ref::<LazyFileModel>() {
+        lazy.select(row, toggle, extend);
+        ui.set_selection_count(lazy.selection_count() as i32);
+    }
+}
+
+/// 获取当前选中的文件项，没有多选时返回当前选中的单个文件
+///
+/// # Arguments
+/// * `ui` - UI 实例
+pub fn selected_file_items(ui: &AppWindow) -> Vec<FileItem> {
+    let model = ui.get_file_items();
+    let items = model
+        .as_any()
+        .downcast_ref::<LazyFileModel>()
+        .map(|lazy| lazy.selected_items())
+        .unwrap_or_default();
+
+    if items.is_empty() && ui.get_selected_index() >= 0 {
+        vec![ui.get_selected_file_item()]
+    } else {
+        items
+    }
+}
+
+/// 获取当前结果集中的全部文件项，不含分组标题
+///
+/// 懒加载的结果通过数据库管理线程查询，返回的 future 在事件循环中等待，不阻塞界面
+///
+/// # Arguments
+/// * `ui` - UI 实例
+pub fn result_file_items(ui: &AppWindow) -> impl Future<Output = Result<Vec<FileItem>>> + 'static {
+    let model = ui.get_file_items();
+    let pending = model.as_any().downcast_ref::<LazyFileModel>().map(LazyFileModel::all_items);
+    let items = if pending.is_some() {
+        Vec::new()
+    } else if let Some(grouped) = model.as_any().downcast_ref::<GroupedFileModel>() {
+        grouped.all_items()
+    } else {
+        model.iter().filter(|item| !item.is_group_header).collect()
+    };
+    async move {
+        match pending {
+            Some(pending) => pending.await,
+            None => Ok(items),
+        }
+    }
+}
+
 /// 将数据库信息列表转换为字符串数组供 ComboBox 使用
 ///
 /// # Arguments
@@ -87,3 +934,219 @@ pub fn database_list_to_string_model(
 
     ModelRc::new(slint::VecModel::from(items))
 }
+
+/// 生成状态栏中的搜索结果摘要
+///
+/// # Arguments
+/// * `count` - 匹配的结果数
+/// * `elapsed` - 搜索耗时
+///
+/// # Returns
+/// * `String` - 如 "1,234 results in 18 ms"
+pub fn format_result_summary(count: usize, elapsed: Duration) -> String {
+    format!("{} results in {} ms", format_count(count), elapsed.as_millis())
+}
+
+/// 格式化状态栏中的网盘空间用量，如 "网盘: 1.20 TB / 2.00 TB (60%)"
+pub fn format_quota(info: &AccountInfo) -> String {
+    let used = format_size(info.space_used);
+    match info.used_percent() {
+        Some(percent) => format!(
+            "网盘: {} / {} ({}%)",
+            used,
+            format_size(info.space_total()),
+            percent
+        ),
+        None => format!("网盘: 已用 {}", used),
+    }
+}
+
+/// 计算右键菜单的位置，使菜单完整显示在窗口内
+///
+/// 右侧或下方空间不足时菜单向左或向上展开，窗口比菜单还小时贴靠左上角
+///
+/// # Arguments
+/// * `anchor` - 右键点击位置
+/// * `menu` - 菜单宽高
+/// * `window` - 窗口宽高（逻辑像素）
+///
+/// # Returns
+/// * `(f32, f32)` - 菜单左上角坐标
+pub fn clamp_menu_position(anchor: (f32, f32), menu: (f32, f32), window: (f32, f32)) -> (f32, f32) {
+    fn clamp_axis(anchor: f32, size: f32, limit: f32) -> f32 {
+        let start = if anchor + size <= limit {
+            anchor
+        } else if anchor >= size {
+            anchor - size
+        } else {
+            limit - size
+        };
+        start.max(0.0)
+    }
+
+    (
+        clamp_axis(anchor.0, menu.0, window.0),
+        clamp_axis(anchor.1, menu.1, window.1),
+    )
+}
+
+/// 根据 UI 中的排序状态构造搜索选项
+///
+/// # Arguments
+/// * `ui` - UI 实例
+pub fn search_options_from_ui(ui: &AppWindow) -> SearchOptions {
+    let sort = SortColumn::from_name(ui.get_sort_column().as_str()).map(|column| SortOrder {
+        column,
+        ascending: ui.get_sort_ascending(),
+    });
+
+    let scope = ui.get_search_scope();
+    SearchOptions {
+        sort,
+        scope: (!scope.is_empty()).then(|| scope.to_string()),
+        categories: active_categories(ui),
+    }
+}
+
+/// 类型筛选按钮的 UI 模型
+///
+/// # Arguments
+/// * `active` - 选中的分类
+pub fn type_filter_chips(active: &[FileCategory]) -> ModelRc<TypeChip> {
+    let chips: Vec<TypeChip> = FileCategory::ALL
+        .iter()
+        .map(|category| TypeChip {
+            label: category.label().into(),
+            active: active.contains(category),
+        })
+        .collect();
+    ModelRc::new(slint::VecModel::from(chips))
+}
+
+/// UI 中选中的类型筛选
+pub fn active_categories(ui: &AppWindow) -> Vec<FileCategory> {
+    ui.get_type_filter_chips()
+        .iter()
+        .zip(FileCategory::ALL)
+        .filter(|(chip, _)| chip.active)
+        .map(|(_, category)| category)
+        .collect()
+}
+
+/// 将搜索范围拆分为面包屑，每一级可以点击切换到该文件夹
+///
+/// 例如 `/movies/2020` 拆分为 `movies`（/movies）和 `2020`（/movies/2020）
+pub fn scope_crumbs(scope: &str) -> Vec<(String, String)> {
+    let separator = if scope.contains('\\') && !scope.contains('/') { '\\' } else { '/' };
+    let mut crumbs = Vec::new();
+    let mut path = String::new();
+    for part in scope.split(separator) {
+        if part.is_empty() {
+            // 保留开头的分隔符，忽略重复和末尾的分隔符
+            if path.is_empty() {
+                path.push(separator);
+            }
+            continue;
+        }
+        if !path.is_empty() && !path.ends_with(separator) {
+            path.push(separator);
+        }
+        path.push_str(part);
+        crumbs.push((part.to_string(), path.clone()));
+    }
+    crumbs
+}
+
+/// 将搜索范围转换为面包屑模型
+pub fn scope_crumbs_to_model(scope: &str) -> ModelRc<ScopeCrumb> {
+    let items: Vec<ScopeCrumb> = scope_crumbs(scope)
+        .into_iter()
+        .map(|(name, path)| ScopeCrumb {
+            name: name.into(),
+            path: path.into(),
+        })
+        .collect();
+    ModelRc::new(slint::VecModel::from(items))
+}
+
+/// 将保存的列宽应用到 UI（顺序：名称、大小、修改时间）
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `widths` - 列宽（逻辑像素），数量不足时保留 UI 默认值
+pub fn apply_column_widths(ui: &AppWindow, widths: &[f32]) {
+    if let Some(width) = widths.first() {
+        ui.set_name_column_width(*width);
+    }
+    if let Some(width) = widths.get(1) {
+        ui.set_size_column_width(*width);
+    }
+    if let Some(width) = widths.get(2) {
+        ui.set_modified_column_width(*width);
+    }
+}
+
+/// 读取 UI 当前的列宽（顺序：名称、大小、修改时间）
+pub fn column_widths_from_ui(ui: &AppWindow) -> Vec<f32> {
+    vec![
+        ui.get_name_column_width(),
+        ui.get_size_column_width(),
+        ui.get_modified_column_width(),
+    ]
+}
+
+/// 应用界面主题
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `theme` - 主题，`Theme::System` 时由 UI 跟随系统配色
+pub fn apply_theme(ui: &AppWindow, theme: Theme) {
+    debug!("Applying theme: {:?}", theme);
+    ui.global::<AppTheme>().set_mode(theme.as_index());
+}
+
+/// 恢复保存的窗口大小、位置、列宽和排序状态
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `config` - 应用配置
+pub fn restore_ui_state(ui: &AppWindow, config: &AppConfig) {
+    let window = ui.window();
+    window.set_size(LogicalSize::new(
+        config.window_width as f32,
+        config.window_height as f32,
+    ));
+    if let (Some(x), Some(y)) = (config.ui.window_x, config.ui.window_y) {
+        window.set_position(LogicalPosition::new(x as f32, y as f32));
+    }
+
+    apply_column_widths(ui, &config.ui.column_widths);
+    if let Some(column) = &config.ui.sort_column {
+        ui.set_sort_column(column.into());
+        ui.set_sort_ascending(config.ui.sort_ascending);
+    }
+    ui.set_type_filter_chips(type_filter_chips(&[]));
+}
+
+/// 将当前窗口大小、位置、列宽和排序状态写回配置
+///
+/// # Arguments
+/// * `ui` - UI 实例
+/// * `config` - 应用配置
+pub fn save_ui_state(ui: &AppWindow, config: &mut AppConfig) {
+    let window = ui.window();
+    let scale = window.scale_factor();
+    let size = window.size().to_logical(scale);
+    let position = window.position().to_logical(scale);
+
+    config.window_width = size.width.round() as u32;
+    config.window_height = size.height.round() as u32;
+    config.ui.window_x = Some(position.x.round() as i32);
+    config.ui.window_y = Some(position.y.round() as i32);
+    config.ui.column_widths = column_widths_from_ui(ui);
+
+    let sort_column = ui.get_sort_column();
+    config.ui.sort_column = (!sort_column.is_empty()).then(|| sort_column.to_string());
+    config.ui.sort_ascending = ui.get_sort_ascending();
+    config.ui.selected_database = Some(config.database.name.clone());
+}
@@ -12,6 +12,9 @@ async fn test_aria2_service_creation() {
         rpc_port: 6800,
         rpc_secret: None,
         download_dir: "./test_downloads".to_string(),
+        speed_schedule: Vec::new(),
+        block_insufficient_space: false,
+        max_active_downloads: 0,
     };
 
     let mut service = Aria2Service::new(config);
@@ -62,6 +65,9 @@ fn test_aria2_config_serialization() {
         rpc_port: 6800,
         rpc_secret: Some("secret123".to_string()),
         download_dir: "./downloads".to_string(),
+        speed_schedule: Vec::new(),
+        block_insufficient_space: false,
+        max_active_downloads: 0,
     };
     
     // 测试序列化
@@ -76,4 +82,469 @@ fn test_aria2_config_serialization() {
     assert_eq!(deserialized.download_dir, config.download_dir);
     
     println!("Aria2 config serialization test passed");
-}
\ No newline at end of file
+}
+#[test]
+fn test_expired_url_error_detection() {
+    use netdisk_db::services::download_manager::is_expired_url_error;
+
+    assert!(is_expired_url_error("22", "Invalid response status=403"));
+    assert!(is_expired_url_error("24", "Authorization failed."));
+    assert!(is_expired_url_error("1", "403 Forbidden"));
+    assert!(!is_expired_url_error("22", "Invalid response status=500"));
+    assert!(!is_expired_url_error("3", "Resource not found"));
+}
+
+#[test]
+fn test_speed_limit_schedule() {
+    use netdisk_db::models::config::SpeedLimitRule;
+
+    let config = Aria2Config {
+        speed_schedule: vec![
+            SpeedLimitRule {
+                start_hour: 9,
+                end_hour: 18,
+                max_download_limit: "2M".to_string(),
+            },
+            SpeedLimitRule {
+                start_hour: 23,
+                end_hour: 7,
+                max_download_limit: "0".to_string(),
+            },
+        ],
+        ..Aria2Config::default()
+    };
+
+    assert_eq!(config.speed_limit_for_hour(10), Some("2M"));
+    assert_eq!(config.speed_limit_for_hour(18), None);
+    assert_eq!(config.speed_limit_for_hour(23), Some("0"));
+    assert_eq!(config.speed_limit_for_hour(3), Some("0"));
+    assert_eq!(config.speed_limit_for_hour(8), None);
+}
+
+#[test]
+fn test_speed_limit_all_day_rule() {
+    use netdisk_db::models::config::SpeedLimitRule;
+
+    // 开始和结束相同表示全天
+    let rule = SpeedLimitRule {
+        start_hour: 0,
+        end_hour: 0,
+        max_download_limit: "1M".to_string(),
+    };
+    assert!((0..24).all(|hour| rule.matches(hour)));
+
+    let config = Aria2Config {
+        speed_schedule: vec![SpeedLimitRule {
+            start_hour: 8,
+            end_hour: 8,
+            max_download_limit: "512K".to_string(),
+        }],
+        ..Aria2Config::default()
+    };
+    assert_eq!(config.speed_limit_for_hour(7), Some("512K"));
+    assert_eq!(config.speed_limit_for_hour(20), Some("512K"));
+}
+
+#[tokio::test]
+async fn test_watch_folder_link_files() {
+    use netdisk_db::services::aria2::create_shared_aria2_service;
+    use netdisk_db::services::download_manager::DownloadManager;
+    use netdisk_db::services::watch_folder::{parse_links, read_link_file, LinkFile, WatchFolder};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    let links = parse_links("# 注释\nhttps://example.com/a.iso\n\n  magnet:?xt=urn:btih:abc  \nnot a link\n");
+    assert_eq!(links, vec!["https://example.com/a.iso", "magnet:?xt=urn:btih:abc"]);
+
+    let root = std::env::temp_dir().join("netdisk_db_watch_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("submitted")).unwrap();
+    std::fs::write(root.join("a.txt"), "https://example.com/a.iso\n").unwrap();
+    std::fs::write(root.join("b.torrent"), b"d4:infoe").unwrap();
+    std::fs::write(root.join("c.jpg"), b"ignored").unwrap();
+    std::fs::write(root.join("fresh.magnet"), "magnet:?xt=urn:btih:def").unwrap();
+
+    // 刚写入的文件等待下次扫描
+    let old = SystemTime::now() - Duration::from_secs(60);
+    for name in ["a.txt", "b.torrent", "c.jpg"] {
+        std::fs::File::options()
+            .write(true)
+            .open(root.join(name))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+    }
+
+    assert_eq!(
+        read_link_file(&root.join("b.torrent")).unwrap(),
+        Some(LinkFile::Torrent(b"d4:infoe".to_vec()))
+    );
+    assert_eq!(read_link_file(&root.join("c.jpg")).unwrap(), None);
+
+    let aria2 = create_shared_aria2_service(Aria2Config::default());
+    let manager = Arc::new(DownloadManager::new(aria2));
+    let watch = WatchFolder::new(&root, manager, Duration::from_secs(1));
+    let pending: Vec<String> = watch
+        .pending_files()
+        .unwrap()
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(pending, vec!["a.txt", "b.torrent"]);
+
+    // Aria2未启动时不处理文件
+    let summary = watch.scan_once().await.unwrap();
+    assert_eq!(summary.files, 0);
+    assert!(root.join("a.txt").exists());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_torrent_options_and_magnet_links() {
+    use netdisk_db::services::aria2::{is_magnet_uri, TorrentFile, TorrentOptions};
+    use serde_json::json;
+
+    assert!(is_magnet_uri("magnet:?dn=x&xt=urn:btih:0123456789abcdef"));
+    assert!(!is_magnet_uri("magnet:?dn=x"));
+    assert!(!is_magnet_uri("https://example.com/a.torrent"));
+
+    let options = TorrentOptions {
+        dir: None,
+        select_files: vec![1, 3],
+        seed_time_minutes: Some(0),
+    };
+    let value = serde_json::to_value(options.to_download_options("/downloads")).unwrap();
+    assert_eq!(value, json!({ "dir": "/downloads", "select-file": "1,3", "seed-time": "0" }));
+
+    // 默认下载全部文件，不设置做种时间
+    let value = serde_json::to_value(TorrentOptions::default().to_download_options("/downloads")).unwrap();
+    assert_eq!(value, json!({ "dir": "/downloads" }));
+
+    let files = TorrentFile::list_from_value(&json!([
+        { "index": "1", "path": "/downloads/a.mkv", "length": "1024", "selected": "true" },
+        { "index": "2", "path": "/downloads/b.nfo", "length": "12", "selected": "false" }
+    ]));
+    assert_eq!(files.len(), 2);
+    assert_eq!((files[1].index, files[1].length, files[1].selected), (2, 12, false));
+}
+
+#[tokio::test]
+async fn test_mock_aria2_server() {
+    use netdisk_db::services::download_manager::DownloadManager;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let service = Aria2Service::connect(server.config());
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+    assert!(manager.is_available());
+
+    let gid = manager
+        .submit_uri("https://example.com/a.mkv", Some("/downloads"), None)
+        .await
+        .unwrap();
+    assert_eq!(gid, "0000000000000001");
+    let calls = server.calls_to("aria2.addUri");
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].params[0], serde_json::json!(["https://example.com/a.mkv"]));
+
+    // 预设的 RPC 错误作为错误返回
+    server.fail_next("aria2.addUri", 1, "disk full");
+    let error = manager.submit_uri("https://example.com/b.mkv", None, None).await.unwrap_err();
+    assert!(error.to_string().contains("disk full"));
+}
+
+#[test]
+fn test_download_source_naming() {
+    use netdisk_db::models::database::FileRecord;
+    use netdisk_db::services::download_manager::DownloadSource;
+    use std::path::Path;
+
+    let record = FileRecord {
+        id: 1,
+        name: "Movie (2020).mkv".to_string(),
+        path: "/movies/Movie (2020).mkv".to_string(),
+        size: 1024,
+        etag: "etag".to_string(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+    };
+
+    // 保存为记录中的文件名，并按类型放入子目录
+    let source = DownloadSource::from_record(&record, None);
+    assert_eq!(source.save_name(), Some("Movie (2020).mkv"));
+    assert_eq!(source.save_dir("/downloads"), Path::new("/downloads").join("Videos").to_string_lossy());
+
+    let source = DownloadSource::from_record(&record, Some("/data".to_string()));
+    assert_eq!(source.save_dir("/downloads"), Path::new("/data").join("Videos").to_string_lossy());
+
+    // 只有路径时由扩展名判断类型，无法识别时直接使用下载目录
+    let source = DownloadSource::from_path("/docs/report.pdf", "etag", 1, None);
+    assert_eq!(source.save_name(), Some("report.pdf"));
+    assert_eq!(source.save_dir("/downloads"), Path::new("/downloads").join("Documents").to_string_lossy());
+    let source = DownloadSource::from_path("/misc/data.bin", "etag", 1, None);
+    assert_eq!(source.save_dir("/downloads"), "/downloads");
+
+    // 索引中的文件名带有路径时只保留最后一段，不能写到下载目录之外
+    let unsafe_record = |name: &str| FileRecord {
+        name: name.to_string(),
+        ..record.clone()
+    };
+    let source = DownloadSource::from_record(&unsafe_record("../x"), None);
+    assert_eq!(source.save_name(), Some("x"));
+    let source = DownloadSource::from_record(&unsafe_record("/etc/passwd"), None);
+    assert_eq!(source.save_name(), Some("passwd"));
+    let source = DownloadSource::from_record(&unsafe_record("..\\..\\x.mkv"), None);
+    assert_eq!(source.save_name(), Some("x.mkv"));
+    let source = DownloadSource::from_record(&unsafe_record(".."), None);
+    assert_eq!(source.save_name(), Some("etag"));
+    let source = DownloadSource::from_record(&unsafe_record(""), None);
+    assert_eq!(source.save_name(), Some("etag"));
+}
+
+#[tokio::test]
+async fn test_download_queue_limit() {
+    use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, Submitted};
+    use netdisk_db::services::link_resolver::shared_link_resolver;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let download_dir = std::env::temp_dir().join("netdisk_db_download_queue_test");
+    let config = Aria2Config {
+        download_dir: download_dir.to_string_lossy().to_string(),
+        max_active_downloads: 1,
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+    assert_eq!(manager.max_active(), 1);
+
+    // 预先缓存链接，提交时不访问网盘
+    let source = |name: &str| {
+        let etag = format!("queue-{}", name);
+        shared_link_resolver().insert(&etag, 1, format!("https://example.com/{}", name));
+        DownloadSource::from_path(&format!("/queue/{}", name), &etag, 1, None)
+    };
+
+    let first = manager.submit(source("a.bin")).await.unwrap();
+    let Submitted::Started(gid) = first else {
+        panic!("first download should start immediately");
+    };
+    let Submitted::Queued(b) = manager.submit(source("b.bin")).await.unwrap() else {
+        panic!("second download should wait in the queue");
+    };
+    let Submitted::Queued(c) = manager.submit(source("c.bin")).await.unwrap() else {
+        panic!("third download should wait in the queue");
+    };
+    assert_eq!(server.calls_to("aria2.addUri").len(), 1);
+
+    // 拖动调整顺序，超出范围时移到末尾
+    assert!(manager.move_queued(c, 0));
+    let order: Vec<u64> = manager.queued_downloads().iter().map(|queued| queued.id).collect();
+    assert_eq!(order, vec![c, b]);
+    assert!(manager.move_queued(c, 10));
+    assert!(manager.move_queued(c, 0));
+    assert!(!manager.move_queued(999, 0));
+
+    // 正在下载的任务完成后按队列顺序提交下一个
+    server.reply_next("aria2.tellStatus", serde_json::json!({ "gid": gid, "status": "complete" }));
+    manager.check_tasks().await.unwrap();
+    let calls = server.calls_to("aria2.addUri");
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1].params[0], serde_json::json!(["https://example.com/c.bin"]));
+    assert_eq!(manager.queued_downloads().len(), 1);
+
+    // 移除等待中的任务，调大同时下载数后不再排队
+    assert_eq!(manager.remove_queued(b).map(|queued| queued.source.name), Some("b.bin".to_string()));
+    manager.set_max_active(0);
+    assert!(matches!(manager.submit(source("d.bin")).await.unwrap(), Submitted::Started(_)));
+    let _ = std::fs::remove_dir_all(&download_dir);
+}
+
+#[tokio::test]
+async fn test_overlapping_download_checks() {
+    use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, Submitted};
+    use netdisk_db::services::link_resolver::shared_link_resolver;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let download_dir = std::env::temp_dir().join("netdisk_db_overlapping_check_test");
+    let config = Aria2Config {
+        download_dir: download_dir.to_string_lossy().to_string(),
+        max_active_downloads: 1,
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+
+    let source = |name: &str| {
+        let etag = format!("overlap-{}", name);
+        shared_link_resolver().insert(&etag, 1, format!("https://example.com/{}", name));
+        DownloadSource::from_path(&format!("/overlap/{}", name), &etag, 1, None)
+    };
+    let Submitted::Started(gid) = manager.submit(source("a.bin")).await.unwrap() else {
+        panic!("first download should start immediately");
+    };
+    manager.submit(source("b.bin")).await.unwrap();
+    manager.submit(source("c.bin")).await.unwrap();
+
+    // 两次检查同时进行时，只有一次处理完成的任务并提交队列中的下一个
+    server.reply_next("aria2.tellStatus", serde_json::json!({ "gid": gid, "status": "complete" }));
+    server.reply_next("aria2.tellStatus", serde_json::json!({ "gid": gid, "status": "complete" }));
+    let (first, second) = tokio::join!(manager.check_tasks(), manager.check_tasks());
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(server.calls_to("aria2.addUri").len(), 2);
+    assert_eq!(manager.tracked_tasks().len(), 1);
+    assert_eq!(manager.queued_downloads().len(), 1);
+    let _ = std::fs::remove_dir_all(&download_dir);
+}
+
+#[tokio::test]
+async fn test_concurrent_queue_starts_respect_limit() {
+    use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, Submitted};
+    use netdisk_db::services::link_resolver::shared_link_resolver;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let download_dir = std::env::temp_dir().join("netdisk_db_concurrent_queue_test");
+    let config = Aria2Config {
+        download_dir: download_dir.to_string_lossy().to_string(),
+        max_active_downloads: 1,
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+
+    let source = |name: &str| {
+        let etag = format!("concurrent-{}", name);
+        shared_link_resolver().insert(&etag, 1, format!("https://example.com/{}", name));
+        DownloadSource::from_path(&format!("/concurrent/{}", name), &etag, 1, None)
+    };
+    assert!(matches!(manager.submit(source("a.bin")).await.unwrap(), Submitted::Started(_)));
+    manager.submit(source("b.bin")).await.unwrap();
+    manager.submit(source("c.bin")).await.unwrap();
+
+    // 空出一个位置后同时提交队列，只有一个下载拿到空位
+    manager.set_max_active(2);
+    let (first, second) = tokio::join!(manager.start_queued(), manager.start_queued());
+    assert_eq!(first + second, 1);
+    assert_eq!(server.calls_to("aria2.addUri").len(), 2);
+    assert_eq!(manager.tracked_tasks().len(), 2);
+    assert_eq!(manager.queued_downloads().len(), 1);
+    let _ = std::fs::remove_dir_all(&download_dir);
+}
+
+#[tokio::test]
+async fn test_submit_keeps_queue_order() {
+    use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, Submitted};
+    use netdisk_db::services::link_resolver::shared_link_resolver;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let download_dir = std::env::temp_dir().join("netdisk_db_queue_order_test");
+    let config = Aria2Config {
+        download_dir: download_dir.to_string_lossy().to_string(),
+        max_active_downloads: 1,
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+
+    let source = |name: &str| {
+        let etag = format!("order-{}", name);
+        shared_link_resolver().insert(&etag, 1, format!("https://example.com/{}", name));
+        DownloadSource::from_path(&format!("/order/{}", name), &etag, 1, None)
+    };
+    assert!(matches!(manager.submit(source("a.bin")).await.unwrap(), Submitted::Started(_)));
+    assert!(matches!(manager.submit(source("b.bin")).await.unwrap(), Submitted::Queued(_)));
+
+    // 有了空位但队列中还有等待的下载，新的下载排在队尾，空位交给先来的下载
+    manager.set_max_active(2);
+    assert!(matches!(manager.submit(source("c.bin")).await.unwrap(), Submitted::Queued(_)));
+    let calls = server.calls_to("aria2.addUri");
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1].params[0], serde_json::json!(["https://example.com/b.bin"]));
+    let queued: Vec<String> = manager.queued_downloads().into_iter().map(|queued| queued.source.name).collect();
+    assert_eq!(queued, vec!["c.bin".to_string()]);
+    let _ = std::fs::remove_dir_all(&download_dir);
+}
+
+#[tokio::test]
+async fn test_preflight_counts_queued_downloads() {
+    use netdisk_db::services::disk_space::{self, SpaceCheck};
+    use netdisk_db::services::download_manager::{DownloadManager, DownloadSource, Submitted};
+    use netdisk_db::services::link_resolver::shared_link_resolver;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let download_dir = std::env::temp_dir().join("netdisk_db_preflight_queue_test");
+    let config = Aria2Config {
+        download_dir: download_dir.to_string_lossy().to_string(),
+        max_active_downloads: 1,
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+
+    let source = |name: &str, size: u64| {
+        let etag = format!("preflight-{}", name);
+        shared_link_resolver().insert(&etag, size, format!("https://example.com/{}", name));
+        DownloadSource::from_path(&format!("/preflight/{}", name), &etag, size, None)
+    };
+    let available = disk_space::available_space(Path::new(&download_dir)).unwrap();
+    assert!(manager.preflight(&source("c.bin", 1)).await.unwrap().is_sufficient());
+
+    // 等待中的下载不在Aria2中，也要计入需要的空间
+    assert!(matches!(manager.submit(source("a.bin", 1)).await.unwrap(), Submitted::Started(_)));
+    assert!(matches!(manager.submit(source("b.bin", available)).await.unwrap(), Submitted::Queued(_)));
+    let check = manager.preflight(&source("c.bin", 1)).await.unwrap();
+    assert!(matches!(check, SpaceCheck::QueueExceeds { required: 1, .. }), "{:?}", check);
+    let _ = std::fs::remove_dir_all(&download_dir);
+}
+
+#[tokio::test]
+async fn test_speed_schedule_reapplied_after_restart() {
+    use netdisk_db::models::config::SpeedLimitRule;
+    use netdisk_db::services::download_manager::DownloadManager;
+    use netdisk_db::testing::mock_aria2::MockAria2Server;
+    use std::sync::{Arc, Mutex};
+
+    let server = MockAria2Server::start().await.unwrap();
+    let config = Aria2Config {
+        speed_schedule: vec![SpeedLimitRule {
+            start_hour: 9,
+            end_hour: 18,
+            max_download_limit: "2M".to_string(),
+        }],
+        ..server.config()
+    };
+    let service = Aria2Service::connect(config);
+    assert!(service.wait_until_ready(2).await);
+    let manager = DownloadManager::new(Arc::new(Mutex::new(service)));
+
+    // 限速不变时只设置一次
+    manager.apply_speed_schedule_at(10).await.unwrap();
+    manager.apply_speed_schedule_at(11).await.unwrap();
+    let calls = server.calls_to("aria2.changeGlobalOption");
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].params[0], serde_json::json!({ "max-overall-download-limit": "2M" }));
+
+    // aria2c 重新启动后会话 ID 改变，重新应用同一限速
+    server.reply_next("aria2.getSessionInfo", serde_json::json!({ "sessionId": "restarted" }));
+    manager.apply_speed_schedule_at(12).await.unwrap();
+    assert_eq!(server.calls_to("aria2.changeGlobalOption").len(), 2);
+}
@@ -0,0 +1,150 @@
+//! 命令行模式参数解析测试
+
+use netdisk_db::controllers::cli::{parse_args, CliCommand};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_parse_args_without_no_gui() {
+    assert!(parse_args(args(&[])).unwrap().is_none());
+    assert!(parse_args(args(&["--data-dir", "/tmp/netdisk"])).unwrap().is_none());
+    assert!(parse_args(args(&["--verbose"])).unwrap().is_none());
+    assert!(parse_args(args(&["--portable"])).unwrap().is_none());
+}
+
+#[test]
+fn test_parse_search_command() {
+    let parsed = parse_args(args(&["--no-gui", "--json", "search", "sky", "fall", "--limit", "5"]))
+        .unwrap()
+        .unwrap();
+    assert!(parsed.json);
+    assert_eq!(
+        parsed.command,
+        CliCommand::Search {
+            query: "sky fall".to_string(),
+            limit: 5
+        }
+    );
+}
+
+#[test]
+fn test_parse_export_and_send_commands() {
+    let parsed = parse_args(args(&["--no-gui", "export", "mkv", "-o", "out.csv"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Export {
+            query: "mkv".to_string(),
+            output: Some("out.csv".into())
+        }
+    );
+
+    let parsed = parse_args(args(&["--data-dir=/tmp/x", "-v", "--no-gui", "send", "42"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(parsed.command, CliCommand::Send { id: 42 });
+    assert!(parsed.command.needs_backend());
+
+    let parsed = parse_args(args(&["--no-gui", "sync"])).unwrap().unwrap();
+    assert_eq!(parsed.command, CliCommand::Sync { root_id: 0 });
+    assert!(parsed.command.needs_backend());
+
+    assert!(parse_args(args(&["--no-gui", "send", "abc"])).is_err());
+    assert!(parse_args(args(&["--no-gui", "unknown"])).is_err());
+    assert!(parse_args(args(&["--no-gui"])).is_err());
+}
+
+#[test]
+fn test_parse_maintain_command() {
+    use netdisk_db::models::database::MaintenanceOperation;
+
+    let parsed = parse_args(args(&["--no-gui", "maintain"])).unwrap().unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Maintain {
+            operations: MaintenanceOperation::ROUTINE.to_vec()
+        }
+    );
+    assert!(!parsed.command.needs_backend());
+
+    let parsed = parse_args(args(&["--no-gui", "maintain", "cleanup", "/old", "/gone"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Maintain {
+            operations: vec![MaintenanceOperation::CleanupOrphans {
+                roots: vec!["/old".to_string(), "/gone".to_string()]
+            }]
+        }
+    );
+
+    assert!(parse_args(args(&["--no-gui", "maintain", "cleanup"])).is_err());
+    assert!(parse_args(args(&["--no-gui", "maintain", "defrag"])).is_err());
+}
+
+#[test]
+fn test_parse_backup_and_restore_commands() {
+    use std::path::PathBuf;
+
+    let parsed = parse_args(args(&["--no-gui", "backup"])).unwrap().unwrap();
+    assert_eq!(parsed.command, CliCommand::Backup { dest: None });
+
+    let parsed = parse_args(args(&["--no-gui", "backup", "/tmp/index.bak"])).unwrap().unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Backup {
+            dest: Some(PathBuf::from("/tmp/index.bak"))
+        }
+    );
+
+    let parsed = parse_args(args(&["--no-gui", "restore"])).unwrap().unwrap();
+    assert_eq!(parsed.command, CliCommand::Restore { source: None });
+    assert!(!parsed.command.needs_backend());
+}
+
+#[test]
+fn test_parse_compare_command() {
+    use netdisk_db::models::database::CompareKey;
+
+    let parsed = parse_args(args(&["--no-gui", "compare", "mirror", "dump"])).unwrap().unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Compare {
+            left: "mirror".to_string(),
+            right: "dump".to_string(),
+            key: CompareKey::Path
+        }
+    );
+
+    let parsed = parse_args(args(&["--no-gui", "compare", "mirror", "dump", "etag"])).unwrap().unwrap();
+    assert!(matches!(parsed.command, CliCommand::Compare { key: CompareKey::Etag, .. }));
+
+    assert!(parse_args(args(&["--no-gui", "compare", "mirror"])).is_err());
+    assert!(parse_args(args(&["--no-gui", "compare", "mirror", "dump", "size"])).is_err());
+}
+
+#[test]
+fn test_parse_remote_command() {
+    use netdisk_db::services::single_instance::InstanceRequest;
+
+    let parsed = parse_args(args(&["--no-gui", "remote", "search", "movie", "2024"])).unwrap().unwrap();
+    assert_eq!(
+        parsed.command,
+        CliCommand::Remote {
+            request: InstanceRequest::Search {
+                query: "movie 2024".to_string()
+            }
+        }
+    );
+    assert!(!parsed.command.needs_backend());
+
+    let parsed = parse_args(args(&["--no-gui", "remote", "open", "42"])).unwrap().unwrap();
+    assert_eq!(parsed.command, CliCommand::Remote { request: InstanceRequest::Open { id: 42 } });
+
+    assert!(parse_args(args(&["--no-gui", "remote"])).is_err());
+    assert!(parse_args(args(&["--no-gui", "remote", "open", "abc"])).is_err());
+}
@@ -1,7 +1,7 @@
 //! HTTP 请求功能测试
 
-use netdisk_db::controllers::handlers::{send_file_upload_request, UploadFileItemPayload};
-use reqwest::Client;
+use netdisk_db::models::config::ApiEndpoints;
+use netdisk_db::services::api_client::{ApiClient, LinkError, UploadFileItemPayload};
 
 #[tokio::test]
 async fn test_send_file_upload_request() {
@@ -14,13 +14,14 @@ async fn test_send_file_upload_request() {
     };
 
     // 发送请求（注意：这需要本地服务器运行在 127.0.0.1:8080）
-    let client = Client::new();
-    match send_file_upload_request(&client, payload).await {
+    let client = ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:8080");
+    match client.upload_file(&payload).await {
         Ok(_) => println!("请求发送成功"),
         Err(e) => println!("请求发送失败: {}", e),
     }
 }
 
+#[cfg(feature = "gui")]
 #[tokio::test]
 async fn test_send_to_aria2_integration() {
     use netdisk_db::controllers::handlers::send_to_aria2;
@@ -36,4 +37,438 @@ async fn test_send_to_aria2_integration() {
         Ok(_) => println!("send_to_aria2 执行成功"),
         Err(e) => println!("send_to_aria2 执行失败: {}", e),
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_api_client_endpoint_url() {
+    let client = ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:8081/");
+    assert_eq!(client.base_url(), "http://127.0.0.1:8081");
+    assert_eq!(client.endpoint_url("/file/upload"), "http://127.0.0.1:8081/file/upload");
+
+    // 配置了后端地址时不使用内置后端服务
+    let endpoints = ApiEndpoints {
+        base_url: Some("http://nas.local:9000".to_string()),
+        ..ApiEndpoints::default()
+    };
+    let client = ApiClient::new(endpoints, "http://127.0.0.1:8080");
+    assert_eq!(client.endpoint_url("file/download"), "http://nas.local:9000/file/download");
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_rate_limiter_window() {
+    use netdisk_db::controllers::middleware::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    let limiter = RateLimiter::new(2, Duration::from_secs(60));
+    let ip = "127.0.0.1".parse().unwrap();
+    let other = "10.0.0.2".parse().unwrap();
+    let start = Instant::now();
+
+    assert!(limiter.check_at(ip, start));
+    assert!(limiter.check_at(ip, start));
+    assert!(!limiter.check_at(ip, start + Duration::from_secs(1)));
+    // 不同 IP 分别计数
+    assert!(limiter.check_at(other, start));
+    // 新窗口重新计数
+    assert!(limiter.check_at(ip, start + Duration::from_secs(61)));
+}
+
+#[cfg(feature = "server")]
+#[actix_web::test]
+async fn test_fresh_access_token_middleware() {
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App, HttpResponse};
+    use netdisk_core::responses::prelude::AccessToken;
+    use netdisk_db::controllers::middleware;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    // 应用级注册的是启动时的令牌，接口应拿到中间件放入的最新令牌
+    let stale = web::Data::new(AccessToken::default());
+    let stale_token = stale.clone().into_inner();
+    let shared = web::Data::new(Arc::new(RwLock::new(AccessToken::default())));
+    let app = test::init_service(
+        App::new()
+            .app_data(stale)
+            .app_data(shared)
+            .route(
+                "/token",
+                web::get().to(move |token: web::Data<AccessToken>| {
+                    let fresh = !Arc::ptr_eq(&token.into_inner(), &stale_token);
+                    async move { HttpResponse::Ok().body(fresh.to_string()) }
+                }),
+            )
+            .wrap(from_fn(middleware::fresh_access_token)),
+    )
+    .await;
+
+    let body = test::call_and_read_body(&app, test::TestRequest::get().uri("/token").to_request()).await;
+    assert_eq!(body, "true");
+}
+
+#[test]
+fn test_link_resolver_cache() {
+    use netdisk_db::services::link_resolver::LinkResolver;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let api = Arc::new(ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:8080"));
+    let resolver = LinkResolver::new(api.clone(), Duration::from_secs(60));
+    assert_eq!(resolver.cached("etag", 10), None);
+
+    resolver.insert("etag", 10, "https://example.com/a".to_string());
+    assert_eq!(resolver.cached("etag", 10).as_deref(), Some("https://example.com/a"));
+    // 大小不同视为不同文件
+    assert_eq!(resolver.cached("etag", 11), None);
+
+    resolver.invalidate("etag", 10);
+    assert_eq!(resolver.cached("etag", 10), None);
+
+    // 有效期为 0 时不缓存
+    let resolver = LinkResolver::new(api, Duration::ZERO);
+    resolver.insert("etag", 10, "https://example.com/a".to_string());
+    assert_eq!(resolver.cached("etag", 10), None);
+}
+
+#[tokio::test]
+async fn test_link_resolver_resolve_many_partial_failure() {
+    use netdisk_db::services::link_resolver::LinkResolver;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // 不可连接的后端地址，未缓存的文件会获取失败
+    let api = Arc::new(ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:1"));
+    let resolver = Arc::new(LinkResolver::new(api, Duration::from_secs(60)));
+    resolver.insert("a", 1, "https://example.com/a".to_string());
+    resolver.insert("c", 3, "https://example.com/c".to_string());
+
+    let results = resolver
+        .resolve_many(
+            vec![
+                ("/x/a.mkv".to_string(), "a".to_string(), 1),
+                ("/x/b.mkv".to_string(), "b".to_string(), 2),
+                ("/x/c.mkv".to_string(), "c".to_string(), 3),
+            ],
+            2,
+        )
+        .await;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].link.as_deref().ok(), Some("https://example.com/a"));
+    assert_eq!(results[1].path, "/x/b.mkv");
+    // 连接失败归类为 HTTP 错误
+    assert!(matches!(results[1].link, Err(LinkError::Http(_))));
+    assert_eq!(results[2].link.as_deref().ok(), Some("https://example.com/c"));
+}
+
+#[tokio::test]
+async fn test_link_resolver_progress() {
+    use netdisk_db::services::link_resolver::LinkResolver;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let api = Arc::new(ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:1"));
+    let resolver = Arc::new(LinkResolver::new(api, Duration::from_secs(60)));
+    resolver.insert("a", 1, "https://example.com/a".to_string());
+    resolver.insert("b", 2, "https://example.com/b".to_string());
+
+    // 每完成一个文件报告一次，失败的文件也计入
+    let mut progress = Vec::new();
+    let results = resolver
+        .resolve_many_with_progress(
+            vec![
+                ("/x/a.mkv".to_string(), "a".to_string(), 1),
+                ("/x/b.mkv".to_string(), "b".to_string(), 2),
+                ("/x/c.mkv".to_string(), "c".to_string(), 3),
+            ],
+            2,
+            |done, total| progress.push((done, total)),
+        )
+        .await;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+}
+
+#[test]
+fn test_link_error_messages() {
+    let error = LinkError::Status {
+        status: 502,
+        body: "bad gateway".to_string(),
+    };
+    assert_eq!(error.to_string(), "HTTP 请求失败，状态码: 502，错误信息: bad gateway");
+
+    let error = LinkError::Business {
+        code: 401,
+        message: "token expired".to_string(),
+    };
+    assert!(error.to_string().contains("code=401"));
+
+    // 错误类型可以跨线程传递
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<LinkError>();
+}
+
+#[test]
+fn test_link_resolver_remote_check() {
+    use netdisk_db::services::link_resolver::LinkResolver;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let api = Arc::new(ApiClient::new(ApiEndpoints::default(), "http://127.0.0.1:8080"));
+    let resolver = LinkResolver::new(api.clone(), Duration::from_secs(60));
+    assert!(!resolver.verifies_remote());
+    assert!(!resolver.is_missing("etag", 10));
+    assert_eq!(resolver.parent_folder(), 0);
+    resolver.set_parent_folder(42);
+    assert_eq!(resolver.parent_folder(), 42);
+
+    let resolver = LinkResolver::new(api, Duration::from_secs(60)).with_remote_check(true);
+    assert!(resolver.verifies_remote());
+
+    let error = LinkError::RemoteMissing("a.mp4".to_string());
+    assert!(error.to_string().contains("a.mp4"));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_account_info_quota() {
+    use netdisk_db::services::api_client::AccountInfo;
+
+    let info: AccountInfo = serde_json::from_str(
+        r#"{"uid": 1, "nickname": "me", "spaceUsed": 1099511627776, "spacePermanent": 2199023255552, "spaceTemp": 0}"#,
+    )
+    .unwrap();
+    assert_eq!(info.used_percent(), Some(50));
+    assert_eq!(
+        netdisk_db::views::ui::format_quota(&info),
+        "网盘: 1.00 TB / 2.00 TB (50%)"
+    );
+
+    // 总空间未知时只显示已用空间
+    let info = AccountInfo {
+        space_used: 1024,
+        ..Default::default()
+    };
+    assert_eq!(info.used_percent(), None);
+}
+
+#[test]
+fn test_share_request_serialization() {
+    use netdisk_db::services::api_client::ShareRequest;
+
+    let request = ShareRequest {
+        share_name: "movie.mkv".to_string(),
+        share_expire: 7,
+        file_id_list: "1,2".to_string(),
+        share_pwd: None,
+    };
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["shareName"], "movie.mkv");
+    assert_eq!(json["shareExpire"], 7);
+    assert_eq!(json["fileIDList"], "1,2");
+    // 未设置提取码时不发送该字段
+    assert!(json.get("sharePwd").is_none());
+}
+
+#[test]
+fn test_link_formats() {
+    use netdisk_db::services::link_format::{LinkEntry, LinkFormat};
+
+    let entries = vec![
+        LinkEntry {
+            path: "/videos/It's (2017).mkv".to_string(),
+            etag: "e1".to_string(),
+            size: 1024,
+            url: "https://example.com/a?x=1".to_string(),
+        },
+        LinkEntry {
+            path: "/docs/b.pdf".to_string(),
+            etag: "e2".to_string(),
+            size: 2048,
+            url: "https://example.com/b".to_string(),
+        },
+    ];
+
+    assert_eq!(LinkFormat::from_index(0), LinkFormat::Url);
+    assert_eq!(LinkFormat::from_index(4), LinkFormat::Json);
+    assert_eq!(LinkFormat::from_index(-1), LinkFormat::Url);
+    assert_eq!(LinkFormat::from_index(9), LinkFormat::Url);
+
+    assert_eq!(
+        LinkFormat::Url.format(&entries),
+        "https://example.com/a?x=1\nhttps://example.com/b"
+    );
+    // 文件名中的单引号需要转义
+    assert_eq!(
+        LinkFormat::Aria2c.format(&entries[..1]),
+        "aria2c -o 'It'\\''s (2017).mkv' 'https://example.com/a?x=1'"
+    );
+    assert_eq!(
+        LinkFormat::Curl.format(&entries[1..]),
+        "curl -L -o 'b.pdf' 'https://example.com/b'"
+    );
+    assert_eq!(
+        LinkFormat::Markdown.format(&entries[1..]),
+        "[b.pdf](https://example.com/b)"
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&LinkFormat::Json.format(&entries)).unwrap();
+    assert_eq!(json[0]["etag"], "e1");
+    assert_eq!(json[0]["size"], 1024);
+    assert_eq!(json[1]["name"], "b.pdf");
+}
+
+/// 在本地端口上提供固定内容，支持 Range 和 If-Range，返回下载地址
+async fn serve_file(body: &'static [u8], etag: &'static str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = vec![0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+            let header = |name: &str| {
+                request
+                    .lines()
+                    .find_map(|line| line.strip_prefix(&format!("{}:", name)).map(|v| v.trim().to_string()))
+            };
+            let range_start = header("range")
+                .and_then(|range| range.strip_prefix("bytes=").and_then(|r| r.trim_end_matches('-').parse::<usize>().ok()))
+                // If-Range 与当前 ETag 不一致时返回完整内容
+                .filter(|_| header("if-range").map_or(true, |tag| tag == etag));
+            let response = match range_start {
+                Some(start) if start >= body.len() => format!(
+                    "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .into_bytes(),
+                Some(start) => {
+                    let mut response = format!(
+                        "HTTP/1.1 206 Partial Content\r\nETag: {}\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        etag,
+                        start,
+                        body.len() - 1,
+                        body.len(),
+                        body.len() - start
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(&body[start..]);
+                    response
+                }
+                None => {
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        etag,
+                        body.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                }
+            };
+            let _ = stream.write_all(&response).await;
+        }
+    });
+    format!("http://127.0.0.1:{}/file", port)
+}
+
+#[tokio::test]
+async fn test_http_downloader_resume() {
+    use netdisk_db::services::http_downloader::HttpDownloader;
+
+    const BODY: &[u8] = b"0123456789";
+    let url = serve_file(BODY, "\"v1\"").await;
+    let root = std::env::temp_dir().join("netdisk_db_http_download_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let downloader = HttpDownloader::new(&root);
+    let target = root.join("a.bin");
+    let part = HttpDownloader::part_path(&target);
+    let state = root.join("a.bin.part.json");
+
+    // 已存在的同名文件不会被当作未完成的下载
+    std::fs::write(&target, b"junk").unwrap();
+    let path = downloader.download(&url, "a.bin", Some(10), |_| {}).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    assert!(!part.exists() && !state.exists());
+
+    // ETag 和大小一致时从已下载的位置续传
+    std::fs::write(&part, b"01234").unwrap();
+    std::fs::write(&state, r#"{"etag":"\"v1\"","total":10}"#).unwrap();
+    let mut first = None;
+    downloader
+        .download(&url, "a.bin", Some(10), |progress| {
+            first.get_or_insert(progress.downloaded);
+        })
+        .await
+        .unwrap();
+    assert_eq!(first, Some(5));
+    assert_eq!(std::fs::read(&target).unwrap(), BODY);
+
+    // 服务器上的文件已变化（ETag 不同）时重新下载
+    std::fs::write(&part, b"abcde").unwrap();
+    std::fs::write(&state, r#"{"etag":"\"v0\"","total":10}"#).unwrap();
+    downloader.download(&url, "a.bin", Some(10), |_| {}).await.unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), BODY);
+
+    // 大小与期望不一致时不续传
+    std::fs::write(&part, b"abcde").unwrap();
+    std::fs::write(&state, r#"{"etag":"\"v1\"","total":20}"#).unwrap();
+    downloader.download(&url, "a.bin", Some(10), |_| {}).await.unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), BODY);
+
+    // 已完整下载但未重命名时，416 表示完成
+    std::fs::write(&part, BODY).unwrap();
+    std::fs::write(&state, r#"{"etag":"\"v1\"","total":10}"#).unwrap();
+    downloader.download(&url, "a.bin", Some(10), |_| {}).await.unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), BODY);
+    assert!(!part.exists());
+
+    // 服务器报告的大小与已下载的部分不一致时 416 不算完成，丢弃后重新下载
+    std::fs::write(&part, b"0123456789xyz").unwrap();
+    std::fs::write(&state, r#"{"etag":"\"v1\"","total":13}"#).unwrap();
+    downloader.download(&url, "a.bin", None, |_| {}).await.unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), BODY);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[cfg(feature = "server")]
+#[actix_web::test]
+async fn test_collections_unknown_id_not_found() {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use netdisk_db::controllers::collections;
+    use netdisk_db::services::collections::{set_shared_collections, Collections};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let path = std::env::temp_dir().join("netdisk_db_collections_http_test.db");
+    let _ = std::fs::remove_file(&path);
+    set_shared_collections(Arc::new(Collections::open(&path.to_string_lossy()).unwrap()));
+    let app = test::init_service(App::new().configure(collections::configure)).await;
+
+    // 不存在的收藏集在所有接口中都返回 404
+    let requests = [
+        test::TestRequest::put()
+            .uri("/api/collections/999999")
+            .set_json(json!({ "name": "renamed" })),
+        test::TestRequest::delete().uri("/api/collections/999999"),
+        test::TestRequest::get().uri("/api/collections/999999/items"),
+        test::TestRequest::post()
+            .uri("/api/collections/999999/items")
+            .set_json(json!({ "database": "video", "records": [] })),
+        test::TestRequest::delete()
+            .uri("/api/collections/999999/items")
+            .set_json(json!({ "database": "video", "path": "/a.mkv" })),
+    ];
+    for request in requests {
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    let _ = std::fs::remove_file(&path);
+}
@@ -11,6 +11,30 @@ fn test_config_default() {
     assert_eq!(config.database.connection_string, "file_search.db");
     assert_eq!(config.window_width, 800);
     assert_eq!(config.window_height, 600);
+    assert!(config.ui.sort_ascending);
+    assert!(config.ui.window_x.is_none());
+    assert_eq!(config.search.debounce_ms, 300);
+    assert_eq!(config.search.min_query_len, 2);
+}
+
+#[test]
+fn test_ui_state_round_trip() {
+    let mut config = AppConfig::default();
+    config.ui.window_x = Some(120);
+    config.ui.window_y = Some(80);
+    config.ui.sort_column = Some("size".to_string());
+    config.ui.sort_ascending = false;
+    config.ui.selected_database = Some("Default Database".to_string());
+
+    let json = serde_json::to_string(&config).unwrap();
+    let loaded: AppConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.ui.window_x, Some(120));
+    assert_eq!(loaded.ui.sort_column.as_deref(), Some("size"));
+    assert!(!loaded.ui.sort_ascending);
+    assert_eq!(
+        loaded.database_index_by_name(loaded.ui.selected_database.as_deref().unwrap()),
+        Some(0)
+    );
 }
 
 #[test]
@@ -41,6 +65,25 @@ fn test_utils_functions() {
     assert_eq!(format_file_size(1024), "1.00 KB");
     assert_eq!(format_file_size(1536), "1.50 KB");
     assert_eq!(format_file_size(1048576), "1.00 MB");
+    // 超过 i64 范围的大小不会变成负数
+    assert_eq!(format_file_size(u64::MAX), "16384.00 PB");
+
+    use netdisk_db::models::config::SizeUnits;
+    let size = split_file_size(59_570_941_009, SizeUnits::Binary);
+    assert_eq!((size.number('.').as_str(), size.unit), ("55.48", "GB"));
+    let size = split_file_size(1536, SizeUnits::Iec);
+    assert_eq!((size.number(',').as_str(), size.unit), ("1,50", "KiB"));
+    assert_eq!(split_file_size(1500, SizeUnits::Decimal).to_string(), "1.50 kB");
+    assert_eq!(split_file_size(999, SizeUnits::Decimal).to_string(), "999 B");
+
+    // 具体时间依赖本地时区，只检查格式
+    assert_eq!(format_timestamp(0), "-");
+    assert_eq!(format_timestamp(1640995200).len(), "2022-01-01 08:00".len());
+
+    assert_eq!(format_count(0), "0");
+    assert_eq!(format_count(999), "999");
+    assert_eq!(format_count(1234), "1,234");
+    assert_eq!(format_count(1234567), "1,234,567");
     
     assert!(file_exists("src/lib.rs"));
     assert!(!file_exists("non_existent_file.txt"));
@@ -72,4 +115,1573 @@ fn test_database_connector_factory() {
     // 测试不支持的数据库类型
     let invalid_connector = DatabaseConnectorFactory::create_connector("invalid");
     assert!(invalid_connector.is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn test_activity_log_recent() {
+    use netdisk_db::services::activity_log::{ActivityKind, ActivityLog};
+
+    let path = std::env::temp_dir().join("netdisk_db_activity_test.sqlite");
+    let _ = std::fs::remove_file(&path);
+    let log = ActivityLog::open(path.to_str().unwrap()).expect("Failed to open activity log");
+
+    let record = FileRecord {
+        id: 7,
+        path: "/videos/movie.mkv".to_string(),
+        size: 2048,
+        etag: "etag-1".to_string(),
+        modified_time: 1640995200,
+        file_type: "video/x-matroska".to_string(),
+        name: "movie.mkv".to_string(),
+    };
+    log.record(ActivityKind::CopiedLink, &record, "Video Database").unwrap();
+    log.record(ActivityKind::SentToAria2, &record, "Video Database").unwrap();
+
+    let recent = log.recent(10).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].kind, ActivityKind::SentToAria2);
+    assert_eq!(recent[0].record.etag, "etag-1");
+    assert_eq!(recent[1].database, "Video Database");
+    assert_eq!(log.recent(1).unwrap().len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_config_toml_round_trip() {
+    let path = std::env::temp_dir().join("netdisk_db_config_test.toml");
+    let path = path.to_str().unwrap();
+
+    let mut config = AppConfig::default();
+    config.window_width = 1024;
+    config.search.min_query_len = 3;
+    config.save_to_file(path).expect("Failed to save TOML config");
+
+    let content = std::fs::read_to_string(path).unwrap();
+    assert!(content.contains("window_width = 1024"));
+
+    let loaded = AppConfig::load_from_file(path).expect("Failed to load TOML config");
+    assert_eq!(loaded.window_width, 1024);
+    assert_eq!(loaded.search.min_query_len, 3);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_config_missing_fields_use_defaults() {
+    // 旧版本配置只包含部分字段
+    let config: AppConfig = serde_json::from_str(r#"{ "window_width": 1280 }"#).unwrap();
+    assert_eq!(config.window_width, 1280);
+    assert_eq!(config.window_height, 600);
+    assert_eq!(config.aria2.rpc_port, 6800);
+
+    let config: AppConfig = toml::from_str("window_height = 720\n[aria2]\nrpc_port = 6801\n").unwrap();
+    assert_eq!(config.window_height, 720);
+    assert_eq!(config.aria2.rpc_port, 6801);
+    assert!(config.aria2.enabled);
+}
+
+#[test]
+fn test_discover_sqlite_databases_with_depth() {
+    use netdisk_db::models::config::ScanConfig;
+
+    let base = std::env::temp_dir().join("netdisk_db_scan_test");
+    let nested = base.join("nested");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(base.join("top.db"), b"").unwrap();
+    std::fs::write(nested.join("inner.db"), b"").unwrap();
+    std::fs::write(base.join("notes.txt"), b"").unwrap();
+
+    let mut scan = ScanConfig {
+        directories: vec![base.to_string_lossy().to_string()],
+        max_depth: 0,
+    };
+    let names: Vec<String> = DatabaseManager::discover_sqlite_databases(&scan)
+        .into_iter()
+        .map(|db| db.name)
+        .collect();
+    assert_eq!(names, vec!["top".to_string()]);
+
+    // 同一目录重复配置时不会重复添加
+    scan.max_depth = 1;
+    scan.directories.push(base.to_string_lossy().to_string());
+    let mut names: Vec<String> = DatabaseManager::discover_sqlite_databases(&scan)
+        .into_iter()
+        .map(|db| db.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["inner".to_string(), "top".to_string()]);
+
+    let _ = std::fs::remove_dir_all(&base);
+}
+
+#[test]
+fn test_rescan_preserves_manual_databases() {
+    let mut config = AppConfig::default();
+    config.add_database(DatabaseConfig {
+        db_type: "mysql".to_string(),
+        connection_string: "mysql://root@localhost:3306/files".to_string(),
+        name: "Manual MySQL".to_string(),
+        description: None,
+        download_dir: None,
+        auto_discovered: false,
+        credential_id: None,
+        remote_folder_id: 0,
+        remote_folder_path: None,
+        path_mappings: Vec::new(),
+    });
+
+    let discovered = |name: &str| DatabaseConfig {
+        name: name.to_string(),
+        connection_string: format!("{}.db", name),
+        ..DatabaseConfig::default()
+    };
+
+    config.replace_discovered_databases(vec![discovered("videos"), discovered("music")]);
+    let names: Vec<&str> = config.database_list().iter().map(|db| db.name.as_str()).collect();
+    assert_eq!(names, vec!["Manual MySQL", "videos", "music"]);
+
+    // 选中的数据库在重新扫描后仍然保持选中
+    config.switch_database(2).unwrap();
+    config.replace_discovered_databases(vec![discovered("music")]);
+    let names: Vec<&str> = config.database_list().iter().map(|db| db.name.as_str()).collect();
+    assert_eq!(names, vec!["Manual MySQL", "music"]);
+    assert_eq!(config.database.name, "music");
+    assert!(config.database.auto_discovered);
+}
+
+#[test]
+fn test_connection_string_password_split() {
+    use netdisk_db::services::credentials::{insert_password, split_password};
+
+    let (url, password) = split_password("mysql://root:s3cr@t@localhost:3306/files");
+    assert_eq!(url, "mysql://root@localhost:3306/files");
+    assert_eq!(password.as_deref(), Some("s3cr@t"));
+    assert_eq!(insert_password(&url, "s3cr@t"), "mysql://root:s3cr@t@localhost:3306/files");
+
+    // 没有密码或不是 URL 形式时原样返回
+    assert_eq!(split_password("mysql://root@localhost/files").1, None);
+    assert_eq!(split_password("file_search.db").1, None);
+    assert_eq!(insert_password("file_search.db", "x"), "file_search.db");
+}
+
+#[test]
+fn test_config_migrates_legacy_shape() {
+    use netdisk_db::models::config::CURRENT_CONFIG_VERSION;
+
+    let path = std::env::temp_dir().join("netdisk_db_legacy_config.json");
+    let path = path.to_str().unwrap();
+    std::fs::write(
+        path,
+        r#"{ "database_path": "legacy.db", "window_width": 1024, "window_height": 768 }"#,
+    )
+    .unwrap();
+
+    let (config, migrated) = AppConfig::load_and_migrate(path).expect("Failed to migrate config");
+    assert!(migrated);
+    assert_eq!(config.config_version, CURRENT_CONFIG_VERSION);
+    assert_eq!(config.database.connection_string, "legacy.db");
+    assert_eq!(config.database_list().len(), 1);
+    assert_eq!(config.database_list()[0].connection_string, "legacy.db");
+    assert_eq!(config.window_width, 1024);
+    assert!(config.aria2.enabled);
+
+    // 当前版本的配置不需要升级
+    config.save_to_file(path).unwrap();
+    let (_, migrated) = AppConfig::load_and_migrate(path).unwrap();
+    assert!(!migrated);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_backend_config_base_url() {
+    use netdisk_db::models::config::BackendConfig;
+
+    let mut backend = BackendConfig::default();
+    assert_eq!(backend.port, 8080);
+    assert_eq!(backend.base_url(8081), "http://127.0.0.1:8081");
+
+    // 监听所有地址时客户端通过本机回环地址访问
+    backend.host = "0.0.0.0".to_string();
+    assert_eq!(backend.base_url(9000), "http://127.0.0.1:9000");
+}
+
+#[test]
+fn test_event_bus_serializes_events() {
+    use netdisk_db::services::events::{self, AppEvent};
+
+    let mut receiver = events::subscribe();
+    events::publish(AppEvent::DownloadStatus {
+        gid: "abc".to_string(),
+        path: "/movies/a.mkv".to_string(),
+        status: "complete".to_string(),
+    });
+
+    let event = receiver.try_recv().expect("Event not delivered");
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["type"], "download-status");
+    assert_eq!(json["gid"], "abc");
+    assert_eq!(json["status"], "complete");
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_registered_tasks() {
+    use netdisk_db::services::shutdown::ShutdownCoordinator;
+    use std::time::Duration;
+
+    let coordinator = ShutdownCoordinator::new();
+    let mut receiver = coordinator.subscribe();
+    let finished = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = finished.clone();
+    coordinator.register(
+        "worker",
+        tokio::spawn(async move {
+            let _ = receiver.recv().await;
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+        }),
+    );
+
+    // 不响应退出通知的任务在超时后被中止
+    coordinator.register("stuck", tokio::spawn(std::future::pending::<()>()));
+
+    coordinator.shutdown(Duration::from_millis(200)).await;
+    assert!(coordinator.is_shutting_down());
+    assert!(finished.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[test]
+fn test_metrics_export() {
+    use netdisk_db::services::metrics;
+    use std::time::Duration;
+
+    metrics::observe_search(Duration::from_millis(12), 42);
+    metrics::ARIA2_RPC_ERRORS.with_label_values(&["aria2.addUri"]).inc();
+
+    let text = metrics::gather();
+    assert!(text.contains("netdisk_search_duration_seconds_count"));
+    assert!(text.contains("netdisk_search_results_bucket"));
+    assert!(text.contains("netdisk_aria2_rpc_errors_total{method=\"aria2.addUri\"}"));
+    assert!(text.contains("netdisk_active_downloads"));
+}
+
+#[tokio::test]
+async fn test_job_queue_status_and_cancel() {
+    use netdisk_db::services::jobs::{JobQueue, JobStatus};
+    use std::time::Duration;
+
+    let queue = JobQueue::new(tokio::runtime::Handle::current(), 1);
+
+    let done = queue.submit("ok", |ctx| async move {
+        ctx.set_progress("half");
+        Ok(())
+    });
+    let failed = queue.submit("fail", |_ctx| async { anyhow::bail!("boom") });
+    let cancelled = queue.submit("cancel", |ctx| async move {
+        while !ctx.is_cancelled() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        Ok(())
+    });
+    assert!(queue.cancel(cancelled));
+
+    for _ in 0..200 {
+        if queue.active_count() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let done = queue.get(done).unwrap();
+    assert_eq!(done.status, JobStatus::Completed);
+    assert_eq!(done.progress, "half");
+    assert_eq!(queue.get(failed).unwrap().status, JobStatus::Failed("boom".into()));
+    assert_eq!(queue.get(cancelled).unwrap().status, JobStatus::Cancelled);
+    assert!(!queue.cancel(cancelled));
+    assert_eq!(queue.list().len(), 3);
+}
+
+#[tokio::test]
+async fn test_token_manager_refresh() {
+    use netdisk_core::responses::prelude::AccessToken;
+    use netdisk_db::services::token_manager::{TokenManager, TokenSource};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct FlakySource(Arc<AtomicUsize>);
+
+    impl TokenSource for FlakySource {
+        async fn fetch(&self) -> anyhow::Result<AccessToken> {
+            match self.0.fetch_add(1, Ordering::SeqCst) {
+                0 => anyhow::bail!("network down"),
+                _ => Ok(AccessToken::default()),
+            }
+        }
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let manager = Arc::new(TokenManager::new(
+        FlakySource(calls.clone()),
+        Duration::from_millis(10),
+    ));
+    assert!(manager.refresh().await.is_err());
+    assert!(manager.refresh().await.is_ok());
+
+    // 后台刷新按间隔执行，收到退出通知后停止
+    let (stop, receiver) = tokio::sync::broadcast::channel(1);
+    let task = tokio::spawn(manager.clone().run(receiver));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stop.send(()).unwrap();
+    task.await.unwrap();
+    assert!(calls.load(Ordering::SeqCst) > 2);
+}
+
+#[test]
+fn test_token_refresh_schedule() {
+    use netdisk_db::services::token_manager::{refresh_delay, token_lifetime};
+    use serde_json::json;
+    use std::time::Duration;
+
+    let now = 1_700_000_000;
+    assert_eq!(
+        token_lifetime(&json!({ "accessToken": "a", "expires_in": 7200 }), now),
+        Some(Duration::from_secs(7200))
+    );
+    assert_eq!(
+        token_lifetime(&json!({ "accessToken": "a", "expiredAt": "2023-11-14T22:30:00+00:00" }), now),
+        Some(Duration::from_secs(1000))
+    );
+    assert_eq!(token_lifetime(&json!({ "expired_at": now - 10 }), now), Some(Duration::ZERO));
+    // 没有有效期或为空时未知
+    assert_eq!(token_lifetime(&json!({ "accessToken": "a", "expiredAt": "" }), now), None);
+    assert_eq!(token_lifetime(&json!({ "expires_in": 0 }), now), None);
+
+    // 有效期短于固定间隔时按有效期提前刷新
+    let interval = Duration::from_secs(1800);
+    assert_eq!(refresh_delay(None, interval), interval);
+    assert_eq!(refresh_delay(Some(Duration::from_secs(7200)), interval), Duration::from_secs(6900));
+    assert_eq!(refresh_delay(Some(Duration::from_secs(600)), interval), Duration::from_secs(480));
+    assert_eq!(refresh_delay(Some(Duration::ZERO), interval), Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn test_remote_sync_incremental() {
+    use netdisk_db::models::database::Database;
+    use netdisk_db::services::api_client::{FileListPage, RemoteFile};
+    use netdisk_db::services::database::sqlite::SqliteDatabase;
+    use netdisk_db::services::remote_sync::{RemoteLister, RemoteSync};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    struct MockLister(Mutex<HashMap<i64, Vec<RemoteFile>>>);
+
+    impl RemoteLister for MockLister {
+        async fn list_page(&self, parent_id: i64, _last_file_id: i64) -> anyhow::Result<FileListPage> {
+            Ok(FileListPage {
+                last_file_id: -1,
+                file_list: self.0.lock().unwrap().get(&parent_id).cloned().unwrap_or_default(),
+            })
+        }
+    }
+
+    fn remote(file_id: i64, filename: &str, kind: i32, etag: &str) -> RemoteFile {
+        RemoteFile {
+            file_id,
+            filename: filename.to_string(),
+            kind,
+            size: 100,
+            etag: etag.to_string(),
+            trashed: 0,
+            update_at: "2024-01-15 10:30:00".to_string(),
+        }
+    }
+
+    let db_path = std::env::temp_dir().join("netdisk_db_remote_sync_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).unwrap();
+    db.init_database().unwrap();
+    let db = Arc::new(Mutex::new(db));
+
+    let lister = Arc::new(MockLister(Mutex::new(HashMap::from([
+        (0, vec![remote(1, "Movies", 1, ""), remote(2, "a.txt", 0, "e1")]),
+        (1, vec![remote(3, "b.mkv", 0, "e2")]),
+    ]))));
+    let sync = RemoteSync::new(lister.clone(), db.clone());
+
+    let summary = sync.sync(0, "/cloud", |_| {}).await.unwrap();
+    assert_eq!((summary.added, summary.updated, summary.removed), (2, 0, 0));
+
+    // 修改一个文件、删除一个文件后再次同步
+    {
+        let mut folders = lister.0.lock().unwrap();
+        folders.insert(0, vec![remote(1, "Movies", 1, "")]);
+        folders.insert(1, vec![remote(3, "b.mkv", 0, "e3")]);
+    }
+    let summary = sync.sync(0, "/cloud", |_| {}).await.unwrap();
+    assert_eq!((summary.added, summary.updated, summary.removed), (0, 1, 1));
+
+    let etags = db.lock().unwrap().etags_under("/cloud/").unwrap();
+    assert_eq!(etags.len(), 1);
+    assert_eq!(etags["/cloud/Movies/b.mkv"], "e3");
+    // 示例数据不在同步路径下，不受影响
+    assert_eq!(db.lock().unwrap().search_files("/home/user").unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_pending_operations_retry() {
+    use netdisk_db::services::api_client::LinkError;
+    use netdisk_db::services::pending_ops::{PendingKind, PendingOperations, MAX_ATTEMPTS};
+
+    let path = std::env::temp_dir().join("netdisk_db_pending_ops_test.sqlite");
+    let _ = std::fs::remove_file(&path);
+    let pending = PendingOperations::open(path.to_str().unwrap()).expect("Failed to open pending operations");
+
+    assert!(pending.enqueue(PendingKind::ResolveLink, "/a.mkv", "e1", 10, None).unwrap());
+    // 同一文件的同类操作只保留一条
+    assert!(!pending.enqueue(PendingKind::ResolveLink, "/a.mkv", "e1", 10, None).unwrap());
+    assert!(pending.enqueue(PendingKind::Download, "/b.mkv", "e2", 20, Some("/downloads")).unwrap());
+    assert_eq!(pending.count().unwrap(), 2);
+
+    // 仍然无法访问时停止本轮重试，操作保留在队列中
+    let summary = pending
+        .retry_all(|_| async {
+            Err(anyhow::Error::new(LinkError::Status {
+                status: 503,
+                body: String::new(),
+            }))
+        })
+        .await
+        .unwrap();
+    assert!(summary.offline);
+    assert_eq!(summary.remaining, 2);
+    let operations = pending.list().unwrap();
+    assert_eq!(operations[0].attempts, 1);
+    assert_eq!(operations[1].attempts, 0);
+    assert_eq!(operations[1].target_dir.as_deref(), Some("/downloads"));
+
+    // 链接获取成功，下载因其他原因反复失败，超过重试次数后移除
+    for _ in 0..MAX_ATTEMPTS {
+        pending
+            .retry_all(|operation| async move {
+                match operation.kind {
+                    PendingKind::ResolveLink => Ok(()),
+                    PendingKind::Download => Err(anyhow::anyhow!("aria2 rejected the task")),
+                }
+            })
+            .await
+            .unwrap();
+    }
+    assert_eq!(pending.count().unwrap(), 0);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "gui")]
+#[tokio::test]
+async fn test_remote_folder_selection() {
+    use netdisk_db::services::api_client::{FileListPage, RemoteFile};
+    use netdisk_db::services::remote_sync::{list_folders, RemoteLister};
+    use netdisk_db::views::ui::RemoteFolderBrowser;
+
+    struct PagedLister;
+
+    impl RemoteLister for PagedLister {
+        async fn list_page(&self, _parent_id: i64, last_file_id: i64) -> anyhow::Result<FileListPage> {
+            let folder = |file_id: i64, filename: &str, kind: i32, trashed: i32| RemoteFile {
+                file_id,
+                filename: filename.to_string(),
+                kind,
+                size: 0,
+                etag: String::new(),
+                trashed,
+                update_at: String::new(),
+            };
+            Ok(match last_file_id {
+                0 => FileListPage {
+                    last_file_id: 3,
+                    file_list: vec![folder(2, "电影", 1, 0), folder(3, "a.txt", 0, 0)],
+                },
+                _ => FileListPage {
+                    last_file_id: -1,
+                    file_list: vec![folder(4, "剧集", 1, 0), folder(5, "旧文件夹", 1, 1)],
+                },
+            })
+        }
+    }
+
+    // 跨页收集，只保留未删除的文件夹
+    let folders = list_folders(&PagedLister, 0).await.unwrap();
+    let names: Vec<&str> = folders.iter().map(|folder| folder.filename.as_str()).collect();
+    assert_eq!(names, vec!["剧集", "电影"]);
+
+    let mut browser = RemoteFolderBrowser::default();
+    assert_eq!((browser.current_id(), browser.path()), (0, "/".to_string()));
+    browser.enter(2, "电影");
+    browser.enter(7, "2024");
+    assert_eq!((browser.current_id(), browser.path()), (7, "/电影/2024".to_string()));
+    assert!(browser.up());
+    assert_eq!(browser.current_id(), 2);
+    browser.reset();
+    assert!(!browser.up());
+
+    // 网盘文件夹保存在当前数据库的配置中
+    let mut config = AppConfig::default();
+    config.set_remote_folder(2, Some("/电影".to_string()));
+    assert_eq!(config.database.remote_folder_id, 2);
+    assert_eq!(config.database_list()[0].remote_folder_id, 2);
+    assert_eq!(config.database_list()[0].remote_folder_path.as_deref(), Some("/电影"));
+}
+
+#[cfg(feature = "clipboard")]
+#[tokio::test]
+async fn test_clipboard_worker() {
+    use netdisk_db::services::clipboard::{ClipboardBackend, ClipboardCommand, ClipboardHandle};
+    use std::sync::{Arc, Mutex};
+
+    struct MockClipboard {
+        contents: Arc<Mutex<Vec<String>>>,
+        failures: usize, // 前几次写入失败
+    }
+
+    impl ClipboardBackend for MockClipboard {
+        fn set_text(&mut self, text: &str) -> Result<(), String> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err("clipboard busy".to_string());
+            }
+            self.contents.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    let contents = Arc::new(Mutex::new(Vec::new()));
+    let created = Arc::new(Mutex::new(0));
+    let clipboard = ClipboardHandle::spawn_with({
+        let contents = contents.clone();
+        let created = created.clone();
+        move || {
+            let mut created = created.lock().unwrap();
+            *created += 1;
+            // 只有第一次创建的剪切板写入失败
+            Ok(MockClipboard {
+                contents: contents.clone(),
+                failures: if *created == 1 { 1 } else { 0 },
+            })
+        }
+    });
+
+    // 写入失败时重新创建剪切板后重试
+    clipboard.set_text("https://example.com/a").await.unwrap();
+    clipboard.send(ClipboardCommand::SetText("https://example.com/b".to_string())).unwrap();
+    clipboard.clone().set_text("https://example.com/c").await.unwrap();
+    assert_eq!(
+        *contents.lock().unwrap(),
+        vec!["https://example.com/a", "https://example.com/b", "https://example.com/c"]
+    );
+    assert_eq!(*created.lock().unwrap(), 2);
+
+    // 成功写入的内容记录在剪切板历史中，最新的在前，重复内容移到最前
+    clipboard.set_text("https://example.com/a").await.unwrap();
+    let history: Vec<String> = clipboard.history().recent().into_iter().map(|entry| entry.text).collect();
+    assert_eq!(history, vec!["https://example.com/a", "https://example.com/c", "https://example.com/b"]);
+    assert_eq!(clipboard.history().get(1).unwrap().text, "https://example.com/c");
+    assert!(clipboard.history().get(3).is_none());
+
+    // 无法打开剪切板时返回错误而不是阻塞，也不记录历史
+    let unavailable = ClipboardHandle::spawn_with(|| Err::<MockClipboard, _>("no display".to_string()));
+    let error = unavailable.set_text("x").await.unwrap_err();
+    assert!(error.to_string().contains("no display"));
+    assert!(unavailable.history().recent().is_empty());
+}
+
+#[cfg(feature = "clipboard")]
+#[test]
+fn test_clipboard_history_capacity() {
+    use netdisk_db::services::clipboard::ClipboardHistory;
+
+    let history = ClipboardHistory::new(3);
+    for i in 0..5 {
+        history.record(&format!("https://example.com/{}", i));
+    }
+    history.record("  ");
+    let texts: Vec<String> = history.recent().into_iter().map(|entry| entry.text).collect();
+    assert_eq!(texts, vec!["https://example.com/4", "https://example.com/3", "https://example.com/2"]);
+
+    history.record("https://example.com/a\nhttps://example.com/b\n");
+    assert_eq!(history.recent()[0].summary(), "https://example.com/a（共 2 行）");
+
+    history.clear();
+    assert!(history.recent().is_empty());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_file_item_size_parsing() {
+    use netdisk_db::views::ui::{file_item_size, file_item_to_record, file_items_to_records, FileItem};
+
+    let item = |path: &str, size: &str| FileItem {
+        path: path.into(),
+        etag: "etag".into(),
+        size_bytes: size.into(),
+        ..Default::default()
+    };
+
+    assert_eq!(file_item_size(&item("/a.mkv", " 59570941009 ")).unwrap(), 59_570_941_009);
+    let error = file_item_size(&item("/b.mkv", "")).unwrap_err();
+    assert!(error.to_string().contains("/b.mkv"));
+
+    // 无效的大小不会导致崩溃，批量转换时跳过
+    let (records, invalid) = file_items_to_records(&[item("/a.mkv", "10"), item("/b.mkv", "abc")]);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].size, 10);
+    assert_eq!(invalid.len(), 1);
+    assert_eq!(file_item_to_record(&item("/b.mkv", "abc")).size, 0);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_open_missing_file_reports_error() {
+    let error = netdisk_db::handle_open_file("/non/existent dir/file & name.mp4").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    assert!(error.to_string().contains("file & name.mp4"));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_file_uri_encoding() {
+    use netdisk_db::controllers::handlers::file_uri;
+    use std::path::Path;
+
+    assert_eq!(file_uri(Path::new("/home/user/a.mp4")), "file:///home/user/a.mp4");
+    assert_eq!(
+        file_uri(Path::new("/home/user/my file#1&视频.mp4")),
+        "file:///home/user/my%20file%231%26%E8%A7%86%E9%A2%91.mp4"
+    );
+    assert!(netdisk_db::handle_open_file_location("/non/existent/file.mp4").is_err());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_context_menu_position_clamped() {
+    use netdisk_db::views::ui::clamp_menu_position;
+
+    let window = (800.0, 600.0);
+    let menu = (150.0, 300.0);
+    // 空间足够时菜单在点击位置右下方展开
+    assert_eq!(clamp_menu_position((100.0, 100.0), menu, window), (100.0, 100.0));
+    // 靠近右下角时向左上展开
+    assert_eq!(clamp_menu_position((780.0, 590.0), menu, window), (630.0, 290.0));
+    // 两侧空间都不足时贴靠窗口边缘
+    assert_eq!(clamp_menu_position((100.0, 200.0), menu, (800.0, 350.0)), (100.0, 50.0));
+    assert_eq!(clamp_menu_position((50.0, 50.0), menu, (100.0, 100.0)), (0.0, 0.0));
+}
+
+#[tokio::test]
+async fn test_custom_context_menu_actions() {
+    use netdisk_db::controllers::context_menu::ContextMenuManager;
+
+    let record = |path: &str, etag: &str| FileRecord {
+        id: 1,
+        path: path.to_string(),
+        size: 10,
+        etag: etag.to_string(),
+        modified_time: 0,
+        file_type: "mp4".to_string(),
+        name: path.rsplit('/').next().unwrap_or(path).to_string(),
+    };
+
+    let menu = ContextMenuManager::builder()
+        .action("统计", |records| async move { anyhow::Ok(format!("{} 个文件", records.len())) })
+        .action_when(
+            "复制 ETag",
+            |records| records.iter().all(|r| !r.etag.is_empty()),
+            |records| async move {
+                anyhow::Ok(records.into_iter().map(|r| r.etag).collect::<Vec<_>>().join(","))
+            },
+        )
+        .action("失败", |_| async move { Err::<String, _>(anyhow::anyhow!("not supported")) })
+        .build();
+    assert_eq!(menu.actions().len(), 3);
+
+    // 没有 ETag 的文件不显示"复制 ETag"
+    let without_etag = vec![record("/a.mp4", "")];
+    let labels: Vec<&str> = menu
+        .available_actions(&without_etag)
+        .into_iter()
+        .map(|(_, action)| action.label())
+        .collect();
+    assert_eq!(labels, vec!["统计", "失败"]);
+    assert!(menu.available_actions(&[]).is_empty());
+
+    let records = vec![record("/a.mp4", "e1"), record("/b.mp4", "e2")];
+    assert_eq!(menu.run(0, records.clone()).unwrap().await.unwrap(), "2 个文件");
+    assert_eq!(menu.run(1, records.clone()).unwrap().await.unwrap(), "e1,e2");
+    assert!(menu.run(2, records.clone()).unwrap().await.is_err());
+    assert!(menu.run(3, records).is_none());
+}
+
+#[test]
+fn test_checksum_verification() {
+    use netdisk_db::services::checksum::{file_md5, ChecksumVerifier};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    let root = std::env::temp_dir().join("netdisk_db_checksum_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let good = root.join("good.txt");
+    let bad = root.join("bad.txt");
+    std::fs::write(&good, b"hello").unwrap();
+    std::fs::write(&bad, b"corrupted").unwrap();
+
+    let cancel = AtomicBool::new(false);
+    let hello_md5 = "5d41402abc4b2a76b9719d911017c592";
+    assert_eq!(file_md5(&good, &cancel).unwrap(), hello_md5);
+
+    let db_path = root.join("checksum.db");
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    let record = |path: &std::path::Path, etag: &str| FileRecord {
+        id: 0,
+        path: path.to_string_lossy().to_string(),
+        size: 5,
+        etag: etag.to_string(),
+        modified_time: 0,
+        file_type: "txt".to_string(),
+        name: path.file_name().unwrap().to_string_lossy().to_string(),
+    };
+    let records = vec![
+        record(&good, &hello_md5.to_uppercase()),
+        record(&bad, hello_md5),
+        record(&root.join("missing.txt"), hello_md5),
+    ];
+    db.insert_files(&records).unwrap();
+
+    let database: Arc<Mutex<dyn Database>> = Arc::new(Mutex::new(db));
+    let mut reported = 0;
+    let summary = ChecksumVerifier::new(database.clone())
+        .verify(&records, &cancel, |done, total| {
+            assert_eq!(total, 3);
+            reported = done;
+        })
+        .unwrap();
+    assert_eq!((summary.matched, summary.mismatched, summary.skipped), (1, 1, 1));
+    assert_eq!(reported, 3);
+
+    let paths: Vec<String> = records.iter().map(|r| r.path.clone()).collect();
+    let status = database.lock().unwrap().verification_status(&paths).unwrap();
+    assert_eq!(status.get(&paths[0]), Some(&true));
+    assert_eq!(status.get(&paths[1]), Some(&false));
+    assert_eq!(status.get(&paths[2]), None);
+
+    // 其他系统上生成的数据库按路径映射读取本机文件
+    let windows_record = FileRecord {
+        path: r"D:\media\good.txt".to_string(),
+        ..record(&good, hello_md5)
+    };
+    let mappings = vec![netdisk_db::models::config::PathMapping {
+        from: r"D:\media".to_string(),
+        to: root.to_string_lossy().to_string(),
+    }];
+    let summary = ChecksumVerifier::new(database.clone())
+        .with_path_mappings(mappings)
+        .verify(std::slice::from_ref(&windows_record), &cancel, |_, _| {})
+        .unwrap();
+    assert_eq!((summary.matched, summary.skipped), (1, 0));
+
+    drop(database);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_config_backup_fallback() {
+    let root = std::env::temp_dir().join("netdisk_db_config_backup_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join("config.json");
+    let path = path.to_str().unwrap();
+
+    let mut config = AppConfig::default();
+    config.window_width = 1024;
+    config.save_to_file(path).unwrap();
+    // 第一次保存时还没有可备份的配置
+    assert!(!std::path::Path::new(&AppConfig::backup_path(path)).exists());
+    config.window_width = 1280;
+    config.save_to_file(path).unwrap();
+
+    // 配置文件损坏时使用上次保存前的备份，并提示重新保存
+    std::fs::write(path, "{ not json").unwrap();
+    let (loaded, needs_save) = AppConfig::load_with_fallback(path).unwrap();
+    assert_eq!(loaded.window_width, 1024);
+    assert!(needs_save);
+
+    // 损坏的配置不会覆盖备份
+    loaded.save_to_file(path).unwrap();
+    let (backup, _) = AppConfig::load_and_migrate(&AppConfig::backup_path(path)).unwrap();
+    assert_eq!(backup.window_width, 1024);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_random_file() {
+    use netdisk_db::models::database::SearchOptions;
+    use netdisk_db::utils::filetype::FileCategory;
+
+    let root = std::env::temp_dir().join("netdisk_db_random_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db = SqliteDatabase::new(root.join("index.db").to_str().unwrap()).unwrap();
+    db.init_database().unwrap();
+    let all: Vec<i64> = db.search_files("").unwrap().into_iter().map(|r| r.id).collect();
+
+    // 不加条件时按 id 范围选取，结果总是已有的记录
+    for _ in 0..20 {
+        let record = db.random_file("", &SearchOptions::default()).unwrap().unwrap();
+        assert!(all.contains(&record.id));
+    }
+
+    // 加条件时只在匹配的记录中选取
+    for _ in 0..10 {
+        let record = db.random_file("documents", &SearchOptions::default()).unwrap().unwrap();
+        assert!(record.path.contains("documents"));
+    }
+    let images = SearchOptions {
+        categories: vec![FileCategory::Image],
+        ..SearchOptions::default()
+    };
+    assert_eq!(db.random_file("", &images).unwrap().unwrap().path, "/home/user/pictures/image.jpg");
+    assert!(db.random_file("no-such-file", &SearchOptions::default()).unwrap().is_none());
+
+    db.delete_paths(&db.search_files("").unwrap().into_iter().map(|r| r.path).collect::<Vec<_>>())
+        .unwrap();
+    assert!(db.random_file("", &SearchOptions::default()).unwrap().is_none());
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_find_file_by_id() {
+    let root = std::env::temp_dir().join("netdisk_db_find_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db = SqliteDatabase::new(root.join("index.db").to_str().unwrap()).unwrap();
+    db.init_database().unwrap();
+
+    // 插入足够多的记录，使 id 1 也是 10、11 等 id 的子串
+    let records: Vec<FileRecord> = (0..12)
+        .map(|i| FileRecord {
+            id: 0,
+            path: format!("/find/{}.txt", i),
+            size: 1,
+            etag: format!("etag{}", i),
+            modified_time: 0,
+            file_type: "txt".to_string(),
+            name: format!("{}.txt", i),
+        })
+        .collect();
+    db.insert_files(&records).unwrap();
+    let ids: Vec<i64> = db.search_files("/find/").unwrap().into_iter().map(|r| r.id).collect();
+    assert!(ids.iter().any(|id| *id >= 10));
+
+    for id in ids {
+        assert_eq!(db.find_file(id).unwrap().unwrap().id, id);
+    }
+    assert_eq!(db.find_file(1).unwrap().unwrap().id, 1);
+    assert!(db.find_file(i64::MAX).unwrap().is_none());
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_sqlite_backup_and_restore() {
+    use netdisk_db::models::database::SearchOptions;
+    use netdisk_db::services::database::sqlite::validate_backup;
+
+    let root = std::env::temp_dir().join("netdisk_db_backup_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db = SqliteDatabase::new(root.join("index.db").to_str().unwrap()).unwrap();
+    db.init_database().unwrap();
+    let original = db.count_files("", &SearchOptions::default()).unwrap();
+
+    let backup = root.join("backups").join("index-1.bak");
+    db.backup_to(&backup).expect("Failed to back up database");
+    assert_eq!(validate_backup(&backup).unwrap(), original);
+
+    db.delete_paths(&db.search_files("").unwrap().into_iter().map(|r| r.path).collect::<Vec<_>>())
+        .unwrap();
+    assert_eq!(db.count_files("", &SearchOptions::default()).unwrap(), 0);
+
+    db.restore_from(&backup).expect("Failed to restore database");
+    assert_eq!(db.count_files("", &SearchOptions::default()).unwrap(), original);
+
+    // 损坏的备份不会覆盖现有数据
+    let corrupted = root.join("corrupted.bak");
+    std::fs::write(&corrupted, b"not a database").unwrap();
+    assert!(validate_backup(&corrupted).is_err());
+    assert!(db.restore_from(&corrupted).is_err());
+    assert!(validate_backup(&root.join("missing.bak")).is_err());
+    assert_eq!(db.count_files("", &SearchOptions::default()).unwrap(), original);
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_logging_config() {
+    use netdisk_db::models::config::{LogFormat, LoggingConfig};
+    use netdisk_db::services::logging::filter_directives;
+
+    // 旧配置文件没有 logging 时使用默认值
+    let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+    value.as_object_mut().unwrap().remove("logging");
+    let config: AppConfig = serde_json::from_value(value).unwrap();
+    assert_eq!(config.logging.level, "info");
+    assert_eq!(config.logging.format, LogFormat::Pretty);
+    assert!(config.logging.file);
+
+    let logging: LoggingConfig = serde_json::from_str(
+        r#"{ "level": "warn", "modules": { "actix_web": "error", "netdisk_db": "info" }, "format": "json" }"#,
+    )
+    .unwrap();
+    assert_eq!(logging.format, LogFormat::Json);
+    assert_eq!(logging.max_files, 7);
+    assert_eq!(filter_directives(&logging, false), "warn,actix_web=error,netdisk_db=info");
+    // --verbose 覆盖配置中本程序的级别
+    assert_eq!(filter_directives(&logging, true), "warn,actix_web=error,netdisk_db=debug");
+}
+
+#[test]
+fn test_portable_path_mapping() {
+    use netdisk_db::utils::paths::{relative_path_in, resolve_path_in};
+
+    let root = std::env::temp_dir().join("netdisk_db_portable");
+    let inside = root.join("data").join("files.db").to_string_lossy().into_owned();
+    let outside = std::env::temp_dir().join("other.db").to_string_lossy().into_owned();
+
+    assert_eq!(relative_path_in(&root, &inside), std::path::Path::new("data").join("files.db").to_string_lossy());
+    assert_eq!(relative_path_in(&root, &outside), outside);
+    assert_eq!(relative_path_in(&root, &root.to_string_lossy()), ".");
+    assert_eq!(resolve_path_in(&root, &relative_path_in(&root, &inside)), inside);
+    assert_eq!(resolve_path_in(&root, &outside), outside);
+    assert_eq!(resolve_path_in(&root, ""), "");
+
+    // 只转换 SQLite 数据库的路径，MySQL 连接字符串保持不变
+    let mut config = AppConfig::default();
+    config.aria2.download_dir = root.join("downloads").to_string_lossy().into_owned();
+    config.multi_database.databases = vec![
+        DatabaseConfig {
+            connection_string: inside.clone(),
+            ..DatabaseConfig::default()
+        },
+        DatabaseConfig {
+            db_type: "mysql".to_string(),
+            connection_string: "mysql://user@localhost/files".to_string(),
+            ..DatabaseConfig::default()
+        },
+    ];
+    config.map_paths(|path| relative_path_in(&root, path));
+    assert_eq!(config.aria2.download_dir, "downloads");
+    assert!(!std::path::Path::new(&config.multi_database.databases[0].connection_string).is_absolute());
+    assert_eq!(config.multi_database.databases[1].connection_string, "mysql://user@localhost/files");
+
+    config.map_paths(|path| resolve_path_in(&root, path));
+    assert_eq!(config.multi_database.databases[0].connection_string, inside);
+}
+
+#[test]
+fn test_update_version_comparison() {
+    use netdisk_db::services::update_check::{is_newer, Version};
+
+    assert_eq!(
+        Version::parse("v1.2.3-beta.1+build5"),
+        Some(Version { major: 1, minor: 2, patch: 3, pre: Some("beta.1".to_string()) })
+    );
+    assert_eq!(Version::parse("2").map(|v| (v.major, v.minor, v.patch)), Some((2, 0, 0)));
+    assert_eq!(Version::parse("1.2.x"), None);
+
+    assert!(is_newer("v0.2.0", "0.1.9"));
+    assert!(is_newer("0.10.0", "0.9.0"));
+    assert!(is_newer("1.0.0", "1.0.0-rc.1"));
+    assert!(!is_newer("1.0.0-rc.1", "1.0.0"));
+    assert!(!is_newer("0.1.0", "0.1.0"));
+    assert!(!is_newer("nightly", "0.1.0"));
+}
+
+#[test]
+fn test_locale_formatting() {
+    use netdisk_db::models::config::{LocaleConfig, SizeUnits};
+    use netdisk_db::utils::locale::{is_valid_date_format, Locale};
+
+    let en = Locale::from_tag("en_US.UTF-8");
+    assert_eq!(en.format_size(512), "512 B");
+    assert_eq!(en.format_size(1536), "1.50 KB");
+    assert_eq!(en.date_format, "%m/%d/%Y %H:%M");
+
+    let de = Locale::from_tag("de-DE");
+    assert_eq!(de.format_size(1536), "1,50 KB");
+    assert_eq!(de.date_format, "%d.%m.%Y %H:%M");
+    assert_eq!(de.format_timestamp(0), "-");
+    assert_eq!(de.format_timestamp(1640995200).len(), "01.01.2022 08:00".len());
+
+    assert_eq!(Locale::from_tag("").date_format, "%Y-%m-%d %H:%M");
+    assert_eq!(Locale::from_tag("zh_CN.UTF-8").decimal_separator, '.');
+
+    let config = LocaleConfig {
+        language: Some("fr_FR".to_string()),
+        size_units: SizeUnits::Decimal,
+        date_format: Some("%Y".to_string()),
+    };
+    let fr = Locale::from_config(&config);
+    assert_eq!(fr.format_size(1500), "1,50 kB");
+    assert_eq!(fr.format_size(1_000_000), "1,00 MB");
+    assert_eq!(fr.date_format, "%Y");
+
+    // 无效的日期格式被忽略
+    assert!(!is_valid_date_format("%Q"));
+    let invalid = Locale::from_config(&LocaleConfig {
+        date_format: Some("%Q".to_string()),
+        ..config
+    });
+    assert_eq!(invalid.date_format, "%d/%m/%Y %H:%M");
+}
+
+#[test]
+fn test_timestamp_normalization() {
+    use netdisk_db::utils::time::{normalize_timestamp, parse_timestamp};
+
+    assert_eq!(normalize_timestamp(1705314600), 1705314600);
+    assert_eq!(normalize_timestamp(1705314600123), 1705314600);
+    assert_eq!(normalize_timestamp(1705314600123456), 1705314600);
+    assert_eq!(normalize_timestamp(-1), 0);
+
+    assert_eq!(parse_timestamp("1705314600"), Some(1705314600));
+    assert_eq!(parse_timestamp(" 1705314600123 "), Some(1705314600));
+    assert_eq!(parse_timestamp("2024-01-15 10:30:00"), Some(1705314600));
+    assert_eq!(parse_timestamp("2024-01-15T10:30:00"), Some(1705314600));
+    assert_eq!(parse_timestamp("2024-01-15 10:30:00.250"), Some(1705314600));
+    assert_eq!(parse_timestamp("2024-01-15T18:30:00+08:00"), Some(1705314600));
+    assert_eq!(parse_timestamp("2024-01-15"), Some(1705276800));
+    assert_eq!(parse_timestamp("yesterday"), None);
+    assert_eq!(parse_timestamp(""), None);
+
+    // 外部数据库中混合存储的修改时间读取时统一为秒
+    let db_path = std::env::temp_dir().join("netdisk_db_timestamp_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).unwrap();
+    db.init_database().unwrap();
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        for (name, modified) in [
+            ("seconds.mkv", rusqlite::types::Value::Integer(1705314600)),
+            ("millis.mkv", rusqlite::types::Value::Integer(1705314600000)),
+            ("text.mkv", rusqlite::types::Value::Text("2024-01-15 10:30:00".to_string())),
+            ("invalid.mkv", rusqlite::types::Value::Text("unknown".to_string())),
+        ] {
+            conn.execute(
+                "INSERT INTO video (name, path, size, etag, modified_time, file_type) VALUES (?1, ?2, 1, 'e', ?3, 'mkv')",
+                rusqlite::params![name, format!("/ts/{}", name), modified],
+            )
+            .unwrap();
+        }
+    }
+    let records = db.search_files("/ts/").unwrap();
+    let modified = |name: &str| records.iter().find(|r| r.name == name).unwrap().modified_time;
+    assert_eq!(modified("seconds.mkv"), 1705314600);
+    assert_eq!(modified("millis.mkv"), 1705314600);
+    assert_eq!(modified("text.mkv"), 1705314600);
+    assert_eq!(modified("invalid.mkv"), 0);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_path_mapping() {
+    use netdisk_db::models::config::PathMapping;
+    use netdisk_db::utils::path_mapping::{is_windows_path, map_path};
+
+    let mapping = |from: &str, to: &str| PathMapping { from: from.to_string(), to: to.to_string() };
+    let mappings = vec![
+        mapping("D:\\media", "/mnt/media"),
+        mapping("D:\\media\\movies", "/mnt/movies"),
+        mapping("/srv/share", "\\\\nas\\share"),
+    ];
+
+    assert_eq!(map_path("D:\\media\\tv\\a.mkv", &mappings), "/mnt/media/tv/a.mkv");
+    // Windows 路径不区分大小写，正斜杠也能识别
+    assert_eq!(map_path("d:/Media/a.mkv", &mappings), "/mnt/media/a.mkv");
+    // 多条映射匹配时使用最长的前缀
+    assert_eq!(map_path("D:\\media\\movies\\b.mkv", &mappings), "/mnt/movies/b.mkv");
+    assert_eq!(map_path("D:\\media", &mappings), "/mnt/media");
+    // 只在路径组件边界处匹配
+    assert_eq!(map_path("D:\\media2\\a.mkv", &mappings), "D:\\media2\\a.mkv");
+    // Unix 路径区分大小写，映射到 Windows 路径时使用反斜杠
+    assert_eq!(map_path("/srv/share/docs/a.pdf", &mappings), "\\\\nas\\share\\docs\\a.pdf");
+    assert_eq!(map_path("/srv/Share/a.pdf", &mappings), "/srv/Share/a.pdf");
+    assert_eq!(map_path("E:\\other.mkv", &[]), "E:\\other.mkv");
+
+    assert!(is_windows_path("C:\\Users"));
+    assert!(is_windows_path("\\\\nas\\share"));
+    assert!(!is_windows_path("/home/me"));
+
+    // 旧配置中没有路径映射时使用空列表
+    let config: DatabaseConfig = serde_json::from_str(r#"{"name": "Old"}"#).unwrap();
+    assert!(config.path_mappings.is_empty());
+}
+
+#[test]
+fn test_qr_code_render() {
+    use netdisk_db::services::qr_code::{encode, render, QUIET_ZONE};
+
+    let url = "https://example.com/download?etag=abc&size=1024";
+    let (width, modules) = encode(url).unwrap();
+    assert_eq!(modules.len(), width * width);
+    // 左上角的定位图案：外圈为深色，内圈为浅色
+    assert!(modules[0]);
+    assert!(!modules[width + 1]);
+
+    let image = render(url, 3).unwrap();
+    assert_eq!(image.size as usize, (width + QUIET_ZONE * 2) * 3);
+    assert_eq!(image.pixels.len(), (image.size * image.size * 4) as usize);
+    let pixel = |x: usize, y: usize| {
+        let start = (y * image.size as usize + x) * 4;
+        &image.pixels[start..start + 4]
+    };
+    // 空白区域为白色，第一个模块为黑色
+    assert_eq!(pixel(0, 0), [0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(pixel(QUIET_ZONE * 3, QUIET_ZONE * 3), [0, 0, 0, 0xff]);
+
+    // 超出二维码容量的文本返回错误
+    assert!(encode(&"x".repeat(8000)).is_err());
+}
+
+#[test]
+fn test_player_command() {
+    use netdisk_db::services::player::{build_command, split_command};
+
+    assert_eq!(split_command("mpv --fs"), vec!["mpv", "--fs"]);
+    assert_eq!(
+        split_command(r#""C:\Program Files\VLC\vlc.exe" --meta-title='{name}'  {url}"#),
+        vec![r"C:\Program Files\VLC\vlc.exe", "--meta-title={name}", "{url}"]
+    );
+    assert_eq!(split_command("player ''"), vec!["player", ""]);
+    assert!(split_command("   ").is_empty());
+
+    let url = "https://example.com/a.mkv?sign=x&t=1";
+    let (program, args) = build_command("mpv --force-media-title={name} {url}", url, "My Movie.mkv").unwrap();
+    assert_eq!(program, "mpv");
+    assert_eq!(args, vec!["--force-media-title=My Movie.mkv", url]);
+
+    // 模板中没有 {url} 时链接追加在最后
+    let (program, args) = build_command("vlc --play-and-exit", url, "a.mkv").unwrap();
+    assert_eq!(program, "vlc");
+    assert_eq!(args, vec!["--play-and-exit", url]);
+
+    assert!(build_command("", url, "a.mkv").is_err());
+
+    // 旧配置中没有播放器时为空
+    let config: AppConfig = serde_json::from_str("{}").unwrap();
+    assert!(config.players.is_empty());
+}
+
+#[test]
+fn test_media_info_parse_and_cache() {
+    use netdisk_db::services::media_info::{format_duration, parse_ffprobe_output, MediaInfoCache, MediaInfoService};
+
+    let output = r#"{
+        "streams": [
+            { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+            { "index": 1, "codec_type": "audio", "codec_name": "aac" },
+            { "index": 2, "codec_type": "audio", "codec_name": "ac3" }
+        ],
+        "format": { "duration": "5405.120000", "bit_rate": "5000000", "format_name": "matroska,webm" }
+    }"#;
+    let info = parse_ffprobe_output(output).unwrap();
+    assert_eq!(info.width, Some(1920));
+    assert_eq!(info.height, Some(1080));
+    assert_eq!(info.video_codec.as_deref(), Some("h264"));
+    assert_eq!(info.audio_codec.as_deref(), Some("aac"));
+    assert_eq!(info.summary(), "1:30:05 · 1920×1080 · h264 / aac · 5.0 Mbps");
+
+    // 纯音频文件，时长只记录在流中
+    let audio = parse_ffprobe_output(
+        r#"{ "streams": [ { "codec_type": "audio", "codec_name": "flac", "duration": "245.4" } ], "format": {} }"#,
+    )
+    .unwrap();
+    assert_eq!(audio.summary(), "4:05 · flac");
+    assert!(parse_ffprobe_output("not json").is_err());
+    assert_eq!(format_duration(59.6), "1:00");
+
+    assert!(MediaInfoService::is_supported("mkv", "a.mkv"));
+    assert!(MediaInfoService::is_supported("", "song.flac"));
+    assert!(!MediaInfoService::is_supported("pdf", "a.pdf"));
+
+    // 文件大小或修改时间变化后缓存失效
+    let db_path = std::env::temp_dir().join("netdisk_db_media_info_test.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let cache = MediaInfoCache::open(&db_path.to_string_lossy()).unwrap();
+    cache.put("/media/a.mkv", 100, 1700000000, &info).unwrap();
+    assert_eq!(cache.get("/media/a.mkv", 100, 1700000000).unwrap(), Some(info.clone()));
+    assert_eq!(cache.get("/media/a.mkv", 101, 1700000000).unwrap(), None);
+    assert_eq!(cache.get("/media/a.mkv", 100, 1700000001).unwrap(), None);
+    cache.put("/media/a.mkv", 101, 1700000001, &audio).unwrap();
+    assert_eq!(cache.get("/media/a.mkv", 101, 1700000001).unwrap(), Some(audio));
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_subtitle_matching() {
+    use netdisk_db::services::subtitles::{is_subtitle, is_subtitle_for, matching_subtitles};
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: "etag".to_string(),
+        modified_time: 0,
+        file_type: path.rsplit('.').next().unwrap_or_default().to_string(),
+        name: path.rsplit('/').next().unwrap_or_default().to_string(),
+    };
+
+    assert!(is_subtitle("Movie.SRT"));
+    assert!(!is_subtitle("Movie.mkv"));
+    assert!(is_subtitle_for("Movie.2020.mkv", "movie.2020.srt"));
+    assert!(is_subtitle_for("Movie.2020.mkv", "Movie.2020.zh.ass"));
+    assert!(!is_subtitle_for("Movie.2020.mkv", "Movie.2020-extras.srt"));
+    assert!(!is_subtitle_for("Movie.2020.mkv", "Movie.2020.nfo"));
+
+    let video = record("/movies/Movie/Movie.mkv");
+    let candidates = vec![
+        video.clone(),
+        record("/movies/Movie/Movie.srt"),
+        record("/movies/Movie/Movie.en.ass"),
+        record("/subs/Movie.srt"),
+        record("/movies/Movie/Movie Trailer.srt"),
+    ];
+    // 同一文件夹中有字幕时只使用这些字幕
+    let paths: Vec<String> = matching_subtitles(&video, &candidates).into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, vec!["/movies/Movie/Movie.srt", "/movies/Movie/Movie.en.ass"]);
+
+    // 否则使用其他文件夹中的同名字幕
+    let elsewhere = record("/other/Movie.mkv");
+    let paths: Vec<String> = matching_subtitles(&elsewhere, &candidates).into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, vec!["/movies/Movie/Movie.srt", "/movies/Movie/Movie.en.ass", "/subs/Movie.srt"]);
+}
+
+#[test]
+fn test_collections() {
+    use netdisk_db::services::collections::Collections;
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: "etag".to_string(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap_or_default().to_string(),
+    };
+
+    let db_path = std::env::temp_dir().join("netdisk_db_collections_test.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let collections = Collections::open(&db_path.to_string_lossy()).unwrap();
+
+    let movies = collections.create(" 电影 ").unwrap();
+    assert!(collections.create("电影").is_err());
+    assert!(collections.create("  ").is_err());
+    let other = collections.create("其他").unwrap();
+
+    // 同一路径在不同数据库中是不同的文件，重复加入忽略
+    let added = collections
+        .add_records(movies, &[record("/a/Movie.mkv"), record("/b/Show.mkv")], "disk1")
+        .unwrap();
+    assert_eq!(added, 2);
+    assert_eq!(collections.add_records(movies, &[record("/a/Movie.mkv")], "disk1").unwrap(), 0);
+    assert_eq!(collections.add_records(movies, &[record("/a/Movie.mkv")], "disk2").unwrap(), 1);
+    assert!(collections.add_records(9999, &[record("/a/Movie.mkv")], "disk1").is_err());
+
+    let items = collections.items(movies).unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].record.name, "Movie.mkv");
+    assert_eq!(items[2].database, "disk2");
+
+    let list = collections.list().unwrap();
+    let names: Vec<&str> = list.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["其他", "电影"]);
+    assert_eq!(collections.get(movies).unwrap().unwrap().item_count, 3);
+
+    collections.rename(movies, "影视").unwrap();
+    assert!(collections.rename(other, "影视").is_err());
+    assert_eq!(collections.get(movies).unwrap().unwrap().name, "影视");
+
+    assert!(collections.remove_record(movies, "disk2", "/a/Movie.mkv").unwrap());
+    assert!(!collections.remove_record(movies, "disk2", "/a/Movie.mkv").unwrap());
+    assert_eq!(collections.items(movies).unwrap().len(), 2);
+
+    assert!(collections.delete(movies).unwrap());
+    assert!(!collections.delete(movies).unwrap());
+    assert!(collections.items(movies).unwrap().is_empty());
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_download_sidecar() {
+    use netdisk_db::services::sidecar::{self, sidecar_path, target_of, DownloadSidecar};
+
+    let record = FileRecord {
+        id: 42,
+        name: "Movie.mkv".to_string(),
+        path: "/movies/Movie.mkv".to_string(),
+        size: 1024,
+        etag: "etag".to_string(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+    };
+
+    let base = std::env::temp_dir().join("netdisk_db_sidecar_test");
+    let _ = std::fs::remove_dir_all(&base);
+    let target = base.join("Videos").join("Movie.mkv");
+    assert_eq!(sidecar_path(&target), base.join("Videos").join("Movie.mkv.netdisk.json"));
+    assert_eq!(target_of(&sidecar_path(&target)), Some(target.clone()));
+    assert_eq!(target_of(&target), None);
+
+    // 文件尚未下载完成时也可以写入，目录自动创建
+    let mut older = DownloadSidecar::for_record(&record, "电影库");
+    older.created_at = 100;
+    sidecar::write(&target, &older).unwrap();
+    assert_eq!(sidecar::read(&target).unwrap(), Some(older.clone()));
+    assert_eq!(sidecar::read(&base.join("other.mkv")).unwrap(), None);
+
+    let other = base.join("a.pdf");
+    let mut newer = DownloadSidecar::for_record(&record, "文档库");
+    newer.created_at = 200;
+    sidecar::write(&other, &newer).unwrap();
+    std::fs::write(base.join("broken.bin.netdisk.json"), "not json").unwrap();
+
+    // 扫描子目录，按时间从新到旧，重复的目录只扫描一次
+    let found = sidecar::scan(&[base.clone(), base.clone()]);
+    assert_eq!(found, vec![(other, newer), (target, older)]);
+    let _ = std::fs::remove_dir_all(&base);
+}
+
+#[test]
+fn test_download_history() {
+    use netdisk_db::services::download_history::{DownloadHistory, DownloadHistoryEntry, DownloadOutcome};
+
+    let entry = |path: &str, finished_at: i64, outcome: DownloadOutcome| DownloadHistoryEntry {
+        id: 0,
+        gid: "2089b05ecca3d829".to_string(),
+        record: FileRecord {
+            id: 7,
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            size: 1024,
+            etag: "etag".to_string(),
+            modified_time: 0,
+            file_type: "mkv".to_string(),
+        },
+        database: "电影库".to_string(),
+        save_path: format!("/downloads{}", path),
+        started_at: finished_at - 90,
+        finished_at,
+        outcome,
+        error: (outcome == DownloadOutcome::Failed).then(|| "connection reset".to_string()),
+    };
+
+    let db_path = std::env::temp_dir().join("netdisk_db_download_history_test.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let history = DownloadHistory::open(&db_path.to_string_lossy()).unwrap();
+    let first = history.record(&entry("/a.mkv", 1000, DownloadOutcome::Complete)).unwrap();
+    history.record(&entry("/b.mkv", 2000, DownloadOutcome::Failed)).unwrap();
+
+    // 重新打开后历史仍在，最新的在前
+    drop(history);
+    let history = DownloadHistory::open(&db_path.to_string_lossy()).unwrap();
+    let recent = history.recent(10).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].record.path, "/b.mkv");
+    assert_eq!(recent[0].outcome, DownloadOutcome::Failed);
+    assert_eq!(recent[0].error.as_deref(), Some("connection reset"));
+    assert_eq!(recent[1].duration_secs(), 90);
+    assert_eq!(history.recent(1).unwrap().len(), 1);
+
+    let stored = history.get(first).unwrap().unwrap();
+    assert_eq!(stored.record.etag, "etag");
+    assert_eq!(stored.save_path, "/downloads/a.mkv");
+    assert_eq!(stored.database, "电影库");
+    assert!(history.get(9999).unwrap().is_none());
+
+    history.clear().unwrap();
+    assert!(history.recent(10).unwrap().is_empty());
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_disk_space_preflight() {
+    use netdisk_db::services::disk_space::{self, SpaceCheck};
+
+    assert_eq!(SpaceCheck::evaluate(100, 200, 1000), SpaceCheck::Sufficient);
+    assert_eq!(
+        SpaceCheck::evaluate(400, 700, 1000),
+        SpaceCheck::QueueExceeds { required: 400, queued: 700, available: 1000 }
+    );
+    let insufficient = SpaceCheck::evaluate(2000, 0, 1000);
+    assert_eq!(insufficient, SpaceCheck::Insufficient { required: 2000, available: 1000 });
+
+    // 只有文件本身无法保存且设置了拒绝时才拒绝
+    assert!(insufficient.blocks(true));
+    assert!(!insufficient.blocks(false));
+    assert!(!SpaceCheck::evaluate(400, 700, 1000).blocks(true));
+    assert!(SpaceCheck::Sufficient.message().is_none());
+    assert!(insufficient.message().unwrap().contains("磁盘空间不足"));
+
+    // 尚未创建的下载目录按已存在的上级目录查询
+    let missing = std::env::temp_dir().join("netdisk_db_disk_space_test").join("Videos");
+    let available = disk_space::available_space(&missing).unwrap();
+    assert!(available > 0);
+    assert!(disk_space::preflight(&missing, 0, 0).unwrap().is_sufficient());
+    assert!(!disk_space::preflight(&missing, u64::MAX, 0).unwrap().is_sufficient());
+}
+
+#[test]
+fn test_notification_settings() {
+    use netdisk_db::models::config::{AppConfig, NotificationConfig};
+    use netdisk_db::services::notifications::{NotificationKind, Notifier};
+
+    // 未写出的事件类型默认开启
+    let config: AppConfig = serde_json::from_str(r#"{ "notifications": { "sync_errors": false } }"#).unwrap();
+    assert!(config.notifications.enabled);
+    assert!(config.notifications.download_complete);
+    assert!(!config.notifications.sync_errors);
+
+    let notifier = Notifier::new(config.notifications);
+    assert!(notifier.is_enabled(NotificationKind::DownloadComplete));
+    assert!(notifier.is_enabled(NotificationKind::IndexingComplete));
+    assert!(!notifier.is_enabled(NotificationKind::SyncError));
+
+    // 总开关关闭时所有类型都不显示
+    notifier.configure(NotificationConfig {
+        enabled: false,
+        ..NotificationConfig::default()
+    });
+    assert!(!notifier.is_enabled(NotificationKind::DownloadComplete));
+    notifier.notify(NotificationKind::DownloadComplete, "下载完成", "/tmp/a.mkv");
+}
+
+#[test]
+fn test_quick_search_hotkey_config() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::services::hotkey;
+
+    let config: AppConfig = serde_json::from_str(r#"{ "quick_search": { "enabled": false } }"#).unwrap();
+    assert!(!config.quick_search.enabled);
+    assert_eq!(config.quick_search.hotkey, AppConfig::default().quick_search.hotkey);
+
+    if cfg!(feature = "hotkey") {
+        assert!(hotkey::validate(&config.quick_search.hotkey).is_ok());
+        assert!(hotkey::validate("alt+F1").is_ok());
+        assert!(hotkey::validate("Ctrl+Shift+NoSuchKey").is_err());
+    } else {
+        assert!(hotkey::validate("Alt+F1").is_err());
+    }
+}
+
+#[test]
+fn test_startup_self_check() {
+    use netdisk_db::models::config::{AppConfig, BackendConfig, DatabaseConfig};
+    use netdisk_db::services::self_check::{self, CheckStatus, SelfCheckReport};
+
+    let dir = std::env::temp_dir().join("netdisk_db_self_check_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // 配置文件：不存在、正常、无法解析且没有备份
+    let config_path = dir.join("config.json").to_string_lossy().to_string();
+    assert_eq!(self_check::check_config(&config_path).status, CheckStatus::Warning);
+    AppConfig::default().save_to_file(&config_path).unwrap();
+    let _ = std::fs::remove_file(AppConfig::backup_path(&config_path));
+    assert_eq!(self_check::check_config(&config_path).status, CheckStatus::Passed);
+    std::fs::write(&config_path, "{ not json").unwrap();
+    let broken = self_check::check_config(&config_path);
+    assert_eq!(broken.status, CheckStatus::Failed);
+    assert!(broken.hint.is_some());
+
+    // 数据库：正常、不存在、不是 SQLite 文件
+    let valid = dir.join("valid.db");
+    rusqlite::Connection::open(&valid)
+        .unwrap()
+        .execute("CREATE TABLE files (id INTEGER)", [])
+        .unwrap();
+    let corrupt = dir.join("corrupt.db");
+    std::fs::write(&corrupt, vec![0x42u8; 4096]).unwrap();
+    let database = |name: &str, path: &std::path::Path| DatabaseConfig {
+        name: name.to_string(),
+        connection_string: path.to_string_lossy().to_string(),
+        ..DatabaseConfig::default()
+    };
+    let mut config = AppConfig::default();
+    config.multi_database.databases = vec![
+        database("valid", &valid),
+        database("missing", &dir.join("missing.db")),
+        database("corrupt", &corrupt),
+    ];
+    let statuses: Vec<CheckStatus> = self_check::check_databases(&config).iter().map(|item| item.status).collect();
+    assert_eq!(statuses, vec![CheckStatus::Passed, CheckStatus::Warning, CheckStatus::Failed]);
+
+    // 端口被占用时，可以尝试其他端口为警告，否则为失败
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let backend = BackendConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        port_attempts: 1,
+        ..BackendConfig::default()
+    };
+    assert_eq!(self_check::check_backend_port(&backend).status, CheckStatus::Failed);
+    let backend = BackendConfig { port_attempts: 5, ..backend };
+    assert_eq!(self_check::check_backend_port(&backend).status, CheckStatus::Warning);
+    drop(listener);
+    assert_eq!(self_check::check_backend_port(&backend).status, CheckStatus::Passed);
+
+    let report = SelfCheckReport {
+        items: self_check::check_databases(&config),
+    };
+    assert_eq!(report.problem_count(), 2);
+    assert!(!report.is_healthy());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_instance_request_protocol() {
+    use netdisk_db::services::single_instance::{self, InstanceRequest};
+
+    assert_eq!(InstanceRequest::parse("activate\n").unwrap(), InstanceRequest::Activate);
+    assert_eq!(
+        InstanceRequest::parse(&InstanceRequest::Activate.to_line()).unwrap(),
+        InstanceRequest::Activate
+    );
+    assert!(InstanceRequest::parse("shutdown").is_err());
+
+    let requests = [
+        InstanceRequest::Search { query: "旅行 视频".to_string() },
+        InstanceRequest::AddDownload { url: "magnet:?xt=urn:btih:abc".to_string(), name: None },
+        InstanceRequest::AddDownload {
+            url: "https://example.com/d/abc?sign=1".to_string(),
+            name: Some("旅行 2024.mkv".to_string()),
+        },
+        InstanceRequest::Open { id: 7 },
+    ];
+    for request in requests {
+        assert_eq!(InstanceRequest::parse(&request.to_line()).unwrap(), request);
+    }
+    assert!(InstanceRequest::parse("search").is_err());
+    assert!(InstanceRequest::parse("add-download  ").is_err());
+    assert!(InstanceRequest::parse("open 7x").is_err());
+
+    assert_eq!(single_instance::ok_response(""), "ok");
+    assert_eq!(single_instance::ok_response("[]"), "ok []");
+    let error = anyhow::anyhow!("line one\nline two");
+    assert_eq!(single_instance::error_response(&error), "error line one line two");
+}
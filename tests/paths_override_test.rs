@@ -0,0 +1,22 @@
+//! 数据目录覆盖测试
+//!
+//! 覆盖目录是进程范围的设置，单独放在一个测试程序中，避免影响其他测试中的默认路径
+
+use netdisk_db::utils::paths;
+
+#[test]
+fn test_paths_override_dir() {
+    let base = std::env::temp_dir().join("netdisk_db_paths_test");
+    paths::set_override_dir(base.clone());
+
+    assert_eq!(paths::config_dir(), base.join("config"));
+    assert_eq!(paths::data_dir(), base.join("data"));
+    assert_eq!(paths::downloads_dir(), base.join("downloads"));
+    assert_eq!(paths::config_file(), base.join("config").join("config.json"));
+    assert!(paths::thumbnail_cache_dir().starts_with(base.join("cache")));
+
+    paths::ensure_dirs().expect("Failed to create app directories");
+    assert!(base.join("logs").is_dir());
+
+    let _ = std::fs::remove_dir_all(&base);
+}
@@ -43,4 +43,1221 @@ fn test_database_connection() {
     } else {
         println!("视频数据库不存在，跳过连接测试");
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_sorted_paged_search() {
+    use netdisk_db::models::database::{SearchOptions, SortColumn, SortOrder};
+
+    let _ = tracing_subscriber::fmt::try_init();
+
+    // 连接池中每个连接对应独立的内存数据库，这里使用临时文件
+    let db_path = std::env::temp_dir().join("netdisk_db_sorted_paged_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+
+    let options = SearchOptions {
+        sort: Some(SortOrder {
+            column: SortColumn::Size,
+            ascending: false,
+        }),
+        ..SearchOptions::default()
+    };
+
+    // 示例数据共有 5 条记录
+    assert_eq!(db.count_files("/home/user", &options).unwrap(), 5);
+
+    let first_page = db.search_files_paged("/home/user", &options, 0, 2).unwrap();
+    let sizes: Vec<u64> = first_page.iter().map(|r| r.size).collect();
+    assert_eq!(sizes, vec![4096, 3072]);
+
+    let last_page = db.search_files_paged("/home/user", &options, 4, 2).unwrap();
+    assert_eq!(last_page.len(), 1);
+    assert_eq!(last_page[0].size, 512);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_index_folder() {
+    use netdisk_db::models::database::SearchOptions;
+    use netdisk_db::services::indexer::Indexer;
+    use std::sync::{Arc, Mutex};
+
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let root = std::env::temp_dir().join("netdisk_db_index_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("nested")).unwrap();
+    std::fs::write(root.join("a.mp4"), b"video").unwrap();
+    std::fs::write(root.join("nested").join("b.txt"), b"text").unwrap();
+
+    let db_path = std::env::temp_dir().join("netdisk_db_index_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    assert!(db.is_writable());
+
+    let database: Arc<Mutex<dyn Database>> = Arc::new(Mutex::new(db));
+    let indexer = Indexer::new(database.clone());
+    let summary = indexer.index_folder(&root, |_| {}).expect("Failed to index folder");
+    assert_eq!(summary.indexed, 2);
+    assert!(!summary.cancelled);
+
+    let query = root.to_string_lossy().to_string();
+    let options = SearchOptions::default();
+    assert_eq!(database.lock().unwrap().count_files(&query, &options).unwrap(), 2);
+
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_group_records() {
+    use netdisk_db::models::database::{group_records, FileRecord, GroupBy};
+
+    let record = |path: &str, file_type: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 0,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: file_type.to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    let records = vec![
+        record("/videos/b.mp4", "mp4"),
+        record("/docs/a.pdf", "pdf"),
+        record("/videos/a.mkv", ""),
+    ];
+
+    let by_folder = group_records(records.clone(), GroupBy::Folder);
+    let keys: Vec<&str> = by_folder.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, vec!["/docs", "/videos"]);
+    // 组内保持原有顺序
+    assert_eq!(by_folder[1].records[0].name, "b.mp4");
+
+    let by_type = group_records(records, GroupBy::FileType);
+    assert_eq!(by_type.len(), 3);
+    assert!(by_type.iter().any(|g| g.key == "(未知类型)"));
+    assert_eq!(GroupBy::from_index(0), None);
+}
+
+#[test]
+fn test_database_handle_uses_current_database() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use std::sync::{Arc, Mutex};
+
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let root = std::env::temp_dir().join("netdisk_db_handle_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for name in ["alpha", "beta"] {
+        let db_path = root.join(format!("{}.db", name));
+        let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+        db.init_database().expect("Failed to initialize database");
+        db.insert_files(&[FileRecord {
+            id: 0,
+            path: format!("/{}/only-here.mp4", name),
+            size: 1,
+            etag: name.to_string(),
+            modified_time: 0,
+            file_type: "mp4".to_string(),
+            name: "only-here.mp4".to_string(),
+        }])
+        .expect("Failed to insert record");
+    }
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let manager = DatabaseManager::new(Arc::new(Mutex::new(config))).expect("Failed to create manager");
+    let handle = DatabaseHandle::spawn(manager);
+
+    let databases = handle.snapshot().unwrap().databases;
+    let index_of = |name: &str| {
+        databases
+            .iter()
+            .find(|(db_name, _, _)| db_name == name)
+            .map(|(_, _, index)| *index)
+            .expect("Database not discovered")
+    };
+
+    let search = |handle: &DatabaseHandle| {
+        handle
+            .search("only-here", SearchOptions::default(), 10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.path)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(handle.switch_database(index_of("alpha")).unwrap().name, "alpha");
+    assert_eq!(search(&handle), vec!["/alpha/only-here.mp4".to_string()]);
+
+    // 克隆的句柄在切换数据库后同样作用于新的数据库
+    let cloned = handle.clone();
+    assert_eq!(handle.switch_database(index_of("beta")).unwrap().name, "beta");
+    assert_eq!(search(&cloned), vec!["/beta/only-here.mp4".to_string()]);
+    assert_eq!(cloned.snapshot().unwrap().current.name, "beta");
+
+    assert_eq!(cloned.count_files("only-here", SearchOptions::default()).unwrap(), 1);
+
+    drop((handle, cloned));
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_database_switch_notifies_search() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
+
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let root = std::env::temp_dir().join("netdisk_db_switch_notify_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for name in ["first", "second"] {
+        let db_path = root.join(format!("{}.db", name));
+        let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+        db.init_database().expect("Failed to initialize database");
+        db.insert_files(&[FileRecord {
+            id: 0,
+            path: format!("/{}/switch-target.mkv", name),
+            size: 1,
+            etag: name.to_string(),
+            modified_time: 0,
+            file_type: "mkv".to_string(),
+            name: "switch-target.mkv".to_string(),
+        }])
+        .expect("Failed to insert record");
+    }
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let manager = DatabaseManager::new(Arc::new(Mutex::new(config))).expect("Failed to create manager");
+    let handle = DatabaseHandle::spawn(manager);
+
+    // 模拟搜索处理器：收到切换通知后立即在当前数据库中搜索
+    let (tx, rx) = mpsc::channel();
+    handle.on_database_changed(move |index, config| {
+        let _ = tx.send((index, config.name.clone()));
+    });
+
+    let databases = handle.snapshot().unwrap().databases;
+    for target in ["first", "second"] {
+        let index = databases
+            .iter()
+            .find(|(name, _, _)| name == target)
+            .map(|(_, _, index)| *index)
+            .expect("Database not discovered");
+        handle.switch_database(index).unwrap();
+
+        let (notified_index, notified_name) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((notified_index, notified_name.as_str()), (index, target));
+
+        let paths: Vec<String> = handle
+            .search("switch-target", SearchOptions::default(), 10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.path)
+            .collect();
+        assert_eq!(paths, vec![format!("/{}/switch-target.mkv", target)]);
+    }
+
+    // 切换失败时不通知
+    assert!(handle.switch_database(usize::MAX).is_err());
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+    drop(handle);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_bulk_rename_preview_and_undo() {
+    use netdisk_db::models::database::{FileRecord, RenameEntry};
+    use netdisk_db::services::rename::{preview, RenamePattern};
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+
+    let db_path = std::env::temp_dir().join("netdisk_db_rename_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    db.insert_files(&[record("/rename/show.s01.mkv"), record("/rename/show.s02.mkv"), record("/rename/other.mkv")])
+        .unwrap();
+    let mut records = db.search_files("show.s0").unwrap();
+    records.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(records.len(), 2);
+
+    // 查找替换只改文件名，保留所在文件夹
+    let entries = preview(&records, &RenamePattern::from_ui(0, "show", "Show")).unwrap();
+    assert_eq!(entries[0].new_path, "/rename/Show.s01.mkv");
+    assert!(preview(&records, &RenamePattern::from_ui(0, "", "x")).is_err());
+    assert!(preview(&records, &RenamePattern::from_ui(0, "show", "a/b")).is_err());
+
+    // 编号模板
+    let numbered = preview(&records, &RenamePattern::from_ui(1, "{name}_{n:3}.{ext}", "9")).unwrap();
+    let names: Vec<&str> = numbered.iter().map(|e| e.new_name.as_str()).collect();
+    assert_eq!(names, vec!["show.s01_009.mkv", "show.s02_010.mkv"]);
+    // 不含编号时重命名后重复
+    assert!(preview(&records, &RenamePattern::from_ui(1, "same.mkv", "")).is_err());
+
+    assert_eq!(db.rename_files(&numbered).unwrap(), 2);
+    assert!(db.search_files("show.s01_009").unwrap()[0].path.ends_with("/rename/show.s01_009.mkv"));
+
+    let undo: Vec<RenameEntry> = numbered.iter().map(RenameEntry::reversed).collect();
+    assert_eq!(db.rename_files(&undo).unwrap(), 2);
+    assert!(db.search_files("_009").unwrap().is_empty());
+    assert_eq!(db.search_files("show.s0").unwrap().len(), 2);
+
+    // 链式重命名按 id 匹配，不会把第一条重命名的结果再改一次
+    let entry = |record: &FileRecord, new_name: &str| RenameEntry {
+        id: record.id,
+        old_name: record.name.clone(),
+        old_path: record.path.clone(),
+        new_name: new_name.to_string(),
+        new_path: format!("/rename/{}", new_name),
+    };
+    let (first, second) = (&records[0], &records[1]);
+    assert_eq!(db.rename_files(&[entry(first, "show.s02.mkv"), entry(second, "show.s03.mkv")]).unwrap(), 2);
+    let mut paths: Vec<String> = db.search_files("/rename/show").unwrap().into_iter().map(|r| r.path).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/rename/show.s02.mkv".to_string(), "/rename/show.s03.mkv".to_string()]);
+
+    // 同一路径有多条记录时只修改指定的记录
+    db.insert_files(&[record("/rename/other.mkv")]).unwrap();
+    let other = db.search_files("/rename/other.mkv").unwrap().remove(0);
+    assert_eq!(db.rename_files(&[entry(&other, "renamed.mkv")]).unwrap(), 1);
+    assert_eq!(db.search_files("/rename/other.mkv").unwrap().len(), 1);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_restore_files_into_legacy_table() {
+    use netdisk_db::models::database::FileRecord;
+    use std::collections::HashMap;
+
+    // 旧的索引表没有 AUTOINCREMENT 和 verified 列，删除的 id 会被新记录重新使用
+    let db_path = std::env::temp_dir().join("netdisk_db_restore_legacy_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE video (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                etag TEXT NOT NULL,
+                modified_time INTEGER NOT NULL,
+                file_type TEXT NOT NULL
+            );
+            INSERT INTO video VALUES (1, 'a.mkv', '/legacy/a.mkv', 1, 'a', 0, 'mkv');",
+        )
+        .unwrap();
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).unwrap();
+
+    let deleted = db.find_file(1).unwrap().unwrap();
+    db.delete_file(1).unwrap();
+    let reused = FileRecord {
+        id: 0,
+        name: "b.mkv".to_string(),
+        path: "/legacy/b.mkv".to_string(),
+        ..deleted.clone()
+    };
+    db.insert_files(&[reused]).unwrap();
+    assert_eq!(db.find_file(1).unwrap().unwrap().path, "/legacy/b.mkv");
+
+    // id 已被占用时分配新的 id，不因主键冲突丢失撤销
+    let verified = HashMap::from([(deleted.path.clone(), true)]);
+    let ids = db.restore_files(&[deleted], &verified).unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_ne!(ids[0], 1);
+    assert_eq!(db.find_file(ids[0]).unwrap().unwrap().path, "/legacy/a.mkv");
+    assert_eq!(db.find_file(1).unwrap().unwrap().path, "/legacy/b.mkv");
+    drop(db);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_undo_redo_index_operations() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, RenameEntry, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use netdisk_db::services::journal::{IndexOperation, OperationJournal, JOURNAL_LIMIT};
+    use std::sync::{Arc, Mutex};
+
+    let root = std::env::temp_dir().join("netdisk_db_journal_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db_path = root.join("journal.db");
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    drop(db);
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let manager = DatabaseManager::new(Arc::new(Mutex::new(config))).expect("Failed to create manager");
+    let handle = DatabaseHandle::spawn(manager);
+    let index = handle.snapshot().unwrap().databases[0].2;
+    handle.switch_database(index).unwrap();
+
+    let record = FileRecord {
+        id: 0,
+        path: "/journal/a.mkv".to_string(),
+        size: 1,
+        etag: "a".to_string(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: "a.mkv".to_string(),
+    };
+    let paths = |handle: &DatabaseHandle| {
+        handle
+            .search("/journal/", SearchOptions::default(), 10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.path)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(handle.undo().unwrap(), None);
+    handle.apply(IndexOperation::insert(vec![record.clone()])).unwrap();
+    let inserted = handle.search("/journal/a.mkv", SearchOptions::default(), 10).unwrap().remove(0);
+    handle
+        .apply(IndexOperation::Rename(vec![RenameEntry {
+            id: inserted.id,
+            old_name: "a.mkv".to_string(),
+            old_path: "/journal/a.mkv".to_string(),
+            new_name: "b.mkv".to_string(),
+            new_path: "/journal/b.mkv".to_string(),
+        }]))
+        .unwrap();
+    let mut renamed = inserted.clone();
+    renamed.path = "/journal/b.mkv".to_string();
+    renamed.name = "b.mkv".to_string();
+    handle.apply(IndexOperation::delete(vec![renamed])).unwrap();
+    assert!(paths(&handle).is_empty());
+    assert_eq!(handle.snapshot().unwrap().next_undo.as_deref(), Some("删除 1 个文件"));
+
+    // 撤销删除后按原 id 恢复，撤销重命名仍能匹配到同一条记录
+    assert_eq!(handle.undo().unwrap().as_deref(), Some("删除 1 个文件"));
+    assert_eq!(paths(&handle), vec!["/journal/b.mkv".to_string()]);
+    assert_eq!(handle.find_file(inserted.id).unwrap().unwrap().path, "/journal/b.mkv");
+    assert_eq!(handle.undo().unwrap().as_deref(), Some("重命名 1 个文件"));
+    assert_eq!(paths(&handle), vec!["/journal/a.mkv".to_string()]);
+
+    assert_eq!(handle.redo().unwrap().as_deref(), Some("重命名 1 个文件"));
+    assert_eq!(paths(&handle), vec!["/journal/b.mkv".to_string()]);
+
+    // 撤销插入只删除插入的记录，重做后仍使用同一 id
+    assert_eq!(handle.undo().unwrap().as_deref(), Some("重命名 1 个文件"));
+    assert_eq!(handle.undo().unwrap().as_deref(), Some("添加 1 个文件"));
+    assert!(paths(&handle).is_empty());
+    assert_eq!(handle.redo().unwrap().as_deref(), Some("添加 1 个文件"));
+    assert_eq!(handle.find_file(inserted.id).unwrap().unwrap().path, "/journal/a.mkv");
+
+    // 新的修改清空重做栈
+    handle.apply(IndexOperation::insert(vec![record])).unwrap();
+    assert_eq!(handle.redo().unwrap(), None);
+
+    // 切换数据库后清空日志
+    handle.switch_database(index).unwrap();
+    assert_eq!(handle.snapshot().unwrap().next_undo, None);
+
+    // 日志有上限
+    let mut journal = OperationJournal::default();
+    for _ in 0..JOURNAL_LIMIT + 5 {
+        journal.record(IndexOperation::insert(Vec::new()));
+    }
+    assert_eq!(journal.len(), JOURNAL_LIMIT);
+
+    drop(handle);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_database_maintenance() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, MaintenanceOperation};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use std::sync::{Arc, Mutex};
+
+    let root = std::env::temp_dir().join("netdisk_db_maintenance_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db_path = root.join("maintenance.db");
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    db.insert_files(&[
+        record("/removed/a.mkv"),
+        record("/removed/sub/b.mkv"),
+        record("/removed_other/c.mkv"),
+        record("/kept/d.mkv"),
+    ])
+    .unwrap();
+    drop(db);
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let manager = DatabaseManager::new(Arc::new(Mutex::new(config))).expect("Failed to create manager");
+    let handle = DatabaseHandle::spawn(manager);
+    let index = handle.snapshot().unwrap().databases[0].2;
+    handle.switch_database(index).unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut operations = MaintenanceOperation::ROUTINE.to_vec();
+    operations.push(MaintenanceOperation::CleanupOrphans {
+        roots: vec!["/removed/".to_string()],
+    });
+    let report = handle
+        .run_maintenance(operations, move |index, total, operation| {
+            let _ = tx.send((index, total, operation.label()));
+        })
+        .unwrap();
+    // 只删除目录下的记录，不影响名称以该目录开头的其他目录
+    assert_eq!(report.orphans_removed, 2);
+    assert!(report.size_before.is_some() && report.size_after.is_some());
+    let progress: Vec<_> = rx.try_iter().collect();
+    assert_eq!(progress.len(), 4);
+    assert_eq!(progress[3], (4, 4, "清理孤立记录"));
+
+    let database = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to open database");
+    let remaining = database.search_files("/removed").unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].path, "/removed_other/c.mkv");
+
+    drop((handle, database));
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_compare_databases() {
+    use netdisk_db::models::database::{CompareKey, FileRecord};
+    use netdisk_db::services::database::sqlite::compare_databases;
+
+    let root = std::env::temp_dir().join("netdisk_db_compare_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let record = |path: &str, etag: &str, modified_time: i64| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: etag.to_string(),
+        modified_time,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    let create = |name: &str, records: &[FileRecord]| {
+        let path = root.join(name);
+        let db = SqliteDatabase::new(path.to_str().unwrap()).unwrap();
+        db.init_database().unwrap();
+        // 去掉示例数据
+        let existing: Vec<String> = db.search_files("").unwrap().into_iter().map(|r| r.path).collect();
+        db.delete_paths(&existing).unwrap();
+        db.insert_files(records).unwrap();
+        path
+    };
+    let left = create(
+        "left.db",
+        &[
+            record("/same.mkv", "aaa", 100),
+            record("/only_left.mkv", "bbb", 100),
+            record("/edited.mkv", "ccc", 100),
+            record("/old/moved.mkv", "ddd", 100),
+        ],
+    );
+    let right = create(
+        "right.db",
+        &[
+            record("/same.mkv", "aaa", 100),
+            record("/only_right.mkv", "eee", 300),
+            record("/edited.mkv", "fff", 200),
+            record("/new/moved.mkv", "ddd", 100),
+        ],
+    );
+
+    let diff = compare_databases(&left, &right, CompareKey::Path).unwrap();
+    let paths = |records: &[FileRecord]| records.iter().map(|r| r.path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths(&diff.only_left), vec!["/old/moved.mkv", "/only_left.mkv"]);
+    assert_eq!(paths(&diff.only_right), vec!["/new/moved.mkv", "/only_right.mkv"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!((diff.changed[0].0.etag.as_str(), diff.changed[0].1.etag.as_str()), ("ccc", "fff"));
+    assert_eq!(diff.newer(), Some(std::cmp::Ordering::Less));
+    assert!(!diff.is_identical());
+
+    // 按 etag 比较时移动的文件配对，修改过的文件两侧各出现一次
+    let diff = compare_databases(&left, &right, CompareKey::Etag).unwrap();
+    assert_eq!(paths(&diff.only_left), vec!["/edited.mkv", "/only_left.mkv"]);
+    assert_eq!(paths(&diff.only_right), vec!["/edited.mkv", "/only_right.mkv"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].0.path, "/old/moved.mkv");
+    assert_eq!(diff.changed[0].1.path, "/new/moved.mkv");
+
+    let diff = compare_databases(&left, &left, CompareKey::Path).unwrap();
+    assert!(diff.is_identical());
+    assert_eq!(diff.newer(), None);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_search_scope() {
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+
+    let db_path = std::env::temp_dir().join("netdisk_db_scope_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    db.insert_files(&[
+        record("/movies/a.mkv"),
+        record("/movies/2020/b.mkv"),
+        record("/movies2/c.mkv"),
+        record("/my_dir/d.mkv"),
+        record("/myXdir/e.mkv"),
+    ])
+    .unwrap();
+
+    let scoped = |scope: &str| SearchOptions {
+        scope: Some(scope.to_string()),
+        ..SearchOptions::default()
+    };
+    // 范围按文件夹匹配，不包含名称以其开头的其他文件夹
+    assert_eq!(db.count_files("mkv", &scoped("/movies")).unwrap(), 2);
+    assert_eq!(db.count_files("mkv", &scoped("/movies/")).unwrap(), 2);
+    assert_eq!(db.count_files("2020", &scoped("/movies")).unwrap(), 1);
+    let paths: Vec<String> = db
+        .search_files_paged("", &scoped("/movies/2020"), 0, 10)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.path)
+        .collect();
+    assert_eq!(paths, vec!["/movies/2020/b.mkv"]);
+    // LIKE 通配符按字面匹配
+    assert_eq!(db.count_files("", &scoped("/my_dir")).unwrap(), 1);
+    assert_eq!(db.count_files("mkv", &SearchOptions::default()).unwrap(), 5);
+
+    assert_eq!(scoped("  ").scope_prefix(), None);
+    assert_eq!(scoped("D:\\videos").scope_prefix().as_deref(), Some("D:\\videos\\"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn test_scope_crumbs() {
+    use netdisk_db::views::ui::scope_crumbs;
+
+    assert_eq!(
+        scope_crumbs("/movies/2020/"),
+        vec![
+            ("movies".to_string(), "/movies".to_string()),
+            ("2020".to_string(), "/movies/2020".to_string()),
+        ]
+    );
+    assert_eq!(
+        scope_crumbs("D:\\videos"),
+        vec![
+            ("D:".to_string(), "D:".to_string()),
+            ("videos".to_string(), "D:\\videos".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_slow_query_diagnostics() {
+    use netdisk_db::models::database::SearchOptions;
+    use netdisk_db::services::diagnostics::{shared_query_diagnostics, QueryDiagnostics, SlowQuery};
+    use std::time::Duration;
+
+    let db_path = std::env::temp_dir().join("netdisk_db_diagnostics_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+
+    // 阈值为 0 时记录所有查询
+    let diagnostics = shared_query_diagnostics();
+    diagnostics.configure(true, 0);
+    db.search_files_paged("diagnostics-marker", &SearchOptions::default(), 0, 10)
+        .unwrap();
+    diagnostics.configure(false, 200);
+    db.count_files("diagnostics-disabled", &SearchOptions::default()).unwrap();
+
+    let recent = diagnostics.recent();
+    let query = recent
+        .iter()
+        .find(|query| query.params.contains(&"'%diagnostics-marker%'".to_string()))
+        .expect("Slow query not recorded");
+    assert!(!query.plan.is_empty());
+    // LIKE '%...%' 无法使用索引
+    assert!(query.has_full_scan());
+    assert!(query.summary().contains("LIMIT ?2 OFFSET ?3"));
+    assert!(!recent
+        .iter()
+        .any(|query| query.params.iter().any(|param| param.contains("diagnostics-disabled"))));
+
+    let local = QueryDiagnostics::default();
+    assert!(!local.should_record(Duration::from_secs(10)));
+    local.configure(true, 100);
+    assert!(!local.should_record(Duration::from_millis(99)));
+    assert!(local.should_record(Duration::from_millis(100)));
+    for i in 0..60 {
+        local.record(SlowQuery {
+            sql: format!("SELECT {}", i),
+            params: Vec::new(),
+            elapsed: Duration::from_millis(100),
+            plan: vec!["SEARCH video USING INDEX idx_video_path (path>? AND path<?)".to_string()],
+            recorded_at: 0,
+        });
+    }
+    let recent = local.recent();
+    assert_eq!(recent.len(), netdisk_db::services::diagnostics::SLOW_QUERY_HISTORY);
+    assert_eq!(recent[0].sql, "SELECT 59");
+    assert!(!recent[0].has_full_scan());
+    local.clear();
+    assert!(local.recent().is_empty());
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_sql_console_validation() {
+    use netdisk_db::services::sql_console::validate;
+
+    assert_eq!(validate("  select * from video;  ").unwrap(), "select * from video");
+    assert!(validate("WITH big AS (SELECT * FROM video) SELECT * FROM big").is_ok());
+    assert!(validate("EXPLAIN QUERY PLAN SELECT * FROM video").is_ok());
+    assert!(validate("DELETE FROM video").is_err());
+    assert!(validate("PRAGMA journal_mode = DELETE").is_err());
+    assert!(validate("SELECT 1; DROP TABLE video").is_err());
+    assert!(validate(" ; ").is_err());
+}
+
+#[test]
+fn test_sql_console_query() {
+    use netdisk_db::models::database::SearchOptions;
+
+    let db_path = std::env::temp_dir().join("netdisk_db_sql_console_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    let total = db.count_files("", &SearchOptions::default()).unwrap();
+
+    // 包含文件记录的全部字段时转换为记录
+    let result = db.query_read_only("SELECT * FROM video ORDER BY id", 100).unwrap();
+    let records = result.records.expect("Columns should map to file records");
+    assert_eq!(records.len(), total);
+    assert_eq!(result.rows.len(), total);
+    assert!(!result.truncated);
+
+    // 只有部分字段时只返回表格
+    let result = db.query_read_only("SELECT path, size FROM video", 100).unwrap();
+    assert_eq!(result.columns, vec!["path".to_string(), "size".to_string()]);
+    assert!(result.records.is_none());
+
+    // 超过行数限制
+    let result = db.query_read_only("SELECT * FROM video", 2).unwrap();
+    assert_eq!(result.rows.len(), 2);
+    assert!(result.truncated);
+
+    // 通过白名单但会写入的语句由 SQLite 拒绝
+    assert!(db
+        .query_read_only("WITH old AS (SELECT 1) DELETE FROM video", 100)
+        .is_err());
+    assert_eq!(db.count_files("", &SearchOptions::default()).unwrap(), total);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_search_engine_headless() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::SearchEngine;
+
+    let root = std::env::temp_dir().join("netdisk_db_engine_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let db_path = root.join("library.db");
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    db.insert_files(&[FileRecord {
+        id: 0,
+        path: "/library/engine-only.mkv".to_string(),
+        size: 1,
+        etag: "engine".to_string(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: "engine-only.mkv".to_string(),
+    }])
+    .unwrap();
+    drop(db);
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let engine = SearchEngine::builder()
+        .config(config.clone())
+        .database("library")
+        .build()
+        .expect("Failed to build search engine");
+    assert!(engine.databases().unwrap().contains(&"library".to_string()));
+    assert_eq!(engine.current_database().name, "library");
+    let records = engine.search("engine-only", SearchOptions::default(), 10).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].path, "/library/engine-only.mkv");
+
+    // 未启动下载服务时添加下载返回错误
+    #[cfg(feature = "aria2")]
+    {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        assert!(runtime.block_on(engine.enqueue_download(&records[0], None)).is_err());
+    }
+
+    assert!(SearchEngine::builder().config(config).database("missing").build().is_err());
+
+    drop(engine);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_mock_database_through_handle() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use netdisk_db::testing::mock_database::MockDatabase;
+    use std::sync::{Arc, Mutex};
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    let mock = MockDatabase::new(vec![record("/movies/A.mkv"), record("/movies/b.mkv"), record("/shows/a.mkv")]);
+    mock.fail_next("search_files_paged", "disk I/O error");
+    let shared = mock.into_shared();
+
+    let manager = DatabaseManager::with_database(Arc::new(Mutex::new(AppConfig::default())), shared.clone());
+    let handle = DatabaseHandle::spawn(manager);
+
+    // 预设的失败只影响下一次调用
+    let error = handle.search("a", SearchOptions::default(), 10).unwrap_err();
+    assert!(error.to_string().contains("disk I/O error"));
+    let scoped = SearchOptions {
+        scope: Some("/movies".to_string()),
+        ..SearchOptions::default()
+    };
+    let records = handle.search("a.mkv", scoped, 10).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].path, "/movies/A.mkv");
+    assert_eq!(records[0].id, 1);
+
+    assert!(handle.snapshot().unwrap().writable);
+    let database = shared.lock().unwrap();
+    database.delete_file(1).unwrap();
+    assert_eq!(database.count_files("a.mkv", &SearchOptions::default()).unwrap(), 1);
+    drop(database);
+
+    let read_only = MockDatabase::read_only(vec![record("/a.mkv")]);
+    assert!(read_only.insert_files(&[record("/b.mkv")]).is_err());
+    assert_eq!(read_only.calls(), vec!["insert_files".to_string()]);
+}
+
+#[test]
+fn test_result_pages_through_handle() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use netdisk_db::testing::mock_database::MockDatabase;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    let shared = MockDatabase::new(vec![record("/a/1.mkv"), record("/a/2.mkv"), record("/a/3.mkv")]).into_shared();
+    let spawn = |database| {
+        DatabaseHandle::spawn(DatabaseManager::with_database(
+            Arc::new(Mutex::new(AppConfig::default())),
+            database,
+        ))
+    };
+    let handle = spawn(shared.clone());
+
+    // 计数和分页都在数据库管理线程中执行，结果页通过回调返回
+    assert_eq!(handle.count_files("mkv", SearchOptions::default()).unwrap(), 3);
+    let (tx, rx) = mpsc::channel();
+    handle.search_page_then("mkv", SearchOptions::default(), 1, 5, move |result| {
+        let _ = tx.send(result);
+    });
+    let page = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    let paths: Vec<_> = page.records.iter().map(|record| record.path.as_str()).collect();
+    assert_eq!(paths, vec!["/a/2.mkv", "/a/3.mkv"]);
+    assert!(page.verified.is_empty());
+
+    // 拖放的文件夹写入可写的当前数据库
+    let folder = std::env::temp_dir().join("netdisk_db_handle_index_test");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("dropped.mkv"), b"x").unwrap();
+    let summary = handle
+        .index_folder(folder.clone(), Arc::new(AtomicBool::new(false)), |_| {})
+        .unwrap()
+        .expect("Writable database should be indexed");
+    assert_eq!(summary.indexed, 1);
+    assert_eq!(handle.count_files("dropped", SearchOptions::default()).unwrap(), 1);
+
+    // 当前数据库只读时不写入，由调用方新建数据库
+    let read_only = spawn(MockDatabase::read_only(Vec::new()).into_shared());
+    assert!(!read_only.snapshot().unwrap().writable);
+    let skipped = read_only
+        .index_folder(folder.clone(), Arc::new(AtomicBool::new(false)), |_| {})
+        .unwrap();
+    assert!(skipped.is_none());
+
+    drop((handle, read_only));
+    let _ = std::fs::remove_dir_all(&folder);
+}
+
+#[test]
+fn test_default_search_applies_scope() {
+    use netdisk_db::models::database::{Database, FileRecord, SearchOptions};
+    use netdisk_db::testing::mock_database::MockDatabase;
+
+    // 只实现 search_files 的数据库，分页、计数和随机选取都使用默认实现
+    struct SearchOnly(MockDatabase);
+
+    impl Database for SearchOnly {
+        fn search_files(&self, query: &str) -> anyhow::Result<Vec<FileRecord>> {
+            self.0.search_files(query)
+        }
+
+        fn init_database(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    let record = |path: &str| FileRecord {
+        id: 0,
+        path: path.to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    };
+    let database = SearchOnly(MockDatabase::new(vec![
+        record("/movies/a.mkv"),
+        record("/movies/b.mkv"),
+        record("/movies2/c.mkv"),
+        record("/shows/d.mkv"),
+    ]));
+    let scoped = SearchOptions {
+        scope: Some("/movies".to_string()),
+        ..SearchOptions::default()
+    };
+
+    assert_eq!(database.count_files("mkv", &SearchOptions::default()).unwrap(), 4);
+    assert_eq!(database.count_files("mkv", &scoped).unwrap(), 2);
+    let page = database.search_files_paged("mkv", &scoped, 0, 10).unwrap();
+    assert_eq!(page.len(), 2);
+    assert!(page.iter().all(|record| record.path.starts_with("/movies/")));
+    for _ in 0..20 {
+        let picked = database.random_file("", &scoped).unwrap().unwrap();
+        assert!(picked.path.starts_with("/movies/"));
+    }
+    let empty = SearchOptions {
+        scope: Some("/music".to_string()),
+        ..SearchOptions::default()
+    };
+    assert!(database.random_file("", &empty).unwrap().is_none());
+}
+
+#[test]
+fn test_file_type_filter() {
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::testing::mock_database::MockDatabase;
+    use netdisk_db::utils::filetype::{category_of, mime_type, FileCategory};
+
+    assert_eq!(category_of("mkv", "a.mkv"), Some(FileCategory::Video));
+    assert_eq!(category_of("application/pdf", "report"), Some(FileCategory::Document));
+    assert_eq!(category_of("", "Song.FLAC"), Some(FileCategory::Audio));
+    assert_eq!(category_of("application/epub+zip", "book.epub"), Some(FileCategory::Document));
+    assert_eq!(category_of("bin", "firmware.bin"), None);
+    assert_eq!(mime_type("movie.MKV"), "video/x-matroska");
+    assert_eq!(mime_type("unknown.xyz"), "application/octet-stream");
+    assert_eq!(FileCategory::from_mask(0b10001), vec![FileCategory::Video, FileCategory::Archive]);
+
+    let record = |name: &str, file_type: &str| FileRecord {
+        id: 0,
+        path: format!("/files/{}", name),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: file_type.to_string(),
+        name: name.to_string(),
+    };
+    let records = vec![
+        record("a.mkv", "mkv"),
+        record("b.mp4", "video/mp4"),
+        record("c.flac", ""),
+        record("d.pdf", "pdf"),
+        record("e.zip", "zip"),
+        record("f.bin", "bin"),
+    ];
+    let filtered = |categories: Vec<FileCategory>| SearchOptions {
+        categories,
+        ..SearchOptions::default()
+    };
+
+    let db_path = std::env::temp_dir().join("netdisk_db_type_filter_test.db");
+    let _ = std::fs::remove_file(&db_path);
+    let db = SqliteDatabase::new(db_path.to_str().unwrap()).expect("Failed to create database");
+    db.init_database().expect("Failed to initialize database");
+    db.insert_files(&records).unwrap();
+    let mock = MockDatabase::new(records);
+
+    // SQLite 的 WHERE 条件与内存数据库的筛选结果一致
+    for database in [&db as &dyn Database, &mock as &dyn Database] {
+        assert_eq!(database.count_files("/files/", &filtered(vec![FileCategory::Video])).unwrap(), 2);
+        assert_eq!(database.count_files("/files/", &filtered(vec![FileCategory::Audio])).unwrap(), 1);
+        assert_eq!(
+            database
+                .count_files("/files/", &filtered(vec![FileCategory::Document, FileCategory::Archive]))
+                .unwrap(),
+            2
+        );
+        assert_eq!(database.count_files("/files/", &SearchOptions::default()).unwrap(), 6);
+        let names: Vec<String> = database
+            .search_files_paged("/files/", &filtered(vec![FileCategory::Video]), 0, 10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.name)
+            .collect();
+        assert_eq!(names, vec!["a.mkv", "b.mp4"]);
+    }
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_remove_current_database_reloads() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::DatabaseManager;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    let root = std::env::temp_dir().join("netdisk_db_remove_current_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for name in ["first", "second"] {
+        let db = SqliteDatabase::new(root.join(format!("{}.db", name)).to_str().unwrap()).unwrap();
+        db.init_database().unwrap();
+        db.insert_files(&[FileRecord {
+            id: 0,
+            path: format!("/{}/remove-target.mkv", name),
+            size: 1,
+            etag: name.to_string(),
+            modified_time: 0,
+            file_type: "mkv".to_string(),
+            name: "remove-target.mkv".to_string(),
+        }])
+        .unwrap();
+    }
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let mut manager = DatabaseManager::new(Arc::new(Mutex::new(config))).unwrap();
+    let (tx, rx) = mpsc::channel();
+    manager.on_database_changed(move |index, config| {
+        let _ = tx.send((index, config.name.clone()));
+    });
+
+    // 当前数据库不在索引 0 时移除它，应切换到前一个数据库并通知
+    let index = manager
+        .get_database_list()
+        .into_iter()
+        .find(|(name, _, _)| name == "second")
+        .map(|(_, _, index)| index)
+        .expect("Database not discovered");
+    assert!(index > 0);
+    manager.switch_database(index).unwrap();
+    let _ = rx.try_recv();
+
+    manager.remove_database(index).unwrap();
+    let (notified_index, notified_name) = rx.try_recv().expect("Removing the current database should notify");
+    assert_eq!(notified_index, index - 1);
+    assert_eq!(notified_name, manager.get_current_database_config().name);
+    let paths: Vec<String> = manager
+        .get_current_database()
+        .lock()
+        .unwrap()
+        .search_files_paged("remove-target", &SearchOptions::default(), 0, 10)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.path)
+        .collect();
+    assert!(!paths.contains(&"/second/remove-target.mkv".to_string()));
+
+    drop(manager);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_refresh_reloads_when_current_database_disappears() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, SearchOptions};
+    use netdisk_db::services::database_manager::DatabaseManager;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    let root = std::env::temp_dir().join("netdisk_db_refresh_current_test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for name in ["first", "second"] {
+        let db = SqliteDatabase::new(root.join(format!("{}.db", name)).to_str().unwrap()).unwrap();
+        db.init_database().unwrap();
+        db.insert_files(&[FileRecord {
+            id: 0,
+            path: format!("/{}/refresh-target.mkv", name),
+            size: 1,
+            etag: name.to_string(),
+            modified_time: 0,
+            file_type: "mkv".to_string(),
+            name: "refresh-target.mkv".to_string(),
+        }])
+        .unwrap();
+    }
+
+    let mut config = AppConfig::default();
+    config.scan.directories = vec![root.to_string_lossy().to_string()];
+    let mut manager = DatabaseManager::new(Arc::new(Mutex::new(config))).unwrap();
+    let (tx, rx) = mpsc::channel();
+    manager.on_database_changed(move |index, config| {
+        let _ = tx.send((index, config.name.clone()));
+    });
+    let index = manager
+        .get_database_list()
+        .into_iter()
+        .find(|(name, _, _)| name == "second")
+        .map(|(_, _, index)| index)
+        .expect("Database not discovered");
+    manager.switch_database(index).unwrap();
+    let _ = rx.try_recv();
+
+    // 当前数据库仍然存在时刷新不会重新加载
+    manager.refresh_database_list().unwrap();
+    assert!(rx.try_recv().is_err());
+    assert_eq!(manager.get_current_database_config().name, "second");
+
+    // 当前数据库文件被删除后刷新，回退到其他数据库并通知
+    std::fs::remove_file(root.join("second.db")).unwrap();
+    manager.refresh_database_list().unwrap();
+    let (notified_index, notified_name) = rx.try_recv().expect("Losing the current database should notify");
+    assert_eq!(notified_index, manager.get_current_database_index());
+    assert_ne!(notified_name, "second");
+    let paths: Vec<String> = manager
+        .get_current_database()
+        .lock()
+        .unwrap()
+        .search_files_paged("refresh-target", &SearchOptions::default(), 0, 10)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.path)
+        .collect();
+    assert!(!paths.contains(&"/second/refresh-target.mkv".to_string()));
+
+    drop(manager);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_long_commands_do_not_block_requests() {
+    use netdisk_db::models::config::AppConfig;
+    use netdisk_db::models::database::{FileRecord, MaintenanceOperation, SearchOptions};
+    use netdisk_db::services::database_manager::{DatabaseHandle, DatabaseManager};
+    use netdisk_db::testing::mock_database::MockDatabase;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
+
+    let record = FileRecord {
+        id: 0,
+        path: "/movies/a.mkv".to_string(),
+        size: 1,
+        etag: String::new(),
+        modified_time: 0,
+        file_type: "mkv".to_string(),
+        name: "a.mkv".to_string(),
+    };
+    let shared = MockDatabase::new(vec![record]).into_shared();
+    let manager = DatabaseManager::with_database(Arc::new(Mutex::new(AppConfig::default())), shared);
+    let handle = DatabaseHandle::spawn(manager);
+
+    // 维护操作在工作线程中执行，进度回调阻塞时数据库管理线程仍能处理其他请求
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let maintenance = {
+        let handle = handle.clone();
+        std::thread::spawn(move || {
+            handle.run_maintenance(MaintenanceOperation::ROUTINE[..1].to_vec(), move |_, _, _| {
+                let _ = started_tx.send(());
+                let _ = release_rx.recv();
+            })
+        })
+    };
+    started_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(handle.search("a.mkv", SearchOptions::default(), 10).unwrap().len(), 1);
+
+    // 不等待结果的请求通过回调返回
+    let (reply_tx, reply_rx) = mpsc::channel();
+    handle.random_file_then("a.mkv", SearchOptions::default(), move |result| {
+        let _ = reply_tx.send(result.map(|record| record.map(|record| record.path)));
+    });
+    let path = reply_rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    assert_eq!(path.as_deref(), Some("/movies/a.mkv"));
+
+    release_tx.send(()).unwrap();
+    // 内存数据库不支持维护，错误照常返回给调用方
+    assert!(maintenance.join().unwrap().is_err());
+}